@@ -0,0 +1,235 @@
+//! Checks of the toolchain needed to build move-to-polka and to link Move programs, reported
+//! by `move-to-polka doctor` with a remediation step for every failed check.
+
+use crate::{find_lld, find_llvm_ar, NativeVariant};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use which::which;
+
+/// The LLVM major versions `llvm-sys` can be built against, selected by the `llvm18`,
+/// `llvm19` (the default) and `llvm20` features of move-to-polka.
+pub const LLVM_MAJOR_VERSIONS: &[&str] = &["18", "19", "20"];
+
+/// Environment variables `llvm-sys` looks up the LLVM installation in, one per version.
+pub const LLVM_PREFIX_ENVS: &[&str] = &[
+    "LLVM_SYS_181_PREFIX",
+    "LLVM_SYS_191_PREFIX",
+    "LLVM_SYS_201_PREFIX",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    /// What the check is needed for, e.g. linking Move programs.
+    pub needed_for: &'static str,
+    /// The version or path found, or what is wrong.
+    pub detail: String,
+    pub ok: bool,
+    /// How to fix a failed check.
+    pub remediation: &'static str,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.ok { "ok" } else { "FAIL" };
+        write!(f, "[{status:>4}] {}: {}", self.name, self.detail)?;
+        if !self.ok {
+            write!(
+                f,
+                "\n       needed for {}\n       fix: {}",
+                self.needed_for, self.remediation
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run all checks. The checks for building the move-native runtime (nightly rustc, `rust-src`
+/// and writing the embedded target spec) run in `native_crate`, the polkavm-move-native crate
+/// directory, whose `rust-toolchain.toml` selects the toolchain; without it they are skipped.
+pub fn run(native_crate: Option<&Path>) -> Vec<Check> {
+    let mut checks = vec![lld(), llvm_ar(), llvm_headers()];
+    if let Some(native_crate) = native_crate {
+        checks.push(nightly_rustc(native_crate));
+        checks.push(rust_src(native_crate));
+        checks.push(target_specs());
+    }
+    checks
+}
+
+fn lld() -> Check {
+    let (detail, ok) = match find_lld() {
+        Ok(path) => tool_version(&path),
+        Err(e) => (e.to_string(), false),
+    };
+    Check {
+        name: "ld.lld",
+        needed_for: "linking Move programs and building the move-native runtime",
+        detail,
+        ok,
+        remediation: "install LLD (e.g. `apt install lld-19` or `brew install llvm`) and put \
+                      `ld.lld` on the PATH",
+    }
+}
+
+fn llvm_ar() -> Check {
+    let (detail, ok) = match find_llvm_ar() {
+        Ok(path) => tool_version(&path),
+        Err(e) => (e.to_string(), false),
+    };
+    Check {
+        name: "llvm-ar",
+        needed_for: "building the move-native runtime",
+        detail,
+        ok,
+        remediation: "install the LLVM tools (e.g. `apt install llvm-19`) and put `llvm-ar` on \
+                      the PATH",
+    }
+}
+
+fn llvm_headers() -> Check {
+    let remediation = "install LLVM 18, 19 or 20 with its development headers (e.g. `apt \
+                       install llvm-19-dev libpolly-19-dev`) and set LLVM_SYS_191_PREFIX, or \
+                       the variable of that version, to its prefix";
+    let prefix = LLVM_PREFIX_ENVS
+        .iter()
+        .find_map(|env| std::env::var(env).ok());
+    let llvm_config = match prefix {
+        Some(prefix) => Some(PathBuf::from(prefix).join("bin").join("llvm-config")),
+        None => which("llvm-config").ok().or_else(|| {
+            LLVM_MAJOR_VERSIONS
+                .iter()
+                .rev()
+                .find_map(|major| which(format!("llvm-config-{major}")).ok())
+        }),
+    };
+    let supported = |version: &str| {
+        LLVM_MAJOR_VERSIONS
+            .iter()
+            .any(|major| version.starts_with(&format!("{major}.")))
+    };
+    let (detail, ok) = match llvm_config {
+        None => (
+            format!(
+                "no llvm-config in PATH and none of {} is set",
+                LLVM_PREFIX_ENVS.join(", ")
+            ),
+            false,
+        ),
+        Some(llvm_config) => match (
+            output(&llvm_config, &["--version"], None),
+            output(&llvm_config, &["--includedir"], None),
+        ) {
+            (Some(version), _) if !supported(&version) => (
+                format!(
+                    "{} is LLVM {version}, LLVM 18, 19 or 20 is required",
+                    llvm_config.display()
+                ),
+                false,
+            ),
+            (Some(version), Some(include_dir)) => {
+                let header = Path::new(&include_dir).join("llvm-c").join("Core.h");
+                if header.exists() {
+                    (format!("LLVM {version}, headers in {include_dir}"), true)
+                } else {
+                    (
+                        format!("LLVM {version}, but {} is missing", header.display()),
+                        false,
+                    )
+                }
+            }
+            _ => (format!("{} failed to run", llvm_config.display()), false),
+        },
+    };
+    Check {
+        name: "LLVM",
+        needed_for: "building move-to-polka (llvm-sys)",
+        detail,
+        ok,
+        remediation,
+    }
+}
+
+fn nightly_rustc(native_crate: &Path) -> Check {
+    let (detail, ok) = match output(Path::new("rustc"), &["--version"], Some(native_crate)) {
+        Some(version) => {
+            let ok = version.contains("nightly");
+            (version, ok)
+        }
+        None => ("rustc failed to run".to_string(), false),
+    };
+    Check {
+        name: "nightly rustc",
+        needed_for: "building the move-native runtime (-Z build-std)",
+        detail,
+        ok,
+        remediation: "install rustup and run `rustup toolchain install nightly`; the toolchain \
+                      is selected by crates/polkavm-move-native/rust-toolchain.toml",
+    }
+}
+
+fn rust_src(native_crate: &Path) -> Check {
+    let sysroot = output(
+        Path::new("rustc"),
+        &["--print", "sysroot"],
+        Some(native_crate),
+    );
+    let (detail, ok) = match sysroot {
+        Some(sysroot) => {
+            let library = Path::new(&sysroot).join("lib/rustlib/src/rust/library");
+            if library.join("core").exists() {
+                (library.display().to_string(), true)
+            } else {
+                (format!("{} is missing", library.display()), false)
+            }
+        }
+        None => ("rustc failed to run".to_string(), false),
+    };
+    Check {
+        name: "rust-src",
+        needed_for: "building the move-native runtime (-Z build-std)",
+        detail,
+        ok,
+        remediation: "run `rustup component add rust-src --toolchain nightly`",
+    }
+}
+
+fn target_specs() -> Check {
+    let (detail, ok) = match NativeVariant::DEFAULT.target_spec_path() {
+        Ok(path) => (format!("written to {}", path.display()), true),
+        Err(e) => (format!("{e:#}"), false),
+    };
+    Check {
+        name: "polkavm target spec",
+        needed_for: "building the move-native runtime",
+        detail,
+        ok,
+        remediation: "make the system temp directory writable, or point TMPDIR to one that is",
+    }
+}
+
+/// The version `tool --version` reports, and whether it ran.
+fn tool_version(tool: &Path) -> (String, bool) {
+    match output(tool, &["--version"], None) {
+        Some(version) => (format!("{version} ({})", tool.display()), true),
+        None => (format!("{} failed to run", tool.display()), false),
+    }
+}
+
+/// First line of the standard output of a successful `command args`.
+fn output(command: &Path, args: &[&str], current_dir: Option<&Path>) -> Option<String> {
+    let mut command = Command::new(command);
+    command.args(args);
+    if let Some(dir) = current_dir {
+        command.current_dir(dir);
+    }
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
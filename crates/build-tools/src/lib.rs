@@ -1,45 +1,418 @@
 use std::{
+    io::Read,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, ExitStatus, Stdio},
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 use anyhow::Context;
 use itertools::Itertools;
-use log::{debug, error};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, instrument, warn};
 use which::{which, which_in};
 
-pub struct Lld(PathBuf);
+pub mod doctor;
+
+/// Time after which a hanging `ld.lld` is killed.
+pub const DEFAULT_LLD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Number of object files above which they are passed to `ld.lld` in a response file
+/// rather than on the command line, which is limited to 32K characters on Windows.
+pub const RESPONSE_FILE_THRESHOLD: usize = 64;
+
+pub struct Lld {
+    path: PathBuf,
+    timeout: Duration,
+}
 
 impl Lld {
     pub fn try_init() -> anyhow::Result<Self> {
-        let path = which("ld.lld")
-            .or(which_in("ld.lld", Some("/opt/homebrew/bin"), "/"))
-            .context("no ld.lld in PATH")?;
-        Ok(Self(path))
+        Ok(Self::at(find_lld()?))
+    }
+
+    /// Use the `ld.lld` executable at `path`.
+    pub fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            timeout: DEFAULT_LLD_TIMEOUT,
+        }
+    }
+
+    /// Kill `ld.lld` and fail when it runs longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
     }
 
+    #[instrument(level = "debug", skip_all, fields(objects = sources.len(), ?output))]
     pub fn merge_object_files(
         &self,
         sources: &[&PathBuf],
         output: &PathBuf,
         gc_sections: bool,
     ) -> anyhow::Result<()> {
-        let mut cmd = Command::new(&self.0);
+        let mut cmd = Command::new(&self.path);
         // this flag is essential as it strips all unused symbols AFTER we merge native lib with actual move program code
         // otherwise there are lot of bits (like atomics) included by rust compiler which result in undefined symbols
         // during polka linking phase.
         if gc_sections {
             cmd.arg("--gc-sections");
         }
-        let status = cmd.arg("-r").arg("-o").arg(output).args(sources).status()?;
-        if !status.success() {
+        cmd.arg("-r").arg("-o").arg(output);
+
+        let response_file = if sources.len() > RESPONSE_FILE_THRESHOLD {
+            let path = output.with_extension("rsp");
+            let contents = sources
+                .iter()
+                .map(|source| quote_argument(source))
+                .join("\n");
+            std::fs::write(&path, contents)
+                .with_context(|| format!("writing lld response file {}", path.display()))?;
+            debug!(
+                "passing {} object files to ld.lld in {}",
+                sources.len(),
+                path.display()
+            );
+            // response files are tokenized the Windows way by default on Windows
+            cmd.arg("--rsp-quoting=posix");
+            cmd.arg(format!("@{}", path.display()));
+            Some(path)
+        } else {
+            cmd.args(sources);
+            None
+        };
+
+        let result = run_captured(cmd, self.timeout);
+        if let Some(path) = response_file {
+            let _ = std::fs::remove_file(path);
+        }
+        let output = result?;
+        if !output.status.success() {
             error!("ld.lld execution error:");
-            anyhow::bail!("lld failed: exit status: {}", status.code().unwrap())
+            anyhow::bail!(
+                "lld failed: exit status: {}{}",
+                output.status,
+                output.diagnostics()
+            )
+        }
+        if !output.stderr.trim().is_empty() {
+            warn!("ld.lld: {}", output.stderr.trim());
         }
         Ok(())
     }
 }
 
+/// Quote `path` for a response file read with `--rsp-quoting=posix`.
+fn quote_argument(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Exit status and output of a finished tool.
+struct CapturedOutput {
+    status: ExitStatus,
+    stdout: String,
+    stderr: String,
+}
+
+impl CapturedOutput {
+    /// The non-empty output of the tool, formatted to be appended to an error message.
+    fn diagnostics(&self) -> String {
+        [("stderr", &self.stderr), ("stdout", &self.stdout)]
+            .into_iter()
+            .filter(|(_, output)| !output.trim().is_empty())
+            .map(|(name, output)| format!("\n{name}:\n{}", output.trim_end()))
+            .join("")
+    }
+}
+
+/// Run `cmd` to completion, capturing its output, and kill it once it runs longer than `timeout`.
+fn run_captured(mut cmd: Command, timeout: Duration) -> anyhow::Result<CapturedOutput> {
+    debug!("running {cmd:?}");
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("starting {program}"))?;
+    // drain both pipes while waiting, so a chatty tool can't block on a full pipe
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            let output = CapturedOutput {
+                status: ExitStatus::default(),
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+            };
+            anyhow::bail!(
+                "{program} timed out after {}s{}",
+                timeout.as_secs_f32(),
+                output.diagnostics()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    Ok(CapturedOutput {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+pub(crate) fn find_lld() -> anyhow::Result<PathBuf> {
+    which("ld.lld")
+        .or(which_in("ld.lld", Some("/opt/homebrew/bin"), "/"))
+        .context("no ld.lld in PATH")
+}
+
+pub(crate) fn find_llvm_ar() -> anyhow::Result<PathBuf> {
+    which("llvm-ar")
+        .or(which_in(
+            "llvm-ar",
+            Some("/opt/homebrew/opt/llvm/bin/"),
+            "/",
+        ))
+        .context("no llvm-ar in PATH")
+}
+
+/// Copy or download a prebuilt move-native runtime object from `source`, a path or an
+/// `http(s)://` URL, to `output`.
+#[instrument(level = "debug")]
+pub fn fetch_prebuilt_object(source: &str, output: &Path) -> anyhow::Result<PathBuf> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let curl = which("curl").context("no curl in PATH to download the prebuilt runtime")?;
+        debug!("downloading prebuilt move-native runtime from {source}");
+        let status = Command::new(curl)
+            .args([
+                "--fail",
+                "--silent",
+                "--show-error",
+                "--location",
+                "--output",
+            ])
+            .arg(output)
+            .arg(source)
+            .status()?;
+        if !status.success() {
+            anyhow::bail!("downloading {source} failed: exit status: {status}");
+        }
+    } else {
+        std::fs::copy(source, output).with_context(|| format!("reading {source}"))?;
+    }
+    Ok(output.to_path_buf())
+}
+
+/// Fail unless the SHA-256 of the file at `path` is `expected` (hex encoded).
+pub fn verify_sha256(path: &Path, expected: &str) -> anyhow::Result<()> {
+    let digest = Sha256::digest(std::fs::read(path)?);
+    let actual = digest.iter().map(|byte| format!("{byte:02x}")).join("");
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        anyhow::bail!(
+            "checksum mismatch for {}: expected {expected}, got {actual}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// The polkavm target spec of the move-native runtime, embedded so building it depends
+/// neither on the working directory nor on the files next to the move-native crate. It lives
+/// in this crate, so it builds when packaged or used as a git dependency; the copy next to
+/// the move-native crate, for its `build.sh`, must match it.
+const RV64_TARGET_SPEC: &str = include_str!("../riscv64emac-unknown-none-polkavm.json");
+
+/// Directory under the system temp dir [`NativeVariant::target_spec_path`] writes the target
+/// specs to.
+pub const TARGET_SPEC_DIR: &str = "move-polka-target-specs";
+
+/// Architecture a move-native runtime is built for, the one the code generator targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NativeArch {
+    Rv64,
+}
+
+/// Cargo profile a move-native runtime is built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NativeProfile {
+    Debug,
+    Release,
+}
+
+/// Length of Move addresses in a move-native runtime and in the code compiled against it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AddressLength {
+    /// The length of pallet-revive H160 accounts.
+    Bytes20,
+    /// The length of Move VM addresses.
+    #[default]
+    Bytes32,
+}
+
+impl AddressLength {
+    pub const fn bytes(&self) -> usize {
+        match self {
+            AddressLength::Bytes20 => 20,
+            AddressLength::Bytes32 => 32,
+        }
+    }
+}
+
+impl std::fmt::Display for AddressLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.bytes())
+    }
+}
+
+impl std::str::FromStr for AddressLength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "20" => Ok(AddressLength::Bytes20),
+            "32" => Ok(AddressLength::Bytes32),
+            _ => Err(format!("invalid address length '{s}', expected 20 or 32")),
+        }
+    }
+}
+
+/// A build flavor of the move-native runtime, written as `<arch>-<profile>`,
+/// e.g. `rv64-release`, followed by `-addr20` for 20 byte addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NativeVariant {
+    pub arch: NativeArch,
+    pub profile: NativeProfile,
+    pub address_length: AddressLength,
+}
+
+impl NativeVariant {
+    pub const DEFAULT: NativeVariant = NativeVariant {
+        arch: NativeArch::Rv64,
+        profile: NativeProfile::Release,
+        address_length: AddressLength::Bytes32,
+    };
+
+    /// Name of the target, see [`NativeVariant::target_spec`].
+    pub fn target(&self) -> &'static str {
+        match self.arch {
+            NativeArch::Rv64 => "riscv64emac-unknown-none-polkavm",
+        }
+    }
+
+    /// The target spec of the architecture, as JSON.
+    pub fn target_spec(&self) -> &'static str {
+        match self.arch {
+            NativeArch::Rv64 => RV64_TARGET_SPEC,
+        }
+    }
+
+    /// Write the target spec to `dir` as `<target>.json`, the name cargo derives the target
+    /// name and output directory from, and return its path. A spec an earlier build wrote is
+    /// reused as long as it is unchanged.
+    pub fn write_target_spec(&self, dir: &Path) -> anyhow::Result<PathBuf> {
+        let spec = self.target_spec();
+        let path = dir.join(format!("{}.json", self.target()));
+        if std::fs::read_to_string(&path).is_ok_and(|written| written == spec) {
+            return Ok(path);
+        }
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating target spec directory {}", dir.display()))?;
+        // builds running at the same time may write the spec too, the rename keeps it whole
+        let partial = dir.join(format!("{}.json.{}", self.target(), std::process::id()));
+        std::fs::write(&partial, spec)
+            .with_context(|| format!("writing target spec {}", partial.display()))?;
+        std::fs::rename(&partial, &path)
+            .with_context(|| format!("writing target spec {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Path of the target spec, written to [`TARGET_SPEC_DIR`] under the system temp dir.
+    pub fn target_spec_path(&self) -> anyhow::Result<PathBuf> {
+        self.write_target_spec(&std::env::temp_dir().join(TARGET_SPEC_DIR))
+    }
+
+    /// Cargo output directory of the profile.
+    pub fn profile_dir(&self) -> &'static str {
+        match self.profile {
+            NativeProfile::Debug => "debug",
+            NativeProfile::Release => "release",
+        }
+    }
+}
+
+impl Default for NativeVariant {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl std::fmt::Display for NativeVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arch = match self.arch {
+            NativeArch::Rv64 => "rv64",
+        };
+        write!(f, "{arch}-{}", self.profile_dir())?;
+        if self.address_length != AddressLength::Bytes32 {
+            write!(f, "-addr{}", self.address_length)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for NativeVariant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid move-native variant '{s}', expected rv64-<debug|release>[-addr20]");
+        let mut parts = s.trim().split('-');
+        let (Some(arch), Some(profile)) = (parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let arch = match arch {
+            "rv64" => NativeArch::Rv64,
+            _ => return Err(invalid()),
+        };
+        let profile = match profile {
+            "debug" => NativeProfile::Debug,
+            "release" => NativeProfile::Release,
+            _ => return Err(invalid()),
+        };
+        let address_length = match (parts.next(), parts.next()) {
+            (None, _) => AddressLength::Bytes32,
+            (Some(length), None) => length
+                .strip_prefix("addr")
+                .and_then(|length| length.parse().ok())
+                .ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        Ok(NativeVariant {
+            arch,
+            profile,
+            address_length,
+        })
+    }
+}
+
 pub struct NativeBuildTools {
     cargo: PathBuf,
     lld: Lld,
@@ -51,13 +424,7 @@ impl NativeBuildTools {
         Ok(Self {
             cargo: which("cargo").context("no cargo in PATH")?,
             lld: Lld::try_init()?,
-            llvm_ar: which("llvm-ar")
-                .or(which_in(
-                    "llvm-ar",
-                    Some("/opt/homebrew/opt/llvm/bin/"),
-                    "/",
-                ))
-                .context("no llvm-ar in PATH")?,
+            llvm_ar: find_llvm_ar()?,
         })
     }
 
@@ -110,54 +477,69 @@ impl NativeBuildTools {
         Ok(())
     }
 
+    #[instrument(level = "debug", skip_all, fields(%variant))]
     pub fn build_native_move_lib(
         &self,
         crate_path: &Path,
         out_path: &PathBuf,
+        variant: NativeVariant,
     ) -> anyhow::Result<PathBuf> {
-        debug!("building move-native runtime for polkavm in {out_path:?}");
-        let final_object_file = out_path.join("polkavm_native_final.o");
+        debug!("building {variant} move-native runtime for polkavm in {out_path:?}");
+        let final_object_file = out_path.join(format!("polkavm_native_{variant}.o"));
 
-        let target_json = "riscv64emac-unknown-none-polkavm.json".to_string();
+        // cargo runs in the crate directory, the path must not be relative
+        let target_spec =
+            variant.write_target_spec(&out_path.canonicalize()?.join("target-specs"))?;
+        let target_spec = target_spec.to_str().context("non UTF-8 target spec path")?;
 
         // Using `cargo rustc` to compile move-native as a staticlib.
         // See move-native documentation on `no-std` compatibilty for explanation.
-        // Release mode is required to eliminate large stack frames.
+        // Debug builds keep debug assertions and debug info, but are still optimized,
+        // as unoptimized code has large stack frames.
+        let mut args = vec![
+            "rustc",
+            "--crate-type=staticlib",
+            "-Z",
+            "build-std=core,alloc",
+            "--target",
+            target_spec,
+        ];
+        if variant.profile == NativeProfile::Release {
+            args.push("--release");
+        }
+        let features = match variant.address_length {
+            AddressLength::Bytes20 => "polkavm,address20",
+            AddressLength::Bytes32 => "polkavm",
+        };
+        args.extend([
+            "--features",
+            features,
+            "--verbose", // for build process debuging purposes
+            "--",
+            // following are direct rustc flags
+            // create one object in static library - we need this for object merge invocation later (and probably embedding too)
+            "-C",
+            "codegen-units=1",
+            // optimize for binary size, but also respect performance
+            "-C",
+            "opt-level=s",
+        ]);
         self.run_cargo(
             &crate_path.canonicalize()?,
             &out_path.canonicalize()?,
-            &[
-                "rustc",
-                "--crate-type=staticlib",
-                "-Z",
-                "build-std=core,alloc",
-                "--target",
-                &target_json,
-                "--release",
-                "--features",
-                "polkavm",
-                "--verbose", // for build process debuging purposes
-                "--",
-                // following are direct rustc flags
-                // create one object in static library - we need this for object merge invocation later (and probably embedding too)
-                "-C",
-                "codegen-units=1",
-                // optimize for binary size, but also respect performance
-                "-C",
-                "opt-level=s",
-            ],
+            &args,
         )?;
 
         let archive_file = out_path
-            .join("riscv64emac-unknown-none-polkavm")
-            .join("release")
+            .join(variant.target())
+            .join(variant.profile_dir())
             .join("libpolkavm_move_native.a");
 
         if !archive_file.exists() {
             anyhow::bail!("native runtime not found at {archive_file:?}. this is a bug");
         }
 
-        let extracted_content = out_path.join("archive_contents");
+        let extracted_content = out_path.join(format!("archive_contents_{variant}"));
         // cleanup any possible leftovers
         if extracted_content.exists() {
             std::fs::remove_dir_all(&extracted_content)?;
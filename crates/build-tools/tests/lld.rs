@@ -0,0 +1,90 @@
+#![cfg(unix)]
+
+use std::{
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use build_tools::{Lld, RESPONSE_FILE_THRESHOLD};
+
+/// A stand-in for `ld.lld` running `script`, which sees the linker arguments as `$@`.
+fn fake_lld(dir: &Path, script: &str) -> Lld {
+    let path = dir.join("ld.lld");
+    std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    Lld::at(path)
+}
+
+fn objects(dir: &Path, count: usize) -> Vec<PathBuf> {
+    (0..count)
+        .map(|index| dir.join(format!("object {index}.o")))
+        .collect()
+}
+
+#[test]
+pub fn failure_reports_the_linker_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let lld = fake_lld(
+        dir.path(),
+        "echo 'ld.lld: error: undefined symbol: move_rt_abort' >&2; echo linking; exit 1",
+    );
+    let objects = objects(dir.path(), 2);
+    let error = lld
+        .merge_object_files(
+            &objects.iter().collect::<Vec<_>>(),
+            &dir.path().join("merged.o"),
+            true,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("exit status: 1"), "{error}");
+    assert!(error.contains("undefined symbol: move_rt_abort"), "{error}");
+    assert!(error.contains("linking"), "{error}");
+}
+
+#[test]
+pub fn long_object_lists_are_passed_in_a_response_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let arguments = dir.path().join("arguments");
+    let response = dir.path().join("response");
+    let lld = fake_lld(
+        dir.path(),
+        &format!(
+            "echo \"$@\" > '{}'; for arg; do case $arg in @*) cat \"${{arg#@}}\" > '{}';; esac; done",
+            arguments.display(),
+            response.display()
+        ),
+    );
+    let objects = objects(dir.path(), RESPONSE_FILE_THRESHOLD + 1);
+    let output = dir.path().join("merged.o");
+    lld.merge_object_files(&objects.iter().collect::<Vec<_>>(), &output, false)
+        .unwrap();
+
+    let arguments = std::fs::read_to_string(arguments).unwrap();
+    assert!(arguments.contains("--rsp-quoting=posix"), "{arguments}");
+    assert!(!arguments.contains("object 0.o"), "{arguments}");
+    let response = std::fs::read_to_string(response).unwrap();
+    assert_eq!(response.lines().count(), objects.len());
+    assert!(response.contains(&format!("\"{}\"", objects[0].display())));
+    // the response file is removed after linking
+    assert!(!output.with_extension("rsp").exists());
+}
+
+#[test]
+pub fn hanging_linker_is_killed() {
+    let dir = tempfile::tempdir().unwrap();
+    let lld = fake_lld(dir.path(), "echo started >&2; exec sleep 30")
+        .with_timeout(Duration::from_millis(200));
+    let objects = objects(dir.path(), 1);
+    let error = lld
+        .merge_object_files(
+            &objects.iter().collect::<Vec<_>>(),
+            &dir.path().join("merged.o"),
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("timed out"), "{error}");
+    assert!(error.contains("started"), "{error}");
+}
@@ -0,0 +1,46 @@
+use build_tools::NativeVariant;
+
+#[test]
+pub fn target_spec_is_written_under_the_target_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let variant = NativeVariant::DEFAULT;
+    let path = variant.write_target_spec(dir.path()).unwrap();
+    assert_eq!(
+        path,
+        dir.path().join("riscv64emac-unknown-none-polkavm.json")
+    );
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        variant.target_spec()
+    );
+}
+
+#[test]
+pub fn changed_target_spec_is_rewritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let variant = NativeVariant::DEFAULT;
+    let path = variant.write_target_spec(dir.path()).unwrap();
+    std::fs::write(&path, "{}").unwrap();
+
+    assert_eq!(variant.write_target_spec(dir.path()).unwrap(), path);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        variant.target_spec()
+    );
+    // no partial files are left behind
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+pub fn move_native_target_spec_matches() {
+    let move_native =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../polkavm-move-native");
+    let variant = NativeVariant::DEFAULT;
+    let name = format!("{}.json", variant.target());
+    let copy = std::fs::read_to_string(move_native.join(&name)).unwrap();
+    assert_eq!(
+        copy,
+        variant.target_spec(),
+        "{name} differs from build-tools"
+    );
+}
@@ -0,0 +1,72 @@
+//! What instrumented builds record about the generated code.
+//!
+//! The code generator numbers coverage counters and traced functions as it emits them;
+//! the driver turns the collected tables into the coverage and stack trace maps it writes
+//! next to the blob. With [`crate::options::CodegenOptions::collect_cfgs`], it also records
+//! the control flow graph of every translated function, which the driver writes as dot or
+//! JSON files.
+
+use move_binary_format::file_format::CodeOffset;
+use serde::{Deserialize, Serialize};
+
+/// Symbol of the coverage counter array, defined once per blob.
+pub const COVERAGE_COUNTERS_SYMBOL: &str = "move_rt_coverage_counters";
+
+/// Symbol of the number of coverage counters, passed to the host along with the array.
+pub const COVERAGE_LEN_SYMBOL: &str = "move_rt_coverage_len";
+
+/// Source location of the basic block a coverage counter belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageCounter {
+    pub function: String,
+    pub file: String,
+    pub line: u32,
+}
+
+/// Control flow graph of a translated function, over its stackless bytecode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionCfg {
+    /// Name of the function, e.g. `0xa000::vector::sum`. Instances of a generic function
+    /// share one graph.
+    pub function: String,
+    /// Ids of the blocks execution starts in.
+    pub entry_blocks: Vec<CodeOffset>,
+    pub blocks: Vec<CfgBlock>,
+    /// The graph in GraphViz dot format, labelled with the function.
+    #[serde(skip)]
+    pub dot: String,
+}
+
+/// A basic block of a [`FunctionCfg`]. The entry and exit blocks added by the analysis
+/// have no instructions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgBlock {
+    pub id: CodeOffset,
+    /// The stackless bytecode instructions, as displayed in the dot graph.
+    pub instructions: Vec<String>,
+    pub successors: Vec<CodeOffset>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Instrumentation {
+    /// Coverage counters, in the order of the counter array.
+    pub coverage_counters: Vec<CoverageCounter>,
+    /// Functions recorded in shadow stacks, indexed by function id.
+    pub traced_functions: Vec<String>,
+    /// Control flow graphs of the translated functions, in translation order.
+    pub cfgs: Vec<FunctionCfg>,
+}
+
+impl Instrumentation {
+    /// Add a coverage counter and return its index in the counter array.
+    pub fn push_coverage_counter(&mut self, counter: CoverageCounter) -> u32 {
+        self.coverage_counters.push(counter);
+        (self.coverage_counters.len() - 1) as u32
+    }
+
+    /// Add a traced function and return its id.
+    pub fn push_traced_function(&mut self, function: String) -> u32 {
+        self.traced_functions.push(function);
+        (self.traced_functions.len() - 1) as u32
+    }
+}
@@ -0,0 +1,50 @@
+//! Translation of Move programs to LLVM IR for PolkaVM.
+//!
+//! This crate holds the code generator only: the LLVM wrappers, [`stackless::GlobalContext`]
+//! and the per module and per function translation. It does not parse command lines, run
+//! the Move compiler, write files or link blobs; that is the job of the `move-to-polka`
+//! driver. The interface between the two is:
+//!
+//! - [`options::CodegenOptions`], the subset of the driver options code generation reads,
+//! - [`stackless::GlobalContext::create_module_context`] and
+//!   [`stackless::ModuleContext::translate`], which fill one [`stackless::Module`] per Move
+//!   module,
+//! - [`stackless::ExportRegistry`], the functions the translated modules export and
+//!   dispatch, with the selectors the driver writes to the ABI and the metadata, held by the
+//!   `GlobalContext`,
+//! - [`instrument::Instrumentation`], which collects what instrumented builds need to know
+//!   about the generated code, such as the source line of every coverage counter,
+//! - the analyses the driver runs before translating: [`stackless::check_module`],
+//!   [`stackless::reachable_functions`], [`stackless::ExportPolicy`] and
+//!   [`stackless::entry_selector`].
+//!
+//! Errors are reported as `anyhow::Error`; the driver maps them to its typed errors.
+//!
+//! The crate builds against LLVM 18, 19 (the default) or 20, selected by the `llvm18`,
+//! `llvm19` and `llvm20` features. The few C APIs whose signatures differ between them are
+//! wrapped in [`stackless`]'s LLVM module, and [`stackless::check_llvm_version`] rejects a
+//! different LLVM library loaded at runtime.
+
+#[cfg(not(any(feature = "llvm18", feature = "llvm19", feature = "llvm20")))]
+compile_error!("select the LLVM version with one of the llvm18, llvm19 or llvm20 features");
+#[cfg(any(
+    all(feature = "llvm18", feature = "llvm19"),
+    all(feature = "llvm18", feature = "llvm20"),
+    all(feature = "llvm19", feature = "llvm20"),
+))]
+compile_error!(
+    "only one of the llvm18, llvm19 and llvm20 features can be enabled, \
+     use --no-default-features to select llvm18 or llvm20"
+);
+
+#[cfg(feature = "llvm18")]
+extern crate llvm_sys_181 as llvm_sys;
+#[cfg(all(feature = "llvm19", not(feature = "llvm18")))]
+extern crate llvm_sys_191 as llvm_sys;
+#[cfg(all(feature = "llvm20", not(any(feature = "llvm18", feature = "llvm19"))))]
+extern crate llvm_sys_201 as llvm_sys;
+
+pub mod cstr;
+pub mod instrument;
+pub mod options;
+pub mod stackless;
@@ -0,0 +1,95 @@
+use move_core_types::account_address::AccountAddress;
+use move_model::model::ModuleId;
+
+/// The options code generation depends on. The driver derives them from its command
+/// line options; see the documentation of the corresponding flags there.
+#[derive(Debug, Clone)]
+pub struct CodegenOptions {
+    /// Emit DWARF debug info.
+    pub debug: bool,
+    /// Abort with ARITHMETIC_ERROR when unsigned `+`, `-` or `*` overflows.
+    pub overflow_checks: bool,
+    /// Omit the dynamic checks of arithmetic, for code proven not to abort on them: overflow,
+    /// division by zero, oversized shifts and narrowing casts no longer abort with
+    /// ARITHMETIC_ERROR, whatever `overflow_checks` says.
+    pub assume_no_abort: bool,
+    /// Compile calls to the `debug` natives to no-ops.
+    pub strip_debug_natives: bool,
+    /// Also export the non-generic `public` functions, without a call selector.
+    pub export_public: bool,
+    /// Do not export entry functions, only dispatch them through the call selector, so the
+    /// blob exports just the runtime's `call` and `deploy` and the `extra_exports`.
+    pub strip_exports: bool,
+    /// Functions (`module::function`) to export in addition, also with `strip_exports`.
+    pub extra_exports: Vec<String>,
+    /// Instrument every basic block with a coverage counter.
+    pub coverage: bool,
+    /// Register the guest allocator's counters with the host on entry of exported functions.
+    pub heap_stats: bool,
+    /// Check every reference against the valid memory ranges before it is dereferenced.
+    pub paranoid: bool,
+    /// Keep a shadow stack of the active Move functions for stack traces on abort.
+    pub stack_traces: bool,
+    /// Entry function selector overrides, as `module::function=0x12345678`.
+    pub selector_overrides: Vec<String>,
+    /// Inline hints overriding the `#[inline_*]` attributes, as `module::function=always`,
+    /// see [`crate::stackless::InlineHint`].
+    pub inline_overrides: Vec<String>,
+    /// Revert calls that transfer value to entry functions without `#[payable]`.
+    pub payable_checks: bool,
+    /// Function attributes to pass on to the ABI and metadata, besides
+    /// [`crate::stackless::FUNCTION_ATTRIBUTES`].
+    pub keep_attributes: Vec<String>,
+    /// Additional functions (`module::function`) to treat as reachable when pruning.
+    pub reachability_roots: Vec<String>,
+    /// Unit test function name, when compiling for testing.
+    pub unit_test_function: Option<String>,
+    /// Build for the mock host, allowing calls to natives pallet-revive doesn't serve, see
+    /// [`crate::stackless::MOCK_HOST_NATIVES`]. Unit test builds are always for the mock host.
+    pub mock_host: bool,
+    /// Record the control flow graph of every translated function in
+    /// [`crate::instrument::Instrumentation::cfgs`].
+    pub collect_cfgs: bool,
+    /// Length of Move addresses in bytes, 20 or 32. It must match the linked runtime.
+    pub address_length: usize,
+    /// Address of the standard library, the `std` named address. The natives of its `debug`
+    /// module are lowered to runtime calls.
+    pub stdlib_address: AccountAddress,
+    /// Lower `u256` arithmetic, comparisons and casts to calls into the runtime helpers
+    /// (`move_rt_add256`, ...) instead of `i256` LLVM operations, which the riscv32 backend
+    /// expands poorly or not at all.
+    pub u256_helpers: bool,
+    /// Generate the code of the blob of this module only, with `--split-modules`: the entry
+    /// functions of other modules are neither exported nor dispatched by its call selector.
+    pub split_module: Option<ModuleId>,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            debug: false,
+            overflow_checks: false,
+            assume_no_abort: false,
+            strip_debug_natives: false,
+            export_public: false,
+            strip_exports: false,
+            extra_exports: vec![],
+            coverage: false,
+            heap_stats: false,
+            paranoid: false,
+            stack_traces: false,
+            selector_overrides: vec![],
+            inline_overrides: vec![],
+            payable_checks: false,
+            keep_attributes: vec![],
+            reachability_roots: vec![],
+            unit_test_function: None,
+            mock_host: false,
+            collect_cfgs: false,
+            address_length: AccountAddress::LENGTH,
+            stdlib_address: AccountAddress::ONE,
+            u256_helpers: false,
+            split_module: None,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Move attributes of functions the compiler passes on.
+//!
+//! - `#[selector = 0x...]` overrides the selector of an entry function, see [`entry_selector`].
+//! - `#[payable]` lets an entry function receive value; with `--payable-checks`,
+//!   `call_selector` reverts calls transferring value to any other entry function.
+//! - `#[deprecated]`, and the attributes named with `--keep-attribute`, are only passed through.
+//!
+//! All of them are listed, with their values, in the ABI and the package metadata, so contract
+//! authors can annotate functions for their own tooling. Other attributes, such as `#[test]`,
+//! are dropped.
+//!
+//! `#[inline_hint]`, `#[inline_always]` and `#[inline_never]` only tune code generation, see
+//! [`inline_hint`]; they are not listed.
+//!
+//! [`entry_selector`]: crate::stackless::entry_selector
+
+use crate::options::CodegenOptions;
+use itertools::Itertools;
+use move_model::{ast as mast, model as mm};
+
+/// Function attributes passed on without `--keep-attribute`.
+pub const FUNCTION_ATTRIBUTES: &[&str] = &["selector", "payable", "deprecated"];
+
+/// An attribute of a function, e.g. `#[deprecated(note = b"use g")]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionAttribute {
+    pub name: String,
+    /// The value of `#[name = value]`, or the arguments of `#[name(args)]` as written
+    /// (`note = 0x757365`), `None` for a bare `#[name]`. Numbers are given in decimal, but
+    /// selectors in hex, and byte strings in hex.
+    pub value: Option<String>,
+}
+
+/// The attributes of `fn_env` that are passed on, in declaration order.
+pub fn function_attributes(
+    fn_env: &mm::FunctionEnv,
+    options: &CodegenOptions,
+) -> Vec<FunctionAttribute> {
+    fn_env
+        .get_attributes()
+        .iter()
+        .filter_map(|attr| {
+            let name = attribute_name(fn_env, attr);
+            let selected = FUNCTION_ATTRIBUTES.contains(&name.as_str())
+                || options.keep_attributes.contains(&name);
+            selected.then(|| FunctionAttribute {
+                value: attribute_value(fn_env, attr),
+                name,
+            })
+        })
+        .collect()
+}
+
+/// The attribute `name` of `fn_env`, if it has one.
+pub fn find_attribute<'a>(fn_env: &'a mm::FunctionEnv, name: &str) -> Option<&'a mast::Attribute> {
+    fn_env
+        .get_attributes()
+        .iter()
+        .find(|attr| attribute_name(fn_env, attr) == name)
+}
+
+/// Whether the entry function `fn_env` is marked `#[payable]`.
+pub fn is_payable(fn_env: &mm::FunctionEnv) -> bool {
+    find_attribute(fn_env, "payable").is_some()
+}
+
+/// Attributes giving the inline hint of a function. `inline` itself is a Move keyword, for
+/// functions the Move compiler expands at their call sites.
+pub const INLINE_ATTRIBUTES: &[(&str, InlineHint)] = &[
+    ("inline_hint", InlineHint::Hint),
+    ("inline_always", InlineHint::Always),
+    ("inline_never", InlineHint::Never),
+];
+
+/// How LLVM should inline calls to a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlineHint {
+    /// `#[inline_hint]`: inlining is desirable, the inliner still decides.
+    Hint,
+    /// `#[inline_always]`: inline every call, also without optimizations.
+    Always,
+    /// `#[inline_never]`: never inline calls, e.g. to keep a cold path out of its callers.
+    Never,
+}
+
+impl InlineHint {
+    /// The LLVM function attribute of the hint.
+    pub fn llvm_attribute(&self) -> &'static str {
+        match self {
+            InlineHint::Hint => "inlinehint",
+            InlineHint::Always => "alwaysinline",
+            InlineHint::Never => "noinline",
+        }
+    }
+}
+
+impl std::str::FromStr for InlineHint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "hint" => Ok(InlineHint::Hint),
+            "always" => Ok(InlineHint::Always),
+            "never" => Ok(InlineHint::Never),
+            _ => Err(format!(
+                "invalid inline hint '{s}', expected hint, always or never"
+            )),
+        }
+    }
+}
+
+/// The inline hint of `fn_env`: the `--inline module::function=<hint>` override, or its
+/// `#[inline_hint]`, `#[inline_always]` or `#[inline_never]` attribute.
+pub fn inline_hint(
+    fn_env: &mm::FunctionEnv,
+    options: &CodegenOptions,
+) -> anyhow::Result<Option<InlineHint>> {
+    let name = fn_env.get_full_name_str();
+    if let Some((_, hint)) = options
+        .inline_overrides
+        .iter()
+        .filter_map(|o| o.split_once('='))
+        .find(|(f, _)| *f == name)
+    {
+        return hint
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("`--inline` of `{name}`: {e}"));
+    }
+    let hints = INLINE_ATTRIBUTES
+        .iter()
+        .filter_map(|(attr_name, hint)| find_attribute(fn_env, attr_name).map(|attr| (attr, *hint)))
+        .collect::<Vec<_>>();
+    match hints.as_slice() {
+        [] => Ok(None),
+        [(mast::Attribute::Apply(_, _, args), hint)] if args.is_empty() => Ok(Some(*hint)),
+        [(attr, _)] => anyhow::bail!(
+            "`#[{}]` of `{name}` takes no value",
+            attribute_name(fn_env, attr)
+        ),
+        _ => anyhow::bail!(
+            "`{name}` has more than one of `#[inline_hint]`, `#[inline_always]` and \
+             `#[inline_never]`"
+        ),
+    }
+}
+
+fn attribute_name(fn_env: &mm::FunctionEnv, attr: &mast::Attribute) -> String {
+    let sym = match attr {
+        mast::Attribute::Assign(_, sym, _) | mast::Attribute::Apply(_, sym, _) => sym,
+    };
+    fn_env.module_env.symbol_pool().string(*sym).to_string()
+}
+
+fn attribute_value(fn_env: &mm::FunctionEnv, attr: &mast::Attribute) -> Option<String> {
+    match attr {
+        mast::Attribute::Assign(_, sym, value) => Some(match value {
+            // selectors are read in hex, as `#[selector = 0x...]` is written
+            mast::AttributeValue::Value(_, mast::Value::Number(n))
+                if fn_env.module_env.symbol_pool().string(*sym).as_str() == "selector" =>
+            {
+                format!("0x{n:08x}")
+            }
+            value => display_value(fn_env, value),
+        }),
+        mast::Attribute::Apply(_, _, args) if args.is_empty() => None,
+        mast::Attribute::Apply(_, _, args) => Some(
+            args.iter()
+                .map(|arg| {
+                    let name = attribute_name(fn_env, arg);
+                    match attribute_value(fn_env, arg) {
+                        Some(value) if matches!(arg, mast::Attribute::Assign(..)) => {
+                            format!("{name} = {value}")
+                        }
+                        Some(args) => format!("{name}({args})"),
+                        None => name,
+                    }
+                })
+                .join(", "),
+        ),
+    }
+}
+
+fn display_value(fn_env: &mm::FunctionEnv, value: &mast::AttributeValue) -> String {
+    match value {
+        mast::AttributeValue::Value(_, mast::Value::Number(n)) => n.to_string(),
+        mast::AttributeValue::Value(_, mast::Value::Bool(b)) => b.to_string(),
+        mast::AttributeValue::Value(_, mast::Value::ByteArray(bytes)) => {
+            format!("0x{}", hex::encode(bytes))
+        }
+        mast::AttributeValue::Value(_, value) => format!("{value:?}"),
+        mast::AttributeValue::Name(_, module, sym) => {
+            let name = fn_env.module_env.symbol_pool().string(*sym);
+            match module {
+                Some(module) => format!("{}::{name}", module.display(fn_env.module_env.env)),
+                None => name.to_string(),
+            }
+        }
+    }
+}
@@ -0,0 +1,737 @@
+//! Validation of stackless bytecode without involving LLVM.
+//!
+//! The code generator panics with `todo!` on constructs it does not support yet.
+//! This pass walks the same stackless bytecode ahead of time, so unsupported
+//! constructs are all reported at once, with their source locations, before any
+//! code is emitted. Selector collisions between entry functions, across all modules,
+//! `--extra-export`s that can't be exported, invalid inline hints, recursive struct types and
+//! entry function parameters `call_selector` can't pass are reported the same way, as are
+//! types only specifications have, such as `num`, outside of specifications, and calls to
+//! natives only the mock host serves outside of mock builds. `--check` runs only this pass.
+
+use crate::{
+    options::CodegenOptions,
+    stackless::{
+        called_functions, entry_selector, extensions::FunctionEnvExt, inline_hint, ExportPolicy,
+    },
+};
+use move_model::{ast as mast, model as mm, ty as mty};
+use move_stackless_bytecode::{
+    stackless_bytecode as sbc, stackless_bytecode_generator::StacklessBytecodeGenerator,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::debug;
+
+/// Collect a message for every unsupported construct in the functions of `module_env`,
+/// prefixed with its source location. With `reachable`, other functions are skipped,
+/// as no code is generated for them. Address constants must fit in `address_length` bytes.
+pub fn check_module(
+    module_env: &mm::ModuleEnv,
+    reachable: Option<&BTreeSet<mm::QualifiedId<mm::FunId>>>,
+    address_length: usize,
+) -> Vec<String> {
+    let global_env = module_env.env;
+    let mut issues = vec![];
+    for fn_env in module_env.get_functions() {
+        if fn_env.is_native() || fn_env.is_inline() {
+            continue;
+        }
+        if reachable.is_some_and(|reachable| !reachable.contains(&fn_env.get_qualified_id())) {
+            continue;
+        }
+        let fn_name = fn_env.get_full_name_str();
+        debug!("Checking function {fn_name}");
+        let fn_data = StacklessBytecodeGenerator::new(&fn_env).generate_function();
+        let fn_loc = describe_loc(global_env, &fn_env.get_loc());
+        let tdc = fn_env.get_type_display_ctx();
+        for (idx, local_mty) in fn_data.local_types.iter().enumerate() {
+            if let Some(spec_mty) = spec_only_type(local_mty) {
+                issues.push(format!(
+                    "{fn_loc}: {fn_name}: local {idx}: unsupported type {} in non-spec context",
+                    spec_mty.display(&tdc)
+                ));
+            } else if !is_supported_value_type(local_mty) {
+                issues.push(format!(
+                    "{fn_loc}: {fn_name}: local {idx} has unsupported type {}",
+                    local_mty.display(&tdc)
+                ));
+            }
+        }
+        for instr in &fn_data.code {
+            let loc = || {
+                fn_data
+                    .locations
+                    .get(&instr.get_attr_id())
+                    .map(|loc| describe_loc(global_env, loc))
+                    .unwrap_or_else(|| fn_loc.clone())
+            };
+            match instr {
+                sbc::Bytecode::Call(_, _, op, _, _) if !is_supported_operation(op) => {
+                    issues.push(format!(
+                        "{}: {fn_name}: unsupported operation {op:?}",
+                        loc()
+                    ));
+                }
+                sbc::Bytecode::Load(_, idx, sbc::Constant::Vector(_)) => {
+                    let elt_mty = fn_data.local_types[*idx].vector_element_type();
+                    if !is_supported_vec_constant_element(&elt_mty) {
+                        issues.push(format!(
+                            "{}: {fn_name}: unsupported vector constant with elements of type {}",
+                            loc(),
+                            elt_mty.display(&tdc)
+                        ));
+                    }
+                }
+                sbc::Bytecode::Call(_, _, op, _, _) => {
+                    if let Some(spec_mty) = operation_type_args(op).iter().find_map(spec_only_type)
+                    {
+                        issues.push(format!(
+                            "{}: {fn_name}: unsupported type {} in non-spec context",
+                            loc(),
+                            spec_mty.display(&tdc)
+                        ));
+                    }
+                }
+                _ => {}
+            }
+            if let sbc::Bytecode::Load(_, _, constant) = instr {
+                if let Some(address) = constant_addresses(constant)
+                    .into_iter()
+                    .find(|address| !fits_address_length(address, address_length))
+                {
+                    issues.push(format!(
+                        "{}: {fn_name}: address {} is longer than {address_length} bytes",
+                        loc(),
+                        address.expect_numerical().to_hex_literal()
+                    ));
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Collect a message for every type only specifications have, such as `num`, `range` and
+/// event stores, in a struct field or in the signature of a function, prefixed with its
+/// source location. The code generator has no layout for these, and the compiler only puts
+/// them in specifications, but models built otherwise, e.g. from `--bytecode`, may not.
+/// With `reachable`, the signatures of other functions are skipped.
+pub fn check_types(
+    global_env: &mm::GlobalEnv,
+    reachable: Option<&BTreeSet<mm::QualifiedId<mm::FunId>>>,
+) -> Vec<String> {
+    let mut issues = vec![];
+    for module_env in global_env.get_modules() {
+        for s_env in module_env.get_structs() {
+            let tdc = global_env.get_type_display_ctx();
+            for fld_env in s_env.get_fields() {
+                if let Some(spec_mty) = spec_only_type(&fld_env.get_type()) {
+                    issues.push(format!(
+                        "{}: {}: field {}: unsupported type {} in non-spec context",
+                        describe_loc(global_env, &s_env.get_loc()),
+                        s_env.get_full_name_str(),
+                        fld_env.get_name().display(s_env.symbol_pool()),
+                        spec_mty.display(&tdc)
+                    ));
+                }
+            }
+        }
+        for fn_env in module_env.get_functions() {
+            if fn_env.is_inline()
+                || reachable
+                    .is_some_and(|reachable| !reachable.contains(&fn_env.get_qualified_id()))
+            {
+                continue;
+            }
+            let fn_loc = describe_loc(global_env, &fn_env.get_loc());
+            let fn_name = fn_env.get_full_name_str();
+            let tdc = fn_env.get_type_display_ctx();
+            for param in fn_env.get_parameters() {
+                if let Some(spec_mty) = spec_only_type(&param.1) {
+                    issues.push(format!(
+                        "{fn_loc}: {fn_name}: parameter {}: unsupported type {} in non-spec context",
+                        param.0.display(fn_env.module_env.symbol_pool()),
+                        spec_mty.display(&tdc)
+                    ));
+                }
+            }
+            if let Some(spec_mty) = spec_only_type(&fn_env.get_result_type()) {
+                issues.push(format!(
+                    "{fn_loc}: {fn_name}: return type: unsupported type {} in non-spec context",
+                    spec_mty.display(&tdc)
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Collect a message for every entry function whose selector is invalid or is the selector
+/// of another entry function, in any module, prefixed with its source location. All entry
+/// functions are dispatched by the one `call_selector`, so their selectors must be unique.
+/// The `--selector` overrides are checked first: one that is malformed, names no entry
+/// function or overrides a function a second time is reported instead.
+pub fn check_selectors(global_env: &mm::GlobalEnv, options: &CodegenOptions) -> Vec<String> {
+    let entry_functions = global_env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .filter(|f| f.is_entry())
+        .collect::<Vec<_>>();
+    let mut overridden = BTreeSet::new();
+    let mut issues = vec![];
+    for selector in &options.selector_overrides {
+        let Some((name, value)) = selector.split_once('=') else {
+            issues.push(format!(
+                "selector `{selector}`: expected `module::function=0x12345678`"
+            ));
+            continue;
+        };
+        if !entry_functions
+            .iter()
+            .any(|f| f.get_full_name_str() == name)
+        {
+            issues.push(format!("selector `{selector}`: no entry function `{name}`"));
+        } else if let Err(e) = u32::from_str_radix(value.trim_start_matches("0x"), 16) {
+            issues.push(format!(
+                "selector `{selector}`: invalid selector `{value}`: {e}"
+            ));
+        } else if !overridden.insert(name) {
+            issues.push(format!(
+                "selector `{selector}`: the selector of `{name}` is already overridden"
+            ));
+        }
+    }
+    if !issues.is_empty() {
+        return issues;
+    }
+
+    let mut selectors: BTreeMap<u32, String> = BTreeMap::new();
+    for fn_env in entry_functions {
+        let fn_name = fn_env.get_full_name_str();
+        let fn_loc = describe_loc(global_env, &fn_env.get_loc());
+        match entry_selector(&fn_env, options) {
+            Ok(selector) => {
+                if let Some(other) = selectors.insert(selector, fn_name.clone()) {
+                    issues.push(format!(
+                        "{fn_loc}: selector collision: entry functions `{other}` and `{fn_name}` \
+                         both map to selector 0x{:08x}; override one of them with \
+                         `#[selector = 0x...]` or `--selector`",
+                        u32::from_be_bytes(selector.to_le_bytes())
+                    ));
+                }
+            }
+            Err(e) => issues.push(format!("{fn_loc}: {e}")),
+        }
+    }
+    issues
+}
+
+/// A parameter of an entry function that `call_selector` can't pass as the caller expects,
+/// found by [`check_entry_params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryParamIssue {
+    /// Calls can never succeed, the build fails. Otherwise the issue is a warning.
+    pub is_error: bool,
+    /// `file:line:column` of the function.
+    pub location: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for EntryParamIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Check the parameters of every entry function against what `call_selector` can pass: the
+/// signer of the call for a leading `signer` or `&signer`, and values decoded from the call
+/// data for everything else. Only the first parameter can be the signer of the call, later
+/// signer parameters are errors. References to other types can't be decoded from call data,
+/// every call aborts: those are errors too. So are structs the caller could forge by choosing
+/// their fields: structs without `copy` and `drop`, which would mint resources, and structs
+/// of other modules, whose fields only their own module may set, and signers in vectors or
+/// structs, which the runtime refuses to decode from call data. Generic entry functions,
+/// which have no instantiation to call and are not dispatched, are warnings.
+pub fn check_entry_params(
+    global_env: &mm::GlobalEnv,
+    options: &CodegenOptions,
+) -> Vec<EntryParamIssue> {
+    let mut issues = vec![];
+    for fn_env in global_env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .filter(|f| !f.is_native() && ExportPolicy::of(f, options).is_dispatched())
+    {
+        let fn_name = fn_env.get_full_name_str();
+        let fn_loc = describe_loc(global_env, &fn_env.get_loc());
+        let mut issue = |is_error: bool, message: String| {
+            issues.push(EntryParamIssue {
+                is_error,
+                location: fn_loc.clone(),
+                message: format!("entry function `{fn_name}`: {message}"),
+            })
+        };
+        if fn_env.get_type_parameter_count() > 0 {
+            issue(
+                false,
+                "generic entry functions are not dispatched by call_selector and can't be called"
+                    .to_string(),
+            );
+            continue;
+        }
+        let tdc = fn_env.get_type_display_ctx();
+        let module_id = fn_env.module_env.get_id();
+        for (i, param) in fn_env.get_parameters().into_iter().enumerate() {
+            let param_name = param.0.display(fn_env.symbol_pool()).to_string();
+            let param_ty = &param.1;
+            if let Some(reason) = forgeable_struct(global_env, module_id, param_ty) {
+                issue(
+                    true,
+                    format!(
+                        "parameter `{param_name}` of type `{}` can't be decoded from call data: \
+                         {reason}",
+                        param_ty.display(&tdc)
+                    ),
+                );
+                continue;
+            }
+            let is_signer =
+                |ty: &mty::Type| *ty == mty::Type::Primitive(mty::PrimitiveType::Signer);
+            match param_ty {
+                ty if is_signer(ty)
+                    || matches!(ty, mty::Type::Reference(_, ty) if is_signer(ty)) =>
+                {
+                    if i > 0 {
+                        issue(
+                            true,
+                            format!(
+                                "parameter `{param_name}`: only the first parameter can be the \
+                                 signer of the call"
+                            ),
+                        )
+                    }
+                }
+                mty::Type::Reference(..) => issue(
+                    true,
+                    format!(
+                        "parameter `{param_name}` of type `{}` can't be decoded from call data, \
+                         only `&signer` is passed by reference; take the value instead",
+                        param_ty.display(&tdc)
+                    ),
+                ),
+                _ if contains_signer(global_env, param_ty) => issue(
+                    true,
+                    format!(
+                        "parameter `{param_name}` of type `{}` contains signers, which can't be \
+                         decoded from call data: the caller would choose them",
+                        param_ty.display(&tdc)
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+    issues
+}
+
+/// Whether `mty` is or contains a signer, in vectors, fields and type arguments.
+fn contains_signer(global_env: &mm::GlobalEnv, mty: &mty::Type) -> bool {
+    match mty {
+        mty::Type::Primitive(mty::PrimitiveType::Signer) => true,
+        mty::Type::Vector(inner) | mty::Type::Reference(_, inner) => {
+            contains_signer(global_env, inner)
+        }
+        mty::Type::Struct(mid, sid, tys) => global_env
+            .get_struct(mid.qualified(*sid))
+            .get_fields()
+            .any(|field| contains_signer(global_env, &field.get_type().instantiate(tys))),
+        _ => false,
+    }
+}
+
+/// Why the caller could forge a value of a struct in `mty`, in vectors, fields and type
+/// arguments, if it was decoded from call data for an entry function of module `module_id`:
+/// the struct lacks `copy` or `drop`, or is defined in another module.
+fn forgeable_struct(
+    global_env: &mm::GlobalEnv,
+    module_id: mm::ModuleId,
+    mty: &mty::Type,
+) -> Option<String> {
+    match mty {
+        mty::Type::Vector(inner) => forgeable_struct(global_env, module_id, inner),
+        mty::Type::Struct(mid, sid, tys) => {
+            let s_env = global_env.get_struct(mid.qualified(*sid));
+            let abilities = s_env.get_abilities();
+            if !(abilities.has_copy() && abilities.has_drop()) {
+                return Some(format!(
+                    "struct `{}` lacks `copy` or `drop`, the caller could mint it",
+                    s_env.get_full_name_str()
+                ));
+            }
+            if *mid != module_id {
+                return Some(format!(
+                    "struct `{}` is defined in another module, only that module may set its fields",
+                    s_env.get_full_name_str()
+                ));
+            }
+            tys.iter()
+                .find_map(|ty| forgeable_struct(global_env, module_id, ty))
+                .or_else(|| {
+                    s_env.get_fields().find_map(|field| {
+                        forgeable_struct(global_env, module_id, &field.get_type().instantiate(tys))
+                    })
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Collect a message for every exported function whose export name, the plain Move function
+/// name, is the export name of another exported function, e.g. a `public fun init` in two
+/// modules built with `--export-public`. A blob can only export each name once.
+pub fn check_export_collisions(
+    global_env: &mm::GlobalEnv,
+    options: &CodegenOptions,
+) -> Vec<String> {
+    let mut exports: BTreeMap<String, String> = BTreeMap::new();
+    let mut issues = vec![];
+    for fn_env in global_env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .filter(|f| ExportPolicy::of(f, options).is_exported())
+    {
+        let fn_name = fn_env.get_full_name_str();
+        let symbol = fn_env.llvm_symbol_name(&[]);
+        if let Some(other) = exports.insert(symbol.clone(), fn_name.clone()) {
+            issues.push(format!(
+                "{}: export collision: `{other}` and `{fn_name}` are both exported as `{symbol}`; \
+                 exported functions must have unique names",
+                describe_loc(global_env, &fn_env.get_loc())
+            ));
+        }
+    }
+    issues
+}
+
+/// Collect a message for every `--extra-export` that names no function, or a function that
+/// can't be exported: a generic or native one.
+pub fn check_extra_exports(global_env: &mm::GlobalEnv, options: &CodegenOptions) -> Vec<String> {
+    options
+        .extra_exports
+        .iter()
+        .filter_map(|name| {
+            let Some(fn_env) = global_env
+                .get_modules()
+                .flat_map(|m| m.into_functions())
+                .find(|f| f.get_full_name_str() == *name)
+            else {
+                return Some(format!("extra export `{name}`: no such function"));
+            };
+            (fn_env.get_type_parameter_count() > 0 || fn_env.is_native()).then(|| {
+                format!(
+                    "{}: extra export `{name}`: generic and native functions can't be exported",
+                    describe_loc(global_env, &fn_env.get_loc())
+                )
+            })
+        })
+        .collect()
+}
+
+/// Natives whose host imports only the mock host serves: pallet-revive has no source of
+/// randomness, so a blob calling them can't be deployed.
+pub const MOCK_HOST_NATIVES: &[&str] = &["move_native_random_bytes"];
+
+/// Collect a message for every call to one of the [`MOCK_HOST_NATIVES`], unless the build is
+/// for the mock host, `mock_host` or a unit test. With `reachable`, other callers are skipped.
+pub fn check_mock_natives(
+    global_env: &mm::GlobalEnv,
+    reachable: Option<&BTreeSet<mm::QualifiedId<mm::FunId>>>,
+    options: &CodegenOptions,
+) -> Vec<String> {
+    if options.mock_host || options.unit_test_function.is_some() {
+        return vec![];
+    }
+    global_env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .filter(|f| !reachable.is_some_and(|reachable| !reachable.contains(&f.get_qualified_id())))
+        .flat_map(|caller| {
+            called_functions(&caller)
+                .into_iter()
+                .map(|callee| global_env.get_function(callee))
+                .filter(|callee| {
+                    callee.is_native()
+                        && MOCK_HOST_NATIVES.contains(&callee.llvm_native_fn_symbol_name().as_str())
+                })
+                .map(|callee| {
+                    format!(
+                        "{}: {}: `{}` is only served by the mock host, build with --mock-host",
+                        describe_loc(global_env, &caller.get_loc()),
+                        caller.get_full_name_str(),
+                        callee.get_full_name_str()
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Collect a message for every invalid inline hint attribute, and every `--inline` that names
+/// no function or gives an invalid hint.
+pub fn check_inline_hints(global_env: &mm::GlobalEnv, options: &CodegenOptions) -> Vec<String> {
+    let functions = global_env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .collect::<Vec<_>>();
+    let mut issues = functions
+        .iter()
+        .filter_map(|fn_env| {
+            inline_hint(fn_env, options)
+                .err()
+                .map(|e| format!("{}: {e}", describe_loc(global_env, &fn_env.get_loc())))
+        })
+        .collect::<Vec<_>>();
+    for inline in &options.inline_overrides {
+        match inline.split_once('=') {
+            Some((name, _)) if functions.iter().any(|f| f.get_full_name_str() == name) => {}
+            Some((name, _)) => issues.push(format!("inline hint `{inline}`: no function `{name}`")),
+            None => issues.push(format!(
+                "inline hint `{inline}`: expected `module::function=<hint|always|never>`"
+            )),
+        }
+    }
+    issues
+}
+
+/// Collect a message for every cycle among struct definitions: a struct containing itself,
+/// directly or through other structs, in a field or in a type argument of a field. The
+/// bytecode verifier rejects these, but modules compiled from `--bytecode` are not verified,
+/// and laying out such a struct would recurse forever.
+pub fn check_struct_cycles(global_env: &mm::GlobalEnv) -> Vec<String> {
+    let mut done = BTreeSet::new();
+    let mut issues = vec![];
+    for module_env in global_env.get_modules() {
+        for s_env in module_env.get_structs() {
+            visit_struct(
+                global_env,
+                s_env.get_qualified_id(),
+                &mut vec![],
+                &mut done,
+                &mut issues,
+            );
+        }
+    }
+    issues
+}
+
+/// Depth-first walk of the structs contained in struct `id`, reporting the back edges to
+/// the structs on `path`, which is the cycle they close.
+fn visit_struct(
+    global_env: &mm::GlobalEnv,
+    id: mm::QualifiedId<mm::StructId>,
+    path: &mut Vec<mm::QualifiedId<mm::StructId>>,
+    done: &mut BTreeSet<mm::QualifiedId<mm::StructId>>,
+    issues: &mut Vec<String>,
+) {
+    if done.contains(&id) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|other| *other == id) {
+        let cycle = path[start..]
+            .iter()
+            .chain([&id])
+            .map(|id| global_env.get_struct(*id).get_full_name_str())
+            .collect::<Vec<_>>();
+        let s_env = global_env.get_struct(path[start]);
+        issues.push(format!(
+            "{}: recursive struct type: {}",
+            describe_loc(global_env, &s_env.get_loc()),
+            cycle.join(" -> ")
+        ));
+        return;
+    }
+    path.push(id);
+    let mut contained = BTreeSet::new();
+    for fld_env in global_env.get_struct(id).get_fields() {
+        contained_structs(&fld_env.get_type(), &mut contained);
+    }
+    for other in contained {
+        visit_struct(global_env, other, path, done, issues);
+    }
+    path.pop();
+    done.insert(id);
+}
+
+/// The structs `mty` is or contains, in type arguments, vectors and references.
+fn contained_structs(mty: &mty::Type, structs: &mut BTreeSet<mm::QualifiedId<mm::StructId>>) {
+    match mty {
+        mty::Type::Struct(mid, sid, tys) => {
+            structs.insert(mid.qualified(*sid));
+            tys.iter().for_each(|ty| contained_structs(ty, structs));
+        }
+        mty::Type::Vector(inner) | mty::Type::Reference(_, inner) => {
+            contained_structs(inner, structs)
+        }
+        _ => {}
+    }
+}
+
+/// Whether `address` fits in a target address of `address_length` bytes, laid out like
+/// `FunctionContext::address_constant_bytes` does.
+fn fits_address_length(address: &mast::Address, address_length: usize) -> bool {
+    match address {
+        mast::Address::Numerical(address) => {
+            address.to_big_uint().to_bytes_le().len() <= address_length
+        }
+        mast::Address::Symbolic(_) => true,
+    }
+}
+
+/// The addresses in a constant, including the elements of (nested) vectors.
+fn constant_addresses(constant: &sbc::Constant) -> Vec<&mast::Address> {
+    match constant {
+        sbc::Constant::Address(address) => vec![address],
+        sbc::Constant::AddressArray(addresses) => addresses.iter().collect(),
+        sbc::Constant::Vector(elements) => elements.iter().flat_map(constant_addresses).collect(),
+        _ => vec![],
+    }
+}
+
+/// `file:line:column` of `loc`, 1-based.
+fn describe_loc(global_env: &mm::GlobalEnv, loc: &mm::Loc) -> String {
+    match global_env.get_file_and_location(loc) {
+        Some((file, location)) => {
+            format!("{file}:{}:{}", location.line.0 + 1, location.column.0 + 1)
+        }
+        None => "<unknown location>".to_string(),
+    }
+}
+
+/// Types of locals the code generator can lay out and assign: primitives, vectors,
+/// structs and references to them. Function values and tuples are not supported.
+fn is_supported_value_type(mty: &mty::Type) -> bool {
+    match mty {
+        mty::Type::Primitive(
+            mty::PrimitiveType::Bool
+            | mty::PrimitiveType::U8
+            | mty::PrimitiveType::U16
+            | mty::PrimitiveType::U32
+            | mty::PrimitiveType::U64
+            | mty::PrimitiveType::U128
+            | mty::PrimitiveType::U256
+            | mty::PrimitiveType::Address
+            | mty::PrimitiveType::Signer,
+        ) => true,
+        mty::Type::Vector(inner) | mty::Type::Reference(_, inner) => is_supported_value_type(inner),
+        mty::Type::Struct(_, _, tys) => tys.iter().all(is_supported_value_type),
+        mty::Type::TypeParameter(_) => true,
+        _ => false,
+    }
+}
+
+/// The type in `mty` that only specifications have, if any: a `num`, `range` or event store,
+/// also as a vector element, a referenced type or a type argument.
+pub fn spec_only_type(mty: &mty::Type) -> Option<&mty::Type> {
+    match mty {
+        mty::Type::Primitive(
+            mty::PrimitiveType::Num | mty::PrimitiveType::Range | mty::PrimitiveType::EventStore,
+        ) => Some(mty),
+        mty::Type::Vector(inner) | mty::Type::Reference(_, inner) => spec_only_type(inner),
+        mty::Type::Struct(_, _, tys) | mty::Type::Tuple(tys) => tys.iter().find_map(spec_only_type),
+        _ => None,
+    }
+}
+
+/// The type arguments of an operation on a function or struct.
+fn operation_type_args(op: &sbc::Operation) -> &[mty::Type] {
+    use sbc::Operation;
+    match op {
+        Operation::Function(_, _, tys)
+        | Operation::Pack(_, _, tys)
+        | Operation::Unpack(_, _, tys)
+        | Operation::MoveTo(_, _, tys)
+        | Operation::MoveFrom(_, _, tys)
+        | Operation::Exists(_, _, tys)
+        | Operation::BorrowGlobal(_, _, tys, _)
+        | Operation::BorrowField(_, _, tys, _) => tys,
+        _ => &[],
+    }
+}
+
+/// Element types of vector literals that `FunctionContext::constant` can emit as data.
+fn is_supported_vec_constant_element(elt_mty: &mty::Type) -> bool {
+    match elt_mty {
+        mty::Type::Vector(inner) => is_supported_vec_constant_element(inner),
+        mty::Type::Primitive(mty::PrimitiveType::Address) => true,
+        _ => elt_mty.is_number() || elt_mty.is_bool(),
+    }
+}
+
+/// Operations handled by `FunctionContext::translate_call`.
+fn is_supported_operation(op: &sbc::Operation) -> bool {
+    use sbc::Operation;
+    matches!(
+        op,
+        Operation::Function(..)
+            | Operation::MoveTo(..)
+            | Operation::MoveFrom(..)
+            | Operation::Exists(..)
+            | Operation::BorrowGlobal(..)
+            | Operation::BorrowLoc
+            | Operation::BorrowField(..)
+            | Operation::Pack(..)
+            | Operation::Vector
+            | Operation::Unpack(..)
+            | Operation::Release
+            | Operation::Drop
+            | Operation::ReadRef
+            | Operation::WriteRef
+            | Operation::FreezeRef(..)
+            | Operation::Add
+            | Operation::Sub
+            | Operation::Mul
+            | Operation::Div
+            | Operation::Mod
+            | Operation::BitOr
+            | Operation::BitAnd
+            | Operation::Xor
+            | Operation::Shl
+            | Operation::Shr
+            | Operation::Lt
+            | Operation::Gt
+            | Operation::Le
+            | Operation::Ge
+            | Operation::Or
+            | Operation::And
+            | Operation::Eq
+            | Operation::Neq
+            | Operation::Not
+            | Operation::CastU8
+            | Operation::CastU16
+            | Operation::CastU32
+            | Operation::CastU64
+            | Operation::CastU128
+            | Operation::CastU256
+            // Specification-related operations are ignored by the code generator.
+            | Operation::GetField(..)
+            | Operation::GetGlobal(..)
+            | Operation::IsParent(..)
+            | Operation::WriteBack(..)
+            | Operation::UnpackRef
+            | Operation::PackRef
+            | Operation::UnpackRefDeep
+            | Operation::PackRefDeep
+            | Operation::TraceLocal(..)
+            | Operation::TraceReturn(..)
+            | Operation::TraceAbort
+            | Operation::TraceExp(..)
+            | Operation::TraceGlobalMem(..)
+            | Operation::EmitEvent
+            | Operation::EventStoreDiverge
+            | Operation::OpaqueCallBegin(..)
+            | Operation::OpaqueCallEnd(..)
+            | Operation::Uninit
+            | Operation::Havoc(..)
+            | Operation::Stop
+    )
+}
@@ -4,9 +4,15 @@
 
 //! Dwarf routines.
 //!
+//! Locals are declared as DWARF variables under the names they have in the source, in the
+//! lexical block of the innermost loop or branch arm covering all their uses, see
+//! [`block_scopes`]. Temporaries of the stackless bytecode have no source name and are left
+//! out.
 
 use crate::stackless::{
-    extensions::FunctionEnvExt, llvm::Module, Alloca, FunctionContext, ModuleContext, TargetData,
+    extensions::FunctionEnvExt,
+    llvm::{di_insert_declare_at_end, Module},
+    Alloca, FunctionContext, ModuleContext, TargetData,
 };
 use anyhow::{Context, Result};
 use codespan::Location;
@@ -18,12 +24,12 @@ use llvm_sys::{
         LLVMDIBuilderCreateCompileUnit, LLVMDIBuilderCreateDebugLocation,
         LLVMDIBuilderCreateExpression, LLVMDIBuilderCreateFile, LLVMDIBuilderCreateFunction,
         LLVMDIBuilderCreateLexicalBlock, LLVMDIBuilderCreateMemberType,
-        LLVMDIBuilderCreateNameSpace, LLVMDIBuilderCreatePointerType,
-        LLVMDIBuilderCreateStructType, LLVMDIBuilderCreateSubroutineType,
-        LLVMDIBuilderCreateUnspecifiedType, LLVMDIBuilderCreateVectorType, LLVMDIBuilderFinalize,
-        LLVMDIBuilderFinalizeSubprogram, LLVMDIBuilderGetOrCreateSubrange,
-        LLVMDIBuilderInsertDeclareRecordAtEnd, LLVMDIFlagObjcClassComplete, LLVMDIFlagZero,
-        LLVMDIFlags, LLVMDITypeGetName, LLVMDWARFEmissionKind,
+        LLVMDIBuilderCreateNameSpace, LLVMDIBuilderCreateParameterVariable,
+        LLVMDIBuilderCreatePointerType, LLVMDIBuilderCreateStructType,
+        LLVMDIBuilderCreateSubroutineType, LLVMDIBuilderCreateUnspecifiedType,
+        LLVMDIBuilderCreateVectorType, LLVMDIBuilderFinalize, LLVMDIBuilderFinalizeSubprogram,
+        LLVMDIBuilderGetOrCreateSubrange, LLVMDIFlagObjcClassComplete, LLVMDIFlagZero, LLVMDIFlags,
+        LLVMDITypeGetName, LLVMDWARFEmissionKind,
         LLVMDWARFSourceLanguage::LLVMDWARFSourceLanguageRust, LLVMDWARFTypeEncoding,
         LLVMGetMetadataKind, LLVMInstructionSetDebugLoc, LLVMMetadataKind, LLVMSetSubprogram,
     },
@@ -31,18 +37,21 @@ use llvm_sys::{
     LLVMModule, LLVMOpaqueMetadata, LLVMValue,
 };
 
-use log::{debug, error, warn};
+use move_model::ast::TempIndex;
 use move_model::model::{GlobalEnv, ModuleId, StructId};
-use move_stackless_bytecode::stackless_bytecode::Bytecode;
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Label};
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     env,
     ffi::CStr,
     fs::File,
     io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
     ptr,
 };
+use tracing::{debug, error, warn};
 
 use super::{GlobalContext, StructType, Type};
 
@@ -119,6 +128,10 @@ pub struct DIBuilderCore<'up> {
     producer: String,
     module_source: String,
     current_function: RefCell<*mut LLVMOpaqueMetadata>,
+    // lexical blocks of the current function by the code they cover, outermost first
+    scopes: RefCell<Vec<(Range<usize>, LLVMMetadataRef)>>,
+    // scope of the instruction being translated, the function or one of `scopes`
+    current_scope: RefCell<LLVMMetadataRef>,
     // basic types
     type_unspecified: LLVMMetadataRef,
     type_u8: LLVMMetadataRef,
@@ -313,13 +326,13 @@ impl<'up> Instruction<'up> {
                 "LLVM modules must be the same"
             );
 
-            let current_function = *di_builder.core().current_function.borrow_mut();
+            let current_scope = *di_builder.core().current_scope.borrow();
 
             let debug_location = LLVMDIBuilderCreateDebugLocation(
                 module_context,
                 line,
                 column,
-                current_function,
+                current_scope,
                 std::ptr::null_mut(), // Inlined at
             );
             dbg_meta_operand!(
@@ -335,7 +348,7 @@ impl<'up> Instruction<'up> {
             if let Some((mty, _ty, alloca)) = more {
                 let lexical_block = LLVMDIBuilderCreateLexicalBlock(
                     builder_ref,
-                    current_function,
+                    current_scope,
                     di_builder_file,
                     line,
                     column,
@@ -368,7 +381,7 @@ impl<'up> Instruction<'up> {
                     LLVMDIBuilderCreateExpression(builder_ref, std::ptr::null_mut(), 0);
 
                 // NOTE: code below inserts llvm.dbg.declare after instruction, but it is not needed for a call instruction
-                let instr_debug = LLVMDIBuilderInsertDeclareRecordAtEnd(
+                di_insert_declare_at_end(
                     builder_ref,
                     alloca.get0(), // local variable we trace in dbg
                     loc,
@@ -376,7 +389,6 @@ impl<'up> Instruction<'up> {
                     debug_location,
                     basic_block,
                 );
-                debug!(target: "instruction", "create_instr_impl instr_debug starting at next line and until line starting with !!!\n{instr_debug:#?}\n!!!\n");
             }
 
             let module_info = module_ref.print_to_str();
@@ -587,6 +599,8 @@ impl<'up> DIBuilder<'up> {
                 producer: producer.clone(),
                 module_source: source.to_string(),
                 current_function: RefCell::new(std::ptr::null_mut::<LLVMOpaqueMetadata>()),
+                scopes: RefCell::new(vec![]),
+                current_scope: RefCell::new(std::ptr::null_mut::<LLVMOpaqueMetadata>()),
                 type_unspecified: create_unspecified_type(builder_ref),
                 type_u8: create_type(builder_ref, "u8", 8, 0, LLVMDIFlagZero),
                 type_u16: create_type(builder_ref, "u16", 16, 0, LLVMDIFlagZero),
@@ -600,7 +614,11 @@ impl<'up> DIBuilder<'up> {
             let module_di_info = print_module_to_str(&module_di);
             debug!(target: "dwarf", "DIBuilder bof DI starting at next line and until line starting with !!!\n{module_di_info}\n!!!\n");
 
-            module.verify();
+            // A failure is reported again, and fails the build, when the translated module is
+            // verified.
+            if let Err(e) = module.verify() {
+                warn!(target: "dwarf", "{e:#}");
+            }
 
             DIBuilder(Some(builder_core))
         } else {
@@ -999,6 +1017,8 @@ impl<'up> DIBuilder<'up> {
             };
             let mut current_function = di_builder_core.current_function.borrow_mut();
             *current_function = function;
+            di_builder_core.scopes.borrow_mut().clear();
+            *di_builder_core.current_scope.borrow_mut() = function;
             unsafe {
                 dbg_meta_operand!(ll_mod, ll_ctx, function, "functions", "create_function");
             };
@@ -1060,6 +1080,123 @@ impl<'up> DIBuilder<'up> {
         None
     }
 
+    /// Create a lexical block for every block of `code` (see [`block_scopes`]) in the function
+    /// being built, and a variable for every local with a source name, declared at the end of
+    /// the entry block. Parameters are declared in the function, other locals in the innermost
+    /// block covering all their uses.
+    pub fn create_scopes_and_locals(&self, func_ctx: &FunctionContext<'_, '_>, code: &[Bytecode]) {
+        let Some(core) = &self.0 else {
+            return;
+        };
+        let di_builder = core.builder_ref;
+        let file = core.builder_file;
+        let function = *core.current_function.borrow();
+        let fn_env = &func_ctx.env;
+        let line_column = |offset: usize| {
+            let loc = fn_env
+                .get_bytecode_loc(code[offset].get_attr_id().as_usize() as u16)
+                .unwrap_or_else(|| fn_env.get_loc());
+            let (_file, line, column, _start, _end) = loc_display(&loc, fn_env.module_env.env);
+            (line, column)
+        };
+
+        let mut scopes = core.scopes.borrow_mut();
+        for range in block_scopes(code) {
+            let parent = innermost_scope(&scopes, &range).unwrap_or(function);
+            let (line, column) = line_column(range.start);
+            let block =
+                unsafe { LLVMDIBuilderCreateLexicalBlock(di_builder, parent, file, line, column) };
+            debug!(target: "dwarf", "lexical block for code {range:?} at {line}:{column}");
+            scopes.push((range, block));
+        }
+
+        let param_count = fn_env.get_parameter_count();
+        let symbol_pool = fn_env.module_env.symbol_pool();
+        let uses = local_uses(code);
+        let local_count = fn_env.get_local_count().unwrap_or(0);
+        for (idx, local) in func_ctx.locals.iter().enumerate().take(local_count) {
+            let Some(name) = fn_env.get_local_name(idx) else {
+                continue;
+            };
+            let name = name.display(symbol_pool).to_string();
+            if !is_source_name(&name) {
+                continue;
+            }
+            let (scope, (line, column)) = match uses.get(&idx) {
+                Some(range) if idx >= param_count => (
+                    innermost_scope(&scopes, range).unwrap_or(function),
+                    line_column(range.start),
+                ),
+                _ => {
+                    let (_file, line, column, _start, _end) =
+                        loc_display(&fn_env.get_loc(), fn_env.module_env.env);
+                    (function, (line, column))
+                }
+            };
+            let ty = self.get_type(local.mty().clone(), &name);
+            let cstr = to_cstring!(name.as_str());
+            let (nm_ptr, nm_len) = (cstr.as_ptr(), cstr.as_bytes().len());
+            unsafe {
+                let variable = if idx < param_count {
+                    LLVMDIBuilderCreateParameterVariable(
+                        di_builder,
+                        function,
+                        nm_ptr,
+                        nm_len,
+                        idx as u32 + 1,
+                        file,
+                        line,
+                        ty,
+                        1, // AlwaysPreserve
+                        LLVMDIFlagZero,
+                    )
+                } else {
+                    LLVMDIBuilderCreateAutoVariable(
+                        di_builder,
+                        scope,
+                        nm_ptr,
+                        nm_len,
+                        file,
+                        line,
+                        ty,
+                        1, // AlwaysPreserve
+                        LLVMDIFlagZero,
+                        0,
+                    )
+                };
+                let ll_ctx = func_ctx.module_cx.llvm_cx.0;
+                let debug_location = LLVMDIBuilderCreateDebugLocation(
+                    ll_ctx,
+                    line,
+                    column,
+                    scope,
+                    std::ptr::null_mut(),
+                );
+                let expression = LLVMDIBuilderCreateExpression(di_builder, std::ptr::null_mut(), 0);
+                let alloca = local.llval().get0();
+                di_insert_declare_at_end(
+                    di_builder,
+                    alloca,
+                    variable,
+                    expression,
+                    debug_location,
+                    LLVMGetInstructionParent(alloca),
+                );
+            }
+            debug!(target: "dwarf", "local {idx} declared as {name}");
+        }
+    }
+
+    /// Move to the instruction at `offset`, so the instructions translated next are located
+    /// in the innermost block covering it.
+    pub fn enter_code_offset(&self, offset: usize) {
+        if let Some(core) = &self.0 {
+            let scope = innermost_scope(&core.scopes.borrow(), &(offset..offset + 1))
+                .unwrap_or(*core.current_function.borrow());
+            *core.current_scope.borrow_mut() = scope;
+        }
+    }
+
     pub fn finalize_function(
         &self,
         func_ctx: &FunctionContext<'_, '_>,
@@ -1427,6 +1564,96 @@ impl<'up> DIBuilder<'up> {
     }
 }
 
+/// Code ranges of the blocks of a function body, outermost first:
+/// - loops, from the label of the loop to the last jump back to it, and
+/// - the arms of a forward branch, from the label of the arm to the next arm or to the label
+///   both arms join at.
+///
+/// Ranges are offsets into `code`. Structured Move code yields properly nested ranges.
+pub fn block_scopes(code: &[Bytecode]) -> Vec<Range<usize>> {
+    let labels: BTreeMap<Label, usize> = code
+        .iter()
+        .enumerate()
+        .filter_map(|(offset, bc)| match bc {
+            Bytecode::Label(_, label) => Some((*label, offset)),
+            _ => None,
+        })
+        .collect();
+    let mut scopes = BTreeSet::new();
+    let mut loop_ends: BTreeMap<usize, usize> = BTreeMap::new();
+    for (offset, bc) in code.iter().enumerate() {
+        let targets = match bc {
+            Bytecode::Jump(_, label) => vec![labels[label]],
+            Bytecode::Branch(_, then_label, else_label, _) => {
+                let (then_start, else_start) = (labels[then_label], labels[else_label]);
+                if offset < then_start && then_start < else_start {
+                    scopes.insert((then_start, else_start));
+                    // the then arm ends jumping over the else arm to where both join
+                    if let Bytecode::Jump(_, join) = &code[else_start - 1] {
+                        if labels[join] > else_start {
+                            scopes.insert((else_start, labels[join]));
+                        }
+                    }
+                }
+                vec![then_start, else_start]
+            }
+            _ => vec![],
+        };
+        for start in targets.into_iter().filter(|start| *start <= offset) {
+            let end = loop_ends.entry(start).or_default();
+            *end = (*end).max(offset + 1);
+        }
+    }
+    scopes.extend(loop_ends);
+    let mut scopes: Vec<_> = scopes.into_iter().map(|(start, end)| start..end).collect();
+    scopes.sort_by_key(|scope| (scope.start, Reverse(scope.end)));
+    scopes
+}
+
+/// The block of the innermost of `scopes` (outermost first) containing `range`.
+fn innermost_scope(
+    scopes: &[(Range<usize>, LLVMMetadataRef)],
+    range: &Range<usize>,
+) -> Option<LLVMMetadataRef> {
+    scopes
+        .iter()
+        .rev()
+        .find(|(scope, _)| scope.start <= range.start && range.end <= scope.end)
+        .map(|(_, block)| *block)
+}
+
+/// The code range from the first to the last instruction using each local.
+fn local_uses(code: &[Bytecode]) -> BTreeMap<TempIndex, Range<usize>> {
+    let mut uses: BTreeMap<TempIndex, Range<usize>> = BTreeMap::new();
+    for (offset, bc) in code.iter().enumerate() {
+        let locals = match bc {
+            Bytecode::Assign(_, dst, src, _) => vec![*dst, *src],
+            Bytecode::Call(_, dsts, _, srcs, _) => dsts.iter().chain(srcs).copied().collect(),
+            Bytecode::Ret(_, srcs) => srcs.clone(),
+            Bytecode::Load(_, dst, _) => vec![*dst],
+            Bytecode::Branch(_, _, _, cond) => vec![*cond],
+            Bytecode::Abort(_, src) => vec![*src],
+            _ => vec![],
+        };
+        for local in locals {
+            uses.entry(local)
+                .and_modify(|range| range.end = offset + 1)
+                .or_insert(offset..offset + 1);
+        }
+    }
+    uses
+}
+
+/// Whether `name` is the name of a local in the source, rather than one the compiler made up
+/// for a temporary, like `$t3`.
+fn is_source_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 fn loc_display(loc: &move_model::model::Loc, env: &GlobalEnv) -> (String, u32, u32, u32, u32) {
     if let Some((fname, pos)) = env.get_file_and_location(loc) {
         (
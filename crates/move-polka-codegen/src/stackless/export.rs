@@ -0,0 +1,294 @@
+//! Which Move functions a blob exports.
+//!
+//! - `entry` functions are exported and dispatched by `call_selector`. With `--strip-exports`
+//!   they are only dispatched, so the blob exports nothing but the runtime's `call` and
+//!   `deploy`, and the polka linker drops the code only reachable through the stripped exports.
+//! - `public` functions of the package's own modules are exported with `--export-public`,
+//!   without a selector, and any function named with `--extra-export`, also with
+//!   `--strip-exports`. Generic functions are never exported, since they have no single
+//!   instantiation to call.
+//! - `friend` and private functions are never exported. Every module is a separate object, so
+//!   friend functions called by their friends keep external linkage, as do private functions
+//!   called by generic functions of their module, which are expanded into the modules calling
+//!   them. All others get private linkage, see [`cross_module_callees`].
+//!
+//! [`cross_module_callees`]: crate::stackless::cross_module_callees
+//! - The `--unit-test-function` gets external linkage so test harnesses can link against it.
+//! - With `--split-modules`, the blob of a module only exports and dispatches the entry
+//!   functions of that module, see [`CodegenOptions::split_module`].
+//!
+//! Exports use the plain Move function name, so exported names must be unique in the blob;
+//! [`check_export_collisions`] rejects a build exporting two functions under one name.
+//!
+//! [`check_export_collisions`]: crate::stackless::check_export_collisions
+//!
+//! Next to its PolkaVM export metadata, every export gets a compact type signature in the
+//! [`EXPORT_SIGNATURES_SECTION`], one `name=signature` line each, see [`export_signature`].
+//! The PolkaVM linker drops the section, so the driver reads it from the linked ELF object.
+//!
+//! The [`ExportRegistry`] of the [`GlobalContext`] collects the exported and dispatched
+//! functions of all modules as they are declared, and emits the export metadata of each once.
+//!
+//! [`GlobalContext`]: crate::stackless::GlobalContext
+
+use crate::{
+    options::CodegenOptions,
+    stackless::{extensions::*, llvm},
+};
+use move_binary_format::file_format::Visibility;
+use move_model::{
+    model as mm,
+    ty::{PrimitiveType, Type},
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+};
+
+/// ELF section holding the `name=signature` line of every export.
+pub const EXPORT_SIGNATURES_SECTION: &str = ".move_polka_signatures";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportPolicy {
+    /// Exported and dispatched by `call_selector`.
+    Entry,
+    /// Dispatched by `call_selector`, but not exported, with `--strip-exports`.
+    Dispatched,
+    /// Exported without a selector, with `--export-public` or `--extra-export`.
+    Public,
+    /// Not exported, but linked externally for the test harness.
+    UnitTest,
+    /// Not exported.
+    Internal,
+}
+
+impl ExportPolicy {
+    pub fn of(fn_env: &mm::FunctionEnv, options: &CodegenOptions) -> Self {
+        let unit_test = options.unit_test_function.as_deref();
+        let extra_export = options.extra_exports.contains(&fn_env.get_full_name_str());
+        // the blob of a split module only dispatches the module's own entry functions
+        let other_module =
+            matches!(options.split_module, Some(id) if id != fn_env.module_env.get_id());
+        if fn_env.is_entry() && !other_module {
+            if options.strip_exports && !extra_export {
+                ExportPolicy::Dispatched
+            } else {
+                ExportPolicy::Entry
+            }
+        } else if (extra_export
+            || options.export_public
+                && fn_env.visibility() == Visibility::Public
+                && fn_env.module_env.is_target())
+            && fn_env.get_type_parameter_count() == 0
+            && !fn_env.is_native()
+        {
+            ExportPolicy::Public
+        } else if unit_test == Some(fn_env.get_full_name_str().replace("::", "__").as_str()) {
+            ExportPolicy::UnitTest
+        } else {
+            ExportPolicy::Internal
+        }
+    }
+
+    /// Whether `call_selector` dispatches to the function.
+    pub fn is_dispatched(&self) -> bool {
+        matches!(self, ExportPolicy::Entry | ExportPolicy::Dispatched)
+    }
+
+    /// Whether the function gets a PolkaVM export.
+    pub fn is_exported(&self) -> bool {
+        matches!(self, ExportPolicy::Entry | ExportPolicy::Public)
+    }
+
+    /// Whether the function is called from outside the blob, either as an export or
+    /// by the test harness.
+    pub fn is_external(&self) -> bool {
+        *self != ExportPolicy::Internal
+    }
+
+    /// Linkage of the function, given the linkage it gets from its Move visibility.
+    pub fn linkage(&self, visibility_linkage: llvm::LLVMLinkage) -> llvm::LLVMLinkage {
+        if self.is_external() {
+            llvm::LLVMLinkage::LLVMExternalLinkage
+        } else {
+            visibility_linkage
+        }
+    }
+}
+
+/// A function the blob exports or `call_selector` dispatches to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredExport {
+    /// `module::function`.
+    pub name: String,
+    /// The LLVM name of the module declaring the function.
+    pub module: String,
+    /// The LLVM symbol of the function.
+    pub symbol: String,
+    /// The type signature of the PolkaVM export, `None` if the function is only dispatched.
+    pub signature: Option<String>,
+    /// The selector as loaded from call data, once `call_selector` is generated.
+    pub selector: Option<u32>,
+}
+
+/// The exports of a program, shared by the translation of all its modules.
+///
+/// Every module object declares the functions it calls, so an export may be declared in
+/// several modules; its PolkaVM metadata is emitted into the first one only.
+#[derive(Debug, Default)]
+pub struct ExportRegistry {
+    exports: RefCell<BTreeMap<String, RegisteredExport>>,
+    call_selector: Cell<bool>,
+}
+
+impl ExportRegistry {
+    /// Register `function`, declared in `llmod` for `fn_env`, if `policy` exports or dispatches
+    /// it, and emit its export metadata into `llmod` if it is exported and not registered yet.
+    pub fn declare(
+        &self,
+        llmod: &llvm::Module,
+        function: llvm::Function,
+        fn_env: &mm::FunctionEnv,
+        policy: ExportPolicy,
+    ) {
+        if !policy.is_exported() && !policy.is_dispatched() {
+            return;
+        }
+        let name = fn_env.get_full_name_str();
+        if self.contains(&name) {
+            return;
+        }
+        let module = fn_env.module_env.llvm_module_name();
+        let symbol = function.get_name();
+        let signature = policy
+            .is_exported()
+            .then(|| export_signature(fn_env, &parameter_names(fn_env)));
+        if let Some(signature) = &signature {
+            llmod.add_polkavm_export(function, &module, &fn_env.llvm_symbol_name(&[]), signature);
+        }
+        self.register(RegisteredExport {
+            name,
+            module,
+            symbol,
+            signature,
+            selector: None,
+        });
+    }
+
+    /// Register `export` unless a function of the same name is registered already, and return
+    /// whether it was registered.
+    pub fn register(&self, export: RegisteredExport) -> bool {
+        let mut exports = self.exports.borrow_mut();
+        if exports.contains_key(&export.name) {
+            return false;
+        }
+        exports.insert(export.name.clone(), export);
+        true
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.exports.borrow().contains_key(name)
+    }
+
+    /// Note that `call_selector` dispatches selector `selector` to the function `name`.
+    pub fn set_selector(&self, name: &str, selector: u32) {
+        if let Some(export) = self.exports.borrow_mut().get_mut(name) {
+            export.selector = Some(selector);
+        }
+    }
+
+    /// Claim the generation of `call_selector`: true the first time only, since one module
+    /// dispatches to the entry functions of all.
+    pub fn claim_call_selector(&self) -> bool {
+        !self.call_selector.replace(true)
+    }
+
+    /// The registered functions, by name.
+    pub fn exports(&self) -> Vec<RegisteredExport> {
+        self.exports.borrow().values().cloned().collect()
+    }
+
+    /// The registered functions by the module declaring them.
+    pub fn by_module(&self) -> BTreeMap<String, Vec<RegisteredExport>> {
+        let mut modules: BTreeMap<String, Vec<RegisteredExport>> = BTreeMap::new();
+        for export in self.exports.borrow().values() {
+            modules
+                .entry(export.module.clone())
+                .or_default()
+                .push(export.clone());
+        }
+        modules
+    }
+
+    /// The `module::function` names `call_selector` dispatches to, by selector. The metadata
+    /// and the ABI of the blob take their selectors from this table.
+    pub fn selector_table(&self) -> BTreeMap<u32, String> {
+        self.exports
+            .borrow()
+            .values()
+            .filter_map(|export| Some((export.selector?, export.name.clone())))
+            .collect()
+    }
+}
+
+/// The compact type signature of an exported function: the parameter types, `:` and the
+/// result types, one code per type:
+///
+/// | code | type   | code | type      | code | type               |
+/// |------|--------|------|-----------|------|--------------------|
+/// | `b`  | `bool` | `8`  | `u64`     | `s`  | `signer`           |
+/// | `1`  | `u8`   | `x`  | `u128`    | `v`  | `vector<` next `>` |
+/// | `2`  | `u16`  | `y`  | `u256`    | `r`  | `&` next           |
+/// | `4`  | `u32`  | `a`  | `address` | `m`  | `&mut` next        |
+///
+/// Structs are `t`. The `param_names`, if any, follow after a `;`, separated by commas. For
+/// example `fun sum(v: &vector<u64>): u64` is `rv8:8;v`.
+pub fn export_signature(fn_env: &mm::FunctionEnv, param_names: &[String]) -> String {
+    let mut signature = String::new();
+    for ty in fn_env.get_parameter_types() {
+        encode_type(&ty, &mut signature);
+    }
+    signature.push(':');
+    match fn_env.get_result_type() {
+        Type::Tuple(tys) => tys.iter().for_each(|ty| encode_type(ty, &mut signature)),
+        ty => encode_type(&ty, &mut signature),
+    }
+    if !param_names.is_empty() {
+        signature.push(';');
+        signature.push_str(&param_names.join(","));
+    }
+    signature
+}
+
+/// The names of the parameters of `fn_env`, as in the source.
+pub fn parameter_names(fn_env: &mm::FunctionEnv) -> Vec<String> {
+    let symbol_pool = fn_env.module_env.symbol_pool();
+    fn_env
+        .get_parameters()
+        .iter()
+        .map(|param| param.0.display(symbol_pool).to_string())
+        .collect()
+}
+
+fn encode_type(ty: &Type, out: &mut String) {
+    match ty {
+        Type::Primitive(PrimitiveType::Bool) => out.push('b'),
+        Type::Primitive(PrimitiveType::U8) => out.push('1'),
+        Type::Primitive(PrimitiveType::U16) => out.push('2'),
+        Type::Primitive(PrimitiveType::U32) => out.push('4'),
+        Type::Primitive(PrimitiveType::U64) => out.push('8'),
+        Type::Primitive(PrimitiveType::U128) => out.push('x'),
+        Type::Primitive(PrimitiveType::U256) => out.push('y'),
+        Type::Primitive(PrimitiveType::Address) => out.push('a'),
+        Type::Primitive(PrimitiveType::Signer) => out.push('s'),
+        Type::Vector(ty) => {
+            out.push('v');
+            encode_type(ty, out);
+        }
+        Type::Reference(_, referent) => {
+            out.push(if ty.is_mutable_reference() { 'm' } else { 'r' });
+            encode_type(referent, out);
+        }
+        _ => out.push('t'),
+    }
+}
@@ -120,6 +120,16 @@ pub impl FunctionEnvExt for mm::FunctionEnv<'_> {
     /// Native functions follow their own naming convention
     fn llvm_native_fn_symbol_name(&self) -> String {
         let name = self.get_full_name_str();
+        // Aptos' `0x1::from_bcs::from_bytes` decodes BCS and is implemented next to
+        // `bcs::to_bytes`. The hashes of Aptos' `aptos_hash` are those of `revive::hash`.
+        let name = match name.as_str() {
+            "from_bcs::from_bytes" if self.module_env.get_full_name_str() == "0x1::from_bcs" => {
+                "bcs::from_bytes".to_string()
+            }
+            "aptos_hash::keccak256" => "hash::keccak256".to_string(),
+            "aptos_hash::blake2b_256" => "hash::blake2b256".to_string(),
+            _ => name,
+        };
         let name = name.replace("::", "_");
         format!("move_native_{name}")
     }
@@ -13,21 +13,21 @@
 //! - Hides weirdly mutable array pointers.
 //! - Provides high-level instruction builders compatible with the stackless bytecode model.
 
-use libc::abort;
 use llvm_sys::{
     core::*, prelude::*, target::*, target_machine::*, LLVMIntPredicate::LLVMIntEQ, LLVMOpcode,
     LLVMUnnamedAddr,
 };
-use log::{debug, warn};
 use move_core_types::u256;
 use num_traits::{PrimInt, ToPrimitive};
+use tracing::{debug, warn};
 
-use crate::cstr::SafeCStr;
+use crate::{cstr::SafeCStr, stackless::EXPORT_SIGNATURES_SECTION};
 
 use std::{
     backtrace::Backtrace,
     cell::RefCell,
     ffi::{CStr, CString},
+    fmt,
     hash::DefaultHasher,
     ptr,
     rc::Rc,
@@ -49,14 +49,69 @@ use crate::stackless::{
     GlobalContext, ModuleContext,
 };
 
-pub fn initialize_riscv() {
+/// The LLVM major version the crate is built against, see the `llvm*` features.
+pub const LLVM_MAJOR_VERSION: u32 = if cfg!(feature = "llvm18") {
+    18
+} else if cfg!(feature = "llvm19") {
+    19
+} else {
+    20
+};
+
+/// The version of the LLVM library loaded at runtime, as `(major, minor, patch)`.
+pub fn llvm_version() -> (u32, u32, u32) {
+    let (mut major, mut minor, mut patch) = (0, 0, 0);
     unsafe {
+        LLVMGetVersion(&mut major, &mut minor, &mut patch);
+    }
+    (major, minor, patch)
+}
+
+/// Fail if the LLVM library loaded at runtime is not the version the crate was built
+/// against, e.g. a shared libLLVM replaced by a distribution upgrade, rather than crash
+/// somewhere in code generation.
+pub fn check_llvm_version() -> anyhow::Result<()> {
+    let (major, minor, patch) = llvm_version();
+    if major != LLVM_MAJOR_VERSION {
+        anyhow::bail!(
+            "move-to-polka was built against LLVM {LLVM_MAJOR_VERSION}, but LLVM \
+             {major}.{minor}.{patch} is loaded; rebuild it with the llvm{major} feature or \
+             install LLVM {LLVM_MAJOR_VERSION}"
+        );
+    }
+    Ok(())
+}
+
+/// Declare the variable `var_info` stored at `storage` at the end of `block`. LLVM 19 and
+/// later insert a debug record, LLVM 18 a call to `llvm.dbg.declare`.
+pub(crate) unsafe fn di_insert_declare_at_end(
+    builder: LLVMDIBuilderRef,
+    storage: LLVMValueRef,
+    var_info: LLVMMetadataRef,
+    expr: LLVMMetadataRef,
+    debug_loc: LLVMMetadataRef,
+    block: LLVMBasicBlockRef,
+) {
+    #[cfg(feature = "llvm18")]
+    llvm_sys::debuginfo::LLVMDIBuilderInsertDeclareAtEnd(
+        builder, storage, var_info, expr, debug_loc, block,
+    );
+    #[cfg(not(feature = "llvm18"))]
+    llvm_sys::debuginfo::LLVMDIBuilderInsertDeclareRecordAtEnd(
+        builder, storage, var_info, expr, debug_loc, block,
+    );
+}
+
+/// Register the RISC-V target with LLVM. Only the first call, from any thread, does so.
+pub fn initialize_riscv() {
+    static INITIALIZED: std::sync::Once = std::sync::Once::new();
+    INITIALIZED.call_once(|| unsafe {
         LLVMInitializeRISCVTargetInfo();
         LLVMInitializeRISCVTarget();
         LLVMInitializeRISCVTargetMC();
         LLVMInitializeRISCVAsmPrinter();
         LLVMInitializeRISCVAsmParser();
-    }
+    });
 }
 
 // Return a unique id given the name of an enum attribute, or None if no attribute by
@@ -281,6 +336,34 @@ impl Context {
 #[derive(Copy, Clone)]
 pub struct TargetData(LLVMTargetDataRef);
 
+/// LLVM rejected the IR generated for a module. Returned, wrapped in an `anyhow::Error`, by
+/// [`Module::verify`] and [`Function::verify`], so drivers can tell a code generator bug apart
+/// from other failures and report it along with their own diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationError {
+    /// The LLVM module, see [`Module::get_module_id`].
+    pub module: String,
+    /// What the verifier reported.
+    pub message: String,
+    /// Name and IR of every function the verifier rejects.
+    pub functions: Vec<(String, String)>,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} module verification failed", self.module)?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        for (name, ir) in &self.functions {
+            write!(f, "\nin function {name}:\n{}", ir.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
 #[derive(Debug)]
 pub struct Module(pub LLVMModuleRef, pub Rc<RefCell<String>>, pub String); // (module, asm, name)
 
@@ -328,6 +411,38 @@ impl Module {
         }
     }
 
+    /// Reserve `heap_size` bytes of RW data as the guest heap (`move_rt_heap`, with its size in
+    /// `move_rt_heap_size`) and request a minimum stack size from the polka linker.
+    /// Emit this into exactly one module of the program.
+    pub fn add_memory_config(&self, heap_size: u32, min_stack_size: Option<u32>) {
+        let mut asm = self.1.borrow_mut();
+        asm.push_str(&format!(
+            ".pushsection .bss.move_rt_heap,\"aw\",@nobits\n.globl move_rt_heap\n.p2align 4\nmove_rt_heap:\n.zero {heap_size}\n.popsection\n"
+        ));
+        asm.push_str(&format!(
+            ".pushsection .rodata.move_rt_heap_size,\"a\",@progbits\n.globl move_rt_heap_size\n.p2align 2\nmove_rt_heap_size:\n.4byte {heap_size}\n.popsection\n"
+        ));
+        if let Some(size) = min_stack_size {
+            // same section `polkavm_derive::min_stack_size!` emits
+            asm.push_str(&format!(
+                ".pushsection .polkavm_min_stack_size,\"R\",@note\n.4byte {size}\n.popsection\n"
+            ));
+        }
+    }
+
+    /// Define the zeroed `move_rt_coverage_counters` array of `count` `u32` counters, with
+    /// its length in `move_rt_coverage_len`. Emit this into exactly one module of the program.
+    pub fn add_coverage_counters(&self, count: u32) {
+        let mut asm = self.1.borrow_mut();
+        let size = count * 4;
+        asm.push_str(&format!(
+            ".pushsection .bss.move_rt_coverage_counters,\"aw\",@nobits\n.globl move_rt_coverage_counters\n.p2align 2\nmove_rt_coverage_counters:\n.zero {size}\n.popsection\n"
+        ));
+        asm.push_str(&format!(
+            ".pushsection .rodata.move_rt_coverage_len,\"a\",@progbits\n.globl move_rt_coverage_len\n.p2align 2\nmove_rt_coverage_len:\n.4byte {count}\n.popsection\n"
+        ));
+    }
+
     pub fn get_module_id(&self) -> String {
         let mut mod_len: ::libc::size_t = 0;
         let mod_ptr = unsafe { LLVMGetModuleIdentifier(self.0, &mut mod_len) };
@@ -350,15 +465,8 @@ impl Module {
         unsafe { LLVMSetSourceFileName(self.0, name.as_ptr() as *const libc::c_char, name.len()) }
     }
 
-    pub fn add_function(
-        &self,
-        exports: &mut Vec<String>,
-        module: &str,
-        name: &str,
-        ty: FunctionType,
-        polka_export: bool,
-    ) -> Function {
-        log::debug!("Adding function {module}:{name}");
+    pub fn add_function(&self, module: &str, name: &str, ty: FunctionType) -> Function {
+        tracing::debug!("Adding function {module}:{name}");
         unsafe {
             let mut symbol = name.to_owned();
             if module != "native" {
@@ -373,25 +481,33 @@ impl Module {
                 );
                 symbol = mangled;
             }
-            let function = LLVMAddFunction(self.0, symbol.cstr(), ty.0);
-            // TODO: it doesnt feel like the right place for polka section generation just on the fly
-            // on any function we need to declare. Its looks more like additional pass when finalizing module
-            // but we leave this for now to move forward
-            if polka_export && !exports.contains(&symbol) {
-                let context = LLVMGetModuleContext(self.0);
-                let num_args = LLVMCountParams(function) as u8;
-                add_polkavm_metadata(
-                    self.0,
-                    context,
-                    module,
-                    name,
-                    symbol.as_str(),
-                    num_args,
-                    self.1.clone(),
-                );
-                exports.push(symbol.clone());
-            }
-            Function(function)
+            Function(LLVMAddFunction(self.0, symbol.cstr(), ty.0))
+        }
+    }
+
+    /// Export `function`, the Move function `module::name`, from the blob: emit its PolkaVM
+    /// export metadata and its type `signature`, see `export_signature`. The sections are
+    /// generated as inline assembly when the module is finalized.
+    pub fn add_polkavm_export(
+        &self,
+        function: Function,
+        module: &str,
+        name: &str,
+        signature: &str,
+    ) {
+        unsafe {
+            let context = LLVMGetModuleContext(self.0);
+            let num_args = LLVMCountParams(function.0) as u8;
+            add_polkavm_metadata(
+                self.0,
+                context,
+                module,
+                name,
+                &function.get_name(),
+                num_args,
+                signature,
+                self.1.clone(),
+            );
         }
     }
 
@@ -460,30 +576,93 @@ impl Module {
             ];
             let memcmp_rty = Type(LLVMInt32TypeInContext(cx));
             let memcmp_fty = FunctionType::new(memcmp_rty, &memcmp_arg_tys);
-            self.add_function(&mut vec![], "native", "memcmp", memcmp_fty, false);
+            self.add_function("native", "memcmp", memcmp_fty);
         }
     }
 
-    pub fn verify(&self) {
+    /// Link `other` into this module. Both must belong to the same context; `other` is consumed.
+    pub fn link_in(&self, other: Module) -> anyhow::Result<()> {
+        let name = other.get_module_id();
+        // LLVMLinkModules2 takes ownership of the source module and destroys it, even on
+        // failure, so the wrapper must not dispose of it again.
+        let other = std::mem::ManuallyDrop::new(other);
+        let failed = unsafe { llvm_sys::linker::LLVMLinkModules2(self.0, other.0) };
+        if failed != 0 {
+            anyhow::bail!("failed to link module {name} into {}", self.get_module_id());
+        }
+        Ok(())
+    }
+
+    /// Run a new pass manager pipeline, e.g. `function(mem2reg,simplifycfg)`, over the module.
+    pub fn run_passes(&self, machine: &TargetMachine, passes: &str) -> anyhow::Result<()> {
+        use llvm_sys::{error::*, transforms::pass_builder::*};
+        unsafe {
+            let options = LLVMCreatePassBuilderOptions();
+            let error = LLVMRunPasses(self.0, passes.cstr(), machine.0, options);
+            LLVMDisposePassBuilderOptions(options);
+            if error.is_null() {
+                Ok(())
+            } else {
+                let message = LLVMGetErrorMessage(error);
+                let rust_error = CStr::from_ptr(message).to_string_lossy().to_string();
+                LLVMDisposeErrorMessage(message);
+                anyhow::bail!("{passes}: {rust_error}");
+            }
+        }
+    }
+
+    /// Run the LLVM verifier over the module. On failure the error is a
+    /// [`VerificationError`], carrying the IR of the functions the verifier rejects.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let name = self.get_module_id();
+        let addr = &self.0;
+        debug!(target: "module", "{name} module verification address {addr:#?}");
+        let Some(message) = self.verifier_message() else {
+            return Ok(());
+        };
+        let module_info = self.print_to_str();
+        debug!(target: "module", "Module content:\n{module_info}\n");
+        let functions = self
+            .functions()
+            .filter(|function| !function.is_valid())
+            .map(|function| (function.get_name(), function.print_to_str().to_string()))
+            .collect();
+        Err(VerificationError {
+            module: name,
+            message,
+            functions,
+        }
+        .into())
+    }
+
+    /// The message of the LLVM verifier, if the module is invalid.
+    fn verifier_message(&self) -> Option<String> {
         use llvm_sys::analysis::*;
         unsafe {
-            let name = &self.get_module_id();
-            let addr = &self.0;
-            debug!(target: "module", "{name} module verification address {addr:#?}");
-            if LLVMVerifyModule(
+            let mut message = ptr::null_mut();
+            let failed = LLVMVerifyModule(
                 self.0,
-                LLVMVerifierFailureAction::LLVMPrintMessageAction,
-                ptr::null_mut(),
-            ) == 1
-            {
-                println!("\n{} module verification failed\n", &self.get_module_id());
-                let module_info = &self.print_to_str();
-                debug!(target: "module", "Module content:\n{module_info}\n");
-                abort();
-            }
+                LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                &mut message,
+            ) == 1;
+            let text = (!message.is_null()).then(|| {
+                let text = CStr::from_ptr(message).to_string_lossy().trim().to_string();
+                LLVMDisposeMessage(message);
+                text
+            });
+            failed.then(|| text.unwrap_or_default())
         }
     }
 
+    /// The functions of the module, declarations included.
+    pub fn functions(&self) -> impl Iterator<Item = Function> {
+        let first = unsafe { LLVMGetFirstFunction(self.0) };
+        std::iter::successors((!first.is_null()).then_some(Function(first)), |function| {
+            let next = unsafe { LLVMGetNextFunction(function.0) };
+            (!next.is_null()).then_some(Function(next))
+        })
+    }
+
     pub fn set_data_layout(&self, machine: &TargetMachine) {
         unsafe {
             let target_data = LLVMCreateTargetDataLayout(machine.0);
@@ -1406,18 +1585,30 @@ impl Function {
         unsafe { Type(LLVMGetReturnType(LLVMGlobalGetValueType(self.0))) }
     }
 
-    pub fn verify(&self, module_cx: &ModuleContext<'_, '_>) {
-        use llvm_sys::analysis::*;
+    /// Run the LLVM verifier over the function, see [`Module::verify`].
+    pub fn verify(&self, module_cx: &ModuleContext<'_, '_>) -> anyhow::Result<()> {
         let module_info = module_cx.llvm_module.print_to_str();
         debug!(target: "verify function", "Module content:");
         debug!(target: "verify function", "------------------------------");
         debug!(target: "verify function", "{module_info}");
         debug!(target: "verify function", "------------------------------");
+        if self.is_valid() {
+            return Ok(());
+        }
+        // The function verifier reports no message, the module verifier names the problem.
+        let message = module_cx.llvm_module.verifier_message().unwrap_or_default();
+        Err(VerificationError {
+            module: module_cx.llvm_module.get_module_id(),
+            message,
+            functions: vec![(self.get_name(), self.print_to_str().to_string())],
+        }
+        .into())
+    }
+
+    fn is_valid(&self) -> bool {
+        use llvm_sys::analysis::*;
         unsafe {
-            if LLVMVerifyFunction(self.0, LLVMVerifierFailureAction::LLVMPrintMessageAction) == 1 {
-                println!("{} function verifiction failed", &self.get_name());
-                abort();
-            }
+            LLVMVerifyFunction(self.0, LLVMVerifierFailureAction::LLVMReturnStatusAction) == 0
         }
     }
 }
@@ -1652,6 +1843,7 @@ pub struct Target(LLVMTargetRef);
 
 impl Target {
     pub fn from_triple(triple: &str) -> anyhow::Result<Target> {
+        check_llvm_version()?;
         unsafe {
             let target: &mut LLVMTargetRef = &mut ptr::null_mut();
             let error: &mut *mut libc::c_char = &mut ptr::null_mut();
@@ -1712,6 +1904,10 @@ impl Target {
 
 pub struct TargetMachine(LLVMTargetMachineRef);
 
+// LLVM target machines can be used from any thread, one at a time. Not `Sync`: a machine is
+// shared only within one compilation, and pooled for the next one once it is done.
+unsafe impl Send for TargetMachine {}
+
 impl Drop for TargetMachine {
     fn drop(&mut self) {
         unsafe {
@@ -1759,6 +1955,7 @@ unsafe fn add_polkavm_metadata(
     fn_name: &str,
     mangled_fn_name: &str,
     num_args: u8,
+    signature: &str,
     asm: Rc<RefCell<String>>,
 ) {
     debug!("Adding PolkaVM metadata for function: {fn_name} in module: {module_name}");
@@ -1847,6 +2044,10 @@ unsafe fn add_polkavm_metadata(
     )
         .as_str(),
     );
+    // The type signature, in a non-allocated section the linkers keep but don't load.
+    asm.borrow_mut().push_str(&format!(
+        ".pushsection {EXPORT_SIGNATURES_SECTION},\"\",@progbits\n.ascii \"{fn_name}={signature}\\n\"\n.popsection\n"
+    ));
 }
 
 fn hash_string(s: &str) -> String {
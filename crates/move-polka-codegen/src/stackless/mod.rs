@@ -2,13 +2,21 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod attributes;
+mod check;
 mod dwarf;
+mod export;
 pub mod extensions;
 mod llvm;
 mod module_context;
+mod reachability;
 mod rttydesc;
 mod translate;
 
+pub use attributes::*;
+pub use check::*;
+pub use export::*;
 pub use llvm::*;
 pub use module_context::*;
+pub use reachability::*;
 pub use translate::*;
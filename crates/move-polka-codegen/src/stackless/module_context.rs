@@ -3,9 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    options::Options,
+    instrument::Instrumentation,
+    options::CodegenOptions,
     stackless::{
+        attributes::{find_attribute, inline_hint, is_payable},
         dwarf::{DIBuilder, UnresolvedPrintLogLevel},
+        export::{ExportPolicy, ExportRegistry},
         extensions::*,
         llvm::{self, TargetMachine},
         rttydesc::RttyContext,
@@ -13,10 +16,11 @@ use crate::{
     },
 };
 use codespan::Location;
-use log::debug;
-use move_binary_format::file_format::SignatureToken;
+use itertools::Itertools;
+use move_binary_format::file_format::{SignatureToken, Visibility};
 use move_core_types::u256::U256;
 use move_model::{
+    ast as mast,
     model::{self as mm},
     ty as mty,
 };
@@ -24,9 +28,22 @@ use move_stackless_bytecode::{
     function_target::FunctionData, stackless_bytecode as sbc,
     stackless_bytecode_generator::StacklessBytecodeGenerator,
 };
-use polkavm_move_native::types::{MOVE_TYPE_DESC_SIZE, MOVE_UNTYPED_VEC_DESC_SIZE};
-use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use num_traits::ToPrimitive;
+use polkavm_move_native::{
+    types::{MOVE_TYPE_DESC_SIZE, MOVE_UNTYPED_VEC_DESC_SIZE},
+    MALFORMED_INPUT_CODE,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    rc::Rc,
+    time::{Duration, Instant},
+};
 use tiny_keccak::{Hasher, Keccak};
+use tracing::{debug, debug_span};
+
+/// Symbol of the runtime's implementation of `debug::print`.
+pub const DEBUG_PRINT_SYMBOL: &str = "move_native_debug_print";
 
 pub struct ModuleContext<'mm: 'up, 'up> {
     pub env: mm::ModuleEnv<'mm>,
@@ -40,16 +57,34 @@ pub struct ModuleContext<'mm: 'up, 'up> {
     /// This includes local functions and dependencies.
     pub fn_decls: BTreeMap<String, llvm::Function>,
     pub fn_is_entry: BTreeMap<String, bool>,
+    /// Entry functions by name, used to build the call selector.
+    pub fn_entry_ids: BTreeMap<String, mm::QualifiedId<mm::FunId>>,
     pub expanded_functions: Vec<mm::QualifiedInstId<mm::FunId>>,
+    /// Stackless bytecode of every function walked while declaring, reused for translation.
+    ///
+    /// The bytecode of a generic function doesn't depend on its instantiation, so all
+    /// expansions of a function share one entry.
+    pub function_data: BTreeMap<mm::QualifiedId<mm::FunId>, Rc<FunctionData>>,
+    /// Time spent generating stackless bytecode, for `--time-passes`.
+    pub stackless_time: Duration,
     pub target: TargetPlatform,
     pub target_machine: &'up TargetMachine,
-    pub options: &'up Options,
+    pub options: &'up CodegenOptions,
     pub rtty_cx: RttyContext<'mm, 'up>,
     pub source: &'up str,
+    /// Functions reachable from the program roots, when unreachable functions are pruned.
+    pub reachable_functions: Option<&'up BTreeSet<mm::QualifiedId<mm::FunId>>>,
+    /// Functions the objects of other modules call, see [`super::cross_module_callees`].
+    pub cross_module_callees: &'up BTreeSet<mm::QualifiedId<mm::FunId>>,
+    /// The exports of all modules, see [`ExportRegistry`].
+    pub exports: &'up ExportRegistry,
+    /// The read-only globals of the address and vector constants loaded so far, by type and
+    /// value, so each is emitted once.
+    pub constant_globals: RefCell<BTreeMap<String, llvm::Global>>,
 }
 
 impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
-    pub fn translate(&mut self, exports: &mut Vec<String>) {
+    pub fn translate(&mut self, instrumentation: &mut Instrumentation) -> anyhow::Result<()> {
         let filename = self.env.get_source_path().to_str().expect("utf-8");
         self.llvm_module.set_source_file_name(filename);
         self.llvm_module.set_target(self.target.triple());
@@ -65,32 +100,43 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
         // Declaring functions will populate list `expanded_functions` containing all
         // concrete Move functions and expanded concrete instances of generic Move functions.
-        self.declare_functions(exports);
+        self.declare_functions();
+        // Emit function bodies, and the type descriptors they create, in a canonical order.
+        let g_env = self.env.env;
+        self.expanded_functions.sort_by_cached_key(|fn_qiid| {
+            let fn_env = g_env.get_function(fn_qiid.to_qualified_id());
+            (
+                fn_env.get_full_name_str(),
+                fn_env.llvm_symbol_name_full(&fn_qiid.inst),
+            )
+        });
 
         let mut has_entry = false;
 
         for fn_qiid in &self.expanded_functions {
             let fn_env = self.env.env.get_function(fn_qiid.to_qualified_id());
-            if fn_env.is_entry() {
+            if ExportPolicy::of(&fn_env, self.options).is_dispatched() {
                 has_entry = true;
             }
             assert!(!fn_env.is_native());
+            let _span =
+                debug_span!("translate_function", function = %fn_env.get_full_name_str()).entered();
             self.rtty_cx.reset_func(fn_qiid);
             let fn_cx = self.create_fn_context(fn_env, self, &fn_qiid.inst);
-            fn_cx.translate();
+            fn_cx.translate(instrumentation)?;
         }
 
         if has_entry {
-            // only generate the call selector if there is an entry function
-            // Assumption: no other module contains an entry function
-            self.generate_call_selector(exports);
+            // The first module with an entry function generates the call selector, which
+            // dispatches to the entry functions of all modules.
+            self.generate_call_selector()?;
         }
 
         self.llvm_di_builder
             .print_log_unresoled_types(UnresolvedPrintLogLevel::Warning);
         self.llvm_di_builder.finalize();
         self.llvm_module.finalize(); // this generates the inline ASM for the polkavm sections
-        self.llvm_module.verify();
+        self.llvm_module.verify()
     }
 
     /// Generate LLVM IR struct declarations for all Move structures.
@@ -300,8 +346,27 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
     // e.g. Struct_A<Vector<Struct_B<T>>>, where T is substituted by a
     // concrete type, won't be declared correctly.
     fn translate_struct(&self, s_env: &mm::StructEnv<'mm>, tyvec: &[mty::Type]) {
+        self.translate_struct_within(s_env, tyvec, &mut vec![]);
+    }
+
+    /// Translate a struct while the structs in `enclosing` are being translated, which
+    /// contain it. `check_struct_cycles` reports recursive struct types before code
+    /// generation, this stops the recursion should one get here anyway.
+    fn translate_struct_within(
+        &self,
+        s_env: &mm::StructEnv<'mm>,
+        tyvec: &[mty::Type],
+        enclosing: &mut Vec<String>,
+    ) {
         let ll_name = s_env.ll_struct_name_from_raw_name(tyvec);
         debug!(target: "structs", "translating struct {} as {}", s_env.struct_raw_type_name(tyvec), ll_name);
+        if let Some(start) = enclosing.iter().position(|name| *name == ll_name) {
+            panic!(
+                "recursive struct type: {} -> {ll_name}",
+                enclosing[start..].join(" -> ")
+            );
+        }
+        enclosing.push(ll_name.clone());
         // Visit each field in this struct, collecting field types.
         let mut ll_field_tys = Vec::with_capacity(s_env.get_field_count() + 1);
         for fld_env in s_env.get_fields() {
@@ -311,13 +376,13 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                 if let mty::Type::Struct(m, s, tys) = new_sty {
                     let g_env = &self.env.env;
                     let s_env = g_env.get_module(*m).into_struct(*s);
-                    self.translate_struct(&s_env, tys);
+                    self.translate_struct_within(&s_env, tys, enclosing);
                 }
             } else if let mty::Type::TypeParameter(x) = &fld_env.get_type() {
                 if let mty::Type::Struct(m, s, tys) = &tyvec[*x as usize] {
                     let g_env = &self.env.env;
                     let s_env = g_env.get_module(*m).into_struct(*s);
-                    self.translate_struct(&s_env, tys);
+                    self.translate_struct_within(&s_env, tys, enclosing);
                 }
             }
             let ll_fld_type = self.to_llvm_type(&fld_env.get_type(), tyvec).unwrap();
@@ -329,6 +394,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
             ll_field_tys.push(ll_fld_type);
         }
         debug!(target: "structs", "Finished translating fields for {ll_name}");
+        enclosing.pop();
         if self.llvm_cx.named_struct_type(&ll_name).is_none() {
             debug!(target: "structs", "Create opaque struct {}", &ll_name);
             self.llvm_cx.create_opaque_named_struct(&ll_name);
@@ -435,7 +501,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
     /// Create LLVM function decls for all local functions and
     /// all extern functions that might be called.
-    fn declare_functions(&mut self, exports: &mut Vec<String>) {
+    fn declare_functions(&mut self) {
         let mod_env = self.env.clone(); // fixme bad clone
 
         // We have previously discovered through experience that some of the model-provided
@@ -450,8 +516,23 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
         //
         // While this results in yet another linear walk over all the code, it seems to be the
         // simplest way to work around the model inconsistencies.
-        for fn_env in mod_env.get_functions() {
-            self.declare_functions_walk(&mod_env, &fn_env, vec![], exports);
+        //
+        // Functions are visited by name, so declarations and exports come out in the same order
+        // for identical inputs.
+        for fn_env in mod_env
+            .get_functions()
+            .sorted_by_cached_key(|fn_env| fn_env.get_full_name_str())
+        {
+            if let Some(reachable) = self.reachable_functions {
+                if !reachable.contains(&fn_env.get_qualified_id()) {
+                    debug!(
+                        "function {} is unreachable - skipping",
+                        fn_env.get_full_name_str()
+                    );
+                    continue;
+                }
+            }
+            self.declare_functions_walk(&mod_env, &fn_env, vec![]);
         }
     }
 
@@ -460,7 +541,6 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
         mod_env: &mm::ModuleEnv,
         curr_fn_env: &mm::FunctionEnv,
         curr_type_vec: Vec<mty::Type>,
-        exports: &mut Vec<String>,
     ) {
         let g_env = &mod_env.env;
 
@@ -490,7 +570,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
         }
 
         debug!("Declaring function {fn_name}",);
-        let fn_data = StacklessBytecodeGenerator::new(curr_fn_env).generate_function();
+        let fn_data = self.function_data(curr_fn_env);
         debug!("Declared function {fn_name}",);
 
         // If the current function is either a native function or a concrete Move function,
@@ -511,8 +591,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                 curr_fn_env,
                 &[],
                 &fn_data,
-                curr_fn_env.llvm_linkage(),
-                exports,
+                self.visibility_linkage(curr_fn_env),
             );
             if curr_fn_qid.module_id != mod_env.get_id() {
                 // True foreign functions are only declared in our module, don't process further.
@@ -539,7 +618,6 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                 &curr_type_vec,
                 &fn_data,
                 llvm::LLVMLinkage::LLVMPrivateLinkage,
-                exports,
             );
             self.expanded_functions.push(curr_fn_qiid);
         }
@@ -561,20 +639,51 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
                 // Recursively discover/declare more functions on this call path.
                 let called_fn_env = g_env.get_function((*mod_id).qualified(*fun_id));
-                self.declare_functions_walk(mod_env, &called_fn_env, types, exports);
+                self.declare_functions_walk(mod_env, &called_fn_env, types);
             }
         }
     }
 
+    /// Linkage of the concrete Move function `fn_env` from its visibility. Public and entry
+    /// functions are linked externally. Friend and private functions only when the object of
+    /// another module calls them: friend functions from their friends, private ones from the
+    /// instantiations of generic functions of their module expanded into other modules.
+    fn visibility_linkage(&self, fn_env: &mm::FunctionEnv) -> llvm::LLVMLinkage {
+        if fn_env.visibility() == Visibility::Public
+            || fn_env.is_entry()
+            || self
+                .cross_module_callees
+                .contains(&fn_env.get_qualified_id())
+        {
+            llvm::LLVMLinkage::LLVMExternalLinkage
+        } else {
+            llvm::LLVMLinkage::LLVMPrivateLinkage
+        }
+    }
+
+    /// The stackless bytecode of `fn_env`, generated on first use.
+    fn function_data(&mut self, fn_env: &mm::FunctionEnv) -> Rc<FunctionData> {
+        let stackless_time = &mut self.stackless_time;
+        self.function_data
+            .entry(fn_env.get_qualified_id())
+            .or_insert_with(|| {
+                let start = Instant::now();
+                let fn_data = StacklessBytecodeGenerator::new(fn_env).generate_function();
+                *stackless_time += start.elapsed();
+                Rc::new(fn_data)
+            })
+            .clone()
+    }
+
     fn declare_move_function(
         &mut self,
         fn_env: &mm::FunctionEnv,
         tyvec: &[mty::Type],
         fn_data: &FunctionData,
         linkage: llvm::LLVMLinkage,
-        exports: &mut Vec<String>,
     ) {
-        let mut linkage = linkage;
+        let export_policy = ExportPolicy::of(fn_env, self.options);
+        let linkage = export_policy.linkage(linkage);
         let ll_sym_name = fn_env.llvm_symbol_name(tyvec);
         debug!(
             "Declare Move function {ll_sym_name} in {}",
@@ -620,26 +729,34 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                     attrs.push((parm_num, "noalias", None));
                 }
             }
-            let unit_test = self.options.unit_test_function.clone().unwrap_or_default();
-            if fn_env.is_entry() || fn_env.get_full_name_str().replace("::", "__") == unit_test {
-                linkage = llvm::LLVMLinkage::LLVMExternalLinkage;
+            // `check_inline_hints` reported invalid hints before code generation
+            if let Ok(Some(hint)) = inline_hint(fn_env, self.options) {
+                attrs.push((
+                    llvm::LLVMAttributeFunctionIndex,
+                    hint.llvm_attribute(),
+                    None,
+                ));
             }
             let tfn = self.llvm_module.add_function(
-                exports,
                 &fn_env.module_env.llvm_module_name(),
                 &ll_sym_name,
                 ll_fnty,
-                fn_env.is_entry(),
             );
             self.llvm_module.add_attributes(tfn, &attrs);
             tfn
         };
+        self.exports
+            .declare(self.llvm_module, ll_fn, fn_env, export_policy);
 
         ll_fn.as_gv().set_linkage(linkage);
         debug!("Adding declared {ll_sym_name} to current module");
         self.fn_decls.insert(fn_env.get_full_name_str(), ll_fn);
         self.fn_is_entry
             .insert(fn_env.get_full_name_str(), fn_env.is_entry());
+        if export_policy.is_dispatched() {
+            self.fn_entry_ids
+                .insert(fn_env.get_full_name_str(), fn_env.get_qualified_id());
+        }
     }
 
     /// Generate the call selector function.
@@ -651,14 +768,37 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
     /// This method will loop over all declared functions check if the keccak hash of the function name
     /// matches the input hash, and if so, it will call the function. If no match is found, the
     /// function should abort.
-    fn generate_call_selector(&mut self, exports: &mut Vec<String>) {
+    ///
+    /// The selector of a function can be overridden with a `#[selector = 0x12345678]` attribute
+    /// or the `--selector` option, e.g. to match an existing Solidity interface. Two entry
+    /// functions mapping to the same selector is a compile error.
+    ///
+    /// There is one call selector for the whole program. The entry functions of other modules
+    /// are declared in this module and called across objects, like other foreign functions.
+    fn generate_call_selector(&mut self) -> anyhow::Result<()> {
+        let _span = debug_span!("generate_call_selector").entered();
         debug!("Generating call selector function");
         let llvm_cx = self.llvm_cx;
         let llvm_module = self.llvm_module;
-        if exports.contains(&"call_selector".to_string()) {
+        if !self.exports.claim_call_selector() {
             debug!("call_selector already declared, skipping");
-            return;
+            return Ok(());
+        }
+        self.declare_foreign_entry_functions();
+
+        let mut selectors: BTreeMap<u32, &String> = BTreeMap::new();
+        for name in self.fn_entry_ids.keys() {
+            let sel = self.entry_selector(name)?;
+            if let Some(other) = selectors.insert(sel, name) {
+                anyhow::bail!(
+                    "selector collision: entry functions `{other}` and `{name}` both map to selector 0x{:08x}; \
+                     override one of them with `#[selector = 0x...]` or `--selector`",
+                    u32::from_be_bytes(sel.to_le_bytes())
+                );
+            }
+            self.exports.set_selector(name, sel);
         }
+
         let i64_t = llvm_cx.int_type(64);
         let i32_t = llvm_cx.int_type(32);
         let i8_p = llvm_cx.ptr_type();
@@ -666,13 +806,28 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
         let param_tys = [i8_p, i64_t];
         let llty = llvm::FunctionType::new(ret_ty, &param_tys);
-        let ll_fn = llvm_module.add_function(&mut vec![], "native", "call_selector", llty, false);
+        let ll_fn = llvm_module.add_function("native", "call_selector", llty);
         let attrs = vec![(1, "readonly", None), (1, "nonnull", None)];
         llvm_module.add_attributes(ll_fn, &attrs);
         let builder = llvm_cx.create_builder();
         let entry_bb = ll_fn.append_basic_block("entry");
         builder.position_at_end(entry_bb);
         let buf_ptr = ll_fn.get_param(0);
+        let buf_size = ll_fn.get_param(1);
+
+        // every check of the call data length branches to this block, which aborts with
+        // MALFORMED_INPUT_CODE instead of reading past the end of the buffer
+        let malformed_bb = ll_fn.append_basic_block("malformed");
+        let select_bb = ll_fn.append_basic_block("select");
+        let selector_len = llvm::Constant::const_int(i64_t, 4, 0);
+        let too_short = builder.build_compare(
+            llvm::LLVMIntPredicate::LLVMIntULT,
+            buf_size.as_any_value(),
+            selector_len.as_any_value(),
+            "no_selector",
+        );
+        builder.build_cond_br(too_short, malformed_bb, select_bb);
+        builder.position_at_end(select_bb);
 
         // cast `i8*` → `i32*` so we can load a 4‐byte selector
         let sel_ptr = builder.build_unary_bitcast(buf_ptr.as_any_value(), i8_p, "sel_ptr");
@@ -681,18 +836,12 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
         // build the switch
         let default_bb = ll_fn.append_basic_block("default");
-        let switch_inst = builder.build_switch(sel64, default_bb, self.fn_decls.len() as u32);
-        for (name, func) in self.fn_decls.iter() {
-            if !self.fn_is_entry.get(name).unwrap_or(&false) {
-                debug!("Skipping function {name} as it is not an entry function");
-                continue;
-            }
-            debug!("Adding call selector function {name} to exports");
-            let mut keccak = Keccak::v256();
-            keccak.update(name.as_bytes());
-            let mut hash = [0u8; 32];
-            keccak.finalize(&mut hash);
-            let sel = u32::from_be_bytes([hash[3], hash[2], hash[1], hash[0]]);
+        let switch_inst = builder.build_switch(sel64, default_bb, selectors.len() as u32);
+        // cases in entry function name order, like the declarations
+        let mut cases = selectors.into_iter().collect::<Vec<_>>();
+        cases.sort_by_key(|(_, name)| *name);
+        for (sel, name) in cases {
+            let func = &self.fn_decls[name];
             debug!("Adding call selector function {name} with selector {sel:x?} to exports");
 
             // create a basic block for this case
@@ -704,6 +853,30 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
             builder.position_at_end(case_bb);
 
+            let min_size = self.min_call_buffer_size(name);
+            let min_size = llvm::Constant::const_int(i64_t, min_size, 0);
+            let too_short = builder.build_compare(
+                llvm::LLVMIntPredicate::LLVMIntULT,
+                buf_size.as_any_value(),
+                min_size.as_any_value(),
+                "too_short",
+            );
+            let args_bb = ll_fn.append_basic_block(&format!("args_{name}"));
+            builder.build_cond_br(too_short, malformed_bb, args_bb);
+            builder.position_at_end(args_bb);
+
+            if self.options.payable_checks
+                && !is_payable(&self.env.env.get_function(self.fn_entry_ids[name]))
+            {
+                let require_no_value_fn = Self::get_runtime_function_by_name(
+                    llvm_cx,
+                    llvm_module,
+                    &self.rtty_cx,
+                    "require_no_value",
+                );
+                builder.call(require_no_value_fn, &[]);
+            }
+
             let four = llvm::Constant::const_int(i64_t, 4, 0);
             let signer_ptr = builder.build_address_with_indices(
                 llvm_cx.int_type(8),
@@ -711,23 +884,229 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                 &[four.as_any_value()],
                 "signer",
             );
-            let args = &[signer_ptr];
-            builder.call(*func, args);
+            let args = self.decode_entry_args(
+                &builder,
+                name,
+                buf_ptr.as_any_value(),
+                buf_size.as_any_value(),
+                signer_ptr,
+            );
+            let result = builder.call(*func, &args);
             debug!("built call");
+            self.encode_entry_returns(&builder, name, result);
             builder.build_return_void();
             debug!("built return");
         }
         debug!("Added all cases to call selector");
 
-        // create basic block for the default case which will call abort, this triggers terminate
-        // on pallet-revive
+        // create basic block for the default case which will call abort, this reverts the call
+        // through seal_return on pallet-revive
         builder.position_at_end(default_bb);
         let abort_args = &[llvm::Constant::const_int(i64_t, 2, 0).as_any_value()];
         let abort_fn =
             Self::get_runtime_function_by_name(llvm_cx, llvm_module, &self.rtty_cx, "abort");
         builder.call(abort_fn, abort_args);
         builder.build_unreachable();
-        exports.push("call_selector".to_string());
+
+        builder.position_at_end(malformed_bb);
+        let abort_args =
+            &[llvm::Constant::const_int(i64_t, MALFORMED_INPUT_CODE, 0).as_any_value()];
+        builder.call(abort_fn, abort_args);
+        builder.build_unreachable();
+        Ok(())
+    }
+
+    /// Declare the entry functions of all modules that are not declared in this module yet.
+    /// Generic entry functions have no instantiation to call and are not dispatched.
+    fn declare_foreign_entry_functions(&mut self) {
+        let g_env = self.env.env;
+        for fn_env in g_env.get_modules().flat_map(|m| m.into_functions()) {
+            if !ExportPolicy::of(&fn_env, self.options).is_dispatched()
+                || fn_env.is_native()
+                || fn_env.get_type_parameter_count() > 0
+                || self.fn_decls.contains_key(&fn_env.get_full_name_str())
+            {
+                continue;
+            }
+            let fn_data = self.function_data(&fn_env);
+            self.declare_move_function(&fn_env, &[], &fn_data, fn_env.llvm_linkage());
+        }
+    }
+
+    /// The arguments of the entry function `name` in the `call_selector` buffer at `buf_ptr`
+    /// of `buf_size` bytes: the selector, the signer at `signer_ptr`, then the BCS encoded
+    /// arguments of the call data. The signer is passed for `&signer` and `signer`
+    /// parameters; every other parameter is decoded from the call data, in order, by
+    /// `move_rt_decode_call_arg`.
+    fn decode_entry_args(
+        &self,
+        builder: &llvm::Builder,
+        name: &str,
+        buf_ptr: llvm::AnyValue,
+        buf_size: llvm::AnyValue,
+        signer_ptr: llvm::AnyValue,
+    ) -> Vec<llvm::AnyValue> {
+        use mty::{PrimitiveType, Type};
+
+        let llvm_cx = self.llvm_cx;
+        let i8_t = llvm_cx.int_type(8);
+        let i64_t = llvm_cx.int_type(64);
+        let fn_env = self.env.env.get_function(self.fn_entry_ids[name]);
+        let decode_fn = Self::get_runtime_function_by_name(
+            llvm_cx,
+            self.llvm_module,
+            &self.rtty_cx,
+            "decode_call_arg",
+        );
+        let mut call_data = None;
+        let mut args = vec![];
+        for (i, param_ty) in fn_env.get_parameter_types().iter().enumerate() {
+            match param_ty {
+                Type::Reference(_, ty) if **ty == Type::Primitive(PrimitiveType::Signer) => {
+                    args.push(signer_ptr);
+                }
+                Type::Primitive(PrimitiveType::Signer) => {
+                    let llty = self.rtty_cx.get_llvm_type_for_signer();
+                    args.push(builder.load(signer_ptr, llty, "signer_value"));
+                }
+                _ => {
+                    // `{ cursor, end }` of the call data arguments, set up on first use
+                    let (cursor, end) = *call_data.get_or_insert_with(|| {
+                        let offset = (4 + self.options.address_length) as u64;
+                        let offset = llvm::Constant::const_int(i64_t, offset, 0).as_any_value();
+                        let args_ptr =
+                            builder.build_address_with_indices(i8_t, buf_ptr, &[offset], "args");
+                        let cursor = builder.build_alloca(llvm_cx.ptr_type(), "cursor");
+                        builder.store(args_ptr, cursor.as_any_value());
+                        let end = builder.build_address_with_indices(
+                            i8_t,
+                            buf_ptr,
+                            &[buf_size],
+                            "args_end",
+                        );
+                        (cursor, end)
+                    });
+                    let llty = self
+                        .to_llvm_type(param_ty, &[])
+                        .unwrap_or_else(|| self.declare_struct_instance(param_ty, &[]));
+                    let value = builder.build_alloca(llty, &format!("arg{i}"));
+                    let tydesc = self.rtty_cx.define_llvm_tydesc(param_ty).ptr();
+                    builder.call(
+                        decode_fn,
+                        &[
+                            tydesc.as_any_value(),
+                            cursor.as_any_value(),
+                            end,
+                            value.as_any_value(),
+                        ],
+                    );
+                    args.push(builder.load(value.as_any_value(), llty, &format!("arg{i}_value")));
+                }
+            }
+        }
+        args
+    }
+
+    /// Return the values returned by the entry function `name` as `result` as the output of
+    /// the call, BCS encoded one after the other by `move_rt_encode_return`. References are
+    /// returned like the values they point to.
+    fn encode_entry_returns(&self, builder: &llvm::Builder, name: &str, result: llvm::AnyValue) {
+        let fn_env = self.env.env.get_function(self.fn_entry_ids[name]);
+        let returns = match fn_env.get_result_type() {
+            mty::Type::Tuple(tys) => tys,
+            ty => vec![ty],
+        };
+        if returns.is_empty() {
+            return;
+        }
+        let llvm_cx = self.llvm_cx;
+        let encode_fn = Self::get_runtime_function_by_name(
+            llvm_cx,
+            self.llvm_module,
+            &self.rtty_cx,
+            "encode_return",
+        );
+        for (i, ty) in returns.iter().enumerate() {
+            let value = if returns.len() == 1 {
+                result
+            } else {
+                builder.build_extract_value(result, i as u32, &format!("ret{i}"))
+            };
+            let (ty, ptr) = match ty {
+                mty::Type::Reference(_, ty) => (ty.as_ref(), value),
+                _ => {
+                    let llty = self
+                        .to_llvm_type(ty, &[])
+                        .unwrap_or_else(|| self.declare_struct_instance(ty, &[]));
+                    let ptr = builder.build_alloca(llty, &format!("ret{i}_ptr"));
+                    builder.store(value, ptr.as_any_value());
+                    (ty, ptr.as_any_value())
+                }
+            };
+            let tydesc = self.rtty_cx.define_llvm_tydesc(ty).ptr();
+            builder.call(encode_fn, &[tydesc.as_any_value(), ptr]);
+        }
+        let return_data_fn = Self::get_runtime_function_by_name(
+            llvm_cx,
+            self.llvm_module,
+            &self.rtty_cx,
+            "return_data",
+        );
+        builder.call(return_data_fn, &[]);
+    }
+
+    /// The smallest `call_selector` buffer the entry function `name` can be called with: the
+    /// selector, the signer, and the smallest encoding of each argument in the call data. A
+    /// shorter buffer can't hold the arguments, so it is rejected before anything is read.
+    fn min_call_buffer_size(&self, name: &str) -> u64 {
+        use mty::{PrimitiveType, Type};
+
+        let fn_env = self.env.env.get_function(self.fn_entry_ids[name]);
+        let header = (4 + self.options.address_length) as u64;
+        let args: u64 = fn_env
+            .get_parameter_types()
+            .iter()
+            .map(|param_ty| match param_ty {
+                // passed in the header
+                Type::Primitive(PrimitiveType::Signer) | Type::Reference(..) => 0,
+                _ => self.min_encoded_size(param_ty),
+            })
+            .sum();
+        header + args
+    }
+
+    /// The size of the smallest BCS encoding of a value of `mty` in call data.
+    fn min_encoded_size(&self, mty: &mty::Type) -> u64 {
+        use mty::{PrimitiveType, Type};
+
+        match mty {
+            Type::Primitive(PrimitiveType::Address | PrimitiveType::Signer) => {
+                self.options.address_length as u64
+            }
+            Type::Primitive(PrimitiveType::Bool | PrimitiveType::U8) => 1,
+            Type::Primitive(PrimitiveType::U16) => 2,
+            Type::Primitive(PrimitiveType::U32) => 4,
+            Type::Primitive(PrimitiveType::U64) => 8,
+            Type::Primitive(PrimitiveType::U128) => 16,
+            Type::Primitive(PrimitiveType::U256) => 32,
+            // the ULEB128 length
+            Type::Vector(_) => 1,
+            Type::Struct(mid, sid, tys) => self
+                .env
+                .env
+                .get_struct(mid.qualified(*sid))
+                .get_fields()
+                .map(|field| self.min_encoded_size(&field.get_type().instantiate(tys)))
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// Selector of an entry function as loaded from the call data, i.e. the first 4 bytes
+    /// read as a little-endian `u32`.
+    fn entry_selector(&self, name: &str) -> anyhow::Result<u32> {
+        let fn_env = self.env.env.get_function(self.fn_entry_ids[name]);
+        entry_selector(&fn_env, self.options)
     }
 
     /// Declare native functions.
@@ -770,8 +1149,11 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
 
                 let ll_parm_tys = fn_env.get_parameter_types();
                 let ll_parm_tys = ll_parm_tys.iter().map(|mty| {
-                    // Pass type parameters and vectors as pointers
-                    if mty.is_type_parameter() || mty.is_vector() {
+                    // Pass type parameters, vectors and addresses as pointers
+                    if mty.is_type_parameter()
+                        || mty.is_vector()
+                        || matches!(mty, mty::Type::Primitive(mty::PrimitiveType::Address))
+                    {
                         llcx.ptr_type()
                     } else if let Some(ty) = self.to_llvm_type(mty, &[]) {
                         ty
@@ -789,13 +1171,8 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
             };
             // native functions are functions imported by guest program and exported by polkavm
             // we don't need to export polka sections for those
-            self.llvm_module.add_function(
-                &mut vec![],
-                "native",
-                &ll_native_sym_name,
-                ll_fnty,
-                false,
-            )
+            self.llvm_module
+                .add_function("native", &ll_native_sym_name, ll_fnty)
         };
 
         ll_fn.as_gv().set_linkage(linkage);
@@ -850,7 +1227,8 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
             Type::Primitive(PrimitiveType::Num)
             | Type::Primitive(PrimitiveType::Range)
             | Type::Primitive(PrimitiveType::EventStore) => {
-                panic!("{mty:?} only appears in specifications.")
+                // `check_types` rejects these outside of specifications before translation.
+                unreachable!("unsupported type {mty:?} in non-spec context")
             }
             Type::Reference(_, _) => Some(self.llvm_cx.ptr_type()),
             Type::TypeParameter(tp_idx) => {
@@ -918,7 +1296,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
             | Type::ResourceDomain(_, _, _)
             | Type::Error
             | Type::Var(_) => {
-                panic!("unexpected field type {mty:?}")
+                unreachable!("unexpected field type {mty:?}")
             }
         }
     }
@@ -939,6 +1317,13 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
         }
     }
 
+    /// Declare the coverage global `name`, defined by the coverage object of the program.
+    pub fn coverage_global(&self, name: &str, llty: llvm::Type) -> llvm::Global {
+        self.llvm_module
+            .get_global(name)
+            .unwrap_or_else(|| self.llvm_module.add_global(llty, name))
+    }
+
     pub fn get_rttydesc_ptrs(&self, types: &[mty::Type]) -> Vec<llvm::Constant> {
         let mut ll_global_ptrs = vec![];
         for type_ in types {
@@ -999,6 +1384,84 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                     .collect();
                 self.llvm_builder.call(llfn, &typarams)
             }
+            RtCall::VecPushBack(ll_vec_value, ll_elt_value, elt_mty) => {
+                // Note, no retval from vec_push_back.
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                let mut typarams: Vec<_> = self
+                    .get_rttydesc_ptrs(std::slice::from_ref(elt_mty))
+                    .iter()
+                    .map(|llval| llval.as_any_value())
+                    .collect();
+                typarams.push(*ll_vec_value);
+                typarams.push(*ll_elt_value);
+                self.llvm_builder.call(llfn, &typarams)
+            }
+            RtCall::Assert(ll_cond, ll_code) => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder.call(llfn, &[*ll_cond, *ll_code])
+            }
+            RtCall::Log(ll_level, ll_msg_ptr, ll_msg_len) => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder
+                    .call(llfn, &[*ll_level, *ll_msg_ptr, *ll_msg_len])
+            }
+            RtCall::CoverageRegister(ll_counters, ll_len) => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder.call(llfn, &[*ll_counters, *ll_len])
+            }
+            RtCall::TraceEnter(ll_function, ll_caller_pc) => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder.call(llfn, &[*ll_function, *ll_caller_pc])
+            }
+            RtCall::CheckRef(ll_ptr) => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder.call(llfn, &[*ll_ptr])
+            }
+            RtCall::DebugPrint(ll_value, mty) => {
+                let llfn = self.debug_print_function();
+                let tydesc = self.get_rttydesc_ptrs(std::slice::from_ref(mty))[0];
+                self.llvm_builder
+                    .call(llfn, &[tydesc.as_any_value(), *ll_value])
+            }
+            RtCall::TraceExit | RtCall::TraceDump | RtCall::HeapStatsRegister => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder.call(llfn, &[])
+            }
             RtCall::StrCmpEq(str1_ptr, str1_len, str2_ptr, str2_len) => {
                 let llfn = Self::get_runtime_function(
                     self.llvm_cx,
@@ -1025,10 +1488,33 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                 typarams.push(*ll_src2_value);
                 self.llvm_builder.call(llfn, &typarams)
             }
+            RtCall::U256(_, args) => {
+                let llfn = Self::get_runtime_function(
+                    self.llvm_cx,
+                    self.llvm_module,
+                    &self.rtty_cx,
+                    &rtcall,
+                );
+                self.llvm_builder.call(llfn, args)
+            }
             _ => unreachable!(),
         }
     }
 
+    /// The runtime's `move_native_debug_print(type: &MoveType, value: &AnyValue)`, the
+    /// implementation of `debug::print`, which the `debug` print natives of every flavor
+    /// are lowered to.
+    fn debug_print_function(&self) -> llvm::Function {
+        self.llvm_module
+            .get_named_function(DEBUG_PRINT_SYMBOL)
+            .unwrap_or_else(|| {
+                let ptr_ty = self.llvm_cx.ptr_type();
+                let llty = llvm::FunctionType::new(self.llvm_cx.void_type(), &[ptr_ty, ptr_ty]);
+                self.llvm_module
+                    .add_function("native", DEBUG_PRINT_SYMBOL, llty)
+            })
+    }
+
     // TODO: consider better refactoring for this and other
     // class-level methods, which used to be instance methods.
     // These methods were converted to class-level because their code
@@ -1058,11 +1544,21 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
     ) -> llvm::Function {
         let name = match rtcall {
             RtCall::Abort(..) => "abort",
+            RtCall::Assert(..) => "assert",
+            RtCall::Log(..) => "log",
+            RtCall::CoverageRegister(..) => "coverage_register",
+            RtCall::HeapStatsRegister => "heap_stats_register",
+            RtCall::TraceEnter(..) => "trace_enter",
+            RtCall::TraceExit => "trace_exit",
+            RtCall::TraceDump => "trace_dump",
+            RtCall::DebugPrint(..) => unreachable!("not a move_rt function"),
+            RtCall::CheckRef(..) => "check_ref",
             RtCall::Deserialize(..) => "deserialize",
             RtCall::VecDestroy(..) => "vec_destroy",
             RtCall::VecCopy(..) => "vec_copy",
             RtCall::VecCmpEq(..) => "vec_cmp_eq",
             RtCall::VecEmpty(..) => "vec_empty",
+            RtCall::VecPushBack(..) => "vec_push_back",
             RtCall::StrCmpEq(..) => "str_cmp_eq",
             RtCall::StructCmpEq(..) => "struct_cmp_eq",
             RtCall::MoveTo(..) => "move_to",
@@ -1070,6 +1566,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
             RtCall::BorrowGlobal(..) => "borrow_global",
             RtCall::Exists(..) => "exists",
             RtCall::Release(..) => "release",
+            RtCall::U256(helper, _) => helper.name(),
         };
         Self::get_runtime_function_by_name(llvm_cx, llvm_module, rtty_cx, name)
     }
@@ -1099,6 +1596,49 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                     ];
                     (llty, attrs)
                 }
+                "assert" => {
+                    // assert(cond: bool, code: u64)
+                    let ret_ty = llvm_cx.void_type();
+                    let param_tys = &[llvm_cx.int_type(1), llvm_cx.int_type(64)];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    (llty, vec![])
+                }
+                "log" => {
+                    // log(level: u32, msg_ptr: *const u8, len: u64)
+                    let ret_ty = llvm_cx.void_type();
+                    let param_tys = &[
+                        llvm_cx.int_type(32),
+                        llvm_cx.ptr_type(),
+                        llvm_cx.int_type(64),
+                    ];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    let attrs = vec![(2, "readonly", None)];
+                    (llty, attrs)
+                }
+                "coverage_register" => {
+                    // coverage_register(counters: *const u32, len: u32)
+                    let ret_ty = llvm_cx.void_type();
+                    let param_tys = &[llvm_cx.ptr_type(), llvm_cx.int_type(32)];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    (llty, vec![])
+                }
+                "trace_enter" => {
+                    // trace_enter(function: u32, caller_pc: u32)
+                    let ret_ty = llvm_cx.void_type();
+                    let param_tys = &[llvm_cx.int_type(32), llvm_cx.int_type(32)];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    (llty, vec![])
+                }
+                "check_ref" => {
+                    // check_ref(ptr: *const u8)
+                    let ret_ty = llvm_cx.void_type();
+                    let llty = llvm::FunctionType::new(ret_ty, &[llvm_cx.ptr_type()]);
+                    (llty, vec![])
+                }
+                "trace_exit" | "trace_dump" | "heap_stats_register" => {
+                    let llty = llvm::FunctionType::new(llvm_cx.void_type(), &[]);
+                    (llty, vec![])
+                }
                 "deserialize" => {
                     let ret_ty = llvm_cx.void_type();
                     let ptr_ty = llvm_cx.ptr_type();
@@ -1110,11 +1650,90 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                         llvm_cx.get_anonymous_struct_type(&[ptr_ty, int_ty, int_ty]),
                     ]);
                     let llty = llvm::FunctionType::new(ret_ty, param_tys);
-                    let ll_fn =
-                        llvm_module.add_function(&mut vec![], "native", &fn_name, llty, false);
+                    let ll_fn = llvm_module.add_function("native", &fn_name, llty);
                     llvm_module.add_type_attribute(ll_fn, 1, "sret", ll_sret);
                     return ll_fn;
                 }
+                "encode_return" => {
+                    // encode_return(type_v: &MoveType, v: &AnyValue)
+                    let ret_ty = llvm_cx.void_type();
+                    let ptr_ty = llvm_cx.ptr_type();
+                    let llty = llvm::FunctionType::new(ret_ty, &[ptr_ty, ptr_ty]);
+                    let mut attrs = Self::mk_pattrs_for_move_type(1);
+                    attrs.push((2, "readonly", None));
+                    attrs.push((2, "nonnull", None));
+                    (llty, attrs)
+                }
+                "return_data" => {
+                    // return_data(), hands the encoded return values to the host
+                    let llty = llvm::FunctionType::new(llvm_cx.void_type(), &[]);
+                    (llty, vec![])
+                }
+                "require_no_value" => {
+                    // require_no_value(), aborts if the call transfers value
+                    let ret_ty = llvm_cx.void_type();
+                    let llty = llvm::FunctionType::new(ret_ty, &[]);
+                    (llty, vec![])
+                }
+                "decode_call_arg" => {
+                    // decode_call_arg(type_v: &MoveType, cursor: &mut *const u8, end: *const u8,
+                    //                 v: *mut AnyValue)
+                    let ret_ty = llvm_cx.void_type();
+                    let ptr_ty = llvm_cx.ptr_type();
+                    let param_tys = &[ptr_ty, ptr_ty, ptr_ty, ptr_ty];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    let mut attrs = Self::mk_pattrs_for_move_type(1);
+                    attrs.push((2, "nonnull", None));
+                    attrs.push((4, "nonnull", None));
+                    (llty, attrs)
+                }
+                "add256" | "sub256" | "mul256" | "div256" | "mod256" => {
+                    // add256(a: *const U256, b: *const U256, dst: *mut U256) -> bool
+                    let ret_ty = llvm_cx.int_type(1);
+                    let ptr_ty = llvm_cx.ptr_type();
+                    let param_tys = &[ptr_ty, ptr_ty, ptr_ty];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    let attrs = vec![
+                        (1, "readonly", None),
+                        (1, "nonnull", None),
+                        (2, "readonly", None),
+                        (2, "nonnull", None),
+                        (3, "nonnull", None),
+                    ];
+                    (llty, attrs)
+                }
+                "cmp256" => {
+                    // cmp256(a: *const U256, b: *const U256) -> i32
+                    let ret_ty = llvm_cx.int_type(32);
+                    let ptr_ty = llvm_cx.ptr_type();
+                    let param_tys = &[ptr_ty, ptr_ty];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    let attrs = vec![
+                        (1, "readonly", None),
+                        (1, "nonnull", None),
+                        (2, "readonly", None),
+                        (2, "nonnull", None),
+                    ];
+                    (llty, attrs)
+                }
+                "zext256" | "trunc256" => {
+                    // zext256(src: *const u8, dst: *mut U256, len: u32)
+                    // trunc256(src: *const U256, dst: *mut u8, len: u32) -> bool
+                    let ret_ty = if rtcall_name == "zext256" {
+                        llvm_cx.void_type()
+                    } else {
+                        llvm_cx.int_type(1)
+                    };
+                    let ptr_ty = llvm_cx.ptr_type();
+                    let param_tys = &[ptr_ty, ptr_ty, llvm_cx.int_type(32)];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    let attrs = vec![
+                        (1, "readonly", None),
+                        (1, "nonnull", None),
+                        (2, "nonnull", None),
+                    ];
+                    (llty, attrs)
+                }
                 "vec_destroy" => {
                     // vec_destroy(type_ve: &MoveType, v: MoveUntypedVector)
                     let ret_ty = llvm_cx.void_type();
@@ -1170,6 +1789,19 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                     let attrs = Self::mk_pattrs_for_move_type(1);
                     (llty, attrs)
                 }
+                "vec_push_back" => {
+                    // vec_push_back(type_ve: &MoveType, v: &mut MoveUntypedVector, e: *mut AnyValue)
+                    let ret_ty = llvm_cx.void_type();
+                    let tydesc_ty = llvm_cx.ptr_type();
+                    let vector_ty = llvm_cx.ptr_type();
+                    let anyval_ty = llvm_cx.ptr_type();
+                    let param_tys = &[tydesc_ty, vector_ty, anyval_ty];
+                    let llty = llvm::FunctionType::new(ret_ty, param_tys);
+                    let mut attrs = Self::mk_pattrs_for_move_type(1);
+                    attrs.extend(Self::mk_pattrs_for_move_untyped_vec(2, true /* mut */));
+                    attrs.push((3, "nonnull", None));
+                    (llty, attrs)
+                }
                 "str_cmp_eq" => {
                     // str_cmp_eq(str1_ptr: &AnyValue, str1_len: &AnyValue,
                     //            str2_ptr: &AnyValue, str1_len: &AnyValue) -> bool
@@ -1298,7 +1930,7 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
                 n => panic!("unknown runtime function {n}"),
             };
 
-            let ll_fn = llvm_module.add_function(&mut vec![], "native", &fn_name, llty, false);
+            let ll_fn = llvm_module.add_function("native", &fn_name, llty);
             llvm_module.add_attributes(ll_fn, &attrs);
             ll_fn
         }
@@ -1340,3 +1972,126 @@ impl<'mm: 'up, 'up> ModuleContext<'mm, 'up> {
         attrs
     }
 }
+
+/// `module::NAME` of the Move `const` of `module_env` of type `mty` with the value `mc`, if
+/// exactly one has it. `module_env` is the module declaring the function loading `mc`, which
+/// is not the module being translated for instantiations of foreign generic functions. The
+/// compiler puts a literal and a `const` of the same type and value into one constant pool
+/// entry, so a literal equal to a `const` is named after it.
+pub fn constant_name(
+    module_env: &mm::ModuleEnv,
+    mc: &sbc::Constant,
+    mty: &mty::Type,
+) -> Option<String> {
+    let symbol_pool = module_env.symbol_pool();
+    let (named,) = module_env
+        .get_named_constants()
+        .filter(|named| named.get_type() == *mty && constant_has_value(mc, &named.get_value()))
+        .collect_tuple()?;
+    Some(format!(
+        "{}::{}",
+        module_env.get_name().display(module_env.env),
+        named.get_name().display(symbol_pool)
+    ))
+}
+
+/// Whether the stackless bytecode constant `mc` is the value of a Move `const`.
+fn constant_has_value(mc: &sbc::Constant, value: &mast::Value) -> bool {
+    use sbc::Constant;
+    match (mc, value) {
+        (Constant::Bool(b), mast::Value::Bool(v)) => b == v,
+        (
+            Constant::U8(_)
+            | Constant::U16(_)
+            | Constant::U32(_)
+            | Constant::U64(_)
+            | Constant::U128(_)
+            | Constant::U256(_),
+            mast::Value::Number(n),
+        ) => constant_number(mc) == n.to_string(),
+        (Constant::Address(a), mast::Value::Address(v)) => a == v,
+        (Constant::ByteArray(bytes), mast::Value::ByteArray(v)) => bytes == v,
+        (Constant::ByteArray(bytes), mast::Value::Vector(v)) => {
+            bytes.len() == v.len()
+                && bytes
+                    .iter()
+                    .zip(v)
+                    .all(|(b, v)| constant_has_value(&Constant::U8(*b), v))
+        }
+        (Constant::AddressArray(addrs), mast::Value::AddressArray(v)) => addrs == v,
+        (Constant::AddressArray(addrs), mast::Value::Vector(v)) => {
+            addrs.len() == v.len()
+                && addrs
+                    .iter()
+                    .zip(v)
+                    .all(|(a, v)| matches!(v, mast::Value::Address(v) if a == v))
+        }
+        (Constant::Vector(elts), mast::Value::Vector(v)) => {
+            elts.len() == v.len()
+                && elts
+                    .iter()
+                    .zip(v)
+                    .all(|(elt, v)| constant_has_value(elt, v))
+        }
+        (Constant::Vector(elts), mast::Value::ByteArray(v)) => {
+            elts.len() == v.len()
+                && elts
+                    .iter()
+                    .zip(v)
+                    .all(|(elt, b)| constant_has_value(elt, &mast::Value::Number((*b).into())))
+        }
+        _ => false,
+    }
+}
+
+/// The decimal value of the number constant `mc`.
+fn constant_number(mc: &sbc::Constant) -> String {
+    use sbc::Constant;
+    match mc {
+        Constant::U8(n) => n.to_string(),
+        Constant::U16(n) => n.to_string(),
+        Constant::U32(n) => n.to_string(),
+        Constant::U64(n) => n.to_string(),
+        Constant::U128(n) => n.to_string(),
+        Constant::U256(n) => n.to_string(),
+        _ => unreachable!("{mc:?} is not a number"),
+    }
+}
+
+/// The selector `call_selector` dispatches to the entry function `fn_env` with, as loaded
+/// (little endian) from call data. It can be overridden with `#[selector = 0x...]` or
+/// `--selector`, and defaults to the first 4 bytes of the keccak256 hash of `module::function`.
+pub fn entry_selector(fn_env: &mm::FunctionEnv, options: &CodegenOptions) -> anyhow::Result<u32> {
+    let name = fn_env.get_full_name_str();
+    let attr_override = match find_attribute(fn_env, "selector") {
+        Some(mast::Attribute::Assign(_, _, mast::AttributeValue::Value(_, value))) => Some(value),
+        Some(_) => anyhow::bail!("`#[selector]` of `{name}` must be `#[selector = 0x...]`"),
+        None => None,
+    };
+    let option_override = options
+        .selector_overrides
+        .iter()
+        .filter_map(|o| o.split_once('='))
+        .find(|(f, _)| *f == name)
+        .map(|(_, sel)| sel);
+
+    let sel = if let Some(sel) = option_override {
+        let sel = u32::from_str_radix(sel.trim_start_matches("0x"), 16)
+            .map_err(|e| anyhow::anyhow!("invalid selector `{sel}` for `{name}`: {e}"))?;
+        sel.to_be_bytes()
+    } else if let Some(value) = attr_override {
+        let sel = match value {
+            mast::Value::Number(n) => n.to_u32(),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow::anyhow!("`#[selector]` of `{name}` must be a 4 byte number"))?;
+        sel.to_be_bytes()
+    } else {
+        let mut keccak = Keccak::v256();
+        keccak.update(name.as_bytes());
+        let mut hash = [0u8; 32];
+        keccak.finalize(&mut hash);
+        [hash[0], hash[1], hash[2], hash[3]]
+    };
+    Ok(u32::from_le_bytes(sel))
+}
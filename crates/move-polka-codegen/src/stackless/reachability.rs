@@ -0,0 +1,108 @@
+//! Reachability analysis over the stackless bytecode of the whole program.
+//!
+//! Starting from a set of root functions (exported functions by default), follow
+//! every call site transitively. Functions that are never reached can be
+//! skipped before any LLVM code is generated for them.
+
+use crate::{options::CodegenOptions, stackless::ExportPolicy};
+use move_model::model as mm;
+use move_stackless_bytecode::{
+    stackless_bytecode as sbc, stackless_bytecode_generator::StacklessBytecodeGenerator,
+};
+use std::collections::{BTreeSet, VecDeque};
+use tracing::debug;
+
+/// Whether `fn_env` is a root of the reachability analysis for the given options.
+pub fn is_reachability_root(fn_env: &mm::FunctionEnv, options: &CodegenOptions) -> bool {
+    ExportPolicy::of(fn_env, options).is_external()
+        || options
+            .reachability_roots
+            .contains(&fn_env.get_full_name_str())
+}
+
+/// Compute all functions transitively called from the roots selected by `options`.
+pub fn reachable_functions(
+    env: &mm::GlobalEnv,
+    options: &CodegenOptions,
+) -> BTreeSet<mm::QualifiedId<mm::FunId>> {
+    let mut reachable = BTreeSet::new();
+    let mut worklist: VecDeque<mm::QualifiedId<mm::FunId>> = env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .filter(|f| is_reachability_root(f, options))
+        .map(|f| f.get_qualified_id())
+        .collect();
+
+    while let Some(qid) = worklist.pop_front() {
+        if !reachable.insert(qid) {
+            continue;
+        }
+        for callee in called_functions(&env.get_function(qid)) {
+            if !reachable.contains(&callee) {
+                worklist.push_back(callee);
+            }
+        }
+    }
+    debug!("{} reachable functions", reachable.len());
+    reachable
+}
+
+/// The functions `fn_env` calls directly, none for a native function.
+pub fn called_functions(fn_env: &mm::FunctionEnv) -> BTreeSet<mm::QualifiedId<mm::FunId>> {
+    if fn_env.is_native() {
+        return BTreeSet::new();
+    }
+    let fn_data = StacklessBytecodeGenerator::new(fn_env).generate_function();
+    fn_data
+        .code
+        .iter()
+        .filter_map(|instr| match instr {
+            sbc::Bytecode::Call(_, _, sbc::Operation::Function(mod_id, fun_id, _), _, _) => {
+                Some(mod_id.qualified(*fun_id))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The functions called from the object of a module other than their own: those called by
+/// functions of other modules, friends calling friend functions included, and those called by
+/// generic functions, whose instantiations are expanded into the modules calling them.
+pub fn cross_module_callees(env: &mm::GlobalEnv) -> BTreeSet<mm::QualifiedId<mm::FunId>> {
+    let mut callees = BTreeSet::new();
+    for caller in env.get_modules().flat_map(|m| m.into_functions()) {
+        // inline functions are expanded by the Move compiler, their calls belong to the caller
+        if caller.is_inline() {
+            continue;
+        }
+        let module_id = caller.module_env.get_id();
+        let generic = caller.get_type_parameter_count() > 0;
+        callees.extend(
+            called_functions(&caller)
+                .into_iter()
+                .filter(|callee| generic || callee.module_id != module_id),
+        );
+    }
+    debug!("{} functions called across modules", callees.len());
+    callees
+}
+
+/// The modules the blob of module `id` holds with `--split-modules`: the module itself and
+/// the modules of all functions it calls, transitively.
+pub fn module_closure(env: &mm::GlobalEnv, id: mm::ModuleId) -> BTreeSet<mm::ModuleId> {
+    let mut modules = BTreeSet::from([id]);
+    let mut visited = BTreeSet::new();
+    let mut worklist: VecDeque<mm::QualifiedId<mm::FunId>> = env
+        .get_module(id)
+        .get_functions()
+        .map(|f| f.get_qualified_id())
+        .collect();
+    while let Some(qid) = worklist.pop_front() {
+        if !visited.insert(qid) {
+            continue;
+        }
+        modules.insert(qid.module_id);
+        worklist.extend(called_functions(&env.get_function(qid)));
+    }
+    modules
+}
@@ -13,13 +13,13 @@ use crate::stackless::{
     llvm,
     module_context::ModuleContext,
 };
-use log::{debug, Level};
-use move_core_types::{account_address, u256::U256};
+use move_core_types::u256::U256;
 use move_model::{
     ast::{self as mast, Address},
     model as mm, ty as mty,
 };
 use polkavm_move_native::types::TypeDesc;
+use tracing::{debug, Level};
 
 static TD_NAME: &str = "__move_rt_type";
 static TD_TYPE_NAME_NAME: &str = "__move_rt_type_name";
@@ -33,6 +33,8 @@ pub struct RttyContext<'mm, 'up> {
     llvm_cx: &'up llvm::Context,
     llvm_module: &'up llvm::Module,
     f_env: Option<mm::FunctionEnv<'mm>>,
+    /// Length of Move addresses in bytes, see `CodegenOptions::address_length`.
+    address_length: usize,
 }
 
 impl<'mm, 'up> RttyContext<'mm, 'up> {
@@ -40,12 +42,14 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
         env: &'mm mm::GlobalEnv,
         llvm_cx: &'up llvm::Context,
         llmod: &'up llvm::Module,
+        address_length: usize,
     ) -> RttyContext<'mm, 'up> {
         RttyContext {
             g_env: env,
             llvm_cx,
             llvm_module: llmod,
             f_env: None,
+            address_length,
         }
     }
 
@@ -110,7 +114,7 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
         // Create a type `[N x i8]` (an account address) corresponding
         // to `move_native::rt_types::MoveAddress`.
         let llcx = &self.get_llvm_cx();
-        llcx.array_type(llcx.int_type(8), account_address::AccountAddress::LENGTH)
+        llcx.array_type(llcx.int_type(8), self.address_length)
     }
 
     pub fn get_llvm_type_for_signer(&self) -> llvm::Type {
@@ -238,7 +242,7 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
             }
             mty::Type::TypeParameter(_) => "type_parameter".to_string(),
             other => {
-                panic!("no name strategy for Move-type {other:?}");
+                unreachable!("no name strategy for Move-type {other:?}");
             }
         }
     }
@@ -259,7 +263,8 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
             Type::Vector(_) => TypeDesc::Vector as u64,
             Type::Struct(_, _, _) => TypeDesc::Struct as u64,
             Type::TypeParameter(_) => 14,
-            _ => todo!("{:?}", mty),
+            // `check_types` rejects the types only specifications have before translation.
+            _ => unreachable!("no type descriptor for {mty:?}"),
         }
     }
 
@@ -295,10 +300,10 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
                         | Type::Struct(_, _, _) => {
                             self.define_type_info_global_vec(&symbol_name, elt_ty)
                         }
-                        _ => todo!("{:?}", mty),
+                        _ => unreachable!("no type info for {mty:?}"),
                     },
                     Type::Struct(_, _, _) => self.define_type_info_global_struct(&symbol_name, mty),
-                    _ => todo!("{:?}", mty),
+                    _ => unreachable!("no type info for {mty:?}"),
                 }
             }
         }
@@ -448,12 +453,14 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
         //     pub field_array_len: u64,
         //     pub size: u64,
         //     pub alignment: u64,
+        //     pub abilities: u64,
         //   }
         let ll_struct_type_info_ty = llcx.get_anonymous_struct_type(&[
             llcx.ptr_type(),
             ll_int64_ty,
             ll_int64_ty,
             ll_int64_ty,
+            ll_int64_ty,
         ]);
         let ll_struct_type_info = llmod.add_global(ll_struct_type_info_ty, symbol_name);
         ll_struct_type_info.set_constant();
@@ -464,9 +471,20 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
         let fld_array_len = llvm::Constant::int(ll_int64_ty, U256::from(ll_fld_count as u64));
         let struct_size = llvm::Constant::int(ll_int64_ty, U256::from(ll_struct_size as u64));
         let elt_align = llvm::Constant::int(ll_int64_ty, U256::from(ll_struct_align as u64));
-
-        let ll_struct_type_info_literal =
-            llcx.const_struct(&[ll_fld_array.ptr(), fld_array_len, struct_size, elt_align]);
+        // The declared abilities, which the runtime checks before global storage operations.
+        // The bit layout of `AbilitySet` matches the runtime `ABILITY_*` constants.
+        let abilities = llvm::Constant::int(
+            ll_int64_ty,
+            U256::from(s_env.get_abilities().into_u8() as u64),
+        );
+
+        let ll_struct_type_info_literal = llcx.const_struct(&[
+            ll_fld_array.ptr(),
+            fld_array_len,
+            struct_size,
+            elt_align,
+            abilities,
+        ]);
         ll_struct_type_info.set_initializer(ll_struct_type_info_literal);
         ll_struct_type_info
     }
@@ -500,7 +518,7 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
             ) => false,
             Type::TypeParameter(_) => false,
             Type::Vector(_) | Type::Struct(_, _, _) => true,
-            _ => todo!(),
+            _ => unreachable!("no type info for {mty:?}"),
         }
     }
 
@@ -515,7 +533,7 @@ impl<'mm, 'up> RttyContext<'mm, 'up> {
             Type::Vector(_) | Type::Struct(_, _, _) | Type::TypeParameter(_) => {
                 mty.sanitized_display_name(&tdc)
             }
-            _ => todo!(),
+            _ => unreachable!("no type info for {mty:?}"),
         };
 
         format!("__move_rttydesc_{name}_info")
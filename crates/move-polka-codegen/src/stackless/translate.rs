@@ -29,21 +29,53 @@
 //!
 //! In general though this compiler does not need to be efficient at compile time -
 //! we can clone things when it makes managing lifetimes easier.
+//!
+//! # Memory model
+//!
+//! Every local, including every temporary of the stackless bytecode, is an
+//! alloca in the function's entry block. A local of reference type holds a
+//! plain pointer to the referent, whatever the reference was borrowed from:
+//!
+//! - `BorrowLoc` stores the address of the borrowed local's alloca.
+//! - `BorrowField` stores a GEP into the struct the source reference points to.
+//! - `vector::borrow` and `vector::borrow_mut` store the address of the element
+//!   inside the vector's heap buffer, returned by the runtime.
+//! - `BorrowGlobal` stores the address of a heap box the runtime deserializes
+//!   the global into. The matching `Release` serializes the box back to storage
+//!   when the borrow ends, so writes through a `&mut` global reach storage.
+//!
+//! `ReadRef` and `WriteRef` therefore only load the pointer and then load or store
+//! through it; they never need to know where the reference came from. `ReadRef` of
+//! a vector copies the buffer, like copying a vector local, so the copy does not
+//! alias the referent; nested vectors are copied as well. `ReadRef` of a struct is
+//! shallow, like `Assign(Copy)` of a struct local: a vector field of the copy still
+//! shares its buffer with the referent, so writing to the field through one of them
+//! is visible through the other. `WriteRef` overwrites the referent in place, so a reference
+//! must not outlive the storage it points to: vector element references are
+//! invalidated by operations that grow the vector, which the Move borrow checker
+//! already rules out.
 
 use crate::{
-    options::Options,
+    instrument::{
+        CfgBlock, CoverageCounter, FunctionCfg, Instrumentation, COVERAGE_COUNTERS_SYMBOL,
+        COVERAGE_LEN_SYMBOL,
+    },
+    options::CodegenOptions,
     stackless::{
-        dwarf::DIContext, extensions::*, llvm, module_context::ModuleContext,
-        rttydesc::RttyContext, Constant, Global,
+        dwarf::DIContext,
+        export::{ExportPolicy, ExportRegistry},
+        extensions::*,
+        llvm,
+        module_context::{constant_name, ModuleContext},
+        reachability::cross_module_callees,
+        rttydesc::RttyContext,
+        Constant, Global,
     },
 };
 use codespan::Location;
 use llvm_sys::core::LLVMGetModuleContext;
-use log::{debug, trace};
 use move_core_types::{
-    account_address::{self, AccountAddress},
-    u256::U256,
-    vm_status::StatusCode::ARITHMETIC_ERROR,
+    account_address::AccountAddress, u256::U256, vm_status::StatusCode::ARITHMETIC_ERROR,
 };
 use move_model::{
     ast::{self as mast, Address},
@@ -51,14 +83,21 @@ use move_model::{
     ty::{self as mty, Type},
 };
 use move_stackless_bytecode::{
-    function_target::FunctionData, stackless_bytecode as sbc,
-    stackless_bytecode_generator::StacklessBytecodeGenerator,
-    stackless_control_flow_graph::generate_cfg_in_dot_format,
+    function_target::{FunctionData, FunctionTarget},
+    stackless_bytecode as sbc,
+    stackless_control_flow_graph::{
+        generate_cfg_in_dot_format, BlockContent, StacklessControlFlowGraph,
+    },
 };
 use num::BigUint;
 use num_traits::ToBytes;
 use sha2::Digest;
-use std::collections::BTreeMap;
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+use tracing::{debug, trace};
 
 #[derive(Copy, Clone)]
 pub enum TargetPlatform {
@@ -99,6 +138,10 @@ pub struct GlobalContext<'up> {
     target: TargetPlatform,
     target_machine: &'up llvm::TargetMachine,
     pub di_context: DIContext,
+    /// See [`cross_module_callees`], computed for the first module translated.
+    cross_module_callees: OnceCell<BTreeSet<mm::QualifiedId<mm::FunId>>>,
+    /// The exported and dispatched functions of all modules translated so far.
+    pub exports: ExportRegistry,
 }
 
 impl<'up> GlobalContext<'up> {
@@ -107,22 +150,11 @@ impl<'up> GlobalContext<'up> {
         target: TargetPlatform,
         target_machine: &'up llvm::TargetMachine,
     ) -> GlobalContext<'up> {
-        // Sanity/consistency check that the world was built with the target platform's account
-        // address size. The various Move components we depend on, this compiler, and the native
-        // runtime must all agree on the account length, otherwise bizarre behavior occurs.
-        //
-        // Now ideally we would just reference move_native::target_defs::ACCOUNT_ADDRESS_LENGTH
-        // instead of hardcoding 32 below. Unfortunately, that is not currently possible because
-        // move-native is built two different ways. For the runtime scenario, it is built with
-        // the "solana" feature by the platform tools and therefore gets the proper target_defs
-        // (e.g., account address length).
-        //
-        // On the other hand, when it is built for move-to-solana, it uses the Move-blessed
-        // Rust version. That would ordinarily be fine except that we can't enable feature "solana"
-        // with that toolchain (and recall, we need feature "solana" to get the proper target_defs
-        // compiled in). The move-native crate is no_std, so it interferes with std on the compiler
-        // build (e.g,, duplicate panic_impl). Also, in the "solana" config, the crate requires
-        // feature(default_alloc_error_handler) which is rejected by the Move-blessed Rust.
+        // The Move components we depend on, this compiler, and the native runtime must all
+        // agree on the account address length, otherwise bizarre behavior occurs. The Move
+        // model always uses 32 byte addresses; the length of the generated code is
+        // `CodegenOptions::address_length`, which the driver matches with the runtime variant
+        // it links, and `check_module` rejects addresses that don't fit.
 
         trace!(target: "globalenv", "{env:#?}");
 
@@ -132,15 +164,27 @@ impl<'up> GlobalContext<'up> {
             target,
             target_machine,
             di_context: DIContext::new(),
+            cross_module_callees: OnceCell::new(),
+            exports: ExportRegistry::default(),
         }
     }
 
+    /// An empty module for the target, for code outside any Move module such as the
+    /// coverage counters.
+    pub fn create_target_module(&self, name: &str) -> llvm::Module {
+        let llmod = self.llvm_cx.create_module(name);
+        llmod.set_target(self.target.triple());
+        llmod.set_data_layout(self.target_machine);
+        llmod
+    }
+
     pub fn create_module_context<'this: 'up>(
         &'this self,
         id: mm::ModuleId,
         llmod: &'this llvm::Module,
-        options: &'this Options,
+        options: &'this CodegenOptions,
         source: &'this str,
+        reachable_functions: Option<&'this BTreeSet<mm::QualifiedId<mm::FunId>>>,
     ) -> ModuleContext<'up, 'this> {
         let Self { env, llvm_cx, .. } = self;
 
@@ -150,7 +194,7 @@ impl<'up> GlobalContext<'up> {
         // DIBuilder does not depend on Builder and can be created first
         let llvm_di_builder = llvm_cx.create_di_builder(self, llmod, source, options.debug);
         let llvm_builder = llvm_cx.create_builder();
-        let rtty_cx = RttyContext::new(self.env, &self.llvm_cx, llmod);
+        let rtty_cx = RttyContext::new(self.env, &self.llvm_cx, llmod, options.address_length);
         ModuleContext {
             env: self.env.get_module(id),
             llvm_cx: &self.llvm_cx,
@@ -159,12 +203,21 @@ impl<'up> GlobalContext<'up> {
             llvm_di_builder,
             fn_decls: BTreeMap::new(),
             fn_is_entry: BTreeMap::new(),
+            fn_entry_ids: BTreeMap::new(),
             expanded_functions: Vec::new(),
+            function_data: BTreeMap::new(),
+            stackless_time: Duration::ZERO,
             target: self.target,
             target_machine: self.target_machine,
             options,
             rtty_cx,
             source,
+            reachable_functions,
+            cross_module_callees: self
+                .cross_module_callees
+                .get_or_init(|| cross_module_callees(env)),
+            constant_globals: RefCell::new(BTreeMap::new()),
+            exports: &self.exports,
         }
     }
 }
@@ -213,10 +266,12 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         self.env.module_env.env
     }
 
-    pub fn translate(mut self) {
-        let fn_data = StacklessBytecodeGenerator::new(&self.env).generate_function();
+    pub fn translate(mut self, instrumentation: &mut Instrumentation) -> anyhow::Result<()> {
+        // Every expanded function was walked, and its bytecode generated, while declaring.
+        let module_cx = self.module_cx;
+        let fn_data = &module_cx.function_data[&self.env.get_qualified_id()];
         let func_target =
-            move_stackless_bytecode::function_target::FunctionTarget::new(&self.env, &fn_data);
+            move_stackless_bytecode::function_target::FunctionTarget::new(&self.env, fn_data);
         debug!(target: "sbc", "\n{func_target}");
 
         let g_env = self.get_global_env();
@@ -227,27 +282,17 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
             .collect();
         trace!(target: "nodes", "\n{:#?}", &map_node_to_type);
 
-        // Write the control flow graph to a .dot file for viewing.
-        let options = &self.module_cx.options;
-        let action = (*options.gen_dot_cfg).to_owned();
-        if action == "write" || action == "view" {
-            let fname = &self.env.llvm_symbol_name(self.type_params);
-            let dot_graph = generate_cfg_in_dot_format(&func_target, true);
-            let graph_label = format!("digraph {{ label=\"Function: {fname}\"\n");
-            let dgraph2 = dot_graph.replacen("digraph {", &graph_label, 1);
-            let output_path = (*options.dot_file_path).to_owned();
-            let path_sep = match &*output_path {
-                "" => "",
-                _ => "/",
-            };
-            let dot_file = format!("{output_path}{path_sep}{fname}_cfg.dot");
-            std::fs::write(&dot_file, dgraph2).expect("generating dot file for CFG");
-            // If requested by user, also invoke the xdot viewer.
-            if action == "view" {
-                std::process::Command::new("xdot")
-                    .arg(dot_file)
-                    .status()
-                    .expect("failed to execute 'xdot'");
+        if self.module_cx.options.collect_cfgs {
+            // Instances of a generic function share their bytecode, record its graph once.
+            let function = self.env.get_full_name_str();
+            if !instrumentation
+                .cfgs
+                .iter()
+                .any(|cfg| cfg.function == function)
+            {
+                instrumentation
+                    .cfgs
+                    .push(function_cfg(function, &func_target));
             }
         }
 
@@ -277,7 +322,7 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
 
         // Collect some local names from various structure field references.
         let mut named_locals = BTreeMap::new();
-        self.collect_local_names(&fn_data, &mut named_locals);
+        self.collect_local_names(fn_data, &mut named_locals);
 
         // Declare all the locals as allocas
         {
@@ -355,16 +400,141 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         }
 
         let di_func = self.module_cx.llvm_di_builder.create_function(&self, None);
+        self.module_cx
+            .llvm_di_builder
+            .create_scopes_and_locals(&self, &fn_data.code);
+
+        if self.module_cx.options.stack_traces {
+            self.emit_trace_enter(instrumentation);
+        }
+
+        let instrument = self.module_cx.options.coverage;
+        if instrument {
+            if ExportPolicy::of(&self.env, self.module_cx.options).is_external() {
+                self.emit_coverage_register();
+            }
+            self.emit_coverage_counter(instrumentation, &self.env.get_loc());
+        }
+
+        if self.module_cx.options.heap_stats
+            && ExportPolicy::of(&self.env, self.module_cx.options).is_external()
+        {
+            self.module_cx
+                .emit_rtcall_with_retval(RtCall::HeapStatsRegister);
+        }
 
         // Translate instructions
-        for instr in &fn_data.code {
+        for (offset, instr) in fn_data.code.iter().enumerate() {
+            self.module_cx.llvm_di_builder.enter_code_offset(offset);
             self.translate_instruction(instr);
+            if let (true, sbc::Bytecode::Label(attr_id, _)) = (instrument, instr) {
+                let loc = self
+                    .env
+                    .get_bytecode_loc(attr_id.as_usize() as u16)
+                    .unwrap_or_else(|| self.env.get_loc());
+                self.emit_coverage_counter(instrumentation, &loc);
+            }
         }
 
         self.module_cx
             .llvm_di_builder
             .finalize_function(&self, di_func);
-        ll_fn.verify(self.module_cx);
+        ll_fn.verify(self.module_cx)
+    }
+
+    /// Push this function onto the shadow stack of a `--stack-traces` build.
+    fn emit_trace_enter(&self, instrumentation: &mut Instrumentation) {
+        let mut function = self.env.get_full_name_str();
+        if !self.type_params.is_empty() {
+            let tdc = self.env.get_type_display_ctx();
+            let tys = self
+                .type_params
+                .iter()
+                .map(|ty| ty.display(&tdc).to_string())
+                .collect::<Vec<_>>();
+            function = format!("{function}<{}>", tys.join(", "));
+        }
+        let function = instrumentation.push_traced_function(function);
+
+        let llcx = self.module_cx.llvm_cx;
+        let builder = &self.module_cx.llvm_builder;
+        let i32_ty = llcx.int_type(32);
+        let level = Constant::const_int(i32_ty, 0, 0).as_any_value();
+        let return_address = builder.build_intrinsic_call(
+            self.module_cx.llvm_module,
+            "llvm.returnaddress",
+            &[],
+            &[level],
+            "return_address",
+        );
+        let caller_pc = builder.build_pointer_to_int(return_address, i32_ty, "caller_pc");
+        let function = Constant::const_int(i32_ty, function as u64, 0).as_any_value();
+        self.module_cx
+            .emit_rtcall_with_retval(RtCall::TraceEnter(function, caller_pc));
+    }
+
+    /// Pop the shadow stack before returning, or hand it to the host for `print_stack_trace()`.
+    fn emit_trace(&self, rtcall: RtCall) {
+        if self.module_cx.options.stack_traces {
+            self.module_cx.emit_rtcall_with_retval(rtcall);
+        }
+    }
+
+    /// Validate the reference in `ref_idx` before it is dereferenced, with `--paranoid`.
+    fn emit_ref_check(&self, ref_idx: mast::TempIndex) {
+        if self.module_cx.options.paranoid {
+            let ptr = self.load_reg(ref_idx, "checked_ref");
+            self.module_cx
+                .emit_rtcall_with_retval(RtCall::CheckRef(ptr));
+        }
+    }
+
+    /// Tell the host where the coverage counters are, so it can read them after the call.
+    fn emit_coverage_register(&self) {
+        let llcx = self.module_cx.llvm_cx;
+        let i32_ty = llcx.int_type(32);
+        let counters = self
+            .module_cx
+            .coverage_global(COVERAGE_COUNTERS_SYMBOL, llcx.array_type(i32_ty, 0));
+        let len = self.module_cx.coverage_global(COVERAGE_LEN_SYMBOL, i32_ty);
+        let len = self
+            .module_cx
+            .llvm_builder
+            .load(len.as_any_value(), i32_ty, "coverage_len");
+        self.module_cx
+            .emit_rtcall_with_retval(RtCall::CoverageRegister(counters.as_any_value(), len));
+    }
+
+    /// Add a counter for the block at the builder position, attributed to `loc`,
+    /// and increment it.
+    fn emit_coverage_counter(&self, instrumentation: &mut Instrumentation, loc: &mm::Loc) {
+        let (file, location) = self
+            .get_global_env()
+            .get_file_and_location(loc)
+            .unwrap_or(("unknown".to_string(), Location::new(0, 0)));
+        let index = instrumentation.push_coverage_counter(CoverageCounter {
+            function: self.env.get_full_name_str(),
+            file,
+            line: location.line.0 + 1,
+        });
+
+        let llcx = self.module_cx.llvm_cx;
+        let builder = &self.module_cx.llvm_builder;
+        let i32_ty = llcx.int_type(32);
+        let counters = self
+            .module_cx
+            .coverage_global(COVERAGE_COUNTERS_SYMBOL, llcx.array_type(i32_ty, 0));
+        let index = Constant::const_int(i32_ty, index as u64, 0).as_any_value();
+        let counter = builder.build_address_with_indices(
+            i32_ty,
+            counters.as_any_value(),
+            &[index],
+            "counter",
+        );
+        let count = builder.load(counter, i32_ty, "count");
+        let one = Constant::const_int(i32_ty, 1, 0).as_any_value();
+        let count = builder.build_binop(llvm_sys::LLVMOpcode::LLVMAdd, count, one, "count");
+        builder.store(count, counter);
     }
 
     fn translate_instruction(&mut self, instr: &sbc::Bytecode) {
@@ -510,30 +680,33 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
             sbc::Bytecode::Call(_, dst, op, src, None) => {
                 self.translate_call(dst, op, src, instr, instr_dbg);
             }
-            sbc::Bytecode::Ret(_, vals) => match vals.len() {
-                0 => {
-                    builder.build_return_void();
-                }
-                1 => {
-                    let idx = vals[0];
-                    let llval = self.locals[idx].llval;
-                    let llty = self.locals[idx].llty;
-                    builder.load_return(llty, llval);
-                }
-                _ => {
-                    // Multiple return values are wrapped in a struct.
-                    let nvals = vals
-                        .iter()
-                        .map(|i| (self.locals[*i].llty, self.locals[*i].llval))
-                        .collect::<Vec<_>>();
-
-                    let ll_fn = self.module_cx.lookup_move_fn_decl(
-                        self.env.get_qualified_inst_id(self.type_params.to_vec()),
-                    );
-                    let ret_ty = ll_fn.llvm_return_type();
-                    builder.load_multi_return(ret_ty, &nvals);
+            sbc::Bytecode::Ret(_, vals) => {
+                self.emit_trace(RtCall::TraceExit);
+                match vals.len() {
+                    0 => {
+                        builder.build_return_void();
+                    }
+                    1 => {
+                        let idx = vals[0];
+                        let llval = self.locals[idx].llval;
+                        let llty = self.locals[idx].llty;
+                        builder.load_return(llty, llval);
+                    }
+                    _ => {
+                        // Multiple return values are wrapped in a struct.
+                        let nvals = vals
+                            .iter()
+                            .map(|i| (self.locals[*i].llty, self.locals[*i].llval))
+                            .collect::<Vec<_>>();
+
+                        let ll_fn = self.module_cx.lookup_move_fn_decl(
+                            self.env.get_qualified_inst_id(self.type_params.to_vec()),
+                        );
+                        let ret_ty = ll_fn.llvm_return_type();
+                        builder.load_multi_return(ret_ty, &nvals);
+                    }
                 }
-            },
+            }
             sbc::Bytecode::Load(_, idx, val) => {
                 let local_llval = self.locals[*idx].llval;
                 let const_llval = self.constant(val, Some(&self.locals[*idx].mty));
@@ -719,7 +892,7 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
 
     fn emit_postcond_for_add(
         &self,
-        _args: &[Option<(mast::TempIndex, llvm::AnyValue)>], // src0, src1, dst.
+        args: &[Option<(mast::TempIndex, llvm::AnyValue)>], // src0, src1, dst.
     ) {
         // Generate the following LLVM IR to check that unsigned addition did not overflow.
         // This is indicated when the unsigned sum is less than the first input.
@@ -734,16 +907,16 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         //
 
         // Generate the overflow check compare.
-        // let src0 = args[0].unwrap();
-        // let dst = args[2].unwrap();
-        // let cond_reg = self.module_cx.llvm_builder.build_compare(
-        //     llvm::LLVMIntPredicate::LLVMIntULT,
-        //     dst.1,
-        //     src0.1,
-        //     "ovfcond",
-        // );
-        //
-        // self.emit_prepost_new_blocks_with_abort(cond_reg);
+        let src0 = args[0].unwrap();
+        let dst = args[2].unwrap();
+        let cond_reg = self.module_cx.llvm_builder.build_compare(
+            llvm::LLVMIntPredicate::LLVMIntULT,
+            dst.1,
+            src0.1,
+            "ovfcond",
+        );
+
+        self.emit_prepost_new_blocks_with_abort(cond_reg);
     }
 
     fn emit_postcond_for_sub(
@@ -827,8 +1000,6 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         self.emit_prepost_new_blocks_with_abort(cond_reg);
     }
 
-    // TODO this can probably be removed, but good for reference
-    #[allow(dead_code)]
     fn translate_address_comparison_impl(
         &self,
         dst: &[mast::TempIndex],
@@ -840,8 +1011,8 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         // Note that only eq/ne apply to these.
         //
         // The incoming sources are allocas or global values of array type [N x i8],
-        // where N = account_address::AccountAddress::LENGTH (typically 16, 20, or 32 bytes,
-        // according to target platform/chain). Use memcmp to do the comparison.
+        // where N = `CodegenOptions::address_length` (20 or 32 bytes, according to the
+        // target chain). Use memcmp to do the comparison.
         //    ...
         //    %t = call i32 @memcmp(ptr %local_0, ptr %local_1, i64 N)
         //    %{eq,ne}_dst = icmp {eq,ne} %t, 0
@@ -862,8 +1033,11 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
             src_mty
         };
         assert!(cmp_mty.is_signer_or_address());
+        assert!(
+            pred == llvm::LLVMIntPredicate::LLVMIntEQ || pred == llvm::LLVMIntPredicate::LLVMIntNE
+        );
 
-        let num_elts = account_address::AccountAddress::LENGTH as u64;
+        let num_elts = self.module_cx.options.address_length as u64;
         let builder = &self.module_cx.llvm_builder;
         let llcx = &self.module_cx.llvm_cx;
         let memcmp = self
@@ -1077,7 +1251,8 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         let mut src1_reg = self.load_reg(src[1], &format!("{name}_src_1"));
 
         // Emit any dynamic pre-condition checking code.
-        if dyncheck_emitter_fn.1 == EmitterFnKind::PreCheck {
+        let checked = !self.module_cx.options.assume_no_abort;
+        if checked && dyncheck_emitter_fn.1 == EmitterFnKind::PreCheck {
             let args = [Some((src[0], src0_reg)), Some((src[1], src1_reg)), None];
             dyncheck_emitter_fn.0(self, &args);
         }
@@ -1105,7 +1280,7 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                 .build_binop(op, src0_reg, src1_reg, &format!("{name}_dst"));
 
         // Emit any dynamic post-condition checking code.
-        if dyncheck_emitter_fn.1 == EmitterFnKind::PostCheck {
+        if checked && dyncheck_emitter_fn.1 == EmitterFnKind::PostCheck {
             let args = [Some((src[0], src0_reg)), None, Some((dst[0], dst_reg))];
             dyncheck_emitter_fn.0(self, &args);
         }
@@ -1133,7 +1308,7 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         //
 
         // This check only needs to be emitted with the source type is larger than the dest type.
-        if src_width <= dst_width {
+        if src_width <= dst_width || self.module_cx.options.assume_no_abort {
             return;
         }
         assert!(dst_width <= 128);
@@ -1187,6 +1362,111 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         self.store_reg(dst[0], dst_reg);
     }
 
+    /// Lower `op` on `u256` operands to a call of a runtime helper, for
+    /// [`CodegenOptions::u256_helpers`]. The helpers take the operands by pointer: the allocas
+    /// of the locals, or the referenced values. Returns false if `op` doesn't involve `u256`
+    /// values or is translated inline anyway, like bitwise operations and shifts, which LLVM
+    /// expands on any target.
+    fn translate_u256_op(
+        &self,
+        dst: &[mast::TempIndex],
+        op: &sbc::Operation,
+        src: &[mast::TempIndex],
+    ) -> bool {
+        use sbc::Operation;
+        let is_u256 = |idx: &mast::TempIndex| {
+            matches!(
+                self.locals[*idx].mty.skip_reference(),
+                mty::Type::Primitive(mty::PrimitiveType::U256)
+            )
+        };
+        if !src.iter().chain(dst).any(is_u256) {
+            return false;
+        }
+        let ptr = |idx: mast::TempIndex| {
+            if self.locals[idx].mty.is_reference() {
+                self.load_reg(idx, "u256_ref")
+            } else {
+                self.locals[idx].llval.as_any_value()
+            }
+        };
+        let len = |idx: mast::TempIndex| {
+            let bytes = self.locals[idx].mty.get_bitwidth() / 8;
+            llvm::Constant::int(self.module_cx.llvm_cx.int_type(32), U256::from(bytes))
+                .as_any_value()
+        };
+        let call = |helper: U256Helper, args: Vec<llvm::AnyValue>| {
+            self.module_cx
+                .emit_rtcall_with_retval(RtCall::U256(helper, args))
+        };
+        let checked = !self.module_cx.options.assume_no_abort;
+        let overflow_checks = checked && self.module_cx.options.overflow_checks;
+        match op {
+            Operation::Add | Operation::Sub | Operation::Mul | Operation::Div | Operation::Mod => {
+                // the helpers return whether the operation overflowed or divided by zero
+                let (helper, checked) = match op {
+                    Operation::Add => (U256Helper::Add, overflow_checks),
+                    Operation::Sub => (U256Helper::Sub, overflow_checks),
+                    Operation::Mul => (U256Helper::Mul, overflow_checks),
+                    Operation::Div => (U256Helper::Div, checked),
+                    _ => (U256Helper::Mod, checked),
+                };
+                let failed = call(helper, vec![ptr(src[0]), ptr(src[1]), ptr(dst[0])]);
+                if checked {
+                    self.emit_prepost_new_blocks_with_abort(failed);
+                }
+            }
+            Operation::Lt
+            | Operation::Gt
+            | Operation::Le
+            | Operation::Ge
+            | Operation::Eq
+            | Operation::Neq => {
+                // cmp256 returns -1, 0 or 1, compare that to zero
+                let pred = match op {
+                    Operation::Lt => llvm::LLVMIntPredicate::LLVMIntSLT,
+                    Operation::Gt => llvm::LLVMIntPredicate::LLVMIntSGT,
+                    Operation::Le => llvm::LLVMIntPredicate::LLVMIntSLE,
+                    Operation::Ge => llvm::LLVMIntPredicate::LLVMIntSGE,
+                    Operation::Eq => llvm::LLVMIntPredicate::LLVMIntEQ,
+                    _ => llvm::LLVMIntPredicate::LLVMIntNE,
+                };
+                let ordering = call(U256Helper::Cmp, vec![ptr(src[0]), ptr(src[1])]);
+                let zero = llvm::Constant::get_const_null(self.module_cx.llvm_cx.int_type(32))
+                    .as_any_value();
+                let dst_reg =
+                    self.module_cx
+                        .llvm_builder
+                        .build_compare(pred, ordering, zero, "cmp256_dst");
+                self.store_reg(dst[0], dst_reg);
+            }
+            Operation::CastU8
+            | Operation::CastU16
+            | Operation::CastU32
+            | Operation::CastU64
+            | Operation::CastU128
+            | Operation::CastU256 => {
+                if is_u256(&src[0]) {
+                    // trunc256 returns whether the value doesn't fit the destination
+                    let failed = call(
+                        U256Helper::Trunc,
+                        vec![ptr(src[0]), ptr(dst[0]), len(dst[0])],
+                    );
+                    if checked {
+                        self.emit_prepost_new_blocks_with_abort(failed);
+                    }
+                } else {
+                    call(
+                        U256Helper::Zext,
+                        vec![ptr(src[0]), ptr(dst[0]), len(src[0])],
+                    );
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
     fn translate_call(
         &self,
         dst: &[mast::TempIndex],
@@ -1200,6 +1480,9 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         let builder = &self.module_cx.llvm_builder;
         let di_builder = &self.module_cx.llvm_di_builder;
         trace!(target: "dwarf", "translate_call op {op:#?} dst {dst:#?} src {src:#?}");
+        if self.module_cx.options.u256_helpers && self.translate_u256_op(dst, op, src) {
+            return;
+        }
         match op {
             Operation::Function(mod_id, fun_id, types) => {
                 let types = mty::Type::instantiate_vec(types.to_vec(), self.type_params);
@@ -1264,6 +1547,7 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                 let types = mty::Type::instantiate_vec(types.to_vec(), self.type_params);
                 assert_eq!(src.len(), 1);
                 assert_eq!(dst.len(), 1);
+                self.emit_ref_check(src[0]);
                 let src_llval = self.locals[src[0]].llval;
                 let dst_llval = self.locals[dst[0]].llval;
                 let struct_env = self
@@ -1397,6 +1681,25 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                     }
                 }
             }
+            Operation::Vector => {
+                // Vector literals with non-constant elements, e.g. `vector[a, b, c]`.
+                // Start from an empty vector and move each element into it.
+                assert_eq!(dst.len(), 1);
+                let dst_idx = dst[0];
+                let elt_mty = self.locals[dst_idx].mty.vector_element_type();
+                let dst_llval = self.locals[dst_idx].llval;
+                let res_val = self
+                    .module_cx
+                    .emit_rtcall_with_retval(RtCall::VecEmpty(elt_mty.clone()));
+                builder.build_store(res_val, dst_llval);
+                for src_idx in src {
+                    self.module_cx.emit_rtcall_with_retval(RtCall::VecPushBack(
+                        dst_llval.as_any_value(),
+                        self.locals[*src_idx].llval.as_any_value(),
+                        elt_mty.clone(),
+                    ));
+                }
+            }
             Operation::Drop => {
                 debug!(target: "dwarf", "translate_call Release dst {dst:#?} src {src:#?}");
             }
@@ -1405,10 +1708,22 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                 assert_eq!(dst.len(), 1);
                 let src_idx = src[0];
                 let dst_idx = dst[0];
+                self.emit_ref_check(src_idx);
                 let dst_llty = self.locals[dst_idx].llty;
                 let src_llval = self.locals[src_idx].llval;
                 let dst_llval = self.locals[dst_idx].llval;
-                builder.load_deref_store(dst_llty, src_llval, dst_llval);
+                if let mty::Type::Vector(elt_mty) = &self.locals[dst_idx].mty {
+                    // Reading a vector through a reference copies it, like `Assign(Copy)`.
+                    // Structs are copied shallowly, vector fields included; see the module docs.
+                    let src_ptr = self.load_reg(src_idx, "read_ref_vec");
+                    self.module_cx.emit_rtcall_with_retval(RtCall::VecCopy(
+                        dst_llval.as_any_value(),
+                        src_ptr,
+                        (**elt_mty).clone(),
+                    ));
+                } else {
+                    builder.load_deref_store(dst_llty, src_llval, dst_llval);
+                }
             }
             Operation::WriteRef => {
                 // nb: both operands are from the "src" vector.
@@ -1419,6 +1734,7 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                 assert_eq!(dst.len(), 0);
                 let src_idx = src[1];
                 let dst_idx = src[0];
+                self.emit_ref_check(dst_idx);
                 let src_llty = self.locals[src_idx].llty;
                 let src_llval = self.locals[src_idx].llval;
                 let dst_llval = self.locals[dst_idx].llval;
@@ -1435,21 +1751,43 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                 builder.load_store(src_llty, src_llval, dst_llval);
             }
             Operation::Add => {
+                let postcond: CheckEmitterFn = if self.module_cx.options.overflow_checks {
+                    (Self::emit_postcond_for_add, EmitterFnKind::PostCheck)
+                } else {
+                    emitter_nop
+                };
                 self.translate_arithm_impl(
                     dst,
                     src,
                     "add",
                     llvm_sys::LLVMOpcode::LLVMAdd,
-                    (Self::emit_postcond_for_add, EmitterFnKind::PostCheck),
+                    postcond,
                 );
             }
             Operation::Sub => {
+                let postcond: CheckEmitterFn = if self.module_cx.options.overflow_checks {
+                    (Self::emit_postcond_for_sub, EmitterFnKind::PostCheck)
+                } else {
+                    emitter_nop
+                };
                 self.translate_arithm_impl(
                     dst,
                     src,
                     "sub",
                     llvm_sys::LLVMOpcode::LLVMSub,
-                    (Self::emit_postcond_for_sub, EmitterFnKind::PostCheck),
+                    postcond,
+                );
+            }
+            Operation::Mul
+                if !self.module_cx.options.overflow_checks
+                    || self.module_cx.options.assume_no_abort =>
+            {
+                self.translate_arithm_impl(
+                    dst,
+                    src,
+                    "mul",
+                    llvm_sys::LLVMOpcode::LLVMMul,
+                    emitter_nop,
                 );
             }
             Operation::Mul => {
@@ -1671,6 +2009,88 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         builder.position_at_end(cont_bb);
     }
 
+    /// Calls to the natives of the standard library's `debug` module, at the
+    /// [`CodegenOptions::stdlib_address`], are lowered to the `move_rt_assert` and
+    /// `move_rt_log` runtime calls, or dropped entirely when compiling with
+    /// `--strip-debug-natives`. The print natives of every stdlib flavor, `print<T>(x: &T)`
+    /// and Aptos' `native_print(x: String)`, all call `move_native_debug_print`, and
+    /// `print_stack_trace()` dumps the stack trace of `--stack-traces` builds.
+    /// Returns false if `fn_env` is not one of those natives.
+    fn translate_debug_native_call(
+        &self,
+        fn_env: &mm::FunctionEnv,
+        types: &[mty::Type],
+        src: &[mast::TempIndex],
+    ) -> bool {
+        let fn_name = fn_env.get_name_str();
+        let debug_module = format!(
+            "{}::debug",
+            self.module_cx.options.stdlib_address.to_hex_literal()
+        );
+        if fn_env.module_env.get_full_name_str() != debug_module
+            || !matches!(
+                fn_name.as_str(),
+                "print" | "native_print" | "print_stack_trace" | "hex_dump" | "log" | "assert"
+            )
+        {
+            return false;
+        }
+        if self.module_cx.options.strip_debug_natives {
+            debug!(target: "functions", "stripping call to debug::{fn_name}");
+            return true;
+        }
+        let llcx = self.module_cx.llvm_cx;
+        let builder = &self.module_cx.llvm_builder;
+        match fn_name.as_str() {
+            "assert" => {
+                // assert(cond: bool, code: u64)
+                assert_eq!(src.len(), 2);
+                let cond = self.load_reg(src[0], "cond");
+                let code = self.load_reg(src[1], "code");
+                self.module_cx
+                    .emit_rtcall_with_retval(RtCall::Assert(cond, code));
+                true
+            }
+            "log" => {
+                // log(level: u8, msg: &vector<u8>)
+                assert_eq!(src.len(), 2);
+                let level = self.load_reg(src[0], "level");
+                let level = builder.build_zext(level, llcx.int_type(32), "level");
+                let msg_ref = self.load_reg(src[1], "msg");
+                let vec_ty = self
+                    .module_cx
+                    .rtty_cx
+                    .get_llvm_type_for_move_native_vector();
+                let msg = builder.build_load_from_valref(vec_ty, msg_ref, "msg_vec");
+                let msg_ptr = builder.build_extract_value(msg, 0, "msg_ptr");
+                // { ptr, capacity, length }
+                let msg_len = builder.build_extract_value(msg, 2, "msg_len");
+                self.module_cx
+                    .emit_rtcall_with_retval(RtCall::Log(level, msg_ptr, msg_len));
+                true
+            }
+            "print" | "native_print" => {
+                // print<T>(x: &T) or native_print(x: String)
+                assert_eq!(src.len(), 1);
+                let (value, mty) = match &fn_env.get_parameter_types()[0] {
+                    mty::Type::Reference(_, mty) => (self.load_reg(src[0], "value"), &**mty),
+                    mty => (self.locals[src[0]].llval.as_any_value(), mty),
+                };
+                let mty = mty.instantiate(types);
+                self.module_cx
+                    .emit_rtcall_with_retval(RtCall::DebugPrint(value, mty));
+                true
+            }
+            "print_stack_trace" => {
+                // print_stack_trace()
+                self.emit_trace(RtCall::TraceDump);
+                true
+            }
+            // `hex_dump` keeps calling its native implementation.
+            _ => false,
+        }
+    }
+
     /// Translation of calls to native functions.
     ///
     /// Native functions are unlike Move functions in that they
@@ -1712,10 +2132,11 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
             .into_iter()
             .zip(callee_arg_types)
             .map(|(local, callee_arg_type)| {
-                // Pass generic values and vectors by their stack pointer
+                // Pass generic values, vectors and addresses by their stack pointer
                 match callee_arg_type {
                     mty::Type::TypeParameter(_) => local.llval.as_any_value(),
                     mty::Type::Vector(_) => local.llval.as_any_value(),
+                    mty::Type::Primitive(mty::PrimitiveType::Address) => local.llval.as_any_value(),
                     _ => self
                         .module_cx
                         .llvm_builder
@@ -1762,6 +2183,10 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
             let fn_env = global_env.get_function(fn_id);
             if fn_env.is_native() {
                 debug!(target: "functions", "translate_fun_call native function {fn_id:?}");
+                let types = mty::Type::instantiate_vec(types.to_vec(), self.type_params);
+                if self.translate_debug_native_call(&fn_env, &types, src) {
+                    return;
+                }
                 return self.translate_native_fun_call(mod_id, fun_id, types, dst, src, instr);
             }
         }
@@ -1837,96 +2262,48 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
                 let newval = U256::from_str_radix(&as_str, 10).expect("cannot convert to U256");
                 llvm::Constant::int(llcx.int_type(256), newval)
             }
-            Constant::Address(val) => {
-                let addr_len = account_address::AccountAddress::LENGTH;
-
-                // Create a global constant value of type [LENGTH x i8] with this account address
-                // as the contents (in LSB first order).
-                //
-                // The address is a BigUint which only stores as many bits as needed, so pad it out
-                // to the full address length if needed.
-                let mut bytes = val.expect_numerical().to_big_uint().to_bytes_le();
-                bytes.extend(vec![0; addr_len - bytes.len()]);
-                let aval = llcx.const_int_array::<u8>(&bytes).as_const();
-                let gval = self
-                    .module_cx
-                    .llvm_module
-                    .add_global2(aval.llvm_type(), "acct.addr");
-                gval.set_constant();
-                gval.set_internal_linkage();
-                gval.set_initializer(aval);
+            Constant::Address(_) => {
+                let elt_mty = Type::Primitive(PrimitiveType::Address);
+                let gval = self.constant_global(mc, &elt_mty);
                 builder.build_load_global_const(gval)
             }
-            Constant::AddressArray(val_vec) => {
+            Constant::AddressArray(_) => {
                 // This is just like Constant(Vector(_)) below, except that the stackless bytecode
                 // currently treats it specially with Vec<BigUint> instead of Vec<sbc::Constant>.
-                //
-                // Transform `Vec<BigUint>` to `Vec<llvm::Constant>`.
-                // Then create global array value containing the vector literal data.
-                let addr_len = account_address::AccountAddress::LENGTH;
-                let vals: Vec<llvm::Constant> = val_vec
-                    .iter()
-                    .map(|v| {
-                        let mut bytes: Vec<u8> = v.expect_numerical().to_big_uint().to_bytes_le();
-                        bytes.extend(vec![0; addr_len - bytes.len()]);
-                        llcx.const_int_array::<u8>(&bytes).as_const()
-                    })
-                    .collect();
-                let aval =
-                    llcx.const_array(&vals, self.module_cx.rtty_cx.get_llvm_type_for_address());
-
                 let elt_mty = Type::Primitive(PrimitiveType::Address);
+                let vec_descriptor =
+                    self.constant_global(mc, &Type::Vector(Box::new(elt_mty.clone())));
                 let (res_val_type, res_ptr) =
-                    self.make_global_array_and_copy_to_new_vec(aval, &elt_mty);
+                    self.copy_global_array_to_new_vec(vec_descriptor, &elt_mty);
 
                 builder
                     .build_load(res_val_type, res_ptr, "reload")
                     .as_constant()
             }
-            Constant::ByteArray(val_vec) => {
+            Constant::ByteArray(_) => {
                 // Similar to Constant(Vector(_)) below, except that the stackless bytecode
                 // currently treats it specially with Vec<u8> instead of Vec<sbc::Constant>.
-                //
-                // Create global array value containing the vector literal data.
-                let aval = llcx.const_int_array::<u8>(val_vec);
-
                 let elt_mty = Type::Primitive(PrimitiveType::U8);
+                let vec_descriptor =
+                    self.constant_global(mc, &Type::Vector(Box::new(elt_mty.clone())));
                 let (res_val_type, res_ptr) =
-                    self.make_global_array_and_copy_to_new_vec(aval, &elt_mty);
+                    self.copy_global_array_to_new_vec(vec_descriptor, &elt_mty);
 
                 builder
                     .build_load(res_val_type, res_ptr, "reload")
                     .as_constant()
             }
-            Constant::Vector(val_vec) => {
+            Constant::Vector(_) => {
                 // What we'd like to do below is simply match Constant::* on an element of
                 // val_vec. But Move allows an empty vector literal (e.g., let v = vector[]),
                 // so that we may not be able to index an element of the vector. Instead, we
                 // have callers pass in an mty from their context and match on that to indirectly
                 // determine the Constant element type.
-                //
-                // Transform `Vec<sbc::Constant>` to `Vec<llvm::Constant>`.
-                // Then create global array value containing the vector literal data.
                 let vmty = vec_mty.unwrap();
                 let elt_mty = vmty.vector_element_type();
-
-                let aval = match elt_mty {
-                    _ if elt_mty.is_number() || elt_mty.is_bool() => {
-                        let vals = self.rewrap_vec_constant(val_vec);
-                        llcx.const_array(&vals, self.module_cx.to_llvm_type(&elt_mty, &[]).unwrap())
-                    }
-                    Type::Vector(bt) if bt.is_number_u8() => {
-                        // This is a Constant::ByteArray element type.
-                        assert!(matches!(val_vec[0], Constant::ByteArray(_)));
-                        todo!("{:?}", mc);
-                    }
-                    _ => {
-                        todo!("unexpected vec constant: {}: {:#?}", val_vec.len(), val_vec);
-                    }
-                };
-
+                let vec_descriptor = self.constant_global(mc, vmty);
                 let (res_val_type, res_ptr) =
-                    self.make_global_array_and_copy_to_new_vec(aval, &elt_mty);
+                    self.copy_global_array_to_new_vec(vec_descriptor, &elt_mty);
 
                 builder
                     .build_load(res_val_type, res_ptr, "reload")
@@ -1935,6 +2312,106 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         }
     }
 
+    // The read-only global holding the constant `mc` of type `mty`: the bytes of an address,
+    // or the descriptor of the elements of a vector, see `make_vec_literal_descriptor`.
+    //
+    // Every constant is emitted once per module and shared by all functions loading it. It is
+    // named after the Move `const` with that value of the module declaring the function, e.g.
+    // `vault::ADMIN`, so it can be found in the IR and in the symbols of the object, see
+    // `constant_name`.
+    fn constant_global(&self, mc: &sbc::Constant, mty: &mty::Type) -> llvm::Global {
+        let mod_cx = &self.module_cx;
+        let default_name = match mc {
+            sbc::Constant::Address(_) => "acct.addr",
+            _ => "vdesc",
+        };
+        let name = constant_name(&self.env.module_env, mc, mty)
+            .unwrap_or_else(|| default_name.to_string());
+        // keyed by name too, so the name doesn't depend on which function loads it first
+        let key = format!("{name}:{mty:?}={mc:?}");
+        if let Some(gval) = mod_cx.constant_globals.borrow().get(&key) {
+            return *gval;
+        }
+        let init = match mc {
+            sbc::Constant::Address(val) => self.address_constant_bytes(val),
+            _ => self.make_vec_literal_descriptor(self.vec_literal_array(mc, mty)),
+        };
+        let gval = mod_cx.llvm_module.add_global2(init.llvm_type(), &name);
+        gval.set_constant();
+        gval.set_internal_linkage();
+        gval.set_initializer(init);
+        mod_cx.constant_globals.borrow_mut().insert(key, gval);
+        gval
+    }
+
+    // Create the constant array holding the elements of a vector literal of type `vec_mty`.
+    //
+    // Elements which are vectors themselves get their own read-only data, and the array
+    // holds their `MoveUntypedVector` descriptors, so arbitrarily nested literals are
+    // emitted as fully-initialized constants.
+    fn vec_literal_array(&self, mc: &sbc::Constant, vec_mty: &mty::Type) -> llvm::ArrayValue {
+        use mty::{PrimitiveType, Type};
+        use sbc::Constant;
+        let llcx = self.module_cx.llvm_cx;
+        let rtty_cx = &self.module_cx.rtty_cx;
+        let elt_mty = vec_mty.vector_element_type();
+        match mc {
+            Constant::ByteArray(val_vec) => llcx.const_int_array::<u8>(val_vec),
+            Constant::AddressArray(val_vec) => {
+                let vals: Vec<llvm::Constant> = val_vec
+                    .iter()
+                    .map(|v| self.address_constant_bytes(v))
+                    .collect();
+                llcx.const_array(&vals, rtty_cx.get_llvm_type_for_address())
+            }
+            Constant::Vector(val_vec) => match elt_mty {
+                _ if elt_mty.is_number() || elt_mty.is_bool() => {
+                    let vals = self.rewrap_vec_constant(val_vec);
+                    llcx.const_array(&vals, self.module_cx.to_llvm_type(&elt_mty, &[]).unwrap())
+                }
+                Type::Primitive(PrimitiveType::Address) => {
+                    let vals: Vec<llvm::Constant> = val_vec
+                        .iter()
+                        .map(|v| match v {
+                            Constant::Address(addr) => self.address_constant_bytes(addr),
+                            _ => unreachable!("{:?}", v),
+                        })
+                        .collect();
+                    llcx.const_array(&vals, rtty_cx.get_llvm_type_for_address())
+                }
+                Type::Vector(_) => {
+                    let vals: Vec<llvm::Constant> = val_vec
+                        .iter()
+                        .map(|v| {
+                            let inner = self.vec_literal_array(v, &elt_mty);
+                            self.make_vec_literal_descriptor(inner)
+                        })
+                        .collect();
+                    llcx.const_array(&vals, rtty_cx.get_llvm_type_for_move_native_vector())
+                }
+                _ => {
+                    todo!("unexpected vec constant: {}: {:#?}", val_vec.len(), val_vec);
+                }
+            },
+            _ => unreachable!("{:?} is not a vector constant", mc),
+        }
+    }
+
+    // Create a `[LENGTH x i8]` constant with the account address (in LSB first order).
+    //
+    // The address is a BigUint which only stores as many bits as needed, so pad it out
+    // to the full address length if needed. `check_module` rejects addresses that are
+    // longer than a 20 byte target address.
+    fn address_constant_bytes(&self, val: &Address) -> llvm::Constant {
+        let addr_len = self.module_cx.options.address_length;
+        let mut bytes: Vec<u8> = val.expect_numerical().to_big_uint().to_bytes_le();
+        bytes.extend(vec![0; addr_len - bytes.len()]);
+        self.module_cx
+            .llvm_cx
+            .const_int_array::<u8>(&bytes)
+            .as_const()
+    }
+
     // Transform `Vec<sbc::Constant>` to `Vec<llvm::Constant>`.
     fn rewrap_vec_constant(&self, vc: &[sbc::Constant]) -> Vec<llvm::Constant> {
         use sbc::Constant;
@@ -1954,16 +2431,11 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         retvec
     }
 
-    fn make_global_array_and_copy_to_new_vec(
-        &self,
-        aval: llvm::ArrayValue,
-        elt_mty: &mty::Type,
-    ) -> (llvm::Type, llvm::Alloca) {
+    // Create an LLVM global for the array of literal values and return a constant
+    // vector descriptor pointing at it. The format of the descriptor corresponds to
+    // 'move_native::rt_types::MoveUntypedVector'.
+    fn make_vec_literal_descriptor(&self, aval: llvm::ArrayValue) -> llvm::Constant {
         let mod_cx = &self.module_cx;
-        let builder = &mod_cx.llvm_builder;
-        let llcx = &mod_cx.llvm_cx;
-
-        // Create an LLVM global for the array of literal values.
         let raw_vec_data = mod_cx
             .llvm_module
             .add_global2(aval.llvm_type(), "vec_literal");
@@ -1971,21 +2443,23 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
         raw_vec_data.set_internal_linkage();
         raw_vec_data.set_initializer(aval.as_const());
 
-        // Create an LLVM global containing the vector descriptor (to be passed to the
-        // runtime) and initialize it with the array created above. The format of the
-        // descriptor corresponds to 'move_native::rt_types::MoveUntypedVector'
         let vec_len = aval.llvm_type().get_array_length();
-        let vec_descriptor_init = llcx.const_struct(&[
+        mod_cx.llvm_cx.const_struct(&[
             raw_vec_data.ptr(),
             self.constant(&sbc::Constant::U64(vec_len as u64), None),
             self.constant(&sbc::Constant::U64(vec_len as u64), None),
-        ]);
-        let vec_descriptor = mod_cx
-            .llvm_module
-            .add_global2(vec_descriptor_init.llvm_type(), "vdesc");
-        vec_descriptor.set_constant();
-        vec_descriptor.set_internal_linkage();
-        vec_descriptor.set_initializer(vec_descriptor_init);
+        ])
+    }
+
+    // Copy the elements of the constant vector described by `vec_descriptor` into a new
+    // vector, so the constant itself is never written.
+    fn copy_global_array_to_new_vec(
+        &self,
+        vec_descriptor: llvm::Global,
+        elt_mty: &mty::Type,
+    ) -> (llvm::Type, llvm::Alloca) {
+        let mod_cx = &self.module_cx;
+        let builder = &mod_cx.llvm_builder;
 
         // Generate LLVM IR to construct a new empty vector and then copy the global
         // data into the new vector.
@@ -2268,11 +2742,21 @@ impl<'mm, 'up> FunctionContext<'mm, 'up> {
 
 pub enum RtCall {
     Abort(mast::TempIndex),
+    Assert(llvm::AnyValue, llvm::AnyValue),
+    Log(llvm::AnyValue, llvm::AnyValue, llvm::AnyValue),
+    CoverageRegister(llvm::AnyValue, llvm::AnyValue),
+    HeapStatsRegister,
+    TraceEnter(llvm::AnyValue, llvm::AnyValue),
+    TraceExit,
+    TraceDump,
+    DebugPrint(llvm::AnyValue, mty::Type),
+    CheckRef(llvm::AnyValue),
     Deserialize(llvm::AnyValue, llvm::AnyValue),
     VecDestroy(mast::TempIndex, mty::Type),
     VecCopy(llvm::AnyValue, llvm::AnyValue, mty::Type),
     VecCmpEq(llvm::AnyValue, llvm::AnyValue, mty::Type),
     VecEmpty(mty::Type),
+    VecPushBack(llvm::AnyValue, llvm::AnyValue, mty::Type),
     StrCmpEq(
         llvm::AnyValue,
         llvm::AnyValue,
@@ -2285,8 +2769,49 @@ pub enum RtCall {
     BorrowGlobal(llvm::AnyValue, mty::Type, u32),
     Exists(llvm::AnyValue, mty::Type),
     Release(llvm::AnyValue, llvm::AnyValue, mty::Type),
+    U256(U256Helper, Vec<llvm::AnyValue>),
 }
 
+/// Runtime helpers `u256` operations are lowered to with [`CodegenOptions::u256_helpers`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum U256Helper {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Cmp,
+    Zext,
+    Trunc,
+}
+
+impl U256Helper {
+    /// Name of the helper, without the `move_rt_` prefix.
+    pub fn name(self) -> &'static str {
+        match self {
+            U256Helper::Add => "add256",
+            U256Helper::Sub => "sub256",
+            U256Helper::Mul => "mul256",
+            U256Helper::Div => "div256",
+            U256Helper::Mod => "mod256",
+            U256Helper::Cmp => "cmp256",
+            U256Helper::Zext => "zext256",
+            U256Helper::Trunc => "trunc256",
+        }
+    }
+}
+
+/// Function passes run on every module before emission, whatever the optimization level.
+/// The translation keeps each local in an alloca and loads/stores it on every access;
+/// promoting them to registers and folding the leftovers avoids most of that stack traffic.
+/// Calls to `#[inline_always]` functions of the same module are inlined first.
+pub const FUNCTION_PASS_PIPELINE: &str = "always-inline,function(mem2reg,instcombine,simplifycfg)";
+
+/// Run over the linked package module with `--lto`, so calls across Move modules are inlined.
+/// No `globaldce`: the PolkaVM export metadata is only referenced from inline asm.
+pub const LTO_PASS_PIPELINE: &str =
+    "function(mem2reg,instcombine,simplifycfg),cgscc(inline),function(instcombine,simplifycfg)";
+
 /// Compile the module to object file.
 ///
 /// This takes the module by value because it would otherwise have
@@ -2296,7 +2821,47 @@ pub fn write_object_file(
     llmachine: &llvm::TargetMachine,
     outpath: &str,
 ) -> anyhow::Result<()> {
-    llmod.verify();
+    llmod.verify()?;
+    llmod.run_passes(llmachine, FUNCTION_PASS_PIPELINE)?;
     llmachine.emit_to_obj_file(&llmod, outpath)?;
     Ok(())
 }
+
+/// The control flow graph of `func_target`, both as data and as a dot graph.
+fn function_cfg(function: String, func_target: &FunctionTarget) -> FunctionCfg {
+    let code = func_target.get_bytecode();
+    let label_offsets = sbc::Bytecode::label_offsets(code);
+    let cfg = StacklessControlFlowGraph::new_forward(code);
+    let blocks = cfg
+        .blocks()
+        .into_iter()
+        .map(|id| {
+            let instructions = match cfg.content(id) {
+                BlockContent::Basic { lower, upper } => (*lower..=*upper)
+                    .map(|offset| {
+                        code[offset as usize]
+                            .display(func_target, &label_offsets)
+                            .to_string()
+                    })
+                    .collect(),
+                BlockContent::Dummy => vec![],
+            };
+            CfgBlock {
+                id,
+                instructions,
+                successors: cfg.successors(id).clone(),
+            }
+        })
+        .collect();
+    let dot = generate_cfg_in_dot_format(func_target, true).replacen(
+        "digraph {",
+        &format!("digraph {{ label=\"Function: {function}\"\n"),
+        1,
+    );
+    FunctionCfg {
+        function,
+        entry_blocks: cfg.entry_blocks(),
+        blocks,
+        dot,
+    }
+}
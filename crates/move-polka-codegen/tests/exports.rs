@@ -0,0 +1,62 @@
+use move_polka_codegen::stackless::{ExportRegistry, RegisteredExport};
+
+fn export(name: &str, module: &str) -> RegisteredExport {
+    RegisteredExport {
+        name: name.to_string(),
+        module: module.to_string(),
+        symbol: name.replace("::", "_"),
+        signature: None,
+        selector: None,
+    }
+}
+
+#[test]
+pub fn exports_are_registered_once() {
+    let registry = ExportRegistry::default();
+    assert!(registry.register(export("vault::deposit", "a_vault")));
+    // declared again in another module object that calls it
+    let mut again = export("vault::deposit", "b_user");
+    again.signature = Some("s:".to_string());
+    assert!(!registry.register(again));
+
+    let exports = registry.exports();
+    assert_eq!(exports.len(), 1);
+    assert_eq!(exports[0].module, "a_vault");
+    assert_eq!(exports[0].signature, None);
+}
+
+#[test]
+pub fn exports_are_grouped_by_module() {
+    let registry = ExportRegistry::default();
+    for (name, module) in [
+        ("vault::deposit", "a_vault"),
+        ("user::register", "b_user"),
+        ("vault::withdraw", "a_vault"),
+    ] {
+        registry.register(export(name, module));
+    }
+    let by_module = registry.by_module();
+    let names = |module: &str| {
+        by_module[module]
+            .iter()
+            .map(|export| export.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(by_module.len(), 2);
+    assert_eq!(names("a_vault"), ["vault::deposit", "vault::withdraw"]);
+    assert_eq!(names("b_user"), ["user::register"]);
+}
+
+#[test]
+pub fn selector_table_lists_dispatched_functions() {
+    let registry = ExportRegistry::default();
+    registry.register(export("vault::deposit", "a_vault"));
+    registry.register(export("vault::total", "a_vault"));
+    registry.set_selector("vault::deposit", 0x4433_2211);
+    // not registered, so not dispatched
+    registry.set_selector("vault::withdraw", 0x8877_6655);
+
+    let table = registry.selector_table();
+    assert_eq!(table.len(), 1);
+    assert_eq!(table[&0x4433_2211], "vault::deposit");
+}
@@ -0,0 +1,31 @@
+use move_polka_codegen::stackless::{
+    Context, FunctionType, Target, TargetPlatform, FUNCTION_PASS_PIPELINE,
+};
+
+#[test]
+pub fn function_passes_promote_locals_to_registers() -> anyhow::Result<()> {
+    let platform = TargetPlatform::PVM;
+    platform.initialize_llvm();
+    let machine = Target::from_triple(platform.triple())?.create_target_machine(
+        platform.triple(),
+        platform.llvm_cpu(),
+        platform.llvm_features(),
+        "none",
+    );
+    let llcx = Context::new();
+    let llmod = llcx.create_module("pipeline");
+    let i64_ty = llcx.int_type(64);
+    let ll_fn = llmod.add_function("pipeline", "identity", FunctionType::new(i64_ty, &[i64_ty]));
+    let builder = llcx.create_builder();
+    builder.position_at_end(ll_fn.append_basic_block("entry"));
+    // a local the way the translation keeps it, in an alloca loaded on every access
+    let local = builder.build_alloca(i64_ty, "local");
+    builder.store_param_to_alloca(ll_fn.get_param(0), local);
+    builder.build_return(builder.load(local.as_any_value(), i64_ty, "value"));
+    assert!(llmod.print_to_str().contains("alloca"));
+
+    llmod.run_passes(&machine, FUNCTION_PASS_PIPELINE)?;
+    let ir = llmod.print_to_str();
+    assert!(!ir.contains("alloca") && !ir.contains("load"), "{ir}");
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Differential tests of the compiler against the Move VM.
+//!
+//! A [`DiffTest`] compiles a Move package once to bytecode and once to a PolkaVM blob with
+//! its public functions exported. [`DiffTest::check`] runs a function on the Move VM
+//! interpreter and on PolkaVM with the same arguments and fails unless both return the same
+//! values or abort with the same code. Miscompilations show up as a mismatch, without
+//! writing down the expected results by hand:
+//!
+//! ```ignore
+//! let difftest = DiffTest::new("../../examples/difftest/", "output/difftest/difftest.polkavm")?;
+//! for (a, b) in [(0, 0), (1, 254), (255, 1)] {
+//!     difftest.check("difftest::add_u8", &[json!(a), json!(b)])?;
+//! }
+//! ```
+//!
+//! Arguments and results are JSON like in [`move_to_polka::abi`]. Functions can take
+//! booleans, integers up to `u64`, addresses and vectors of those, by value or by reference,
+//! and return nothing or one boolean or integer up to `u64`.
+//!
+//! The two sides fail differently on errors that are not aborts: the Move VM with a status
+//! like `VECTOR_OPERATION_ERROR`, compiled code by panicking in the runtime. Both are compared
+//! as an [`Outcome::RuntimeError`].
+
+mod polka;
+mod vm;
+
+use anyhow::{anyhow, bail, Result};
+use move_core_types::language_storage::ModuleId;
+use move_model::ty::Type;
+use move_polka_codegen::stackless::ExportPolicy;
+use move_to_polka::{
+    abi::AbiType,
+    check, compile, get_env_from_source,
+    linker::{create_colored_stdout, package_build_options, parse_to_blob},
+};
+use polkavm::ProgramBlob;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// Result of running a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Returned(Vec<Value>),
+    Aborted(u64),
+    /// Any failure other than an abort, e.g. an index out of bounds.
+    RuntimeError,
+}
+
+/// A public function that can be run on both sides.
+#[derive(Debug, Clone)]
+struct Function {
+    module: ModuleId,
+    name: String,
+    params: Vec<AbiType>,
+    returns: Vec<AbiType>,
+}
+
+/// A Move package compiled for the Move VM and for PolkaVM.
+pub struct DiffTest {
+    blob: ProgramBlob,
+    /// Serialized bytecode of every module, dependencies included.
+    modules: Vec<(ModuleId, Vec<u8>)>,
+    /// Exported functions by `module::function`.
+    functions: BTreeMap<String, Function>,
+}
+
+impl DiffTest {
+    /// Compile the package at `package_dir`, writing the blob to `output`.
+    pub fn new(package_dir: &str, output: &str) -> Result<Self> {
+        let options = package_build_options(output, package_dir, HashSet::new())?
+            .export_public(true)
+            .build();
+        let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+        check(&env, &options)?;
+        let blob_path = compile(&env, &options)?
+            .blob_path
+            .ok_or_else(|| anyhow!("no blob linked for {package_dir}"))?;
+        let blob = parse_to_blob(&std::fs::read(blob_path)?)?;
+
+        let mut modules = vec![];
+        for module in env.get_modules() {
+            let Some(compiled) = module.get_verified_module() else {
+                continue;
+            };
+            let mut bytes = vec![];
+            compiled.serialize(&mut bytes)?;
+            modules.push((compiled.self_id(), bytes));
+        }
+
+        let codegen_options = options.codegen_options();
+        let mut functions = BTreeMap::new();
+        for module in env.get_modules() {
+            let Some(compiled) = module.get_verified_module() else {
+                continue;
+            };
+            let module_name = module
+                .get_name()
+                .name()
+                .display(env.symbol_pool())
+                .to_string();
+            for fn_env in module.get_functions() {
+                if ExportPolicy::of(&fn_env, &codegen_options) != ExportPolicy::Public {
+                    continue;
+                }
+                let returns = match fn_env.get_result_type() {
+                    Type::Tuple(tys) => tys,
+                    ty => vec![ty],
+                };
+                let name = fn_env.get_name_str();
+                functions.insert(
+                    format!("{module_name}::{name}"),
+                    Function {
+                        module: compiled.self_id(),
+                        name,
+                        params: fn_env
+                            .get_parameter_types()
+                            .iter()
+                            .map(|ty| AbiType::of(&fn_env, ty))
+                            .collect(),
+                        returns: returns.iter().map(|ty| AbiType::of(&fn_env, ty)).collect(),
+                    },
+                );
+            }
+        }
+        Ok(Self {
+            blob,
+            modules,
+            functions,
+        })
+    }
+
+    /// Names of the functions that can be run, as `module::function`.
+    pub fn functions(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+
+    /// Run `function` with the JSON `args` on both sides, failing unless the outcomes are the
+    /// same. Returns the common outcome.
+    pub fn check(&self, function: &str, args: &[Value]) -> Result<Outcome> {
+        let vm = self.run_move_vm(function, args)?;
+        let polka = self.run_polkavm(function, args)?;
+        if vm != polka {
+            let args = args.iter().map(Value::to_string).collect::<Vec<_>>();
+            bail!(
+                "{function}({}): {vm:?} on the Move VM, {polka:?} on PolkaVM",
+                args.join(", ")
+            );
+        }
+        Ok(vm)
+    }
+
+    /// Run `function` with the JSON `args` on the Move VM.
+    pub fn run_move_vm(&self, function: &str, args: &[Value]) -> Result<Outcome> {
+        let function = self.function(function, args)?;
+        let mut bcs_args = vec![];
+        for (ty, arg) in function.params.iter().zip(args) {
+            let mut bcs = vec![];
+            ty.encode(arg, &mut bcs)?;
+            bcs_args.push(bcs);
+        }
+        vm::run(&self.modules, function, bcs_args)
+    }
+
+    /// Run `function` with the JSON `args` on a new PolkaVM instance of the blob.
+    pub fn run_polkavm(&self, function: &str, args: &[Value]) -> Result<Outcome> {
+        let function = self.function(function, args)?;
+        polka::run(&self.blob, function, args)
+    }
+
+    fn function(&self, function: &str, args: &[Value]) -> Result<&Function> {
+        let f = self
+            .functions
+            .get(function)
+            .ok_or_else(|| anyhow!("no exported public function `{function}`"))?;
+        if args.len() != f.params.len() {
+            bail!(
+                "`{function}` takes {} argument(s), got {}",
+                f.params.len(),
+                args.len()
+            );
+        }
+        Ok(f)
+    }
+}
@@ -0,0 +1,76 @@
+//! Running exported functions of the compiled blob on PolkaVM.
+
+use crate::{Function, Outcome};
+use anyhow::{anyhow, bail, Result};
+use move_to_polka::{abi::AbiType, guest_memory::write_value, linker::create_instance};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+use serde_json::Value;
+
+/// Run `function` with the JSON `args` on a new instance of `blob`. Numbers and booleans are
+/// passed in registers, addresses and vectors by pointer to guest memory.
+pub(crate) fn run(blob: &ProgramBlob, function: &Function, args: &[Value]) -> Result<Outcome> {
+    let (mut instance, mut runtime) = create_instance(blob.clone())?;
+    let mut regs = vec![];
+    for (ty, arg) in function.params.iter().zip(args) {
+        let reg = match ty {
+            AbiType::Bool => {
+                arg.as_bool()
+                    .ok_or_else(|| anyhow!("expected a bool, got {arg}"))? as u64
+            }
+            AbiType::U8 | AbiType::U16 | AbiType::U32 | AbiType::U64 => arg
+                .as_u64()
+                .ok_or_else(|| anyhow!("expected a number, got {arg}"))?,
+            AbiType::Address | AbiType::Vector(_) => {
+                write_value(&mut instance, &mut runtime.allocator, ty, arg)?.into()
+            }
+            ty => bail!("{ty:?} parameters of `{}` are not supported", function.name),
+        };
+        regs.push(reg);
+    }
+
+    let name = function.name.as_str();
+    let result = match regs[..] {
+        [] => instance.call_typed_and_get_result::<u64, ()>(&mut runtime, name, ()),
+        [a] => instance.call_typed_and_get_result::<u64, (u64,)>(&mut runtime, name, (a,)),
+        [a, b] => instance.call_typed_and_get_result::<u64, (u64, u64)>(&mut runtime, name, (a, b)),
+        [a, b, c] => instance.call_typed_and_get_result::<u64, (u64, u64, u64)>(
+            &mut runtime,
+            name,
+            (a, b, c),
+        ),
+        [a, b, c, d] => instance.call_typed_and_get_result::<u64, (u64, u64, u64, u64)>(
+            &mut runtime,
+            name,
+            (a, b, c, d),
+        ),
+        _ => bail!("`{name}` takes more than 4 arguments"),
+    };
+
+    match result {
+        Ok(reg) => {
+            let returns = match &function.returns[..] {
+                [] => vec![],
+                [ty] => vec![scalar(ty, reg)?],
+                tys => bail!("`{name}` returns {} values", tys.len()),
+            };
+            Ok(Outcome::Returned(returns))
+        }
+        Err(CallError::User(ProgramError::Abort(code))) => Ok(Outcome::Aborted(code)),
+        Err(CallError::User(_) | CallError::Trap) => Ok(Outcome::RuntimeError),
+        Err(e) => Err(anyhow!("calling `{name}` failed: {e:?}")),
+    }
+}
+
+/// The value of type `ty` returned in the register `reg`. Only the low bits of the register
+/// are defined for types narrower than 64 bits.
+fn scalar(ty: &AbiType, reg: u64) -> Result<Value> {
+    Ok(match ty {
+        AbiType::Bool => Value::Bool(reg as u8 != 0),
+        AbiType::U8 => Value::from(reg as u8),
+        AbiType::U16 => Value::from(reg as u16),
+        AbiType::U32 => Value::from(reg as u32),
+        AbiType::U64 => Value::from(reg),
+        ty => bail!("returning {ty:?} values is not supported"),
+    })
+}
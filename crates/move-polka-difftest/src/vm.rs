@@ -0,0 +1,70 @@
+//! Running functions on the Move VM interpreter.
+
+use crate::{Function, Outcome};
+use anyhow::{anyhow, Result};
+use log::debug;
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::IdentStr,
+    language_storage::ModuleId,
+    vm_status::{StatusCode, StatusType},
+};
+use move_stdlib::natives::{all_natives, GasParameters};
+use move_vm_runtime::{
+    module_traversal::{TraversalContext, TraversalStorage},
+    move_vm::MoveVM,
+};
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas::UnmeteredGasMeter;
+
+/// Run `function` with the BCS encoded `args` on a fresh VM with `modules` published.
+pub(crate) fn run(
+    modules: &[(ModuleId, Vec<u8>)],
+    function: &Function,
+    args: Vec<Vec<u8>>,
+) -> Result<Outcome> {
+    let vm = MoveVM::new(all_natives(AccountAddress::ONE, GasParameters::zeros()));
+    let mut storage = InMemoryStorage::new();
+    for (id, bytes) in modules {
+        storage.publish_or_overwrite_module(id.clone(), bytes.clone());
+    }
+    let mut session = vm.new_session(&storage);
+    let traversal_storage = TraversalStorage::new();
+    let result = session.execute_function_bypass_visibility(
+        &function.module,
+        IdentStr::new(&function.name)?,
+        vec![],
+        args,
+        &mut UnmeteredGasMeter,
+        &mut TraversalContext::new(&traversal_storage),
+    );
+    match result {
+        Ok(values) => {
+            let mut returns = vec![];
+            for ((bytes, _), ty) in values.return_values.iter().zip(&function.returns) {
+                returns.push(ty.decode(&mut bytes.as_slice())?);
+            }
+            Ok(Outcome::Returned(returns))
+        }
+        Err(e) => {
+            debug!(
+                "{}::{} failed on the Move VM: {e:?}",
+                function.module, function.name
+            );
+            match e.major_status() {
+                StatusCode::ABORTED => match e.sub_status() {
+                    Some(code) => Ok(Outcome::Aborted(code)),
+                    None => Err(anyhow!("abort without a code: {e:?}")),
+                },
+                StatusCode::ARITHMETIC_ERROR => {
+                    Ok(Outcome::Aborted(StatusCode::ARITHMETIC_ERROR as u64))
+                }
+                status if status.status_type() == StatusType::Execution => {
+                    Ok(Outcome::RuntimeError)
+                }
+                // failing to load or verify the modules is a bug of the test, not an outcome
+                _ => Err(anyhow!("{e:?}")),
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use move_core_types::vm_status::StatusCode;
+use move_polka_difftest::{DiffTest, Outcome};
+use move_to_polka::initialize_logger;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+const ARITHMETIC_ERROR: u64 = StatusCode::ARITHMETIC_ERROR as u64;
+
+static COMPILE_ONCE: OnceCell<DiffTest> = OnceCell::new();
+
+fn difftest() -> &'static DiffTest {
+    COMPILE_ONCE.get_or_init(|| {
+        initialize_logger();
+        DiffTest::new(
+            "../../examples/difftest/",
+            "output/difftest/difftest.polkavm",
+        )
+        .expect("Failed to compile the fixture")
+    })
+}
+
+/// Check `function` on all pairs of `inputs`.
+fn check_pairs(function: &str, inputs: &[u64]) -> anyhow::Result<()> {
+    for a in inputs {
+        for b in inputs {
+            difftest().check(function, &[json!(a), json!(b)])?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+pub fn difftest_arithmetic() -> anyhow::Result<()> {
+    check_pairs("difftest::add_u8", &[0, 1, 127, 128, 254, 255])?;
+    check_pairs(
+        "difftest::sub_u64",
+        &[0, 1, u64::MAX / 2, u64::MAX - 1, u64::MAX],
+    )?;
+    check_pairs(
+        "difftest::mul_u32",
+        &[0, 1, 2, 65535, 65536, 65537, u32::MAX.into()],
+    )?;
+    check_pairs("difftest::div_u16", &[0, 1, 3, 255, 256, u16::MAX.into()])?;
+    check_pairs("difftest::mod_u64", &[0, 1, 3, 1 << 32, u64::MAX])?;
+    check_pairs("difftest::xor_u16", &[0, 1, 0x5555, u16::MAX.into()])?;
+    check_pairs("difftest::less", &[0, 1, u32::MAX.into()])?;
+    Ok(())
+}
+
+#[test]
+pub fn difftest_shifts_and_casts() -> anyhow::Result<()> {
+    check_pairs("difftest::shl_u8", &[0, 1, 7, 8, 255])?;
+    for a in [0, 1, u64::MAX] {
+        for b in [0, 1, 63, 64, 255] {
+            difftest().check("difftest::shr_u64", &[json!(a), json!(b)])?;
+        }
+    }
+    for a in [0, 255, 256, u64::MAX] {
+        difftest().check("difftest::cast_u8", &[json!(a)])?;
+    }
+    Ok(())
+}
+
+#[test]
+pub fn difftest_vectors_and_addresses() -> anyhow::Result<()> {
+    for v in [json!([]), json!([1, 2, 39]), json!([u64::MAX, 1])] {
+        difftest().check("difftest::sum", &[v])?;
+    }
+    for i in [0, 2, 3, u64::MAX] {
+        difftest().check("difftest::nth", &[json!("0x010203"), json!(i)])?;
+    }
+    let alice = json!("0x7da26da5e784569ae3cd4c8558852c82d69fa904bd1a14611cd3fd15c79335d4");
+    let bob = json!("0x1");
+    difftest().check("difftest::same_address", &[alice.clone(), alice.clone()])?;
+    difftest().check("difftest::same_address", &[alice, bob])?;
+    Ok(())
+}
+
+#[test]
+pub fn difftest_outcomes() -> anyhow::Result<()> {
+    let check = |function: &str, args: &[Value]| difftest().check(function, args);
+    assert_eq!(
+        check("difftest::add_u8", &[json!(200), json!(55)])?,
+        Outcome::Returned(vec![json!(255)])
+    );
+    assert_eq!(
+        check("difftest::add_u8", &[json!(200), json!(56)])?,
+        Outcome::Aborted(ARITHMETIC_ERROR)
+    );
+    assert_eq!(
+        check("difftest::checked", &[json!(7)])?,
+        Outcome::Aborted(7)
+    );
+    assert_eq!(
+        check("difftest::nth", &[json!([1]), json!(1)])?,
+        Outcome::RuntimeError
+    );
+    Ok(())
+}
+
+#[test]
+pub fn difftest_covers_all_functions() {
+    let functions = difftest().functions().collect::<Vec<_>>();
+    assert_eq!(functions.len(), 14, "{functions:?}");
+    assert!(difftest().check("difftest::missing", &[]).is_err());
+    assert!(difftest().check("difftest::add_u8", &[json!(1)]).is_err());
+}
@@ -0,0 +1,73 @@
+//! The interface tools use to drive `move-to-polka`: building Move packages to blobs, running
+//! blobs on the test host, and reading the artifacts a build writes next to them.
+//!
+//! `move-to-polka`'s `linker` module mixes these with the internals of a build, such as
+//! object linking and the host call handlers. Tools like `polkavm-wrapper` depend on this
+//! crate alone, so the compiler can change without breaking them:
+//!
+//! - [`build`] turns a package into a blob with [`build::BuildOptions`],
+//! - [`run`] parses blobs, instantiates them with a [`run::Runtime`] host, moves values in
+//!   and out of guest memory, reads the tables of instrumented builds and keeps storage,
+//! - [`artifacts`] reads the ABI, metadata and maps written next to a blob,
+//! - [`testing`] fuzzes, replays and explores blobs beyond single calls.
+//!
+//! The command line `Options` stay in `move-to-polka`: `run_to_polka` and every artifact
+//! writer take them, so moving them here would make the compiler depend on its own driver.
+//! Tools set them through [`build::BuildOptions`] instead.
+//!
+//! The LLVM version is selected like in `move-to-polka`, with the `llvm18`, `llvm19` (the
+//! default) or `llvm20` feature.
+
+pub use move_to_polka::initialize_logger;
+
+/// Building Move packages.
+pub mod build {
+    pub use move_to_polka::{
+        linker::{
+            build_move_package, build_polka_from_move, package_build_options,
+            package_test_build_options, packages_build_options, BuildOptions,
+        },
+        BuildOutput,
+    };
+}
+
+/// Running blobs on the test host.
+pub mod run {
+    pub use move_to_polka::{
+        abort_codes::AbortCodes,
+        linker::{
+            copy_bytes_from_guest, copy_bytes_to_guest, copy_from_guest, copy_to_guest,
+            create_instance, create_instance_on_backend, create_instance_with_print_sink,
+            create_metered_instance, create_stepping_instance, parse_to_blob,
+            read_coverage_counters, read_heap_stats, run_lowlevel, BlobContract, CONTRACT_ADDRESS,
+            DEFAULT_CALL_DATA,
+        },
+        storage::{stored_resources, FileBackend, StoredResource},
+    };
+    pub use polkavm_move_native::{
+        host::{MockRandom, ProgramError, Runtime},
+        storage::{GlobalStorage, StorageBackend},
+        REVIVE_ADDRESS_LENGTH,
+    };
+}
+
+/// Reading the artifacts a build writes next to a blob.
+pub mod artifacts {
+    pub use move_to_polka::{
+        abi::{abi_path, Abi},
+        coverage::CoverageMap,
+        metadata::PackageMetadata,
+        signature::ExportSignature,
+        source_map::SourceMap,
+        stack_trace::StackTraceMap,
+    };
+}
+
+/// Testing blobs beyond single calls.
+pub mod testing {
+    pub use move_to_polka::{
+        fuzz::{fuzz, FuzzOptions, Outcome, DEFAULT_GAS},
+        repl::Session,
+        trace::{record, replay, Trace},
+    };
+}
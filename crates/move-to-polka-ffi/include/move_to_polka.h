@@ -0,0 +1,52 @@
+/*
+ * C API of the move-to-polka compiler, implemented by libmove_to_polka_ffi.
+ *
+ * Build the library with `cargo build --release -p move-to-polka-ffi`.
+ */
+
+#ifndef MOVE_TO_POLKA_H
+#define MOVE_TO_POLKA_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MTP_OK 0
+/* A pointer argument is invalid, not UTF-8, or the options are malformed. */
+#define MTP_INVALID_ARGUMENT 1
+#define MTP_COMPILE_ERROR 2
+#define MTP_TOOLCHAIN_ERROR 3
+#define MTP_UNSUPPORTED 4
+#define MTP_CODEGEN_ERROR 5
+#define MTP_LINK_ERROR 6
+/* Any other failure, including I/O errors and panics of the compiler. */
+#define MTP_INTERNAL_ERROR 7
+
+/*
+ * Compile the Move package at `source_dir` to the blob `out_blob_path`.
+ *
+ * `options_json` is NULL or a JSON object with any of the fields, defaulting to the
+ * defaults of the `move-to-polka` CLI:
+ *   named_addresses (array of "name=address"), export_public, strip_debug_natives,
+ *   emit_metadata, overflow_checks, assume_no_abort, source_map, coverage, stack_traces,
+ *   heap_pages, stack_size, emit_elf, emit_abi, lto, move_native_variant ("rv64-release"),
+ *   address_length (20 or 32), warn_as_error, quiet_warnings.
+ *
+ * Returns MTP_OK, or an error code whose message mtp_last_error returns.
+ */
+int mtp_compile(const char *source_dir, const char *options_json, const char *out_blob_path);
+
+/*
+ * The message of the last failed call on this thread, or NULL if the last call succeeded.
+ * The string is owned by the library and valid until the next call on this thread.
+ */
+const char *mtp_last_error(void);
+
+/* The version of the compiler, a static string. */
+const char *mtp_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MOVE_TO_POLKA_H */
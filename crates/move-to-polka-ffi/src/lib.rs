@@ -0,0 +1,216 @@
+//! C API of the compiler, for build systems that are not written in Rust, e.g. a TypeScript
+//! SDK or a Python test harness, to compile packages without spawning `move-to-polka`.
+//!
+//! The declarations are in `include/move_to_polka.h`. All functions are safe to call from
+//! several threads; the last error is kept per thread.
+//!
+//! ```c
+//! if (mtp_compile("examples/storage", "{\"emit_abi\": true}", "out/storage.polkavm") != MTP_OK) {
+//!     fprintf(stderr, "%s\n", mtp_last_error());
+//! }
+//! ```
+
+use move_to_polka::{
+    error::MoveToPolkaError,
+    linker::{build_polka_from_move, package_build_options, BuildOptions},
+    native::{AddressLength, NativeVariant},
+};
+use serde::Deserialize;
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+pub const MTP_OK: i32 = 0;
+/// A pointer argument is invalid, not UTF-8, or the options are malformed.
+pub const MTP_INVALID_ARGUMENT: i32 = 1;
+pub const MTP_COMPILE_ERROR: i32 = 2;
+pub const MTP_TOOLCHAIN_ERROR: i32 = 3;
+pub const MTP_UNSUPPORTED: i32 = 4;
+pub const MTP_CODEGEN_ERROR: i32 = 5;
+pub const MTP_LINK_ERROR: i32 = 6;
+/// Any other failure, including I/O errors and panics of the compiler.
+pub const MTP_INTERNAL_ERROR: i32 = 7;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Compilation options, given as a JSON object. All fields are optional and default to the
+/// defaults of the `move-to-polka` CLI.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CompileOptions {
+    /// Named address mapping, e.g. `["std=0x1"]`, in addition to the package's.
+    named_addresses: Vec<String>,
+    export_public: bool,
+    strip_debug_natives: bool,
+    emit_metadata: bool,
+    overflow_checks: Option<bool>,
+    assume_no_abort: bool,
+    source_map: bool,
+    coverage: bool,
+    stack_traces: bool,
+    heap_pages: Option<u32>,
+    stack_size: Option<u32>,
+    emit_elf: bool,
+    emit_abi: bool,
+    lto: bool,
+    /// Embedded move-native runtime, as `rv64-<debug|release>`.
+    move_native_variant: Option<String>,
+    /// Length of Move addresses in bytes, 20 or 32.
+    address_length: Option<u32>,
+    warn_as_error: bool,
+    quiet_warnings: bool,
+}
+
+impl CompileOptions {
+    fn apply(self, mut build_options: BuildOptions) -> Result<BuildOptions, Error> {
+        if let Some(variant) = self.move_native_variant {
+            let variant = variant
+                .parse::<NativeVariant>()
+                .map_err(|e| Error(MTP_INVALID_ARGUMENT, e))?;
+            build_options = build_options.move_native_variant(variant);
+        }
+        if let Some(length) = self.address_length {
+            let length = length
+                .to_string()
+                .parse::<AddressLength>()
+                .map_err(|e| Error(MTP_INVALID_ARGUMENT, e))?;
+            build_options = build_options.address_length(length);
+        }
+        if let Some(pages) = self.heap_pages {
+            build_options = build_options.heap_pages(pages);
+        }
+        Ok(build_options
+            .export_public(self.export_public)
+            .strip_debug_natives(self.strip_debug_natives)
+            .emit_metadata(self.emit_metadata)
+            .overflow_checks(self.overflow_checks.unwrap_or(true))
+            .assume_no_abort(self.assume_no_abort)
+            .source_map(self.source_map)
+            .coverage(self.coverage)
+            .stack_traces(self.stack_traces)
+            .stack_size(self.stack_size)
+            .emit_elf(self.emit_elf)
+            .emit_abi(self.emit_abi)
+            .lto(self.lto)
+            .warn_as_error(self.warn_as_error)
+            .quiet_warnings(self.quiet_warnings))
+    }
+}
+
+/// An error code and its message.
+struct Error(i32, String);
+
+impl From<MoveToPolkaError> for Error {
+    fn from(e: MoveToPolkaError) -> Self {
+        let code = match e {
+            MoveToPolkaError::Compile(_) => MTP_COMPILE_ERROR,
+            MoveToPolkaError::Toolchain(_) => MTP_TOOLCHAIN_ERROR,
+            MoveToPolkaError::Unsupported(_) => MTP_UNSUPPORTED,
+            MoveToPolkaError::Codegen(_) | MoveToPolkaError::Verification(_) => MTP_CODEGEN_ERROR,
+            MoveToPolkaError::Link(_) => MTP_LINK_ERROR,
+            _ => MTP_INTERNAL_ERROR,
+        };
+        Error(code, e.to_string())
+    }
+}
+
+/// Compile the Move package at `source_dir` to the blob `out_blob_path`, with the JSON
+/// `options_json`, which may be NULL. Returns `MTP_OK`, or an error code whose message
+/// [`mtp_last_error`] returns.
+///
+/// # Safety
+///
+/// `source_dir` and `out_blob_path` must be valid NUL-terminated strings, `options_json`
+/// one as well or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn mtp_compile(
+    source_dir: *const c_char,
+    options_json: *const c_char,
+    out_blob_path: *const c_char,
+) -> i32 {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let source_dir = unsafe { str_arg("source_dir", source_dir) }?;
+        let out_blob_path = unsafe { str_arg("out_blob_path", out_blob_path) }?;
+        let options = if options_json.is_null() {
+            CompileOptions::default()
+        } else {
+            let json = unsafe { str_arg("options_json", options_json) }?;
+            serde_json::from_str(json)
+                .map_err(|e| Error(MTP_INVALID_ARGUMENT, format!("invalid options: {e}")))?
+        };
+        compile(source_dir, options, out_blob_path)
+    }));
+    let result = result.unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(Error(
+            MTP_INTERNAL_ERROR,
+            format!("compiler panicked: {message}"),
+        ))
+    });
+    match result {
+        Ok(()) => {
+            set_last_error(None);
+            MTP_OK
+        }
+        Err(Error(code, message)) => {
+            set_last_error(Some(message));
+            code
+        }
+    }
+}
+
+/// The message of the last failed call on this thread, or NULL if the last call succeeded.
+/// The string is owned by the library and valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn mtp_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// The version of the compiler, a static string.
+#[no_mangle]
+pub extern "C" fn mtp_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+fn compile(source_dir: &str, options: CompileOptions, out_blob_path: &str) -> Result<(), Error> {
+    let mapping = options
+        .named_addresses
+        .iter()
+        .cloned()
+        .collect::<HashSet<_>>();
+    let build_options = package_build_options(out_blob_path, source_dir, mapping)?;
+    build_polka_from_move(options.apply(build_options)?)?;
+    Ok(())
+}
+
+unsafe fn str_arg<'a>(name: &str, ptr: *const c_char) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return Err(Error(MTP_INVALID_ARGUMENT, format!("{name} is NULL")));
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|e| Error(MTP_INVALID_ARGUMENT, format!("{name} is not UTF-8: {e}")))
+}
+
+fn set_last_error(message: Option<String>) {
+    // messages can't contain NUL bytes in C, cut them there
+    let message = message.map(|message| {
+        let end = message.find('\0').unwrap_or(message.len());
+        CString::new(&message[..end]).expect("NUL bytes were removed")
+    });
+    LAST_ERROR.with(|error| *error.borrow_mut() = message);
+}
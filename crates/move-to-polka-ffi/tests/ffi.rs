@@ -0,0 +1,72 @@
+use std::ffi::{CStr, CString};
+
+use move_to_polka_ffi::{
+    mtp_compile, mtp_last_error, mtp_version, MTP_COMPILE_ERROR, MTP_INVALID_ARGUMENT, MTP_OK,
+};
+
+fn last_error() -> Option<String> {
+    let error = mtp_last_error();
+    (!error.is_null()).then(|| {
+        unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+fn compile(source: &str, options: Option<&str>, output: &str) -> i32 {
+    let source = CString::new(source).unwrap();
+    let options = options.map(|options| CString::new(options).unwrap());
+    let output = CString::new(output).unwrap();
+    unsafe {
+        mtp_compile(
+            source.as_ptr(),
+            options.as_ref().map_or(std::ptr::null(), |o| o.as_ptr()),
+            output.as_ptr(),
+        )
+    }
+}
+
+#[test]
+pub fn ffi_compiles_package() {
+    let output = "output/ffi/entry.polkavm";
+    let code = compile(
+        "../../examples/entry/",
+        Some(r#"{"emit_abi": true, "overflow_checks": false}"#),
+        output,
+    );
+    assert_eq!(code, MTP_OK, "{:?}", last_error());
+    assert_eq!(last_error(), None);
+    assert!(std::fs::metadata(output).unwrap().len() > 0);
+}
+
+#[test]
+pub fn ffi_reports_compile_errors() {
+    let code = compile(
+        "../../examples/does-not-exist/",
+        None,
+        "output/ffi/missing.polkavm",
+    );
+    assert_eq!(code, MTP_COMPILE_ERROR);
+    assert!(last_error().is_some());
+}
+
+#[test]
+pub fn ffi_rejects_invalid_options() {
+    let code = compile(
+        "../../examples/entry/",
+        Some(r#"{"no_such_option": true}"#),
+        "output/ffi/invalid.polkavm",
+    );
+    assert_eq!(code, MTP_INVALID_ARGUMENT);
+    assert!(last_error().unwrap().contains("no_such_option"));
+
+    let code = unsafe { mtp_compile(std::ptr::null(), std::ptr::null(), std::ptr::null()) };
+    assert_eq!(code, MTP_INVALID_ARGUMENT);
+    assert_eq!(last_error().unwrap(), "source_dir is NULL");
+}
+
+#[test]
+pub fn ffi_version() {
+    let version = unsafe { CStr::from_ptr(mtp_version()) };
+    assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+}
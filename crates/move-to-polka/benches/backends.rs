@@ -0,0 +1,183 @@
+//! Execution time of Move workloads on the PolkaVM interpreter and compiler backends, and the
+//! instructions each workload executes, measured as the gas a metered instance consumes.
+//!
+//! The workloads of `examples/bench` stress calls (`fib`), vector accesses (`sort`), the hash
+//! natives (`hash_loop`) and global storage (`storage_churn`). Criterion keeps the times of
+//! earlier runs under `target/criterion`, e.g. `--save-baseline main` then `--baseline main` to
+//! compare against a branch. Instruction counts don't depend on the host, so they are kept in
+//! `benches/instructions.json` to be committed: every run reports the changes against it, and
+//! `MOVE_POLKA_BENCH_SAVE=1` writes the new counts.
+//!
+//! The compiler backend is skipped where PolkaVM doesn't support it.
+//!
+//! Run with `cargo bench -p move-to-polka --bench backends`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, copy_to_guest, create_instance_on_backend, package_build_options,
+        parse_to_blob,
+    },
+};
+use polkavm::{BackendKind, Instance, ProgramBlob};
+use polkavm_move_native::{
+    host::{ProgramError, Runtime},
+    types::{MoveAddress, MoveSigner, ACCOUNT_ADDRESS_LENGTH},
+};
+
+const GAS: i64 = 10_000_000_000;
+const INSTRUCTIONS_FILE: &str = "benches/instructions.json";
+
+/// A workload: the exported function and its argument, none for entry functions, which are
+/// passed a signer.
+struct Workload {
+    name: &'static str,
+    function: &'static str,
+    arg: Option<u64>,
+}
+
+const WORKLOADS: &[Workload] = &[
+    Workload {
+        name: "fib(20)",
+        function: "fib",
+        arg: Some(20),
+    },
+    Workload {
+        name: "sort(200)",
+        function: "sort",
+        arg: Some(200),
+    },
+    Workload {
+        name: "hash_loop(100)",
+        function: "hash_loop",
+        arg: Some(100),
+    },
+    Workload {
+        name: "storage_churn",
+        function: "storage_churn",
+        arg: None,
+    },
+];
+
+fn build_blob() -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/bench/backends.polkavm",
+        "../../examples/bench/",
+        HashSet::new(),
+    )?
+    .export_public(true)
+    .heap_pages(256);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+/// Call the function of `workload` on `instance`.
+fn call(
+    instance: &mut Instance<Runtime, ProgramError>,
+    runtime: &mut Runtime,
+    workload: &Workload,
+) -> anyhow::Result<()> {
+    let result = match workload.arg {
+        Some(arg) => instance
+            .call_typed_and_get_result::<u64, (u64,)>(runtime, workload.function, (arg,))
+            .map(|_| ()),
+        None => {
+            let signer = MoveSigner(MoveAddress([1; ACCOUNT_ADDRESS_LENGTH]));
+            let signer = copy_to_guest(instance, &mut runtime.allocator, &signer)
+                .map_err(|e| anyhow::anyhow!("{}: {e:?}", workload.name))?;
+            instance.call_typed_and_get_result::<(), (u32,)>(runtime, workload.function, (signer,))
+        }
+    };
+    result.map_err(|e| anyhow::anyhow!("{}: {e:?}", workload.name))
+}
+
+/// Instructions `workload` executes, the gas it uses on a metered instance.
+fn instructions(blob: &ProgramBlob, workload: &Workload) -> anyhow::Result<i64> {
+    let (mut instance, mut runtime) =
+        create_instance_on_backend(blob.clone(), BackendKind::Interpreter, Some(GAS))?;
+    call(&mut instance, &mut runtime, workload)?;
+    Ok(GAS - instance.gas())
+}
+
+/// The backends PolkaVM supports on this host.
+fn backends(blob: &ProgramBlob) -> Vec<BackendKind> {
+    [BackendKind::Interpreter, BackendKind::Compiler]
+        .into_iter()
+        .filter(|backend| create_instance_on_backend(blob.clone(), *backend, None).is_ok())
+        .collect()
+}
+
+/// Count the instructions of every workload and compare them with the checked-in counts.
+fn report_instructions(blob: &ProgramBlob) -> anyhow::Result<()> {
+    let previous: BTreeMap<String, i64> = fs::read_to_string(INSTRUCTIONS_FILE)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let mut counts = BTreeMap::new();
+    println!(
+        "{:<16} {:>14} {:>14} {:>8}",
+        "workload", "instructions", "previous", "change"
+    );
+    for workload in WORKLOADS {
+        let count = instructions(blob, workload)?;
+        let (previous, change) = match previous.get(workload.name) {
+            Some(&previous) => (
+                previous.to_string(),
+                format!(
+                    "{:+.2}%",
+                    (count - previous) as f64 * 100.0 / previous as f64
+                ),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:<16} {count:>14} {previous:>14} {change:>8}",
+            workload.name
+        );
+        counts.insert(workload.name.to_string(), count);
+    }
+    if std::env::var_os("MOVE_POLKA_BENCH_SAVE").is_some() {
+        fs::write(
+            Path::new(INSTRUCTIONS_FILE),
+            serde_json::to_string_pretty(&counts)? + "\n",
+        )?;
+        println!("instruction counts written to {INSTRUCTIONS_FILE}");
+    }
+    Ok(())
+}
+
+fn bench_backends(c: &mut Criterion) {
+    let blob = build_blob().expect("Failed to compile the workloads");
+    report_instructions(&blob).expect("Failed to count instructions");
+    let backends = backends(&blob);
+    for workload in WORKLOADS {
+        let mut group = c.benchmark_group(workload.name);
+        for &backend in &backends {
+            group.bench_with_input(
+                BenchmarkId::from_parameter(format!("{backend:?}")),
+                workload,
+                |b, workload| {
+                    // instantiation, the compilation of the compiler backend included, isn't timed
+                    b.iter_batched(
+                        || create_instance_on_backend(blob.clone(), backend, None).unwrap(),
+                        |(mut instance, mut runtime)| {
+                            call(&mut instance, &mut runtime, workload).unwrap()
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_backends);
+criterion_main!(benches);
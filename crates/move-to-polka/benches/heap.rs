@@ -0,0 +1,52 @@
+//! Heap usage of vector-heavy workloads, read from the allocator's counters.
+//!
+//! `big_vector` grows one vector to 20000 elements, `temporary_vectors` builds and frees 1000
+//! vectors of 100 elements. A heap that never frees needs the buffers of every capacity and
+//! every vector; the peak shows how much of that the allocator avoids.
+//!
+//! Run with `cargo bench -p move-to-polka --bench heap`.
+
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_instance, package_build_options, parse_to_blob,
+        read_heap_stats,
+    },
+};
+
+fn main() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/bench/heap.polkavm",
+        "../../examples/heap/",
+        HashSet::new(),
+    )?
+    .heap_pages(256)
+    .stack_size(Some(64 * 1024))
+    .heap_stats(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+
+    println!(
+        "{:<18} {:>10} {:>8} {:>8} {:>10} {:>8}",
+        "function", "peak", "allocs", "frees", "in place", "moved"
+    );
+    for function in ["big_vector", "temporary_vectors"] {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        instance
+            .call_typed_and_get_result::<u32, ()>(&mut runtime, function, ())
+            .map_err(|e| anyhow::anyhow!("{function}: {e:?}"))?;
+        let stats = read_heap_stats(&mut instance, &runtime)?
+            .ok_or_else(|| anyhow::anyhow!("{function} registered no heap stats"))?;
+        println!(
+            "{function:<18} {:>10} {:>8} {:>8} {:>10} {:>8}",
+            stats.peak,
+            stats.allocations,
+            stats.frees,
+            stats.reallocs_in_place,
+            stats.reallocs_moved
+        );
+    }
+    Ok(())
+}
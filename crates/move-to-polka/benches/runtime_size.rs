@@ -0,0 +1,63 @@
+//! Code size of contracts, and how much of it the runtime and `core::fmt` take.
+//!
+//! The panic handler formats its message with `polkavm_move_native::fmt` rather than
+//! `core::fmt`, which would otherwise be linked into every contract. Sizes are those of the
+//! function symbols of the merged ELF object, before the polka linker, and of the code of the
+//! blob; the `core::fmt` column should stay at zero.
+//!
+//! Run with `cargo bench -p move-to-polka --bench runtime_size`.
+
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::linker::{
+    build_polka_from_move, elf_path, package_build_options, parse_to_blob,
+};
+use object::{Object, ObjectSymbol, SymbolKind};
+
+const EXAMPLES: [&str; 4] = ["void", "storage", "basic-coin", "vector"];
+
+/// Bytes of the function symbols of `elf`: of the runtime, of `core::fmt`, and in total.
+fn text_sizes(elf: &[u8]) -> anyhow::Result<(u64, u64, u64)> {
+    let file = object::File::parse(elf)?;
+    let (mut runtime, mut fmt, mut total) = (0, 0, 0);
+    for symbol in file
+        .symbols()
+        .filter(|symbol| symbol.kind() == SymbolKind::Text)
+    {
+        let name = symbol.name().unwrap_or_default();
+        if name.contains("4core3fmt") {
+            fmt += symbol.size();
+        } else if name.starts_with("move_native_")
+            || name.starts_with("move_rt_")
+            || name.contains("polkavm_move_native")
+        {
+            runtime += symbol.size();
+        }
+        total += symbol.size();
+    }
+    Ok((runtime, fmt, total))
+}
+
+fn main() -> anyhow::Result<()> {
+    move_to_polka::initialize_logger();
+    println!(
+        "{:<12} {:>10} {:>10} {:>10} {:>10}",
+        "example", "blob code", "text", "runtime", "core::fmt"
+    );
+    for example in EXAMPLES {
+        let output = format!("output/bench/runtime_size/{example}.polkavm");
+        let build_options = package_build_options(
+            &output,
+            &format!("../../examples/{example}/"),
+            HashSet::new(),
+        )?
+        .emit_elf(true);
+        let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+        let (runtime, fmt, total) = text_sizes(&std::fs::read(elf_path(Path::new(&output)))?)?;
+        println!(
+            "{example:<12} {:>10} {total:>10} {runtime:>10} {fmt:>10}",
+            blob.code().len()
+        );
+    }
+    Ok(())
+}
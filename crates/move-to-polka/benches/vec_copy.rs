@@ -0,0 +1,82 @@
+//! Instructions spent per vector copy, measured as the gas a metered instance consumes.
+//!
+//! Every copy of a vector local goes through `move_rt_vec_copy`. Vectors of primitives are
+//! copied with a single memcpy of the buffer, so the cost per element should be a small
+//! fraction of the per-element push loop used for other element types. `copy_wrapped_u64`
+//! copies `u64`s wrapped in a struct, the same bytes down the element by element path, as the
+//! baseline the memcpy of `copy_u64` is compared to.
+//!
+//! Run with `cargo bench -p move-to-polka --bench vec_copy`.
+
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_metered_instance, package_build_options, parse_to_blob,
+    },
+};
+use polkavm::ProgramBlob;
+
+const GAS: i64 = 1_000_000_000;
+const COPIES: u64 = 8;
+const LENGTHS: [u64; 4] = [0, 16, 128, 1024];
+
+/// Gas used by one copy of the vector `function` builds of `len` elements. Building it costs
+/// the same with and without copies, so it is subtracted.
+fn gas_per_copy(blob: &ProgramBlob, function: &str, len: u64) -> anyhow::Result<i64> {
+    let copies = gas_used(blob, function, len, COPIES)?;
+    let build = gas_used(blob, function, len, 0)?;
+    Ok((copies - build) / COPIES as i64)
+}
+
+/// Gas used by `function(len, copies)`.
+fn gas_used(blob: &ProgramBlob, function: &str, len: u64, copies: u64) -> anyhow::Result<i64> {
+    let (mut instance, mut runtime) = create_metered_instance(blob.clone(), GAS)?;
+    let total = instance
+        .call_typed_and_get_result::<u64, (u64, u64)>(&mut runtime, function, (len, copies))
+        .map_err(|e| anyhow::anyhow!("{function}({len}, {copies}): {e:?}"))?;
+    assert_eq!(total, len * copies);
+    Ok(GAS - instance.gas())
+}
+
+fn main() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/bench/vec_copy.polkavm",
+        "../../examples/vec-copy/",
+        HashSet::new(),
+    )?
+    .export_public(true)
+    .heap_pages(256);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+
+    println!(
+        "{:<18} {:>6} {:>12} {:>12}",
+        "function", "len", "per copy", "per element"
+    );
+    for function in ["copy_u8", "copy_u64", "copy_address", "copy_wrapped_u64"] {
+        for len in LENGTHS {
+            let per_copy = gas_per_copy(&blob, function, len)?;
+            let per_element = if len == 0 {
+                "-".to_string()
+            } else {
+                format!("{:.2}", per_copy as f64 / len as f64)
+            };
+            println!("{function:<18} {len:>6} {per_copy:>12} {per_element:>12}");
+        }
+    }
+
+    println!();
+    println!(
+        "{:>6} {:>12} {:>12} {:>8}",
+        "len", "memcpy", "per element", "speedup"
+    );
+    for len in LENGTHS.into_iter().filter(|len| *len > 0) {
+        let memcpy = gas_per_copy(&blob, "copy_u64", len)?;
+        let per_element = gas_per_copy(&blob, "copy_wrapped_u64", len)?;
+        let speedup = per_element as f64 / memcpy as f64;
+        println!("{len:>6} {memcpy:>12} {per_element:>12} {speedup:>7.1}x");
+    }
+    Ok(())
+}
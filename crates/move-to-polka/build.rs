@@ -1,11 +1,88 @@
-use std::path::PathBuf;
+use build_tools::NativeVariant;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
 
 fn main() -> anyhow::Result<()> {
-    build_move_native_lib()?;
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
+    let out_path = out_dir.join("move-native-lib-build");
+    std::fs::create_dir_all(&out_path)?;
+
+    // `native::MOVE_NATIVE_LIBS`, the embedded runtime object of every variant
+    let mut variants_rs = "const MOVE_NATIVE_LIBS: &[(&str, &[u8])] = &[\n".to_string();
+    for variant in move_native_variants()? {
+        let prebuilt = match variant {
+            NativeVariant::DEFAULT => prebuilt_move_native_lib(&out_path)?,
+            _ => None,
+        };
+        let object_file = match prebuilt {
+            Some(object_file) => object_file,
+            None => build_move_native_lib(&out_path, variant)?,
+        };
+        let object_file = object_file.canonicalize()?.to_string_lossy().to_string();
+        writeln!(
+            variants_rs,
+            "    ({:?}, include_bytes!({object_file:?})),",
+            variant.to_string()
+        )?;
+    }
+    variants_rs.push_str("];\n");
+    std::fs::write(out_dir.join("move_native_variants.rs"), variants_rs)?;
     Ok(())
 }
 
-fn build_move_native_lib() -> anyhow::Result<()> {
+/// Runtime variants to embed, from the comma separated `MOVE_NATIVE_VARIANTS`
+/// (e.g. `rv64-release,rv64-debug`). The default variant is always embedded.
+fn move_native_variants() -> anyhow::Result<Vec<NativeVariant>> {
+    println!("cargo:rerun-if-env-changed=MOVE_NATIVE_VARIANTS");
+    let mut variants = vec![NativeVariant::DEFAULT];
+    if let Ok(names) = std::env::var("MOVE_NATIVE_VARIANTS") {
+        for name in names.split(',').filter(|name| !name.trim().is_empty()) {
+            let variant = name.parse::<NativeVariant>().map_err(anyhow::Error::msg)?;
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+    }
+    Ok(variants)
+}
+
+/// Use the prebuilt default runtime object from `MOVE_NATIVE_PREBUILT`, a path or an URL in which
+/// `{version}` is replaced by the crate version, instead of building it with nightly cargo.
+///
+/// The object is checked against `MOVE_NATIVE_PREBUILT_SHA256`, which is required for URLs.
+/// If the object can't be fetched, the runtime is built from source.
+fn prebuilt_move_native_lib(out_path: &Path) -> anyhow::Result<Option<PathBuf>> {
+    println!("cargo:rerun-if-env-changed=MOVE_NATIVE_PREBUILT");
+    println!("cargo:rerun-if-env-changed=MOVE_NATIVE_PREBUILT_SHA256");
+    let Ok(source) = std::env::var("MOVE_NATIVE_PREBUILT") else {
+        return Ok(None);
+    };
+    let source = source.replace("{version}", &std::env::var("CARGO_PKG_VERSION")?);
+    let sha256 = std::env::var("MOVE_NATIVE_PREBUILT_SHA256").ok();
+    let is_url = source.starts_with("http://") || source.starts_with("https://");
+    if is_url && sha256.is_none() {
+        anyhow::bail!("MOVE_NATIVE_PREBUILT_SHA256 is required to download {source}");
+    }
+    if !is_url {
+        println!("cargo:rerun-if-changed={source}");
+    }
+
+    let object_file = out_path.join("polkavm_native_prebuilt.o");
+    if let Err(e) = build_tools::fetch_prebuilt_object(&source, &object_file) {
+        println!("cargo:warning=prebuilt move-native runtime unavailable ({e:#}), building it from source");
+        return Ok(None);
+    }
+    // A mismatch is an error rather than a reason to fall back: the object was tampered with
+    // or does not belong to this release.
+    if let Some(sha256) = sha256 {
+        build_tools::verify_sha256(&object_file, &sha256)?;
+    }
+    Ok(Some(object_file))
+}
+
+fn build_move_native_lib(out_path: &Path, variant: NativeVariant) -> anyhow::Result<PathBuf> {
     let tools = build_tools::NativeBuildTools::try_init()?;
 
     let move_native_crate =
@@ -13,14 +90,5 @@ fn build_move_native_lib() -> anyhow::Result<()> {
     println!("cargo:rerun-if-changed={move_native_crate}");
     let move_native_crate = PathBuf::from(move_native_crate).canonicalize()?;
 
-    let out_path = PathBuf::from(std::env::var("OUT_DIR")?).join("move-native-lib-build");
-    std::fs::create_dir_all(&out_path)?;
-
-    let object_file = tools.build_native_move_lib(&move_native_crate, &out_path)?;
-    println!(
-        "cargo:rustc-env=MOVE_NATIVE_OBJECT_FILE={}",
-        object_file.canonicalize()?.to_string_lossy()
-    );
-
-    Ok(())
+    tools.build_native_move_lib(&move_native_crate, &out_path.to_path_buf(), variant)
 }
@@ -0,0 +1,606 @@
+//! ABI of a blob: the entry functions `call_selector` dispatches to and their signatures.
+//!
+//! With `--emit-abi` the compiler writes it next to the blob (`<output>.abi.json`):
+//!
+//! ```json
+//! {
+//!   "codec": "bcs",
+//!   "functions": [
+//!     { "name": "storage::store_then_borrow", "selector": "0xfa1e1f30", "params": ["signer"], "param_names": ["account"], "returns": [] },
+//!     { "name": "vault::deposit", "selector": "0x11223344", "params": ["signer"], "param_names": ["account"], "returns": [], "attributes": { "payable": null, "selector": "287454020" } }
+//!   ]
+//! }
+//! ```
+//!
+//! [`Abi::encode_call`] builds the call data submitted to pallet-revive, the selector in call
+//! data byte order followed by the BCS encoded arguments, and [`Abi::decode_return`] decodes
+//! returned bytes. Values are given as JSON: numbers (or decimal strings for `u128`/`u256`),
+//! booleans, `0x` hex strings for addresses, and arrays for vectors. Addresses are encoded with
+//! the `--address-length` of the build, which the ABI records as `address_length` when it isn't
+//! 32. `vector<u8>` also accepts a `0x` hex string. Structs are given as objects with a value
+//! per field, or as arrays of the field values in order. `signer` parameters take no value, the
+//! contract passes the origin of the call; signers in a `vector<signer>` or a struct are
+//! encoded as addresses, but the runtime refuses to decode them.
+//!
+//! The generated `call_selector` decodes the arguments from the call data in the same
+//! encoding, and encodes the values returned by the entry function as the output of the
+//! call.
+//!
+//! The ABI also lists the layout of every struct with the `key` ability, by the hash of its
+//! struct tag, the key resources are stored under along with their address, so hosts can
+//! decode the stored resources, see [`AbiType::decode_stored`].
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    metadata::{hash_selector_table, CODEC},
+    options::Options,
+};
+use move_core_types::{account_address::AccountAddress, u256::U256};
+use move_model::{
+    model::{FunctionEnv, GlobalEnv},
+    ty::{PrimitiveType, Type, TypeDisplayContext},
+};
+use move_polka_codegen::stackless::{function_attributes, parameter_names};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// File name suffix of the ABI written next to a blob.
+pub const ABI_EXTENSION: &str = "abi.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Abi {
+    pub codec: String,
+    /// Length of addresses in bytes, the `--address-length` of the build. Addresses are
+    /// encoded with as many bytes, the value right-aligned like in BCS.
+    #[serde(
+        default = "default_address_length",
+        skip_serializing_if = "is_default_address_length"
+    )]
+    pub address_length: usize,
+    pub functions: Vec<AbiFunction>,
+    /// The structs that can be stored as resources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<AbiResource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiFunction {
+    /// `module::function`, the name the default selector is derived from.
+    pub name: String,
+    /// Hex encoded selector in call data byte order, e.g. `0xfa1e1f30`.
+    pub selector: String,
+    pub params: Vec<AbiType>,
+    /// The names of `params`, as in the source.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub param_names: Vec<String>,
+    pub returns: Vec<AbiType>,
+    /// Attributes passed on by the compiler, e.g. `{ "payable": null }`, see
+    /// [`move_polka_codegen::stackless::function_attributes`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, Option<String>>,
+}
+
+impl AbiFunction {
+    /// The selector as the first 4 bytes of the call data.
+    pub fn selector_bytes(&self) -> Result<[u8; 4]> {
+        decode_hex(&self.selector)?.try_into().map_err(|_| {
+            MoveToPolkaError::codec(format!(
+                "selector `{}` of `{}` is not 4 bytes",
+                self.selector, self.name
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AbiType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    /// A `&signer` parameter, filled in from the origin of the call.
+    Signer,
+    Vector(Box<AbiType>),
+    Struct(AbiStruct),
+    /// A type that can't be encoded, e.g. a type parameter of a generic function, by name.
+    Opaque(String),
+}
+
+/// A struct type, e.g. `{ "name": "0xa::m::Point", "fields": [{ "name": "x", "type": "u64" }] }`.
+/// Structs are encoded as their fields, in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiStruct {
+    pub name: String,
+    pub fields: Vec<AbiField>,
+}
+
+/// A struct with the `key` ability, e.g.
+/// `{ "name": "0xa000::storage::Counter", "tag": "5e1f...", "fields": [{ "name": "value", "type": "u64" }] }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiResource {
+    /// The struct tag, as hashed into `tag`. Instantiations of a generic struct share it.
+    pub name: String,
+    /// Hex encoded SHA-256 hash of `name`, the key of the resource besides its address.
+    pub tag: String,
+    /// The fields, opaque where they depend on a type parameter.
+    pub fields: Vec<AbiField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: AbiType,
+}
+
+impl Abi {
+    /// The ABI of all entry functions in `global_env`, with the selectors `call_selector`
+    /// dispatches them with, the `selector_table` the code generator recorded in its
+    /// `ExportRegistry`.
+    pub fn new(
+        global_env: &GlobalEnv,
+        options: &Options,
+        selector_table: &BTreeMap<u32, String>,
+    ) -> Result<Self> {
+        let codegen_options = options.codegen_options();
+        let selectors: BTreeMap<&str, u32> = selector_table
+            .iter()
+            .map(|(selector, name)| (name.as_str(), *selector))
+            .collect();
+        let mut functions = vec![];
+        for fn_env in global_env
+            .get_modules()
+            .flat_map(|m| m.into_functions())
+            .filter(|f| f.is_entry())
+        {
+            let name = fn_env.get_full_name_str();
+            let selector = *selectors.get(name.as_str()).ok_or_else(|| {
+                MoveToPolkaError::compile(format!("entry function `{name}` is not dispatched"))
+            })?;
+            let returns = match fn_env.get_result_type() {
+                Type::Tuple(tys) => tys,
+                ty => vec![ty],
+            };
+            functions.push(AbiFunction {
+                name,
+                selector: format!("0x{:08x}", u32::from_be_bytes(selector.to_le_bytes())),
+                params: fn_env
+                    .get_parameter_types()
+                    .iter()
+                    .map(|ty| AbiType::of(&fn_env, ty))
+                    .collect(),
+                param_names: parameter_names(&fn_env),
+                returns: returns.iter().map(|ty| AbiType::of(&fn_env, ty)).collect(),
+                attributes: function_attributes(&fn_env, &codegen_options)
+                    .into_iter()
+                    .map(|attr| (attr.name, attr.value))
+                    .collect(),
+            });
+        }
+        let display_ctx = global_env.get_type_display_ctx();
+        let resources = global_env
+            .get_modules()
+            .flat_map(|m| m.into_structs())
+            .filter(|s| s.get_abilities().has_key())
+            .map(|struct_env| {
+                let name = struct_env.get_full_name_with_address();
+                let ty = Type::Struct(
+                    struct_env.module_env.get_id(),
+                    struct_env.get_id(),
+                    struct_env
+                        .get_type_parameters()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, _)| Type::TypeParameter(i as u16))
+                        .collect(),
+                );
+                let AbiType::Struct(layout) = AbiType::of_type(global_env, &display_ctx, &ty)
+                else {
+                    unreachable!("a struct type");
+                };
+                AbiResource {
+                    tag: hex::encode(Sha256::digest(name.as_bytes())),
+                    name,
+                    fields: layout.fields,
+                }
+            })
+            .collect();
+        Ok(Self {
+            codec: CODEC.to_string(),
+            address_length: codegen_options.address_length,
+            functions,
+            resources,
+        })
+    }
+
+    /// Look up `function`, given as `module::function`.
+    pub fn function(&self, function: &str) -> Result<&AbiFunction> {
+        self.functions
+            .iter()
+            .find(|f| f.name == function)
+            .ok_or_else(|| {
+                MoveToPolkaError::codec(format!("no entry function `{function}` in the ABI"))
+            })
+    }
+
+    /// The hash of the selector table of the blob these are the entry functions of, as in
+    /// its metadata, see [`crate::metadata::hash_selector_table`].
+    pub fn selector_table_hash(&self) -> Result<[u8; 32]> {
+        let mut table = BTreeMap::new();
+        for function in &self.functions {
+            table.insert(
+                u32::from_le_bytes(function.selector_bytes()?),
+                function.name.clone(),
+            );
+        }
+        Ok(hash_selector_table(&table))
+    }
+
+    /// Call data invoking `function` with the JSON `args`, one per non-signer parameter:
+    /// the selector followed by the BCS encoded arguments.
+    pub fn encode_call(&self, function: &str, args: &[Value]) -> Result<Vec<u8>> {
+        let function = self.function(function)?;
+        let params = function
+            .params
+            .iter()
+            .filter(|ty| **ty != AbiType::Signer)
+            .collect::<Vec<_>>();
+        if args.len() != params.len() {
+            return Err(MoveToPolkaError::codec(format!(
+                "`{}` takes {} argument(s), got {}",
+                function.name,
+                params.len(),
+                args.len()
+            )));
+        }
+        let mut call_data = decode_hex(&function.selector)?;
+        for (ty, arg) in params.into_iter().zip(args) {
+            ty.encode_with(arg, self.address_length, &mut call_data)?;
+        }
+        Ok(call_data)
+    }
+
+    /// Decode the BCS encoded return values of `function` into JSON.
+    pub fn decode_return(&self, function: &str, bytes: &[u8]) -> Result<Vec<Value>> {
+        let function = self.function(function)?;
+        let mut bytes = bytes;
+        let values = function
+            .returns
+            .iter()
+            .map(|ty| ty.decode_with(&mut bytes, self.address_length, decode_uleb128))
+            .collect::<Result<Vec<_>>>()?;
+        if !bytes.is_empty() {
+            return Err(MoveToPolkaError::codec(format!(
+                "{} trailing byte(s) after the return values of `{}`",
+                bytes.len(),
+                function.name
+            )));
+        }
+        Ok(values)
+    }
+
+    /// Look up the resource stored under the struct tag hash `tag`.
+    pub fn resource(&self, tag: &[u8]) -> Option<&AbiResource> {
+        let tag = hex::encode(tag);
+        self.resources.iter().find(|resource| resource.tag == tag)
+    }
+
+    /// Decode the stored bytes of `resource` into JSON, see [`AbiResource::decode`].
+    pub fn decode_resource(&self, resource: &AbiResource, bytes: &[u8]) -> Result<Value> {
+        resource.decode_with(bytes, self.address_length)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!("malformed ABI {}: {e}", path.display()))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+impl AbiResource {
+    /// Decode the stored bytes of the resource into JSON, see [`AbiType::decode_stored`].
+    pub fn decode(&self, bytes: &[u8]) -> Result<Value> {
+        self.decode_with(bytes, AccountAddress::LENGTH)
+    }
+
+    fn decode_with(&self, bytes: &[u8], address_length: usize) -> Result<Value> {
+        let layout = AbiType::Struct(AbiStruct {
+            name: self.name.clone(),
+            fields: self.fields.clone(),
+        });
+        let mut bytes = bytes;
+        let value = layout.decode_with(&mut bytes, address_length, decode_stored_len)?;
+        if !bytes.is_empty() {
+            return Err(MoveToPolkaError::codec(format!(
+                "{} trailing byte(s) after the {} resource",
+                bytes.len(),
+                self.name
+            )));
+        }
+        Ok(value)
+    }
+}
+
+impl AbiType {
+    /// The ABI type of `ty` in the signature of `fn_env`. References are passed like the
+    /// values they point to.
+    pub fn of(fn_env: &FunctionEnv, ty: &Type) -> Self {
+        Self::of_type(fn_env.module_env.env, &fn_env.get_type_display_ctx(), ty)
+    }
+
+    /// The ABI type of `ty`, naming types as `display_ctx` does.
+    fn of_type(env: &GlobalEnv, display_ctx: &TypeDisplayContext, ty: &Type) -> Self {
+        match ty {
+            Type::Primitive(PrimitiveType::Bool) => AbiType::Bool,
+            Type::Primitive(PrimitiveType::U8) => AbiType::U8,
+            Type::Primitive(PrimitiveType::U16) => AbiType::U16,
+            Type::Primitive(PrimitiveType::U32) => AbiType::U32,
+            Type::Primitive(PrimitiveType::U64) => AbiType::U64,
+            Type::Primitive(PrimitiveType::U128) => AbiType::U128,
+            Type::Primitive(PrimitiveType::U256) => AbiType::U256,
+            Type::Primitive(PrimitiveType::Address) => AbiType::Address,
+            Type::Primitive(PrimitiveType::Signer) => AbiType::Signer,
+            Type::Reference(_, ty) => AbiType::of_type(env, display_ctx, ty),
+            Type::Vector(ty) => AbiType::Vector(Box::new(AbiType::of_type(env, display_ctx, ty))),
+            Type::Struct(mid, sid, tys) => {
+                let struct_env = env.get_struct(mid.qualified(*sid));
+                let symbol_pool = struct_env.symbol_pool();
+                let fields = struct_env
+                    .get_fields()
+                    .map(|field| AbiField {
+                        name: field.get_name().display(symbol_pool).to_string(),
+                        ty: AbiType::of_type(env, display_ctx, &field.get_type().instantiate(tys)),
+                    })
+                    .collect();
+                AbiType::Struct(AbiStruct {
+                    name: ty.display(display_ctx).to_string(),
+                    fields,
+                })
+            }
+            _ => AbiType::Opaque(ty.display(display_ctx).to_string()),
+        }
+    }
+
+    /// Append the BCS encoding of the JSON `value` to `out`, with addresses of the Move VM's
+    /// length.
+    pub fn encode(&self, value: &Value, out: &mut Vec<u8>) -> Result<()> {
+        self.encode_with(value, AccountAddress::LENGTH, out)
+    }
+
+    /// Append the BCS encoding of the JSON `value` to `out`, with addresses of
+    /// `address_length` bytes.
+    pub fn encode_with(
+        &self,
+        value: &Value,
+        address_length: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        let invalid = || MoveToPolkaError::codec(format!("expected a {self:?} value, got {value}"));
+        let int = |max: u64| value.as_u64().filter(|n| *n <= max).ok_or_else(invalid);
+        match self {
+            AbiType::Bool => out.push(value.as_bool().ok_or_else(invalid)? as u8),
+            AbiType::U8 => out.push(int(u8::MAX.into())? as u8),
+            AbiType::U16 => out.extend((int(u16::MAX.into())? as u16).to_le_bytes()),
+            AbiType::U32 => out.extend((int(u32::MAX.into())? as u32).to_le_bytes()),
+            AbiType::U64 => out.extend(int(u64::MAX)?.to_le_bytes()),
+            AbiType::U128 => {
+                let n = match value {
+                    Value::String(s) => s.parse::<u128>().map_err(|_| invalid())?,
+                    _ => value.as_u64().ok_or_else(invalid)? as u128,
+                };
+                out.extend(n.to_le_bytes());
+            }
+            AbiType::U256 => {
+                let n = match value {
+                    Value::String(s) => s.parse::<U256>().map_err(|_| invalid())?,
+                    _ => U256::from(value.as_u64().ok_or_else(invalid)?),
+                };
+                out.extend(n.to_le_bytes());
+            }
+            AbiType::Address => {
+                let address = value
+                    .as_str()
+                    .and_then(|s| AccountAddress::from_hex_literal(s).ok())
+                    .ok_or_else(invalid)?;
+                // the address is right-aligned, the bytes cut off must be zero
+                let (high, low) = address
+                    .as_ref()
+                    .split_at(AccountAddress::LENGTH - address_length);
+                if high.iter().any(|b| *b != 0) {
+                    return Err(MoveToPolkaError::codec(format!(
+                        "address {value} doesn't fit in {address_length} bytes"
+                    )));
+                }
+                out.extend(low);
+            }
+            AbiType::Vector(ty) => match (value, &**ty) {
+                (Value::String(s), AbiType::U8) => {
+                    let bytes = decode_hex(s)?;
+                    encode_uleb128(bytes.len() as u64, out);
+                    out.extend(bytes);
+                }
+                (Value::Array(elements), _) => {
+                    // signers in vectors are passed like addresses, only a `&signer`
+                    // parameter is the origin of the call
+                    let ty = match &**ty {
+                        AbiType::Signer => &AbiType::Address,
+                        ty => ty,
+                    };
+                    encode_uleb128(elements.len() as u64, out);
+                    for element in elements {
+                        ty.encode_with(element, address_length, out)?;
+                    }
+                }
+                _ => return Err(invalid()),
+            },
+            AbiType::Signer => {
+                return Err(MoveToPolkaError::codec(
+                    "signer values can't be encoded, the signer is the origin of the call",
+                ))
+            }
+            AbiType::Struct(abi_struct) => {
+                // an object with all fields by name, or an array of the fields in order
+                let values = match value {
+                    Value::Object(values) => abi_struct
+                        .fields
+                        .iter()
+                        .map(|field| values.get(&field.name).ok_or_else(invalid))
+                        .collect::<Result<Vec<_>>>()?,
+                    Value::Array(values) if values.len() == abi_struct.fields.len() => {
+                        values.iter().collect()
+                    }
+                    _ => return Err(invalid()),
+                };
+                for (field, value) in abi_struct.fields.iter().zip(values) {
+                    match &field.ty {
+                        AbiType::Signer => {
+                            AbiType::Address.encode_with(value, address_length, out)?
+                        }
+                        ty => ty.encode_with(value, address_length, out)?,
+                    }
+                }
+            }
+            AbiType::Opaque(name) => {
+                return Err(MoveToPolkaError::codec(format!(
+                    "values of {name} can't be encoded"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a BCS encoded value from the front of `bytes` into JSON, with addresses of the
+    /// Move VM's length.
+    pub fn decode(&self, bytes: &mut &[u8]) -> Result<Value> {
+        self.decode_with(bytes, AccountAddress::LENGTH, decode_uleb128)
+    }
+
+    /// Decode a value from the front of `bytes` as the runtime stores resources, into JSON.
+    /// The encoding is the one of [`AbiType::decode`], with the vector lengths as
+    /// little-endian `u32` instead of ULEB128.
+    pub fn decode_stored(&self, bytes: &mut &[u8]) -> Result<Value> {
+        self.decode_with(bytes, AccountAddress::LENGTH, decode_stored_len)
+    }
+
+    fn decode_with(
+        &self,
+        bytes: &mut &[u8],
+        address_length: usize,
+        decode_len: fn(&mut &[u8]) -> Result<u64>,
+    ) -> Result<Value> {
+        Ok(match self {
+            AbiType::Bool => match take::<1>(bytes)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                [b] => return Err(MoveToPolkaError::codec(format!("invalid bool byte {b}"))),
+            },
+            AbiType::U8 => Value::from(take::<1>(bytes)?[0]),
+            AbiType::U16 => Value::from(u16::from_le_bytes(take(bytes)?)),
+            AbiType::U32 => Value::from(u32::from_le_bytes(take(bytes)?)),
+            AbiType::U64 => Value::from(u64::from_le_bytes(take(bytes)?)),
+            AbiType::U128 => Value::from(u128::from_le_bytes(take(bytes)?).to_string()),
+            AbiType::U256 => Value::from(U256::from_le_bytes(&take(bytes)?).to_string()),
+            AbiType::Address | AbiType::Signer => {
+                if bytes.len() < address_length {
+                    return Err(MoveToPolkaError::codec("unexpected end of return data"));
+                }
+                let (low, rest) = bytes.split_at(address_length);
+                *bytes = rest;
+                let mut address = [0; AccountAddress::LENGTH];
+                address[AccountAddress::LENGTH - address_length..].copy_from_slice(low);
+                Value::from(AccountAddress::new(address).to_hex_literal())
+            }
+            AbiType::Vector(ty) => {
+                let len = decode_len(bytes)?;
+                let elements = (0..len)
+                    .map(|_| ty.decode_with(bytes, address_length, decode_len))
+                    .collect::<Result<Vec<_>>>()?;
+                Value::Array(elements)
+            }
+            AbiType::Struct(abi_struct) => {
+                let mut values = serde_json::Map::new();
+                for field in &abi_struct.fields {
+                    values.insert(
+                        field.name.clone(),
+                        field.ty.decode_with(bytes, address_length, decode_len)?,
+                    );
+                }
+                Value::Object(values)
+            }
+            AbiType::Opaque(name) => {
+                return Err(MoveToPolkaError::codec(format!(
+                    "values of {name} can't be decoded"
+                )))
+            }
+        })
+    }
+}
+
+/// Path of the ABI belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.abi.json`.
+pub fn abi_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(ABI_EXTENSION)
+}
+
+fn default_address_length() -> usize {
+    AccountAddress::LENGTH
+}
+
+fn is_default_address_length(address_length: &usize) -> bool {
+    *address_length == AccountAddress::LENGTH
+}
+
+fn decode_stored_len(bytes: &mut &[u8]) -> Result<u64> {
+    Ok(u32::from_le_bytes(take(bytes)?).into())
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N]> {
+    if bytes.len() < N {
+        return Err(MoveToPolkaError::codec("unexpected end of return data"));
+    }
+    let (head, rest) = bytes.split_at(N);
+    *bytes = rest;
+    Ok(head.try_into().expect("length checked"))
+}
+
+pub(crate) fn encode_uleb128(mut n: u64, out: &mut Vec<u8>) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+pub(crate) fn decode_uleb128(bytes: &mut &[u8]) -> Result<u64> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let [byte] = take::<1>(bytes)?;
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(MoveToPolkaError::codec("vector length overflows u64"))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    hex::decode(s.trim_start_matches("0x"))
+        .map_err(|e| MoveToPolkaError::codec(format!("invalid hex `{s}`: {e}")))
+}
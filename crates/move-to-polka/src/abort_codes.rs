@@ -0,0 +1,119 @@
+//! Names of abort codes, so reports of aborts read `abort: E_INSUFFICIENT_BALANCE (5)`.
+//!
+//! The codes the generated code and the native runtime abort with are known by name, e.g.
+//! `ARITHMETIC_ERROR` or `PANIC_CODE`. Application codes are given as a JSON object mapping
+//! names to codes, numbers or strings in decimal or hex:
+//!
+//! ```json
+//! { "E_INSUFFICIENT_BALANCE": 5, "E_NOT_OWNER": "0x10001" }
+//! ```
+//!
+//! A name given for a known code replaces the known name.
+
+use crate::error::{MoveToPolkaError, Result};
+use move_core_types::vm_status::StatusCode;
+use polkavm_move_native::{
+    ALLOC_CODE, BCS_DESERIALIZATION_FAILURE, CALL_FAILURE_CODE, INVALID_REFERENCE_CODE,
+    MALFORMED_INPUT_CODE, MISSING_ABILITY_CODE, NOT_PAYABLE_CODE, PANIC_CODE,
+    TRANSFER_FAILURE_CODE,
+};
+use std::{collections::BTreeMap, path::Path};
+
+/// `EINDEX_OUT_OF_BOUNDS` of `std::vector`, aborted with by `remove`, `swap` and friends.
+///
+/// Only the standard library's Move code aborts with it. Out-of-bounds accesses caught by the
+/// native runtime, e.g. `vector::borrow` past the end, panic and abort with `PANIC_CODE`,
+/// like any other runtime panic; the panic message is logged.
+pub const VECTOR_OUT_OF_BOUNDS: u64 = 0x20000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbortCodes {
+    names: BTreeMap<u64, String>,
+}
+
+impl Default for AbortCodes {
+    /// The codes of the generated code, the native runtime and the standard library.
+    fn default() -> Self {
+        let known = [
+            ("ARITHMETIC_ERROR", StatusCode::ARITHMETIC_ERROR as u64),
+            ("VECTOR_OUT_OF_BOUNDS", VECTOR_OUT_OF_BOUNDS),
+            ("PANIC_CODE", PANIC_CODE),
+            ("ALLOC_CODE", ALLOC_CODE),
+            ("MISSING_ABILITY_CODE", MISSING_ABILITY_CODE),
+            ("BCS_DESERIALIZATION_FAILURE", BCS_DESERIALIZATION_FAILURE),
+            ("TRANSFER_FAILURE_CODE", TRANSFER_FAILURE_CODE),
+            ("NOT_PAYABLE_CODE", NOT_PAYABLE_CODE),
+            ("CALL_FAILURE_CODE", CALL_FAILURE_CODE),
+            ("MALFORMED_INPUT_CODE", MALFORMED_INPUT_CODE),
+            ("INVALID_REFERENCE_CODE", INVALID_REFERENCE_CODE),
+        ];
+        Self {
+            names: known
+                .into_iter()
+                .map(|(name, code)| (code, name.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl AbortCodes {
+    /// The known codes and the application codes of the JSON mapping at `path`.
+    pub fn read(path: &Path) -> Result<Self> {
+        let malformed = |e: String| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed abort code mapping {}: {e}",
+                path.display()
+            ))
+        };
+        let json = std::fs::read(path)?;
+        let mapping: BTreeMap<String, serde_json::Value> =
+            serde_json::from_slice(&json).map_err(|e| malformed(e.to_string()))?;
+        let mut codes = Self::default();
+        for (name, code) in mapping {
+            let code = match &code {
+                serde_json::Value::Number(code) => code.as_u64(),
+                serde_json::Value::String(code) => parse_code(code),
+                _ => None,
+            }
+            .ok_or_else(|| malformed(format!("`{name}`: {code} is not an abort code")))?;
+            codes = codes.with(&name, code);
+        }
+        Ok(codes)
+    }
+
+    /// Name `code` `name`, replacing its known name.
+    pub fn with(mut self, name: &str, code: u64) -> Self {
+        self.names.insert(code, name.to_string());
+        self
+    }
+
+    pub fn name(&self, code: u64) -> Option<&str> {
+        self.names.get(&code).map(String::as_str)
+    }
+
+    /// `NAME (code)`, or just the code if it has no name.
+    pub fn describe(&self, code: u64) -> String {
+        match self.name(code) {
+            Some(name) => format!("{name} ({code})"),
+            None => code.to_string(),
+        }
+    }
+
+    /// The code `code` names: a number in decimal or hex, or a name.
+    pub fn parse(&self, code: &str) -> Option<u64> {
+        parse_code(code).or_else(|| {
+            self.names
+                .iter()
+                .find(|(_, name)| *name == code)
+                .map(|(code, _)| *code)
+        })
+    }
+}
+
+/// A code in decimal or, prefixed with `0x`, in hex.
+fn parse_code(code: &str) -> Option<u64> {
+    match code.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => code.parse().ok(),
+    }
+}
@@ -0,0 +1,213 @@
+//! The files a build writes, and how to clean them up.
+//!
+//! Every artifact is named after the blob and written next to it, into `--out-dir` if given:
+//!
+//! | artifact                                   | written with       |
+//! |--------------------------------------------|--------------------|
+//! | `<name>.polkavm`                           | always             |
+//! | `<name>.abi.json`                          | `--emit-abi`       |
+//! | `<name>.metadata.json`                     | `--emit-metadata`  |
+//! | `<name>.sourcemap.json`                    | `--source-map`     |
+//! | `<name>.coverage.json`                     | `--coverage`       |
+//! | `<name>.stacktrace.json`                   | `--stack-traces`   |
+//! | `<name>.sig.json`                          | `--signing-key`    |
+//! | `<name>.elf`                               | `--emit-elf`       |
+//! | `<name>.modules.json`                      | `--split-modules`  |
+//! | `<name>.<address>_<module>.polkavm`        | `--split-modules`  |
+//! | `<name>.build-plan.json`                   | `--build-plan`     |
+//! | `<name>.defines.move`                      | `--define`         |
+//! | `<name>.polkavm.sources/`                  | virtual sources    |
+//!
+//! The objects of the modules and the merged ELF are intermediates, written to a temporary
+//! directory of every build (see `--keep-temps`), never next to the blob. A build removes the
+//! artifacts of the previous build of the same blob first, so rebuilding with other flags, or
+//! after removing a module, leaves no stale files behind. The build plan and the generated
+//! defines module are the exceptions: they are written before the other artifacts are
+//! removed, and replaced or removed by the build itself.
+
+use crate::{
+    abi::abi_path, build_plan::build_plan_path, coverage::coverage_map_path, defines::defines_path,
+    error::Result, linker::elf_path, metadata::metadata_path, options::Options,
+    signing::signature_path, source_map::source_map_path, split, stack_trace::stack_trace_map_path,
+    vfs::VirtualFiles,
+};
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+/// File name suffix of blobs.
+pub const BLOB_EXTENSION: &str = "polkavm";
+
+/// The artifacts of the blob at `blob_path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifacts {
+    blob_path: PathBuf,
+}
+
+impl Artifacts {
+    pub fn new(blob_path: impl Into<PathBuf>) -> Self {
+        Self {
+            blob_path: blob_path.into(),
+        }
+    }
+
+    /// The artifacts of the blob `<out_dir>/<name>.polkavm`.
+    pub fn in_dir(out_dir: &Path, name: &str) -> Self {
+        Self::new(out_dir.join(name).with_extension(BLOB_EXTENSION))
+    }
+
+    pub fn blob_path(&self) -> &Path {
+        &self.blob_path
+    }
+
+    /// Every artifact a build of the blob may write, whether it exists or not. The blobs of
+    /// the modules are those listed in the manifest, if there is one.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let blob = self.blob_path.as_path();
+        let mut module_blobs = vec![];
+        // a manifest that can't be read lists no blobs worth keeping either
+        if let Ok(manifest) = split::SplitManifest::read(&split::manifest_path(blob)) {
+            let dir = blob.parent().unwrap_or(Path::new(""));
+            module_blobs.extend(
+                manifest
+                    .modules
+                    .iter()
+                    .filter(|module| self.is_module_blob(&module.blob))
+                    .map(|module| dir.join(&module.blob)),
+            );
+        }
+        self.collect(None, module_blobs)
+    }
+
+    /// The artifacts a build with `options` writes, in the order of [`Artifacts::paths`], with
+    /// a blob per module of `module_names` (see [`split::module_file_name`]) for
+    /// `--split-modules`. With `-c` or `-S` that is only the defines module and the build
+    /// plan, the objects or IR are written instead of a blob.
+    pub fn written_by(
+        &self,
+        options: &Options,
+        module_names: impl IntoIterator<Item = String>,
+    ) -> Vec<PathBuf> {
+        let blob = self.blob_path.as_path();
+        if options.compile || options.llvm_ir {
+            let defines = (!options.defines.is_empty()).then(|| defines_path(blob));
+            return defines.into_iter().chain([build_plan_path(blob)]).collect();
+        }
+        let module_blobs = module_names
+            .into_iter()
+            .map(|name| split::module_blob_path(blob, &name))
+            .collect();
+        self.collect(Some(options), module_blobs)
+    }
+
+    /// The artifacts a build with `options` writes, or may write without `options`.
+    fn collect(&self, options: Option<&Options>, module_blobs: Vec<PathBuf>) -> Vec<PathBuf> {
+        let blob = self.blob_path.as_path();
+        let written = |flag: fn(&Options) -> bool| match options {
+            Some(options) => flag(options),
+            None => true,
+        };
+        let optional: [(fn(&Options) -> bool, PathBuf); 7] = [
+            (|options| options.emit_abi, abi_path(blob)),
+            (
+                |options| options.emit_metadata || options.signing_key.is_some(),
+                metadata_path(blob),
+            ),
+            (|options| options.source_map, source_map_path(blob)),
+            (|options| options.coverage, coverage_map_path(blob)),
+            (|options| options.stack_traces, stack_trace_map_path(blob)),
+            (
+                |options| options.signing_key.is_some(),
+                signature_path(blob),
+            ),
+            (|options| options.emit_elf, elf_path(blob)),
+        ];
+        let mut paths = vec![blob.to_path_buf()];
+        paths.extend(
+            optional
+                .into_iter()
+                .filter_map(|(flag, path)| written(flag).then_some(path)),
+        );
+        if written(|options| options.split_modules) {
+            paths.extend(module_blobs);
+            paths.push(split::manifest_path(blob));
+        }
+        if written(|options| !options.defines.is_empty()) {
+            paths.push(defines_path(blob));
+        }
+        paths.push(build_plan_path(blob));
+        paths
+    }
+
+    /// Whether `name`, a blob listed in the manifest, is the file name of a module blob of the
+    /// blob, `<name>.<address>_<module>.polkavm`. The manifest is read from disk, so anything
+    /// else, such as a path into another directory, is never removed.
+    fn is_module_blob(&self, name: &str) -> bool {
+        let (Some(stem), Some(extension)) = (
+            self.blob_path.file_stem().and_then(|stem| stem.to_str()),
+            self.blob_path
+                .extension()
+                .and_then(|extension| extension.to_str()),
+        ) else {
+            return false;
+        };
+        let mut components = Path::new(name).components();
+        matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(file)), None) if file == name
+        ) && name
+            .strip_prefix(stem)
+            .and_then(|name| name.strip_prefix('.'))
+            .and_then(|name| name.strip_suffix(extension))
+            .and_then(|module| module.strip_suffix('.'))
+            .is_some_and(|module| !module.is_empty())
+    }
+
+    /// The directory virtual sources are staged in to build the blob.
+    pub fn staging_dir(&self) -> PathBuf {
+        VirtualFiles::staging_dir(&self.blob_path.to_string_lossy())
+    }
+
+    /// Make way for a new build: create the directory of the blob and remove the artifacts
+    /// of the previous build, except the build plan and the defines module the new build has
+    /// written already.
+    pub fn prepare(&self) -> Result<()> {
+        if let Some(dir) = self
+            .blob_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+        let kept = [
+            build_plan_path(&self.blob_path),
+            defines_path(&self.blob_path),
+        ];
+        self.remove_files(|path| !kept.contains(path))?;
+        Ok(())
+    }
+
+    /// Remove the artifacts that exist, the staged virtual sources included, returning their
+    /// paths.
+    pub fn clean(&self) -> Result<Vec<PathBuf>> {
+        let mut removed = self.remove_files(|_| true)?;
+        let staging_dir = self.staging_dir();
+        if staging_dir.is_dir() {
+            std::fs::remove_dir_all(&staging_dir)?;
+            debug!("Removed {}", staging_dir.display());
+            removed.push(staging_dir);
+        }
+        Ok(removed)
+    }
+
+    fn remove_files(&self, remove: impl Fn(&PathBuf) -> bool) -> Result<Vec<PathBuf>> {
+        let mut removed = vec![];
+        for path in self.paths().into_iter().filter(remove) {
+            if path.is_file() {
+                std::fs::remove_file(&path)?;
+                debug!("Removed {}", path.display());
+                removed.push(path);
+            }
+        }
+        Ok(removed)
+    }
+}
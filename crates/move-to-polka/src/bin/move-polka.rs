@@ -0,0 +1,211 @@
+//! `move build`-style front end: builds the package described by `Move.toml` and places
+//! the blob under `build/<package>/polkavm/`, next to the artifacts of other Move backends.
+
+#![forbid(unsafe_code)]
+
+use std::{collections::HashSet, path::Path};
+
+use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use move_to_polka::{
+    debug_codegen_areas, initialize_logger_with,
+    linker::{
+        build_polka_from_move, create_colored_stdout, package_artifact_path, package_build_options,
+        parse_to_blob,
+    },
+    native::{AddressLength, NativeVariant},
+    run_to_polka,
+};
+use tracing::info;
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about)]
+struct Args {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Build the package and place the blob under `build/<package>/polkavm/`
+    Build {
+        #[arg(short, long, default_value = ".")]
+        // path to the package (a directory containing Move.toml)
+        path: String,
+        #[arg(long, default_value = "polkavm")]
+        // target architecture, only `polkavm` is supported
+        arch: String,
+        #[arg(long)]
+        // directory to place the `build` directory in, defaults to the package root
+        install_dir: Option<String>,
+        #[arg(long, alias = "no-emit")]
+        // only check the sources for unsupported constructs, do not emit a blob
+        check: bool,
+        #[arg(long)]
+        // also export the public functions of the package, without a call selector
+        export_public: bool,
+        #[arg(long)]
+        // export only call and deploy, entry functions are still dispatched by the call selector
+        strip_exports: bool,
+        #[arg(long = "extra-export", value_delimiter = ',')]
+        // functions (module::function) to export in addition, also with --strip-exports
+        extra_exports: Vec<String>,
+        #[arg(long)]
+        // revert calls transferring value to entry functions without #[payable]
+        payable_checks: bool,
+        #[arg(long = "keep-attribute", value_delimiter = ',')]
+        // function attributes to list in the ABI and metadata besides selector, payable, deprecated
+        keep_attributes: Vec<String>,
+        #[arg(long)]
+        // compile calls to the debug natives to no-ops
+        strip_debug_natives: bool,
+        #[arg(long)]
+        // write package metadata next to the blob
+        emit_metadata: bool,
+        #[arg(long)]
+        // sign the blob with the hex ed25519 key in this file, or in $NAME if given as env:NAME
+        signing_key: Option<String>,
+        #[arg(long, default_value_t = polkavm_move_native::DEFAULT_HEAP_PAGES)]
+        // number of 4 KiB pages reserved for the guest heap
+        heap_pages: u32,
+        #[arg(long)]
+        // minimum guest stack size in bytes
+        stack_size: Option<u32>,
+        #[arg(long)]
+        // write a JSON source map next to the blob
+        source_map: bool,
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        // abort on unsigned arithmetic overflow, `--overflow-checks false` to wrap instead
+        overflow_checks: bool,
+        #[arg(long)]
+        // omit the arithmetic checks of code proven not to abort on them
+        assume_no_abort: bool,
+        #[arg(long)]
+        // count basic block executions and write a coverage map next to the blob
+        coverage: bool,
+        #[arg(long)]
+        // let the host read the guest allocator's counters after a call
+        heap_stats: bool,
+        #[arg(long)]
+        // check references before dereferencing them, aborting on invalid ones
+        paranoid: bool,
+        #[arg(long)]
+        // record a shadow stack and hand it to the host on abort, implies -g
+        stack_traces: bool,
+        #[arg(long)]
+        // write the entry function ABI next to the blob
+        emit_abi: bool,
+        #[arg(long)]
+        // also link a blob per module and write the manifest of their imports
+        split_modules: bool,
+        #[arg(long)]
+        // link all Move modules into one LLVM module before optimizing, for cross-module inlining
+        lto: bool,
+        #[arg(long, default_value_t = NativeVariant::DEFAULT)]
+        // embedded move-native runtime to link, rv64-<debug|release>
+        move_native_variant: NativeVariant,
+        #[arg(long, default_value_t = AddressLength::Bytes32)]
+        // length of Move addresses in bytes, 20 for pallet-revive H160 accounts or 32
+        address_length: AddressLength,
+        #[arg(long, conflicts_with = "quiet_warnings")]
+        // fail the build when the Move compiler reports warnings
+        warn_as_error: bool,
+        #[arg(long)]
+        // do not print the warnings of the Move compiler
+        quiet_warnings: bool,
+        #[arg(long)]
+        // print the time spent in every compiler phase and the peak RSS
+        time_passes: bool,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = PossibleValuesParser::new(debug_codegen_areas())
+        )]
+        // debug-log these code generator areas: functions, structs, dwarf, rtcall, types or all
+        debug_codegen: Vec<String>,
+        #[arg(long)]
+        // write the log to this file instead of stderr
+        log_file: Option<String>,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    let Command::Build {
+        path,
+        arch,
+        install_dir,
+        check,
+        export_public,
+        strip_exports,
+        extra_exports,
+        payable_checks,
+        keep_attributes,
+        strip_debug_natives,
+        emit_metadata,
+        signing_key,
+        heap_pages,
+        stack_size,
+        source_map,
+        overflow_checks,
+        assume_no_abort,
+        coverage,
+        heap_stats,
+        paranoid,
+        stack_traces,
+        emit_abi,
+        split_modules,
+        lto,
+        move_native_variant,
+        address_length,
+        warn_as_error,
+        quiet_warnings,
+        time_passes,
+        debug_codegen,
+        log_file,
+    } = Args::parse().command;
+    initialize_logger_with(&debug_codegen, log_file.as_deref().map(Path::new))?;
+
+    if arch != "polkavm" {
+        anyhow::bail!("unsupported architecture `{arch}`, only `polkavm` is supported");
+    }
+    if check {
+        let build_options = package_build_options("output.polkavm", &path, HashSet::new())?
+            .check(true)
+            .warn_as_error(warn_as_error)
+            .quiet_warnings(quiet_warnings)
+            .time_passes(time_passes);
+        run_to_polka(&mut create_colored_stdout(), build_options.build())?;
+        return Ok(());
+    }
+
+    let output = package_artifact_path(&path, install_dir.as_deref())?;
+    let output = output.to_string_lossy();
+    let build_options = package_build_options(&output, &path, HashSet::new())?
+        .export_public(export_public)
+        .strip_exports(strip_exports)
+        .extra_exports(extra_exports)
+        .payable_checks(payable_checks)
+        .keep_attributes(keep_attributes)
+        .strip_debug_natives(strip_debug_natives)
+        .emit_metadata(emit_metadata)
+        .signing_key(signing_key)
+        .overflow_checks(overflow_checks)
+        .assume_no_abort(assume_no_abort)
+        .source_map(source_map)
+        .coverage(coverage)
+        .heap_stats(heap_stats)
+        .paranoid(paranoid)
+        .stack_traces(stack_traces)
+        .emit_abi(emit_abi)
+        .split_modules(split_modules)
+        .lto(lto)
+        .move_native_variant(move_native_variant)
+        .address_length(address_length)
+        .heap_pages(heap_pages)
+        .stack_size(stack_size)
+        .warn_as_error(warn_as_error)
+        .quiet_warnings(quiet_warnings)
+        .time_passes(time_passes);
+    parse_to_blob(&build_polka_from_move(build_options)?)?;
+    info!("Built {output}");
+    Ok(())
+}
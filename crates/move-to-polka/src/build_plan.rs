@@ -0,0 +1,147 @@
+//! The build plan: what a build compiles and writes, for external build systems.
+//!
+//! With `--build-plan` the compiler writes, once the sources are checked and before any code
+//! is generated, `<output>.build-plan.json` next to the blob; `--plan-only` stops there. It
+//! lists every module with its source, the modules it depends on and the object it is
+//! compiled to, the named addresses, and the artifacts the build writes, so wrappers such as
+//! Buck or Bazel rules can schedule and cache the steps themselves:
+//!
+//! ```json
+//! {
+//!   "output": "output/storage.polkavm",
+//!   "named_addresses": { "std": "0x1" },
+//!   "modules": [
+//!     {
+//!       "module": "0xa000::storage",
+//!       "source": "examples/storage/sources/storage.move",
+//!       "target": true,
+//!       "dependencies": ["0x1::signer"],
+//!       "object": "0xa000__storage.o"
+//!     }
+//!   ],
+//!   "artifacts": ["output/storage.polkavm", "output/storage.abi.json"]
+//! }
+//! ```
+
+use crate::{
+    artifacts::Artifacts,
+    error::{MoveToPolkaError, Result},
+    options::Options,
+    split,
+};
+use move_model::model::{GlobalEnv, ModuleEnv};
+use move_polka_codegen::stackless::extensions::ModuleEnvExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// File name suffix of the build plan written next to the blob.
+pub const BUILD_PLAN_EXTENSION: &str = "build-plan.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildPlan {
+    /// The blob, or the object or IR destination with `-c` or `-S`.
+    pub output: String,
+    pub named_addresses: BTreeMap<String, String>,
+    /// The modules in compilation order, dependencies first.
+    pub modules: Vec<PlannedModule>,
+    /// The files the build writes next to the blob, see [`Artifacts::written_by`].
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// A module the build compiles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedModule {
+    /// The module, as `address::module`.
+    pub module: String,
+    /// The Move source the module is defined in, empty for modules built from bytecode
+    /// without a source map.
+    pub source: PathBuf,
+    /// Whether the module belongs to the package being built rather than a dependency.
+    pub target: bool,
+    /// The modules it uses, as `address::module`.
+    pub dependencies: Vec<String>,
+    /// File name of the object it is compiled to, in the temporary directory of the build, or
+    /// of its LLVM IR with `-S`. With `--lto` every module is compiled into the same object.
+    pub object: String,
+}
+
+impl BuildPlan {
+    pub fn new(global_env: &GlobalEnv, options: &Options) -> Self {
+        let output = Path::new(&options.output);
+        let lto_object = options.lto.then(|| {
+            let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+            format!("{stem}.{}", options.output_file_extension)
+        });
+        let modules = global_env
+            .get_modules()
+            .map(|module| PlannedModule::new(&module, options, lto_object.as_deref()))
+            .collect();
+        Self {
+            output: options.output.clone(),
+            named_addresses: options.named_addresses(),
+            modules,
+            artifacts: Artifacts::new(output).written_by(
+                options,
+                split::split_modules(global_env).map(|module| split::module_file_name(&module)),
+            ),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed build plan {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Look up `module`, given as `address::module`.
+    pub fn module(&self, module: &str) -> Option<&PlannedModule> {
+        self.modules.iter().find(|planned| planned.module == module)
+    }
+}
+
+impl PlannedModule {
+    fn new(module: &ModuleEnv, options: &Options, lto_object: Option<&str>) -> Self {
+        let env = module.env;
+        let mut dependencies = module
+            .get_used_modules(false)
+            .into_iter()
+            .filter(|id| *id != module.get_id())
+            .map(|id| env.get_module(id).get_full_name_str())
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        let object = match lto_object {
+            Some(object) => object.to_string(),
+            None => format!(
+                "{}.{}",
+                module.llvm_module_name(),
+                options.output_file_extension
+            ),
+        };
+        Self {
+            module: module.get_full_name_str(),
+            source: module.get_source_path().to_path_buf(),
+            target: module.is_target(),
+            dependencies,
+            object,
+        }
+    }
+}
+
+/// Path of the build plan belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.build-plan.json`.
+pub fn build_plan_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(BUILD_PLAN_EXTENSION)
+}
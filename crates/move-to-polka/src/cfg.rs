@@ -0,0 +1,124 @@
+//! Control flow graphs of the compiled functions.
+//!
+//! With `--gen-dot-cfg`, the code generator records the graph of every function over its
+//! stackless bytecode as a [`FunctionCfg`], and the driver writes it to `--dot-out-dir`:
+//! `write` as a GraphViz dot file, `view` as a dot file opened in the `--dot-viewer` (`xdot` by
+//! default), and `json` as a JSON file for analysis tools that don't want to parse dot:
+//!
+//! ```json
+//! {
+//!   "function": "0xa000::vector::sum",
+//!   "entry_blocks": [0],
+//!   "blocks": [{ "id": 0, "instructions": ["$t2 := 0"], "successors": [3] }]
+//! }
+//! ```
+//!
+//! The files are named `<address>__<module>__<function>_cfg.dot` (or `.json`), or
+//! `cfg.dot` in a directory of each function with `--cfg-dir-per-function`.
+//! [`crate::compile_to_cfgs`] returns the graphs without writing anything.
+
+use crate::error::{MoveToPolkaError, Result};
+use clap::ValueEnum;
+pub use move_polka_codegen::instrument::{CfgBlock, FunctionCfg};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{debug, warn};
+
+/// The viewer `view` opens the dot files in, unless `--dot-viewer` names another.
+pub const DEFAULT_DOT_VIEWER: &str = "xdot";
+
+/// What `--gen-dot-cfg` makes of the control flow graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CfgOutput {
+    /// Write a GraphViz dot file per function.
+    Write,
+    /// Write the dot files and open each in the `--dot-viewer`.
+    View,
+    /// Write a JSON file per function.
+    Json,
+}
+
+impl CfgOutput {
+    fn extension(self) -> &'static str {
+        match self {
+            CfgOutput::Write | CfgOutput::View => "dot",
+            CfgOutput::Json => "json",
+        }
+    }
+}
+
+/// Path of the file of `cfg` under `out_dir`, see the module documentation.
+pub fn cfg_path(
+    out_dir: &Path,
+    cfg: &FunctionCfg,
+    output: CfgOutput,
+    dir_per_function: bool,
+) -> PathBuf {
+    let function = cfg.function.replace("::", "__");
+    if dir_per_function {
+        out_dir
+            .join(function)
+            .join(format!("cfg.{}", output.extension()))
+    } else {
+        out_dir.join(format!("{function}_cfg.{}", output.extension()))
+    }
+}
+
+/// Write `cfgs` to `out_dir` and return the paths of the files, in the order of `cfgs`.
+pub fn write_cfgs(
+    cfgs: &[FunctionCfg],
+    out_dir: &Path,
+    output: CfgOutput,
+    dir_per_function: bool,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for cfg in cfgs {
+        let path = cfg_path(out_dir, cfg, output, dir_per_function);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        match output {
+            CfgOutput::Write | CfgOutput::View => std::fs::write(&path, &cfg.dot)?,
+            CfgOutput::Json => {
+                let json =
+                    serde_json::to_vec_pretty(cfg).map_err(MoveToPolkaError::invalid_artifact)?;
+                std::fs::write(&path, json)?;
+            }
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Open each dot file at `paths` in `viewer`, a command with optional arguments the path is
+/// appended to. The viewers run in the background, so the build doesn't wait for them to be
+/// closed. Viewing is best-effort: a viewer that can't be started is logged as a warning, and
+/// the files stay written either way. Returns how many viewers were started.
+pub fn view_cfgs(paths: &[PathBuf], viewer: &str) -> usize {
+    let mut words = viewer.split_whitespace();
+    let Some(program) = words.next() else {
+        warn!("No --dot-viewer given, not opening the control flow graphs");
+        return 0;
+    };
+    let args: Vec<&str> = words.collect();
+    let mut started = 0;
+    for path in paths {
+        match Command::new(program).args(&args).arg(path).spawn() {
+            Ok(child) => {
+                debug!("{viewer} {} started as {}", path.display(), child.id());
+                started += 1;
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to start {viewer}, the control flow graphs are left in {}: {e}",
+                    path.parent().unwrap_or(Path::new(".")).display()
+                );
+                // the next file won't fare better
+                break;
+            }
+        }
+    }
+    started
+}
@@ -0,0 +1,124 @@
+//! Basic block coverage of Move programs.
+//!
+//! With `--coverage`, every basic block increments its own `u32` in the global
+//! [`COVERAGE_COUNTERS_SYMBOL`] array, and exported functions register that array with the
+//! host through the `coverage_register` import. The compiler writes a coverage map next
+//! to the blob, which tells the source line of every counter:
+//!
+//! ```json
+//! {
+//!   "counters": [{ "function": "0xa000::overflow::add_u8", "file": "/path/overflow.move", "line": 6 }]
+//! }
+//! ```
+//!
+//! After execution, [`crate::linker::read_coverage_counters`] reads the counters back and
+//! [`CoverageMap::report`] turns them into hit counts per source line.
+
+use crate::error::{MoveToPolkaError, Result};
+pub use move_polka_codegen::instrument::{
+    CoverageCounter, COVERAGE_COUNTERS_SYMBOL, COVERAGE_LEN_SYMBOL,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// File name suffix of the coverage map written next to a blob.
+pub const COVERAGE_MAP_EXTENSION: &str = "coverage.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageMap {
+    /// Counters in the order of the counter array.
+    pub counters: Vec<CoverageCounter>,
+}
+
+/// Hit counts per file and source line, see [`CoverageMap::report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, BTreeMap<u32, u64>>,
+}
+
+impl CoverageMap {
+    /// Add a counter and return its index in the counter array.
+    pub fn push(&mut self, counter: CoverageCounter) -> u32 {
+        self.counters.push(counter);
+        (self.counters.len() - 1) as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// Map the `counts` read from an instrumented blob back to source lines. Lines of
+    /// blocks that never ran are reported with a count of 0.
+    pub fn report(&self, counts: &[u32]) -> Result<CoverageReport> {
+        if counts.len() != self.counters.len() {
+            return Err(MoveToPolkaError::invalid_artifact(format!(
+                "coverage map has {} counters, but {} were read",
+                self.counters.len(),
+                counts.len()
+            )));
+        }
+        let mut report = CoverageReport::default();
+        for (counter, count) in self.counters.iter().zip(counts) {
+            *report
+                .files
+                .entry(counter.file.clone())
+                .or_default()
+                .entry(counter.line)
+                .or_default() += *count as u64;
+        }
+        Ok(report)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed coverage map {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+impl CoverageReport {
+    /// Number of instrumented lines and how many of them ran at least once.
+    pub fn summary(&self) -> (usize, usize) {
+        let lines = self.files.values().flat_map(|lines| lines.values());
+        lines.fold((0, 0), |(total, covered), count| {
+            (total + 1, covered + usize::from(*count > 0))
+        })
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (file, lines) in &self.files {
+            writeln!(f, "{file}")?;
+            for (line, count) in lines {
+                writeln!(f, "{line:>6}: {count}")?;
+            }
+        }
+        let (total, covered) = self.summary();
+        write!(f, "{covered}/{total} lines covered")
+    }
+}
+
+/// Path of the coverage map belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.coverage.json`.
+pub fn coverage_map_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(COVERAGE_MAP_EXTENSION)
+}
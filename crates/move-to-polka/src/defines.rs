@@ -0,0 +1,130 @@
+//! Compile-time flags, for building one source tree for several chains.
+//!
+//! Move has no conditional compilation, so every `--define NAME[=VALUE]` of a build becomes a
+//! function of the generated module `defines::config`, returning the value:
+//!
+//! ```move
+//! module defines::config {
+//!     public fun mainnet(): bool { true }
+//!     public fun max_supply(): u128 { 1000000u128 }
+//!     public fun treasury(): address { @0xcafe }
+//! }
+//! ```
+//!
+//! Sources read the flags with `use defines::config;` and `config::mainnet()`. A flag without
+//! a value is `true`; values are `true` or `false`, numbers with an optional integer suffix
+//! (`u64` without one), addresses (`@0x1` or `@name`) and byte strings in double quotes.
+//! A name may be given once. The module is written next to the output
+//! (`<output>.defines.move`), or to a temporary directory for `--check`, and compiled as a
+//! dependency, at the address of the named address `defines`, `0xdef` unless mapped
+//! otherwise. The flags are recorded in the blob metadata.
+
+use crate::error::{MoveToPolkaError, Result};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// The named address of the generated module.
+pub const DEFINES_ADDRESS_NAME: &str = "defines";
+
+/// The address of [`DEFINES_ADDRESS_NAME`], unless the named address mapping has it.
+pub const DEFAULT_DEFINES_ADDRESS: &str = "0xdef";
+
+/// File name suffix of the generated module, written next to the output.
+pub const DEFINES_EXTENSION: &str = "defines.move";
+
+const INTEGER_SUFFIXES: [&str; 6] = ["u8", "u16", "u32", "u64", "u128", "u256"];
+
+/// A `--define`, e.g. `max_supply=1000000u128`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Define {
+    pub name: String,
+    /// The Move type of the value, e.g. `u128`.
+    pub ty: String,
+    /// The value as a Move expression, e.g. `1000000u128`.
+    pub value: String,
+}
+
+impl Define {
+    /// Parse `NAME` or `NAME=VALUE`.
+    pub fn parse(define: &str) -> Result<Self> {
+        let (name, value) = match define.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (define.trim(), "true"),
+        };
+        let invalid =
+            |what: &str| MoveToPolkaError::compile(format!("invalid --define `{define}`: {what}"));
+        let mut chars = name.chars();
+        if !chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(invalid("the name is not a Move identifier"));
+        }
+        let ty = if value == "true" || value == "false" {
+            "bool".to_string()
+        } else if let Some(address) = value.strip_prefix('@') {
+            if address.is_empty()
+                || !address
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(invalid("malformed address"));
+            }
+            "address".to_string()
+        } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            if value[1..value.len() - 1].contains(['"', '\\']) {
+                return Err(invalid("byte strings can't contain quotes or escapes"));
+            }
+            "vector<u8>".to_string()
+        } else {
+            let suffix = INTEGER_SUFFIXES
+                .iter()
+                .find(|suffix| value.ends_with(*suffix))
+                .copied();
+            let digits = value
+                .strip_suffix(suffix.unwrap_or_default())
+                .unwrap_or(value);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '_') {
+                return Err(invalid(
+                    "expected true, false, a number, an @address or a \"byte string\"",
+                ));
+            }
+            suffix.unwrap_or("u64").to_string()
+        };
+        let value = match ty.as_str() {
+            "vector<u8>" => format!("b{value}"),
+            _ => value.to_string(),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            ty,
+            value,
+        })
+    }
+}
+
+/// The source of the `defines::config` module returning `defines`.
+pub fn config_module(defines: &[Define]) -> String {
+    let mut source = format!(
+        "// Generated from the --define flags of the build.\nmodule {DEFINES_ADDRESS_NAME}::config {{\n"
+    );
+    for define in defines {
+        writeln!(
+            source,
+            "    public fun {}(): {} {{ {} }}",
+            define.name, define.ty, define.value
+        )
+        .expect("writing to a string");
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Path of the generated module belonging to the output at `output_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.defines.move`.
+pub fn defines_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension(DEFINES_EXTENSION)
+}
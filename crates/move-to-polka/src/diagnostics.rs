@@ -0,0 +1,80 @@
+//! Diagnostics of the Move compiler.
+//!
+//! The compiler reports every diagnostic through an [`Emitter`]. [`FilteringEmitter`] wraps the
+//! emitter printing to the error writer, to hold back warnings with `--quiet-warnings`, and
+//! records all diagnostics as [`MoveDiagnostic`]s, so `--warn-as-error` can fail the build on
+//! warnings and library users get the list. Warnings of the code generator's own checks, such
+//! as entry function parameters, are reported as [`MoveDiagnostic`]s too.
+
+use codespan::{FileId, Files};
+use codespan_reporting::diagnostic::{Diagnostic, Severity};
+use move_compiler_v2::diagnostics::Emitter;
+
+/// A diagnostic reported by the Move compiler, or a warning of the code generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveDiagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// `file:line:column` of the primary label, if any.
+    pub location: Option<String>,
+}
+
+impl MoveDiagnostic {
+    fn new(source_files: &Files<String>, diag: &Diagnostic<FileId>) -> Self {
+        let location = diag.labels.first().and_then(|label| {
+            let location = source_files
+                .location(label.file_id, label.range.start as u32)
+                .ok()?;
+            Some(format!(
+                "{}:{}:{}",
+                source_files.name(label.file_id).to_string_lossy(),
+                location.line.0 + 1,
+                location.column.0 + 1
+            ))
+        });
+        Self {
+            severity: diag.severity,
+            message: diag.message.clone(),
+            location,
+        }
+    }
+
+    /// A warning of the code generator at `location`, not of the Move compiler.
+    pub fn warning(message: String, location: Option<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+            location,
+        }
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+}
+
+pub(crate) struct FilteringEmitter<'w> {
+    inner: Box<dyn Emitter + 'w>,
+    quiet_warnings: bool,
+    pub(crate) diagnostics: Vec<MoveDiagnostic>,
+}
+
+impl<'w> FilteringEmitter<'w> {
+    pub(crate) fn new(inner: Box<dyn Emitter + 'w>, quiet_warnings: bool) -> Self {
+        Self {
+            inner,
+            quiet_warnings,
+            diagnostics: vec![],
+        }
+    }
+}
+
+impl Emitter for FilteringEmitter<'_> {
+    fn emit(&mut self, source_files: &Files<String>, diag: &Diagnostic<FileId>) {
+        self.diagnostics
+            .push(MoveDiagnostic::new(source_files, diag));
+        if !(self.quiet_warnings && diag.severity <= Severity::Warning) {
+            self.inner.emit(source_files, diag);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! LLVM state shared by the compilations of a process.
+//!
+//! Initializing the LLVM targets and creating a target machine for every compilation adds up
+//! for services that compile many packages in-process. A [`CompilerEngine`] initializes LLVM
+//! once and pools target machines by triple, CPU, features and optimization level: a
+//! compilation checks a machine out for its duration, and returns it for the next
+//! compilation with the same settings, so compilations may run on several threads at once.
+//! The free functions [`crate::compile`], [`crate::compile_to_ir_strings`] and
+//! [`crate::compile_to_cfgs`], and with them the command line, use [`CompilerEngine::global`].
+//!
+//! Every compilation still gets an `llvm::Context` of its own: named struct types live in the
+//! context, so the types of one package would leak into the IR of the next.
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    options::Options,
+    timing::PassTimings,
+    BuildOutput,
+};
+use move_model::model::GlobalEnv;
+use move_polka_codegen::stackless::{Target, TargetMachine, TargetPlatform};
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    sync::{Mutex, OnceLock},
+};
+
+/// The settings a target machine is created with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MachineKey {
+    pub triple: String,
+    pub cpu: String,
+    pub features: String,
+    pub opt_level: String,
+}
+
+impl MachineKey {
+    /// The target machine `options` compile for on `platform`.
+    pub fn new(platform: &TargetPlatform, options: &Options) -> Self {
+        Self {
+            triple: platform.triple().to_string(),
+            cpu: options.llvm_cpu(platform).to_string(),
+            features: options.llvm_features(platform).to_string(),
+            opt_level: options.opt_level.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CompilerEngine {
+    /// The idle target machines, by settings.
+    machines: Mutex<HashMap<MachineKey, Vec<TargetMachine>>>,
+}
+
+impl CompilerEngine {
+    pub fn new() -> Self {
+        TargetPlatform::PVM.initialize_llvm();
+        Self::default()
+    }
+
+    /// The engine of the process.
+    pub fn global() -> &'static CompilerEngine {
+        static ENGINE: OnceLock<CompilerEngine> = OnceLock::new();
+        ENGINE.get_or_init(CompilerEngine::new)
+    }
+
+    /// Check out a target machine for `options` on `platform`, an idle one from the pool or
+    /// else a new one. It returns to the pool when dropped.
+    pub fn target_machine(
+        &self,
+        platform: &TargetPlatform,
+        options: &Options,
+    ) -> Result<PooledTargetMachine<'_>> {
+        let key = MachineKey::new(platform, options);
+        let idle = self.idle_machines().get_mut(&key).and_then(Vec::pop);
+        let machine = match idle {
+            Some(machine) => machine,
+            None => {
+                platform.initialize_llvm();
+                Target::from_triple(&key.triple)
+                    .map_err(MoveToPolkaError::toolchain)?
+                    .create_target_machine(&key.triple, &key.cpu, &key.features, &key.opt_level)
+            }
+        };
+        Ok(PooledTargetMachine {
+            engine: self,
+            key,
+            machine: Some(machine),
+        })
+    }
+
+    /// The number of idle target machines in the pool.
+    pub fn idle_count(&self) -> usize {
+        self.idle_machines().values().map(Vec::len).sum()
+    }
+
+    /// Like [`crate::compile`], with the target machines of this engine.
+    pub fn compile(&self, global_env: &GlobalEnv, options: &Options) -> Result<BuildOutput> {
+        self.compile_timed(global_env, options, &mut PassTimings::default())
+    }
+
+    /// Like [`crate::compile_timed`], with the target machines of this engine.
+    pub fn compile_timed(
+        &self,
+        global_env: &GlobalEnv,
+        options: &Options,
+        timings: &mut PassTimings,
+    ) -> Result<BuildOutput> {
+        crate::compile_with(self, global_env, options, timings)
+    }
+
+    /// Like [`crate::compile_to_ir_strings`], with the target machines of this engine.
+    pub fn compile_to_ir_strings(
+        &self,
+        global_env: &GlobalEnv,
+        options: &Options,
+    ) -> Result<Vec<(String, String)>> {
+        crate::compile_to_ir_strings_with(self, global_env, options)
+    }
+
+    fn idle_machines(&self) -> std::sync::MutexGuard<'_, HashMap<MachineKey, Vec<TargetMachine>>> {
+        // a compilation panicking with a machine checked out leaves the pool consistent
+        self.machines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A target machine checked out of a [`CompilerEngine`], returned to it when dropped.
+pub struct PooledTargetMachine<'e> {
+    engine: &'e CompilerEngine,
+    key: MachineKey,
+    machine: Option<TargetMachine>,
+}
+
+impl Deref for PooledTargetMachine<'_> {
+    type Target = TargetMachine;
+
+    fn deref(&self) -> &TargetMachine {
+        self.machine.as_ref().expect("checked out until dropped")
+    }
+}
+
+impl Drop for PooledTargetMachine<'_> {
+    fn drop(&mut self) {
+        if let Some(machine) = self.machine.take() {
+            self.engine
+                .idle_machines()
+                .entry(self.key.clone())
+                .or_default()
+                .push(machine);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Errors returned by the library APIs.
+//!
+//! The variants let callers tell broken Move sources apart from a broken toolchain or an
+//! unsupported language feature. The binaries convert them into `anyhow::Error`.
+
+use std::fmt::Display;
+use thiserror::Error;
+
+pub type Result<T, E = MoveToPolkaError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum MoveToPolkaError {
+    /// The package manifest or Move sources are invalid. Compiler diagnostics have
+    /// already been written to the error writer.
+    #[error("Move compilation failed: {0}")]
+    Compile(String),
+    /// A required tool, such as `ld.lld` or the LLVM RISC-V target, is not available.
+    #[error("toolchain error: {0} (run `move-to-polka doctor` to check the toolchain)")]
+    Toolchain(String),
+    /// The program uses constructs the code generator does not support.
+    #[error("{} unsupported construct(s) found:\n{}", .0.len(), .0.join("\n"))]
+    Unsupported(Vec<String>),
+    /// LLVM rejected the code generated for these modules, a code generator bug. Each entry
+    /// has the verifier message and the IR of the offending functions.
+    #[error("{} module(s) failed LLVM verification:\n{}", .0.len(), .0.join("\n"))]
+    Verification(Vec<String>),
+    /// Generating or emitting LLVM code failed.
+    #[error("code generation failed: {0}")]
+    Codegen(String),
+    /// Linking the object files into a PolkaVM blob failed.
+    #[error("link error: {0}")]
+    Link(String),
+    /// A blob, its metadata or its source map is malformed.
+    #[error("invalid artifact: {0}")]
+    InvalidArtifact(String),
+    /// Call data or return data does not match the ABI.
+    #[error("codec error: {0}")]
+    Codec(String),
+    /// A signing key can't be read, or the signature of a blob does not verify.
+    #[error("signing error: {0}")]
+    Signing(String),
+    /// PolkaVM failed to load, instantiate or run a blob.
+    #[error("PolkaVM error: {0}")]
+    Vm(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl MoveToPolkaError {
+    pub(crate) fn compile(e: impl Display) -> Self {
+        Self::Compile(format!("{e:#}"))
+    }
+
+    pub(crate) fn toolchain(e: impl Display) -> Self {
+        Self::Toolchain(format!("{e:#}"))
+    }
+
+    pub(crate) fn codegen(e: impl Display) -> Self {
+        Self::Codegen(format!("{e:#}"))
+    }
+
+    pub(crate) fn link(e: impl Display) -> Self {
+        Self::Link(format!("{e:#}"))
+    }
+
+    pub(crate) fn invalid_artifact(e: impl Display) -> Self {
+        Self::InvalidArtifact(format!("{e:#}"))
+    }
+
+    pub(crate) fn codec(e: impl Display) -> Self {
+        Self::Codec(format!("{e:#}"))
+    }
+
+    pub(crate) fn signing(e: impl Display) -> Self {
+        Self::Signing(format!("{e:#}"))
+    }
+}
+
+impl From<polkavm::Error> for MoveToPolkaError {
+    fn from(e: polkavm::Error) -> Self {
+        Self::Vm(e.to_string())
+    }
+}
+
+impl From<polkavm::MemoryAccessError> for MoveToPolkaError {
+    fn from(e: polkavm::MemoryAccessError) -> Self {
+        Self::Vm(e.to_string())
+    }
+}
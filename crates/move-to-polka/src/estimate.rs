@@ -0,0 +1,98 @@
+//! Dry-run cost estimates: the size of a compiled blob together with the gas one call of an
+//! entry function uses.
+//!
+//! The call goes through the blob's `call` export with call data encoded from the [`Abi`],
+//! like pallet-revive makes it, on a fresh metered instance with the in-memory storage host.
+//! Gas metering is deterministic, the interpreter and the compiler backend charge the same.
+
+use crate::{
+    abi::Abi,
+    error::Result,
+    fuzz::Outcome,
+    linker::{create_metered_instance, parse_to_blob},
+};
+use polkavm::ProgramBlob;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Sizes of a blob, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeStats {
+    /// The whole blob.
+    pub blob: usize,
+    /// PolkaVM code.
+    pub code: usize,
+    /// Read-only data stored in the blob.
+    pub ro_data: usize,
+    /// Initialized read-write data stored in the blob.
+    pub rw_data: usize,
+    /// Read-write data the program reserves, the heap included.
+    pub rw_data_size: u32,
+    /// Guest stack size.
+    pub stack_size: u32,
+    /// Number of exports.
+    pub exports: usize,
+}
+
+impl SizeStats {
+    /// The sizes of `blob`, parsed from the `len` bytes of a blob file.
+    pub fn of(len: usize, blob: &ProgramBlob) -> Self {
+        Self {
+            blob: len,
+            code: blob.code().len(),
+            ro_data: blob.ro_data().len(),
+            rw_data: blob.rw_data().len(),
+            rw_data_size: blob.rw_data_size(),
+            stack_size: blob.stack_size(),
+            exports: blob.exports().count(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Estimate {
+    pub size: SizeStats,
+    /// Entry function, as `module::function`.
+    pub function: String,
+    pub gas_limit: i64,
+    /// Gas the call used, the whole limit when it ran out of gas.
+    pub gas_used: i64,
+    #[serde(serialize_with = "serialize_outcome")]
+    pub outcome: Outcome,
+}
+
+/// Estimate the cost of calling `function` with the JSON `args`, one per non-signer
+/// parameter, on the blob `bytes`, with at most `gas`.
+pub fn estimate(
+    bytes: &[u8],
+    abi: &Abi,
+    function: &str,
+    args: &[Value],
+    gas: i64,
+) -> Result<Estimate> {
+    let blob = parse_to_blob(bytes)?;
+    let size = SizeStats::of(bytes.len(), &blob);
+    let call_data = abi.encode_call(function, args)?;
+    let (mut instance, mut runtime) = create_metered_instance(blob, gas)?;
+    runtime.call_data = call_data;
+    let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ());
+    Ok(Estimate {
+        size,
+        function: function.to_string(),
+        gas_limit: gas,
+        gas_used: gas - instance.gas().max(0),
+        outcome: Outcome::of(&result),
+    })
+}
+
+fn serialize_outcome<S: serde::Serializer>(
+    outcome: &Outcome,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match outcome {
+        Outcome::Success => serializer.serialize_str("success"),
+        Outcome::Abort(code) => serializer.serialize_str(&format!("abort {code}")),
+        Outcome::OutOfGas => serializer.serialize_str("out of gas"),
+        Outcome::Failure(failure) => serializer.serialize_str(failure),
+    }
+}
@@ -0,0 +1,256 @@
+//! Fuzzing of compiled contracts through their entry functions.
+//!
+//! Every run picks an entry function of the [`Abi`], generates random arguments for it, biased
+//! towards boundary values, and calls the blob's `call` export with the encoded call data on a
+//! fresh instance with the in-memory storage host and a gas limit. One run in eight truncates
+//! the call data, to check that the guest rejects it. Move aborts and running out
+//! of gas are expected outcomes; anything else, a trap, a native lib panic or a VM error, is a
+//! [`Finding`].
+//!
+//! Each run derives everything from its own seed, the base seed plus the run index, so a
+//! finding is replayed with [`generate_case`] and [`run_case`] from the seed it reports:
+//!
+//! ```ignore
+//! let report = fuzz(&blob, &abi, &FuzzOptions { seed: 42, ..Default::default() })?;
+//! for finding in &report.findings {
+//!     let case = generate_case(&abi, finding.case.seed, &options)?.unwrap();
+//!     assert_eq!(run_case(&blob, &case, options.gas)?, finding.outcome);
+//! }
+//! ```
+
+use crate::{
+    abi::{Abi, AbiType},
+    error::Result,
+    linker::create_metered_instance,
+};
+use move_core_types::{account_address::AccountAddress, u256::U256};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Gas limit of a single call unless configured otherwise.
+pub const DEFAULT_GAS: i64 = 10_000_000;
+
+/// One run in this many calls with truncated call data.
+const TRUNCATED_ONE_IN: u64 = 8;
+
+#[derive(Debug, Clone)]
+pub struct FuzzOptions {
+    /// Number of calls to make.
+    pub runs: u64,
+    /// Seed of the first run.
+    pub seed: u64,
+    /// Gas limit of every call.
+    pub gas: i64,
+    /// Maximum length of generated vectors.
+    pub max_vector_len: usize,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            runs: 1000,
+            seed: 0,
+            gas: DEFAULT_GAS,
+            max_vector_len: 16,
+        }
+    }
+}
+
+/// A generated call of an entry function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzCase {
+    /// Seed the case was generated from.
+    pub seed: u64,
+    /// Entry function, as `module::function`.
+    pub function: String,
+    /// Arguments as JSON, without the signer.
+    pub args: Vec<Value>,
+    /// Selector followed by the BCS encoded arguments, cut short in some runs.
+    pub call_data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    /// The call aborted with a Move abort code.
+    Abort(u64),
+    OutOfGas,
+    /// The call failed in any other way.
+    Failure(String),
+}
+
+/// A call that failed other than with a Move abort.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub case: FuzzCase,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzReport {
+    pub runs: u64,
+    pub successes: u64,
+    /// Number of calls per abort code.
+    pub aborts: BTreeMap<u64, u64>,
+    pub out_of_gas: u64,
+    pub findings: Vec<Finding>,
+}
+
+/// Make `options.runs` random calls of the entry functions in `abi`.
+pub fn fuzz(blob: &ProgramBlob, abi: &Abi, options: &FuzzOptions) -> Result<FuzzReport> {
+    let mut report = FuzzReport::default();
+    for run in 0..options.runs {
+        let Some(case) = generate_case(abi, options.seed.wrapping_add(run), options)? else {
+            break;
+        };
+        report.runs += 1;
+        match run_case(blob, &case, options.gas)? {
+            Outcome::Success => report.successes += 1,
+            Outcome::Abort(code) => *report.aborts.entry(code).or_default() += 1,
+            Outcome::OutOfGas => report.out_of_gas += 1,
+            outcome => report.findings.push(Finding { case, outcome }),
+        }
+    }
+    Ok(report)
+}
+
+/// Generate the call of the run with `seed`. Entry functions taking structs can't be called
+/// with call data; without any other entry function there is nothing to call.
+pub fn generate_case(abi: &Abi, seed: u64, options: &FuzzOptions) -> Result<Option<FuzzCase>> {
+    let functions = abi
+        .functions
+        .iter()
+        .filter(|function| function.params.iter().all(is_encodable))
+        .collect::<Vec<_>>();
+    if functions.is_empty() {
+        return Ok(None);
+    }
+    let mut rng = SplitMix64(seed);
+    let function = functions[rng.below(functions.len() as u64) as usize];
+    let args = function
+        .params
+        .iter()
+        .filter(|ty| **ty != AbiType::Signer)
+        .map(|ty| random_value(ty, &mut rng, abi.address_length, options))
+        .collect::<Vec<_>>();
+    let mut call_data = abi.encode_call(&function.name, &args)?;
+    // cut the call data short now and then, which must abort with MALFORMED_INPUT_CODE
+    if rng.below(TRUNCATED_ONE_IN) == 0 {
+        let len = rng.below(call_data.len() as u64);
+        call_data.truncate(len as usize);
+    }
+    Ok(Some(FuzzCase {
+        seed,
+        function: function.name.clone(),
+        args,
+        call_data,
+    }))
+}
+
+/// Call the blob's `call` export with the call data of `case`, on a fresh instance.
+pub fn run_case(blob: &ProgramBlob, case: &FuzzCase, gas: i64) -> Result<Outcome> {
+    let (mut instance, mut runtime) = create_metered_instance(blob.clone(), gas)?;
+    runtime.call_data = case.call_data.clone();
+    let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ());
+    Ok(Outcome::of(&result))
+}
+
+impl Outcome {
+    /// The outcome of a call of the `call` export.
+    pub(crate) fn of(result: &std::result::Result<(), CallError<ProgramError>>) -> Self {
+        match result {
+            Ok(()) => Outcome::Success,
+            Err(CallError::User(ProgramError::Abort(code))) => Outcome::Abort(*code),
+            Err(CallError::NotEnoughGas) => Outcome::OutOfGas,
+            Err(e) => Outcome::Failure(format!("{e:?}")),
+        }
+    }
+}
+
+fn is_encodable(ty: &AbiType) -> bool {
+    match ty {
+        AbiType::Opaque(_) => false,
+        AbiType::Struct(abi_struct) => abi_struct.fields.iter().all(|f| is_encodable(&f.ty)),
+        AbiType::Vector(ty) => is_encodable(ty),
+        _ => true,
+    }
+}
+
+fn random_value(
+    ty: &AbiType,
+    rng: &mut SplitMix64,
+    address_length: usize,
+    options: &FuzzOptions,
+) -> Value {
+    // boundary values half of the time: zero, one or the maximum
+    let int = |rng: &mut SplitMix64, max: u64| match rng.below(8) {
+        0 | 1 => 0,
+        2 => 1,
+        3 => max,
+        _ => rng.next_u64() & max,
+    };
+    match ty {
+        AbiType::Bool => Value::Bool(rng.below(2) == 1),
+        AbiType::U8 => Value::from(int(rng, u8::MAX.into())),
+        AbiType::U16 => Value::from(int(rng, u16::MAX.into())),
+        AbiType::U32 => Value::from(int(rng, u32::MAX.into())),
+        AbiType::U64 => Value::from(int(rng, u64::MAX)),
+        AbiType::U128 => {
+            let n = ((int(rng, u64::MAX) as u128) << 64) | int(rng, u64::MAX) as u128;
+            Value::from(n.to_string())
+        }
+        AbiType::U256 => {
+            let mut bytes = [0u8; 32];
+            for chunk in bytes.chunks_mut(8) {
+                chunk.copy_from_slice(&int(rng, u64::MAX).to_le_bytes());
+            }
+            Value::from(U256::from_le_bytes(&bytes).to_string())
+        }
+        AbiType::Address | AbiType::Signer => {
+            // right-aligned, so the address fits in `address_length` bytes
+            let mut bytes = [0u8; AccountAddress::LENGTH];
+            bytes[AccountAddress::LENGTH - address_length..]
+                .iter_mut()
+                .for_each(|b| *b = rng.next_u64() as u8);
+            Value::from(AccountAddress::new(bytes).to_hex_literal())
+        }
+        AbiType::Vector(element) => {
+            let len = rng.below(options.max_vector_len as u64 + 1);
+            Value::Array(
+                (0..len)
+                    .map(|_| random_value(element, rng, address_length, options))
+                    .collect(),
+            )
+        }
+        AbiType::Struct(abi_struct) => Value::Array(
+            abi_struct
+                .fields
+                .iter()
+                .map(|field| random_value(&field.ty, rng, address_length, options))
+                .collect(),
+        ),
+        // filtered out by `generate_case`
+        AbiType::Opaque(_) => Value::Null,
+    }
+}
+
+/// A small PRNG, so a seed replays the same case regardless of dependency versions.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
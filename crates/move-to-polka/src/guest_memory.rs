@@ -0,0 +1,169 @@
+//! Move values in guest memory, for tests calling compiled functions directly.
+//!
+//! Exported functions take their arguments in registers, so anything beyond a number is passed
+//! by pointer: references, vectors and addresses. [`write_value`] allocates guest memory and
+//! lays a value of an [`AbiType`] out the way compiled code expects it, [`read_value`] reads it
+//! back, e.g. after the function mutated it through a `&mut` parameter:
+//!
+//! ```ignore
+//! let (mut instance, mut runtime) = create_instance(blob)?;
+//! let ty = AbiType::Vector(Box::new(AbiType::U64));
+//! let v = write_value(&mut instance, &mut runtime.allocator, &ty, &json!([1, 2, 3]))?;
+//! instance.call_typed_and_get_result::<(), (u32,)>(&mut runtime, "double_all", (v,))?;
+//! assert_eq!(read_value(&mut instance, &ty, v)?, json!([2, 4, 6]));
+//! ```
+//!
+//! Values are given as JSON like in [`crate::abi`], or as their BCS encoding with
+//! [`write_bcs`] and [`read_bcs`]. Struct values have no layout in the ABI and are rejected.
+
+use crate::{
+    abi::{decode_uleb128, encode_uleb128, AbiStruct, AbiType},
+    error::{MoveToPolkaError, Result},
+    linker::copy_bytes_from_guest,
+};
+use polkavm::RawInstance;
+use polkavm_move_native::{
+    allocator::MemAllocator,
+    types::{ACCOUNT_ADDRESS_LENGTH, MOVE_UNTYPED_VEC_DESC_SIZE},
+};
+use serde_json::Value;
+
+/// Size of a `MoveUntypedVector` in guest memory: pointer, capacity and length.
+const VECTOR_SIZE: usize = MOVE_UNTYPED_VEC_DESC_SIZE as usize;
+
+/// Write the JSON `value` of type `ty` to newly allocated guest memory, returning its address.
+pub fn write_value(
+    instance: &mut RawInstance,
+    allocator: &mut MemAllocator,
+    ty: &AbiType,
+    value: &Value,
+) -> Result<u32> {
+    let mut bcs = vec![];
+    ty.encode(value, &mut bcs)?;
+    write_bcs(instance, allocator, ty, &bcs)
+}
+
+/// Read the value of type `ty` at guest address `address` as JSON.
+pub fn read_value(instance: &mut RawInstance, ty: &AbiType, address: u32) -> Result<Value> {
+    let bcs = read_bcs(instance, ty, address)?;
+    ty.decode(&mut bcs.as_slice())
+}
+
+/// Write the BCS encoded value `bcs` of type `ty` to newly allocated guest memory, returning
+/// its address.
+pub fn write_bcs(
+    instance: &mut RawInstance,
+    allocator: &mut MemAllocator,
+    ty: &AbiType,
+    bcs: &[u8],
+) -> Result<u32> {
+    let (size, align) = layout(ty)?;
+    let address = allocator.alloc(size, align)?;
+    let mut bytes = bcs;
+    write_at(instance, allocator, ty, &mut bytes, address)?;
+    if !bytes.is_empty() {
+        return Err(MoveToPolkaError::codec(format!(
+            "{} trailing byte(s) after a {ty:?} value",
+            bytes.len()
+        )));
+    }
+    Ok(address)
+}
+
+/// Read the value of type `ty` at guest address `address`, BCS encoded.
+pub fn read_bcs(instance: &mut RawInstance, ty: &AbiType, address: u32) -> Result<Vec<u8>> {
+    let mut bcs = vec![];
+    read_at(instance, ty, address, &mut bcs)?;
+    Ok(bcs)
+}
+
+/// Size and alignment of a value of type `ty` in guest memory.
+fn layout(ty: &AbiType) -> Result<(usize, usize)> {
+    Ok(match ty {
+        AbiType::Bool | AbiType::U8 => (1, 1),
+        AbiType::U16 => (2, 2),
+        AbiType::U32 => (4, 4),
+        AbiType::U64 => (8, 8),
+        AbiType::U128 => (16, 16),
+        AbiType::U256 => (32, 16),
+        AbiType::Address | AbiType::Signer => (ACCOUNT_ADDRESS_LENGTH, 1),
+        AbiType::Vector(_) => (VECTOR_SIZE, 8),
+        AbiType::Struct(AbiStruct { name, .. }) | AbiType::Opaque(name) => {
+            return Err(MoveToPolkaError::codec(format!(
+                "values of {name} have no known layout"
+            )))
+        }
+    })
+}
+
+fn write_at(
+    instance: &mut RawInstance,
+    allocator: &mut MemAllocator,
+    ty: &AbiType,
+    bcs: &mut &[u8],
+    address: u32,
+) -> Result<()> {
+    match ty {
+        AbiType::Vector(element) => {
+            let len = decode_uleb128(bcs)? as usize;
+            let (size, align) = layout(element)?;
+            let data = allocator.alloc(size * len, align)?;
+            for i in 0..len {
+                write_at(instance, allocator, element, bcs, data + (i * size) as u32)?;
+            }
+            let mut vector = Vec::with_capacity(VECTOR_SIZE);
+            vector.extend((data as u64).to_le_bytes());
+            vector.extend((len as u64).to_le_bytes());
+            vector.extend((len as u64).to_le_bytes());
+            Ok(instance.write_memory(address, &vector)?)
+        }
+        _ => {
+            let (size, _) = layout(ty)?;
+            if bcs.len() < size {
+                return Err(MoveToPolkaError::codec(format!(
+                    "unexpected end of a {ty:?} value"
+                )));
+            }
+            let (value, rest) = bcs.split_at(size);
+            *bcs = rest;
+            Ok(instance.write_memory(address, &swap_address_bytes(ty, value))?)
+        }
+    }
+}
+
+fn read_at(
+    instance: &mut RawInstance,
+    ty: &AbiType,
+    address: u32,
+    bcs: &mut Vec<u8>,
+) -> Result<()> {
+    match ty {
+        AbiType::Vector(element) => {
+            let vector = copy_bytes_from_guest(instance, address, VECTOR_SIZE)?;
+            let word = |i: usize| u64::from_le_bytes(vector[i * 8..][..8].try_into().unwrap());
+            let (data, len) = (word(0) as u32, word(2));
+            let (size, _) = layout(element)?;
+            encode_uleb128(len, bcs);
+            for i in 0..len as usize {
+                read_at(instance, element, data + (i * size) as u32, bcs)?;
+            }
+            Ok(())
+        }
+        _ => {
+            let (size, _) = layout(ty)?;
+            let value = copy_bytes_from_guest(instance, address, size)?;
+            bcs.extend(swap_address_bytes(ty, &value));
+            Ok(())
+        }
+    }
+}
+
+/// The bytes of a scalar of type `ty` swapped between BCS and guest memory. Addresses are
+/// written most significant byte first in BCS, but are little-endian in memory, see
+/// `MoveAddress`; numbers are little-endian in both.
+fn swap_address_bytes(ty: &AbiType, bytes: &[u8]) -> Vec<u8> {
+    match ty {
+        AbiType::Address | AbiType::Signer => bytes.iter().rev().copied().collect(),
+        _ => bytes.to_vec(),
+    }
+}
@@ -2,30 +2,52 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-pub mod cstr;
+pub mod abi;
+pub mod abort_codes;
+pub mod artifacts;
+pub mod build_plan;
+pub mod cfg;
+pub mod coverage;
+pub mod defines;
+pub mod diagnostics;
+pub mod engine;
+pub mod error;
+pub mod estimate;
+pub mod fuzz;
+pub mod guest_memory;
 pub mod linker;
+pub mod metadata;
 pub mod native;
 pub mod options;
-pub mod stackless;
+pub mod repl;
+pub mod signature;
+pub mod signing;
+pub mod source_map;
+pub mod split;
+pub mod stack_trace;
+pub mod storage;
+pub mod timing;
+pub mod trace;
+pub mod vfs;
 
-use crate::options::Options;
+use crate::{
+    diagnostics::{FilteringEmitter, MoveDiagnostic},
+    engine::CompilerEngine,
+    error::{MoveToPolkaError, Result},
+    options::Options,
+    timing::PassTimings,
+    vfs::VirtualFiles,
+};
+use move_polka_codegen::{instrument::Instrumentation, options::CodegenOptions, stackless};
 
 use anyhow::Context;
 use codespan_reporting::term::termcolor::WriteColor;
 use itertools::Itertools;
 use linker::load_from_elf_with_polka_linker;
-use log::{debug, Level, LevelFilter};
-use move_binary_format::{
-    binary_views::BinaryIndexedView, file_format::CompiledScript, CompiledModule,
-};
-use move_bytecode_source_map::{
-    mapping::SourceMapping, source_map::SourceMap, utils::source_map_from_file,
-};
-use move_command_line_common::files::{
-    FileHash, MOVE_COMPILED_EXTENSION, MOVE_EXTENSION, SOURCE_MAP_EXTENSION,
-};
+use move_binary_format::{file_format::CompiledScript, CompiledModule};
+use move_bytecode_source_map::{source_map::SourceMap, utils::source_map_from_file};
+use move_command_line_common::files::{FileHash, MOVE_COMPILED_EXTENSION, SOURCE_MAP_EXTENSION};
 use move_compiler_v2::{run_move_compiler, Experiment, Options as CompilerV2Options};
-use move_ir_types::location::Spanned;
 use move_model::{
     model::{GlobalEnv, ModuleId, MoveIrLoc},
     parse_addresses_from_options,
@@ -35,251 +57,576 @@ use std::{
     io::Write,
     iter::once,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
+use tracing::{debug, debug_span, info};
+use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+/// Areas of the code generator whose debug logs `--debug-codegen` turns on, with the log targets
+/// of each area.
+pub const DEBUG_CODEGEN_AREAS: &[(&str, &[&str])] = &[
+    (
+        "functions",
+        &[
+            "functions",
+            "globalenv",
+            "module",
+            "nodes",
+            "sbc",
+            "verify function",
+        ],
+    ),
+    ("structs", &["structs", "struct"]),
+    (
+        "dwarf",
+        &["dwarf", "bytecode", "instruction", "vector", "vectors"],
+    ),
+    ("rtcall", &["rtcall", "runtime"]),
+    ("types", &["rtty", "dl"]),
+];
+
+/// The values `--debug-codegen` accepts: the [`DEBUG_CODEGEN_AREAS`] and `all`.
+pub fn debug_codegen_areas() -> impl Iterator<Item = &'static str> {
+    DEBUG_CODEGEN_AREAS
+        .iter()
+        .map(|(area, _)| *area)
+        .chain(once("all"))
+}
 
 // init logger from RUST_LOG env var, defaults to INFO
 pub fn initialize_logger() {
+    initialize_logger_with(&[], None).expect("logging to stderr")
+}
+
+/// Like [`initialize_logger`], but also logs the `debug_codegen` areas (see
+/// [`DEBUG_CODEGEN_AREAS`]) at debug level, whatever `RUST_LOG` says about their targets, and
+/// writes the log to `log_file` instead of stderr.
+///
+/// Compilation runs in debug level spans: `compile`, a `translate` span per module with a
+/// `translate_function` span per function, `link` and a `pass` span per `--time-passes` phase.
+/// With `RUST_LOG=debug` events show the spans they happen in, e.g.
+/// `compile:translate{module=0x1_vector}:translate_function{function=vector::append}`, and every
+/// span logs its time when it closes. The `log` records of the Move compiler are logged too.
+pub fn initialize_logger_with(debug_codegen: &[String], log_file: Option<&Path>) -> Result<()> {
+    let all = debug_codegen.iter().any(|area| area == "all");
+    let targets = DEBUG_CODEGEN_AREAS
+        .iter()
+        .filter(|(area, _)| all || debug_codegen.iter().any(|a| a == area))
+        .flat_map(|(_, targets)| targets.iter());
+
     static LOGGER_INIT: std::sync::Once = std::sync::Once::new();
+    let mut result = Ok(());
     LOGGER_INIT.call_once(|| {
-        use anstyle::{AnsiColor, Color};
-        env_logger::Builder::new()
-            .filter_level(LevelFilter::Info)
-            .parse_default_env()
-            .format(|formatter, record| {
-                let level = record.level();
-                let style = formatter.default_level_style(level);
-                match record.level() {
-                    Level::Error => style.fg_color(Some(Color::Ansi(AnsiColor::Red))),
-                    Level::Warn => style.fg_color(Some(Color::Ansi(AnsiColor::Yellow))),
-                    Level::Info => style.fg_color(Some(Color::Ansi(AnsiColor::Green))),
-                    Level::Debug => style.fg_color(Some(Color::Ansi(AnsiColor::Blue))),
-                    Level::Trace => style.fg_color(Some(Color::Ansi(AnsiColor::Cyan))),
-                };
-                writeln!(
-                    formatter,
-                    "{} [{} {}:{}] {}",
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                    level,
-                    record.target(),
-                    record.line().unwrap_or(0),
-                    record.args()
-                )
-            })
-            .init();
+        // opened here, so a logger initialized already doesn't truncate the file
+        let log_file = match log_file.map(fs::File::create).transpose() {
+            Ok(log_file) => log_file,
+            Err(e) => {
+                result = Err(e.into());
+                return;
+            }
+        };
+        let mut filter =
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+        for target in targets {
+            filter = filter.add_directive(
+                format!("{target}=debug")
+                    .parse()
+                    .expect("debug codegen targets are valid directives"),
+            );
+        }
+        let builder = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_line_number(true)
+            .with_span_events(FmtSpan::CLOSE);
+        // a subscriber set up by the embedding program wins
+        let _ = match log_file {
+            Some(log_file) => builder
+                .with_ansi(false)
+                .with_writer(std::sync::Mutex::new(log_file))
+                .try_init(),
+            None => builder.with_writer(std::io::stderr).try_init(),
+        };
     });
+    result
 }
 
 fn link_object_files(
     out_path: PathBuf,
     objects: &[PathBuf],
     polka_object_file: PathBuf,
-    move_native_path: Option<&str>,
-) -> anyhow::Result<PathBuf> {
-    log::debug!("link_object_files");
-
-    let lld = build_tools::Lld::try_init()?;
+    move_native: PathBuf,
+    elf_file: Option<PathBuf>,
+    timings: &mut PassTimings,
+) -> Result<PathBuf> {
+    let _span = debug_span!("link", objects = objects.len()).entered();
 
-    let native_lib_content = native::move_native_lib_content();
-
-    let move_native = if let Some(move_native) = move_native_path {
-        // if passed explicitly through args - use that
-        PathBuf::from(move_native)
-    } else {
-        let move_native = out_path.join("move_native.o");
-        std::fs::write(&move_native, native_lib_content)?;
-        move_native
-    };
+    let lld = build_tools::Lld::try_init().map_err(MoveToPolkaError::toolchain)?;
 
     debug!("Native lib available at: {move_native:?}");
 
     let merged_object = out_path.join("merged.o");
-    lld.merge_object_files(
-        &objects.iter().chain(once(&move_native)).collect_vec(),
-        &merged_object,
-        true,
-    )?;
+    timings
+        .time("lld merge", || {
+            lld.merge_object_files(
+                &objects.iter().chain(once(&move_native)).collect_vec(),
+                &merged_object,
+                true,
+            )
+        })
+        .map_err(MoveToPolkaError::link)?;
     debug!("Merged object file created at: {}", merged_object.display());
+    if let Some(elf_file) = elf_file {
+        std::fs::copy(&merged_object, &elf_file)?;
+        debug!("Merged object file copied to: {}", elf_file.display());
+    }
 
     let object_bytes = std::fs::read(&merged_object)?;
     debug!(
         "Read merged object file bytes, size: {}",
         object_bytes.len()
     );
-    let polka_object = load_from_elf_with_polka_linker(&object_bytes)?;
+    let polka_object = timings.time("polka linking", || {
+        load_from_elf_with_polka_linker(&object_bytes)
+    })?;
     debug!("Polka object created, size: {}", polka_object.len());
     std::fs::write(&polka_object_file, &polka_object)?;
     debug!(
         "Polka object file written to: {}",
         polka_object_file.display()
     );
-    Ok(polka_object_file)
+    Ok(merged_object)
 }
 
 pub fn get_env_from_source<W: WriteColor>(
     error_writer: &mut W,
     options: &Options,
-) -> anyhow::Result<GlobalEnv> {
-    let addrs = parse_addresses_from_options(options.named_address_mapping.clone())?;
+) -> Result<GlobalEnv> {
+    get_env_and_diagnostics_from_source(error_writer, options).0
+}
+
+/// Like [`get_env_from_source`], also returning every diagnostic the compiler reported, even
+/// when compilation failed. Warnings held back by `--quiet-warnings` are included.
+pub fn get_env_and_diagnostics_from_source<W: WriteColor>(
+    error_writer: &mut W,
+    options: &Options,
+) -> (Result<GlobalEnv>, Vec<MoveDiagnostic>) {
+    compile_sources(error_writer, options, options.resolved_sources())
+}
+
+fn compile_sources<W: WriteColor>(
+    error_writer: &mut W,
+    options: &Options,
+    sources: Result<Vec<String>>,
+) -> (Result<GlobalEnv>, Vec<MoveDiagnostic>) {
+    // holds the defines module of a check until the sources are compiled
+    let (v2_options, _defines_dir) =
+        match sources.and_then(|sources| compiler_options(options, sources)) {
+            Ok(compiler_options) => compiler_options,
+            Err(e) => return (Err(e), vec![]),
+        };
+    let mut emitter = FilteringEmitter::new(
+        v2_options.error_emitter(error_writer),
+        options.quiet_warnings,
+    );
+    let result = run_move_compiler(&mut emitter, v2_options).map_err(MoveToPolkaError::compile);
+    let diagnostics = emitter.diagnostics;
+    let env = result.and_then(|(env, _units)| {
+        env.treat_everything_as_target(false);
+        if env.has_errors() {
+            return Err(MoveToPolkaError::compile("Move source code errors"));
+        }
+        let warnings = diagnostics.iter().filter(|d| d.is_warning()).count();
+        if options.warn_as_error && warnings > 0 {
+            return Err(MoveToPolkaError::compile(format!(
+                "{warnings} warning(s) treated as errors (--warn-as-error)"
+            )));
+        }
+        Ok(env)
+    });
+    (env, diagnostics)
+}
+
+/// Like [`get_env_from_source`], compiling the in-memory `files` instead of `options.sources`.
+/// Dependencies are still looked up in `options.dependencies`.
+pub fn get_env_from_memory<W: WriteColor>(
+    error_writer: &mut W,
+    options: &Options,
+    files: &VirtualFiles,
+) -> Result<GlobalEnv> {
+    get_env_and_diagnostics_from_memory(error_writer, options, files).0
+}
+
+/// Like [`get_env_and_diagnostics_from_source`], compiling the in-memory `files`.
+pub fn get_env_and_diagnostics_from_memory<W: WriteColor>(
+    error_writer: &mut W,
+    options: &Options,
+    files: &VirtualFiles,
+) -> (Result<GlobalEnv>, Vec<MoveDiagnostic>) {
+    let sources = files.stage(&VirtualFiles::staging_dir(&options.output));
+    compile_sources(error_writer, options, sources)
+}
+
+/// The compiler options to compile `sources` with, and the temporary directory the defines
+/// module is written to for `--check`, which writes nothing next to the output.
+fn compiler_options(
+    options: &Options,
+    sources: Vec<String>,
+) -> Result<(CompilerV2Options, Option<tempfile::TempDir>)> {
+    let addrs = parse_addresses_from_options(options.named_address_mapping.clone())
+        .map_err(MoveToPolkaError::compile)?;
     debug!("Named addresses {addrs:?}");
 
-    let mut v2_options = CompilerV2Options {
-        sources: options.sources.clone(),
-        dependencies: options.dependencies.clone(),
-        named_address_mapping: options.named_address_mapping.clone(),
+    let mut dependencies = options.resolved_dependencies()?;
+    let mut named_address_mapping = options.named_address_mapping.clone();
+    let defines = options.parsed_defines()?;
+    let mut path = defines::defines_path(Path::new(&options.output));
+    let mut defines_dir = None;
+    if defines.is_empty() {
+        // the module of an earlier build with defines
+        if !options.check && path.is_file() {
+            std::fs::remove_file(&path)?;
+        }
+    } else {
+        if options.check {
+            let dir = tempfile::Builder::new()
+                .prefix("move-polka-defines-")
+                .tempdir()?;
+            path = dir.path().join(path.file_name().unwrap_or_default());
+            defines_dir = Some(dir);
+        } else if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, defines::config_module(&defines))?;
+        dependencies.push(path.to_string_lossy().to_string());
+        if !addrs.contains_key(defines::DEFINES_ADDRESS_NAME) {
+            named_address_mapping.push(format!(
+                "{}={}",
+                defines::DEFINES_ADDRESS_NAME,
+                defines::DEFAULT_DEFINES_ADDRESS
+            ));
+        }
+    }
+
+    let v2_options = CompilerV2Options {
+        sources,
+        dependencies,
+        named_address_mapping,
         output_dir: options.output.clone(),
         whole_program: true,
         ..Default::default()
     };
 
-    v2_options = v2_options.set_experiment(Experiment::SPEC_REWRITE, true);
-    v2_options = v2_options.set_experiment(Experiment::ATTACH_COMPILED_MODULE, true);
-    let mut emitter = v2_options.error_emitter(error_writer);
-    let (env, _units) = run_move_compiler(emitter.as_mut(), v2_options)?;
-    env.treat_everything_as_target(false);
-
-    if env.has_errors() {
-        anyhow::bail!("Move source code errors")
-    } else {
-        Ok(env)
-    }
+    Ok((
+        v2_options
+            .set_experiment(Experiment::SPEC_REWRITE, true)
+            .set_experiment(Experiment::ATTACH_COMPILED_MODULE, true),
+        defines_dir,
+    ))
 }
 
-fn get_env_from_bytecode(options: &Options) -> anyhow::Result<GlobalEnv> {
-    let move_extension = MOVE_EXTENSION;
-    let mv_bytecode_extension = MOVE_COMPILED_EXTENSION;
-    let source_map_extension = SOURCE_MAP_EXTENSION;
+/// Build the model of `options.bytecode_file_path` and the compiled modules of
+/// `options.dependencies`, with the source maps (`.mvsm`) next to them, if there are any.
+pub fn get_env_from_bytecode(options: &Options) -> Result<GlobalEnv> {
+    bytecode_env(options).map_err(MoveToPolkaError::compile)
+}
 
-    let bytecode_file_path = (options.bytecode_file_path.as_ref()).unwrap();
-    let source_path = Path::new(&bytecode_file_path);
-    let extension = source_path
+fn bytecode_env(options: &Options) -> anyhow::Result<GlobalEnv> {
+    if !options.defines.is_empty() {
+        anyhow::bail!("--define needs Move sources, bytecode is already compiled");
+    }
+    let bytecode_file_path = Path::new(
+        options
+            .bytecode_file_path
+            .as_ref()
+            .context("No bytecode file to compile")?,
+    );
+    let extension = bytecode_file_path
         .extension()
         .context("Missing file extension for bytecode file")?;
-    if extension != mv_bytecode_extension {
+    if extension != MOVE_COMPILED_EXTENSION {
         anyhow::bail!(
             "Bad source file extension {:?}; expected {}",
             extension,
-            mv_bytecode_extension
+            MOVE_COMPILED_EXTENSION
         );
     }
 
-    let bytecode_bytes = fs::read(bytecode_file_path).context("Unable to read bytecode file")?;
-
-    let mut dep_bytecode_bytes = vec![];
-    for dep in &options.dependencies {
-        let bytes = fs::read(dep).context("Unable to read dependency bytecode file {dep}")?;
-        dep_bytecode_bytes.push(bytes);
+    // Dependencies come first, the model builder expects the modules in dependency order.
+    let mut modules = vec![];
+    for dep in &options.resolved_dependencies()? {
+        modules.push(read_compiled_module(Path::new(dep), false)?);
     }
+    modules.push(read_compiled_module(bytecode_file_path, options.is_script)?);
 
-    let source_path = Path::new(&bytecode_file_path).with_extension(move_extension);
-    let source = fs::read_to_string(&source_path).ok();
-    let source_map =
-        source_map_from_file(&Path::new(&bytecode_file_path).with_extension(source_map_extension));
-
-    let no_loc = Spanned::unsafe_no_loc(()).loc;
-    let module: CompiledModule;
-    let script: CompiledScript;
-    let bytecode = if options.is_script {
-        script = CompiledScript::deserialize(&bytecode_bytes)
-            .context("Script blob can't be deserialized")?;
-        BinaryIndexedView::Script(&script)
-    } else {
-        module = CompiledModule::deserialize(&bytecode_bytes)
-            .context("Module blob can't be deserialized")?;
-        BinaryIndexedView::Module(&module)
-    };
-
-    let mut source_mapping = {
-        if let Ok(s) = source_map {
-            SourceMapping::new(s, bytecode)
-        } else {
-            SourceMapping::new_from_view(bytecode, no_loc)
-                .context("Unable to build dummy source mapping")?
-        }
-    };
-
-    if let Some(source_code) = source {
-        source_mapping.with_source_code((source_path.to_str().unwrap().to_string(), source_code));
-    }
+    Ok(run_bytecode_model_builder_with_source_maps(modules)?)
+}
 
-    let main_move_module = if options.is_script {
-        let script = CompiledScript::deserialize(&bytecode_bytes)
-            .context("Script blob can't be deserialized")?;
+/// Deserialize the module or script at `path`, together with the source map
+/// (`.mvsm`) next to it, if there is one.
+fn read_compiled_module(
+    path: &Path,
+    is_script: bool,
+) -> anyhow::Result<(CompiledModule, Option<SourceMap>)> {
+    let bytes = fs::read(path)
+        .with_context(|| format!("Unable to read bytecode file {}", path.display()))?;
+    let module = if is_script {
+        let script =
+            CompiledScript::deserialize(&bytes).context("Script blob can't be deserialized")?;
         move_model::script_into_module(script, "main")
     } else {
-        CompiledModule::deserialize(&bytecode_bytes).context("Module blob can't be deserialized")?
+        CompiledModule::deserialize(&bytes).context("Module blob can't be deserialized")?
     };
-
-    let mut dep_move_modules = vec![];
-
-    for bytes in &dep_bytecode_bytes {
-        let dep_module = CompiledModule::deserialize(bytes)
-            .context("Dependency module blob can't be deserialized")?;
-        dep_move_modules.push(dep_module);
-    }
-
-    let modules = dep_move_modules
-        .into_iter()
-        .chain(Some(main_move_module))
-        .collect::<Vec<_>>();
-
-    run_bytecode_model_builder(&modules)
+    let source_map_path = path.with_extension(SOURCE_MAP_EXTENSION);
+    let source_map = match source_map_from_file(&source_map_path) {
+        Ok(source_map) => Some(source_map),
+        Err(e) => {
+            debug!("No source map for {}: {e}", path.display());
+            None
+        }
+    };
+    Ok((module, source_map))
 }
 
-pub fn compile(global_env: &GlobalEnv, options: &Options) -> anyhow::Result<()> {
-    use crate::stackless::{extensions::ModuleEnvExt, *};
-
-    let tgt_platform = TargetPlatform::PVM;
-    tgt_platform.initialize_llvm();
-    let lltarget = Target::from_triple(tgt_platform.triple())?;
-    let llmachine = lltarget.create_target_machine(
-        tgt_platform.triple(),
-        tgt_platform.llvm_cpu(),
-        tgt_platform.llvm_features(),
-        &options.opt_level,
-    );
-    let global_cx = GlobalContext::new(global_env, tgt_platform, &llmachine);
-    let output_file_path = options.output.clone();
-    let file_stem = Path::new(&output_file_path).file_stem().unwrap();
-    // If building a shared object library, then -o is the
-    // directory to output the compiled modules, each module
-    // 'mod' will get file name 'mod.o'
-    let out_path = Path::new(&output_file_path)
-        .parent()
-        .unwrap()
-        .to_path_buf()
-        .join(file_stem);
-    if !(options.compile || options.llvm_ir) {
-        fs::create_dir_all(&out_path)
-            .or_else(|err| anyhow::bail!("Error creating directory: {}", err))?;
-    }
-    let mut objects = vec![];
+/// Translate every module of `global_env` to LLVM and hand it to `emit` along with its name.
+fn translate_modules(
+    global_cx: &stackless::GlobalContext,
+    options: &Options,
+    codegen_options: CodegenOptions,
+    instrumentation: &mut Instrumentation,
+    timings: &mut PassTimings,
+    mut emit: impl FnMut(String, stackless::Module) -> Result<()>,
+) -> Result<()> {
+    use stackless::{extensions::ModuleEnvExt, *};
 
-    // Deserialization is only for one (the last) module.
-    let skip_cnt = if options.bytecode_file_path.is_some() {
-        global_env.get_modules().count() - 1
-    } else {
-        0
-    };
-    // Keep a list of exported functions to avoid generating the polkaVM sections multiple times.
-    let mut exports: Vec<String> = vec![];
+    let global_env = global_cx.env;
+    let reachable = options.prune_unreachable.then(|| {
+        timings.time("reachability", || {
+            reachable_functions(global_env, &codegen_options)
+        })
+    });
+    // Libraries may translate without `check`, and the types only specifications have would
+    // reach the code generator, which has no layout for them.
+    let type_issues = check_types(global_env, reachable.as_ref());
+    if !type_issues.is_empty() {
+        return Err(MoveToPolkaError::Unsupported(type_issues));
+    }
+    // The blob of a split module only holds the modules it calls into.
+    let included = codegen_options
+        .split_module
+        .map(|id| module_closure(global_env, id));
+    // The heap and stack configuration is emitted into the first module only.
+    let mut memory_config_emitted = false;
+    let mut verification_failures = vec![];
     // Note: don't reverse order of modules, since DI may be inter module dependent and needs the direct order.
     for mod_id in global_env
         .get_modules()
         .collect::<Vec<_>>()
         .iter()
-        .skip(skip_cnt)
         .map(|m| m.get_id())
+        .filter(|id| match &included {
+            Some(included) => included.contains(id),
+            None => true,
+        })
     {
         let module = global_env.get_module(mod_id);
         let modname = module.llvm_module_name();
+        let _span = debug_span!("translate", module = %modname).entered();
         debug!("--------------------------------------");
         debug!("Generating code for module {modname}");
         let llmod = global_cx.llvm_cx.create_module(&modname);
+        if !memory_config_emitted {
+            let heap_size = options
+                .heap_pages
+                .checked_mul(polkavm_move_native::HEAP_PAGE_SIZE)
+                .ok_or_else(|| MoveToPolkaError::codegen("heap too large"))?;
+            llmod.add_memory_config(heap_size, options.stack_size);
+            memory_config_emitted = true;
+        }
         let module_source_path = module.get_source_path().to_str().expect("utf-8");
-        let mod_cx =
-            &mut global_cx.create_module_context(mod_id, &llmod, options, module_source_path);
-        mod_cx.translate(&mut exports);
+        let mod_cx = &mut global_cx.create_module_context(
+            mod_id,
+            &llmod,
+            &codegen_options,
+            module_source_path,
+            reachable.as_ref(),
+        );
+        let start = Instant::now();
+        if let Err(e) = mod_cx.translate(instrumentation) {
+            match e.downcast::<VerificationError>() {
+                // Go on with the other modules, to report every module LLVM rejects at once.
+                Ok(e) => {
+                    verification_failures.push(e.to_string());
+                    continue;
+                }
+                Err(e) => return Err(MoveToPolkaError::codegen(e)),
+            }
+        }
+        // Stackless bytecode is generated on demand while translating, report it apart.
+        let stackless_time = mod_cx.stackless_time;
+        timings.add("stackless generation", stackless_time);
+        timings.add(
+            &format!("translate {modname}"),
+            start.elapsed().saturating_sub(stackless_time),
+        );
+        emit(modname, llmod)?;
+    }
+    if !verification_failures.is_empty() {
+        return Err(MoveToPolkaError::Verification(verification_failures));
+    }
+    Ok(())
+}
+
+/// The textual LLVM IR of every module, as `(module name, IR)` pairs, the same IR `-S`
+/// writes. Nothing is written to disk, so analysis tools can consume the IR directly.
+pub fn compile_to_ir_strings(
+    global_env: &GlobalEnv,
+    options: &Options,
+) -> Result<Vec<(String, String)>> {
+    compile_to_ir_strings_with(CompilerEngine::global(), global_env, options)
+}
 
+fn compile_to_ir_strings_with(
+    engine: &CompilerEngine,
+    global_env: &GlobalEnv,
+    options: &Options,
+) -> Result<Vec<(String, String)>> {
+    use stackless::*;
+
+    with_global_context(engine, global_env, options, |global_cx, _| {
+        let mut modules = vec![];
+        translate_modules(
+            global_cx,
+            options,
+            options.codegen_options(),
+            &mut Instrumentation::default(),
+            &mut PassTimings::default(),
+            |modname, llmod| {
+                modules.push((modname, llmod.print_to_str().to_string()));
+                Ok(())
+            },
+        )?;
+        Ok(modules)
+    })
+}
+
+/// The control flow graph of every translated function, the graphs `--gen-dot-cfg` writes.
+/// Nothing is written to disk.
+pub fn compile_to_cfgs(global_env: &GlobalEnv, options: &Options) -> Result<Vec<cfg::FunctionCfg>> {
+    use stackless::*;
+
+    let engine = CompilerEngine::global();
+    with_global_context(engine, global_env, options, |global_cx, _| {
+        let mut instrumentation = Instrumentation::default();
+        translate_modules(
+            global_cx,
+            options,
+            CodegenOptions {
+                collect_cfgs: true,
+                ..options.codegen_options()
+            },
+            &mut instrumentation,
+            &mut PassTimings::default(),
+            |_, _| Ok(()),
+        )?;
+        Ok(instrumentation.cfgs)
+    })
+}
+
+/// The files a build wrote, so callers don't have to work out where the artifacts went.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildOutput {
+    /// The linked blob, unless only checking, or writing objects (`-c`) or LLVM IR (`-S`).
+    pub blob_path: Option<PathBuf>,
+    /// The object files of the modules with `-c` or `--keep-temps`, or the LLVM IR files
+    /// with `-S`.
+    pub object_paths: Vec<PathBuf>,
+    /// The directory of the intermediate files, with `--keep-temps`.
+    pub temp_dir: Option<PathBuf>,
+    /// The ABI, with `--emit-abi`.
+    pub abi_path: Option<PathBuf>,
+    /// The source map, with `--source-map`.
+    pub map_path: Option<PathBuf>,
+    /// The package metadata, with `--emit-metadata` or `--signing-key`.
+    pub metadata_path: Option<PathBuf>,
+    /// The build plan, with `--build-plan` or `--plan-only`.
+    pub plan_path: Option<PathBuf>,
+    /// The warnings of the Move compiler, those held back by `--quiet-warnings` included.
+    pub warnings: Vec<MoveDiagnostic>,
+}
+
+pub fn compile(global_env: &GlobalEnv, options: &Options) -> Result<BuildOutput> {
+    compile_timed(global_env, options, &mut PassTimings::default())
+}
+
+/// Like [`compile`], adding the wall time of every phase to `timings`.
+pub fn compile_timed(
+    global_env: &GlobalEnv,
+    options: &Options,
+    timings: &mut PassTimings,
+) -> Result<BuildOutput> {
+    compile_with(CompilerEngine::global(), global_env, options, timings)
+}
+
+fn compile_with(
+    engine: &CompilerEngine,
+    global_env: &GlobalEnv,
+    options: &Options,
+    timings: &mut PassTimings,
+) -> Result<BuildOutput> {
+    let _span = debug_span!("compile", output = %options.output).entered();
+    with_global_context(engine, global_env, options, |global_cx, llmachine| {
+        compile_in(global_cx, llmachine, options, timings)
+    })
+}
+
+/// Check out the target machine of `options` from `engine` and hand it to `translate`, with
+/// the global context translating `global_env` for it. Every translation, to objects, IR or
+/// control flow graphs, is set up here, so they all generate the same code.
+fn with_global_context<T>(
+    engine: &CompilerEngine,
+    global_env: &GlobalEnv,
+    options: &Options,
+    translate: impl FnOnce(&stackless::GlobalContext, &stackless::TargetMachine) -> Result<T>,
+) -> Result<T> {
+    let tgt_platform = stackless::TargetPlatform::PVM;
+    let llmachine = engine.target_machine(&tgt_platform, options)?;
+    let global_cx = stackless::GlobalContext::new(global_env, tgt_platform, &llmachine);
+    translate(&global_cx, &llmachine)
+}
+
+/// Compile the modules of `global_cx` to objects and link them, see [`compile`].
+fn compile_in(
+    global_cx: &stackless::GlobalContext,
+    llmachine: &stackless::TargetMachine,
+    options: &Options,
+    timings: &mut PassTimings,
+) -> Result<BuildOutput> {
+    use stackless::*;
+
+    let global_env = global_cx.env;
+    // read before generating any code, so a missing key fails the build early
+    let signing_key = options
+        .signing_key
+        .as_deref()
+        .map(signing::read_signing_key)
+        .transpose()?;
+    let output_file_path = options.output.clone();
+    let file_stem = Path::new(&output_file_path).file_stem().unwrap();
+    // The objects and the merged ELF go to a directory of their own for every build, so
+    // concurrent builds of the same output never overwrite each other's intermediates.
+    // It is removed when the build ends, unless `--keep-temps`.
+    let temp_dir = tempfile::Builder::new()
+        .prefix(&format!("move-polka-{}-", file_stem.to_string_lossy()))
+        .disable_cleanup(options.keep_temps)
+        .tempdir()?;
+    let out_path = temp_dir.path().to_path_buf();
+    let mut objects = vec![];
+
+    // Coverage counters and traced functions of all modules, for instrumented builds.
+    let mut instrumentation = Instrumentation::default();
+    let mut emission_time = Duration::ZERO;
+    let mut write_module = |modname: String, llmod: Module| -> Result<()> {
+        let start = Instant::now();
         let mut out_path = out_path.join(&modname);
         out_path.set_extension(&options.output_file_extension);
         let mut output_file = out_path.to_str().unwrap().to_string();
@@ -293,29 +640,322 @@ pub fn compile(global_env: &GlobalEnv, options: &Options) -> anyhow::Result<()>
                 path.set_extension(&options.output_file_extension);
                 output_file = path.to_string_lossy().to_string();
             }
-            llmod.write_to_file(options.llvm_ir, &output_file)?;
+            llmod
+                .write_to_file(options.llvm_ir, &output_file)
+                .map_err(MoveToPolkaError::codegen)?;
         } else {
             if options.compile {
                 output_file = options.output.clone();
             }
-            write_object_file(llmod, &llmachine, &output_file)?;
+            if options.lto {
+                llmod
+                    .run_passes(llmachine, LTO_PASS_PIPELINE)
+                    .map_err(MoveToPolkaError::codegen)?;
+            }
+            write_object_file(llmod, llmachine, &output_file).map_err(MoveToPolkaError::codegen)?;
         }
-        if !(options.compile || options.llvm_ir) {
-            objects.push(Path::new(&output_file).to_path_buf());
+        objects.push(Path::new(&output_file).to_path_buf());
+        emission_time += start.elapsed();
+        Ok(())
+    };
+    // With `--lto`, all modules are linked into the first one, which is written once at the end.
+    let mut lto_module: Option<Module> = None;
+    translate_modules(
+        global_cx,
+        options,
+        options.codegen_options(),
+        &mut instrumentation,
+        timings,
+        |modname, llmod| {
+            if !options.lto {
+                return write_module(modname, llmod);
+            }
+            match &lto_module {
+                Some(linked) => linked.link_in(llmod).map_err(MoveToPolkaError::codegen),
+                None => {
+                    lto_module = Some(llmod);
+                    Ok(())
+                }
+            }
+        },
+    )?;
+    if let Some(llmod) = lto_module {
+        write_module(file_stem.to_string_lossy().to_string(), llmod)?;
+    }
+    timings.add("object emission", emission_time);
+    if let Some(output) = options.gen_dot_cfg {
+        let paths = cfg::write_cfgs(
+            &instrumentation.cfgs,
+            Path::new(&options.dot_file_path),
+            output,
+            options.cfg_dir_per_function,
+        )?;
+        debug!("{} control flow graphs written", paths.len());
+        if output == cfg::CfgOutput::View {
+            let viewers = cfg::view_cfgs(&paths, &options.dot_viewer);
+            debug!("{viewers} control flow graph viewers started");
         }
     }
+    let coverage = coverage::CoverageMap {
+        counters: instrumentation.coverage_counters,
+    };
+    let stack_trace = stack_trace::StackTraceMap {
+        functions: instrumentation.traced_functions,
+    };
+    if options.coverage && !(options.compile || options.llvm_ir) {
+        // The counter array is sized only now that all modules are instrumented.
+        let llmod = global_cx.create_target_module("move_rt_coverage");
+        llmod.add_coverage_counters(coverage.len() as u32);
+        llmod.finalize();
+        let coverage_object = out_path.join("move_rt_coverage.o");
+        timings
+            .time("object emission", || {
+                write_object_file(llmod, llmachine, coverage_object.to_str().unwrap())
+            })
+            .map_err(MoveToPolkaError::codegen)?;
+        objects.push(coverage_object);
+    }
+    let mut output = BuildOutput {
+        object_paths: objects.clone(),
+        ..BuildOutput::default()
+    };
     if !(options.compile || options.llvm_ir) {
-        link_object_files(
-            out_path,
+        // nothing of an earlier build may outlive this one, whatever flags it was built with
+        artifacts::Artifacts::new(&output_file_path).prepare()?;
+        let merged_object = link_object_files(
+            out_path.clone(),
             objects.as_slice(),
             Path::new(&output_file_path).to_path_buf(),
-            options.move_native_archive.as_deref(),
+            native::resolve_move_native(
+                options.move_native_archive.as_deref(),
+                options.native_variant(),
+            )?,
+            options
+                .emit_elf
+                .then(|| linker::elf_path(Path::new(&output_file_path))),
+            timings,
+        )?;
+        if options.source_map {
+            let blob = linker::parse_to_blob(&fs::read(&output_file_path)?)?;
+            let source_map_file = source_map::source_map_path(Path::new(&output_file_path));
+            source_map::SourceMap::from_blob(&blob)?.write(&source_map_file)?;
+            debug!("Source map written to {}", source_map_file.display());
+            output.map_path = Some(source_map_file);
+        }
+        if options.coverage {
+            let coverage_map_file = coverage::coverage_map_path(Path::new(&output_file_path));
+            coverage.write(&coverage_map_file)?;
+            debug!(
+                "Coverage map with {} counters written to {}",
+                coverage.len(),
+                coverage_map_file.display()
+            );
+        }
+        if options.stack_traces {
+            let stack_trace_map_file =
+                stack_trace::stack_trace_map_path(Path::new(&output_file_path));
+            stack_trace.write(&stack_trace_map_file)?;
+            debug!(
+                "Stack trace map with {} functions written to {}",
+                stack_trace.len(),
+                stack_trace_map_file.display()
+            );
+        }
+        // the ABI and the metadata carry the selectors `call_selector` dispatches
+        let selector_table = global_cx.exports.selector_table();
+        if options.emit_abi {
+            let abi_file = abi::abi_path(Path::new(&output_file_path));
+            abi::Abi::new(global_env, options, &selector_table)?.write(&abi_file)?;
+            debug!("ABI written to {}", abi_file.display());
+            output.abi_path = Some(abi_file);
+        }
+        if options.emit_metadata || signing_key.is_some() {
+            let mut metadata =
+                metadata::PackageMetadata::new(global_env, options, &selector_table)?;
+            metadata.export_signatures =
+                signature::read_export_signatures(&fs::read(&merged_object)?)?;
+            // exports use the plain function name, `call_selector` dispatches `module::function`
+            metadata.entry_functions = global_cx
+                .exports
+                .exports()
+                .into_iter()
+                .filter(|export| export.signature.is_some() && export.selector.is_some())
+                .filter_map(|export| {
+                    let (_, function) = export.name.rsplit_once("::")?;
+                    Some((function.to_string(), export.name))
+                })
+                .collect();
+            metadata.signer_key_hash = signing_key
+                .as_ref()
+                .map(|key| signing::key_hash(&key.verifying_key()));
+            let metadata_file = metadata::metadata_path(Path::new(&output_file_path));
+            metadata.write(&metadata_file)?;
+            debug!("Metadata written to {}", metadata_file.display());
+            output.metadata_path = Some(metadata_file);
+        }
+        if let (Some(key), Some(metadata_file)) = (&signing_key, &output.metadata_path) {
+            let signature_file = signing::signature_path(Path::new(&output_file_path));
+            signing::sign_blob(
+                &fs::read(&output_file_path)?,
+                &fs::read(metadata_file)?,
+                key,
+            )
+            .write(&signature_file)?;
+            debug!("Signature written to {}", signature_file.display());
+        }
+        if options.split_modules {
+            compile_split_modules(global_cx, options, llmachine, &out_path, timings)?;
+        }
+        output.blob_path = Some(PathBuf::from(&output_file_path));
+    }
+    if options.keep_temps {
+        info!("Intermediate files kept in {}", out_path.display());
+        output.temp_dir = Some(out_path);
+    } else {
+        // the objects are removed along with the directory
+        output
+            .object_paths
+            .retain(|path| !path.starts_with(&out_path));
+    }
+    Ok(output)
+}
+
+/// Link the blob of every module of the package and write the manifest of their imports,
+/// see [`split`]. The objects of every blob go to a directory of their own in `out_path`.
+fn compile_split_modules(
+    global_cx: &stackless::GlobalContext,
+    options: &Options,
+    llmachine: &stackless::TargetMachine,
+    out_path: &Path,
+    timings: &mut PassTimings,
+) -> Result<()> {
+    let output = Path::new(&options.output);
+    let mut manifest = split::SplitManifest::default();
+    for module in split::split_modules(global_cx.env) {
+        let name = split::module_file_name(&module);
+        let objects_path = out_path.join("split").join(&name);
+        fs::create_dir_all(&objects_path)?;
+        // instrumentation maps are only written for the package blob
+        let codegen_options = CodegenOptions {
+            split_module: Some(module.get_id()),
+            coverage: false,
+            stack_traces: false,
+            collect_cfgs: false,
+            ..options.codegen_options()
+        };
+        let mut objects = vec![];
+        translate_modules(
+            global_cx,
+            options,
+            codegen_options,
+            &mut Instrumentation::default(),
+            timings,
+            |modname, llmod| {
+                let object = objects_path.join(format!("{modname}.o"));
+                stackless::write_object_file(llmod, llmachine, object.to_str().unwrap())
+                    .map_err(MoveToPolkaError::codegen)?;
+                objects.push(object);
+                Ok(())
+            },
         )?;
+        let blob_path = split::module_blob_path(output, &name);
+        link_object_files(
+            objects_path,
+            &objects,
+            blob_path.clone(),
+            native::resolve_move_native(
+                options.move_native_archive.as_deref(),
+                options.native_variant(),
+            )?,
+            None,
+            timings,
+        )?;
+        let exports = linker::parse_to_blob(&fs::read(&blob_path)?)?
+            .exports()
+            .map(|export| export.symbol().to_string())
+            .collect();
+        debug!("Blob of module {name} written to {}", blob_path.display());
+        manifest.modules.push(split::ModuleBlob::new(
+            &module, &blob_path, exports, options,
+        )?);
     }
+    let manifest_file = split::manifest_path(output);
+    manifest.write(&manifest_file)?;
+    debug!("Module manifest written to {}", manifest_file.display());
     Ok(())
 }
 
-pub fn run_to_polka<W: WriteColor>(error_writer: &mut W, options: Options) -> anyhow::Result<()> {
+/// Check all modules for constructs the code generator does not support and recursive
+/// struct types, the entry functions for selector collisions and parameters `call_selector`
+/// can't pass, the extra exports, the inline hints and the calls to natives only the mock host
+/// serves, without generating any code.
+/// Every construct and collision is reported, not just the first. Returns the warnings about
+/// entry function parameters.
+pub fn check(global_env: &GlobalEnv, options: &Options) -> Result<Vec<MoveDiagnostic>> {
+    use stackless::{
+        check_entry_params, check_export_collisions, check_extra_exports, check_inline_hints,
+        check_mock_natives, check_module, check_selectors, check_struct_cycles, check_types,
+        reachable_functions,
+    };
+
+    let codegen_options = options.codegen_options();
+    let reachable = options
+        .prune_unreachable
+        .then(|| reachable_functions(global_env, &codegen_options));
+    let issues = check_types(global_env, reachable.as_ref());
+    if !issues.is_empty() {
+        return Err(MoveToPolkaError::Unsupported(issues));
+    }
+    let issues = global_env
+        .get_modules()
+        .flat_map(|module| {
+            check_module(&module, reachable.as_ref(), options.address_length.bytes())
+        })
+        .collect_vec();
+    if !issues.is_empty() {
+        return Err(MoveToPolkaError::Unsupported(issues));
+    }
+    let (entry_errors, entry_warnings): (Vec<_>, Vec<_>) =
+        check_entry_params(global_env, &codegen_options)
+            .into_iter()
+            .partition(|issue| issue.is_error);
+    let errors = check_struct_cycles(global_env)
+        .into_iter()
+        .chain(check_selectors(global_env, &codegen_options))
+        .chain(entry_errors.iter().map(ToString::to_string))
+        .chain(check_extra_exports(global_env, &codegen_options))
+        .chain(check_export_collisions(global_env, &codegen_options))
+        .chain(check_inline_hints(global_env, &codegen_options))
+        .chain(check_mock_natives(
+            global_env,
+            reachable.as_ref(),
+            &codegen_options,
+        ))
+        .collect_vec();
+    if !errors.is_empty() {
+        return Err(MoveToPolkaError::compile(errors.join("\n")));
+    }
+    Ok(entry_warnings
+        .into_iter()
+        .map(|issue| MoveDiagnostic::warning(issue.message, Some(issue.location)))
+        .collect())
+}
+
+pub fn run_to_polka<W: WriteColor>(error_writer: &mut W, options: Options) -> Result<BuildOutput> {
+    let mut timings = PassTimings::default();
+    let result = run_to_polka_timed(error_writer, &options, &mut timings);
+    if options.time_passes {
+        eprint!("{timings}");
+    }
+    result
+}
+
+/// Like [`run_to_polka`], adding the wall time of every phase to `timings`.
+pub fn run_to_polka_timed<W: WriteColor>(
+    error_writer: &mut W,
+    options: &Options,
+    timings: &mut PassTimings,
+) -> Result<BuildOutput> {
     // Normally the compiler is invoked on a package from `move build`
     // coomand, and builds an entire package as a .so file.  The test
     // harness is currently designed to invoke stand-alone compiler
@@ -324,60 +964,139 @@ pub fn run_to_polka<W: WriteColor>(error_writer: &mut W, options: Options) -> an
     // link the separate .o files into a .so file.  If all input files
     // are .o object files, the compiler assumes that it should link
     // them into an output .so file.
+    let sources = options.resolved_sources()?;
     if !options.llvm_ir
         && !options.compile
+        && !options.check
         && options.bytecode_file_path.is_none()
-        && options.sources.iter().all(|s| s.ends_with(".o"))
+        && sources.iter().all(|s| s.ends_with(".o"))
     {
         let output = Path::new(&options.output).to_path_buf();
-        let objects: Vec<PathBuf> = options
-            .sources
-            .iter()
-            .map(|s| Path::new(s).to_path_buf())
-            .collect();
+        let objects: Vec<PathBuf> = sources.iter().map(|s| Path::new(s).to_path_buf()).collect();
+        let elf_file = options.emit_elf.then(|| linker::elf_path(&output));
+        artifacts::Artifacts::new(&output).prepare()?;
+        // the merged object goes to a temporary directory, not next to the blob
+        let temp_dir = tempfile::Builder::new()
+            .prefix("move-polka-link-")
+            .tempdir()?;
         link_object_files(
-            output.parent().unwrap().to_path_buf(),
+            temp_dir.path().to_path_buf(),
             objects.as_slice(),
-            output,
-            options.move_native_archive.as_deref(),
+            output.clone(),
+            native::resolve_move_native(
+                options.move_native_archive.as_deref(),
+                options.native_variant(),
+            )?,
+            elf_file,
+            timings,
         )?;
-        return Ok(());
+        return Ok(BuildOutput {
+            blob_path: Some(output),
+            object_paths: objects,
+            ..BuildOutput::default()
+        });
     }
-    match &*options.gen_dot_cfg {
-        "write" | "view" | "" => {}
-        _ => {
-            eprintln!(
-                "unexpected gen-dot-cfg option '{}', ignored.",
-                &options.gen_dot_cfg
-            );
+    let (global_env, diagnostics) = timings.time("model building", || {
+        if options.bytecode_file_path.is_some() {
+            (get_env_from_bytecode(options), vec![])
+        } else {
+            get_env_and_diagnostics_from_source(error_writer, options)
         }
-    };
+    });
+    let global_env = global_env?;
+    let mut warnings = diagnostics
+        .into_iter()
+        .filter(MoveDiagnostic::is_warning)
+        .collect_vec();
 
-    let global_env: GlobalEnv = if options.bytecode_file_path.is_some() {
-        get_env_from_bytecode(&options)?
-    } else {
-        get_env_from_source(error_writer, &options)?
-    };
+    // Report unsupported constructs up front, rather than panicking on the first one
+    // in the middle of code generation.
+    let check_warnings = timings.time("check", || check(&global_env, options))?;
+    report_warnings(error_writer, options, &check_warnings)?;
+    warnings.extend(check_warnings);
+    let plan_path = write_build_plan(&global_env, options)?;
+    if options.check || options.plan_only {
+        return Ok(BuildOutput {
+            warnings,
+            plan_path,
+            ..BuildOutput::default()
+        });
+    }
 
-    compile(&global_env, &options)?;
+    let output = compile_timed(&global_env, options, timings)?;
+    Ok(BuildOutput {
+        warnings,
+        plan_path,
+        ..output
+    })
+}
 
+/// Print the warnings of the code generator's checks to `error_writer`, unless
+/// `--quiet-warnings`, and fail with `--warn-as-error`, like the warnings of the Move compiler.
+fn report_warnings<W: WriteColor>(
+    error_writer: &mut W,
+    options: &Options,
+    warnings: &[MoveDiagnostic],
+) -> Result<()> {
+    if !options.quiet_warnings {
+        for warning in warnings {
+            match &warning.location {
+                Some(location) => {
+                    writeln!(error_writer, "warning: {location}: {}", warning.message)?
+                }
+                None => writeln!(error_writer, "warning: {}", warning.message)?,
+            }
+        }
+    }
+    if options.warn_as_error && !warnings.is_empty() {
+        return Err(MoveToPolkaError::compile(format!(
+            "{} warning(s) treated as errors (--warn-as-error)",
+            warnings.len()
+        )));
+    }
     Ok(())
 }
 
+/// Write the build plan of `global_env` with `--build-plan` or `--plan-only`, returning its
+/// path, or else remove the plan of an earlier build. Building keeps the plan (see
+/// [`artifacts::Artifacts::prepare`]), so it's removed here rather than with the other
+/// artifacts.
+fn write_build_plan(global_env: &GlobalEnv, options: &Options) -> Result<Option<PathBuf>> {
+    let plan_path = build_plan::build_plan_path(Path::new(&options.output));
+    if !options.build_plan && !options.plan_only {
+        if plan_path.is_file() {
+            fs::remove_file(&plan_path)?;
+        }
+        return Ok(None);
+    }
+    if let Some(dir) = plan_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    build_plan::BuildPlan::new(global_env, options).write(&plan_path)?;
+    debug!("Build plan written to: {}", plan_path.display());
+    Ok(Some(plan_path))
+}
+
 /// Build a `GlobalEnv` from a collection of `CompiledModule`'s. The `modules` list must be
 /// topologically sorted by the dependency relation (i.e., a child node in the dependency graph
 /// should appear earlier in the vector than its parents).
 pub fn run_bytecode_model_builder<'a>(
     modules: impl IntoIterator<Item = &'a CompiledModule>,
-) -> anyhow::Result<GlobalEnv> {
+) -> Result<GlobalEnv> {
+    run_bytecode_model_builder_with_source_maps(modules.into_iter().map(|m| (m.clone(), None)))
+}
+
+/// Like [`run_bytecode_model_builder`], attaching each module's source map, when given,
+/// so that locations point into the original Move sources.
+pub fn run_bytecode_model_builder_with_source_maps(
+    modules: impl IntoIterator<Item = (CompiledModule, Option<SourceMap>)>,
+) -> Result<GlobalEnv> {
     let mut env = GlobalEnv::new();
-    for (i, m) in modules.into_iter().enumerate() {
+    for (i, (m, source_map)) in modules.into_iter().enumerate() {
         let module_id = ModuleId::new(i);
-        env.attach_compiled_module(
-            module_id,
-            m.clone(),
-            SourceMap::new(MoveIrLoc::new(FileHash::empty(), 0, 0), None),
-        );
+        let source_map = source_map
+            .unwrap_or_else(|| SourceMap::new(MoveIrLoc::new(FileHash::empty(), 0, 0), None));
+        env.attach_compiled_module(module_id, m, source_map);
     }
     Ok(env)
 }
@@ -1,32 +1,50 @@
-use crate::{options::Options, run_to_polka};
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+use crate::{
+    abi::Abi,
+    cfg::CfgOutput,
+    diagnostics::MoveDiagnostic,
+    error::{MoveToPolkaError, Result},
+    get_env_and_diagnostics_from_source,
+    metadata::PackageMetadata,
+    native::{AddressLength, NativeVariant},
+    options::Options,
+    run_to_polka, BuildOutput,
+};
+use codespan_reporting::term::termcolor::{ColorChoice, NoColor, StandardStream};
 use core::mem::MaybeUninit;
 use gix::{
     progress::Discard,
     remote::{fetch::Shallow, Direction},
 };
-use log::{debug, info, trace, warn};
 use move_package::source_package::{
     layout::SourcePackageLayout, manifest_parser, parsed_manifest::SubstOrRename,
 };
+use object::{Object, ObjectSymbol, SymbolKind};
 use polkavm::{
-    Caller, Config, Engine, Instance, InterruptKind, Linker, MemoryAccessError, Module,
-    ModuleConfig, ProgramBlob, RawInstance, Reg,
+    BackendKind, CallError, Caller, Config, Engine, GasMeteringKind, Instance, InterruptKind,
+    Linker, MemoryAccessError, Module, ModuleConfig, ProgramBlob, RawInstance, Reg,
 };
 use polkavm_move_native::{
     allocator::MemAllocator,
-    host::{ProgramError, Runtime},
+    host::{
+        Contract, LogPrintSink, MockRandom, PrintSink, PrintedValue, ProgramError, Runtime,
+        StackFrame,
+    },
+    storage::Storage,
     types::{MoveAddress, MoveByteVector, MoveSigner, MoveType, TypeDesc},
-    ALLOC_CODE, HEAP_BASE, PANIC_CODE,
+    HeapStats, ALLOC_CODE, INVALID_REFERENCE_CODE, LOG_LEVEL_DEBUG, LOG_LEVEL_ERROR,
+    LOG_LEVEL_INFO, LOG_LEVEL_WARN, MISSING_ABILITY_CODE, PANIC_CODE, RETURN_FLAG_REVERT,
+    REVIVE_ADDRESS_LENGTH, REVIVE_SENTINEL, REVIVE_VALUE_LENGTH, RUNTIME_FAILURE_TAG,
 };
 use sha2::Digest;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fs::create_dir_all,
     num::NonZero,
-    path::Path,
+    path::{Path, PathBuf},
     sync::atomic::AtomicBool,
 };
+use tiny_keccak::{Hasher, Keccak};
+use tracing::{debug, info, trace, warn};
 
 pub fn create_colored_stdout() -> StandardStream {
     let color = if atty::is(atty::Stream::Stderr) && atty::is(atty::Stream::Stdout) {
@@ -37,8 +55,99 @@ pub fn create_colored_stdout() -> StandardStream {
     StandardStream::stderr(color)
 }
 
-pub fn parse_to_blob(program_bytes: &[u8]) -> anyhow::Result<ProgramBlob> {
-    ProgramBlob::parse(program_bytes.into()).map_err(|e| anyhow::anyhow!("{e:?}"))
+pub fn parse_to_blob(program_bytes: &[u8]) -> Result<ProgramBlob> {
+    ProgramBlob::parse(program_bytes.into())
+        .map_err(|e| MoveToPolkaError::invalid_artifact(format!("{e:?}")))
+}
+
+/// An export of a linked blob, see [`exports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportInfo {
+    /// The plain Move function name, or a runtime export such as `call`.
+    pub name: String,
+    pub pc: u32,
+    /// The `module::function` of an entry function `call_selector` dispatches, from the blob
+    /// metadata.
+    pub function: Option<String>,
+    /// The first 4 bytes of the call data `call_selector` dispatches to the entry function
+    /// with: the default selector of `function`, see [`default_selector`], or the selector of
+    /// an ABI, see [`check_exports`].
+    pub selector: Option<[u8; 4]>,
+}
+
+/// The default selector of the entry function `module::function`, the first 4 bytes of the
+/// keccak256 hash of the name, unless overridden with `#[selector]` or `--selector`.
+pub fn default_selector(function: &str) -> [u8; 4] {
+    let mut keccak = Keccak::v256();
+    keccak.update(function.as_bytes());
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// The exports of the blob in `program_bytes`, in the order of the blob. Exports only carry
+/// the plain function name, so the `module::function` of the entry functions is taken from
+/// the `metadata` of the blob, if it is built with `--emit-metadata`, and their default
+/// selectors computed from it.
+pub fn exports(
+    program_bytes: &[u8],
+    metadata: Option<&PackageMetadata>,
+) -> Result<Vec<ExportInfo>> {
+    let entry_functions = metadata
+        .map(|metadata| metadata.entry_functions.clone())
+        .unwrap_or_default();
+    Ok(parse_to_blob(program_bytes)?
+        .exports()
+        .map(|export| {
+            let name = export.symbol().to_string();
+            let function = entry_functions.get(&name).cloned();
+            ExportInfo {
+                pc: export.program_counter().0,
+                selector: function.as_deref().map(default_selector),
+                function,
+                name,
+            }
+        })
+        .collect())
+}
+
+/// Verify the blob in `program_bytes` dispatches the entry functions of `abi` with the
+/// selectors of `abi`, e.g. before deploying it, and list its exports with those selectors.
+/// The ABI's table of selectors and `module::function` names is hashed the way the compiler
+/// hashes the table `call_selector` dispatches, into the blob `metadata`, so the blob must be
+/// built with `--emit-metadata`. Every exported entry function must be in the ABI.
+pub fn check_exports(
+    program_bytes: &[u8],
+    metadata: Option<&PackageMetadata>,
+    abi: &Abi,
+) -> Result<Vec<ExportInfo>> {
+    let metadata = metadata.ok_or_else(|| {
+        MoveToPolkaError::invalid_artifact("the blob has no metadata to check the selectors with")
+    })?;
+    let abi_hash = hex::encode(abi.selector_table_hash()?);
+    if abi_hash != metadata.selector_table_hash {
+        return Err(MoveToPolkaError::invalid_artifact(format!(
+            "the blob dispatches other selectors than the ABI: selector table hash {} instead of {abi_hash}",
+            metadata.selector_table_hash
+        )));
+    }
+    let mut exports = exports(program_bytes, Some(metadata))?;
+    for export in &mut exports {
+        let Some(name) = &export.function else {
+            continue;
+        };
+        let function = abi
+            .functions
+            .iter()
+            .find(|f| f.name == *name)
+            .ok_or_else(|| {
+                MoveToPolkaError::invalid_artifact(format!(
+                    "the blob exports the entry function `{name}`, which the ABI lacks"
+                ))
+            })?;
+        export.selector = Some(function.selector_bytes()?);
+    }
+    Ok(exports)
 }
 
 #[derive(Debug, Default)]
@@ -61,42 +170,352 @@ impl BuildOptions {
         self
     }
 
+    /// Compile the `.mv` module (or script, if `is_script`) at `bytecode_file` instead of
+    /// Move sources. Its dependencies are passed as `.mv` files with [`Self::dependency`].
+    pub fn bytecode(mut self, bytecode_file: &str, is_script: bool) -> Self {
+        self.options.bytecode_file_path = Some(bytecode_file.to_string());
+        self.options.is_script = is_script;
+        self
+    }
+
     pub fn address_mapping(mut self, mapping: String) -> Self {
         self.options.named_address_mapping.push(mapping);
         self
     }
 
+    /// A compile-time flag, `NAME` or `NAME=VALUE`, see [`crate::defines`].
+    pub fn define(mut self, define: &str) -> Self {
+        self.options.defines.push(define.to_string());
+        self
+    }
+
     pub fn dependency(mut self, dependency_path: &str) -> Self {
         self.options.dependencies.push(dependency_path.to_string());
         self
     }
 
+    pub fn check(mut self, check: bool) -> Self {
+        self.options.check = check;
+        self
+    }
+
+    pub fn build_plan(mut self, build_plan: bool) -> Self {
+        self.options.build_plan = build_plan;
+        self
+    }
+
+    pub fn plan_only(mut self, plan_only: bool) -> Self {
+        self.options.plan_only = plan_only;
+        self
+    }
+
+    pub fn warn_as_error(mut self, warn_as_error: bool) -> Self {
+        self.options.warn_as_error = warn_as_error;
+        self
+    }
+
+    pub fn quiet_warnings(mut self, quiet: bool) -> Self {
+        self.options.quiet_warnings = quiet;
+        self
+    }
+
+    pub fn time_passes(mut self, time_passes: bool) -> Self {
+        self.options.time_passes = time_passes;
+        self
+    }
+
+    pub fn prune_unreachable(mut self, prune: bool) -> Self {
+        self.options.prune_unreachable = prune;
+        self
+    }
+
+    pub fn export_public(mut self, export: bool) -> Self {
+        self.options.export_public = export;
+        self
+    }
+
+    pub fn strip_exports(mut self, strip: bool) -> Self {
+        self.options.strip_exports = strip;
+        self
+    }
+
+    pub fn extra_exports(mut self, exports: Vec<String>) -> Self {
+        self.options.extra_exports = exports;
+        self
+    }
+
+    pub fn inline_overrides(mut self, overrides: Vec<String>) -> Self {
+        self.options.inline_overrides = overrides;
+        self
+    }
+
+    pub fn payable_checks(mut self, payable_checks: bool) -> Self {
+        self.options.payable_checks = payable_checks;
+        self
+    }
+
+    pub fn keep_attributes(mut self, attributes: Vec<String>) -> Self {
+        self.options.keep_attributes = attributes;
+        self
+    }
+
+    pub fn strip_debug_natives(mut self, strip: bool) -> Self {
+        self.options.strip_debug_natives = strip;
+        self
+    }
+
+    pub fn overflow_checks(mut self, enabled: bool) -> Self {
+        self.options.overflow_checks = enabled;
+        self
+    }
+
+    pub fn assume_no_abort(mut self, assume_no_abort: bool) -> Self {
+        self.options.assume_no_abort = assume_no_abort;
+        self
+    }
+
+    pub fn source_map(mut self, source_map: bool) -> Self {
+        self.options.source_map = source_map;
+        self
+    }
+
+    pub fn coverage(mut self, coverage: bool) -> Self {
+        self.options.coverage = coverage;
+        self
+    }
+
+    pub fn heap_stats(mut self, heap_stats: bool) -> Self {
+        self.options.heap_stats = heap_stats;
+        self
+    }
+
+    pub fn paranoid(mut self, paranoid: bool) -> Self {
+        self.options.paranoid = paranoid;
+        self
+    }
+
+    pub fn stack_traces(mut self, stack_traces: bool) -> Self {
+        self.options.stack_traces = stack_traces;
+        self
+    }
+
+    pub fn heap_pages(mut self, pages: u32) -> Self {
+        self.options.heap_pages = pages;
+        self
+    }
+
+    pub fn stack_size(mut self, size: Option<u32>) -> Self {
+        self.options.stack_size = size;
+        self
+    }
+
+    pub fn emit_elf(mut self, emit: bool) -> Self {
+        self.options.emit_elf = emit;
+        self
+    }
+
+    pub fn keep_temps(mut self, keep: bool) -> Self {
+        self.options.keep_temps = keep;
+        self
+    }
+
+    pub fn move_native_variant(mut self, variant: NativeVariant) -> Self {
+        self.options.move_native_variant = variant;
+        self
+    }
+
+    pub fn address_length(mut self, length: AddressLength) -> Self {
+        self.options.address_length = length;
+        self
+    }
+
+    pub fn u256_helpers(mut self, helpers: bool) -> Self {
+        self.options.u256_helpers = helpers;
+        self
+    }
+
+    pub fn llvm_cpu(mut self, cpu: &str) -> Self {
+        self.options.llvm_cpu = Some(cpu.to_string());
+        self
+    }
+
+    pub fn llvm_features(mut self, features: &str) -> Self {
+        self.options.llvm_features = Some(features.to_string());
+        self
+    }
+
+    pub fn gen_dot_cfg(mut self, output: Option<CfgOutput>) -> Self {
+        self.options.gen_dot_cfg = output;
+        self
+    }
+
+    pub fn dot_viewer(mut self, viewer: &str) -> Self {
+        self.options.dot_viewer = viewer.to_string();
+        self
+    }
+
+    pub fn dot_out_dir(mut self, dir: &str) -> Self {
+        self.options.dot_file_path = dir.to_string();
+        self
+    }
+
+    pub fn cfg_dir_per_function(mut self, dir_per_function: bool) -> Self {
+        self.options.cfg_dir_per_function = dir_per_function;
+        self
+    }
+
+    pub fn lto(mut self, lto: bool) -> Self {
+        self.options.lto = lto;
+        self
+    }
+
+    pub fn emit_abi(mut self, emit: bool) -> Self {
+        self.options.emit_abi = emit;
+        self
+    }
+
+    pub fn split_modules(mut self, split: bool) -> Self {
+        self.options.split_modules = split;
+        self
+    }
+
+    pub fn emit_metadata(mut self, emit: bool) -> Self {
+        self.options.emit_metadata = emit;
+        self
+    }
+
+    /// Sign the blob with the ed25519 key read from `source`, see [`crate::signing`].
+    pub fn signing_key(mut self, source: Option<String>) -> Self {
+        self.options.signing_key = source;
+        self
+    }
+
+    pub fn package(mut self, name: &str, version: &str) -> Self {
+        self.options.package_name = Some(name.to_string());
+        self.options.package_version = Some(version.to_string());
+        self
+    }
+
+    /// Compile for testing, giving the unit test function `name` external linkage.
+    pub fn unit_test_function(mut self, name: &str) -> Self {
+        self.options.unit_test_function = Some(name.to_string());
+        self
+    }
+
+    /// Build for the mock host, allowing calls to natives pallet-revive doesn't serve, such
+    /// as `revive::random::bytes`.
+    pub fn mock_host(mut self, mock_host: bool) -> Self {
+        self.options.mock_host = mock_host;
+        self
+    }
+
     pub fn build(self) -> Options {
         self.options
     }
 }
 
-pub fn build_polka_from_move(options: BuildOptions) -> anyhow::Result<Vec<u8>> {
-    let output_file = options.options.output.clone();
-    // parse move source files
-    let mut color_writer = create_colored_stdout();
-    run_to_polka(&mut color_writer, options.options)?;
+pub fn build_polka_from_move(options: BuildOptions) -> Result<Vec<u8>> {
+    let output = build_move_package(options)?;
+    let blob_path = output
+        .blob_path
+        .ok_or_else(|| MoveToPolkaError::invalid_artifact("the build linked no blob"))?;
 
     //TODO it would be so nice if compile won't access FS directly so we can work purely in-memory
-    let data = std::fs::read(output_file)?;
+    let data = std::fs::read(blob_path)?;
     Ok(data)
 }
 
-pub fn load_from_elf_with_polka_linker(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+/// Build the package and return the paths of the artifacts written, see [`BuildOutput`].
+pub fn build_move_package(options: BuildOptions) -> Result<BuildOutput> {
+    let mut color_writer = create_colored_stdout();
+    run_to_polka(&mut color_writer, options.options)
+}
+
+/// Link the merged ELF object into a PolkaVM blob.
+///
+/// Symbols and debug line programs are kept (no stripping), since the source map
+/// generated from the blob relies on them.
+pub fn load_from_elf_with_polka_linker(data: &[u8]) -> Result<Vec<u8>> {
+    link_elf(data, &LinkOptions::default())
+}
+
+/// File name suffix of the merged ELF object written next to a blob by `--emit-elf`.
+pub const ELF_EXTENSION: &str = "elf";
+
+/// H160 of the contract in the mock host; `revive::transfer` spends its balance.
+pub const CONTRACT_ADDRESS: [u8; REVIVE_ADDRESS_LENGTH] =
+    hex_literal::hex!("c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0");
+
+// pallet-revive's `ReturnErrorCode::CalleeReverted` and `ReturnErrorCode::TransferFailed`
+const CALLEE_REVERTED: u32 = 2;
+const TRANSFER_FAILED: u32 = 5;
+
+/// Path of the merged ELF object belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.elf`.
+pub fn elf_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(ELF_EXTENSION)
+}
+
+/// The `module::NAME` symbols of the address and vector constants in the merged ELF object
+/// `elf`, see [`ELF_EXTENSION`]. Constants without a Move `const` of their value are unnamed
+/// and not listed.
+pub fn read_constants(elf: &[u8]) -> Result<Vec<String>> {
+    let file = object::File::parse(elf).map_err(MoveToPolkaError::invalid_artifact)?;
+    let mut constants = file
+        .symbols()
+        .filter(|symbol| symbol.is_local() && symbol.kind() == SymbolKind::Data)
+        .filter_map(|symbol| symbol.name().ok().map(str::to_string))
+        .filter(|name| name.contains("::"))
+        .collect::<Vec<_>>();
+    constants.sort();
+    constants.dedup();
+    Ok(constants)
+}
+
+/// Settings of the polka linker step, see [`link_elf`].
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    /// Strip symbols and debug info. Source maps can't be generated from stripped blobs.
+    pub strip: bool,
+    /// Let the polka linker optimize the program.
+    pub optimize: bool,
+    /// Exports to put first in the export table, in this order.
+    pub dispatch_table: Vec<String>,
+}
+
+/// Link the merged ELF object into a PolkaVM blob with the given polka linker settings.
+pub fn link_elf(data: &[u8], link_options: &LinkOptions) -> Result<Vec<u8>> {
     // config is taken from polkatool with default values
     let mut config = polkavm_linker::Config::default();
-    config.set_strip(false);
-    config.set_optimize(false);
+    config.set_strip(link_options.strip);
+    config.set_optimize(link_options.optimize);
+    if !link_options.dispatch_table.is_empty() {
+        config.set_dispatch_table(
+            link_options
+                .dispatch_table
+                .iter()
+                .map(|export| export.as_bytes().to_vec())
+                .collect(),
+        );
+    }
 
-    let res = polkavm_linker::program_from_elf(config, data)?;
+    let res = polkavm_linker::program_from_elf(config, data).map_err(MoveToPolkaError::link)?;
     Ok(res)
 }
 
+/// Run only the polka linker step again on an ELF object written by `--emit-elf`,
+/// skipping codegen. No metadata is written for the relinked blob.
+pub fn relink(elf_file: &Path, output_file: &Path, link_options: &LinkOptions) -> Result<Vec<u8>> {
+    let program = link_elf(&std::fs::read(elf_file)?, link_options)?;
+    std::fs::write(output_file, &program)?;
+    debug!(
+        "Relinked {} into {}",
+        elf_file.display(),
+        output_file.display()
+    );
+    Ok(program)
+}
+
 pub type PolkaError = polkavm::Error;
 pub type LinkerResult<T> = Result<T, PolkaError>;
 
@@ -108,73 +527,198 @@ pub fn new_move_program(
     output: &str,
     source: &str,
     mapping: HashSet<String>,
-) -> Result<(Instance<Runtime, ProgramError>, Runtime), anyhow::Error> {
+) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
     create_instance(create_blob(output, source, mapping)?)
 }
 
 /// Load a Move program from source and create a PolkaVM blob.
-pub fn create_blob(
+pub fn create_blob(output: &str, source: &str, mapping: HashSet<String>) -> Result<ProgramBlob> {
+    let build_options = package_build_options(output, source, mapping)?;
+    debug!("Build options: {build_options:?}");
+    let program_bytes = build_polka_from_move(build_options)?;
+    let blob = parse_to_blob(&program_bytes)?;
+    Ok(blob)
+}
+
+/// Check a Move package for unsupported constructs without generating any code.
+pub fn check_package(source: &str, mapping: HashSet<String>) -> Result<()> {
+    let build_options = package_build_options("output.polkavm", source, mapping)?.check(true);
+    debug!("Build options: {build_options:?}");
+    let mut color_writer = create_colored_stdout();
+    run_to_polka(&mut color_writer, build_options.build()).map(drop)
+}
+
+/// Compile a Move package and return the diagnostics of the Move compiler, warnings included,
+/// without printing them. Errors in the package don't fail the call, they are diagnostics too.
+pub fn package_diagnostics(source: &str, mapping: HashSet<String>) -> Result<Vec<MoveDiagnostic>> {
+    let build_options = package_build_options("output.polkavm", source, mapping)?;
+    debug!("Build options: {build_options:?}");
+    let mut writer = NoColor::new(std::io::sink());
+    let (_, diagnostics) = get_env_and_diagnostics_from_source(&mut writer, &build_options.build());
+    Ok(diagnostics)
+}
+
+/// Path of the blob built for the package at `source`, following the `build/<package>/`
+/// layout of other Move backends: `<install_dir>/build/<package>/polkavm/<package>.polkavm`.
+/// `install_dir` defaults to the package root.
+pub fn package_artifact_path(source: &str, install_dir: Option<&str>) -> Result<PathBuf> {
+    let root =
+        SourcePackageLayout::try_find_root(Path::new(source)).map_err(MoveToPolkaError::compile)?;
+    let manifest = manifest_parser::parse_move_manifest_from_file(&root)
+        .map_err(|e| MoveToPolkaError::compile(format!("Failed to parse Move manifest: {e}")))?;
+    let name = manifest.package.name.as_str();
+    let base = install_dir.map(PathBuf::from).unwrap_or(root);
+    Ok(base
+        .join("build")
+        .join(name)
+        .join("polkavm")
+        .join(format!("{name}.polkavm")))
+}
+
+/// Resolve the sources, dependencies and address mapping of the package rooted at `source`.
+pub fn package_build_options(
     output: &str,
     source: &str,
+    mapping: HashSet<String>,
+) -> Result<BuildOptions> {
+    packages_build_options(output, &[source], &[], mapping)
+}
+
+/// Resolve the package rooted at `source` for a test build of `unit_test_function`: like
+/// [`package_build_options`], with the `[dev-dependencies]` and `[dev-addresses]` of the
+/// package, which regular builds leave out.
+pub fn package_test_build_options(
+    output: &str,
+    source: &str,
+    mapping: HashSet<String>,
+    unit_test_function: &str,
+) -> Result<BuildOptions> {
+    Ok(resolve_packages(output, &[source], &[], mapping, true)?
+        .unit_test_function(unit_test_function))
+}
+
+/// Resolve the sources, dependencies and address mapping of several packages compiled into
+/// one blob. The first package names the blob in the metadata. `dependency_roots` are
+/// directories, files or glob patterns that are only compiled as dependencies, e.g. a
+/// vendored framework without a `Move.toml`. A package that is also a dependency of
+/// another one is compiled as a source.
+pub fn packages_build_options(
+    output: &str,
+    packages: &[&str],
+    dependency_roots: &[&str],
+    mapping: HashSet<String>,
+) -> Result<BuildOptions> {
+    resolve_packages(output, packages, dependency_roots, mapping, false)
+}
+
+/// Like [`packages_build_options`], for test builds when `dev` is set: the
+/// `[dev-dependencies]` of the packages are compiled too, and the `[dev-addresses]` assign
+/// the named addresses left unassigned (`_`) in `[addresses]`. Without `dev`, both are left
+/// out.
+pub(crate) fn resolve_packages(
+    output: &str,
+    packages: &[&str],
+    dependency_roots: &[&str],
     mut mapping: HashSet<String>,
-) -> Result<ProgramBlob, anyhow::Error> {
+    dev: bool,
+) -> Result<BuildOptions> {
     let mut build_options = BuildOptions::new(output);
-    build_options = build_options.source(source);
-    let path = std::path::Path::new(source);
     let mut dep_sources = vec![];
-    if !path.is_dir() {
-        return Err(anyhow::anyhow!(
-            "Source must be a directory containing Move.toml: {source}"
-        ));
-    }
-    let toml = SourcePackageLayout::try_find_root(path)?;
-    let manifest = manifest_parser::parse_move_manifest_from_file(&toml)
-        .map_err(|e| anyhow::anyhow!("Failed to parse Move manifest: {e}"))?;
-    manifest
-        .dependencies
-        .iter()
-        .chain(manifest.dev_dependencies.iter())
-        .for_each(|(key, dep)| {
-            debug!("Processing dependency: {key} => {dep}");
-            if let Some(git_url) = dep.git_info.as_ref().map(|g| g.git_url.as_str()) {
-                fetch_git_dep(key, &mut mapping, &mut dep_sources, dep, git_url)
-                    .expect("Failed to fetch git dependency");
-            } else {
-                let local_path = path.join(Path::new(&dep.local));
-                if local_path.exists() && local_path.is_dir() {
-                    // check if the directory contains Move.toml
-                    let _toml = SourcePackageLayout::try_find_root(&local_path)
-                        .expect("Failed to find Move.toml in dependency");
-                    if let Some(dep_mapping) = dep.subst.as_ref() {
-                        for (name, subst) in dep_mapping {
-                            if let SubstOrRename::Assign(ref addr) = subst {
-                                let mapping_str = format!("{}={}", name, addr.to_standard_string());
-                                mapping.insert(mapping_str);
+    for (i, source) in packages.iter().enumerate() {
+        build_options = build_options.source(source);
+        let path = std::path::Path::new(source);
+        if !path.is_dir() {
+            return Err(MoveToPolkaError::compile(format!(
+                "Source must be a directory containing Move.toml: {source}"
+            )));
+        }
+        let toml = SourcePackageLayout::try_find_root(path).map_err(MoveToPolkaError::compile)?;
+        let manifest = manifest_parser::parse_move_manifest_from_file(&toml).map_err(|e| {
+            MoveToPolkaError::compile(format!("Failed to parse Move manifest: {e}"))
+        })?;
+        if i == 0 {
+            let (major, minor, patch) = manifest.package.version;
+            build_options = build_options.package(
+                manifest.package.name.as_str(),
+                &format!("{major}.{minor}.{patch}"),
+            );
+        }
+        let dev_dependencies = dev.then_some(&manifest.dev_dependencies);
+        manifest
+            .dependencies
+            .iter()
+            .chain(dev_dependencies.into_iter().flatten())
+            .for_each(|(key, dep)| {
+                debug!("Processing dependency: {key} => {dep}");
+                if let Some(git_url) = dep.git_info.as_ref().map(|g| g.git_url.as_str()) {
+                    fetch_git_dep(key, &mut mapping, &mut dep_sources, dep, git_url)
+                        .expect("Failed to fetch git dependency");
+                } else {
+                    let local_path = path.join(Path::new(&dep.local));
+                    if local_path.exists() && local_path.is_dir() {
+                        // check if the directory contains Move.toml
+                        let _toml = SourcePackageLayout::try_find_root(&local_path)
+                            .expect("Failed to find Move.toml in dependency");
+                        if let Some(dep_mapping) = dep.subst.as_ref() {
+                            for (name, subst) in dep_mapping {
+                                if let SubstOrRename::Assign(ref addr) = subst {
+                                    let mapping_str =
+                                        format!("{}={}", name, addr.to_standard_string());
+                                    mapping.insert(mapping_str);
+                                }
                             }
                         }
+                        dep_sources.push(local_path.to_string_lossy().to_string());
                     }
-                    dep_sources.push(local_path.to_string_lossy().to_string());
+                }
+            });
+        if let Some(addresses) = &manifest.addresses {
+            for (name, addr) in addresses.iter() {
+                if let Some(addr) = addr {
+                    let mapping_str = format!("{}={}", name.as_str(), addr.to_standard_string());
+                    mapping.insert(mapping_str);
                 }
             }
-        });
-    if let Some(addresses) = &manifest.addresses {
-        for (name, addr) in addresses.iter() {
-            if let Some(addr) = addr {
-                let mapping_str = format!("{}={}", name.as_str(), addr.to_standard_string());
-                mapping.insert(mapping_str);
+        }
+        if let Some(dev_addresses) = manifest.dev_address_assignments.as_ref().filter(|_| dev) {
+            for (name, addr) in dev_addresses.iter() {
+                let assigned = manifest
+                    .addresses
+                    .as_ref()
+                    .and_then(|addresses| addresses.get(name))
+                    .is_some_and(Option::is_some);
+                if !assigned {
+                    mapping.insert(format!("{}={}", name.as_str(), addr.to_standard_string()));
+                }
             }
         }
     }
+    dep_sources.extend(dependency_roots.iter().map(|root| root.to_string()));
+    // packages depending on each other, or on the same package, list it once
+    let package_roots = packages
+        .iter()
+        .filter_map(|package| Path::new(package).canonicalize().ok())
+        .collect::<Vec<_>>();
+    let mut seen = HashSet::new();
     for source in dep_sources {
-        build_options = build_options.dependency(&source);
+        let canonical = Path::new(&source).canonicalize().ok();
+        if canonical
+            .as_ref()
+            .is_some_and(|path| package_roots.contains(path))
+        {
+            continue;
+        }
+        if seen.insert(canonical.unwrap_or_else(|| PathBuf::from(&source))) {
+            build_options = build_options.dependency(&source);
+        }
     }
+    // sorted, so builds don't depend on the set's iteration order
+    let mut mapping = mapping.into_iter().collect::<Vec<_>>();
+    mapping.sort();
     for m in mapping {
         build_options = build_options.address_mapping(m);
     }
-    debug!("Build options: {build_options:?}");
-    let program_bytes = build_polka_from_move(build_options)?;
-    let blob = parse_to_blob(&program_bytes)?;
-    Ok(blob)
+    Ok(build_options)
 }
 
 fn fetch_git_dep(
@@ -224,19 +768,131 @@ fn fetch_git_dep(
     Ok(())
 }
 
+/// The origin of every call, returned by the `origin` import.
+const ORIGIN_ADDR: &[u8] = &hex_literal::hex!("ab010101010101010101010101010101010101ce");
+
+/// Call data of instances until the caller sets its own, the selector of
+/// `storage::borrow_mut_abort`.
+pub const DEFAULT_CALL_DATA: &[u8] = &hex_literal::hex!("c429b279");
+
 /// Creates a new PolkaVM instance with the Move program blob.
-pub fn create_instance(
+pub fn create_instance(blob: ProgramBlob) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
+    instantiate(blob, None, None, false, Box::new(LogPrintSink))
+}
+
+/// Like [`create_instance`], handing the values the program prints with `debug::print` to
+/// `print_sink` instead of logging them.
+pub fn create_instance_with_print_sink(
+    blob: ProgramBlob,
+    print_sink: impl PrintSink + 'static,
+) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
+    instantiate(blob, None, None, false, Box::new(print_sink))
+}
+
+/// Creates a new PolkaVM instance with the Move program blob, metering gas. Calls running out
+/// of `gas` fail with `CallError::NotEnoughGas`.
+pub fn create_metered_instance(
+    blob: ProgramBlob,
+    gas: i64,
+) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
+    instantiate(blob, None, Some(gas), false, Box::new(LogPrintSink))
+}
+
+/// Creates a new PolkaVM instance running the Move program blob on `backend`, the interpreter
+/// or the compiler, instead of the backend PolkaVM picks, metering gas if `gas` is given. Fails
+/// if the backend isn't available on this host.
+pub fn create_instance_on_backend(
+    blob: ProgramBlob,
+    backend: BackendKind,
+    gas: Option<i64>,
+) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
+    instantiate(blob, Some(backend), gas, false, Box::new(LogPrintSink))
+}
+
+/// Creates a new PolkaVM instance with the Move program blob that interrupts with
+/// `InterruptKind::Step` before every instruction when run with `RawInstance::run`, for
+/// single-stepping, see [`crate::trace`].
+pub fn create_stepping_instance(
+    blob: ProgramBlob,
+) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
+    instantiate(blob, None, None, true, Box::new(LogPrintSink))
+}
+
+/// A Move contract for [`Runtime::contracts`]. Every call runs the `call` export of the blob on
+/// a new instance, with the input as call data; the contract's global storage is kept from one
+/// call to the next, and rolled back when a call reverts. The instance has a runtime of its own,
+/// so the contract doesn't see the balances of the caller and can't call further contracts.
+pub struct BlobContract {
+    blob: ProgramBlob,
+    storage: Option<Box<dyn Storage>>,
+}
+
+impl BlobContract {
+    pub fn new(blob: ProgramBlob) -> Self {
+        Self {
+            blob,
+            storage: None,
+        }
+    }
+}
+
+impl Contract for BlobContract {
+    fn call(&mut self, input: &[u8], value: u128) -> std::result::Result<Vec<u8>, Vec<u8>> {
+        let (mut instance, mut runtime) = match create_instance(self.blob.clone()) {
+            Ok(instance) => instance,
+            Err(e) => {
+                warn!("Failed to instantiate the called contract: {e}");
+                return Err(vec![]);
+            }
+        };
+        if let Some(storage) = self.storage.take() {
+            runtime.storage = storage;
+        }
+        // a reverted call leaves no trace in the storage of the contract
+        let state = runtime.storage.snapshot();
+        runtime.call_data = input.to_vec();
+        runtime.value_transferred = value;
+        let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ());
+        let output = std::mem::take(&mut runtime.output);
+        if result.is_err() {
+            runtime.storage.restore(&state);
+        }
+        self.storage = Some(runtime.storage);
+        match result {
+            Ok(()) => Ok(output),
+            // like the revert of `move_rt_abort`, with the abort code as output
+            Err(CallError::User(ProgramError::Abort(code))) => Err(code.to_le_bytes().to_vec()),
+            Err(e) => {
+                warn!("Called contract failed: {e:?}");
+                Err(vec![])
+            }
+        }
+    }
+}
+
+fn instantiate(
     blob: ProgramBlob,
-) -> Result<(Instance<Runtime, ProgramError>, Runtime), anyhow::Error> {
+    backend: Option<BackendKind>,
+    gas: Option<i64>,
+    step_tracing: bool,
+    print_sink: Box<dyn PrintSink>,
+) -> Result<(Instance<Runtime, ProgramError>, Runtime)> {
     // AUX segment is used to inject data into the guest. The guest allocates on the heap
     // using the LeakingAllocator.
     const AUX_DATA_SIZE: u32 = 4 * 1024;
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
+    if backend.is_some() {
+        config.set_backend(backend);
+    }
 
     let mut module_config = ModuleConfig::new();
     // enforce module loading fail if not all host functions are provided
     module_config.set_strict(true);
     module_config.set_aux_data_size(AUX_DATA_SIZE);
+    if gas.is_some() {
+        module_config.set_gas_metering(Some(GasMeteringKind::Sync));
+    }
+    module_config.set_step_tracing(step_tracing);
 
     let engine = Engine::new(&config)?;
     let module = Module::from_blob(&engine, &module_config, blob.clone())?;
@@ -246,6 +902,18 @@ pub fn create_instance(
     let runtime = Runtime {
         allocator,
         storage: Box::new(storage),
+        coverage_counters: None,
+        heap_stats: None,
+        stack_trace: None,
+        balances: BTreeMap::new(),
+        value_transferred: 0,
+        call_data: DEFAULT_CALL_DATA.to_vec(),
+        output: vec![],
+        return_data: vec![],
+        contracts: BTreeMap::new(),
+        print_sink,
+        timestamp_ms: 0,
+        random: MockRandom::default(),
     };
     let mut linker: MoveProgramLinker = Linker::new();
 
@@ -258,30 +926,59 @@ pub fn create_instance(
         hexdump(instance);
     })?;
 
+    linker.define_typed(
+        "coverage_register",
+        |caller: Caller<Runtime>, counters: u32, len: u32| {
+            caller.user_data.coverage_counters = Some((counters, len));
+        },
+    )?;
+
+    linker.define_typed(
+        "heap_stats_register",
+        |caller: Caller<Runtime>, stats: u32| {
+            caller.user_data.heap_stats = Some(stats);
+        },
+    )?;
+
+    linker.define_typed(
+        "stack_trace",
+        |caller: Caller<Runtime>, frames: u32, depth: u32| {
+            let trace = read_stack_trace(caller.instance, frames, depth)?;
+            caller.user_data.stack_trace = Some(trace);
+            Result::<(), ProgramError>::Ok(())
+        },
+    )?;
+
     linker.define_typed(
         "debug_print",
         |caller: Caller<Runtime>, ptr_to_type: u32, ptr_to_data: u32| {
+            let printed = debug_print(caller.instance, ptr_to_type, ptr_to_data)?;
+            caller.user_data.print_sink.print(printed);
+            Result::<(), ProgramError>::Ok(())
+        },
+    )?;
+
+    linker.define_typed(
+        "log",
+        |caller: Caller<Runtime>, level: u32, ptr_to_msg: u32, len: u64| {
             let instance = caller.instance;
-            debug_print(instance, ptr_to_type, ptr_to_data)
+            guest_log(instance, level, ptr_to_msg, len)
         },
     )?;
 
-    const SELECTOR: &[u8] = &hex_literal::hex!("c429b279");
-    linker.define_typed("call_data_size", || SELECTOR.len() as u64)?;
+    linker.define_typed("call_data_size", |caller: Caller<Runtime>| {
+        caller.user_data.call_data.len() as u64
+    })?;
 
     linker.define_typed("call_selector", || {})?;
 
     linker.define_typed(
         "call_data_copy",
-        |caller: Caller<Runtime>, ptr_to_buf: u32, _size: u32, _offset: u32| {
-            let instance = caller.instance;
-            instance.write_memory(ptr_to_buf, SELECTOR)?;
-            Result::<(), ProgramError>::Ok(())
+        |caller: Caller<Runtime>, ptr_to_buf: u32, size: u32, offset: u32| {
+            call_data_copy(caller.user_data, caller.instance, ptr_to_buf, size, offset)
         },
     )?;
 
-    const ORIGIN_ADDR: &[u8] = &hex_literal::hex!("ab010101010101010101010101010101010101ce");
-
     linker.define_typed("origin", |caller: Caller<Runtime>, ptr_to_buf: u32| {
         let instance = caller.instance;
         instance.write_memory(ptr_to_buf, ORIGIN_ADDR)?;
@@ -325,12 +1022,26 @@ pub fn create_instance(
     )?;
 
     linker.define_typed(
-        "terminate",
-        |caller: Caller<Runtime>, ptr_to_beneficiary: u32| {
-            let instance = caller.instance;
-            let beneficiary = copy_bytes_from_guest(instance, ptr_to_beneficiary, 20)
-                .expect("Failed to copy beneficiary address from guest");
-            guest_abort(instance, beneficiary[0] as u64)
+        "seal_return",
+        |caller: Caller<Runtime>, flags: u32, data_ptr: u32, data_len: u32| {
+            seal_return(caller.user_data, caller.instance, flags, data_ptr, data_len)
+        },
+    )?;
+
+    linker.define_typed("return_data_size", |caller: Caller<Runtime>| {
+        caller.user_data.return_data.len() as u64
+    })?;
+
+    linker.define_typed(
+        "return_data_copy",
+        |caller: Caller<Runtime>, ptr_to_buf: u32, ptr_to_len: u32, offset: u32| {
+            return_data_copy(
+                caller.user_data,
+                caller.instance,
+                ptr_to_buf,
+                ptr_to_len,
+                offset,
+            )
         },
     )?;
 
@@ -351,16 +1062,65 @@ pub fn create_instance(
         },
     )?;
 
+    linker.define_typed(
+        "balance_of",
+        |caller: Caller<Runtime>, ptr_to_addr: u32, ptr_to_out: u32| {
+            balance_of(caller.user_data, caller.instance, ptr_to_addr, ptr_to_out)
+        },
+    )?;
+
+    linker.define_typed(
+        "value_transferred",
+        |caller: Caller<Runtime>, ptr_to_out: u32| {
+            value_transferred(caller.user_data, caller.instance, ptr_to_out)
+        },
+    )?;
+
+    linker.define_typed("now", |caller: Caller<Runtime>, ptr_to_out: u32| {
+        now(caller.user_data, caller.instance, ptr_to_out)
+    })?;
+
+    linker.define_typed(
+        "random_bytes",
+        |caller: Caller<Runtime>, ptr_to_out: u32, len: u32| {
+            random_bytes(caller.user_data, caller.instance, ptr_to_out, len)
+        },
+    )?;
+
+    linker.define_typed(
+        "call",
+        |caller: Caller<Runtime>,
+         flags_and_callee: u64,
+         _ref_time_limit: u64,
+         _proof_size_limit: u64,
+         deposit_and_value: u64,
+         input_data: u64,
+         _output_data: u64| {
+            call(
+                caller.user_data,
+                caller.instance,
+                flags_and_callee,
+                deposit_and_value,
+                input_data,
+            )
+        },
+    )?;
+
     // Link the host functions with the module.
     let instance_pre = linker.instantiate_pre(&module)?;
 
     // Instantiate the module.
     let mut instance = instance_pre.instantiate()?;
+    if let Some(gas) = gas {
+        instance.set_gas(gas);
+    }
     // zero aux data
-    instance.zero_memory(
-        module.memory_map().aux_data_address(),
-        module.memory_map().aux_data_size(),
-    )?;
+    instance
+        .zero_memory(
+            module.memory_map().aux_data_address(),
+            module.memory_map().aux_data_size(),
+        )
+        .map_err(|e| MoveToPolkaError::Vm(format!("{e:?}")))?;
     debug!(
         "Module loaded with RW data size: {}, RO data size: {}, aux data size: {}, heap start: {:x?}",
         module.memory_map().rw_data_size(),
@@ -452,13 +1212,53 @@ pub fn copy_bytes_from_guest(
     Ok(initialized.to_vec())
 }
 
+/// Read the counters of a coverage build back after a call, see [`crate::coverage`].
+/// Returns `None` if no instrumented function ran.
+pub fn read_coverage_counters(
+    instance: &mut RawInstance,
+    runtime: &Runtime,
+) -> Result<Option<Vec<u32>>> {
+    let Some((address, len)) = runtime.coverage_counters else {
+        return Ok(None);
+    };
+    let bytes = copy_bytes_from_guest(instance, address, len as usize * 4)
+        .map_err(|e| MoveToPolkaError::Vm(e.to_string()))?;
+    let counters = bytes
+        .chunks_exact(4)
+        .map(|count| u32::from_le_bytes(count.try_into().unwrap()))
+        .collect();
+    Ok(Some(counters))
+}
+
+/// Read the guest allocator's counters after a call of a `--heap-stats` build.
+/// Returns `None` if no function registered them.
+pub fn read_heap_stats(instance: &mut RawInstance, runtime: &Runtime) -> Result<Option<HeapStats>> {
+    let Some(address) = runtime.heap_stats else {
+        return Ok(None);
+    };
+    let bytes = copy_bytes_from_guest(instance, address, size_of::<HeapStats>())
+        .map_err(|e| MoveToPolkaError::Vm(e.to_string()))?;
+    let mut counters = bytes
+        .chunks_exact(4)
+        .map(|counter| u32::from_le_bytes(counter.try_into().unwrap()));
+    let mut next = || counters.next().unwrap_or_default();
+    Ok(Some(HeapStats {
+        in_use: next(),
+        peak: next(),
+        allocations: next(),
+        frees: next(),
+        reallocs_in_place: next(),
+        reallocs_moved: next(),
+    }))
+}
+
 /// Different way to run the program, which allows to handle low-level interrupts
 /// The caller must store the parameters to the entrypoint function into registers before calling this function.
 pub fn run_lowlevel(
     instance: &mut Instance<Runtime, ProgramError>,
     runtime: &mut Runtime,
     entry: &str,
-) -> Result<(), anyhow::Error> {
+) -> Result<()> {
     let start = instance
         .module()
         .exports()
@@ -470,14 +1270,25 @@ pub fn run_lowlevel(
     // cache imports with their indices
     const ALLOWED_IMPORTS: &[&[u8]] = &[
         b"debug_print",
+        b"log",
         b"hex_dump",
-        b"terminate",
+        b"seal_return",
         b"move_to",
         b"move_from",
         b"exists",
         b"release",
         b"hash_sha2_256",
         b"hash_sha3_256",
+        b"coverage_register",
+        b"heap_stats_register",
+        b"stack_trace",
+        b"balance_of",
+        b"value_transferred",
+        b"now",
+        b"random_bytes",
+        b"call",
+        b"return_data_size",
+        b"return_data_copy",
     ];
     let map: HashMap<usize, &'static str> = imports
         .into_iter()
@@ -541,56 +1352,168 @@ fn handle_ecalli(
     runtime: &mut Runtime,
     syscall: &str,
 ) {
+    match host_call(instance, runtime, syscall) {
+        Ok(()) => {}
+        // a reverting `seal_return` ends the run, and a stack trace is best effort
+        Err(e) if syscall == "seal_return" || syscall == "stack_trace" => {
+            debug!("{syscall} failed: {e:?}")
+        }
+        Err(e) => panic!("Host call {syscall} failed: {e:?}"),
+    }
+}
+
+/// Answer the host call of the import `syscall`, its arguments in the registers, the way the
+/// host functions linked by [`create_instance`] do, for runs handling the `Ecalli` interrupts
+/// themselves. Imports without a host function are ignored.
+pub(crate) fn host_call(
+    instance: &mut polkavm::Instance<Runtime, ProgramError>,
+    runtime: &mut Runtime,
+    syscall: &str,
+) -> Result<(), ProgramError> {
     match syscall {
         "debug_print" => {
             let ptr_to_type = instance.reg(Reg::A0) as u32;
             let ptr_to_data = instance.reg(Reg::A1) as u32;
-            debug_print(instance, ptr_to_type, ptr_to_data).expect("Failed to print debug info");
+            let printed = debug_print(instance, ptr_to_type, ptr_to_data)?;
+            runtime.print_sink.print(printed);
+        }
+        "log" => {
+            let level = instance.reg(Reg::A0) as u32;
+            let ptr_to_msg = instance.reg(Reg::A1) as u32;
+            let len = instance.reg(Reg::A2);
+            guest_log(instance, level, ptr_to_msg, len)?;
         }
         "hex_dump" => {
             hexdump(instance);
         }
+        "call_data_size" => {
+            instance.set_reg(Reg::A0, runtime.call_data.len() as u64);
+        }
+        "call_data_copy" => {
+            let ptr_to_buf = instance.reg(Reg::A0) as u32;
+            let size = instance.reg(Reg::A1) as u32;
+            let offset = instance.reg(Reg::A2) as u32;
+            call_data_copy(runtime, instance, ptr_to_buf, size, offset)?;
+        }
+        "origin" => {
+            let ptr_to_buf = instance.reg(Reg::A0) as u32;
+            instance.write_memory(ptr_to_buf, ORIGIN_ADDR)?;
+        }
         "move_to" => {
             let ptr_to_signer = instance.reg(Reg::A0) as u32;
             let ptr_to_struct = instance.reg(Reg::A1) as u32;
             let ptr_to_tag = instance.reg(Reg::A2) as u32;
-            move_to(runtime, instance, ptr_to_signer, ptr_to_struct, ptr_to_tag)
-                .expect("Failed to print debug info");
+            move_to(runtime, instance, ptr_to_signer, ptr_to_struct, ptr_to_tag)?;
         }
         "move_from" => {
             let ptr_to_signer = instance.reg(Reg::A0) as u32;
             let remove = instance.reg(Reg::A1) as u32;
             let ptr_to_tag = instance.reg(Reg::A2) as u32;
             let is_mut = instance.reg(Reg::A3) as u32;
-            let result = move_from(runtime, instance, ptr_to_signer, remove, ptr_to_tag, is_mut)
-                .expect("Failed to move from global storage");
+            let result = move_from(runtime, instance, ptr_to_signer, remove, ptr_to_tag, is_mut)?;
             instance.set_reg(Reg::A0, result as u64);
         }
         "exists" => {
             let ptr_to_signer = instance.reg(Reg::A0) as u32;
             let ptr_to_tag = instance.reg(Reg::A1) as u32;
-            let result = exists(runtime, instance, ptr_to_signer, ptr_to_tag)
-                .expect("Failed to check if global exists");
+            let result = exists(runtime, instance, ptr_to_signer, ptr_to_tag)?;
             instance.set_reg(Reg::A0, result as u64);
         }
+        "release" => {
+            let ptr_to_addr = instance.reg(Reg::A0) as u32;
+            let ptr_to_struct = instance.reg(Reg::A1) as u32;
+            let ptr_to_tag = instance.reg(Reg::A2) as u32;
+            release(runtime, instance, ptr_to_addr, ptr_to_struct, ptr_to_tag)?;
+        }
         "hash_sha2_256" => {
             let ptr_to_vec = instance.reg(Reg::A0) as u32;
-            let result =
-                hash_sha2_256(runtime, instance, ptr_to_vec).expect("Failed to calculate hash");
+            let result = hash_sha2_256(runtime, instance, ptr_to_vec)?;
             instance.set_reg(Reg::A0, result as u64);
         }
         "hash_sha3_256" => {
             let ptr_to_vec = instance.reg(Reg::A0) as u32;
-            let result =
-                hash_sha3_256(runtime, instance, ptr_to_vec).expect("Failed calculate hash");
+            let result = hash_sha3_256(runtime, instance, ptr_to_vec)?;
             instance.set_reg(Reg::A0, result as u64);
         }
-        "terminate" => {
-            let code = instance.reg(Reg::A0);
-            guest_abort(instance, code).ok();
+        "coverage_register" => {
+            let counters = instance.reg(Reg::A0) as u32;
+            let len = instance.reg(Reg::A1) as u32;
+            runtime.coverage_counters = Some((counters, len));
+        }
+        "heap_stats_register" => {
+            runtime.heap_stats = Some(instance.reg(Reg::A0) as u32);
+        }
+        "stack_trace" => {
+            let frames = instance.reg(Reg::A0) as u32;
+            let depth = instance.reg(Reg::A1) as u32;
+            runtime.stack_trace = Some(read_stack_trace(instance, frames, depth)?);
+        }
+        "balance_of" => {
+            let ptr_to_addr = instance.reg(Reg::A0) as u32;
+            let ptr_to_out = instance.reg(Reg::A1) as u32;
+            balance_of(runtime, instance, ptr_to_addr, ptr_to_out)?;
+        }
+        "value_transferred" => {
+            let ptr_to_out = instance.reg(Reg::A0) as u32;
+            value_transferred(runtime, instance, ptr_to_out)?;
+        }
+        "now" => {
+            let ptr_to_out = instance.reg(Reg::A0) as u32;
+            now(runtime, instance, ptr_to_out)?;
+        }
+        "random_bytes" => {
+            let ptr_to_out = instance.reg(Reg::A0) as u32;
+            let len = instance.reg(Reg::A1) as u32;
+            random_bytes(runtime, instance, ptr_to_out, len)?;
+        }
+        "return_data_size" => {
+            instance.set_reg(Reg::A0, runtime.return_data.len() as u64);
+        }
+        "return_data_copy" => {
+            let ptr_to_buf = instance.reg(Reg::A0) as u32;
+            let ptr_to_len = instance.reg(Reg::A1) as u32;
+            let offset = instance.reg(Reg::A2) as u32;
+            return_data_copy(runtime, instance, ptr_to_buf, ptr_to_len, offset)?;
+        }
+        "call" => {
+            let flags_and_callee = instance.reg(Reg::A0);
+            let deposit_and_value = instance.reg(Reg::A3);
+            let input_data = instance.reg(Reg::A4);
+            let result = call(
+                runtime,
+                instance,
+                flags_and_callee,
+                deposit_and_value,
+                input_data,
+            )?;
+            instance.set_reg(Reg::A0, result as u64);
+        }
+        "seal_return" => {
+            let flags = instance.reg(Reg::A0) as u32;
+            let data_ptr = instance.reg(Reg::A1) as u32;
+            let data_len = instance.reg(Reg::A2) as u32;
+            seal_return(runtime, instance, flags, data_ptr, data_len)?;
         }
         _ => {}
     }
+    Ok(())
+}
+
+/// Copy `size` bytes of the call data from `offset` to `ptr_to_buf`. Like pallet-revive, the
+/// part of the buffer past the end of the call data is zeroed.
+fn call_data_copy(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    ptr_to_buf: u32,
+    size: u32,
+    offset: u32,
+) -> Result<(), ProgramError> {
+    let mut buf = vec![0u8; size as usize];
+    let available = runtime.call_data.get(offset as usize..).unwrap_or_default();
+    let len = available.len().min(buf.len());
+    buf[..len].copy_from_slice(&available[..len]);
+    instance.write_memory(ptr_to_buf, &buf)?;
+    Ok(())
 }
 
 fn hash_sha2_256(
@@ -628,12 +1551,194 @@ fn hash_sha3_256(
     Result::<u32, ProgramError>::Ok(address)
 }
 
+/// Handle `seal_return`, through which the guest both reverts and returns data, e.g. the
+/// encoded return values of an entry function, which a calling contract reads back with
+/// `return_data_copy`. A revert carries the abort code, followed by [`RUNTIME_FAILURE_TAG`]
+/// for a failure of the runtime itself. Record the output of the program, or abort with the
+/// code of a revert. On pallet-revive, `seal_return` ends the call; the mock host keeps
+/// running the program after a plain return.
+fn seal_return(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    flags: u32,
+    data_ptr: u32,
+    data_len: u32,
+) -> Result<(), ProgramError> {
+    debug!("seal_return called with flags: {flags}, data: 0x{data_ptr:X}, len: {data_len}");
+    let data = copy_bytes_from_guest(instance, data_ptr, data_len as usize)?;
+    if flags & RETURN_FLAG_REVERT == 0 {
+        runtime.output = data;
+        return Ok(());
+    }
+    let malformed = || ProgramError::MemoryAccess(format!("revert data of {} bytes", data.len()));
+    let (code, tag) = data.split_at(data.len().min(8));
+    let code = code
+        .try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| malformed())?;
+    match tag {
+        [] => guest_abort(instance, code),
+        [RUNTIME_FAILURE_TAG] => runtime_failure(instance, code),
+        _ => Err(malformed()),
+    }
+}
+
+/// Read the `depth` frames of the shadow stack a stack trace build dumps before aborting.
+fn read_stack_trace(
+    instance: &mut RawInstance,
+    frames: u32,
+    depth: u32,
+) -> Result<Vec<StackFrame>, ProgramError> {
+    debug!("stack_trace called with frames: 0x{frames:X}, depth: {depth}");
+    let bytes = copy_bytes_from_guest(instance, frames, depth as usize * 8)?;
+    let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+    Ok(bytes
+        .chunks_exact(8)
+        .map(|frame| StackFrame {
+            function: word(&frame[..4]),
+            caller_pc: word(&frame[4..]),
+        })
+        .collect())
+}
+
+/// Mock of the pallet-revive `balance_of` import, writing the balance as `U256` little endian.
+fn balance_of(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    ptr_to_addr: u32,
+    ptr_to_out: u32,
+) -> Result<(), ProgramError> {
+    let address: [u8; REVIVE_ADDRESS_LENGTH] = copy_from_guest(instance, ptr_to_addr)?;
+    let balance = runtime.balances.get(&address).copied().unwrap_or_default();
+    debug!("balance_of 0x{} is {balance}", hex::encode(address));
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    value[..16].copy_from_slice(&balance.to_le_bytes());
+    instance.write_memory(ptr_to_out, &value)?;
+    Ok(())
+}
+
+fn value_transferred(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    ptr_to_out: u32,
+) -> Result<(), ProgramError> {
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    value[..16].copy_from_slice(&runtime.value_transferred.to_le_bytes());
+    instance.write_memory(ptr_to_out, &value)?;
+    Ok(())
+}
+
+/// Mock of the pallet-revive `now` import, [`Runtime::timestamp_ms`] in whole seconds.
+fn now(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    ptr_to_out: u32,
+) -> Result<(), ProgramError> {
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    value[..8].copy_from_slice(&(runtime.timestamp_ms / 1000).to_le_bytes());
+    instance.write_memory(ptr_to_out, &value)?;
+    Ok(())
+}
+
+/// Mock randomness, the next `len` bytes of [`Runtime::random`]. pallet-revive has no such
+/// import, blobs calling it are only built with `--mock-host`.
+fn random_bytes(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    ptr_to_out: u32,
+    len: u32,
+) -> Result<(), ProgramError> {
+    let mut bytes = vec![0u8; len as usize];
+    runtime.random.fill(&mut bytes);
+    instance.write_memory(ptr_to_out, &bytes)?;
+    Ok(())
+}
+
+/// Mock of the pallet-revive `call` import. It only moves the value from the contract to the
+/// callee; calling into other contracts is not supported, so the input data is ignored.
+fn call(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    flags_and_callee: u64,
+    deposit_and_value: u64,
+    input_data: u64,
+) -> Result<u32, ProgramError> {
+    let ptr_to_callee = flags_and_callee as u32;
+    let ptr_to_value = deposit_and_value as u32;
+    let input = copy_bytes_from_guest(instance, input_data as u32, (input_data >> 32) as usize)?;
+    let callee: [u8; REVIVE_ADDRESS_LENGTH] = copy_from_guest(instance, ptr_to_callee)?;
+    let value = if ptr_to_value == REVIVE_SENTINEL {
+        0
+    } else {
+        let value: [u8; REVIVE_VALUE_LENGTH] = copy_from_guest(instance, ptr_to_value)?;
+        let (low, high) = value.split_at(16);
+        if high.iter().any(|byte| *byte != 0) {
+            return Ok(TRANSFER_FAILED);
+        }
+        u128::from_le_bytes(low.try_into().unwrap())
+    };
+    debug!("call 0x{} with value {value}", hex::encode(callee));
+    let available = runtime
+        .balances
+        .get(&CONTRACT_ADDRESS)
+        .copied()
+        .unwrap_or_default();
+    let Some(remaining) = available.checked_sub(value) else {
+        return Ok(TRANSFER_FAILED);
+    };
+    // accounts without a contract just receive the value
+    runtime.return_data.clear();
+    if let Some(contract) = runtime.contracts.get_mut(&callee) {
+        match contract.call(&input, value) {
+            Ok(output) => runtime.return_data = output,
+            Err(output) => {
+                // a revert undoes the transfer
+                runtime.return_data = output;
+                return Ok(CALLEE_REVERTED);
+            }
+        }
+    }
+    runtime.balances.insert(CONTRACT_ADDRESS, remaining);
+    let balance = runtime.balances.entry(callee).or_default();
+    *balance = balance.saturating_add(value);
+    Ok(0)
+}
+
+/// Copy the output of the last call from `offset` into the buffer at `ptr_to_buf`, of the
+/// length at `ptr_to_len`, and write the copied length back there.
+fn return_data_copy(
+    runtime: &mut Runtime,
+    instance: &mut RawInstance,
+    ptr_to_buf: u32,
+    ptr_to_len: u32,
+    offset: u32,
+) -> Result<(), ProgramError> {
+    let capacity: u32 = copy_from_guest(instance, ptr_to_len)?;
+    let available = runtime
+        .return_data
+        .get(offset as usize..)
+        .ok_or_else(|| ProgramError::MemoryAccess(format!("return data offset {offset}")))?;
+    let len = available.len().min(capacity as usize);
+    instance.write_memory(ptr_to_buf, &available[..len])?;
+    instance.write_memory(ptr_to_len, &(len as u32).to_le_bytes())?;
+    Ok(())
+}
+
 fn guest_abort(instance: &mut RawInstance, code: u64) -> Result<(), ProgramError> {
+    hexdump(instance);
+    Result::<(), _>::Err(ProgramError::Abort(code))
+}
+
+/// A failure of the runtime itself, which the guest tells from a Move abort with the same code
+/// by [`RUNTIME_FAILURE_TAG`].
+fn runtime_failure(instance: &mut RawInstance, code: u64) -> Result<(), ProgramError> {
     hexdump(instance);
     let program_error = match code {
         PANIC_CODE => ProgramError::NativeLibPanic,
         ALLOC_CODE => ProgramError::NativeLibAllocatorCall,
-        _ => ProgramError::Abort(code),
+        MISSING_ABILITY_CODE => ProgramError::MissingAbility,
+        INVALID_REFERENCE_CODE => ProgramError::InvalidReference,
+        _ => ProgramError::MemoryAccess(format!("unknown runtime failure {code}")),
     };
     Result::<(), _>::Err(program_error)
 }
@@ -715,52 +1820,79 @@ fn move_to(
     Result::<(), ProgramError>::Ok(())
 }
 
+/// Decode the value the program prints, of the type described at `ptr_to_type`.
 fn debug_print(
     instance: &mut RawInstance,
     ptr_to_type: u32,
     ptr_to_data: u32,
-) -> Result<(), ProgramError> {
-    let mut move_type_string = "Unknown".to_string();
+) -> Result<PrintedValue, ProgramError> {
     let move_type: Result<MoveType, MemoryAccessError> = copy_from_guest(instance, ptr_to_type);
     // for some reason, the type is stored in RO memory, which we can't read when dynamic paging is enabled
-    if let Ok(move_type) = move_type {
-        move_type_string = move_type.to_string();
-        match move_type.type_desc {
-            TypeDesc::Bool | TypeDesc::U8 => {
-                let move_value: u8 = copy_from_guest(instance, ptr_to_data)?;
-                debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: 0x{move_value}");
-            }
-            TypeDesc::U16 | TypeDesc::U32 => {
-                let move_value: u32 = copy_from_guest(instance, ptr_to_data)?;
-                debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: 0x{move_value:x?}");
-            }
-            TypeDesc::Signer => {
-                let move_signer: MoveSigner = copy_from_guest(instance, ptr_to_data)?;
-                debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: {move_signer:?}");
-            }
-            TypeDesc::U64 => {
-                let move_value: u64 = copy_from_guest(instance, ptr_to_data)?;
-                debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: 0x{move_value:x?}");
-            }
-            TypeDesc::Vector => {
-                let vec: MoveByteVector = copy_from_guest(instance, ptr_to_data)?;
-                let len = vec.length as usize;
-                let bytes = copy_bytes_from_guest(instance, vec.ptr as u32, len)?;
-                let s = String::from_utf8(bytes.clone());
-                if let Ok(s) = s {
-                    debug!("debug_print called: {s}");
-                } else {
-                    debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: {vec:?}, bytes: {bytes:x?}");
-                }
-            }
-            _ => {
-                let move_value: u64 = copy_from_guest(instance, ptr_to_data)?;
-                debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: 0x{move_value:x}");
-            }
-        }
-    } else {
+    let Ok(move_type) = move_type else {
         let move_value: u32 = copy_from_guest(instance, ptr_to_data)?;
-        debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type_string:?}, value: {move_value}");
+        debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: Unknown, value: {move_value}");
+        return Ok(PrintedValue {
+            type_name: "Unknown".to_string(),
+            value: move_value.to_string(),
+        });
+    };
+    let value = match move_type.type_desc {
+        TypeDesc::Bool => (copy_from_guest::<u8>(instance, ptr_to_data)? != 0).to_string(),
+        TypeDesc::U8 => copy_from_guest::<u8>(instance, ptr_to_data)?.to_string(),
+        TypeDesc::U16 => copy_from_guest::<u16>(instance, ptr_to_data)?.to_string(),
+        TypeDesc::U32 => copy_from_guest::<u32>(instance, ptr_to_data)?.to_string(),
+        TypeDesc::U64 => copy_from_guest::<u64>(instance, ptr_to_data)?.to_string(),
+        TypeDesc::Signer => {
+            let move_signer: MoveSigner = copy_from_guest(instance, ptr_to_data)?;
+            format!("{:?}", move_signer.0)
+        }
+        TypeDesc::Vector => print_bytes(instance, ptr_to_data)?,
+        // a `String` holds just its bytes, e.g. the argument of Aptos' `native_print`
+        TypeDesc::Struct if is_string(instance, &move_type) => print_bytes(instance, ptr_to_data)?,
+        _ => {
+            let move_value: u64 = copy_from_guest(instance, ptr_to_data)?;
+            format!("0x{move_value:x}")
+        }
+    };
+    debug!("debug_print called. type ptr: 0x{ptr_to_type:X} Data ptr: 0x{ptr_to_data:X}, type: {move_type}, value: {value}");
+    Ok(PrintedValue {
+        type_name: move_type.to_string(),
+        value,
+    })
+}
+
+/// Canonical name of `0x1::string::String`, the name of its type descriptor.
+const STRING_TYPE_NAME: &str =
+    "0000000000000000000000000000000000000000000000000000000000000001::string::String";
+
+fn is_string(instance: &mut RawInstance, move_type: &MoveType) -> bool {
+    let name = move_type.name;
+    copy_bytes_from_guest(instance, name.ptr as u32, name.len as usize)
+        .is_ok_and(|name| name == STRING_TYPE_NAME.as_bytes())
+}
+
+/// The `vector<u8>` at `ptr`, as text if it is UTF-8, in hex otherwise.
+fn print_bytes(instance: &mut RawInstance, ptr: u32) -> Result<String, ProgramError> {
+    let vec: MoveByteVector = copy_from_guest(instance, ptr)?;
+    let bytes = copy_bytes_from_guest(instance, vec.ptr as u32, vec.length as usize)?;
+    Ok(String::from_utf8(bytes).unwrap_or_else(|e| format!("0x{}", hex::encode(e.as_bytes()))))
+}
+
+fn guest_log(
+    instance: &mut RawInstance,
+    level: u32,
+    ptr_to_msg: u32,
+    len: u64,
+) -> Result<(), ProgramError> {
+    let bytes = copy_bytes_from_guest(instance, ptr_to_msg, len as usize)?;
+    let message = String::from_utf8_lossy(&bytes);
+    // tracing needs the level at compile time
+    match level {
+        LOG_LEVEL_ERROR => tracing::error!(target: "guest", "{message}"),
+        LOG_LEVEL_WARN => tracing::warn!(target: "guest", "{message}"),
+        LOG_LEVEL_INFO => tracing::info!(target: "guest", "{message}"),
+        LOG_LEVEL_DEBUG => tracing::debug!(target: "guest", "{message}"),
+        _ => tracing::trace!(target: "guest", "{message}"),
     }
     Result::<(), ProgramError>::Ok(())
 }
@@ -809,10 +1941,12 @@ fn hexdump(instance: &mut RawInstance) {
         .read_memory(stack_base, stack_end - stack_base)
         .unwrap_or_else(|_| vec![]);
     print_mem(stack, stack_base as usize, " STACK ");
-    let heap = instance
-        .read_memory(HEAP_BASE, 256)
+    // the heap (`move_rt_heap`) lives in the RW data
+    let rw_base = instance.module().memory_map().rw_data_address();
+    let rw = instance
+        .read_memory(rw_base, 256)
         .unwrap_or_else(|_| vec![]);
-    print_mem(heap, HEAP_BASE as usize, " HEAP ");
+    print_mem(rw, rw_base as usize, " RW  ");
     let address = instance.module().memory_map().aux_data_address();
     let length = 100;
     let aux = instance
@@ -4,27 +4,487 @@
 
 #![forbid(unsafe_code)]
 
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 
-use clap::Parser;
-use move_to_polka::{initialize_logger, linker::create_blob};
+use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    artifacts::Artifacts,
+    debug_codegen_areas,
+    estimate::estimate,
+    fuzz::DEFAULT_GAS,
+    initialize_logger_with,
+    linker::{
+        build_polka_from_move, create_colored_stdout, elf_path, package_build_options,
+        parse_to_blob, read_constants, relink, BuildOptions, LinkOptions,
+    },
+    metadata::{metadata_path, PackageMetadata},
+    native::{AddressLength, NativeVariant},
+    run_to_polka,
+    signature::ExportSignature,
+    signing::{key_hash, read_verifying_key, signature_path, verify_blob, BlobSignature},
+};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[arg(required_unless_present = "bytecode")]
     // path to Move source to compile
-    pub source: String,
+    pub source: Option<String>,
+    #[arg(long, conflicts_with = "source")]
+    // compile a compiled module (.mv) instead of Move sources
+    pub bytecode: Option<String>,
+    #[arg(long, requires = "bytecode")]
+    // treat the --bytecode file as a script
+    pub script: bool,
+    #[arg(long = "dependency", requires = "bytecode")]
+    // compiled dependencies (.mv) of the --bytecode module, in dependency order
+    pub dependencies: Vec<String>,
     #[arg(short, long, default_value = "output/output.polkavm")]
     // output file name
     pub output: String,
+    #[arg(long)]
+    // directory to write the blob and its artifacts to, under the file name of --output
+    pub out_dir: Option<String>,
+    #[arg(long = "define", value_name = "NAME[=VALUE]")]
+    // compile-time flags, read by the sources as defines::config::NAME()
+    pub defines: Vec<String>,
+    #[arg(long, alias = "no-emit")]
+    // only check the sources for unsupported constructs, do not emit a blob
+    pub check: bool,
+    #[arg(long)]
+    // write the build plan (modules, dependencies, named addresses, artifacts) before codegen
+    pub build_plan: bool,
+    #[arg(long)]
+    // write the build plan and stop before generating code
+    pub plan_only: bool,
+    #[arg(long)]
+    // also export the public functions of the package, without a call selector
+    pub export_public: bool,
+    #[arg(long)]
+    // export only call and deploy, entry functions are still dispatched by the call selector
+    pub strip_exports: bool,
+    #[arg(long = "extra-export", value_delimiter = ',')]
+    // functions (module::function) to export in addition, also with --strip-exports
+    pub extra_exports: Vec<String>,
+    #[arg(long = "inline", value_delimiter = ',')]
+    // inline hints (module::function=<hint|always|never>) overriding #[inline_*] attributes
+    pub inline_overrides: Vec<String>,
+    #[arg(long)]
+    // revert calls transferring value to entry functions without #[payable]
+    pub payable_checks: bool,
+    #[arg(long = "keep-attribute", value_delimiter = ',')]
+    // function attributes to list in the ABI and metadata besides selector, payable, deprecated
+    pub keep_attributes: Vec<String>,
+    #[arg(long)]
+    // compile calls to the debug natives to no-ops
+    pub strip_debug_natives: bool,
+    #[arg(long)]
+    // write package metadata next to the blob
+    pub emit_metadata: bool,
+    #[arg(long)]
+    // sign the blob with the hex ed25519 key in this file, or in $NAME if given as env:NAME
+    pub signing_key: Option<String>,
+    #[arg(long, default_value_t = polkavm_move_native::DEFAULT_HEAP_PAGES)]
+    // number of 4 KiB pages reserved for the guest heap
+    pub heap_pages: u32,
+    #[arg(long)]
+    // minimum guest stack size in bytes
+    pub stack_size: Option<u32>,
+    #[arg(long)]
+    // write a JSON source map next to the blob
+    pub source_map: bool,
+    #[arg(long)]
+    // count basic block executions and write a coverage map next to the blob
+    pub coverage: bool,
+    #[arg(long)]
+    // let the host read the guest allocator's counters after a call
+    pub heap_stats: bool,
+    #[arg(long)]
+    // check references before dereferencing them, aborting on invalid ones
+    pub paranoid: bool,
+    #[arg(long)]
+    // record a shadow stack and hand it to the host on abort, implies -g
+    pub stack_traces: bool,
+    #[arg(long)]
+    // also write the merged ELF object to <output>.elf, for `relink`
+    pub emit_elf: bool,
+    #[arg(long)]
+    // keep the temporary directory of the module objects instead of removing it
+    pub keep_temps: bool,
+    #[arg(long)]
+    // write the entry function ABI next to the blob
+    pub emit_abi: bool,
+    #[arg(long)]
+    // also link a blob per module and write the manifest of their imports
+    pub split_modules: bool,
+    #[arg(long)]
+    // link all Move modules into one LLVM module before optimizing, for cross-module inlining
+    pub lto: bool,
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    // abort on unsigned arithmetic overflow, `--overflow-checks false` to wrap instead
+    pub overflow_checks: bool,
+    #[arg(long)]
+    // omit the arithmetic checks of code proven not to abort on them
+    pub assume_no_abort: bool,
+    #[arg(long, default_value_t = NativeVariant::DEFAULT)]
+    // embedded move-native runtime to link, rv64-<debug|release>
+    pub move_native_variant: NativeVariant,
+    #[arg(long, default_value_t = AddressLength::Bytes32)]
+    // length of Move addresses in bytes, 20 for pallet-revive H160 accounts or 32
+    pub address_length: AddressLength,
+    #[arg(long)]
+    // LLVM target CPU, generic-rv64 by default
+    pub llvm_cpu: Option<String>,
+    #[arg(long)]
+    // LLVM target features, +e,+m,+a,+c by default
+    pub llvm_features: Option<String>,
+    #[arg(long, conflicts_with = "quiet_warnings")]
+    // fail the build when the Move compiler reports warnings
+    pub warn_as_error: bool,
+    #[arg(long)]
+    // do not print the warnings of the Move compiler
+    pub quiet_warnings: bool,
+    #[arg(long)]
+    // print the time spent in every compiler phase and the peak RSS
+    pub time_passes: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(debug_codegen_areas())
+    )]
+    // debug-log these code generator areas: functions, structs, dwarf, rtcall, types or all
+    pub debug_codegen: Vec<String>,
+    #[arg(long)]
+    // write the log to this file instead of stderr
+    pub log_file: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Inspect a compiled .polkavm blob
+    Inspect {
+        // path to the .polkavm blob
+        blob: String,
+        #[arg(long)]
+        // print the package metadata written next to the blob by --emit-metadata
+        metadata: bool,
+        #[arg(long, conflicts_with = "metadata")]
+        // print the address and vector constants, from the object of an --emit-elf build
+        constants: bool,
+    },
+    /// Verify the signature of a blob built with --signing-key
+    Verify {
+        // path to the .polkavm blob
+        blob: String,
+        #[arg(long)]
+        // path to the signature, <blob>.sig.json by default
+        signature: Option<String>,
+        #[arg(long = "trusted-key", required = true)]
+        // hex ed25519 public keys (files, or env:NAME), one of which must have signed the blob
+        trusted_keys: Vec<String>,
+    },
+    /// Link an ELF object written by --emit-elf again, without re-running codegen
+    Relink {
+        // path to the .elf object
+        elf: String,
+        #[arg(short, long, default_value = "output/output.polkavm")]
+        // output file name
+        output: String,
+        #[arg(long)]
+        // strip symbols and debug info
+        strip: bool,
+        #[arg(long)]
+        // let the polka linker optimize the program
+        optimize: bool,
+        #[arg(long, value_delimiter = ',')]
+        // exports to put first in the export table, in this order
+        dispatch_table: Vec<String>,
+    },
+    /// Compile a package, then call an entry function with gas metering and report the blob
+    /// size and the gas the call used
+    Estimate {
+        // path to Move source to compile
+        source: String,
+        #[arg(short, long)]
+        // entry function, as `module::function`
+        function: String,
+        #[arg(long, default_value = "[]")]
+        // arguments as a JSON array, e.g. '["0x1", 42]', without the signer
+        args: String,
+        #[arg(long, default_value_t = DEFAULT_GAS)]
+        // gas limit of the call
+        gas: i64,
+        #[arg(short, long, default_value = "output/output.polkavm")]
+        // output file name
+        output: String,
+        #[arg(long)]
+        // print the estimate as JSON
+        json: bool,
+    },
+    /// Remove the blob and every artifact a build of it wrote
+    Clean {
+        #[arg(short, long, default_value = "output/output.polkavm")]
+        // output file name of the build
+        output: String,
+        #[arg(long)]
+        // --out-dir of the build
+        out_dir: Option<String>,
+    },
+    /// Check the toolchain (LLD, LLVM 18 to 20, nightly rustc with rust-src, target spec)
+    Doctor {
+        #[arg(long)]
+        // polkavm-move-native crate directory, to check what rebuilding the runtime needs
+        native_crate: Option<String>,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
-    initialize_logger();
     let options = Args::parse();
-    let source = options.source.as_str();
-    let output = options.output.as_str();
+    initialize_logger_with(
+        &options.debug_codegen,
+        options.log_file.as_deref().map(Path::new),
+    )?;
+
+    match &options.command {
+        Some(Command::Inspect {
+            blob,
+            metadata,
+            constants,
+        }) => return inspect(blob, *metadata, *constants),
+        Some(Command::Verify {
+            blob,
+            signature,
+            trusted_keys,
+        }) => return verify(blob, signature.as_deref(), trusted_keys),
+        Some(Command::Relink {
+            elf,
+            output,
+            strip,
+            optimize,
+            dispatch_table,
+        }) => {
+            let link_options = LinkOptions {
+                strip: *strip,
+                optimize: *optimize,
+                dispatch_table: dispatch_table.clone(),
+            };
+            parse_to_blob(&relink(Path::new(elf), Path::new(output), &link_options)?)?;
+            return Ok(());
+        }
+        Some(Command::Estimate {
+            source,
+            function,
+            args,
+            gas,
+            output,
+            json,
+        }) => {
+            let args: Vec<serde_json::Value> = serde_json::from_str(args)?;
+            return estimate_call(source, output, function, &args, *gas, *json);
+        }
+        Some(Command::Clean { output, out_dir }) => {
+            for path in Artifacts::new(output_path(output, out_dir.as_deref())).clean()? {
+                println!("removed {}", path.display());
+            }
+            return Ok(());
+        }
+        Some(Command::Doctor { native_crate }) => return doctor(native_crate.as_deref()),
+        None => {}
+    }
+
+    let output = output_path(&options.output, options.out_dir.as_deref());
+    let output = output.to_str().expect("output path is not UTF-8");
+
+    if let Some(bytecode) = &options.bytecode {
+        let mut build_options = BuildOptions::new(output)
+            .bytecode(bytecode, options.script)
+            .check(options.check);
+        for dependency in &options.dependencies {
+            build_options = build_options.dependency(dependency);
+        }
+        let build_options = apply_codegen_options(build_options, &options);
+        if options.check || options.plan_only {
+            run_to_polka(&mut create_colored_stdout(), build_options.build())?;
+        } else {
+            parse_to_blob(&build_polka_from_move(build_options)?)?;
+        }
+        return Ok(());
+    }
+
+    let source = options.source.as_deref().expect("source is required");
+    let build_options = package_build_options(output, source, HashSet::new())?;
+    let build_options = apply_codegen_options(build_options, &options).check(options.check);
+    if options.check || options.plan_only {
+        run_to_polka(&mut create_colored_stdout(), build_options.build())?;
+    } else {
+        parse_to_blob(&build_polka_from_move(build_options)?)?;
+    }
+    Ok(())
+}
+
+/// `output`, moved into `out_dir` if given.
+fn output_path(output: &str, out_dir: Option<&str>) -> PathBuf {
+    match out_dir {
+        Some(dir) => Path::new(dir).join(Path::new(output).file_name().unwrap_or_default()),
+        None => PathBuf::from(output),
+    }
+}
+
+fn apply_codegen_options(mut build_options: BuildOptions, options: &Args) -> BuildOptions {
+    if let Some(cpu) = &options.llvm_cpu {
+        build_options = build_options.llvm_cpu(cpu);
+    }
+    if let Some(features) = &options.llvm_features {
+        build_options = build_options.llvm_features(features);
+    }
+    for define in &options.defines {
+        build_options = build_options.define(define);
+    }
+    build_options
+        .export_public(options.export_public)
+        .strip_exports(options.strip_exports)
+        .extra_exports(options.extra_exports.clone())
+        .inline_overrides(options.inline_overrides.clone())
+        .payable_checks(options.payable_checks)
+        .keep_attributes(options.keep_attributes.clone())
+        .strip_debug_natives(options.strip_debug_natives)
+        .emit_metadata(options.emit_metadata)
+        .signing_key(options.signing_key.clone())
+        .overflow_checks(options.overflow_checks)
+        .assume_no_abort(options.assume_no_abort)
+        .source_map(options.source_map)
+        .coverage(options.coverage)
+        .heap_stats(options.heap_stats)
+        .paranoid(options.paranoid)
+        .stack_traces(options.stack_traces)
+        .heap_pages(options.heap_pages)
+        .stack_size(options.stack_size)
+        .emit_elf(options.emit_elf)
+        .keep_temps(options.keep_temps)
+        .emit_abi(options.emit_abi)
+        .split_modules(options.split_modules)
+        .build_plan(options.build_plan)
+        .plan_only(options.plan_only)
+        .lto(options.lto)
+        .move_native_variant(options.move_native_variant)
+        .address_length(options.address_length)
+        .warn_as_error(options.warn_as_error)
+        .quiet_warnings(options.quiet_warnings)
+        .time_passes(options.time_passes)
+}
+
+fn doctor(native_crate: Option<&str>) -> anyhow::Result<()> {
+    // the checkout this binary was built from, unless it has moved since
+    let native_crate = native_crate.map(PathBuf::from).or_else(|| {
+        let checkout = Path::new(env!("CARGO_MANIFEST_DIR")).join("../polkavm-move-native");
+        checkout.is_dir().then_some(checkout)
+    });
+    let checks = build_tools::doctor::run(native_crate.as_deref());
+    for check in &checks {
+        println!("{check}");
+    }
+    if native_crate.is_none() {
+        println!("runtime build checks skipped, pass --native-crate to run them");
+    }
+    let failed = checks.iter().filter(|check| !check.ok).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} toolchain check(s) failed");
+    }
+    Ok(())
+}
+
+fn estimate_call(
+    source: &str,
+    output: &str,
+    function: &str,
+    args: &[serde_json::Value],
+    gas: i64,
+    json: bool,
+) -> anyhow::Result<()> {
+    let build_options = package_build_options(output, source, HashSet::new())?.emit_abi(true);
+    let bytes = build_polka_from_move(build_options)?;
+    let abi = Abi::read(&abi_path(Path::new(output)))?;
+    let estimate = estimate(&bytes, &abi, function, args, gas)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&estimate)?);
+        return Ok(());
+    }
+    let size = &estimate.size;
+    println!("blob size: {} bytes", size.blob);
+    println!("  code: {} bytes", size.code);
+    println!("  ro data: {} bytes", size.ro_data);
+    println!(
+        "  rw data: {} bytes ({} reserved)",
+        size.rw_data, size.rw_data_size
+    );
+    println!("  stack: {} bytes", size.stack_size);
+    println!("  exports: {}", size.exports);
+    println!("{function}: {:?}", estimate.outcome);
+    println!("gas used: {} of {}", estimate.gas_used, estimate.gas_limit);
+    Ok(())
+}
+
+fn inspect(path: &str, metadata: bool, constants: bool) -> anyhow::Result<()> {
+    if constants {
+        let elf_file = elf_path(Path::new(path));
+        let elf = std::fs::read(&elf_file).map_err(|e| {
+            anyhow::anyhow!(
+                "can't read {}, build the blob with --emit-elf: {e}",
+                elf_file.display()
+            )
+        })?;
+        for constant in read_constants(&elf)? {
+            println!("{constant}");
+        }
+        return Ok(());
+    }
+    let package_metadata = PackageMetadata::of_blob(Path::new(path))?;
+    if metadata {
+        match package_metadata {
+            Some(metadata) => println!("{}", serde_json::to_string_pretty(&metadata)?),
+            None => anyhow::bail!("{path} has no metadata, build it with --emit-metadata"),
+        }
+    } else {
+        let blob = parse_to_blob(&std::fs::read(path)?)?;
+        let signatures = package_metadata
+            .map(|metadata| metadata.export_signatures)
+            .unwrap_or_default();
+        for export in blob.exports() {
+            let name = export.symbol().to_string();
+            match signatures.get(&name) {
+                Some(signature) => println!("{name}{}", ExportSignature::parse(signature)?),
+                None => println!("{name}"),
+            }
+        }
+    }
+    Ok(())
+}
 
-    create_blob(output, source, HashSet::new())?;
+fn verify(path: &str, signature: Option<&str>, trusted_keys: &[String]) -> anyhow::Result<()> {
+    let signature_file = signature
+        .map(PathBuf::from)
+        .unwrap_or_else(|| signature_path(Path::new(path)));
+    let trusted_keys = trusted_keys
+        .iter()
+        .map(|source| read_verifying_key(source))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = verify_blob(
+        &std::fs::read(path)?,
+        &std::fs::read(metadata_path(Path::new(path)))?,
+        &BlobSignature::read(&signature_file)?,
+        &trusted_keys,
+    )?;
+    println!(
+        "{path}: signed by {} (key hash {})",
+        hex::encode(key.as_bytes()),
+        key_hash(&key)
+    );
     Ok(())
 }
@@ -0,0 +1,154 @@
+//! Package metadata of produced `.polkavm` blobs.
+//!
+//! With `--emit-metadata`, the metadata is written next to the blob as `<name>.metadata.json`
+//! (see [`metadata_path`]). It is kept out of the blob itself, so the deployed bytes stay a
+//! valid PolkaVM program for pallet-revive.
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    options::Options,
+};
+use move_model::model::GlobalEnv;
+use move_polka_codegen::stackless::function_attributes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use tiny_keccak::{Hasher, Keccak};
+
+/// File name suffix of the metadata written next to a blob.
+pub const METADATA_EXTENSION: &str = "metadata.json";
+
+/// Encoding of entry function arguments and storage values.
+pub const CODEC: &str = "bcs";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    pub package_name: Option<String>,
+    pub package_version: Option<String>,
+    pub compiler_version: String,
+    pub named_addresses: BTreeMap<String, String>,
+    pub codec: String,
+    /// Hex encoded keccak256 hash of the selector table, see [`hash_selector_table`].
+    pub selector_table_hash: String,
+    /// Type signature of every export, by export name, see [`crate::signature`].
+    #[serde(default)]
+    pub export_signatures: BTreeMap<String, String>,
+    /// The `module::function` of every export `call_selector` also dispatches, by export
+    /// name, see [`crate::linker::exports`].
+    #[serde(default)]
+    pub entry_functions: BTreeMap<String, String>,
+    /// Attributes passed on by the compiler, by `module::function`, for the functions that
+    /// have any, see [`move_polka_codegen::stackless::function_attributes`].
+    #[serde(default)]
+    pub function_attributes: BTreeMap<String, BTreeMap<String, Option<String>>>,
+    /// Hex encoded keccak256 hash of the public key the blob is signed with, see
+    /// [`crate::signing`].
+    #[serde(default)]
+    pub signer_key_hash: Option<String>,
+    /// The `--define` flags the blob is built with, the values as Move expressions.
+    #[serde(default)]
+    pub defines: BTreeMap<String, String>,
+    /// Built with `--assume-no-abort`, without the dynamic checks of arithmetic.
+    #[serde(default)]
+    pub assume_no_abort: bool,
+}
+
+impl PackageMetadata {
+    /// The metadata of the blob built from `global_env` with `options`, whose `call_selector`
+    /// dispatches the `selector_table` the code generator recorded in its `ExportRegistry`.
+    pub fn new(
+        global_env: &GlobalEnv,
+        options: &Options,
+        selector_table: &BTreeMap<u32, String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            package_name: options.package_name.clone(),
+            package_version: options.package_version.clone(),
+            compiler_version: env!("CARGO_PKG_VERSION").to_string(),
+            named_addresses: options.named_addresses(),
+            codec: CODEC.to_string(),
+            selector_table_hash: hex::encode(hash_selector_table(selector_table)),
+            export_signatures: BTreeMap::new(),
+            entry_functions: BTreeMap::new(),
+            function_attributes: attribute_table(global_env, options),
+            signer_key_hash: None,
+            defines: options
+                .parsed_defines()?
+                .into_iter()
+                .map(|define| (define.name, define.value))
+                .collect(),
+            assume_no_abort: options.assume_no_abort,
+        })
+    }
+
+    /// The metadata in `json`, the contents of a metadata file.
+    pub fn from_json(json: &[u8]) -> Result<Self> {
+        serde_json::from_slice(json)
+            .map_err(|e| MoveToPolkaError::invalid_artifact(format!("malformed metadata: {e}")))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::from_json(&std::fs::read(path)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// The metadata written next to the blob at `blob_path`, if it was built with
+    /// `--emit-metadata`.
+    pub fn of_blob(blob_path: &Path) -> Result<Option<Self>> {
+        let path = metadata_path(blob_path);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::read(&path).map(Some)
+    }
+}
+
+/// The attributes of every function of `global_env` that has attributes passed on.
+pub fn attribute_table(
+    global_env: &GlobalEnv,
+    options: &Options,
+) -> BTreeMap<String, BTreeMap<String, Option<String>>> {
+    let codegen_options = options.codegen_options();
+    global_env
+        .get_modules()
+        .flat_map(|m| m.into_functions())
+        .filter_map(|fn_env| {
+            let attributes = function_attributes(&fn_env, &codegen_options);
+            (!attributes.is_empty()).then(|| {
+                let attributes = attributes
+                    .into_iter()
+                    .map(|attr| (attr.name, attr.value))
+                    .collect();
+                (fn_env.get_full_name_str(), attributes)
+            })
+        })
+        .collect()
+}
+
+/// Hash the `module::function` names of `table`, by selector as loaded from call data, one
+/// `selector:module::function` line each with the selector in call data byte order.
+pub fn hash_selector_table(table: &BTreeMap<u32, String>) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    for (selector, name) in table {
+        let entry = format!(
+            "{:08x}:{name}\n",
+            u32::from_be_bytes(selector.to_le_bytes())
+        );
+        keccak.update(entry.as_bytes());
+    }
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// The path of the metadata of the blob at `blob_path`.
+pub fn metadata_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(METADATA_EXTENSION)
+}
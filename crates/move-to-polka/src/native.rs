@@ -1,6 +1,114 @@
-// check build.rs how native lib is actually being built
-const MOVE_NATIVE_LIB_BYTES: &[u8] = include_bytes!(env!("MOVE_NATIVE_OBJECT_FILE"));
+use crate::error::{MoveToPolkaError, Result};
+use sha2::Digest;
+use std::path::{Path, PathBuf};
+use tracing::debug;
 
-pub fn move_native_lib_content() -> &'static [u8] {
-    MOVE_NATIVE_LIB_BYTES
+pub use build_tools::{AddressLength, NativeArch, NativeProfile, NativeVariant};
+
+// check build.rs how native libs are actually being built, or where the prebuilt one comes from
+include!(concat!(env!("OUT_DIR"), "/move_native_variants.rs"));
+
+/// Environment variable naming the runtime object to use when `--move-native-archive` is not given.
+pub const MOVE_NATIVE_ARCHIVE_ENV: &str = "MOVE_NATIVE_ARCHIVE";
+
+/// Environment variable overriding the directory extracted runtime objects are cached in.
+pub const MOVE_NATIVE_CACHE_ENV: &str = "MOVE_TO_POLKA_CACHE_DIR";
+
+/// File name of the runtime object, also looked up in `--move-native-archive` directories.
+pub const MOVE_NATIVE_OBJECT_NAME: &str = "move_native.o";
+
+/// Runtime variants embedded in this build, see `MOVE_NATIVE_VARIANTS` in build.rs.
+pub fn embedded_variants() -> Vec<NativeVariant> {
+    MOVE_NATIVE_LIBS
+        .iter()
+        .filter_map(|(name, _)| name.parse().ok())
+        .collect()
+}
+
+pub fn move_native_lib_content(variant: NativeVariant) -> Result<&'static [u8]> {
+    let name = variant.to_string();
+    MOVE_NATIVE_LIBS
+        .iter()
+        .find(|(embedded, _)| *embedded == name)
+        .map(|(_, bytes)| *bytes)
+        .ok_or_else(|| {
+            MoveToPolkaError::toolchain(format!(
+                "the {name} move-native runtime is not embedded in this build, \
+                 rebuild with MOVE_NATIVE_VARIANTS={name}"
+            ))
+        })
+}
+
+/// Hex encoded SHA-256 of the embedded runtime object.
+pub fn move_native_lib_sha256(variant: NativeVariant) -> Result<String> {
+    Ok(hex::encode(sha2::Sha256::digest(move_native_lib_content(
+        variant,
+    )?)))
+}
+
+/// Find the move-native runtime object to link against, in order:
+/// 1. `move_native_archive` (`--move-native-archive`), a file or a directory containing
+///    `move_native.o`,
+/// 2. the `MOVE_NATIVE_ARCHIVE` environment variable, likewise,
+/// 3. the embedded runtime `variant`, extracted once into the cache directory.
+pub fn resolve_move_native(
+    move_native_archive: Option<&str>,
+    variant: NativeVariant,
+) -> Result<PathBuf> {
+    let explicit = move_native_archive
+        .map(str::to_string)
+        .or_else(|| std::env::var(MOVE_NATIVE_ARCHIVE_ENV).ok());
+    if let Some(path) = explicit {
+        let mut path = PathBuf::from(path);
+        if path.is_dir() {
+            path = path.join(MOVE_NATIVE_OBJECT_NAME);
+        }
+        if !path.is_file() {
+            return Err(MoveToPolkaError::toolchain(format!(
+                "move-native runtime not found at {}",
+                path.display()
+            )));
+        }
+        return Ok(path);
+    }
+
+    cached_move_native(variant)
+}
+
+/// Directory extracted runtime objects are cached in: `$MOVE_TO_POLKA_CACHE_DIR`, or
+/// `move-to-polka` in the user cache directory.
+pub fn move_native_cache_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var(MOVE_NATIVE_CACHE_ENV) {
+        return PathBuf::from(dir);
+    }
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    cache_home.join("move-to-polka")
+}
+
+/// Path of the extracted runtime `variant` in the cache directory.
+fn cached_move_native(variant: NativeVariant) -> Result<PathBuf> {
+    extract_move_native(&move_native_cache_dir(), variant)
+}
+
+/// Extract the embedded runtime `variant` into `dir` and return its path. The file name
+/// contains the object hash, so an existing file is reused and runs never see a stale runtime.
+pub fn extract_move_native(dir: &Path, variant: NativeVariant) -> Result<PathBuf> {
+    let content = move_native_lib_content(variant)?;
+    let sha256 = move_native_lib_sha256(variant)?;
+    let move_native = dir.join(format!("move_native-{variant}-{sha256}.o"));
+    if move_native.is_file() {
+        debug!("Using cached native lib {}", move_native.display());
+        return Ok(move_native);
+    }
+
+    std::fs::create_dir_all(dir)?;
+    // write to a temporary file first, so concurrent builds never link a partial object
+    let partial = move_native.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&partial, content)?;
+    std::fs::rename(&partial, &move_native)?;
+    debug!("Embedded native lib extracted to {}", move_native.display());
+    Ok(move_native)
 }
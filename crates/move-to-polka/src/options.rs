@@ -2,12 +2,26 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{
+    cfg::{CfgOutput, DEFAULT_DOT_VIEWER},
+    defines::Define,
+    error::{MoveToPolkaError, Result},
+    linker::resolve_packages,
+    native::{AddressLength, NativeVariant},
+};
 use clap::Parser;
+use move_core_types::account_address::AccountAddress;
+use move_polka_codegen::{options::CodegenOptions, stackless::TargetPlatform};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::Path,
+};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct Options {
-    /// Directories where to lookup dependencies.
+    /// Directories where to lookup dependencies, or glob patterns. With `--bytecode`, the
+    /// `.mv` files of the modules the bytecode depends on, in dependency order.
     #[clap(short)]
     pub dependencies: Vec<String>,
 
@@ -15,12 +29,18 @@ pub struct Options {
     #[clap(short)]
     pub named_address_mapping: Vec<String>,
 
+    /// Compile-time flags, `NAME` or `NAME=VALUE`, readable by the sources as
+    /// `defines::config::NAME()`. See [`crate::defines`].
+    #[clap(long = "define", value_name = "NAME[=VALUE]")]
+    pub defines: Vec<String>,
+
     /// Path to output file or if option `-c` is set to output directory.
     #[clap(short)]
     #[clap(long, default_value = "output.polkavm")]
     pub output: String,
 
-    /// Sources to compile (positional arg)
+    /// Sources to compile (positional arg): files, directories or glob patterns such as
+    /// `sources/**/*.move`. Several package roots may be given.
     pub sources: Vec<String>,
 
     /// Skip printing of private functions.
@@ -43,7 +63,8 @@ pub struct Options {
     #[clap(short = 's', long = "script")]
     pub is_script: bool,
 
-    /// The path to the move bytecode file to compile.
+    /// The path to the move bytecode file to compile. A `.mvsm` source map next to it
+    /// is picked up for locations and debug info.
     #[clap(short = 'b', long = "bytecode")]
     pub bytecode_file_path: Option<String>,
 
@@ -55,10 +76,26 @@ pub struct Options {
     #[clap(short = 'g')]
     pub debug: bool,
 
-    /// Location of precompiled move native library.
+    /// Location of a precompiled move-native runtime object, or a directory containing
+    /// `move_native.o`. Defaults to `$MOVE_NATIVE_ARCHIVE`, then to the embedded runtime.
     #[clap(long = "move-native-archive")]
     pub move_native_archive: Option<String>,
 
+    /// Embedded move-native runtime to link, as `rv64-<debug|release>`. Variants other than
+    /// `rv64-release` are only embedded when listed in `MOVE_NATIVE_VARIANTS` at build time.
+    #[clap(long = "move-native-variant", default_value_t = NativeVariant::DEFAULT)]
+    pub move_native_variant: NativeVariant,
+
+    /// Length of Move addresses in bytes: 32 like the Move VM, or 20 like the H160 accounts
+    /// of pallet-revive. Selects the `-addr20` variant of the move-native runtime.
+    #[clap(long = "address-length", default_value_t = AddressLength::Bytes32)]
+    pub address_length: AddressLength,
+
+    /// Lower u256 arithmetic, comparisons and casts to calls into helpers of the move-native
+    /// runtime instead of 256-bit LLVM operations.
+    #[clap(long = "u256-helpers")]
+    pub u256_helpers: bool,
+
     /// Output file extension. This is used with -c option.
     /// Each created in compilation module `mod` will be placed into file `mod.o`
     /// by default, or extension may be changed by this option.
@@ -69,6 +106,16 @@ pub struct Options {
     #[clap(long = "opt", default_value = "none")]
     pub opt_level: String,
 
+    /// LLVM target CPU to generate code for. Defaults to `generic-rv64`.
+    #[clap(long = "llvm-cpu")]
+    pub llvm_cpu: Option<String>,
+
+    /// LLVM target features, as a comma separated list such as `+e,+m,+a,-c`. Defaults to
+    /// `+e,+m,+a,+c`. PolkaVM requires the `e` extension; disabling `c` trades blob size
+    /// for simpler instruction decoding.
+    #[clap(long = "llvm-features")]
+    pub llvm_features: Option<String>,
+
     /// Output llvm bitcode in a human readable text format.
     #[clap(short = 'S')]
     pub llvm_ir: bool,
@@ -77,18 +124,361 @@ pub struct Options {
     #[clap(long = "signers", use_value_delimiter = true, value_delimiter = ',')]
     pub test_signers: Vec<String>,
 
-    /// Write or view GraphViz dot graph files for each CFG.
-    /// ("write": gen dot files, "view": gen dot files and invoke xdot viewer)"
-    #[clap(long = "gen-dot-cfg", default_value = "")]
-    pub gen_dot_cfg: String,
+    /// Write the control flow graph of each function: `write` GraphViz dot files, `view`
+    /// dot files opened in the `--dot-viewer`, `json` JSON files for analysis tools.
+    #[clap(long = "gen-dot-cfg", value_enum)]
+    pub gen_dot_cfg: Option<CfgOutput>,
+
+    /// Command opening a dot file for `--gen-dot-cfg view`, with the path appended. It runs in
+    /// the background; if it can't be started, the build only warns.
+    #[clap(long = "dot-viewer", default_value = DEFAULT_DOT_VIEWER)]
+    pub dot_viewer: String,
 
-    /// Path to GraphViz output files (defaults to current working directory).
+    /// Directory of the control flow graph files (defaults to current working directory).
     #[clap(long = "dot-out-dir", default_value = "")]
     pub dot_file_path: String,
 
+    /// Write the control flow graph of each function into a directory of its own under
+    /// `--dot-out-dir`.
+    #[clap(long = "cfg-dir-per-function")]
+    pub cfg_dir_per_function: bool,
+
+    /// Override the selector of an entry function, as `module::function=0x12345678`.
+    /// The selector is given in call data byte order, like a Solidity function selector.
+    #[clap(long = "selector", value_delimiter = ',')]
+    pub selector_overrides: Vec<String>,
+
+    /// Inline hint of a function, as `module::function=<hint|always|never>`, overriding its
+    /// `#[inline_hint]`, `#[inline_always]` or `#[inline_never]` attribute.
+    #[clap(long = "inline", value_delimiter = ',')]
+    pub inline_overrides: Vec<String>,
+
+    /// Revert calls that transfer value to entry functions not marked `#[payable]`, with
+    /// abort code `NOT_PAYABLE_CODE`.
+    #[clap(long = "payable-checks")]
+    pub payable_checks: bool,
+
+    /// Function attributes to list in the ABI and metadata besides `selector`, `payable` and
+    /// `deprecated`, e.g. `--keep-attribute view,audited`.
+    #[clap(long = "keep-attribute", value_delimiter = ',')]
+    pub keep_attributes: Vec<String>,
+
+    /// Skip code generation for functions not reachable from entry functions,
+    /// the unit test function or `--root` functions.
+    #[clap(long = "prune-unreachable")]
+    pub prune_unreachable: bool,
+
+    /// Additional functions (`module::function`) to treat as reachable when pruning.
+    #[clap(long = "root", value_delimiter = ',')]
+    pub reachability_roots: Vec<String>,
+
+    /// Only check the sources: build the model and stackless bytecode and report
+    /// unsupported constructs, without invoking LLVM or the linker.
+    #[clap(long = "check", alias = "no-emit")]
+    pub check: bool,
+
+    /// Write the modules to compile, their dependencies and objects, the named addresses and
+    /// the artifacts of the build as JSON (`<output>.build-plan.json`) before generating code,
+    /// for external build systems.
+    #[clap(long = "build-plan")]
+    pub build_plan: bool,
+
+    /// Write the build plan, which this implies, and stop: no code is generated.
+    #[clap(long = "plan-only")]
+    pub plan_only: bool,
+
+    /// Fail the build when the Move compiler reports warnings.
+    #[clap(long = "warn-as-error", conflicts_with = "quiet_warnings")]
+    pub warn_as_error: bool,
+
+    /// Do not print the warnings of the Move compiler. Errors are still printed.
+    #[clap(long = "quiet-warnings")]
+    pub quiet_warnings: bool,
+
+    /// Print the wall time of every compiler phase and the peak RSS to stderr: model
+    /// building, stackless generation, LLVM translation of each module, object emission,
+    /// lld merge and polka linking.
+    #[clap(long = "time-passes")]
+    pub time_passes: bool,
+
+    /// Also export the non-generic `public` functions of the package's modules, without a
+    /// call selector. Entry functions are exported unless `--strip-exports`; friend and private
+    /// functions only with `--extra-export`.
+    #[clap(long = "export-public")]
+    pub export_public: bool,
+
+    /// Export only the runtime's `call` and `deploy`, and the `--extra-export` functions.
+    /// Entry functions are still dispatched by the call selector.
+    #[clap(long = "strip-exports")]
+    pub strip_exports: bool,
+
+    /// Functions (`module::function`) to export from the blob in addition, also with
+    /// `--strip-exports`. Generic functions can't be exported.
+    #[clap(long = "extra-export", value_delimiter = ',')]
+    pub extra_exports: Vec<String>,
+
+    /// Compile calls to the `debug` natives (`print`, `native_print`, `print_stack_trace`,
+    /// `hex_dump`, `log` and `assert`) to no-ops, so release builds do not pay for debugging aids.
+    #[clap(long = "strip-debug-natives")]
+    pub strip_debug_natives: bool,
+
+    /// Abort with ARITHMETIC_ERROR when unsigned `+`, `-` or `*` overflows.
+    /// Pass `--overflow-checks false` to emit wrapping arithmetic instead.
+    #[clap(long = "overflow-checks", default_value_t = true, action = clap::ArgAction::Set)]
+    pub overflow_checks: bool,
+
+    /// Omit the dynamic checks of overflow, division by zero, shift counts and narrowing casts,
+    /// for code proven never to fail them, e.g. with the Move Prover. Recorded in the metadata.
+    #[clap(long = "assume-no-abort")]
+    pub assume_no_abort: bool,
+
+    /// Write a JSON source map, mapping program counters to Move source lines, next to the
+    /// output blob (`<output>.sourcemap.json`). Implies `-g`.
+    #[clap(long = "source-map")]
+    pub source_map: bool,
+
+    /// Count how often every basic block runs and write a coverage map
+    /// (`<output>.coverage.json`) next to the blob. Coverage builds import
+    /// `coverage_register`, which only the test host provides.
+    #[clap(long = "coverage")]
+    pub coverage: bool,
+
+    /// Let the host read the guest allocator's counters (live and peak heap usage,
+    /// allocations, frees and reallocations) after a call, see `linker::read_heap_stats`.
+    /// These builds import `heap_stats_register`, which only the test host provides.
+    #[clap(long = "heap-stats")]
+    pub heap_stats: bool,
+
+    /// Check every reference before it is read, written or borrowed from: it must not be null
+    /// and point into the data, the used heap or the live stack. Invalid references abort
+    /// with `INVALID_REFERENCE_CODE`, to chase miscompilations and memory corruption.
+    #[clap(long = "paranoid")]
+    pub paranoid: bool,
+
+    /// Keep a shadow stack of the active Move functions and hand it to the host when the
+    /// program aborts, along with a stack trace map (`<output>.stacktrace.json`) naming the
+    /// functions. These builds import `stack_trace`, which only the test host provides.
+    /// Implies `-g`.
+    #[clap(long = "stack-traces")]
+    pub stack_traces: bool,
+
+    /// Number of 4 KiB pages reserved for the guest heap.
+    #[clap(long = "heap-pages", default_value_t = polkavm_move_native::DEFAULT_HEAP_PAGES)]
+    pub heap_pages: u32,
+
+    /// Minimum guest stack size in bytes. The polka linker default is used when unset.
+    #[clap(long = "stack-size")]
+    pub stack_size: Option<u32>,
+
+    /// Also write the merged ELF object the blob is linked from to `<output>.elf`,
+    /// so the polka linker step can be repeated with `move-to-polka relink`.
+    #[clap(long = "emit-elf")]
+    pub emit_elf: bool,
+
+    /// Keep the per-build temporary directory holding the module objects and the merged ELF,
+    /// instead of removing it when the build ends. Its path is logged and returned in
+    /// `BuildOutput::temp_dir`.
+    #[clap(long = "keep-temps")]
+    pub keep_temps: bool,
+
+    /// Link the LLVM modules of all Move modules into one before optimizing, so calls across
+    /// modules can be inlined, and emit a single object (`-c`/`-S` write the linked module).
+    #[clap(long = "lto")]
+    pub lto: bool,
+
+    /// Write the entry functions with their selectors and signatures as JSON
+    /// (`<output>.abi.json`) next to the blob, for encoding call data.
+    #[clap(long = "emit-abi")]
+    pub emit_abi: bool,
+
+    /// Also link a blob per module of the package (`<output>.<address>_<module>.polkavm`),
+    /// exporting and dispatching only that module's entry functions, and write the manifest of
+    /// the functions each blob imports from other modules (`<output>.modules.json`).
+    #[clap(long = "split-modules")]
+    pub split_modules: bool,
+
+    /// Write package metadata (name, version, compiler version, named addresses,
+    /// codec and selector table hash) next to the produced blob, to `<output>.metadata.json`.
+    #[clap(long = "emit-metadata")]
+    pub emit_metadata: bool,
+
+    /// Sign the blob with the ed25519 key in this file, or in the environment variable
+    /// `NAME` if given as `env:NAME` (hex encoded). The hash of the public key is recorded
+    /// in the metadata, which this implies, and the signature written to `<output>.sig.json`.
+    #[clap(long = "signing-key")]
+    pub signing_key: Option<String>,
+
+    /// Package name recorded in the metadata.
+    #[clap(long = "package-name")]
+    pub package_name: Option<String>,
+
+    /// Package version recorded in the metadata.
+    #[clap(long = "package-version")]
+    pub package_version: Option<String>,
+
     /// Unit test function name, when compiling for testing
     #[clap(long, required(false))]
     pub unit_test_function: Option<String>,
+
+    /// Build for the mock host of the tests and `polkavm-wrapper`, allowing calls to natives
+    /// pallet-revive doesn't serve, such as `revive::random::bytes`.
+    #[clap(long = "mock-host")]
+    pub mock_host: bool,
+}
+
+impl Options {
+    /// The options passed on to the code generator. `--source-map` and `--stack-traces`
+    /// imply `-g`.
+    pub fn codegen_options(&self) -> CodegenOptions {
+        CodegenOptions {
+            debug: self.debug || self.source_map || self.stack_traces,
+            overflow_checks: self.overflow_checks,
+            assume_no_abort: self.assume_no_abort,
+            strip_debug_natives: self.strip_debug_natives,
+            export_public: self.export_public,
+            strip_exports: self.strip_exports,
+            extra_exports: self.extra_exports.clone(),
+            coverage: self.coverage,
+            heap_stats: self.heap_stats,
+            paranoid: self.paranoid,
+            stack_traces: self.stack_traces,
+            selector_overrides: self.selector_overrides.clone(),
+            inline_overrides: self.inline_overrides.clone(),
+            payable_checks: self.payable_checks,
+            keep_attributes: self.keep_attributes.clone(),
+            reachability_roots: self.reachability_roots.clone(),
+            unit_test_function: self.unit_test_function.clone(),
+            mock_host: self.mock_host,
+            collect_cfgs: self.gen_dot_cfg.is_some(),
+            address_length: self.address_length.bytes(),
+            stdlib_address: self.stdlib_address(),
+            u256_helpers: self.u256_helpers,
+            split_module: None,
+        }
+    }
+
+    /// The LLVM target CPU, `--llvm-cpu` or the default of `platform`.
+    pub fn llvm_cpu<'a>(&'a self, platform: &TargetPlatform) -> &'a str {
+        self.llvm_cpu
+            .as_deref()
+            .unwrap_or_else(|| platform.llvm_cpu())
+    }
+
+    /// The LLVM target features, `--llvm-features` or the defaults of `platform`.
+    pub fn llvm_features<'a>(&'a self, platform: &TargetPlatform) -> &'a str {
+        self.llvm_features
+            .as_deref()
+            .unwrap_or_else(|| platform.llvm_features())
+    }
+
+    /// The named address mapping, by name. Mappings that aren't `name=address` are skipped.
+    pub fn named_addresses(&self) -> BTreeMap<String, String> {
+        self.named_address_mapping
+            .iter()
+            .filter_map(|m| m.split_once('='))
+            .map(|(name, addr)| (name.trim().to_string(), addr.trim().to_string()))
+            .collect()
+    }
+
+    /// The address of the `std` named address, `0x1` unless it is mapped to another one.
+    pub fn stdlib_address(&self) -> AccountAddress {
+        self.named_addresses()
+            .get("std")
+            .and_then(|addr| AccountAddress::from_hex_literal(addr).ok())
+            .unwrap_or(AccountAddress::ONE)
+    }
+
+    /// The `--define` flags, parsed. A name defined twice is an error rather than two
+    /// functions of the same name in the generated module.
+    pub fn parsed_defines(&self) -> Result<Vec<Define>> {
+        let defines = self
+            .defines
+            .iter()
+            .map(|define| Define::parse(define))
+            .collect::<Result<Vec<_>>>()?;
+        let mut names = BTreeSet::new();
+        if let Some(define) = defines.iter().find(|define| !names.insert(&define.name)) {
+            return Err(MoveToPolkaError::compile(format!(
+                "--define `{}` is given more than once",
+                define.name
+            )));
+        }
+        Ok(defines)
+    }
+
+    /// The move-native runtime to link, `--move-native-variant` with `--address-length`.
+    pub fn native_variant(&self) -> NativeVariant {
+        NativeVariant {
+            address_length: self.address_length,
+            ..self.move_native_variant
+        }
+    }
+}
+
+impl Options {
+    /// Options compiling the package described by the `Move.toml` at `path` (the manifest or
+    /// its directory), resolved like the command line does: the package root is the source,
+    /// local and git dependencies are dependencies, and the named address mapping holds
+    /// `[addresses]` and the substitutions of the dependencies. `dev` resolves for a test
+    /// build, adding `[dev-dependencies]` and `[dev-addresses]`.
+    pub fn from_move_toml(path: &Path, dev: bool) -> Result<Options> {
+        let root = if path.is_file() {
+            path.parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+        } else {
+            path
+        };
+        let root = root.to_string_lossy();
+        let output = Options::default().output;
+        Ok(resolve_packages(&output, &[&root], &[], HashSet::new(), dev)?.build())
+    }
+
+    /// `sources` with glob patterns expanded.
+    pub fn resolved_sources(&self) -> Result<Vec<String>> {
+        resolve_paths(&self.sources)
+    }
+
+    /// `dependencies` with glob patterns expanded, without the paths that are also sources:
+    /// a root given as both is compiled as a source.
+    pub fn resolved_dependencies(&self) -> Result<Vec<String>> {
+        let sources = self.resolved_sources()?;
+        Ok(resolve_paths(&self.dependencies)?
+            .into_iter()
+            .filter(|dependency| !sources.contains(dependency))
+            .collect())
+    }
+}
+
+/// Expand the glob patterns among `paths`. Plain paths are kept as they are, a pattern
+/// matching nothing is an error. The matches of a pattern are sorted and paths given more
+/// than once are kept once, so the order of the file system doesn't leak into builds.
+fn resolve_paths(paths: &[String]) -> Result<Vec<String>> {
+    let mut resolved: Vec<String> = vec![];
+    for path in paths {
+        let matches = if path.contains(['*', '?', '[']) {
+            let mut matches = glob::glob(path)
+                .map_err(|e| MoveToPolkaError::compile(format!("invalid pattern `{path}`: {e}")))?
+                .map(|entry| entry.map(|path| path.to_string_lossy().to_string()))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(MoveToPolkaError::compile)?;
+            if matches.is_empty() {
+                return Err(MoveToPolkaError::compile(format!(
+                    "`{path}` matches no files"
+                )));
+            }
+            matches.sort();
+            matches
+        } else {
+            vec![path.clone()]
+        };
+        for path in matches {
+            if !resolved.contains(&path) {
+                resolved.push(path);
+            }
+        }
+    }
+    Ok(resolved)
 }
 
 impl Default for Options {
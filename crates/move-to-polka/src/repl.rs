@@ -0,0 +1,200 @@
+//! A session of calls against one blob, for exploratory testing with `polkavm-wrapper repl`.
+//!
+//! Unlike a single `polkavm-wrapper` invocation, which starts from a cold instance every
+//! time, a [`Session`] keeps the instance and its storage alive between calls, so resources
+//! stored by one call are seen by the next. Exports are called with numeric arguments, like
+//! `polkavm-wrapper -e`, and entry functions with typed JSON arguments through the `call`
+//! export, encoded with the blob's [`Abi`]. [`Session::reset`] goes back to the storage the
+//! session started with, on a fresh instance. A call that doesn't succeed leaves the storage
+//! as it was before the call.
+
+use crate::{
+    abi::Abi,
+    error::{MoveToPolkaError, Result},
+    fuzz::Outcome,
+    linker::{create_instance, parse_to_blob},
+    metadata::PackageMetadata,
+    signature::ExportSignature,
+    storage::{stored_resources, StoredResource},
+};
+use polkavm::{Instance, ProgramBlob};
+use polkavm_move_native::{
+    host::{ProgramError, Runtime},
+    storage::{GlobalStorage, StorageBackend, StorageSnapshot},
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The result of a call in a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallResult {
+    pub outcome: Outcome,
+    /// The returned values: the value in `a0` of a successful export call, or the output of
+    /// an entry function decoded with the ABI, if it returned any.
+    pub values: Vec<Value>,
+}
+
+pub struct Session {
+    blob: ProgramBlob,
+    abi: Option<Abi>,
+    /// Export signatures from the metadata, by export name.
+    signatures: BTreeMap<String, String>,
+    instance: Instance<Runtime, ProgramError>,
+    runtime: Runtime,
+    /// The storage the session started with, restored by [`Session::reset`].
+    initial_state: StorageSnapshot,
+}
+
+impl Session {
+    /// Start a session on the blob `bytes`, with the export signatures of its `metadata` and
+    /// the resources of `backend` if given. Without an `abi`, entry functions can't be called.
+    pub fn new(
+        bytes: &[u8],
+        metadata: Option<PackageMetadata>,
+        abi: Option<Abi>,
+        backend: Option<Box<dyn StorageBackend>>,
+    ) -> Result<Self> {
+        let blob = parse_to_blob(bytes)?;
+        let (instance, mut runtime) = create_instance(blob.clone())?;
+        if let Some(backend) = backend {
+            runtime.storage = Box::new(GlobalStorage::with_backend(backend));
+        }
+        let initial_state = runtime.storage.snapshot();
+        Ok(Self {
+            blob,
+            abi,
+            signatures: metadata
+                .map(|metadata| metadata.export_signatures)
+                .unwrap_or_default(),
+            instance,
+            runtime,
+            initial_state,
+        })
+    }
+
+    /// The exports of the blob, with their signatures if the blob carries them.
+    pub fn exports(&self) -> Result<Vec<(String, Option<ExportSignature>)>> {
+        self.blob
+            .exports()
+            .map(|export| {
+                let name = export.symbol().to_string();
+                let signature = self
+                    .signatures
+                    .get(&name)
+                    .map(|signature| ExportSignature::parse(signature))
+                    .transpose()?;
+                Ok((name, signature))
+            })
+            .collect()
+    }
+
+    /// Call the export `name` with up to 4 numeric arguments.
+    pub fn call_export(&mut self, name: &str, args: &[u64]) -> Result<CallResult> {
+        let export = self
+            .blob
+            .exports()
+            .find(|export| export.symbol().to_string() == name)
+            .ok_or_else(|| MoveToPolkaError::codec(format!("the blob doesn't export `{name}`")))?;
+        let signature = self
+            .signatures
+            .get(name)
+            .map(|signature| ExportSignature::parse(signature))
+            .transpose()?;
+        if let Some(signature) = &signature {
+            signature.check_call(name, args)?;
+        }
+        let pc = export.program_counter();
+        let state = self.runtime.storage.snapshot();
+        let (instance, runtime) = (&mut self.instance, &mut self.runtime);
+        let result = match *args {
+            [] => instance.call_typed_and_get_result::<u64, ()>(runtime, pc, ()),
+            [a] => instance.call_typed_and_get_result::<u64, (u64,)>(runtime, pc, (a,)),
+            [a, b] => instance.call_typed_and_get_result::<u64, (u64, u64)>(runtime, pc, (a, b)),
+            [a, b, c] => {
+                instance.call_typed_and_get_result::<u64, (u64, u64, u64)>(runtime, pc, (a, b, c))
+            }
+            [a, b, c, d] => instance.call_typed_and_get_result::<u64, (u64, u64, u64, u64)>(
+                runtime,
+                pc,
+                (a, b, c, d),
+            ),
+            _ => {
+                return Err(MoveToPolkaError::codec(format!(
+                    "`{name}` called with {} arguments, at most 4 are supported",
+                    args.len()
+                )))
+            }
+        };
+        // without a signature, the result register is shown even if nothing was returned
+        let returns = !matches!(&signature, Some(signature) if signature.results.is_empty());
+        let values = match &result {
+            Ok(value) if returns => vec![Value::from(*value)],
+            _ => vec![],
+        };
+        let outcome = Outcome::of(&result.map(|_| ()));
+        if outcome != Outcome::Success {
+            self.runtime.storage.restore(&state);
+        }
+        Ok(CallResult { outcome, values })
+    }
+
+    /// Call the entry function `function`, given as `module::function`, with the JSON `args`
+    /// through the `call` export.
+    pub fn call_entry(&mut self, function: &str, args: &[Value]) -> Result<CallResult> {
+        let abi = self.abi.as_ref().ok_or_else(|| {
+            MoveToPolkaError::codec(format!("no ABI given to encode the call of `{function}`"))
+        })?;
+        self.runtime.call_data = abi.encode_call(function, args)?;
+        self.runtime.output.clear();
+        let state = self.runtime.storage.snapshot();
+        let result =
+            self.instance
+                .call_typed_and_get_result::<(), ()>(&mut self.runtime, "call", ());
+        let outcome = Outcome::of(&result);
+        if outcome != Outcome::Success {
+            // like a reverted transaction, an aborted call leaves no writes behind
+            self.runtime.storage.restore(&state);
+        }
+        let values = match outcome {
+            Outcome::Success if !self.runtime.output.is_empty() => {
+                abi.decode_return(function, &self.runtime.output)?
+            }
+            _ => vec![],
+        };
+        Ok(CallResult { outcome, values })
+    }
+
+    /// The resources currently stored.
+    pub fn storage(&self) -> StorageSnapshot {
+        self.runtime.storage.snapshot()
+    }
+
+    /// The resources currently stored, decoded with the ABI, see [`stored_resources`].
+    pub fn resources(&self) -> Vec<StoredResource> {
+        stored_resources(self.runtime.storage.as_ref(), self.abi.as_ref())
+    }
+
+    /// Restore the storage the session started with, on a fresh instance. Balances and mock
+    /// contracts are kept.
+    pub fn reset(&mut self) -> Result<()> {
+        let (instance, mut runtime) = create_instance(self.blob.clone())?;
+        // keep the storage, and its backend, of the session
+        runtime.storage = std::mem::replace(
+            &mut self.runtime.storage,
+            Box::new(GlobalStorage::default()),
+        );
+        runtime.storage.restore(&self.initial_state);
+        runtime.balances = std::mem::take(&mut self.runtime.balances);
+        runtime.contracts = std::mem::take(&mut self.runtime.contracts);
+        runtime.timestamp_ms = self.runtime.timestamp_ms;
+        runtime.random = std::mem::take(&mut self.runtime.random);
+        self.instance = instance;
+        self.runtime = runtime;
+        Ok(())
+    }
+
+    /// The runtime of the instance, e.g. to set balances or mock contracts.
+    pub fn runtime(&mut self) -> &mut Runtime {
+        &mut self.runtime
+    }
+}
@@ -0,0 +1,155 @@
+//! Move type signatures of exported functions.
+//!
+//! PolkaVM export metadata only records how many arguments an export takes. The code generator
+//! additionally writes a compact type signature of every export to the
+//! [`EXPORT_SIGNATURES_SECTION`] of its object files, see
+//! [`move_polka_codegen::stackless::export_signature`]. The PolkaVM linker does not keep custom
+//! sections, so [`read_export_signatures`] reads them from the linked ELF object, and the
+//! signatures travel in the [`crate::metadata::PackageMetadata`] file instead. Hosts use
+//! [`ExportSignature`] to check a call against the export before making it, and to show it with
+//! the parameter names.
+
+use crate::error::{MoveToPolkaError, Result};
+use move_polka_codegen::stackless::EXPORT_SIGNATURES_SECTION;
+use object::{Object, ObjectSection};
+use std::{collections::BTreeMap, fmt, str::Chars};
+
+/// Read the signature of every export from the ELF object `elf`, by export name.
+/// An object without exports has no signatures section.
+pub fn read_export_signatures(elf: &[u8]) -> Result<BTreeMap<String, String>> {
+    let file = object::File::parse(elf).map_err(MoveToPolkaError::invalid_artifact)?;
+    let mut signatures = BTreeMap::new();
+    let Some(section) = file.section_by_name(EXPORT_SIGNATURES_SECTION) else {
+        return Ok(signatures);
+    };
+    let data = section.data().map_err(MoveToPolkaError::invalid_artifact)?;
+    let data = std::str::from_utf8(data).map_err(MoveToPolkaError::invalid_artifact)?;
+    for line in data.lines().filter(|line| !line.is_empty()) {
+        let (name, signature) = line.split_once('=').ok_or_else(|| {
+            MoveToPolkaError::invalid_artifact(format!("malformed export signature {line}"))
+        })?;
+        signatures.insert(name.to_string(), signature.to_string());
+    }
+    Ok(signatures)
+}
+
+/// A parsed export signature, with the Move types of the parameters and results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSignature {
+    pub params: Vec<String>,
+    pub results: Vec<String>,
+    /// The names of the parameters, empty for signatures written without them.
+    pub param_names: Vec<String>,
+}
+
+impl ExportSignature {
+    pub fn parse(signature: &str) -> Result<Self> {
+        let malformed = || {
+            MoveToPolkaError::invalid_artifact(format!("malformed export signature {signature}"))
+        };
+        let (types, param_names) = match signature.split_once(';') {
+            Some((types, names)) => (types, names.split(',').map(str::to_string).collect()),
+            None => (signature, vec![]),
+        };
+        let (params, results) = types.split_once(':').ok_or_else(malformed)?;
+        let decode_all = |codes: &str| {
+            let mut chars = codes.chars();
+            let mut types = vec![];
+            while chars.as_str() != "" {
+                types.push(decode_type(&mut chars).ok_or_else(malformed)?);
+            }
+            Ok::<_, MoveToPolkaError>(types)
+        };
+        let params = decode_all(params)?;
+        if !param_names.is_empty() && param_names.len() != params.len() {
+            return Err(malformed());
+        }
+        Ok(Self {
+            params,
+            results: decode_all(results)?,
+            param_names,
+        })
+    }
+
+    /// Check that a call of the export `name` passes `args` the way the export takes its
+    /// parameters: a register each, two for a `u128` (low half first), holding a value of the
+    /// parameter's type. Parameters passed by pointer, i.e. all but `bool` and the integers up
+    /// to `u128`, take a 32-bit guest address.
+    pub fn check_call(&self, name: &str, args: &[u64]) -> Result<()> {
+        let registers: usize = self
+            .params
+            .iter()
+            .map(|ty| if ty == "u128" { 2 } else { 1 })
+            .sum();
+        if args.len() != registers {
+            return Err(MoveToPolkaError::codec(format!(
+                "{name}{self} takes {registers} argument(s), {} given",
+                args.len()
+            )));
+        }
+        let mut args = args.iter();
+        for (index, ty) in self.params.iter().enumerate() {
+            let arg = *args.next().expect("counted above");
+            let max = match ty.as_str() {
+                "bool" => 1,
+                "u8" => u8::MAX.into(),
+                "u16" => u16::MAX.into(),
+                "u32" => u32::MAX.into(),
+                "u64" => u64::MAX,
+                "u128" => {
+                    args.next();
+                    u64::MAX
+                }
+                _ => u32::MAX.into(),
+            };
+            if arg > max {
+                let param = self
+                    .param_names
+                    .get(index)
+                    .map_or(ty.as_str(), String::as_str);
+                return Err(MoveToPolkaError::codec(format!(
+                    "{name}{self}: {arg} is not a valid `{ty}` for `{param}`"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExportSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = match self.param_names.as_slice() {
+            [] => self.params.clone(),
+            names => names
+                .iter()
+                .zip(&self.params)
+                .map(|(name, ty)| format!("{name}: {ty}"))
+                .collect(),
+        };
+        write!(f, "({})", params.join(", "))?;
+        match self.results.as_slice() {
+            [] => Ok(()),
+            [result] => write!(f, ": {result}"),
+            results => write!(f, ": ({})", results.join(", ")),
+        }
+    }
+}
+
+fn decode_type(codes: &mut Chars) -> Option<String> {
+    Some(match codes.next()? {
+        'b' => "bool".to_string(),
+        '1' => "u8".to_string(),
+        '2' => "u16".to_string(),
+        '4' => "u32".to_string(),
+        '8' => "u64".to_string(),
+        'x' => "u128".to_string(),
+        'y' => "u256".to_string(),
+        'a' => "address".to_string(),
+        's' => "signer".to_string(),
+        't' => "struct".to_string(),
+        'v' => format!("vector<{}>", decode_type(codes)?),
+        'r' => format!("&{}", decode_type(codes)?),
+        'm' => format!("&mut {}", decode_type(codes)?),
+        _ => return None,
+    })
+}
@@ -0,0 +1,165 @@
+//! Ed25519 signatures of produced blobs, for provenance of CI artifacts.
+//!
+//! With `--signing-key`, the compiler records the hash of the public key in the blob's
+//! metadata ([`crate::metadata::PackageMetadata::signer_key_hash`]), then signs the blob
+//! followed by its metadata file and writes a detached signature next to it:
+//!
+//! ```json
+//! { "algorithm": "ed25519", "public_key": "d75a98...", "signature": "e5564300..." }
+//! ```
+//!
+//! [`verify_blob`] checks the signature against the blob and its metadata, the key hash in
+//! the metadata, and that the blob was signed by a trusted key: a signature alone only shows
+//! that whoever built the blob also signed it. Keys are 32 byte ed25519 secret keys (public
+//! keys for verification), hex encoded, read from a file or, given as `env:NAME`, from the
+//! environment variable `NAME`.
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    metadata::PackageMetadata,
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tiny_keccak::{Hasher, Keccak};
+
+/// File name suffix of the detached signature written next to a blob.
+pub const SIGNATURE_EXTENSION: &str = "sig.json";
+
+/// The only signature algorithm.
+pub const ED25519: &str = "ed25519";
+
+/// A detached signature of a blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobSignature {
+    pub algorithm: String,
+    /// Hex encoded public key of the signer.
+    pub public_key: String,
+    /// Hex encoded signature of the blob.
+    pub signature: String,
+}
+
+impl BlobSignature {
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed signature {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Path of the signature belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.sig.json`.
+pub fn signature_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(SIGNATURE_EXTENSION)
+}
+
+/// Read the hex encoded 32 bytes of a key from the file `source`, or from the environment
+/// variable `NAME` if `source` is `env:NAME`.
+fn read_key_bytes(source: &str) -> Result<[u8; 32]> {
+    let hex_key = match source.strip_prefix("env:") {
+        Some(name) => std::env::var(name).map_err(|e| {
+            MoveToPolkaError::signing(format!("can't read the key from ${name}: {e}"))
+        })?,
+        None => std::fs::read_to_string(source)?,
+    };
+    let bytes = hex::decode(hex_key.trim().trim_start_matches("0x"))
+        .map_err(|e| MoveToPolkaError::signing(format!("malformed key in {source}: {e}")))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        MoveToPolkaError::signing(format!(
+            "the key in {source} has {} bytes instead of 32",
+            bytes.len()
+        ))
+    })
+}
+
+/// Read an ed25519 secret key, see [`read_key_bytes`].
+pub fn read_signing_key(source: &str) -> Result<SigningKey> {
+    Ok(SigningKey::from_bytes(&read_key_bytes(source)?))
+}
+
+/// Read an ed25519 public key, see [`read_key_bytes`].
+pub fn read_verifying_key(source: &str) -> Result<VerifyingKey> {
+    VerifyingKey::from_bytes(&read_key_bytes(source)?).map_err(MoveToPolkaError::signing)
+}
+
+/// Hex encoded keccak256 hash of `key`, as recorded in the metadata.
+pub fn key_hash(key: &VerifyingKey) -> String {
+    let mut keccak = Keccak::v256();
+    keccak.update(key.as_bytes());
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hex::encode(hash)
+}
+
+/// Sign the blob `bytes` followed by `metadata`, the contents of its metadata file.
+pub fn sign_blob(bytes: &[u8], metadata: &[u8], key: &SigningKey) -> BlobSignature {
+    BlobSignature {
+        algorithm: ED25519.to_string(),
+        public_key: hex::encode(key.verifying_key().as_bytes()),
+        signature: hex::encode(key.sign(&[bytes, metadata].concat()).to_bytes()),
+    }
+}
+
+/// Check that `signature` is a signature of the blob `bytes` and its `metadata` file by the
+/// key whose hash is recorded in the metadata, and by one of `trusted_keys`, of which there
+/// must be at least one. Returns the key that signed the blob.
+pub fn verify_blob(
+    bytes: &[u8],
+    metadata: &[u8],
+    signature: &BlobSignature,
+    trusted_keys: &[VerifyingKey],
+) -> Result<VerifyingKey> {
+    if trusted_keys.is_empty() {
+        return Err(MoveToPolkaError::signing(
+            "no trusted key given, any key can sign a blob",
+        ));
+    }
+    if signature.algorithm != ED25519 {
+        return Err(MoveToPolkaError::signing(format!(
+            "unsupported signature algorithm {}",
+            signature.algorithm
+        )));
+    }
+    let decode = |hex_value: &str, what: &str| {
+        hex::decode(hex_value)
+            .map_err(|e| MoveToPolkaError::signing(format!("malformed {what}: {e}")))
+    };
+    let public_key: [u8; 32] = decode(&signature.public_key, "public key")?
+        .try_into()
+        .map_err(|_| MoveToPolkaError::signing("the public key is not 32 bytes"))?;
+    let key = VerifyingKey::from_bytes(&public_key).map_err(MoveToPolkaError::signing)?;
+    let signature_bytes: [u8; 64] = decode(&signature.signature, "signature")?
+        .try_into()
+        .map_err(|_| MoveToPolkaError::signing("the signature is not 64 bytes"))?;
+
+    let recorded_hash = PackageMetadata::from_json(metadata)?
+        .signer_key_hash
+        .ok_or_else(|| MoveToPolkaError::signing("the metadata has no signer key hash"))?;
+    if recorded_hash != key_hash(&key) {
+        return Err(MoveToPolkaError::signing(
+            "the blob was built for a different signing key",
+        ));
+    }
+    if !trusted_keys.contains(&key) {
+        return Err(MoveToPolkaError::signing(format!(
+            "the blob is signed by the untrusted key {}",
+            signature.public_key
+        )));
+    }
+    key.verify_strict(
+        &[bytes, metadata].concat(),
+        &Signature::from_bytes(&signature_bytes),
+    )
+    .map_err(|_| MoveToPolkaError::signing("the signature does not match the blob"))?;
+    Ok(key)
+}
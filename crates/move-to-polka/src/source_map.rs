@@ -0,0 +1,143 @@
+//! Compact JSON source maps for `.polkavm` blobs.
+//!
+//! The map is computed from the debug line programs the polka linker keeps in the blob,
+//! so block explorers and trap reporters can resolve a program counter to a Move source
+//! line without parsing DWARF:
+//!
+//! ```json
+//! {
+//!   "exports": { "main": 0 },
+//!   "functions": {
+//!     "0xa000::overflow::add_u8": [{ "start": 12, "end": 18, "file": "/path/overflow.move", "line": 6 }]
+//!   }
+//! }
+//! ```
+
+use crate::error::{MoveToPolkaError, Result};
+use polkavm::{ProgramBlob, ProgramCounter, ProgramParseError};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// File name suffix of the source map written next to a blob.
+pub const SOURCE_MAP_EXTENSION: &str = "sourcemap.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMap {
+    /// Program counter of every exported symbol.
+    pub exports: BTreeMap<String, u32>,
+    /// Source lines of every function, as sorted, non-overlapping program counter ranges.
+    pub functions: BTreeMap<String, Vec<PcRange>>,
+}
+
+/// Instructions in `start..end` were generated for `file:line`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcRange {
+    pub start: u32,
+    pub end: u32,
+    pub file: String,
+    pub line: u32,
+}
+
+impl SourceMap {
+    /// Build the source map of a linked blob. Blobs linked without debug info only
+    /// get their exports mapped.
+    pub fn from_blob(blob: &ProgramBlob) -> Result<Self> {
+        let parse_error =
+            |e: ProgramParseError| MoveToPolkaError::invalid_artifact(format!("{e:?}"));
+        let exports = blob
+            .exports()
+            .map(|export| (export.symbol().to_string(), export.program_counter().0))
+            .collect();
+
+        let mut functions: BTreeMap<String, Vec<PcRange>> = BTreeMap::new();
+        let code_len = blob.code().len() as u32;
+        let mut pc = 0;
+        while pc < code_len {
+            let program = blob
+                .get_debug_line_program_at(ProgramCounter(pc))
+                .map_err(parse_error)?;
+            let Some(mut program) = program else {
+                pc += 1;
+                continue;
+            };
+            let mut next_pc = pc + 1;
+            while let Some(region) = program.run().map_err(parse_error)? {
+                let range = region.instruction_range();
+                next_pc = next_pc.max(range.end.0);
+                // The innermost frame is the one the instructions were generated for.
+                let Some(frame) = region.frames().last() else {
+                    continue;
+                };
+                let Some(line) = frame.line() else {
+                    continue;
+                };
+                let name = frame.full_name().map_err(parse_error)?.to_string();
+                let file = frame
+                    .path()
+                    .map_err(parse_error)?
+                    .map(|path| path.into_owned())
+                    .unwrap_or_default();
+                push_range(
+                    functions.entry(name).or_default(),
+                    PcRange {
+                        start: range.start.0,
+                        end: range.end.0,
+                        file,
+                        line,
+                    },
+                );
+            }
+            pc = next_pc;
+        }
+        for ranges in functions.values_mut() {
+            ranges.sort_by_key(|range| range.start);
+        }
+        Ok(Self { exports, functions })
+    }
+
+    /// Find the function and source line `pc` belongs to.
+    pub fn lookup(&self, pc: u32) -> Option<(&str, &PcRange)> {
+        self.functions.iter().find_map(|(name, ranges)| {
+            ranges
+                .iter()
+                .find(|range| (range.start..range.end).contains(&pc))
+                .map(|range| (name.as_str(), range))
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed source map {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Path of the source map belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.sourcemap.json`.
+pub fn source_map_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(SOURCE_MAP_EXTENSION)
+}
+
+/// Append `range`, merging it into the previous one when both map to the same line.
+fn push_range(ranges: &mut Vec<PcRange>, range: PcRange) {
+    if let Some(last) = ranges.last_mut() {
+        if last.end == range.start && last.file == range.file && last.line == range.line {
+            last.end = range.end;
+            return;
+        }
+    }
+    ranges.push(range);
+}
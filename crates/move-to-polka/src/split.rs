@@ -0,0 +1,180 @@
+//! Per-module blobs, for dynamic linking experiments.
+//!
+//! With `--split-modules`, the compiler links, besides the blob of the whole package, a blob
+//! per module of the package (`<output>.<address>_<module>.polkavm`). Its call selector only
+//! dispatches the entry functions of that module, and only they are exported. PolkaVM has no
+//! dynamic linking yet, so every blob still holds the code of the modules it calls; the
+//! manifest written next to the package blob (`<output>.modules.json`) records, per blob, the
+//! functions it imports from other modules, so a loader or a pallet-side dispatcher can
+//! compose the blobs instead:
+//!
+//! ```json
+//! {
+//!   "modules": [
+//!     {
+//!       "module": "0xa000::second",
+//!       "blob": "modules.0xa000_second.polkavm",
+//!       "exports": ["call", "deploy"],
+//!       "selectors": { "second::negate": "0x...", "second::sum_twice": "0x..." },
+//!       "imports": [{ "module": "0xa000::first", "functions": ["first::sum"] }]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    options::Options,
+};
+use move_model::model::{GlobalEnv, ModuleEnv};
+use move_polka_codegen::{
+    options::CodegenOptions,
+    stackless::{called_functions, entry_selector, ExportPolicy},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// File name suffix of the manifest written next to the package blob.
+pub const MANIFEST_EXTENSION: &str = "modules.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitManifest {
+    pub modules: Vec<ModuleBlob>,
+}
+
+/// The blob of one module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleBlob {
+    /// The module, as `address::module`.
+    pub module: String,
+    /// File name of the blob, next to the manifest.
+    pub blob: String,
+    /// The exports of the blob, the runtime's `call` and `deploy` included.
+    pub exports: Vec<String>,
+    /// Hex encoded selector of every entry function the blob dispatches, by
+    /// `module::function`.
+    pub selectors: BTreeMap<String, String>,
+    /// The functions of other modules the module calls, natives excluded.
+    pub imports: Vec<ModuleImport>,
+}
+
+/// The functions a module calls in another module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleImport {
+    /// The module, as `address::module`.
+    pub module: String,
+    /// The called functions, as `module::function`.
+    pub functions: Vec<String>,
+}
+
+impl ModuleBlob {
+    /// The manifest entry of `module`, linked to `blob_path` with the given `exports`.
+    pub fn new(
+        module: &ModuleEnv,
+        blob_path: &Path,
+        exports: Vec<String>,
+        options: &Options,
+    ) -> Result<Self> {
+        let codegen_options = CodegenOptions {
+            split_module: Some(module.get_id()),
+            ..options.codegen_options()
+        };
+        let env = module.env;
+        let mut selectors = BTreeMap::new();
+        let mut imports: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for fn_env in module.get_functions() {
+            if ExportPolicy::of(&fn_env, &codegen_options).is_dispatched()
+                && fn_env.get_type_parameter_count() == 0
+            {
+                let selector =
+                    entry_selector(&fn_env, &codegen_options).map_err(MoveToPolkaError::compile)?;
+                selectors.insert(
+                    fn_env.get_full_name_str(),
+                    format!("0x{:08x}", u32::from_be_bytes(selector.to_le_bytes())),
+                );
+            }
+            for callee in called_functions(&fn_env) {
+                let callee = env.get_function(callee);
+                if callee.module_env.get_id() != module.get_id() && !callee.is_native() {
+                    imports
+                        .entry(callee.module_env.get_full_name_str())
+                        .or_default()
+                        .push(callee.get_full_name_str());
+                }
+            }
+        }
+        Ok(Self {
+            module: module.get_full_name_str(),
+            blob: blob_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            exports,
+            selectors,
+            imports: imports
+                .into_iter()
+                .map(|(module, mut functions)| {
+                    functions.sort();
+                    functions.dedup();
+                    ModuleImport { module, functions }
+                })
+                .collect(),
+        })
+    }
+}
+
+impl SplitManifest {
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed module manifest {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Look up the blob of `module`, given as `address::module`.
+    pub fn module(&self, module: &str) -> Option<&ModuleBlob> {
+        self.modules.iter().find(|blob| blob.module == module)
+    }
+}
+
+/// The modules of `global_env` that get a blob of their own: the modules of the package.
+pub fn split_modules(global_env: &GlobalEnv) -> impl Iterator<Item = ModuleEnv<'_>> {
+    global_env
+        .get_modules()
+        .filter(|module| module.is_target() && !module.is_script_module())
+}
+
+/// Path of the manifest belonging to the package blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.modules.json`.
+pub fn manifest_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(MANIFEST_EXTENSION)
+}
+
+/// Name of the files of `module`, `<address>_<module>`, so that modules of the same name at
+/// different addresses don't overwrite each other's blob or objects.
+pub fn module_file_name(module: &ModuleEnv) -> String {
+    module.get_full_name_str().replace("::", "_")
+}
+
+/// Path of the blob of the module `module_name`, see [`module_file_name`], next to the package
+/// blob at `blob_path`, e.g. `output/foo.polkavm` -> `output/foo.0xa000_bar.polkavm` for
+/// module `0xa000::bar`.
+pub fn module_blob_path(blob_path: &Path, module_name: &str) -> PathBuf {
+    let extension = blob_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+    blob_path.with_extension(format!("{module_name}.{extension}"))
+}
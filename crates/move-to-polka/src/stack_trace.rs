@@ -0,0 +1,137 @@
+//! Stack traces of aborting Move programs.
+//!
+//! With `--stack-traces`, every Move function pushes `(function id, caller pc)` onto a
+//! shadow stack in guest memory on entry and pops it before returning. Right before an
+//! abort, the guest hands the shadow stack to the host through the `stack_trace` import,
+//! which stores it in [`polkavm_move_native::host::Runtime::stack_trace`]. The compiler
+//! writes a stack trace map next to the blob, which names the function of every id:
+//!
+//! ```json
+//! { "functions": ["0xa000::overflow::add_u8", "0xa000::overflow::add_u8_overflow"] }
+//! ```
+//!
+//! [`StackTraceMap::symbolize`] turns the recorded frames into a readable trace. Given the
+//! blob and its [`SourceMap`], it also resolves the return addresses to call sites.
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    source_map::SourceMap,
+};
+use polkavm::ProgramBlob;
+use polkavm_move_native::host::StackFrame;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// File name suffix of the stack trace map written next to a blob.
+pub const STACK_TRACE_MAP_EXTENSION: &str = "stacktrace.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackTraceMap {
+    /// Function names, indexed by the function id recorded in the shadow stack.
+    pub functions: Vec<String>,
+}
+
+/// A symbolized shadow stack, innermost frame first, see [`StackTraceMap::symbolize`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackTrace {
+    pub frames: Vec<SymbolizedFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolizedFrame {
+    pub function: String,
+    pub caller_pc: u32,
+    /// `file:line` of the call site, when the blob and its source map were given.
+    pub call_site: Option<String>,
+}
+
+impl StackTraceMap {
+    /// Add a function and return its id.
+    pub fn push(&mut self, function: String) -> u32 {
+        self.functions.push(function);
+        (self.functions.len() - 1) as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Name the `frames` recorded by an aborting blob, resolving the call sites through the
+    /// blob's jump table and source map if given.
+    pub fn symbolize(
+        &self,
+        frames: &[StackFrame],
+        source_map: Option<(&ProgramBlob, &SourceMap)>,
+    ) -> Result<StackTrace> {
+        let frames = frames
+            .iter()
+            .rev()
+            .map(|frame| {
+                let function = self
+                    .functions
+                    .get(frame.function as usize)
+                    .ok_or_else(|| {
+                        MoveToPolkaError::invalid_artifact(format!(
+                            "stack trace map has no function {}",
+                            frame.function
+                        ))
+                    })?
+                    .clone();
+                let call_site = source_map
+                    .and_then(|(blob, source_map)| {
+                        let pc = blob.jump_table().get_by_address(frame.caller_pc)?;
+                        source_map.lookup(pc.0)
+                    })
+                    .map(|(_, range)| format!("{}:{}", range.file, range.line));
+                Ok(SymbolizedFrame {
+                    function,
+                    caller_pc: frame.caller_pc,
+                    call_site,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(StackTrace { frames })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!(
+                "malformed stack trace map {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (depth, frame) in self.frames.iter().enumerate() {
+            write!(f, "{depth:>4}: {}", frame.function)?;
+            match &frame.call_site {
+                Some(call_site) => writeln!(f, " (called from {call_site})")?,
+                None => writeln!(f, " (return address 0x{:x})", frame.caller_pc)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Path of the stack trace map belonging to the blob at `blob_path`,
+/// e.g. `output/foo.polkavm` -> `output/foo.stacktrace.json`.
+pub fn stack_trace_map_path(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(STACK_TRACE_MAP_EXTENSION)
+}
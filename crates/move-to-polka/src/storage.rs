@@ -0,0 +1,148 @@
+//! Host-side storage backends used by the runtime to hold global resources, and
+//! [`stored_resources`] to inspect them.
+
+use crate::abi::Abi;
+use polkavm_move_native::{
+    host::ProgramError,
+    storage::{MemoryBackend, Storage, StorageBackend, StructTagHash},
+    types::{MoveAddress, ACCOUNT_ADDRESS_LENGTH},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StateEntry {
+    address: String,
+    tag: String,
+    data: String,
+}
+
+/// Backend keeping resources in memory and persisting them to a JSON file on flush.
+///
+/// Addresses, tags and resource bytes are stored hex encoded, so the file can be
+/// inspected and edited by hand between runs.
+pub struct FileBackend {
+    path: PathBuf,
+    inner: MemoryBackend,
+    dirty: bool,
+}
+
+impl FileBackend {
+    /// Open a state file, loading its resources if the file exists.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut inner = MemoryBackend::new();
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            let entries: Vec<StateEntry> = serde_json::from_str(&content)?;
+            for entry in entries {
+                let address: [u8; ACCOUNT_ADDRESS_LENGTH] = hex::decode(&entry.address)?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("invalid address {}", entry.address))?;
+                let tag: StructTagHash = hex::decode(&entry.tag)?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("invalid struct tag {}", entry.tag))?;
+                inner.set(MoveAddress(address), tag, hex::decode(&entry.data)?);
+            }
+        }
+        Ok(Self {
+            path,
+            inner,
+            dirty: false,
+        })
+    }
+
+    fn persist(&self) -> anyhow::Result<()> {
+        let mut entries: Vec<StateEntry> = self
+            .inner
+            .entries()
+            .into_iter()
+            .map(|(address, tag, data)| StateEntry {
+                address: hex::encode(address.0),
+                tag: hex::encode(tag),
+                data: hex::encode(data),
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.address, &a.tag).cmp(&(&b.address, &b.tag)));
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+}
+
+impl StorageBackend for FileBackend {
+    fn get(&self, address: MoveAddress, tag: StructTagHash) -> Option<Vec<u8>> {
+        self.inner.get(address, tag)
+    }
+
+    fn set(&mut self, address: MoveAddress, tag: StructTagHash, value: Vec<u8>) {
+        self.dirty = true;
+        self.inner.set(address, tag, value);
+    }
+
+    fn remove(&mut self, address: MoveAddress, tag: StructTagHash) -> Option<Vec<u8>> {
+        self.dirty = true;
+        self.inner.remove(address, tag)
+    }
+
+    fn contains(&self, address: MoveAddress, tag: StructTagHash) -> bool {
+        self.inner.contains(address, tag)
+    }
+
+    fn entries(&self) -> Vec<(MoveAddress, StructTagHash, Vec<u8>)> {
+        self.inner.entries()
+    }
+
+    fn flush(&mut self) -> Result<(), ProgramError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.persist()
+            .map_err(|e| ProgramError::Persistence(format!("{}: {e}", self.path.display())))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// A resource in storage, see [`stored_resources`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredResource {
+    /// The address the resource is stored at, `0x` hex encoded.
+    pub address: String,
+    /// Hex encoded hash of the struct tag, the other half of the key of the resource.
+    pub tag: String,
+    /// The struct tag, e.g. `0xa000::storage::Counter`, if the ABI lists the struct.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub struct_tag: Option<String>,
+    /// The value, if its layout is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// The stored bytes, hex encoded, if the value can't be decoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<String>,
+}
+
+/// Every resource in `storage`, ordered by address and tag. Resources of the structs `abi`
+/// lists are decoded, the others keep their stored bytes.
+pub fn stored_resources(storage: &dyn Storage, abi: Option<&Abi>) -> Vec<StoredResource> {
+    storage
+        .snapshot()
+        .entries()
+        .map(|(address, tag, data)| {
+            let resource = abi.and_then(|abi| abi.resource(&tag));
+            let value = abi
+                .zip(resource)
+                .and_then(|(abi, resource)| abi.decode_resource(resource, data).ok());
+            StoredResource {
+                address: format!("0x{}", hex::encode(address.0)),
+                tag: hex::encode(tag),
+                struct_tag: resource.map(|resource| resource.name.clone()),
+                bytes: value.is_none().then(|| hex::encode(data)),
+                value,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,402 @@
+//! Recording and replaying the host calls of a run, to reproduce failures deterministically.
+//!
+//! [`record`] runs an export like [`run_lowlevel`], answering every host call itself, and
+//! notes each one in a [`Trace`]: the import, the argument registers, the returned `A0` and the
+//! guest memory the host wrote. [`replay`] runs the export again, answering the host calls
+//! from the trace instead of the host, so a failure seen with some storage, balances and
+//! called contracts is reproduced without any of them.
+//!
+//! Both run on a [`create_stepping_instance`] and count the instructions executed, so every
+//! host call is known by its step. A replay matches host calls on their argument registers and
+//! writes guest memory at the recorded addresses, so it reproduces runs of the recorded blob
+//! only: another build, even of the same sources, lays out its stack and heap differently and
+//! diverges at the first host call taking a pointer.
+//!
+//! ```ignore
+//! let (mut instance, mut runtime) = create_stepping_instance(failing_blob.clone())?;
+//! let (trace, _) = record(&mut instance, &mut runtime, "call", &[])?;
+//! let (mut instance, _) = create_stepping_instance(failing_blob)?;
+//! if let Some(divergence) = replay(&mut instance, &trace)?.divergence {
+//!     println!("{divergence}");
+//! }
+//! ```
+//!
+//! [`run_lowlevel`]: crate::linker::run_lowlevel
+//! [`create_stepping_instance`]: crate::linker::create_stepping_instance
+
+use crate::{
+    error::{MoveToPolkaError, Result},
+    fuzz::Outcome,
+    linker::host_call,
+};
+use polkavm::{CallError, Instance, InterruptKind, Module, Reg};
+use polkavm_move_native::host::{ProgramError, Runtime};
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::Range, path::Path};
+
+/// Format version of [`Trace`] files.
+pub const TRACE_VERSION: u32 = 1;
+
+/// Registers holding the arguments of a host call.
+const ARG_REGS: [Reg; 6] = [Reg::A0, Reg::A1, Reg::A2, Reg::A3, Reg::A4, Reg::A5];
+
+/// Guest memory is compared page by page around every host call.
+const PAGE_SIZE: u32 = 4096;
+
+/// The host calls of a run, written as JSON, e.g.
+/// `{ "entry": "call", "host_calls": [{ "import": "call_data_size", ... }], ... }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trace {
+    pub version: u32,
+    /// The export the run called.
+    pub entry: String,
+    /// The arguments of the export, in `A0` onwards.
+    pub args: Vec<u64>,
+    /// Hex encoded call data of the run. Replays don't need it, the host calls reading it
+    /// are in the trace.
+    pub call_data: String,
+    pub host_calls: Vec<HostCall>,
+    /// Instructions executed by the run.
+    pub steps: u64,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCall {
+    pub import: String,
+    /// `A0` to `A5` when the guest made the call.
+    pub args: Vec<u64>,
+    /// `A0` when the host returned.
+    pub result: u64,
+    /// The guest memory the host wrote.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub writes: Vec<MemoryWrite>,
+    /// Instructions executed before the call.
+    pub step: u64,
+    pub pc: Option<u32>,
+    /// The error ending the run, if the host call failed, e.g. a reverting `seal_return`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryWrite {
+    pub address: u32,
+    /// Hex encoded bytes written from `address` on.
+    pub data: String,
+}
+
+/// How a replay went, see [`replay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    /// Instructions executed.
+    pub steps: u64,
+    /// Host calls answered from the trace.
+    pub host_calls: usize,
+    pub outcome: Outcome,
+    /// The first difference to the trace, if any.
+    pub divergence: Option<Divergence>,
+}
+
+/// The first point a replay differs from its trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the host call of the trace that was expected next.
+    pub host_call: usize,
+    /// Instructions executed up to the difference.
+    pub step: u64,
+    pub pc: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "host call {} at step {}", self.host_call, self.step)?;
+        if let Some(pc) = self.pc {
+            write!(f, " (pc {pc})")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Trace {
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        let trace: Self = serde_json::from_slice(&json).map_err(|e| {
+            MoveToPolkaError::invalid_artifact(format!("malformed trace {}: {e}", path.display()))
+        })?;
+        if trace.version != TRACE_VERSION {
+            return Err(MoveToPolkaError::invalid_artifact(format!(
+                "trace {} has version {}, expected {TRACE_VERSION}",
+                path.display(),
+                trace.version
+            )));
+        }
+        Ok(trace)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(MoveToPolkaError::invalid_artifact)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Call the export `entry` with `args`, answering the host calls with `runtime` and recording
+/// them. Returns the trace and the result of the call.
+pub fn record(
+    instance: &mut Instance<Runtime, ProgramError>,
+    runtime: &mut Runtime,
+    entry: &str,
+    args: &[u64],
+) -> Result<(Trace, std::result::Result<(), CallError<ProgramError>>)> {
+    let imports = start(instance, entry, args)?;
+    let regions = memory_regions(instance.module());
+    let mut host_calls = vec![];
+    let mut steps = 0;
+    let result = loop {
+        match instance.run()? {
+            InterruptKind::Step => steps += 1,
+            InterruptKind::Ecalli(n) => {
+                let import = import_name(&imports, n);
+                let args = ARG_REGS.map(|reg| instance.reg(reg)).to_vec();
+                let pc = instance.program_counter().map(|pc| pc.0);
+                let before = snapshot(instance, &regions);
+                let result = host_call(instance, runtime, &import);
+                let writes = memory_writes(&before, &snapshot(instance, &regions));
+                host_calls.push(HostCall {
+                    import,
+                    args,
+                    result: instance.reg(Reg::A0),
+                    writes,
+                    step: steps,
+                    pc,
+                    error: result.as_ref().err().map(|e| format!("{e:?}")),
+                });
+                if let Err(e) = result {
+                    break Err(CallError::User(e));
+                }
+            }
+            InterruptKind::Finished => break Ok(()),
+            InterruptKind::NotEnoughGas => break Err(CallError::NotEnoughGas),
+            InterruptKind::Trap | InterruptKind::Segfault(_) => break Err(CallError::Trap),
+        }
+    };
+    runtime.storage.release_all();
+    let trace = Trace {
+        version: TRACE_VERSION,
+        entry: entry.to_string(),
+        args: args.to_vec(),
+        call_data: hex::encode(&runtime.call_data),
+        host_calls,
+        steps,
+        outcome: Outcome::of(&result),
+    };
+    Ok((trace, result))
+}
+
+/// Call the export of `trace` again on `instance`, answering the host calls from the trace:
+/// the guest memory they wrote is written again and `A0` set to what they returned. The
+/// replay stops at the first host call that is not the next one of the trace, or has other
+/// arguments.
+pub fn replay(instance: &mut Instance<Runtime, ProgramError>, trace: &Trace) -> Result<Replay> {
+    let imports = start(instance, &trace.entry, &trace.args)?;
+    let mut steps = 0;
+    let mut next = 0;
+    let diverged = |instance: &Instance<Runtime, ProgramError>,
+                    next: usize,
+                    steps: u64,
+                    message: String| Replay {
+        steps,
+        host_calls: next,
+        outcome: trace.outcome.clone(),
+        divergence: Some(Divergence {
+            host_call: next,
+            step: steps,
+            pc: instance.program_counter().map(|pc| pc.0),
+            message,
+        }),
+    };
+    let result = loop {
+        match instance.run()? {
+            InterruptKind::Step => steps += 1,
+            InterruptKind::Ecalli(n) => {
+                let import = import_name(&imports, n);
+                let args = ARG_REGS.map(|reg| instance.reg(reg)).to_vec();
+                let Some(expected) = trace.host_calls.get(next) else {
+                    let message = format!(
+                        "{} after the last host call of the trace",
+                        describe_call(&import, &args)
+                    );
+                    return Ok(diverged(instance, next, steps, message));
+                };
+                if expected.import != import || expected.args != args {
+                    let message = format!(
+                        "expected {}, found {}",
+                        describe_call(&expected.import, &expected.args),
+                        describe_call(&import, &args)
+                    );
+                    return Ok(diverged(instance, next, steps, message));
+                }
+                for write in &expected.writes {
+                    let data = hex::decode(&write.data).map_err(|e| {
+                        MoveToPolkaError::invalid_artifact(format!(
+                            "malformed write of host call {next}: {e}"
+                        ))
+                    })?;
+                    instance.write_memory(write.address, &data)?;
+                }
+                instance.set_reg(Reg::A0, expected.result);
+                next += 1;
+                // the failing host call ended the recorded run
+                if expected.error.is_some() {
+                    break None;
+                }
+            }
+            InterruptKind::Finished => break Some(Ok(())),
+            InterruptKind::NotEnoughGas => break Some(Err(CallError::NotEnoughGas)),
+            InterruptKind::Trap | InterruptKind::Segfault(_) => break Some(Err(CallError::Trap)),
+        }
+    };
+    let outcome = match &result {
+        Some(result) => Outcome::of(result),
+        None => trace.outcome.clone(),
+    };
+    if next < trace.host_calls.len() {
+        let message = format!(
+            "the run ended with {outcome:?} before {}",
+            describe_call(&trace.host_calls[next].import, &trace.host_calls[next].args)
+        );
+        return Ok(diverged(instance, next, steps, message));
+    }
+    if outcome != trace.outcome {
+        let message = format!(
+            "the run ended with {outcome:?} instead of {:?}",
+            trace.outcome
+        );
+        return Ok(diverged(instance, next, steps, message));
+    }
+    Ok(Replay {
+        steps,
+        host_calls: next,
+        outcome,
+        divergence: None,
+    })
+}
+
+/// Prepare `instance` to call the export `entry` with `args` and return the names of the
+/// imports, by index.
+fn start(
+    instance: &mut Instance<Runtime, ProgramError>,
+    entry: &str,
+    args: &[u64],
+) -> Result<Vec<String>> {
+    if args.len() > ARG_REGS.len() {
+        return Err(MoveToPolkaError::Vm(format!(
+            "{} arguments given, at most {} are passed in registers",
+            args.len(),
+            ARG_REGS.len()
+        )));
+    }
+    let module = instance.module().clone();
+    let start = module
+        .exports()
+        .find(|export| export.symbol() == entry)
+        .ok_or_else(|| MoveToPolkaError::Vm(format!("no export `{entry}`")))?
+        .program_counter();
+    instance.set_next_program_counter(start);
+    instance.set_reg(Reg::RA, polkavm::RETURN_TO_HOST);
+    instance.set_reg(Reg::SP, module.default_sp());
+    for (reg, arg) in ARG_REGS.iter().zip(args) {
+        instance.set_reg(*reg, *arg);
+    }
+    Ok(module
+        .imports()
+        .iter()
+        .map(|import| {
+            import
+                .map(|import| String::from_utf8_lossy(import.as_bytes()).into_owned())
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+fn import_name(imports: &[String], index: u32) -> String {
+    imports
+        .get(index as usize)
+        .cloned()
+        .unwrap_or_else(|| format!("<import {index}>"))
+}
+
+/// `import(0x1, 0x2, ...)`.
+fn describe_call(import: &str, args: &[u64]) -> String {
+    let args = args
+        .iter()
+        .map(|arg| format!("{arg:#x}"))
+        .collect::<Vec<_>>();
+    format!("{import}({})", args.join(", "))
+}
+
+/// The guest memory host calls may write: the RW data with the heap, the stack and the aux
+/// data the host allocates in.
+fn memory_regions(module: &Module) -> Vec<Range<u32>> {
+    let memory_map = module.memory_map();
+    [
+        memory_map.rw_data_address()..memory_map.rw_data_address() + memory_map.rw_data_size(),
+        memory_map.stack_address_low()..memory_map.stack_address_high(),
+        memory_map.aux_data_address()..memory_map.aux_data_address() + memory_map.aux_data_size(),
+    ]
+    .into_iter()
+    .filter(|region| !region.is_empty())
+    .collect()
+}
+
+/// The pages of `regions`, by address, `None` where the guest can't access them.
+fn snapshot(
+    instance: &mut Instance<Runtime, ProgramError>,
+    regions: &[Range<u32>],
+) -> Vec<(u32, Option<Vec<u8>>)> {
+    regions
+        .iter()
+        .flat_map(|region| {
+            region
+                .clone()
+                .step_by(PAGE_SIZE as usize)
+                .map(move |page| (region, page))
+        })
+        .map(|(region, page)| {
+            let len = PAGE_SIZE.min(region.end - page);
+            (page, instance.read_memory(page, len).ok())
+        })
+        .collect()
+}
+
+/// The runs of bytes that differ between the snapshots `before` and `after`.
+fn memory_writes(
+    before: &[(u32, Option<Vec<u8>>)],
+    after: &[(u32, Option<Vec<u8>>)],
+) -> Vec<MemoryWrite> {
+    let mut writes = vec![];
+    for ((page, before), (_, after)) in before.iter().zip(after) {
+        let Some(after) = after else {
+            continue;
+        };
+        let before = before.as_deref().unwrap_or_default();
+        let mut offset = 0;
+        while offset < after.len() {
+            if before.get(offset) == Some(&after[offset]) {
+                offset += 1;
+                continue;
+            }
+            let start = offset;
+            while offset < after.len() && before.get(offset) != Some(&after[offset]) {
+                offset += 1;
+            }
+            writes.push(MemoryWrite {
+                address: page + start as u32,
+                data: hex::encode(&after[start..offset]),
+            });
+        }
+    }
+    writes
+}
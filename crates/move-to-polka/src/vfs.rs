@@ -0,0 +1,95 @@
+//! Move sources held in memory, for tools and tests that compile snippets.
+//!
+//! ```no_run
+//! # use move_to_polka::{
+//! #     get_env_from_memory,
+//! #     linker::{create_colored_stdout, BuildOptions},
+//! #     vfs::VirtualFiles,
+//! # };
+//! let files = VirtualFiles::new().file(
+//!     "sources/snippet.move",
+//!     "module 0xa000::snippet { public entry fun run() {} }",
+//! );
+//! let options = BuildOptions::new("output/snippet.polkavm").build();
+//! let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+//! # Ok::<(), move_to_polka::error::MoveToPolkaError>(())
+//! ```
+//!
+//! The files live in memory on the caller's side only: the compiler frontend reads sources
+//! from disk, so they are staged in the `<output>.sources` directory next to the output file,
+//! at their virtual paths, rather than in a temporary directory, so that diagnostics point to
+//! files that are still there. Every compilation replaces the directory, once every path is
+//! known to stay inside it, so callers never create or clean up directories themselves.
+
+use crate::error::{MoveToPolkaError, Result};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// `(path, contents)` pairs of Move source files. Paths are relative, like paths inside a
+/// package root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VirtualFiles {
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl VirtualFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the file at `path`, replacing any file added before at the same path.
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The files, in path order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.files
+            .iter()
+            .map(|(path, contents)| (path.as_path(), contents.as_str()))
+    }
+
+    /// The directory the files are staged in to compile to `output`.
+    pub fn staging_dir(output: &str) -> PathBuf {
+        PathBuf::from(format!("{output}.sources"))
+    }
+
+    /// Write the files below `root`, after removing whatever it held, and return their paths.
+    /// Nothing is removed unless every path is relative and stays below `root`.
+    pub(crate) fn stage(&self, root: &Path) -> Result<Vec<String>> {
+        if self.is_empty() {
+            return Err(MoveToPolkaError::compile("no virtual source files"));
+        }
+        if let Some((path, _)) = self.iter().find(|(path, _)| {
+            !path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        }) {
+            return Err(MoveToPolkaError::compile(format!(
+                "virtual source `{}` must be a relative path without `..`",
+                path.display()
+            )));
+        }
+        if root.exists() {
+            fs::remove_dir_all(root)?;
+        }
+        let mut staged = vec![];
+        for (path, contents) in self.iter() {
+            let staged_path = root.join(path);
+            if let Some(parent) = staged_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&staged_path, contents)?;
+            staged.push(staged_path.to_string_lossy().to_string());
+        }
+        Ok(staged)
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi, AbiFunction, AbiType},
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+    metadata::CODEC,
+    native::AddressLength,
+};
+use polkavm::CallError;
+use polkavm_move_native::host::ProgramError;
+use serde_json::json;
+
+#[test]
+pub fn abi_is_written_next_to_blob() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/abi/storage.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/storage/", HashSet::new())?.emit_abi(true);
+    build_polka_from_move(build_options)?;
+
+    let abi = Abi::read(&abi_path(Path::new(output)))?;
+    assert_eq!(abi.codec, CODEC);
+    let function = abi.function("storage::store_then_borrow")?;
+    assert_eq!(function.selector, "0xfa1e1f30");
+    assert_eq!(function.params, vec![AbiType::Signer]);
+    assert_eq!(function.param_names, vec!["account"]);
+    assert!(function.returns.is_empty());
+
+    // the signer is the origin of the call, only the selector is passed
+    let call_data = abi.encode_call("storage::store_then_borrow", &[])?;
+    assert_eq!(call_data, vec![0xfa, 0x1e, 0x1f, 0x30]);
+    Ok(())
+}
+
+#[test]
+pub fn abi_encodes_and_decodes_bcs() -> anyhow::Result<()> {
+    let abi = Abi {
+        codec: CODEC.to_string(),
+        address_length: 32,
+        functions: vec![AbiFunction {
+            name: "m::f".to_string(),
+            selector: "0x12345678".to_string(),
+            params: vec![
+                AbiType::Signer,
+                AbiType::U64,
+                AbiType::Vector(Box::new(AbiType::U8)),
+                AbiType::U128,
+            ],
+            param_names: vec![],
+            returns: vec![AbiType::Bool, AbiType::Vector(Box::new(AbiType::U16))],
+            attributes: Default::default(),
+        }],
+        resources: vec![],
+    };
+
+    let call_data = abi.encode_call("m::f", &[json!(42), json!("0xcafe"), json!("7")])?;
+    let mut expected = hex::decode("12345678")?;
+    expected.extend(42u64.to_le_bytes());
+    expected.extend([2, 0xca, 0xfe]);
+    expected.extend(7u128.to_le_bytes());
+    assert_eq!(call_data, expected);
+
+    let values = abi.decode_return("m::f", &[1, 2, 1, 0, 2, 0])?;
+    assert_eq!(values, vec![json!(true), json!([1, 2])]);
+
+    let result = abi.encode_call("m::f", &[json!(42)]);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected an argument count error, got {result:?}"
+    );
+    let result = abi.decode_return("m::f", &[1, 2, 1, 0]);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected a truncated return data error, got {result:?}"
+    );
+    Ok(())
+}
+
+/// Build `examples/entry-args` and check that `is_owner`, which asserts on both of its
+/// arguments, sees the addresses it was called with.
+#[test]
+pub fn encoded_calls_are_decoded_by_the_guest() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/abi/entry_args.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/entry-args/", HashSet::new())?.emit_abi(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let abi = Abi::read(&abi_path(Path::new(output)))?;
+    assert_eq!(abi.address_length, AddressLength::Bytes32.bytes());
+    let call = |args: &[serde_json::Value]| -> anyhow::Result<_> {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        runtime.call_data = abi.encode_call("entry_args::is_owner", args)?;
+        Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+    };
+
+    call(&[json!("0xa000"), json!(["0x1", "0xcafe"])])?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let result = call(&[json!("0xa001"), json!(["0x1", "0xcafe"])])?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(6)))),
+        "Expected abort 6, got {result:?}"
+    );
+    let result = call(&[json!("0xa000"), json!(["0x1"])])?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(7)))),
+        "Expected abort 7, got {result:?}"
+    );
+    Ok(())
+}
+
+/// The host of `create_instance` only runs programs with 32 byte addresses, so the calls of a
+/// build with `--address-length 20` are only checked for their encoding.
+#[test]
+pub fn encoded_calls_use_the_address_length_of_the_abi() -> anyhow::Result<()> {
+    let abi = Abi {
+        codec: CODEC.to_string(),
+        address_length: AddressLength::Bytes20.bytes(),
+        functions: vec![AbiFunction {
+            name: "entry_args::is_owner".to_string(),
+            selector: "0x12345678".to_string(),
+            params: vec![
+                AbiType::Address,
+                AbiType::Vector(Box::new(AbiType::Address)),
+            ],
+            param_names: vec![],
+            returns: vec![],
+            attributes: Default::default(),
+        }],
+        resources: vec![],
+    };
+    let call_data = abi.encode_call("entry_args::is_owner", &[json!("0xa000"), json!(["0x1"])])?;
+    let mut expected = hex::decode("12345678")?;
+    expected.extend([0; 18]);
+    expected.extend([0xa0, 0x00, 1]);
+    expected.extend([0; 19]);
+    expected.push(1);
+    assert_eq!(call_data, expected);
+
+    let call_data = abi.encode_call("entry_args::is_owner", &[json!("0xa000"), json!([])])?;
+    // selector, a 20 byte address and the length of the empty vector
+    assert_eq!(call_data.len(), 4 + 20 + 1);
+    let result = abi.encode_call(
+        "entry_args::is_owner",
+        &[json!(format!("0x1{}", "0".repeat(40))), json!([])],
+    );
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected a 21 byte address to be rejected, got {result:?}"
+    );
+    Ok(())
+}
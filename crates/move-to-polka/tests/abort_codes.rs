@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use move_core_types::vm_status::StatusCode;
+use move_to_polka::{
+    abort_codes::{AbortCodes, VECTOR_OUT_OF_BOUNDS},
+    error::MoveToPolkaError,
+};
+use polkavm_move_native::{ALLOC_CODE, PANIC_CODE};
+
+#[test]
+pub fn known_abort_codes_have_names() {
+    let codes = AbortCodes::default();
+    assert_eq!(
+        codes.describe(StatusCode::ARITHMETIC_ERROR as u64),
+        "ARITHMETIC_ERROR (4017)"
+    );
+    assert_eq!(
+        codes.name(VECTOR_OUT_OF_BOUNDS),
+        Some("VECTOR_OUT_OF_BOUNDS")
+    );
+    assert_eq!(codes.name(PANIC_CODE), Some("PANIC_CODE"));
+    assert_eq!(codes.name(ALLOC_CODE), Some("ALLOC_CODE"));
+    assert_eq!(codes.describe(5), "5");
+}
+
+#[test]
+pub fn application_abort_codes_are_read_from_json() -> anyhow::Result<()> {
+    let path = Path::new("output/abort_codes/codes.json");
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(
+        path,
+        r#"{ "E_INSUFFICIENT_BALANCE": 5, "E_NOT_OWNER": "0x10001", "E_PANIC": "57005" }"#,
+    )?;
+    let codes = AbortCodes::read(path)?;
+    assert_eq!(codes.describe(5), "E_INSUFFICIENT_BALANCE (5)");
+    assert_eq!(codes.name(0x10001), Some("E_NOT_OWNER"));
+    // an application name replaces the known one
+    assert_eq!(codes.name(PANIC_CODE), Some("E_PANIC"));
+    assert_eq!(codes.name(ALLOC_CODE), Some("ALLOC_CODE"));
+
+    assert_eq!(codes.parse("E_INSUFFICIENT_BALANCE"), Some(5));
+    assert_eq!(codes.parse("ARITHMETIC_ERROR"), Some(4017));
+    assert_eq!(codes.parse("0x10"), Some(16));
+    assert_eq!(codes.parse("7"), Some(7));
+    assert_eq!(codes.parse("E_UNKNOWN"), None);
+
+    std::fs::write(path, r#"{ "E_NEGATIVE": -1 }"#)?;
+    let result = AbortCodes::read(path);
+    assert!(
+        matches!(&result, Err(MoveToPolkaError::InvalidArtifact(e)) if e.contains("E_NEGATIVE")),
+        "{result:?}"
+    );
+    Ok(())
+}
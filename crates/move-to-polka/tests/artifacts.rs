@@ -0,0 +1,95 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use move_to_polka::{
+    abi::abi_path,
+    artifacts::Artifacts,
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options},
+    metadata::metadata_path,
+    source_map::source_map_path,
+    split::{manifest_path, ModuleBlob, SplitManifest},
+};
+
+#[test]
+pub fn rebuilding_removes_stale_artifacts() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/artifacts/rebuild/storage.polkavm";
+    let build_options = package_build_options(output, "../../examples/storage/", HashSet::new())?
+        .emit_abi(true)
+        .emit_metadata(true)
+        .source_map(true);
+    build_polka_from_move(build_options)?;
+    assert!(abi_path(Path::new(output)).is_file());
+    assert!(metadata_path(Path::new(output)).is_file());
+    assert!(source_map_path(Path::new(output)).is_file());
+
+    let build_options = package_build_options(output, "../../examples/storage/", HashSet::new())?;
+    build_polka_from_move(build_options)?;
+    assert!(Path::new(output).is_file());
+    assert!(!abi_path(Path::new(output)).exists());
+    assert!(!metadata_path(Path::new(output)).exists());
+    assert!(!source_map_path(Path::new(output)).exists());
+    Ok(())
+}
+
+#[test]
+pub fn clean_removes_every_artifact() -> anyhow::Result<()> {
+    initialize_logger();
+    let artifacts = Artifacts::in_dir(Path::new("output/artifacts/clean"), "storage");
+    let output = artifacts.blob_path().to_str().unwrap();
+    let build_options = package_build_options(output, "../../examples/storage/", HashSet::new())?
+        .emit_abi(true)
+        .emit_elf(true)
+        .split_modules(true);
+    build_polka_from_move(build_options)?;
+
+    let written: Vec<_> = artifacts
+        .paths()
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect();
+    // the blob, ABI, ELF, manifest and the blobs of the debug and storage modules
+    assert_eq!(written.len(), 6, "{written:?}");
+    assert_eq!(artifacts.clean()?, written);
+    assert!(written.iter().all(|path| !path.exists()));
+    assert!(artifacts.clean()?.is_empty());
+    Ok(())
+}
+
+#[test]
+pub fn clean_only_removes_module_blobs_of_the_blob() -> anyhow::Result<()> {
+    let dir = Path::new("output/artifacts/manifest");
+    std::fs::create_dir_all(dir.join("inner"))?;
+    let artifacts = Artifacts::in_dir(dir, "storage");
+    let outside = [
+        "../outside.polkavm",
+        "inner/storage.x.polkavm",
+        "other.x.polkavm",
+    ];
+    let module = |module: &str, blob: &str| ModuleBlob {
+        module: module.to_string(),
+        blob: blob.to_string(),
+        exports: vec![],
+        selectors: BTreeMap::new(),
+        imports: vec![],
+    };
+    let mut modules = vec![module("0xa000::storage", "storage.storage.polkavm")];
+    modules.extend(outside.iter().map(|blob| module("0xa000::evil", blob)));
+    SplitManifest { modules }.write(&manifest_path(artifacts.blob_path()))?;
+    for blob in outside.iter().chain(&["storage.storage.polkavm"]) {
+        std::fs::write(dir.join(blob), b"")?;
+    }
+
+    let removed = artifacts.clean()?;
+    assert!(
+        removed.contains(&dir.join("storage.storage.polkavm")),
+        "{removed:?}"
+    );
+    for blob in outside {
+        assert!(dir.join(blob).exists(), "{blob} was removed");
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+    metadata::PackageMetadata,
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{host::ProgramError, NOT_PAYABLE_CODE};
+
+const OUTPUT: &str = "output/attributes/attributes.polkavm";
+
+static COMPILE_ONCE: OnceCell<(Vec<u8>, Abi)> = OnceCell::new();
+
+fn build_once() -> (Vec<u8>, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let build_options =
+                package_build_options(OUTPUT, "../../examples/attributes/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true)
+                    .emit_metadata(true)
+                    .payable_checks(true)
+                    .keep_attributes(vec!["audited".to_string()]);
+            let bytes = build_polka_from_move(build_options).expect("Failed to compile");
+            let abi = Abi::read(&abi_path(Path::new(OUTPUT))).expect("Failed to read the ABI");
+            (bytes, abi)
+        })
+        .clone()
+}
+
+/// Call `function` through the `call` export, transferring `value`.
+fn call(function: &str, value: u128) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (bytes, abi) = build_once();
+    let blob: ProgramBlob = parse_to_blob(&bytes)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = abi.encode_call(function, &[])?;
+    runtime.value_transferred = value;
+    Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+}
+
+#[test]
+pub fn attributes_are_listed_in_the_abi() -> anyhow::Result<()> {
+    let (_, abi) = build_once();
+    let deposit = abi.function("attributes::deposit")?;
+    assert_eq!(deposit.attributes, [("payable".to_string(), None)].into());
+    assert!(abi.function("attributes::withdraw")?.attributes.is_empty());
+
+    let legacy = abi.function("attributes::legacy_withdraw")?;
+    assert_eq!(legacy.selector, "0x11223344");
+    assert_eq!(
+        legacy.attributes,
+        [
+            ("selector".to_string(), Some("0x11223344".to_string())),
+            (
+                "deprecated".to_string(),
+                Some(format!("note = 0x{}", hex::encode("use withdraw")))
+            ),
+        ]
+        .into()
+    );
+    Ok(())
+}
+
+#[test]
+pub fn attributes_are_listed_in_the_metadata() -> anyhow::Result<()> {
+    build_once();
+    let metadata = PackageMetadata::of_blob(Path::new(OUTPUT))?;
+    let attributes = metadata.expect("metadata not written").function_attributes;
+    assert_eq!(
+        attributes["attributes::total"],
+        [(
+            "audited".to_string(),
+            Some(format!("by = 0x{}, round = 2", hex::encode("eiger")))
+        )]
+        .into(),
+        "only kept attributes are listed: {attributes:?}"
+    );
+    assert!(attributes.contains_key("attributes::deposit"));
+    assert!(!attributes.contains_key("attributes::withdraw"));
+    Ok(())
+}
+
+#[test]
+pub fn payable_checks_reject_value_for_other_entry_functions() -> anyhow::Result<()> {
+    call("attributes::deposit", 0)?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call("attributes::deposit", 100)?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call("attributes::withdraw", 0)?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    for function in ["attributes::withdraw", "attributes::legacy_withdraw"] {
+        let result = call(function, 1)?;
+        assert!(
+            matches!(
+                result,
+                Err(CallError::User(ProgramError::Abort(NOT_PAYABLE_CODE)))
+            ),
+            "Expected {function} to abort with NOT_PAYABLE_CODE, got {result:?}"
+        );
+    }
+    Ok(())
+}
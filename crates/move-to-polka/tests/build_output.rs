@@ -0,0 +1,95 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::abi_path,
+    initialize_logger,
+    linker::{build_move_package, package_build_options},
+    source_map::source_map_path,
+    BuildOutput,
+};
+
+#[test]
+pub fn build_output_lists_the_artifacts() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/build-output/warnings.polkavm";
+    let build_options = package_build_options(output, "../../examples/warnings/", HashSet::new())?
+        .emit_abi(true)
+        .source_map(true)
+        .quiet_warnings(true)
+        .keep_temps(true);
+    let BuildOutput {
+        blob_path,
+        object_paths,
+        temp_dir,
+        abi_path: abi,
+        map_path,
+        metadata_path,
+        plan_path,
+        warnings,
+    } = build_move_package(build_options)?;
+    assert_eq!(blob_path.as_deref(), Some(Path::new(output)));
+    assert_eq!(abi, Some(abi_path(Path::new(output))));
+    assert_eq!(map_path, Some(source_map_path(Path::new(output))));
+    // only written with `--emit-metadata` and `--build-plan`
+    assert_eq!(metadata_path, None);
+    assert_eq!(plan_path, None);
+    let temp_dir = temp_dir.expect("temp dir not kept");
+    assert!(!object_paths.is_empty());
+    assert!(object_paths.iter().all(|path| path.starts_with(&temp_dir)));
+    for path in blob_path
+        .iter()
+        .chain(&object_paths)
+        .chain(&abi)
+        .chain(&map_path)
+    {
+        assert!(path.exists(), "{} was not written", path.display());
+    }
+    // held back from the output, but still returned
+    assert!(
+        warnings.iter().any(|w| w.message.contains("unused")),
+        "{warnings:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn check_builds_write_no_artifacts() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/build-output/check.polkavm",
+        "../../examples/void/",
+        HashSet::new(),
+    )?
+    .check(true);
+    let output = build_move_package(build_options)?;
+    assert_eq!(output.blob_path, None);
+    assert!(output.object_paths.is_empty());
+    Ok(())
+}
+
+#[test]
+pub fn builds_of_the_same_output_use_their_own_temp_dir() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/build-output/void.polkavm";
+    let build = |keep_temps| {
+        package_build_options(output, "../../examples/void/", HashSet::new())
+            .and_then(|options| build_move_package(options.keep_temps(keep_temps)))
+    };
+    let (first, second) = std::thread::scope(|scope| {
+        let first = scope.spawn(|| build(true));
+        let second = scope.spawn(|| build(true));
+        (first.join().unwrap(), second.join().unwrap())
+    });
+    let (first, second) = (first?, second?);
+    assert_ne!(first.temp_dir, second.temp_dir);
+    for output in [&first, &second] {
+        assert!(output.object_paths.iter().all(|path| path.exists()));
+    }
+
+    // without --keep-temps the intermediates are removed
+    let output = build(false)?;
+    assert_eq!(output.temp_dir, None);
+    assert!(output.object_paths.is_empty());
+    assert!(output.blob_path.is_some_and(|path| path.exists()));
+    Ok(())
+}
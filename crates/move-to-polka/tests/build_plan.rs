@@ -0,0 +1,107 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::abi_path,
+    artifacts::Artifacts,
+    build_plan::{build_plan_path, BuildPlan},
+    initialize_logger,
+    linker::{build_move_package, package_build_options},
+};
+
+const PACKAGE: &str = "../../examples/storage/";
+
+#[test]
+pub fn the_plan_lists_modules_dependencies_and_artifacts() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/build_plan/full/storage.polkavm";
+    let build_options = package_build_options(output, PACKAGE, HashSet::new())?
+        .build_plan(true)
+        .emit_abi(true);
+    let built = build_move_package(build_options)?;
+    let plan_path = build_plan_path(Path::new(output));
+    assert_eq!(built.plan_path.as_deref(), Some(plan_path.as_path()));
+
+    let plan = BuildPlan::read(&plan_path)?;
+    assert_eq!(
+        plan.named_addresses.get("std").map(String::as_str),
+        Some("0x1")
+    );
+    let storage = plan.module("0xa000::storage").expect("storage planned");
+    assert!(storage.target);
+    assert!(storage.source.ends_with("storage.move"));
+    for dependency in ["0x1::signer", "0x1::vector", "0x1::debug"] {
+        assert!(
+            storage.dependencies.contains(&dependency.to_string()),
+            "{:?}",
+            storage.dependencies
+        );
+    }
+    assert!(!plan.module("0x1::signer").expect("signer planned").target);
+    assert_eq!(
+        plan.artifacts,
+        [
+            Path::new(output).to_path_buf(),
+            abi_path(Path::new(output)),
+            plan_path.clone()
+        ]
+    );
+    // the build removes stale artifacts after the plan is written, and keeps the plan
+    assert!(plan.artifacts.iter().all(|path| path.is_file()));
+
+    let build_options = package_build_options(output, PACKAGE, HashSet::new())?;
+    assert!(build_move_package(build_options)?.plan_path.is_none());
+    assert!(!plan_path.exists());
+    Ok(())
+}
+
+#[test]
+pub fn plan_only_generates_no_code() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/build_plan/plan_only/storage.polkavm";
+    let build_options = package_build_options(output, PACKAGE, HashSet::new())?
+        .plan_only(true)
+        .lto(true);
+    let built = build_move_package(build_options)?;
+    assert!(built.blob_path.is_none());
+    assert!(!Path::new(output).exists());
+
+    let plan = BuildPlan::read(&built.plan_path.expect("plan written"))?;
+    assert!(plan
+        .modules
+        .iter()
+        .all(|module| module.object == "storage.o"));
+    Ok(())
+}
+
+#[test]
+pub fn the_plan_lists_the_module_blobs_the_clean_up_removes() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/build_plan/split/modules.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/entry-modules/", HashSet::new())?
+            .build_plan(true)
+            .split_modules(true);
+    build_move_package(build_options)?;
+    let plan = BuildPlan::read(&build_plan_path(Path::new(output)))?;
+    let module_blobs: Vec<_> = plan
+        .artifacts
+        .iter()
+        .filter_map(|path| path.file_name()?.to_str())
+        .filter(|name| name.starts_with("modules.0xa000_"))
+        .collect();
+    assert_eq!(
+        module_blobs,
+        [
+            "modules.0xa000_first.polkavm",
+            "modules.0xa000_second.polkavm"
+        ]
+    );
+    // what the build writes is what the next build removes
+    assert!(plan.artifacts.iter().all(|path| path.is_file()));
+    let paths = Artifacts::new(output).paths();
+    assert!(
+        plan.artifacts.iter().all(|path| paths.contains(path)),
+        "{paths:?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashSet, path::Path};
+
+use move_binary_format::file_format::{SignatureToken, StructFieldInformation, TypeSignature};
+use move_core_types::vm_status::StatusCode;
+use move_to_polka::{
+    get_env_from_bytecode, get_env_from_source, initialize_logger,
+    linker::{
+        build_polka_from_move, create_colored_stdout, create_instance, package_build_options,
+        parse_to_blob, BuildOptions,
+    },
+};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+
+/// Compile the overflow example to a standalone `.mv` module and return its path. With
+/// `with_source_map`, its `.mvsm` source map is written next to it.
+fn write_overflow_module(dir: &str, with_source_map: bool) -> anyhow::Result<String> {
+    let options = package_build_options(
+        &format!("{dir}/unused.polkavm"),
+        "../../examples/overflow/",
+        HashSet::new(),
+    )?
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let module = env
+        .get_modules()
+        .find(|m| m.get_full_name_str() == "0xa000::overflow")
+        .expect("overflow module not found");
+    let mut bytes = vec![];
+    module
+        .get_verified_module()
+        .expect("overflow module is not verified")
+        .serialize(&mut bytes)?;
+
+    std::fs::create_dir_all(dir)?;
+    let path = format!("{dir}/overflow.mv");
+    std::fs::write(&path, bytes)?;
+    let source_map_path = format!("{dir}/overflow.mvsm");
+    if with_source_map {
+        let source_map = module
+            .get_source_map()
+            .expect("overflow module has no source map");
+        std::fs::write(&source_map_path, bcs::to_bytes(source_map)?)?;
+    } else if Path::new(&source_map_path).exists() {
+        std::fs::remove_file(&source_map_path)?;
+    }
+    Ok(path)
+}
+
+fn build_blob_from_bytecode() -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let module_path = write_overflow_module("output/bytecode", false)?;
+    let build_options =
+        BuildOptions::new("output/bytecode/overflow.polkavm").bytecode(&module_path, false);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+#[test]
+pub fn test_compile_bytecode_module() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(build_blob_from_bytecode()?)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "in_range", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, "add_u8_overflow", ());
+    assert!(
+        matches!(
+            result,
+            Err(CallError::User(ProgramError::Abort(code)))
+                if code == StatusCode::ARITHMETIC_ERROR as u64
+        ),
+        "Expected add_u8_overflow to abort, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_recursive_struct_is_reported() -> anyhow::Result<()> {
+    initialize_logger();
+    let dir = "output/bytecode/recursive";
+    let options = package_build_options(
+        &format!("{dir}/unused.polkavm"),
+        "../../examples/basic/",
+        HashSet::new(),
+    )?
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let mut module = env
+        .get_modules()
+        .find(|m| m.get_full_name_str() == "0x1234::structbasic")
+        .expect("structbasic module not found")
+        .get_verified_module()
+        .expect("structbasic module is not verified")
+        .clone();
+    // `Counter { value: u64 }` becomes `Counter { value: Counter }`, which the verifier
+    // would reject
+    let counter = &mut module.struct_defs[0];
+    let StructFieldInformation::Declared(fields) = &mut counter.field_information else {
+        panic!("Counter has no declared fields");
+    };
+    fields[0].signature = TypeSignature(SignatureToken::Struct(counter.struct_handle));
+    let mut bytes = vec![];
+    module.serialize(&mut bytes)?;
+    std::fs::create_dir_all(dir)?;
+    let module_path = format!("{dir}/structbasic.mv");
+    std::fs::write(&module_path, bytes)?;
+
+    let build_options =
+        BuildOptions::new(&format!("{dir}/structbasic.polkavm")).bytecode(&module_path, false);
+    let error = build_polka_from_move(build_options)
+        .expect_err("recursive struct compiled")
+        .to_string();
+    assert!(
+        error.contains("recursive struct type: structbasic::Counter -> structbasic::Counter"),
+        "{error}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_bytecode_source_map_is_loaded() -> anyhow::Result<()> {
+    initialize_logger();
+    // the span of `add_u8_overflow` in overflow.move, or nothing without a source map
+    let function_span = |dir: &str, with_source_map: bool| -> anyhow::Result<_> {
+        let module_path = write_overflow_module(dir, with_source_map)?;
+        let options = BuildOptions::new(&format!("{dir}/overflow.polkavm"))
+            .bytecode(&module_path, false)
+            .build();
+        let env = get_env_from_bytecode(&options)?;
+        let module = env
+            .get_modules()
+            .find(|m| m.get_full_name_str() == "0xa000::overflow")
+            .expect("overflow module not found");
+        let function = module
+            .get_functions()
+            .find(|f| f.get_name_str() == "add_u8_overflow")
+            .expect("add_u8_overflow not found");
+        let span = function.get_loc().span();
+        Ok(span.end().0 - span.start().0)
+    };
+    assert!(function_span("output/bytecode/source_map", true)? > 0);
+    assert_eq!(function_span("output/bytecode/no_source_map", false)?, 0);
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    cfg::{view_cfgs, CfgOutput, FunctionCfg},
+    compile_to_cfgs, get_env_from_source, initialize_logger,
+    linker::{build_polka_from_move, create_colored_stdout, package_build_options},
+};
+
+#[test]
+pub fn test_compile_to_cfgs() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/cfg/overflow.polkavm",
+        "../../examples/overflow/",
+        HashSet::new(),
+    )?
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let cfgs = compile_to_cfgs(&env, &options)?;
+
+    let cfg = cfgs
+        .iter()
+        .find(|cfg| cfg.function == "0xa000::overflow::add_u8")
+        .expect("no graph of add_u8");
+    assert!(!cfg.entry_blocks.is_empty());
+    assert!(cfg.blocks.iter().any(|b| !b.instructions.is_empty()));
+    for block in &cfg.blocks {
+        for successor in &block.successors {
+            assert!(
+                cfg.blocks.iter().any(|b| b.id == *successor),
+                "successor {successor} of block {} is not a block",
+                block.id
+            );
+        }
+    }
+    assert!(cfg.dot.contains("Function: 0xa000::overflow::add_u8"));
+    Ok(())
+}
+
+#[test]
+pub fn test_write_json_cfgs_per_function() -> anyhow::Result<()> {
+    initialize_logger();
+    let out_dir = "output/cfg/json";
+    let _ = std::fs::remove_dir_all(out_dir);
+    let options = package_build_options(
+        "output/cfg/overflow_json.polkavm",
+        "../../examples/overflow/",
+        HashSet::new(),
+    )?
+    .gen_dot_cfg(Some(CfgOutput::Json))
+    .dot_out_dir(out_dir)
+    .cfg_dir_per_function(true);
+    build_polka_from_move(options)?;
+
+    let json = std::fs::read(format!("{out_dir}/0xa000__overflow__add_u8/cfg.json"))?;
+    let cfg: FunctionCfg = serde_json::from_slice(&json)?;
+    assert_eq!(cfg.function, "0xa000::overflow::add_u8");
+    assert!(!cfg.blocks.is_empty());
+    // the dot graph is only written by `write` and `view`
+    assert!(cfg.dot.is_empty());
+    Ok(())
+}
+
+#[test]
+pub fn test_missing_dot_viewer_does_not_fail_the_build() -> anyhow::Result<()> {
+    initialize_logger();
+    let out_dir = "output/cfg/view";
+    let _ = std::fs::remove_dir_all(out_dir);
+    let options = package_build_options(
+        "output/cfg/overflow_view.polkavm",
+        "../../examples/overflow/",
+        HashSet::new(),
+    )?
+    .gen_dot_cfg(Some(CfgOutput::View))
+    .dot_viewer("move-polka-no-such-viewer")
+    .dot_out_dir(out_dir);
+    build_polka_from_move(options)?;
+
+    let dot = Path::new(out_dir).join("0xa000__overflow__add_u8_cfg.dot");
+    assert!(dot.is_file());
+    assert_eq!(view_cfgs(&[dot.clone()], "move-polka-no-such-viewer"), 0);
+    // the viewer gets its arguments, then the path
+    assert_eq!(view_cfgs(&[dot], "true --ignored"), 1);
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_polka_from_move, check_package, package_build_options},
+    native::AddressLength,
+};
+
+#[test]
+pub fn check_supported_package() -> anyhow::Result<()> {
+    initialize_logger();
+    check_package("../../examples/basic/", HashSet::new())?;
+
+    Ok(())
+}
+
+#[test]
+pub fn check_rejects_non_package() {
+    initialize_logger();
+    let result = check_package("../../examples/call-local.move", HashSet::new());
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Compile(_))),
+        "Expected a compile error, got {result:?}"
+    );
+}
+
+#[test]
+pub fn check_reports_all_unsupported_constructs_with_locations() {
+    initialize_logger();
+    let result = check_package("../../examples/unsupported/", HashSet::new());
+    let Err(MoveToPolkaError::Unsupported(issues)) = result else {
+        panic!("Expected unsupported constructs, got {result:?}");
+    };
+    // both enum operations are reported, not just the first one
+    assert!(issues.len() >= 2, "{issues:#?}");
+    assert!(
+        issues.iter().all(|issue| issue.contains("shape.move:")),
+        "{issues:#?}"
+    );
+    assert!(
+        issues
+            .iter()
+            .any(|issue| issue.contains("0xa000::shape::circle")),
+        "{issues:#?}"
+    );
+    assert!(
+        issues
+            .iter()
+            .any(|issue| issue.contains("0xa000::shape::is_circle")),
+        "{issues:#?}"
+    );
+}
+
+#[test]
+pub fn check_reports_unsupported_operations_at_their_line() {
+    initialize_logger();
+    let result = check_package("../../examples/unsupported/", HashSet::new());
+    let Err(MoveToPolkaError::Unsupported(issues)) = result else {
+        panic!("Expected unsupported operations, got {result:?}");
+    };
+    // `Shape::Circle { radius }` on line 8 and `shape is Circle` on line 12 of shape.move
+    for (line, function, operation) in [
+        (8, "shape::circle", "PackVariant"),
+        (12, "shape::is_circle", "TestVariant"),
+    ] {
+        assert!(
+            issues.iter().any(|issue| {
+                issue.contains(&format!("shape.move:{line}:"))
+                    && issue.contains(&format!("{function}: unsupported operation {operation}"))
+            }),
+            "no {operation} reported on line {line}: {issues:#?}"
+        );
+    }
+}
+
+#[test]
+pub fn check_rejects_addresses_longer_than_target() -> anyhow::Result<()> {
+    initialize_logger();
+    check_package("../../examples/addresses/", HashSet::new())?;
+
+    let build_options = package_build_options(
+        "output/check/addresses.polkavm",
+        "../../examples/addresses/",
+        HashSet::new(),
+    )?
+    .check(true)
+    .address_length(AddressLength::Bytes20);
+    let result = build_polka_from_move(build_options);
+    let Err(MoveToPolkaError::Unsupported(issues)) = result else {
+        panic!("Expected the 32 byte address to be rejected, got {result:?}");
+    };
+    assert!(
+        issues
+            .iter()
+            .any(|issue| issue.contains("0xa000::addresses::owner")
+                && issue.contains("longer than 20 bytes")),
+        "{issues:#?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn spec_only_types_are_found_where_code_would_lay_them_out() {
+    use move_model::ty::{PrimitiveType, ReferenceKind, Type};
+    use move_polka_codegen::stackless::spec_only_type;
+
+    let num = Type::Primitive(PrimitiveType::Num);
+    let nested = Type::Reference(
+        ReferenceKind::Immutable,
+        Box::new(Type::Vector(Box::new(num.clone()))),
+    );
+    assert_eq!(spec_only_type(&nested), Some(&num));
+    let range = Type::Primitive(PrimitiveType::Range);
+    let tuple = Type::Tuple(vec![Type::Primitive(PrimitiveType::U64), range.clone()]);
+    assert_eq!(spec_only_type(&tuple), Some(&range));
+    let supported = Type::Vector(Box::new(Type::Primitive(PrimitiveType::Address)));
+    assert_eq!(spec_only_type(&supported), None);
+}
+
+#[test]
+pub fn check_types_accepts_specifications_using_num() -> anyhow::Result<()> {
+    use move_polka_codegen::stackless::check_types;
+    use move_to_polka::{
+        get_env_from_memory,
+        linker::{create_colored_stdout, BuildOptions},
+        vfs::VirtualFiles,
+    };
+
+    initialize_logger();
+    let options = BuildOptions::new("output/check/spec_num.polkavm").build();
+    let files = VirtualFiles::new().file(
+        "sources/spec_num.move",
+        "module 0xa000::spec_num {
+            struct Supply has key { total: u64 }
+            public fun double(x: u64): u64 { x * 2 }
+            spec double {
+                aborts_if x * 2 > MAX_U64;
+                ensures result == x * 2;
+            }
+        }",
+    );
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    assert_eq!(check_types(&env, None), Vec::<String>::new());
+    Ok(())
+}
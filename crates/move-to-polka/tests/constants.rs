@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_move_package, create_instance, elf_path, package_build_options, parse_to_blob,
+        read_constants,
+    },
+};
+
+#[test]
+pub fn test_constants_are_loaded() -> anyhow::Result<()> {
+    initialize_logger();
+    // the functions are public, not entry, so they are only exported with --export-public
+    let options = package_build_options(
+        "output/constants/constants.polkavm",
+        "../../examples/constants/",
+        HashSet::new(),
+    )?
+    .export_public(true)
+    .emit_elf(true);
+    let blob_path = build_move_package(options)?
+        .blob_path
+        .expect("no blob linked");
+    let blob = parse_to_blob(&std::fs::read(&blob_path)?)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    for (function, expected) in [
+        ("sum_primes", 28),
+        ("count_primes", 5),
+        ("greeting_len", 10),
+        ("count_primes_generic", 5),
+    ] {
+        let result = instance
+            .call_typed_and_get_result::<u64, ()>(&mut runtime, function, ())
+            .map_err(|e| anyhow::anyhow!("{function}: {e:?}"))?;
+        assert_eq!(result, expected, "{function}");
+    }
+
+    // `inspect --constants` lists them from the --emit-elf object
+    let constants = read_constants(&std::fs::read(elf_path(&blob_path))?)?;
+    for name in [
+        "constants::PRIMES",
+        "constants::GREETING",
+        "primes::FIRST_PRIMES",
+    ] {
+        assert!(
+            constants.iter().any(|c| c == name),
+            "{name} not in {constants:?}"
+        );
+    }
+    Ok(())
+}
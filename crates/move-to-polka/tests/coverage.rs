@@ -0,0 +1,57 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    coverage::{coverage_map_path, CoverageMap},
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_instance, package_build_options, parse_to_blob,
+        read_coverage_counters,
+    },
+};
+
+const OUTPUT: &str = "output/coverage/overflow.polkavm";
+
+fn counts_of(map: &CoverageMap, counts: &[u32], function: &str) -> Vec<u32> {
+    map.counters
+        .iter()
+        .zip(counts)
+        .filter(|(counter, _)| counter.function == function)
+        .map(|(_, count)| *count)
+        .collect()
+}
+
+#[test]
+pub fn test_coverage_counters() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options =
+        package_build_options(OUTPUT, "../../examples/overflow/", HashSet::new())?.coverage(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let map = CoverageMap::read(&coverage_map_path(Path::new(OUTPUT)))?;
+    assert!(!map.is_empty());
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    assert_eq!(read_coverage_counters(&mut instance, &runtime)?, None);
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "in_range", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let counts = read_coverage_counters(&mut instance, &runtime)?.expect("counters registered");
+    assert_eq!(counts.len(), map.len());
+
+    let in_range = counts_of(&map, &counts, "0xa000::overflow::in_range");
+    // blocks that abort on a failed assert never run
+    assert!(in_range.iter().any(|count| *count > 0));
+    assert!(in_range.iter().any(|count| *count == 0));
+    let add_u8 = counts_of(&map, &counts, "0xa000::overflow::add_u8");
+    assert!(add_u8.iter().any(|count| *count > 0));
+    let wrapping = counts_of(&map, &counts, "0xa000::overflow::wrapping");
+    assert!(!wrapping.is_empty() && wrapping.iter().all(|count| *count == 0));
+
+    let report = map.report(&counts)?;
+    let (total, covered) = report.summary();
+    assert!(covered > 0 && covered < total);
+    assert!(report
+        .files
+        .keys()
+        .any(|file| file.ends_with("overflow.move")));
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_instance, package_build_options, parse_to_blob, BlobContract,
+    },
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{
+    host::{Contract, ProgramError},
+    CALL_FAILURE_CODE,
+};
+use serde_json::json;
+
+static COMPILE_ONCE: OnceCell<(ProgramBlob, Abi)> = OnceCell::new();
+
+// H160 accounts of the called contracts
+const CALLEE: [u8; 20] = hex_literal::hex!("ca11eeca11eeca11eeca11eeca11eeca11eeca11");
+const ECHO: [u8; 20] = hex_literal::hex!("ec40ec40ec40ec40ec40ec40ec40ec40ec40ec40");
+
+fn create_blob_once() -> (ProgramBlob, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/cross-call/cross_call.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/cross-call/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true);
+            let blob =
+                parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                    .expect("Failed to parse the blob");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (blob, abi)
+        })
+        .clone()
+}
+
+/// The Move address of the H160 `account`.
+fn address(account: [u8; 20]) -> String {
+    format!("0x{}{}", hex::encode(account), "00".repeat(12))
+}
+
+/// Call `forward(callee, input, expected)` of a contract that can call another instance of
+/// itself at `CALLEE` and a contract reversing its input at `ECHO`.
+fn forward(
+    callee: [u8; 20],
+    input: &[u8],
+    expected: &[u8],
+) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (blob, abi) = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob.clone())?;
+    runtime
+        .contracts
+        .insert(CALLEE, Box::new(BlobContract::new(blob)));
+    runtime.contracts.insert(
+        ECHO,
+        Box::new(|input: &[u8], _value: u128| -> Result<Vec<u8>, Vec<u8>> {
+            Ok(input.iter().rev().copied().collect())
+        }),
+    );
+    runtime.call_data = abi.encode_call(
+        "cross_call::forward",
+        &[
+            json!(address(callee)),
+            json!(format!("0x{}", hex::encode(input))),
+            json!(format!("0x{}", hex::encode(expected))),
+        ],
+    )?;
+    Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+}
+
+#[test]
+pub fn call_another_move_contract() -> anyhow::Result<()> {
+    let (_, abi) = create_blob_once();
+    let input = abi.encode_call("cross_call::check", &[json!(42)])?;
+    forward(CALLEE, &input, &[])?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn reverted_call_aborts() -> anyhow::Result<()> {
+    let (_, abi) = create_blob_once();
+    let input = abi.encode_call("cross_call::check", &[json!(7)])?;
+    let result = forward(CALLEE, &input, &[])?;
+    assert!(
+        matches!(
+            result,
+            Err(CallError::User(ProgramError::Abort(CALL_FAILURE_CODE)))
+        ),
+        "Expected the call to abort with CALL_FAILURE_CODE, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn output_is_returned() -> anyhow::Result<()> {
+    forward(ECHO, &[1, 2, 3], &[3, 2, 1])?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let result = forward(ECHO, &[1, 2, 3], &[1, 2, 3])?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(1)))),
+        "Expected a mismatching output to abort with 1, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn reverted_call_discards_its_writes() -> anyhow::Result<()> {
+    let (blob, abi) = create_blob_once();
+    let mut contract = BlobContract::new(blob);
+    contract
+        .call(&abi.encode_call("cross_call::bump", &[json!(false)])?, 0)
+        .map_err(|e| anyhow::anyhow!("bump reverted with {e:?}"))?;
+    let reverted = contract.call(&abi.encode_call("cross_call::bump", &[json!(true)])?, 0);
+    assert_eq!(reverted, Err(3u64.to_le_bytes().to_vec()));
+    // the counter is still 1, the increment of the reverted call is gone
+    contract
+        .call(&abi.encode_call("cross_call::count_is", &[json!(1)])?, 0)
+        .map_err(|e| anyhow::anyhow!("count_is reverted with {e:?}"))?;
+    Ok(())
+}
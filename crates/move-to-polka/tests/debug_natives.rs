@@ -0,0 +1,131 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    io::Write,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_instance, create_instance_with_print_sink,
+        package_build_options, parse_to_blob,
+    },
+};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::{PrintedValue, ProgramError};
+
+fn build_blob(output: &str, strip: bool) -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let build_options =
+        package_build_options(output, "../../examples/debug-natives/", HashSet::new())?
+            .strip_debug_natives(strip);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+#[test]
+pub fn debug_natives_log_and_assert() -> anyhow::Result<()> {
+    let blob = build_blob("output/debug-natives/debug.polkavm", false)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "log_and_assert", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, "failing_assert", ());
+    if let CallError::User(ProgramError::Abort(code)) = result.err().unwrap() {
+        assert_eq!(code, 42, "Expected an abort with code 42");
+    } else {
+        panic!("Expected a ProgramError::Abort(42)");
+    }
+
+    Ok(())
+}
+
+/// Collects what a `tracing` subscriber writes.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn logged_messages_end_at_the_vector_length() -> anyhow::Result<()> {
+    let blob = build_blob("output/debug-natives-log/debug.polkavm", false)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_max_level(tracing::Level::INFO)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        instance
+            .call_typed_and_get_result::<u32, ()>(&mut runtime, "log_and_assert", ())
+            .map_err(|e| anyhow::anyhow!("{e:?}"))
+    })?;
+    let output = String::from_utf8_lossy(&captured.0.lock().unwrap()).into_owned();
+    let messages = output
+        .lines()
+        .filter_map(|line| line.split_once("guest: ").map(|(_, message)| message))
+        .collect::<Vec<_>>();
+    // the second message is built with `push_back`, so its capacity exceeds its length
+    assert_eq!(
+        messages,
+        ["checking value", "pushed byte by byte"],
+        "{output}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn stripped_debug_natives_are_no_ops() -> anyhow::Result<()> {
+    let blob = build_blob("output/debug-natives-stripped/debug.polkavm", true)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "failing_assert", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn prints_reach_the_print_sink() -> anyhow::Result<()> {
+    for (output, strip, expected) in [
+        (
+            "output/debug-natives-sink/debug.polkavm",
+            false,
+            vec!["42", "value checked"],
+        ),
+        (
+            "output/debug-natives-sink-stripped/debug.polkavm",
+            true,
+            vec![],
+        ),
+    ] {
+        let printed: Rc<RefCell<Vec<PrintedValue>>> = Rc::default();
+        let sink = printed.clone();
+        let (mut instance, mut runtime) =
+            create_instance_with_print_sink(build_blob(output, strip)?, move |value| {
+                sink.borrow_mut().push(value)
+            })?;
+        instance
+            .call_typed_and_get_result::<u32, ()>(&mut runtime, "log_and_assert", ())
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let values = printed
+            .borrow()
+            .iter()
+            .map(|printed| printed.value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(values, expected);
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    defines::{config_module, defines_path, Define},
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{
+        build_move_package, build_polka_from_move, create_instance, package_build_options,
+        parse_to_blob,
+    },
+    metadata::PackageMetadata,
+};
+
+const PACKAGE: &str = "../../examples/defines/";
+
+fn fee(output: &str, defines: &[&str]) -> anyhow::Result<u64> {
+    let mut build_options =
+        package_build_options(output, PACKAGE, HashSet::new())?.emit_metadata(true);
+    for define in defines {
+        build_options = build_options.define(define);
+    }
+    let bytes = build_polka_from_move(build_options)?;
+
+    let metadata = PackageMetadata::of_blob(Path::new(output))?;
+    let recorded = metadata.expect("metadata written").defines;
+    assert_eq!(recorded.len(), defines.len(), "{recorded:?}");
+    assert!(recorded.contains_key("fee"));
+    assert!(recorded.contains_key("mainnet"));
+
+    let (mut instance, mut runtime) = create_instance(parse_to_blob(&bytes)?)?;
+    instance
+        .call_typed_and_get_result::<u64, ()>(&mut runtime, "fee", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))
+}
+
+#[test]
+pub fn one_source_tree_builds_for_several_chains() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/defines/mainnet.polkavm";
+    assert_eq!(fee(output, &["mainnet", "fee=25"])?, 25);
+    assert!(defines_path(Path::new(output)).is_file());
+    assert_eq!(
+        fee(
+            "output/defines/testnet.polkavm",
+            &["mainnet=false", "fee=25"]
+        )?,
+        0
+    );
+    Ok(())
+}
+
+#[test]
+pub fn define_values_are_typed() -> anyhow::Result<()> {
+    let parsed = [
+        "mainnet",
+        "supply=1_000u128",
+        "treasury=@0xcafe",
+        "name=\"polka\"",
+    ]
+    .map(Define::parse)
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        config_module(&parsed),
+        "// Generated from the --define flags of the build.\n\
+         module defines::config {\n    \
+         public fun mainnet(): bool { true }\n    \
+         public fun supply(): u128 { 1_000u128 }\n    \
+         public fun treasury(): address { @0xcafe }\n    \
+         public fun name(): vector<u8> { b\"polka\" }\n\
+         }\n"
+    );
+    for invalid in ["1fee=1", "fee=-1", "fee=1u7", "fee=x", "name=\"a\\\"b\""] {
+        assert!(Define::parse(invalid).is_err(), "{invalid}");
+    }
+    Ok(())
+}
+
+#[test]
+pub fn a_define_is_given_once() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options =
+        package_build_options("output/defines/twice.polkavm", PACKAGE, HashSet::new())?
+            .define("mainnet")
+            .define("fee=25")
+            .define("fee=30");
+    let result = build_polka_from_move(build_options);
+    assert!(
+        matches!(&result, Err(MoveToPolkaError::Compile(e)) if e.contains("`fee` is given more than once")),
+        "{result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn a_check_writes_no_defines_module() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/defines/check.polkavm";
+    let build_options = package_build_options(output, PACKAGE, HashSet::new())?
+        .define("mainnet")
+        .define("fee=25")
+        .check(true);
+    build_move_package(build_options)?;
+    assert!(!defines_path(Path::new(output)).exists());
+    Ok(())
+}
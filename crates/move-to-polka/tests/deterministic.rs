@@ -0,0 +1,64 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options},
+};
+
+/// Build basic-coin into `dir`, with every artifact a build can write.
+fn build_basic_coin(dir: &str) -> anyhow::Result<()> {
+    let _ = std::fs::remove_dir_all(dir);
+    let build_options = package_build_options(
+        &format!("{dir}/basic_coin.polkavm"),
+        "../../examples/basic-coin/",
+        HashSet::new(),
+    )?
+    .emit_metadata(true)
+    .emit_abi(true)
+    .emit_elf(true)
+    .source_map(true);
+    build_polka_from_move(build_options)?;
+    Ok(())
+}
+
+/// The contents of every file below `dir`, by path relative to it.
+fn read_tree(dir: &Path) -> anyhow::Result<BTreeMap<PathBuf, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        for entry in std::fs::read_dir(current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.insert(path.strip_prefix(dir)?.to_path_buf(), std::fs::read(&path)?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[test]
+pub fn test_builds_are_byte_identical() -> anyhow::Result<()> {
+    initialize_logger();
+    build_basic_coin("output/deterministic/first")?;
+    build_basic_coin("output/deterministic/second")?;
+    let first = read_tree(Path::new("output/deterministic/first"))?;
+    let second = read_tree(Path::new("output/deterministic/second"))?;
+    assert!(first.len() > 1, "{:?}", first.keys());
+    assert_eq!(
+        first.keys().collect::<Vec<_>>(),
+        second.keys().collect::<Vec<_>>()
+    );
+    for (path, bytes) in &first {
+        assert!(
+            *bytes == second[path],
+            "{} differs between two builds of the same package",
+            path.display()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{host::ProgramError, MISSING_ABILITY_CODE};
+use serde_json::json;
+
+static COMPILE_ONCE: OnceCell<(ProgramBlob, Abi)> = OnceCell::new();
+
+fn create_blob_once() -> (ProgramBlob, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/dispatch/dispatch.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/dispatch/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true);
+            let blob =
+                parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                    .expect("Failed to parse the blob");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (blob, abi)
+        })
+        .clone()
+}
+
+fn call(call_data: Vec<u8>) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (blob, _) = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = call_data;
+    Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+}
+
+fn call_entry(index: u64, n: u64) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (_, abi) = create_blob_once();
+    call(abi.encode_call(&format!("dispatch::entry_{index:02}"), &[json!(n)])?)
+}
+
+#[test]
+pub fn every_entry_function_is_dispatched() -> anyhow::Result<()> {
+    for index in 0..80 {
+        call_entry(index, index)?.map_err(|e| anyhow::anyhow!("entry_{index:02} failed: {e:?}"))?;
+        let result = call_entry(index, index + 1)?;
+        assert!(
+            matches!(result, Err(CallError::User(ProgramError::Abort(c))) if c == 100 + index),
+            "entry_{index:02} should abort with {}, got {result:?}",
+            100 + index
+        );
+    }
+    Ok(())
+}
+
+#[test]
+pub fn unknown_selector_aborts() -> anyhow::Result<()> {
+    let (_, abi) = create_blob_once();
+    let mut call_data = abi.encode_call("dispatch::entry_00", &[json!(0)])?;
+    call_data[0] ^= 0xff;
+    let result = call(call_data)?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(2)))),
+        "Expected abort 2, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn abort_with_a_runtime_failure_code_is_a_move_abort() -> anyhow::Result<()> {
+    // `entry_71` aborts with 171, the code of the runtime's missing ability failure, which
+    // only a revert tagged as a runtime failure is reported as
+    assert_eq!(MISSING_ABILITY_CODE, 171);
+    let result = call_entry(71, 0)?;
+    assert!(
+        matches!(
+            result,
+            Err(CallError::User(ProgramError::Abort(MISSING_ABILITY_CODE)))
+        ),
+        "Expected abort 171, got {result:?}"
+    );
+    Ok(())
+}
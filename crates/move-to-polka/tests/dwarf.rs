@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{create_colored_stdout, package_build_options},
+};
+
+#[test]
+pub fn test_locals_are_named_and_scoped() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/dwarf-scopes/scopes.polkavm",
+        "../../examples/dwarf-scopes/",
+        HashSet::new(),
+    )?
+    .source_map(true)
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let modules = compile_to_ir_strings(&env, &options)?;
+    let (_, ir) = modules
+        .iter()
+        .find(|(name, _)| name.contains("scopes"))
+        .expect("scopes module not translated");
+
+    let variable = |name: &str| {
+        ir.lines()
+            .find(|line| line.contains(&format!("DILocalVariable(name: \"{name}\"")))
+            .unwrap_or_else(|| panic!("no variable {name} in:\n{ir}"))
+    };
+    assert!(variable("n").contains("arg: 1"));
+    // declared in the function body, the loop and the branch arm
+    for name in ["total", "square", "even_square"] {
+        let scope = variable(name)
+            .split("scope: ")
+            .nth(1)
+            .and_then(|rest| rest.split([',', ')']).next())
+            .expect("variable without a scope");
+        let is_block = ir
+            .lines()
+            .any(|line| line.starts_with(&format!("{scope} ")) && line.contains("DILexicalBlock("));
+        assert_eq!(is_block, name != "total", "scope of {name} in:\n{ir}");
+    }
+    assert!(
+        !ir.contains("DILocalVariable(name: \"$t"),
+        "temporaries declared in:\n{ir}"
+    );
+    Ok(())
+}
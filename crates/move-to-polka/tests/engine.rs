@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    engine::CompilerEngine,
+    get_env_from_source, initialize_logger,
+    linker::{create_colored_stdout, package_build_options, parse_to_blob},
+};
+
+const PACKAGE: &str = "../../examples/storage/";
+
+#[test]
+pub fn target_machines_are_reused_across_compilations() -> anyhow::Result<()> {
+    initialize_logger();
+    let engine = CompilerEngine::new();
+    for run in 0..2 {
+        let options = package_build_options(
+            &format!("output/engine/run{run}.polkavm"),
+            PACKAGE,
+            HashSet::new(),
+        )?
+        .build();
+        let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+        let output = engine.compile(&env, &options)?;
+        parse_to_blob(&std::fs::read(output.blob_path.expect("blob linked"))?)?;
+        assert_eq!(engine.idle_count(), 1);
+    }
+
+    let mut options =
+        package_build_options("output/engine/opt.polkavm", PACKAGE, HashSet::new())?.build();
+    options.opt_level = "default".to_string();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    engine.compile_to_ir_strings(&env, &options)?;
+    assert_eq!(engine.idle_count(), 2);
+    Ok(())
+}
+
+#[test]
+pub fn an_engine_compiles_on_several_threads() -> anyhow::Result<()> {
+    initialize_logger();
+    let engine = CompilerEngine::new();
+    std::thread::scope(|scope| {
+        let threads = (0..3)
+            .map(|thread| {
+                let engine = &engine;
+                scope.spawn(move || -> anyhow::Result<usize> {
+                    let output = format!("output/engine/thread{thread}.polkavm");
+                    let options = package_build_options(&output, PACKAGE, HashSet::new())?.build();
+                    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+                    Ok(engine.compile_to_ir_strings(&env, &options)?.len())
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            let modules = thread.join().expect("compilation panicked")?;
+            assert!(modules > 0);
+        }
+        anyhow::Ok(())
+    })?;
+    let idle = engine.idle_count();
+    assert!((1..=3).contains(&idle), "{idle} idle machines");
+    Ok(())
+}
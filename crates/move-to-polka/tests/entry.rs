@@ -1,8 +1,11 @@
 use std::collections::HashSet;
 
 use move_to_polka::{
-    initialize_logger,
-    linker::{create_blob, create_instance},
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{
+        build_polka_from_move, create_blob, create_colored_stdout, create_instance,
+        package_build_options, parse_to_blob,
+    },
 };
 use once_cell::sync::OnceCell;
 use polkavm::ProgramBlob;
@@ -33,3 +36,51 @@ pub fn entry() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn entry_with_pruned_unreachable_functions() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/entry-pruned/entry.polkavm",
+        "../../examples/entry/",
+        HashSet::new(),
+    )?
+    .prune_unreachable(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "main", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn unreachable_functions_are_not_translated() -> anyhow::Result<()> {
+    initialize_logger();
+    for prune_unreachable in [false, true] {
+        let options = package_build_options(
+            "output/entry-pruned-ir/entry.polkavm",
+            "../../examples/entry/",
+            HashSet::new(),
+        )?
+        .prune_unreachable(prune_unreachable)
+        .build();
+        let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+        let modules = compile_to_ir_strings(&env, &options)?;
+        let defines = |function: &str| {
+            modules.iter().any(|(_, ir)| {
+                ir.lines()
+                    .any(|line| line.starts_with("define") && line.contains(function))
+            })
+        };
+        // `main` reaches `token::new`, nothing reaches `token::never_called`
+        assert!(defines("new"));
+        assert_eq!(
+            defines("never_called"),
+            !prune_unreachable,
+            "never_called with prune_unreachable {prune_unreachable}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{host::ProgramError, BCS_DESERIALIZATION_FAILURE, MALFORMED_INPUT_CODE};
+use serde_json::{json, Value};
+
+static COMPILE_ONCE: OnceCell<(ProgramBlob, Abi)> = OnceCell::new();
+
+fn create_blob_once() -> (ProgramBlob, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/entry_args/entry_args.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/entry-args/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true);
+            let blob =
+                parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                    .expect("Failed to parse the blob");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (blob, abi)
+        })
+        .clone()
+}
+
+/// Call the blob through its `call` export with `call_data`, like pallet-revive does.
+fn call(call_data: Vec<u8>) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (blob, _) = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = call_data;
+    Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+}
+
+fn call_with(
+    function: &str,
+    args: &[Value],
+) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (_, abi) = create_blob_once();
+    call(abi.encode_call(function, args)?)
+}
+
+fn assert_aborts(result: Result<(), CallError<ProgramError>>, code: u64) {
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(c))) if c == code),
+        "Expected abort {code}, got {result:?}"
+    );
+}
+
+#[test]
+pub fn entry_takes_vector_u8() -> anyhow::Result<()> {
+    call_with("entry_args::sum_bytes", &[json!([1, 2, 250]), json!(253)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call_with("entry_args::sum_bytes", &[json!("0x0102"), json!(3)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call_with("entry_args::sum_bytes", &[json!([]), json!(0)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_aborts(
+        call_with("entry_args::sum_bytes", &[json!([1]), json!(2)])?,
+        1,
+    );
+    Ok(())
+}
+
+#[test]
+pub fn entry_takes_signer_and_vector_u64() -> anyhow::Result<()> {
+    let numbers = json!([1, u64::MAX - 1]);
+    call_with("entry_args::sum_numbers", &[numbers, json!(u64::MAX)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_aborts(
+        call_with("entry_args::sum_numbers", &[json!([1, 2]), json!(4)])?,
+        2,
+    );
+    Ok(())
+}
+
+#[test]
+pub fn entry_takes_vector_address() -> anyhow::Result<()> {
+    let addresses = json!(["0xa000", "0x1", "0xa000"]);
+    call_with(
+        "entry_args::count_address",
+        &[addresses, json!("0xa000"), json!(2)],
+    )?
+    .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_aborts(
+        call_with(
+            "entry_args::count_address",
+            &[json!(["0x1"]), json!("0xa000"), json!(1)],
+        )?,
+        3,
+    );
+    Ok(())
+}
+
+#[test]
+pub fn entry_address_equals_literal() -> anyhow::Result<()> {
+    call_with(
+        "entry_args::is_owner",
+        &[json!("0xa000"), json!(["0x1", "0xcafe"])],
+    )?
+    .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_aborts(
+        call_with("entry_args::is_owner", &[json!("0xa0"), json!([])])?,
+        6,
+    );
+    Ok(())
+}
+
+#[test]
+pub fn entry_takes_nested_vectors() -> anyhow::Result<()> {
+    call_with(
+        "entry_args::total_len",
+        &[json!([[1, 2], [], [3]]), json!(3)],
+    )?
+    .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn malformed_call_data_aborts() -> anyhow::Result<()> {
+    let (_, abi) = create_blob_once();
+    let mut call_data = abi.encode_call("entry_args::sum_bytes", &[json!([1, 2]), json!(3)])?;
+    // a vector longer than the call data
+    call_data[4] = 100;
+    assert_aborts(call(call_data.clone())?, BCS_DESERIALIZATION_FAILURE);
+    // the last argument cut short
+    call_data[4] = 2;
+    call_data.truncate(call_data.len() - 1);
+    assert_aborts(call(call_data)?, BCS_DESERIALIZATION_FAILURE);
+    Ok(())
+}
+
+#[test]
+pub fn call_data_shorter_than_the_arguments_aborts() -> anyhow::Result<()> {
+    let (_, abi) = create_blob_once();
+    let call_data = abi.encode_call(
+        "entry_args::count_address",
+        &[json!([]), json!("0xa000"), json!(0)],
+    )?;
+    // the selector alone
+    assert_aborts(call(call_data[..4].to_vec())?, MALFORMED_INPUT_CODE);
+    // the address cut short, the expected count missing
+    assert_aborts(call(call_data[..10].to_vec())?, MALFORMED_INPUT_CODE);
+    call(call_data)?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_colored_stdout, create_instance, package_build_options,
+        parse_to_blob,
+    },
+    run_to_polka,
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+use serde_json::{json, Value};
+
+static COMPILE_ONCE: OnceCell<(ProgramBlob, Abi)> = OnceCell::new();
+
+fn create_blob_once() -> (ProgramBlob, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/entry_modules/entry_modules.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/entry-modules/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true);
+            let blob =
+                parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                    .expect("Failed to parse the blob");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (blob, abi)
+        })
+        .clone()
+}
+
+/// Call the blob through its `call` export with `call_data`, like pallet-revive does.
+fn call(call_data: Vec<u8>) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (blob, _) = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = call_data;
+    Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+}
+
+fn call_with(
+    function: &str,
+    args: &[Value],
+) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    let (_, abi) = create_blob_once();
+    call(abi.encode_call(function, args)?)
+}
+
+fn assert_aborts(result: Result<(), CallError<ProgramError>>, code: u64) {
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(c))) if c == code),
+        "Expected abort {code}, got {result:?}"
+    );
+}
+
+#[test]
+pub fn entry_functions_of_all_modules_are_dispatched() -> anyhow::Result<()> {
+    call_with("first::sum", &[json!(1), json!(2), json!(3)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call_with("first::product", &[json!(3), json!(4), json!(12)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call_with("second::negate", &[json!(true), json!(false)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    call_with("second::sum_twice", &[json!(1), json!(2), json!(6)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    assert_aborts(call_with("first::sum", &[json!(1), json!(2), json!(4)])?, 1);
+    assert_aborts(
+        call_with("first::product", &[json!(3), json!(4), json!(7)])?,
+        5,
+    );
+    assert_aborts(call_with("second::negate", &[json!(true), json!(true)])?, 3);
+    assert_aborts(
+        call_with("second::sum_twice", &[json!(1), json!(2), json!(8)])?,
+        4,
+    );
+    Ok(())
+}
+
+#[test]
+pub fn unknown_selector_aborts() -> anyhow::Result<()> {
+    // no entry function of the package aborts with 2
+    assert_aborts(call(vec![0xde, 0xad, 0xbe, 0xef])?, 2);
+    Ok(())
+}
+
+#[test]
+pub fn selector_collision_across_modules_is_reported() -> anyhow::Result<()> {
+    initialize_logger();
+    let (_, abi) = create_blob_once();
+    let selector = &abi.function("first::sum")?.selector;
+    let mut options = package_build_options(
+        "output/entry_modules/collision.polkavm",
+        "../../examples/entry-modules/",
+        HashSet::new(),
+    )?
+    .check(true)
+    .build();
+    options.selector_overrides = vec![format!("second::negate={selector}")];
+    let err = run_to_polka(&mut create_colored_stdout(), options)
+        .expect_err("colliding selectors must fail the build");
+    let message = err.to_string();
+    assert!(
+        message.contains("selector collision")
+            && message.contains("first::sum")
+            && message.contains("second::negate")
+            && message.contains("modules.move:"),
+        "unexpected error: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn invalid_selector_overrides_are_reported() -> anyhow::Result<()> {
+    initialize_logger();
+    for (selector, expected) in [
+        ("first::sum", "expected `module::function=0x12345678`"),
+        (
+            "first::missing=0x12345678",
+            "no entry function `first::missing`",
+        ),
+        ("first::sum=0xnothex", "invalid selector `0xnothex`"),
+    ] {
+        let mut options = package_build_options(
+            "output/entry_modules/invalid_override.polkavm",
+            "../../examples/entry-modules/",
+            HashSet::new(),
+        )?
+        .check(true)
+        .build();
+        options.selector_overrides = vec![selector.to_string()];
+        let err = run_to_polka(&mut create_colored_stdout(), options)
+            .expect_err("an invalid selector override must fail the build");
+        let message = err.to_string();
+        assert!(
+            message.contains(&format!("selector `{selector}`")) && message.contains(expected),
+            "unexpected error for `{selector}`: {message}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+pub fn stripped_exports_are_still_dispatched() -> anyhow::Result<()> {
+    initialize_logger();
+    let (_, abi) = create_blob_once();
+    let build_options = package_build_options(
+        "output/entry_modules/stripped.polkavm",
+        "../../examples/entry-modules/",
+        HashSet::new(),
+    )?
+    .strip_exports(true)
+    .extra_exports(vec!["second::negate".to_string()]);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let exports = blob
+        .exports()
+        .map(|export| export.symbol().to_string())
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        exports,
+        HashSet::from(["call", "deploy", "negate"].map(String::from))
+    );
+
+    for (function, args, abort) in [
+        ("first::sum", [json!(1), json!(2), json!(3)], None),
+        ("second::sum_twice", [json!(1), json!(2), json!(8)], Some(4)),
+    ] {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        runtime.call_data = abi.encode_call(function, &args)?;
+        let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ());
+        match abort {
+            Some(code) => assert_aborts(result, code),
+            None => result.map_err(|e| anyhow::anyhow!("{e:?}"))?,
+        }
+    }
+    Ok(())
+}
+
+#[test]
+pub fn unknown_extra_export_is_reported() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/entry_modules/unknown_export.polkavm",
+        "../../examples/entry-modules/",
+        HashSet::new(),
+    )?
+    .check(true)
+    .extra_exports(vec!["first::missing".to_string()])
+    .build();
+    let err = run_to_polka(&mut create_colored_stdout(), options)
+        .expect_err("an unknown extra export must fail the build");
+    assert!(
+        err.to_string()
+            .contains("extra export `first::missing`: no such function"),
+        "unexpected error: {err}"
+    );
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use move_to_polka::{
+    check,
+    error::MoveToPolkaError,
+    get_env_from_memory, initialize_logger,
+    linker::{create_colored_stdout, BuildOptions},
+    vfs::VirtualFiles,
+};
+
+#[test]
+pub fn references_in_entry_parameters_are_rejected() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = BuildOptions::new("output/entry_params/by_ref.polkavm").build();
+    let files = VirtualFiles::new().file(
+        "sources/by_ref.move",
+        "module 0xa000::by_ref {
+            public entry fun ok(_account: &signer, _amount: u64) {}
+            public entry fun by_ref(_account: &signer, amount: &u64) { assert!(*amount > 0, 1) }
+        }",
+    );
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    let result = check(&env, &options);
+    let Err(MoveToPolkaError::Compile(message)) = result else {
+        panic!("{result:?}");
+    };
+    assert!(message.contains("by_ref.move:3:"), "{message}");
+    assert!(
+        message.contains("`0xa000::by_ref::by_ref`: parameter `amount` of type `&u64`"),
+        "{message}"
+    );
+    assert!(!message.contains("::ok`"), "{message}");
+    Ok(())
+}
+
+#[test]
+pub fn dubious_entry_parameters_are_warned_about() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = BuildOptions::new("output/entry_params/warnings.polkavm").build();
+    let files = VirtualFiles::new().file(
+        "sources/warnings.move",
+        "module 0xa000::warnings {
+            public entry fun generic<T: drop>(_value: T) {}
+            public entry fun fine(_a: &signer, _values: vector<u64>) {}
+        }",
+    );
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    let warnings = check(&env, &options)?;
+    let messages = warnings
+        .iter()
+        .map(|warning| {
+            assert!(warning.is_warning());
+            assert!(warning
+                .location
+                .as_deref()
+                .is_some_and(|location| location.contains("warnings.move:")));
+            warning.message.as_str()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 1, "{messages:?}");
+    assert!(
+        messages[0].contains("`0xa000::warnings::generic`: generic entry functions"),
+        "{messages:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn forgeable_struct_parameters_are_rejected() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = BuildOptions::new("output/entry_params/structs.polkavm").build();
+    let files = VirtualFiles::new()
+        .file(
+            "sources/coin.move",
+            "module 0xa000::coin {
+                struct Coin has store { value: u64 }
+                struct Receipt has copy, drop { amount: u64 }
+                public entry fun deposit(_account: &signer, _coin: Coin) { abort 1 }
+            }",
+        )
+        .file(
+            "sources/bank.move",
+            "module 0xa000::bank {
+                struct Order has copy, drop { amount: u64 }
+                public entry fun order(_account: &signer, _orders: vector<Order>) {}
+                public entry fun forge(_account: &signer, _receipt: 0xa000::coin::Receipt) {}
+            }",
+        );
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    let result = check(&env, &options);
+    let Err(MoveToPolkaError::Compile(message)) = result else {
+        panic!("{result:?}");
+    };
+    assert!(
+        message.contains("`0xa000::coin::deposit`: parameter `_coin`")
+            && message.contains("`0xa000::coin::Coin` lacks `copy` or `drop`"),
+        "{message}"
+    );
+    assert!(
+        message.contains("`0xa000::bank::forge`: parameter `_receipt`")
+            && message.contains("`0xa000::coin::Receipt` is defined in another module"),
+        "{message}"
+    );
+    assert!(!message.contains("::order`"), "{message}");
+    Ok(())
+}
+
+#[test]
+pub fn later_signer_parameters_are_rejected() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = BuildOptions::new("output/entry_params/signers.polkavm").build();
+    let files = VirtualFiles::new().file(
+        "sources/signers.move",
+        "module 0xa000::signers {
+            public entry fun first(_a: signer, _amount: u64) {}
+            public entry fun second(_amount: u64, _a: &signer) {}
+            public entry fun two(_a: &signer, _b: signer) {}
+            public entry fun many(_signers: vector<signer>) {}
+        }",
+    );
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    let result = check(&env, &options);
+    let Err(MoveToPolkaError::Compile(message)) = result else {
+        panic!("{result:?}");
+    };
+    for expected in [
+        "`0xa000::signers::second`: parameter `_a`: only the first parameter",
+        "`0xa000::signers::two`: parameter `_b`: only the first parameter",
+        "`0xa000::signers::many`: parameter `_signers` of type `vector<signer>` contains signers",
+    ] {
+        assert!(message.contains(expected), "{message}");
+    }
+    assert!(!message.contains("::first`"), "{message}");
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{host::ProgramError, BCS_DESERIALIZATION_FAILURE};
+use serde_json::{json, Value};
+
+static COMPILE_ONCE: OnceCell<(ProgramBlob, Abi)> = OnceCell::new();
+
+fn create_blob_once() -> (ProgramBlob, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/entry_structs/entry_structs.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/entry-structs/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true);
+            let blob =
+                parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                    .expect("Failed to parse the blob");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (blob, abi)
+        })
+        .clone()
+}
+
+/// Call `function` with `args` through the `call` export, like pallet-revive does, and
+/// decode its return values.
+fn call_with(
+    function: &str,
+    args: &[Value],
+) -> anyhow::Result<Result<Vec<Value>, CallError<ProgramError>>> {
+    let (blob, abi) = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = abi.encode_call(function, args)?;
+    runtime.output.clear();
+    Ok(
+        match instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()) {
+            Ok(()) => Ok(abi.decode_return(function, &runtime.output)?),
+            Err(e) => Err(e),
+        },
+    )
+}
+
+fn triangle() -> Value {
+    json!({
+        "name": "0x747269616e676c65",
+        "owner": "0xcafe",
+        "points": [{ "x": 0, "y": 0 }, { "x": 4, "y": 0 }, { "x": 0, "y": 3 }],
+    })
+}
+
+#[test]
+pub fn entry_returns_struct() -> anyhow::Result<()> {
+    let returns = call_with(
+        "shapes::midpoint",
+        &[json!({ "x": 2, "y": 10 }), json!([6, 20])],
+    )?
+    .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(returns, vec![json!({ "x": 4, "y": 15 })]);
+    Ok(())
+}
+
+#[test]
+pub fn entry_takes_struct_with_vector_of_structs() -> anyhow::Result<()> {
+    call_with("shapes::check_polygon", &[triangle(), json!(3)])?
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let result = call_with("shapes::check_polygon", &[triangle(), json!(4)])?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(3)))),
+        "{result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn entry_returns_structs_in_tuple() -> anyhow::Result<()> {
+    let points = json!([{ "x": 5, "y": 1 }, { "x": 2, "y": 8 }, { "x": 9, "y": 4 }]);
+    let returns = call_with("shapes::bounds", &[points])?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(
+        returns,
+        vec![
+            json!({ "x": 2, "y": 1 }),
+            json!({ "x": 9, "y": 8 }),
+            json!(3)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn entry_returns_struct_with_vectors() -> anyhow::Result<()> {
+    let returns =
+        call_with("shapes::reversed", &[triangle()])?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let mut expected = triangle();
+    expected["name"] = json!(b"triangle".to_vec());
+    expected["points"] = json!([{ "x": 0, "y": 3 }, { "x": 4, "y": 0 }, { "x": 0, "y": 0 }]);
+    assert_eq!(returns, vec![expected]);
+    Ok(())
+}
+
+#[test]
+pub fn malformed_struct_aborts() -> anyhow::Result<()> {
+    let (blob, abi) = create_blob_once();
+    let mut call_data = abi.encode_call("shapes::check_polygon", &[triangle(), json!(3)])?;
+    // more points than the call data holds
+    let points_len = 4 + 1 + 8 + 32;
+    call_data[points_len] = 100;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = call_data;
+    let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ());
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(c))) if c == BCS_DESERIALIZATION_FAILURE),
+        "{result:?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    estimate::estimate,
+    fuzz::Outcome,
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options},
+};
+use once_cell::sync::OnceCell;
+use serde_json::json;
+
+static COMPILE_ONCE: OnceCell<(Vec<u8>, Abi)> = OnceCell::new();
+
+fn compile_once() -> &'static (Vec<u8>, Abi) {
+    COMPILE_ONCE.get_or_init(|| {
+        initialize_logger();
+        let output = "output/estimate/entry_args.polkavm";
+        let build_options =
+            package_build_options(output, "../../examples/entry-args/", HashSet::new())
+                .expect("Failed to read the package")
+                .emit_abi(true);
+        let bytes = build_polka_from_move(build_options).expect("Failed to compile");
+        let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+        (bytes, abi)
+    })
+}
+
+#[test]
+pub fn estimate_reports_size_and_gas() -> anyhow::Result<()> {
+    let (bytes, abi) = compile_once();
+    let short = estimate(
+        bytes,
+        abi,
+        "entry_args::sum_bytes",
+        &[json!([1, 2]), json!(3)],
+        10_000_000,
+    )?;
+    assert_eq!(short.outcome, Outcome::Success);
+    assert_eq!(short.size.blob, bytes.len());
+    assert!(short.size.code > 0 && short.size.code < short.size.blob);
+    assert!(short.gas_used > 0 && short.gas_used < short.gas_limit);
+
+    // the loop runs once per byte, so a longer vector costs more
+    let long = estimate(
+        bytes,
+        abi,
+        "entry_args::sum_bytes",
+        &[json!(vec![1; 100]), json!(100)],
+        10_000_000,
+    )?;
+    assert_eq!(long.outcome, Outcome::Success);
+    assert_eq!(long.size, short.size);
+    assert!(long.gas_used > short.gas_used);
+    Ok(())
+}
+
+#[test]
+pub fn estimate_reports_aborts_and_out_of_gas() -> anyhow::Result<()> {
+    let (bytes, abi) = compile_once();
+    let aborted = estimate(
+        bytes,
+        abi,
+        "entry_args::sum_bytes",
+        &[json!([1, 2]), json!(4)],
+        10_000_000,
+    )?;
+    assert_eq!(aborted.outcome, Outcome::Abort(1));
+    assert!(aborted.gas_used > 0);
+
+    let out_of_gas = estimate(
+        bytes,
+        abi,
+        "entry_args::sum_bytes",
+        &[json!([1, 2]), json!(3)],
+        10,
+    )?;
+    assert_eq!(out_of_gas.outcome, Outcome::OutOfGas);
+    assert_eq!(out_of_gas.gas_used, 10);
+
+    assert!(estimate(bytes, abi, "entry_args::sum_bytes", &[], 10).is_err());
+    Ok(())
+}
@@ -110,3 +110,23 @@ pub fn test_struct() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn test_address_comparison() -> anyhow::Result<()> {
+    initialize_logger();
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let mut address_bytes = [1u8; ACCOUNT_ADDRESS_LENGTH];
+    address_bytes[0] = 0xab;
+    address_bytes[ACCOUNT_ADDRESS_LENGTH - 1] = 0xce;
+
+    let move_signer = MoveSigner(MoveAddress(address_bytes));
+
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+
+    instance
+        .call_typed_and_get_result::<(), _>(&mut runtime, "compare_addresses", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
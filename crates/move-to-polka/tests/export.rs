@@ -0,0 +1,130 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{
+        build_polka_from_move, check_exports, create_instance, default_selector, exports,
+        package_build_options, parse_to_blob,
+    },
+    metadata::PackageMetadata,
+};
+use polkavm::ProgramBlob;
+
+fn build_blob(output: &str, export_public: bool) -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let build_options = package_build_options(output, "../../examples/export/", HashSet::new())?
+        .export_public(export_public);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+fn export_names(blob: &ProgramBlob) -> Vec<String> {
+    blob.exports()
+        .map(|export| export.symbol().to_string())
+        .collect()
+}
+
+#[test]
+pub fn test_only_entry_functions_exported_by_default() -> anyhow::Result<()> {
+    let blob = build_blob("output/export/default.polkavm", false)?;
+    let exports = export_names(&blob);
+    assert!(exports.contains(&"run".to_string()));
+    for name in ["double", "identity", "increment", "triple"] {
+        assert!(!exports.contains(&name.to_string()), "{name} is exported");
+    }
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "run", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn test_export_public_functions() -> anyhow::Result<()> {
+    let blob = build_blob("output/export/public.polkavm", true)?;
+    let exports = export_names(&blob);
+    assert!(exports.contains(&"run".to_string()));
+    assert!(exports.contains(&"double".to_string()));
+    for name in ["identity", "increment", "triple"] {
+        assert!(!exports.contains(&name.to_string()), "{name} is exported");
+    }
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let result = instance
+        .call_typed_and_get_result::<u64, (u64,)>(&mut runtime, "double", (21,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(result, 42);
+    Ok(())
+}
+
+#[test]
+pub fn test_export_public_name_collision_is_rejected() -> anyhow::Result<()> {
+    initialize_logger();
+    // GoldCoin and SilverCoin both have a `public fun setup_and_mint`
+    let build_options = package_build_options(
+        "output/export/collision.polkavm",
+        "../../examples/coin-swap/",
+        HashSet::new(),
+    )?
+    .export_public(true);
+    let err = build_polka_from_move(build_options)
+        .expect_err("two public functions exported under one name must fail the build");
+    let message = err.to_string();
+    assert!(
+        message.contains("export collision")
+            && message.contains("GoldCoin::setup_and_mint")
+            && message.contains("SilverCoin::setup_and_mint")
+            && message.contains("exported as `setup_and_mint`")
+            && message.contains("Coin.move:"),
+        "unexpected error: {message}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn exports_are_checked_against_the_abi() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/export/checked.polkavm";
+    let build_options = package_build_options(output, "../../examples/storage/", HashSet::new())?
+        .emit_abi(true)
+        .emit_metadata(true);
+    let program = build_polka_from_move(build_options)?;
+    let mut abi = Abi::read(&abi_path(Path::new(output)))?;
+    let metadata = PackageMetadata::of_blob(Path::new(output))?;
+
+    // the default selectors are computed from the `module::function` names alone
+    let listed = exports(&program, metadata.as_ref())?;
+    let export = listed
+        .iter()
+        .find(|export| export.name == "store_then_borrow")
+        .expect("entry functions are exported");
+    assert_eq!(
+        export.function.as_deref(),
+        Some("storage::store_then_borrow")
+    );
+    assert_eq!(export.selector, Some([0xfa, 0x1e, 0x1f, 0x30]));
+    assert_eq!(
+        default_selector("storage::store_then_borrow"),
+        [0xfa, 0x1e, 0x1f, 0x30]
+    );
+
+    let exports = check_exports(&program, metadata.as_ref(), &abi)?;
+    let export = exports
+        .iter()
+        .find(|export| export.name == "store_then_borrow")
+        .expect("entry functions are exported");
+    assert_eq!(export.selector, Some([0xfa, 0x1e, 0x1f, 0x30]));
+    let call = exports.iter().find(|export| export.name == "call");
+    assert_eq!(call.map(|export| export.selector), Some(None));
+
+    // a blob dispatching other selectors than the expected interface is rejected
+    abi.functions[0].selector = "0x00000001".to_string();
+    let result = check_exports(&program, metadata.as_ref(), &abi);
+    assert!(
+        matches!(&result, Err(MoveToPolkaError::InvalidArtifact(message)) if message.contains("other selectors")),
+        "{result:?}"
+    );
+    Ok(())
+}
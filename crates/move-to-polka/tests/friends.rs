@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{
+        build_polka_from_move, create_colored_stdout, create_instance, package_build_options,
+        parse_to_blob,
+    },
+};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+
+const PACKAGE: &str = "../../examples/friends/";
+
+fn build_blob(output: &str, export_public: bool, prune: bool) -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let build_options = package_build_options(output, PACKAGE, HashSet::new())?
+        .export_public(export_public)
+        .prune_unreachable(prune);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+fn exports(blob: &ProgramBlob) -> Vec<String> {
+    blob.exports()
+        .map(|export| export.symbol().to_string())
+        .collect()
+}
+
+/// The `define` line of `function` of `module` in the IR of the package.
+fn definition(ir: &[(String, String)], module: &str, function: &str) -> String {
+    let (_, ir) = ir
+        .iter()
+        .find(|(name, _)| name.contains(module))
+        .unwrap_or_else(|| panic!("module {module} not translated"));
+    ir.lines()
+        .find(|line| line.starts_with("define") && line.contains(&format!("_{function}_")))
+        .unwrap_or_else(|| panic!("{module}::{function} not defined in:\n{ir}"))
+        .to_string()
+}
+
+#[test]
+pub fn friend_functions_are_called_but_never_exported() -> anyhow::Result<()> {
+    for (output, export_public) in [
+        ("output/friends/default.polkavm", false),
+        ("output/friends/public.polkavm", true),
+    ] {
+        let blob = build_blob(output, export_public, false)?;
+        let exports = exports(&blob);
+        assert!(exports.contains(&"deposit".to_string()));
+        assert_eq!(exports.contains(&"total".to_string()), export_public);
+        for name in ["credit", "scale", "only_local", "check", "mul", "checked"] {
+            assert!(!exports.contains(&name.to_string()), "{name} is exported");
+        }
+
+        let (mut instance, mut runtime) = create_instance(blob)?;
+        instance
+            .call_typed_and_get_result::<(), ()>(&mut runtime, "deposit", ())
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "overdraw", ());
+        assert!(
+            matches!(result, Err(CallError::User(ProgramError::Abort(7)))),
+            "{result:?}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+pub fn functions_only_reachable_through_friends_survive_pruning() -> anyhow::Result<()> {
+    let blob = build_blob("output/friends/pruned.polkavm", false, true)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "deposit", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn only_functions_called_across_modules_are_linked_externally() -> anyhow::Result<()> {
+    initialize_logger();
+    let options =
+        package_build_options("output/friends/ir.polkavm", PACKAGE, HashSet::new())?.build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let ir = compile_to_ir_strings(&env, &options)?;
+
+    // called by the friends
+    assert!(!definition(&ir, "ledger", "credit").contains(" private "));
+    assert!(!definition(&ir, "audit", "check").contains(" private "));
+    // called by the instantiation of `scale` in the bank module
+    assert!(!definition(&ir, "ledger", "mul").contains(" private "));
+    // only called within the ledger module
+    assert!(definition(&ir, "ledger", "only_local").contains(" private "));
+    assert!(definition(&ir, "ledger", "checked").contains(" private "));
+    Ok(())
+}
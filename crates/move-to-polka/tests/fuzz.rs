@@ -0,0 +1,101 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    fuzz::{fuzz, generate_case, run_case, FuzzOptions, Outcome},
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options, parse_to_blob},
+};
+use once_cell::sync::OnceCell;
+use polkavm::ProgramBlob;
+use polkavm_move_native::MALFORMED_INPUT_CODE;
+
+static COMPILE_ONCE: OnceCell<(ProgramBlob, Abi)> = OnceCell::new();
+
+fn create_blob_once() -> (ProgramBlob, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/fuzz/storage.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/storage/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true);
+            let blob =
+                parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                    .expect("Failed to parse the blob");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (blob, abi)
+        })
+        .clone()
+}
+
+#[test]
+pub fn fuzz_counts_every_run() -> anyhow::Result<()> {
+    let (blob, abi) = create_blob_once();
+    let options = FuzzOptions {
+        runs: 50,
+        seed: 7,
+        ..Default::default()
+    };
+    let report = fuzz(&blob, &abi, &options)?;
+    assert_eq!(report.runs, 50);
+    assert_eq!(
+        report.successes
+            + report.aborts.values().sum::<u64>()
+            + report.out_of_gas
+            + report.findings.len() as u64,
+        50
+    );
+    // store, called on fresh storage, always succeeds
+    assert!(report.successes > 0, "{report:?}");
+    // some runs truncate the call data, which the guest rejects
+    assert!(
+        report.aborts.contains_key(&MALFORMED_INPUT_CODE),
+        "{report:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn fuzz_is_deterministic() -> anyhow::Result<()> {
+    let (blob, abi) = create_blob_once();
+    let options = FuzzOptions {
+        runs: 20,
+        seed: 1234,
+        ..Default::default()
+    };
+    assert_eq!(fuzz(&blob, &abi, &options)?, fuzz(&blob, &abi, &options)?);
+    Ok(())
+}
+
+#[test]
+pub fn fuzz_case_replays_from_its_seed() -> anyhow::Result<()> {
+    let (blob, abi) = create_blob_once();
+    let options = FuzzOptions::default();
+    let case = generate_case(&abi, 99, &options)?.expect("storage has entry functions");
+    assert_eq!(generate_case(&abi, 99, &options)?, Some(case.clone()));
+    assert_eq!(
+        run_case(&blob, &case, options.gas)?,
+        run_case(&blob, &case, options.gas)?
+    );
+    Ok(())
+}
+
+#[test]
+pub fn fuzz_stops_calls_running_out_of_gas() -> anyhow::Result<()> {
+    let (blob, abi) = create_blob_once();
+    let options = FuzzOptions {
+        runs: 5,
+        gas: 1,
+        ..Default::default()
+    };
+    let report = fuzz(&blob, &abi, &options)?;
+    assert_eq!(report.out_of_gas, 5, "{report:?}");
+    assert!(report.findings.is_empty());
+    assert!(matches!(
+        run_case(&blob, &generate_case(&abi, 0, &options)?.unwrap(), 1)?,
+        Outcome::OutOfGas
+    ));
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    abi::AbiType,
+    guest_memory::{read_bcs, read_value, write_bcs, write_value},
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+};
+use once_cell::sync::OnceCell;
+use polkavm::ProgramBlob;
+use serde_json::json;
+
+static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+fn create_blob_once() -> ProgramBlob {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let build_options = package_build_options(
+                "output/guest_memory/guest_memory.polkavm",
+                "../../examples/guest_memory/",
+                HashSet::new(),
+            )
+            .expect("Failed to read the package")
+            .export_public(true);
+            parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                .expect("Failed to parse the blob")
+        })
+        .clone()
+}
+
+#[test]
+pub fn test_pass_vector_by_reference() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let ty = AbiType::Vector(Box::new(AbiType::U64));
+    let v = write_value(
+        &mut instance,
+        &mut runtime.allocator,
+        &ty,
+        &json!([1, 2, 39]),
+    )?;
+    let sum = instance
+        .call_typed_and_get_result::<u64, (u32,)>(&mut runtime, "sum", (v,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(sum, 42);
+    Ok(())
+}
+
+#[test]
+pub fn test_read_back_mutated_vector() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let ty = AbiType::Vector(Box::new(AbiType::U64));
+    let v = write_value(
+        &mut instance,
+        &mut runtime.allocator,
+        &ty,
+        &json!([1, 2, 3]),
+    )?;
+    instance
+        .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "double_all", (v,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(read_value(&mut instance, &ty, v)?, json!([2, 4, 6]));
+    Ok(())
+}
+
+#[test]
+pub fn test_pass_addresses() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let allocator = &mut runtime.allocator;
+    let owners = write_value(
+        &mut instance,
+        allocator,
+        &AbiType::Vector(Box::new(AbiType::Address)),
+        &json!(["0x1", "0xcafe", "0x1"]),
+    )?;
+    let owner = write_value(&mut instance, allocator, &AbiType::Address, &json!("0x1"))?;
+    let count = instance
+        .call_typed_and_get_result::<u64, (u32, u32)>(&mut runtime, "count_owned", (owners, owner))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(count, 2);
+
+    // addresses are laid out like the compiler lays out address literals
+    let cafe = write_value(
+        &mut instance,
+        &mut runtime.allocator,
+        &AbiType::Address,
+        &json!("0xcafe"),
+    )?;
+    let is_cafe = instance
+        .call_typed_and_get_result::<u64, (u32,)>(&mut runtime, "is_cafe", (cafe,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(is_cafe, 1);
+    assert_eq!(
+        read_value(&mut instance, &AbiType::Address, cafe)?,
+        json!("0xcafe")
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_bcs_round_trip_of_nested_vectors() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let ty = AbiType::Vector(Box::new(AbiType::Vector(Box::new(AbiType::U8))));
+    // [[0xca, 0xfe], [], [0xba, 0xbe, 0x00]]
+    let bcs = [3, 2, 0xca, 0xfe, 0, 3, 0xba, 0xbe, 0x00];
+    let chunks = write_bcs(&mut instance, &mut runtime.allocator, &ty, &bcs)?;
+    assert_eq!(read_bcs(&mut instance, &ty, chunks)?, bcs);
+    let total = instance
+        .call_typed_and_get_result::<u64, (u32,)>(&mut runtime, "total_length", (chunks,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(total, 5);
+    Ok(())
+}
+
+#[test]
+pub fn test_struct_values_are_rejected() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let ty = AbiType::Struct("0xa000::guest_memory::Coin".to_string());
+    assert!(write_bcs(&mut instance, &mut runtime.allocator, &ty, &[]).is_err());
+    Ok(())
+}
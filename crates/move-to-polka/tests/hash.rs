@@ -46,3 +46,36 @@ pub fn test_sha3() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn test_chained_hashes() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "chained_hashes", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_keccak256() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "keccak256_expected_hash", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_blake2b256() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "blake2b256_expected_hash", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_instance, package_build_options, parse_to_blob,
+        read_heap_stats,
+    },
+};
+use polkavm::ProgramBlob;
+use polkavm_move_native::{HeapStats, DEFAULT_HEAP_PAGES, HEAP_PAGE_SIZE};
+
+// Bytes of the buffer of `big_vector`, whose capacity doubles up to 32768 elements.
+const BIG_VECTOR_BUFFER: u32 = 32768 * 8;
+
+fn build_blob(output: &str, heap_pages: u32) -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let build_options = package_build_options(output, "../../examples/heap/", HashSet::new())?
+        .heap_pages(heap_pages)
+        .stack_size(Some(64 * 1024))
+        .heap_stats(true);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+/// Call `function` and return the allocator's counters afterwards.
+fn call(blob: ProgramBlob, function: &str) -> anyhow::Result<HeapStats> {
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, function, ())
+        .map_err(|e| anyhow::anyhow!("{function}: {e:?}"))?;
+    read_heap_stats(&mut instance, &runtime)?
+        .ok_or_else(|| anyhow::anyhow!("{function} registered no heap stats"))
+}
+
+#[test]
+pub fn big_vector_exhausts_default_heap() -> anyhow::Result<()> {
+    let blob = build_blob("output/heap-default/heap.polkavm", DEFAULT_HEAP_PAGES)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, "big_vector", ());
+    assert!(result.is_err(), "Expected the default heap to run out");
+
+    Ok(())
+}
+
+#[test]
+pub fn big_vector_fits_larger_heap() -> anyhow::Result<()> {
+    let blob = build_blob("output/heap-large/heap.polkavm", 256)?;
+    call(blob, "big_vector")?;
+
+    Ok(())
+}
+
+#[test]
+pub fn growing_vector_reuses_its_buffer() -> anyhow::Result<()> {
+    // Less than the buffers of all capacities together, which a heap that never frees needs.
+    let pages = (BIG_VECTOR_BUFFER + 64 * 1024) / HEAP_PAGE_SIZE;
+    let blob = build_blob("output/heap-growth/heap.polkavm", pages)?;
+    let stats = call(blob, "big_vector")?;
+    assert!(
+        stats.peak >= BIG_VECTOR_BUFFER && stats.peak < BIG_VECTOR_BUFFER + 4096,
+        "{stats:?}"
+    );
+    assert!(stats.reallocs_in_place > 0, "{stats:?}");
+    assert_eq!(stats.reallocs_moved, 0, "{stats:?}");
+
+    Ok(())
+}
+
+#[test]
+pub fn freed_vectors_are_reused() -> anyhow::Result<()> {
+    let blob = build_blob("output/heap-reuse/heap.polkavm", DEFAULT_HEAP_PAGES)?;
+    let stats = call(blob, "temporary_vectors")?;
+    assert!(stats.frees >= 1000, "{stats:?}");
+    assert!(stats.peak < 4096, "{stats:?}");
+    assert_eq!(stats.in_use, 0, "{stats:?}");
+
+    Ok(())
+}
+
+#[test]
+pub fn host_memory_is_not_freed() -> anyhow::Result<()> {
+    let blob = build_blob("output/heap-digests/heap.polkavm", DEFAULT_HEAP_PAGES)?;
+    let stats = call(blob, "dropped_digests")?;
+    // the digests live in memory the host owns, dropping them must not hand it out again
+    assert!(stats.frees < 50, "{stats:?}");
+    assert!(stats.in_use < HEAP_PAGE_SIZE, "{stats:?}");
+
+    Ok(())
+}
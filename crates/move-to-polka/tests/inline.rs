@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{
+        build_polka_from_move, create_colored_stdout, create_instance, package_build_options,
+        parse_to_blob, BuildOptions,
+    },
+};
+
+const PACKAGE: &str = "../../examples/inline/";
+
+fn build_options(output: &str, inline: &[&str]) -> anyhow::Result<BuildOptions> {
+    initialize_logger();
+    Ok(package_build_options(output, PACKAGE, HashSet::new())?
+        .inline_overrides(inline.iter().map(|hint| hint.to_string()).collect()))
+}
+
+/// The function attributes of `function` in `ir`, from the attribute group of its definition.
+fn function_attributes(ir: &str, function: &str) -> String {
+    let define = ir
+        .lines()
+        .find(|line| line.starts_with("define") && line.contains(&format!("_{function}_")))
+        .unwrap_or_else(|| panic!("{function} not defined in:\n{ir}"));
+    let Some(group) = define.split_whitespace().find(|word| word.starts_with('#')) else {
+        return String::new();
+    };
+    ir.lines()
+        .find(|line| line.starts_with(&format!("attributes {group} = ")))
+        .unwrap_or_else(|| panic!("no attribute group {group} in:\n{ir}"))
+        .to_string()
+}
+
+#[test]
+pub fn inline_hints_become_llvm_attributes() -> anyhow::Result<()> {
+    let options = build_options("output/inline/ir.polkavm", &["counter::double=never"])?.build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let ir = compile_to_ir_strings(&env, &options)?;
+    let (_, ir) = ir
+        .iter()
+        .find(|(name, _)| name.contains("counter"))
+        .expect("counter module not translated");
+
+    assert!(function_attributes(ir, "value").contains("alwaysinline"));
+    assert!(function_attributes(ir, "bump").contains("inlinehint"));
+    assert!(function_attributes(ir, "fail").contains("noinline"));
+    // from `--inline`
+    assert!(function_attributes(ir, "double").contains("noinline"));
+    assert!(!function_attributes(ir, "count").contains("inline"));
+    Ok(())
+}
+
+#[test]
+pub fn inlined_functions_run() -> anyhow::Result<()> {
+    let blob = parse_to_blob(&build_polka_from_move(build_options(
+        "output/inline/inline.polkavm",
+        &[],
+    )?)?)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "count", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn invalid_inline_hints_are_reported() -> anyhow::Result<()> {
+    for (inline, message) in [
+        ("counter::missing=always", "no function `counter::missing`"),
+        (
+            "counter::double=sometimes",
+            "invalid inline hint 'sometimes'",
+        ),
+        (
+            "counter::double",
+            "expected `module::function=<hint|always|never>`",
+        ),
+    ] {
+        let options = build_options("output/inline/invalid.polkavm", &[inline])?;
+        let error = build_polka_from_move(options)
+            .expect_err(inline)
+            .to_string();
+        assert!(error.contains(message), "{error}");
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{create_colored_stdout, package_build_options},
+};
+
+#[test]
+pub fn test_compile_to_ir_strings() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/ir/overflow.polkavm",
+        "../../examples/overflow/",
+        HashSet::new(),
+    )?
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let modules = compile_to_ir_strings(&env, &options)?;
+
+    assert_eq!(modules.len(), env.get_modules().count());
+    let (_, ir) = modules
+        .iter()
+        .find(|(name, _)| name.contains("overflow"))
+        .expect("overflow module not translated");
+    assert!(ir.contains("define"), "no function definitions in:\n{ir}");
+    assert!(
+        ir.contains("add_u8_overflow"),
+        "entry function missing in:\n{ir}"
+    );
+    Ok(())
+}
+
+/// The IR of the module `module` of the example package `package`.
+fn module_ir(package: &str, module: &str) -> anyhow::Result<String> {
+    initialize_logger();
+    let options = package_build_options(
+        &format!("output/ir/{package}.polkavm"),
+        &format!("../../examples/{package}/"),
+        HashSet::new(),
+    )?
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let (_, ir) = compile_to_ir_strings(&env, &options)?
+        .into_iter()
+        .find(|(name, _)| name.ends_with(module))
+        .expect("module not translated");
+    Ok(ir)
+}
+
+#[test]
+pub fn test_constants_emitted_once() -> anyhow::Result<()> {
+    let ir = module_ir("constants", "constants")?;
+    for name in ["constants::PRIMES", "constants::GREETING"] {
+        let definitions = ir
+            .lines()
+            .filter(|line| line.contains(&format!("{name}\" = internal constant")))
+            .count();
+        assert_eq!(definitions, 1, "`{name}` not defined once in:\n{ir}");
+    }
+    // the same value loaded by the generic function of another module keeps its own name
+    assert!(
+        ir.contains("primes::FIRST_PRIMES\" = internal constant"),
+        "`primes::FIRST_PRIMES` not defined in:\n{ir}"
+    );
+
+    // address constants too, loaded five times by `pay`
+    let ir = module_ir("payable", "payable")?;
+    let definitions = ir
+        .lines()
+        .filter(|line| line.contains("payable::RECIPIENT\" = internal constant"))
+        .count();
+    assert_eq!(definitions, 1, "`RECIPIENT` not defined once in:\n{ir}");
+    Ok(())
+}
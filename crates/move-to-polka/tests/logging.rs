@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger_with,
+    linker::{build_polka_from_move, package_build_options},
+    DEBUG_CODEGEN_AREAS,
+};
+
+// The logger is global, so this is the only test in this file.
+#[test]
+pub fn test_debug_codegen_log_file() -> anyhow::Result<()> {
+    let log_file = "output/logging/codegen.log";
+    std::fs::create_dir_all("output/logging")?;
+    initialize_logger_with(&["functions".to_string()], Some(log_file.as_ref()))?;
+
+    let build_options = package_build_options(
+        "output/logging/multi_module.polkavm",
+        "../../examples/multi_module/",
+        HashSet::new(),
+    )?;
+    build_polka_from_move(build_options)?;
+
+    let (_, targets) = DEBUG_CODEGEN_AREAS
+        .iter()
+        .find(|(area, _)| *area == "functions")
+        .unwrap();
+    let log = std::fs::read_to_string(log_file)?;
+    // `<time> DEBUG <spans>: <target>: <line>: <message>`, the spans only if there are any
+    let debug_targets = log
+        .lines()
+        .filter_map(|line| {
+            let fields = line
+                .split_once(" DEBUG ")?
+                .1
+                .split(": ")
+                .collect::<Vec<_>>();
+            fields
+                .windows(2)
+                .find(|pair| pair[1].parse::<u32>().is_ok())
+                .map(|pair| pair[0])
+        })
+        .collect::<HashSet<_>>();
+    assert!(debug_targets.contains("functions"), "{log}");
+    assert!(
+        debug_targets.iter().all(|target| targets.contains(target)),
+        "{debug_targets:?}"
+    );
+
+    // the logger is set up already, initializing it again leaves the log alone
+    initialize_logger_with(&[], Some(log_file.as_ref()))?;
+    assert_eq!(std::fs::read_to_string(log_file)?, log);
+    Ok(())
+}
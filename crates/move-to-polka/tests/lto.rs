@@ -0,0 +1,85 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_move_package, create_instance, package_build_options, parse_to_blob},
+};
+use object::{Object, ObjectSection, ObjectSymbol, RelocationTarget, SectionKind};
+
+/// Whether the code of the object at `path` calls `A::add`, i.e. has a relocation against it.
+fn calls_a_add(path: &Path) -> anyhow::Result<bool> {
+    let bytes = std::fs::read(path)?;
+    let file = object::File::parse(&*bytes)?;
+    Ok(file.sections().any(|section| {
+        section.kind() == SectionKind::Text
+            && section
+                .relocations()
+                .any(|(_, relocation)| match relocation.target() {
+                    RelocationTarget::Symbol(index) => file
+                        .symbol_by_index(index)
+                        .and_then(|symbol| symbol.name())
+                        .is_ok_and(|name| name.contains("_A_add_")),
+                    _ => false,
+                })
+    }))
+}
+
+#[test]
+pub fn test_lto_multi_module_call() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/lto/multi_module.polkavm",
+        "../../examples/multi_module/",
+        HashSet::new(),
+    )?
+    .lto(true)
+    .keep_temps(true);
+    let output = build_move_package(build_options)?;
+    // the package is emitted as a single object, named after the output
+    assert_eq!(output.object_paths.len(), 1, "{:?}", output.object_paths);
+    assert!(output.object_paths[0].ends_with("multi_module.o"));
+    assert!(output.object_paths[0].exists());
+
+    let blob = parse_to_blob(&std::fs::read(output.blob_path.unwrap())?)?;
+    std::fs::remove_dir_all(output.temp_dir.unwrap())?;
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "main", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn test_lto_inlines_across_modules() -> anyhow::Result<()> {
+    initialize_logger();
+    let separate = package_build_options(
+        "output/lto-separate/multi_module.polkavm",
+        "../../examples/multi_module/",
+        HashSet::new(),
+    )?
+    .keep_temps(true);
+    let output = build_move_package(separate)?;
+    // without `--lto`, B calls A::add in the object of A
+    let mut calls = 0;
+    for path in &output.object_paths {
+        if calls_a_add(path)? {
+            calls += 1;
+        }
+    }
+    assert_eq!(calls, 1);
+    std::fs::remove_dir_all(output.temp_dir.unwrap())?;
+
+    let linked = package_build_options(
+        "output/lto-inline/multi_module.polkavm",
+        "../../examples/multi_module/",
+        HashSet::new(),
+    )?
+    .lto(true)
+    .keep_temps(true);
+    let output = build_move_package(linked)?;
+    assert_eq!(output.object_paths.len(), 1, "{:?}", output.object_paths);
+    assert!(!calls_a_add(&output.object_paths[0])?);
+    std::fs::remove_dir_all(output.temp_dir.unwrap())?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    linker::{package_build_options, package_test_build_options},
+    options::Options,
+};
+
+const APP: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[addresses]
+app = "_"
+owner = "0xA11CE"
+
+[dev-addresses]
+app = "0xCAFE"
+owner = "0xB0B"
+
+[dependencies]
+Lib = { local = "../lib" }
+
+[dev-dependencies]
+TestLib = { local = "../testlib" }
+"#;
+
+/// Write the `app` package and the packages it depends on under `dir`.
+fn write_packages(dir: &Path) {
+    for (name, manifest) in [
+        ("app", APP.to_string()),
+        ("lib", package("lib")),
+        ("testlib", package("testlib")),
+    ] {
+        std::fs::create_dir_all(dir.join(name).join("sources")).unwrap();
+        std::fs::write(dir.join(name).join("Move.toml"), manifest).unwrap();
+    }
+}
+
+fn package(name: &str) -> String {
+    format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n")
+}
+
+fn depends_on(options: &Options, package: &str) -> bool {
+    options
+        .dependencies
+        .iter()
+        .any(|dependency| Path::new(dependency).ends_with(package))
+}
+
+#[test]
+pub fn manifest_resolves_sources_dependencies_and_addresses() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_packages(dir.path());
+    let root = dir.path().join("app");
+
+    let options = Options::from_move_toml(&root, false)?;
+    assert_eq!(options.sources, [root.to_string_lossy()]);
+    assert!(depends_on(&options, "lib"));
+    assert!(!depends_on(&options, "testlib"));
+    assert!(options
+        .named_address_mapping
+        .iter()
+        .any(|mapping| mapping.starts_with("owner=") && mapping.ends_with("a11ce")));
+    assert!(!options
+        .named_address_mapping
+        .iter()
+        .any(|mapping| mapping.starts_with("app=")));
+
+    // the manifest itself resolves like its directory
+    let from_file = Options::from_move_toml(&root.join("Move.toml"), false)?;
+    assert_eq!(from_file.dependencies, options.dependencies);
+    assert_eq!(
+        from_file.named_address_mapping,
+        options.named_address_mapping
+    );
+    Ok(())
+}
+
+#[test]
+pub fn dev_builds_add_dev_dependencies_and_addresses() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_packages(dir.path());
+
+    let options = Options::from_move_toml(&dir.path().join("app"), true)?;
+    assert!(depends_on(&options, "lib"));
+    assert!(depends_on(&options, "testlib"));
+    let mapping = &options.named_address_mapping;
+    assert!(mapping
+        .iter()
+        .any(|mapping| mapping.starts_with("app=") && mapping.ends_with("cafe")));
+    // dev addresses don't override assigned addresses
+    assert!(mapping
+        .iter()
+        .any(|mapping| mapping.starts_with("owner=") && mapping.ends_with("a11ce")));
+    assert!(!mapping.iter().any(|mapping| mapping.ends_with("b0b")));
+    Ok(())
+}
+
+#[test]
+pub fn test_builds_add_dev_dependencies() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_packages(dir.path());
+    let root = dir.path().join("app");
+    let root = root.to_str().unwrap();
+
+    let options =
+        package_build_options("output/manifest/app.polkavm", root, HashSet::new())?.build();
+    assert!(!depends_on(&options, "testlib"));
+    assert_eq!(options.unit_test_function, None);
+
+    let options = package_test_build_options(
+        "output/manifest/app.polkavm",
+        root,
+        HashSet::new(),
+        "test_transfer",
+    )?
+    .build();
+    assert!(depends_on(&options, "lib"));
+    assert!(depends_on(&options, "testlib"));
+    assert_eq!(options.unit_test_function.as_deref(), Some("test_transfer"));
+    Ok(())
+}
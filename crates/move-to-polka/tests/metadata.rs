@@ -0,0 +1,74 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_move_package, create_instance, package_build_options, parse_to_blob},
+    metadata::{metadata_path, PackageMetadata, CODEC},
+};
+
+#[test]
+pub fn metadata_is_written_next_to_blob() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/metadata/entry.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/entry/", HashSet::new())?.emit_metadata(true);
+    let built = build_move_package(build_options)?;
+    let metadata_file = metadata_path(Path::new(output));
+    assert_eq!(
+        built.metadata_path.as_deref(),
+        Some(metadata_file.as_path())
+    );
+
+    let metadata = PackageMetadata::of_blob(Path::new(output))?.expect("metadata not written");
+    assert_eq!(metadata, PackageMetadata::read(&metadata_file)?);
+    assert_eq!(metadata.package_name.as_deref(), Some("entry"));
+    assert_eq!(metadata.package_version.as_deref(), Some("1.0.0"));
+    assert_eq!(metadata.compiler_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(metadata.codec, CODEC);
+    assert!(metadata.named_addresses.contains_key("entry"));
+    assert_eq!(metadata.selector_table_hash.len(), 64);
+
+    // The blob is the PolkaVM program alone.
+    let blob = parse_to_blob(&std::fs::read(output)?)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "main", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn metadata_round_trip() -> anyhow::Result<()> {
+    let metadata = PackageMetadata {
+        package_name: Some("pkg".to_string()),
+        package_version: Some("0.1.0".to_string()),
+        compiler_version: "0.1.0".to_string(),
+        named_addresses: [("pkg".to_string(), "0x42".to_string())].into(),
+        codec: CODEC.to_string(),
+        selector_table_hash: "00".repeat(32),
+        export_signatures: [("main".to_string(), ":".to_string())].into(),
+        entry_functions: [("main".to_string(), "m::main".to_string())].into(),
+        function_attributes: [("m::f".to_string(), [("payable".to_string(), None)].into())].into(),
+        signer_key_hash: Some("11".repeat(32)),
+        defines: [("mainnet".to_string(), "true".to_string())].into(),
+        assume_no_abort: true,
+    };
+    let dir = tempfile::tempdir()?;
+    let blob = dir.path().join("pkg.polkavm");
+    assert_eq!(PackageMetadata::of_blob(&blob)?, None);
+
+    metadata.write(&metadata_path(&blob))?;
+    assert_eq!(PackageMetadata::of_blob(&blob)?, Some(metadata));
+    Ok(())
+}
+
+#[test]
+pub fn malformed_metadata_is_rejected() {
+    let result = PackageMetadata::from_json(b"{}");
+    assert!(
+        matches!(result, Err(MoveToPolkaError::InvalidArtifact(_))),
+        "Expected an invalid artifact error, got {result:?}"
+    );
+}
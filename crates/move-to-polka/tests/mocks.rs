@@ -0,0 +1,96 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, check_package, create_instance, create_instance_with_print_sink,
+        package_build_options, parse_to_blob,
+    },
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::{MockRandom, PrintedValue, ProgramError};
+
+static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+// 2024-06-01 00:00:00 UTC
+const NOW_MS: u64 = 1_717_200_000_000;
+
+fn create_blob_once() -> ProgramBlob {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            // revive::random::bytes is only served by the mock host
+            let options = package_build_options(
+                "output/lottery/lottery.polkavm",
+                "../../examples/lottery/",
+                HashSet::new(),
+            )
+            .expect("Failed to resolve the package")
+            .mock_host(true);
+            let program_bytes = build_polka_from_move(options)
+                .expect("Failed to compile Move source to PolkaVM bytecode");
+            parse_to_blob(&program_bytes).expect("Failed to parse the blob")
+        })
+        .clone()
+}
+
+/// The ticket numbers of `draws` draws with randomness seeded with `seed`.
+fn draw(seed: u64, draws: usize) -> anyhow::Result<Vec<String>> {
+    let printed: Rc<RefCell<Vec<PrintedValue>>> = Rc::default();
+    let sink = printed.clone();
+    let (mut instance, mut runtime) =
+        create_instance_with_print_sink(create_blob_once(), move |value| {
+            sink.borrow_mut().push(value)
+        })?;
+    runtime.timestamp_ms = NOW_MS;
+    runtime.random = MockRandom::new(seed);
+    for _ in 0..draws {
+        instance
+            .call_typed_and_get_result::<(), ()>(&mut runtime, "draw", ())
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+    let numbers = printed.borrow().iter().map(|p| p.value.clone()).collect();
+    Ok(numbers)
+}
+
+#[test]
+pub fn test_mock_time() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    // before the lottery opens
+    runtime.timestamp_ms = 1_700_000_000_000;
+    let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "draw", ());
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(1)))),
+        "{result:?}"
+    );
+
+    runtime.timestamp_ms = NOW_MS;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "draw", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn test_mock_randomness_is_reproducible() -> anyhow::Result<()> {
+    let numbers = draw(7, 4)?;
+    assert_eq!(numbers.len(), 4);
+    assert!(numbers
+        .iter()
+        .all(|number| number.parse::<u64>().is_ok_and(|n| n < 1000)));
+    assert_eq!(draw(7, 4)?, numbers);
+    assert_ne!(draw(8, 4)?, numbers);
+    Ok(())
+}
+
+#[test]
+pub fn test_random_needs_mock_host() {
+    initialize_logger();
+    let result = check_package("../../examples/lottery/", HashSet::new());
+    let err = result
+        .expect_err("pallet-revive has no randomness")
+        .to_string();
+    assert!(err.contains("`random::bytes`"), "{err}");
+    assert!(err.contains("--mock-host"), "{err}");
+}
@@ -0,0 +1,31 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_polka_from_move, package_artifact_path, package_build_options, parse_to_blob},
+};
+
+#[test]
+pub fn artifacts_follow_move_build_layout() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = package_artifact_path("../../examples/entry/", Some("output/move-polka"))?;
+    assert_eq!(
+        output,
+        Path::new("output/move-polka/build/entry/polkavm/entry.polkavm")
+    );
+
+    let output = output.to_string_lossy();
+    let build_options = package_build_options(&output, "../../examples/entry/", HashSet::new())?;
+    parse_to_blob(&build_polka_from_move(build_options)?)?;
+    assert!(Path::new(output.as_ref()).exists());
+
+    Ok(())
+}
+
+#[test]
+pub fn artifacts_default_to_package_root() -> anyhow::Result<()> {
+    let output = package_artifact_path("../../examples/entry/", None)?;
+    assert!(output.ends_with("examples/entry/build/entry/polkavm/entry.polkavm"));
+
+    Ok(())
+}
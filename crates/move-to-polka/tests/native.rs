@@ -0,0 +1,84 @@
+use move_to_polka::{
+    error::MoveToPolkaError,
+    native::{
+        embedded_variants, extract_move_native, move_native_cache_dir, move_native_lib_content,
+        resolve_move_native, AddressLength, NativeArch, NativeProfile, NativeVariant,
+        MOVE_NATIVE_OBJECT_NAME,
+    },
+};
+
+#[test]
+pub fn test_resolve_embedded_move_native() -> anyhow::Result<()> {
+    assert!(embedded_variants().contains(&NativeVariant::DEFAULT));
+    // extract into an empty directory
+    let cache_dir = tempfile::tempdir()?;
+    let move_native = extract_move_native(cache_dir.path(), NativeVariant::DEFAULT)?;
+    assert!(move_native.starts_with(cache_dir.path()));
+    assert_eq!(
+        std::fs::read(&move_native)?,
+        move_native_lib_content(NativeVariant::DEFAULT)?
+    );
+    // the extracted object is reused by later builds
+    assert_eq!(
+        extract_move_native(cache_dir.path(), NativeVariant::DEFAULT)?,
+        move_native
+    );
+
+    // without a runtime given, the embedded one is extracted into the cache directory,
+    // `target/move-to-polka-cache` in this workspace
+    let resolved = resolve_move_native(None, NativeVariant::DEFAULT)?;
+    assert!(resolved.starts_with(move_native_cache_dir()));
+    Ok(())
+}
+
+#[test]
+pub fn test_parse_move_native_variant() {
+    assert_eq!(
+        "rv64-debug".parse::<NativeVariant>(),
+        Ok(NativeVariant {
+            arch: NativeArch::Rv64,
+            profile: NativeProfile::Debug,
+            address_length: AddressLength::Bytes32,
+        })
+    );
+    // the code generator only targets rv64
+    assert!("rv32-release".parse::<NativeVariant>().is_err());
+    assert_eq!(NativeVariant::DEFAULT.to_string(), "rv64-release");
+    assert!("rv64".parse::<NativeVariant>().is_err());
+    let addr20 = "rv64-release-addr20".parse::<NativeVariant>();
+    assert_eq!(
+        addr20,
+        Ok(NativeVariant {
+            address_length: AddressLength::Bytes20,
+            ..NativeVariant::DEFAULT
+        })
+    );
+    assert_eq!(addr20.unwrap().to_string(), "rv64-release-addr20");
+    assert!("rv64-release-addr16".parse::<NativeVariant>().is_err());
+}
+
+#[test]
+pub fn test_resolve_move_native_directory() -> anyhow::Result<()> {
+    let archive_dir = std::path::PathBuf::from("output/native/archive");
+    std::fs::create_dir_all(&archive_dir)?;
+    std::fs::write(
+        archive_dir.join(MOVE_NATIVE_OBJECT_NAME),
+        move_native_lib_content(NativeVariant::DEFAULT)?,
+    )?;
+
+    let move_native = resolve_move_native(Some("output/native/archive"), NativeVariant::DEFAULT)?;
+    assert_eq!(move_native, archive_dir.join(MOVE_NATIVE_OBJECT_NAME));
+    Ok(())
+}
+
+#[test]
+pub fn test_resolve_missing_move_native() {
+    let result = resolve_move_native(
+        Some("output/native/missing/move_native.o"),
+        NativeVariant::DEFAULT,
+    );
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Toolchain(_))),
+        "Expected a toolchain error, got {result:?}"
+    );
+}
@@ -0,0 +1,157 @@
+use std::{collections::HashSet, path::Path};
+
+use move_core_types::vm_status::StatusCode;
+use move_to_polka::{
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{
+        build_polka_from_move, create_colored_stdout, create_instance, package_build_options,
+        parse_to_blob,
+    },
+    metadata::PackageMetadata,
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+
+const ARITHMETIC_ERROR: u64 = StatusCode::ARITHMETIC_ERROR as u64;
+
+static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+fn build_blob(output: &str, overflow_checks: bool) -> anyhow::Result<ProgramBlob> {
+    initialize_logger();
+    let build_options = package_build_options(output, "../../examples/overflow/", HashSet::new())?
+        .overflow_checks(overflow_checks);
+    Ok(parse_to_blob(&build_polka_from_move(build_options)?)?)
+}
+
+fn create_blob_once() -> ProgramBlob {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            build_blob("output/overflow/overflow.polkavm", true)
+                .expect("Failed to compile Move source to PolkaVM bytecode")
+        })
+        .clone()
+}
+
+fn assert_arithmetic_error(entry: &str) -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, entry, ());
+    match result {
+        Err(CallError::User(ProgramError::Abort(code))) => {
+            assert_eq!(
+                code, ARITHMETIC_ERROR,
+                "{entry} aborted with the wrong code"
+            );
+        }
+        other => panic!("Expected {entry} to abort with ARITHMETIC_ERROR, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+pub fn test_in_range_arithmetic() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "in_range", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_add_u8_overflow() -> anyhow::Result<()> {
+    assert_arithmetic_error("add_u8_overflow")
+}
+
+#[test]
+pub fn test_add_u64_overflow() -> anyhow::Result<()> {
+    assert_arithmetic_error("add_u64_overflow")
+}
+
+#[test]
+pub fn test_sub_u64_underflow() -> anyhow::Result<()> {
+    assert_arithmetic_error("sub_u64_underflow")
+}
+
+#[test]
+pub fn test_mul_u32_overflow() -> anyhow::Result<()> {
+    assert_arithmetic_error("mul_u32_overflow")
+}
+
+#[test]
+pub fn test_mul_u128_overflow() -> anyhow::Result<()> {
+    assert_arithmetic_error("mul_u128_overflow")
+}
+
+#[test]
+pub fn test_wrapping_without_overflow_checks() -> anyhow::Result<()> {
+    let blob = build_blob("output/overflow-wrapping/overflow.polkavm", false)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "wrapping", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_assume_no_abort_omits_checks() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/overflow-unchecked/overflow.polkavm";
+    let build_options = package_build_options(output, "../../examples/overflow/", HashSet::new())?
+        .assume_no_abort(true)
+        .emit_metadata(true);
+    let bytes = build_polka_from_move(build_options)?;
+    let metadata = PackageMetadata::of_blob(Path::new(output))?;
+    assert!(metadata.expect("metadata written").assume_no_abort);
+
+    // the overflow checks are on, but omitted
+    let (mut instance, mut runtime) = create_instance(parse_to_blob(&bytes)?)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "wrapping", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+/// The definition of `function` in `ir`, from `define` to the closing brace.
+fn function_definition<'a>(ir: &'a str, function: &str) -> &'a str {
+    let start = ir
+        .lines()
+        .find(|line| line.starts_with("define") && line.contains(&format!("_{function}_")))
+        .and_then(|define| ir.find(define))
+        .unwrap_or_else(|| panic!("{function} not defined in:\n{ir}"));
+    let end = ir[start..]
+        .find("\n}")
+        .expect("definitions end with a brace");
+    &ir[start..start + end]
+}
+
+#[test]
+pub fn test_assume_no_abort_omits_division_shift_and_cast_checks() -> anyhow::Result<()> {
+    initialize_logger();
+    for assume_no_abort in [false, true] {
+        let options = package_build_options(
+            "output/overflow-ir/overflow.polkavm",
+            "../../examples/overflow/",
+            HashSet::new(),
+        )?
+        .assume_no_abort(assume_no_abort)
+        .build();
+        let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+        let ir = compile_to_ir_strings(&env, &options)?;
+        let (_, ir) = ir
+            .iter()
+            .find(|(name, _)| name.contains("overflow"))
+            .expect("overflow module not translated");
+        // division by zero, a shift by the bit width or more, a value out of the target's range
+        for function in ["div_u64", "shl_u8", "cast_u8"] {
+            assert_eq!(
+                function_definition(ir, function).contains("@move_rt_abort"),
+                !assume_no_abort,
+                "{function} with assume_no_abort {assume_no_abort}"
+            );
+        }
+    }
+    Ok(())
+}
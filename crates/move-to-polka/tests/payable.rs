@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{create_blob, create_instance, CONTRACT_ADDRESS},
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{host::ProgramError, TRANSFER_FAILURE_CODE};
+
+static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+const RECIPIENT: [u8; 20] = hex_literal::hex!("beefbeefbeefbeefbeefbeefbeefbeefbeefbeef");
+
+fn create_blob_once() -> ProgramBlob {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            create_blob(
+                "output/payable/payable.polkavm",
+                "../../examples/payable/",
+                HashSet::new(),
+            )
+            .expect("Failed to compile Move source to PolkaVM bytecode")
+        })
+        .clone()
+}
+
+#[test]
+pub fn test_transfer() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.balances.insert(CONTRACT_ADDRESS, 1000);
+    runtime.balances.insert(RECIPIENT, 1);
+
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "pay", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(runtime.balances[&CONTRACT_ADDRESS], 900);
+    assert_eq!(runtime.balances[&RECIPIENT], 101);
+
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "pay_everything", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(runtime.balances[&CONTRACT_ADDRESS], 0);
+    assert_eq!(runtime.balances[&RECIPIENT], 1001);
+
+    Ok(())
+}
+
+#[test]
+pub fn test_transfer_beyond_balance_aborts() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.balances.insert(CONTRACT_ADDRESS, 1000);
+
+    let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "overdraw", ());
+    assert!(matches!(
+        result,
+        Err(CallError::User(ProgramError::Abort(TRANSFER_FAILURE_CODE)))
+    ));
+    assert_eq!(runtime.balances[&CONTRACT_ADDRESS], 1000);
+    assert!(!runtime.balances.contains_key(&RECIPIENT));
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, copy_to_guest, create_blob, create_instance, package_build_options,
+        parse_to_blob,
+    },
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{
+    host::ProgramError,
+    types::{MoveAddress, MoveSigner, ACCOUNT_ADDRESS_LENGTH},
+};
+
+static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+static COMPILE_PARANOID_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+fn create_blob_once() -> ProgramBlob {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            create_blob(
+                "output/refs/refs.polkavm",
+                "../../examples/refs/",
+                HashSet::new(),
+            )
+            .expect("Failed to compile Move source to PolkaVM bytecode")
+        })
+        .clone()
+}
+
+/// The blob built with `--paranoid`, and `read` exported.
+fn create_paranoid_blob_once() -> ProgramBlob {
+    COMPILE_PARANOID_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let build_options = package_build_options(
+                "output/refs-paranoid/refs.polkavm",
+                "../../examples/refs/",
+                HashSet::new(),
+            )
+            .expect("Failed to read the package")
+            .export_public(true)
+            .paranoid(true);
+            parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+                .expect("Failed to parse the blob")
+        })
+        .clone()
+}
+
+#[test]
+pub fn write_through_local_element_and_field_references() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    for entry in [
+        "write_local",
+        "write_vector_element",
+        "write_struct_field",
+        "read_copies_vector",
+    ] {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        instance
+            .call_typed_and_get_result::<(), ()>(&mut runtime, entry, ())
+            .map_err(|e| anyhow::anyhow!("{entry}: {e:?}"))?;
+    }
+    Ok(())
+}
+
+#[test]
+pub fn write_through_global_references() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let move_signer = MoveSigner(MoveAddress([7u8; ACCOUNT_ADDRESS_LENGTH]));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+
+    let mut call = |entry: &str| {
+        instance
+            .call_typed_and_get_result::<(), (u32,)>(&mut runtime, entry, (signer_address,))
+            .map_err(|e| anyhow::anyhow!("{entry}: {e:?}"))
+    };
+    call("publish")?;
+    call("increment")?;
+    call("increment")?;
+    call("increment")?;
+    instance
+        .call_typed_and_get_result::<(), (u32, u64)>(&mut runtime, "check", (signer_address, 3))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    instance
+        .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "replace", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    instance
+        .call_typed_and_get_result::<(), (u32, u64)>(&mut runtime, "check", (signer_address, 1))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    // A stale expectation must abort, so the checks above really read storage.
+    let stale = instance.call_typed_and_get_result::<(), (u32, u64)>(
+        &mut runtime,
+        "check",
+        (signer_address, 3),
+    );
+    assert!(stale.is_err(), "{stale:?}");
+    Ok(())
+}
+
+#[test]
+pub fn paranoid_checks_accept_valid_references() -> anyhow::Result<()> {
+    let blob = create_paranoid_blob_once();
+    for entry in [
+        "write_local",
+        "write_vector_element",
+        "write_struct_field",
+        "read_copies_vector",
+    ] {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        instance
+            .call_typed_and_get_result::<(), ()>(&mut runtime, entry, ())
+            .map_err(|e| anyhow::anyhow!("{entry}: {e:?}"))?;
+    }
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let move_signer = MoveSigner(MoveAddress([7u8; ACCOUNT_ADDRESS_LENGTH]));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+    for entry in ["publish", "increment", "replace"] {
+        instance
+            .call_typed_and_get_result::<(), (u32,)>(&mut runtime, entry, (signer_address,))
+            .map_err(|e| anyhow::anyhow!("{entry}: {e:?}"))?;
+    }
+    let value = copy_to_guest(&mut instance, &mut runtime.allocator, &42u64)?;
+    let read = instance
+        .call_typed_and_get_result::<u64, (u32,)>(&mut runtime, "read", (value,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(read, 42);
+    Ok(())
+}
+
+#[test]
+pub fn paranoid_checks_reject_invalid_references() -> anyhow::Result<()> {
+    let blob = create_paranoid_blob_once();
+    // null, and below the program's data
+    for address in [0u32, 0x100] {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        let result =
+            instance.call_typed_and_get_result::<u64, (u32,)>(&mut runtime, "read", (address,));
+        assert!(
+            matches!(result, Err(CallError::User(ProgramError::InvalidReference))),
+            "Expected an invalid reference at 0x{address:x}, got {result:?}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_instance, elf_path, package_build_options, parse_to_blob,
+        relink, LinkOptions,
+    },
+};
+
+const OUTPUT: &str = "output/relink/overflow.polkavm";
+
+#[test]
+pub fn test_relink_emitted_elf() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options =
+        package_build_options(OUTPUT, "../../examples/overflow/", HashSet::new())?.emit_elf(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let elf = elf_path(Path::new(OUTPUT));
+    assert!(elf.exists(), "{} was not written", elf.display());
+
+    let link_options = LinkOptions {
+        strip: true,
+        optimize: true,
+        dispatch_table: vec!["in_range".to_string()],
+    };
+    let relinked = parse_to_blob(&relink(
+        &elf,
+        Path::new("output/relink/overflow.stripped.polkavm"),
+        &link_options,
+    )?)?;
+
+    let exports = |blob: &polkavm::ProgramBlob| {
+        let mut exports = blob
+            .exports()
+            .map(|export| export.symbol().to_string())
+            .collect::<Vec<_>>();
+        exports.sort();
+        exports
+    };
+    assert_eq!(exports(&blob), exports(&relinked));
+    assert_eq!(
+        relinked.exports().next().map(|e| e.symbol().to_string()),
+        Some("in_range".to_string())
+    );
+
+    let (mut instance, mut runtime) = create_instance(relinked)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "in_range", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    error::MoveToPolkaError,
+    fuzz::Outcome,
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options},
+    metadata::PackageMetadata,
+    repl::Session,
+};
+use once_cell::sync::OnceCell;
+
+static COMPILE_ONCE: OnceCell<(Vec<u8>, PackageMetadata, Abi)> = OnceCell::new();
+
+fn create_blob_once() -> (Vec<u8>, PackageMetadata, Abi) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/repl/storage.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/storage/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true)
+                    .emit_metadata(true);
+            let bytes = build_polka_from_move(build_options).expect("Failed to compile");
+            let metadata = PackageMetadata::of_blob(Path::new(output))
+                .expect("Failed to read the metadata")
+                .expect("metadata not written");
+            let abi = Abi::read(&abi_path(Path::new(output))).expect("Failed to read the ABI");
+            (bytes, metadata, abi)
+        })
+        .clone()
+}
+
+#[test]
+pub fn session_keeps_storage_between_calls() -> anyhow::Result<()> {
+    let (bytes, metadata, abi) = create_blob_once();
+    let mut session = Session::new(&bytes, Some(metadata), Some(abi), None)?;
+
+    let result = session.call_entry("storage::store", &[])?;
+    assert_eq!(result.outcome, Outcome::Success);
+    assert_eq!(session.storage().len(), 1);
+    // the resource stored by the first call is there for the second one
+    let result = session.call_entry("storage::store", &[])?;
+    assert_ne!(result.outcome, Outcome::Success);
+    let result = session.call_entry("storage::load", &[])?;
+    assert_eq!(result.outcome, Outcome::Success);
+    assert!(session.storage().is_empty());
+
+    session.call_entry("storage::store", &[])?;
+    assert_eq!(session.storage().len(), 1);
+    session.reset()?;
+    assert!(session.storage().is_empty());
+    let result = session.call_entry("storage::load", &[])?;
+    assert_ne!(result.outcome, Outcome::Success);
+    Ok(())
+}
+
+#[test]
+pub fn session_lists_exports_and_needs_an_abi_for_entry_calls() -> anyhow::Result<()> {
+    let (bytes, metadata, _) = create_blob_once();
+    let mut session = Session::new(&bytes, Some(metadata), None, None)?;
+    let exports = session.exports()?;
+    assert!(
+        exports.iter().any(|(name, _)| name == "call"),
+        "{exports:?}"
+    );
+
+    let result = session.call_entry("storage::store", &[]);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected a codec error, got {result:?}"
+    );
+    let result = session.call_export("no_such_export", &[]);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected a codec error, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn session_reverts_aborted_calls() -> anyhow::Result<()> {
+    let (bytes, metadata, abi) = create_blob_once();
+    let mut session = Session::new(&bytes, Some(metadata), Some(abi), None)?;
+
+    // the first resource is stored before the call aborts on the second one
+    let result = session.call_entry("storage::store_twice", &[])?;
+    assert_ne!(result.outcome, Outcome::Success);
+    assert!(session.storage().is_empty());
+
+    session.call_entry("storage::store", &[])?;
+    let before = session.storage();
+    let result = session.call_entry("storage::borrow_mut_abort", &[])?;
+    assert_ne!(result.outcome, Outcome::Success);
+    assert_eq!(session.storage(), before);
+    Ok(())
+}
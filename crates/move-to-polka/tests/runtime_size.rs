@@ -0,0 +1,25 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_polka_from_move, elf_path, package_build_options},
+};
+use object::{Object, ObjectSymbol};
+
+#[test]
+pub fn runtime_links_no_core_fmt() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/runtime_size/void.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/void/", HashSet::new())?.emit_elf(true);
+    build_polka_from_move(build_options)?;
+    let elf = std::fs::read(elf_path(Path::new(output)))?;
+    let file = object::File::parse(elf.as_slice())?;
+    let fmt = file
+        .symbols()
+        .filter_map(|symbol| symbol.name().ok())
+        .filter(|name| name.contains("4core3fmt"))
+        .collect::<Vec<_>>();
+    assert!(fmt.is_empty(), "core::fmt is linked in: {fmt:?}");
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use std::collections::HashSet;
+
+use move_to_polka::{initialize_logger, linker::create_blob};
+
+#[test]
+pub fn selector_collision_is_rejected() {
+    initialize_logger();
+    let result = create_blob(
+        "output/selector/selector.polkavm",
+        "../../examples/selector/",
+        HashSet::new(),
+    );
+    let err = result.expect_err("colliding selectors must fail the build");
+    assert!(
+        format!("{err:?}").contains("selector collision"),
+        "unexpected error: {err:?}"
+    );
+}
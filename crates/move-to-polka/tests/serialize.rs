@@ -5,8 +5,12 @@ use move_to_polka::{
     linker::{copy_to_guest, create_blob, create_instance},
 };
 use once_cell::sync::OnceCell;
-use polkavm::ProgramBlob;
-use polkavm_move_native::types::{MoveAddress, MoveSigner, ACCOUNT_ADDRESS_LENGTH};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{
+    host::ProgramError,
+    types::{MoveAddress, MoveSigner, ACCOUNT_ADDRESS_LENGTH},
+    BCS_DESERIALIZATION_FAILURE,
+};
 
 static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
 
@@ -54,3 +58,45 @@ pub fn test_serialize_signer() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn test_serialize_literals() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "ser_literals", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_deserialize_bcs() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "deser_bcs", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_deserialize_malformed_input_aborts() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    for entry in ["deser_malformed_bool", "deser_trailing_bytes"] {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, entry, ());
+        assert!(
+            matches!(
+                result,
+                Err(CallError::User(ProgramError::Abort(
+                    BCS_DESERIALIZATION_FAILURE
+                )))
+            ),
+            "Expected {entry} to abort, got {result:?}"
+        );
+    }
+
+    Ok(())
+}
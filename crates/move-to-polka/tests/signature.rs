@@ -0,0 +1,104 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options},
+    metadata::PackageMetadata,
+    signature::ExportSignature,
+};
+use once_cell::sync::OnceCell;
+
+static COMPILE_ONCE: OnceCell<PackageMetadata> = OnceCell::new();
+
+fn metadata_once() -> PackageMetadata {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let output = "output/signature/guest_memory.polkavm";
+            let build_options =
+                package_build_options(output, "../../examples/guest_memory/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .export_public(true)
+                    .emit_metadata(true);
+            build_polka_from_move(build_options).expect("Failed to compile");
+            PackageMetadata::of_blob(Path::new(output))
+                .expect("Failed to read the metadata")
+                .expect("metadata not written")
+        })
+        .clone()
+}
+
+#[test]
+pub fn test_export_signatures_are_in_the_metadata() {
+    let signatures = metadata_once().export_signatures;
+    assert_eq!(signatures["sum"], "rv8:8;v");
+    assert_eq!(signatures["double_all"], "mv8:;v");
+    assert_eq!(signatures["count_owned"], "rvara:8;owners,owner");
+    assert_eq!(signatures["total_length"], "rvv1:8;chunks");
+}
+
+#[test]
+pub fn test_export_signature_display() -> anyhow::Result<()> {
+    let metadata = metadata_once();
+    let signature = ExportSignature::parse(&metadata.export_signatures["count_owned"])?;
+    assert_eq!(
+        signature.to_string(),
+        "(owners: &vector<address>, owner: &address): u64"
+    );
+    let signature = ExportSignature::parse(&metadata.export_signatures["double_all"])?;
+    assert_eq!(signature.to_string(), "(v: &mut vector<u64>)");
+    // signatures written without names
+    let signature = ExportSignature::parse("rvara:8")?;
+    assert!(signature.param_names.is_empty());
+    assert_eq!(signature.to_string(), "(&vector<address>, &address): u64");
+    Ok(())
+}
+
+#[test]
+pub fn test_call_shape_is_checked() -> anyhow::Result<()> {
+    let signature = ExportSignature::parse(&metadata_once().export_signatures["sum"])?;
+    signature.check_call("sum", &[0x20000])?;
+    let result = signature.check_call("sum", &[0x20000, 1]);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected a codec error, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_call_argument_types_are_checked() -> anyhow::Result<()> {
+    // fun f(flag: bool, small: u8, big: u128, v: &vector<u64>)
+    let signature = ExportSignature::parse("b1xrv8:;flag,small,big,v")?;
+    // a u128 takes two registers
+    signature.check_call("f", &[1, 0xff, u64::MAX, u64::MAX, 0x20000])?;
+    for args in [
+        [2, 0xff, 0, 0, 0x20000],
+        [1, 0x100, 0, 0, 0x20000],
+        [1, 0xff, 0, 0, 1 << 32],
+    ] {
+        let result = signature.check_call("f", &args);
+        assert!(
+            matches!(result, Err(MoveToPolkaError::Codec(_))),
+            "Expected a codec error for {args:?}, got {result:?}"
+        );
+    }
+    let result = signature.check_call("f", &[1, 0xff, 0, 0x20000]);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Codec(_))),
+        "Expected a codec error for a u128 in one register, got {result:?}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_malformed_signature_is_rejected() {
+    for signature in ["rv8", "rv:8", "q:", "rv8:8;v,w"] {
+        let result = ExportSignature::parse(signature);
+        assert!(
+            matches!(result, Err(MoveToPolkaError::InvalidArtifact(_))),
+            "Expected an invalid artifact error for {signature}, got {result:?}"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use ed25519_dalek::SigningKey;
+use move_to_polka::{
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options},
+    metadata::{metadata_path, PackageMetadata},
+    signing::{key_hash, read_signing_key, sign_blob, signature_path, verify_blob, BlobSignature},
+};
+use once_cell::sync::OnceCell;
+
+const OUTPUT: &str = "output/signing/void.polkavm";
+const KEY_FILE: &str = "output/signing/key.hex";
+const KEY: [u8; 32] = [7; 32];
+
+static COMPILE_ONCE: OnceCell<(Vec<u8>, Vec<u8>, BlobSignature)> = OnceCell::new();
+
+/// The blob signed with `KEY`, its metadata file and its detached signature.
+fn signed_blob_once() -> (Vec<u8>, Vec<u8>, BlobSignature) {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            fs::create_dir_all("output/signing").expect("Failed to create the output directory");
+            fs::write(KEY_FILE, hex::encode(KEY)).expect("Failed to write the key");
+            let build_options =
+                package_build_options(OUTPUT, "../../examples/void/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .signing_key(Some(KEY_FILE.to_string()));
+            let bytes = build_polka_from_move(build_options).expect("Failed to compile");
+            let metadata =
+                fs::read(metadata_path(Path::new(OUTPUT))).expect("Failed to read the metadata");
+            let signature = BlobSignature::read(&signature_path(Path::new(OUTPUT)))
+                .expect("Failed to read the signature");
+            (bytes, metadata, signature)
+        })
+        .clone()
+}
+
+fn assert_rejected(result: Result<impl std::fmt::Debug, MoveToPolkaError>, reason: &str) {
+    assert!(
+        matches!(&result, Err(MoveToPolkaError::Signing(e)) if e.contains(reason)),
+        "Expected the signature to be rejected with `{reason}`, got {result:?}"
+    );
+}
+
+#[test]
+pub fn signed_blob_records_the_key_hash() -> anyhow::Result<()> {
+    let (bytes, metadata, signature) = signed_blob_once();
+    let key = SigningKey::from_bytes(&KEY).verifying_key();
+    let recorded = PackageMetadata::from_json(&metadata)?;
+    assert_eq!(recorded.signer_key_hash, Some(key_hash(&key)));
+    assert_eq!(signature.public_key, hex::encode(key.as_bytes()));
+
+    assert_eq!(verify_blob(&bytes, &metadata, &signature, &[key])?, key);
+    // without a trusted key, the signature says nothing about who signed
+    assert_rejected(
+        verify_blob(&bytes, &metadata, &signature, &[]),
+        "no trusted key",
+    );
+    Ok(())
+}
+
+#[test]
+pub fn tampered_blob_is_rejected() {
+    let (mut bytes, metadata, signature) = signed_blob_once();
+    let key = SigningKey::from_bytes(&KEY).verifying_key();
+    bytes[8] ^= 1;
+    assert_rejected(
+        verify_blob(&bytes, &metadata, &signature, &[key]),
+        "does not match the blob",
+    );
+}
+
+#[test]
+pub fn tampered_metadata_is_rejected() -> anyhow::Result<()> {
+    let (bytes, metadata, signature) = signed_blob_once();
+    let key = SigningKey::from_bytes(&KEY).verifying_key();
+    let mut recorded = PackageMetadata::from_json(&metadata)?;
+    recorded.assume_no_abort = !recorded.assume_no_abort;
+    let tampered = serde_json::to_vec_pretty(&recorded)?;
+    assert_rejected(
+        verify_blob(&bytes, &tampered, &signature, &[key]),
+        "does not match the blob",
+    );
+    Ok(())
+}
+
+#[test]
+pub fn untrusted_or_replaced_key_is_rejected() {
+    let (bytes, metadata, signature) = signed_blob_once();
+    let other = SigningKey::from_bytes(&[8; 32]);
+    assert_rejected(
+        verify_blob(&bytes, &metadata, &signature, &[other.verifying_key()]),
+        "untrusted key",
+    );
+
+    // a valid signature by another key doesn't match the recorded key hash
+    let resigned = sign_blob(&bytes, &metadata, &other);
+    let key = SigningKey::from_bytes(&KEY).verifying_key();
+    assert_rejected(
+        verify_blob(&bytes, &metadata, &resigned, &[key]),
+        "different signing key",
+    );
+}
+
+#[test]
+pub fn signing_key_is_read_from_the_environment() -> anyhow::Result<()> {
+    std::env::set_var(
+        "MOVE_POLKA_TEST_SIGNING_KEY",
+        format!("0x{}\n", hex::encode(KEY)),
+    );
+    let key = read_signing_key("env:MOVE_POLKA_TEST_SIGNING_KEY")?;
+    assert_eq!(key.to_bytes(), KEY);
+
+    std::env::set_var("MOVE_POLKA_TEST_SIGNING_KEY", "0102");
+    assert_rejected(
+        read_signing_key("env:MOVE_POLKA_TEST_SIGNING_KEY"),
+        "2 bytes instead of 32",
+    );
+    assert_rejected(
+        read_signing_key("env:MOVE_POLKA_TEST_NO_SUCH_KEY"),
+        "can't read the key",
+    );
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::{collections::HashSet, path::Path};
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options, parse_to_blob},
+    source_map::{source_map_path, SourceMap},
+};
+
+#[test]
+pub fn test_source_map() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/source-map/overflow.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/overflow/", HashSet::new())?.source_map(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+
+    let source_map = SourceMap::read(&source_map_path(Path::new(output)))?;
+    for export in blob.exports() {
+        assert_eq!(
+            source_map.exports.get(&export.symbol().to_string()),
+            Some(&export.program_counter().0)
+        );
+    }
+
+    let (name, ranges) = source_map
+        .functions
+        .iter()
+        .find(|(name, _)| name.ends_with("add_u8"))
+        .expect("add_u8 is mapped");
+    assert!(!ranges.is_empty());
+    for range in ranges {
+        assert!(range.start < range.end);
+        assert!(range.file.ends_with("overflow.move"), "{}", range.file);
+        assert_eq!(
+            source_map.lookup(range.start).map(|(f, _)| f),
+            Some(name.as_str())
+        );
+    }
+
+    Ok(())
+}
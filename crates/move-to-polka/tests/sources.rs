@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_polka_from_move, packages_build_options, parse_to_blob, BuildOptions},
+};
+use polkavm::ProgramBlob;
+
+fn exports(blob: &ProgramBlob) -> Vec<String> {
+    blob.exports()
+        .map(|export| export.symbol().to_string())
+        .collect()
+}
+
+#[test]
+pub fn test_glob_sources() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = BuildOptions::new("output/sources/glob.polkavm")
+        .source("../../examples/export/sources/*.move");
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    assert!(exports(&blob).contains(&"run".to_string()));
+    Ok(())
+}
+
+#[test]
+pub fn test_glob_matching_nothing_is_an_error() {
+    initialize_logger();
+    let build_options = BuildOptions::new("output/sources/nothing.polkavm")
+        .source("../../examples/export/sources/*.nothing");
+    let result = build_polka_from_move(build_options);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Compile(ref e)) if e.contains("matches no files")),
+        "{result:?}"
+    );
+}
+
+#[test]
+pub fn test_multiple_package_roots() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = packages_build_options(
+        "output/sources/packages.polkavm",
+        &["../../examples/export/", "../../examples/guest_memory/"],
+        &[],
+        HashSet::new(),
+    )?
+    .export_public(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let exports = exports(&blob);
+    for name in ["run", "double", "sum"] {
+        assert!(
+            exports.contains(&name.to_string()),
+            "{name} is not exported"
+        );
+    }
+    Ok(())
+}
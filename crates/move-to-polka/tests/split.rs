@@ -0,0 +1,131 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use move_to_polka::{
+    abi::{abi_path, Abi},
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+    split::{manifest_path, module_blob_path, SplitManifest},
+};
+use once_cell::sync::OnceCell;
+use polkavm::CallError;
+use polkavm_move_native::host::ProgramError;
+use serde_json::{json, Value};
+
+const OUTPUT: &str = "output/split/modules.polkavm";
+
+static COMPILE_ONCE: OnceCell<SplitManifest> = OnceCell::new();
+
+fn split_manifest_once() -> SplitManifest {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            let build_options =
+                package_build_options(OUTPUT, "../../examples/entry-modules/", HashSet::new())
+                    .expect("Failed to read the package")
+                    .emit_abi(true)
+                    .split_modules(true);
+            build_polka_from_move(build_options).expect("Failed to compile");
+            SplitManifest::read(&manifest_path(Path::new(OUTPUT)))
+                .expect("Failed to read the manifest")
+        })
+        .clone()
+}
+
+#[test]
+pub fn every_module_gets_a_blob() -> anyhow::Result<()> {
+    let manifest = split_manifest_once();
+    let modules: Vec<_> = manifest.modules.iter().map(|m| m.module.as_str()).collect();
+    assert_eq!(modules, ["0xa000::first", "0xa000::second"]);
+    for (module, name) in manifest
+        .modules
+        .iter()
+        .zip(["0xa000_first", "0xa000_second"])
+    {
+        let blob_path = module_blob_path(Path::new(OUTPUT), name);
+        assert_eq!(
+            module.blob,
+            blob_path.file_name().unwrap().to_string_lossy()
+        );
+        let blob = parse_to_blob(&fs::read(&blob_path)?)?;
+        assert_eq!(blob.exports().count(), module.exports.len());
+        assert!(module.exports.contains(&"call".to_string()), "{module:?}");
+    }
+    Ok(())
+}
+
+#[test]
+pub fn module_blobs_dispatch_their_own_entry_functions() -> anyhow::Result<()> {
+    let manifest = split_manifest_once();
+    let abi = Abi::read(&abi_path(Path::new(OUTPUT)))?;
+    let first = manifest.module("0xa000::first").expect("no first module");
+    let second = manifest.module("0xa000::second").expect("no second module");
+    assert_eq!(
+        first.selectors.keys().collect::<Vec<_>>(),
+        ["first::product", "first::sum"]
+    );
+    assert_eq!(
+        second.selectors.keys().collect::<Vec<_>>(),
+        ["second::negate", "second::sum_twice"]
+    );
+    // the selectors are the ones of the package blob
+    for (function, selector) in first.selectors.iter().chain(&second.selectors) {
+        assert_eq!(&abi.function(function)?.selector, selector);
+    }
+    Ok(())
+}
+
+#[test]
+pub fn cross_module_calls_are_imports() {
+    let manifest = split_manifest_once();
+    let first = manifest.module("0xa000::first").expect("no first module");
+    let second = manifest.module("0xa000::second").expect("no second module");
+    assert!(first.imports.is_empty(), "{:?}", first.imports);
+    assert_eq!(second.imports.len(), 1);
+    assert_eq!(second.imports[0].module, "0xa000::first");
+    assert_eq!(second.imports[0].functions, ["first::sum"]);
+}
+
+/// Call `function` through the `call` export of the blob of the module `module_name`.
+fn call_module_blob(
+    module_name: &str,
+    function: &str,
+    args: &[Value],
+) -> anyhow::Result<Result<(), CallError<ProgramError>>> {
+    split_manifest_once();
+    let abi = Abi::read(&abi_path(Path::new(OUTPUT)))?;
+    let blob = parse_to_blob(&fs::read(module_blob_path(Path::new(OUTPUT), module_name))?)?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = abi.encode_call(function, args)?;
+    Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+}
+
+#[test]
+pub fn module_blob_runs_its_entry_functions() -> anyhow::Result<()> {
+    // `second::sum_twice` calls into `first`, whose code the blob of `second` holds
+    call_module_blob(
+        "0xa000_second",
+        "second::sum_twice",
+        &[json!(1), json!(2), json!(6)],
+    )?
+    .map_err(|e| anyhow::anyhow!("second::sum_twice failed: {e:?}"))?;
+    let result = call_module_blob(
+        "0xa000_second",
+        "second::sum_twice",
+        &[json!(1), json!(2), json!(7)],
+    )?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(4)))),
+        "Expected abort 4, got {result:?}"
+    );
+    // the entry functions of `first` aren't dispatched by the blob of `second`
+    let result = call_module_blob(
+        "0xa000_second",
+        "first::sum",
+        &[json!(1), json!(2), json!(3)],
+    )?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(2)))),
+        "Expected the unknown selector abort, got {result:?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashSet, path::Path};
+
+use move_core_types::vm_status::StatusCode;
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+    stack_trace::{stack_trace_map_path, StackTraceMap},
+};
+use polkavm::CallError;
+use polkavm_move_native::{host::ProgramError, BCS_DESERIALIZATION_FAILURE};
+
+const OUTPUT: &str = "output/stack_trace/overflow.polkavm";
+const SERIALIZE_OUTPUT: &str = "output/stack_trace/serialize.polkavm";
+
+#[test]
+pub fn test_stack_trace_on_abort() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(OUTPUT, "../../examples/overflow/", HashSet::new())?
+        .stack_traces(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let map = StackTraceMap::read(&stack_trace_map_path(Path::new(OUTPUT)))?;
+    assert!(!map.is_empty());
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "in_range", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(runtime.stack_trace, None, "no abort, no stack trace");
+
+    let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, "add_u8_overflow", ());
+    assert!(matches!(
+        result,
+        Err(CallError::User(ProgramError::Abort(code)))
+            if code == StatusCode::ARITHMETIC_ERROR as u64
+    ));
+    let frames = runtime
+        .stack_trace
+        .as_deref()
+        .expect("stack trace recorded");
+    let trace = map.symbolize(frames, None)?;
+    let functions: Vec<_> = trace.frames.iter().map(|f| f.function.as_str()).collect();
+    assert_eq!(
+        functions,
+        [
+            "0xa000::overflow::add_u8",
+            "0xa000::overflow::add_u8_overflow"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_stack_trace_on_native_abort() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        SERIALIZE_OUTPUT,
+        "../../examples/serialize/",
+        HashSet::new(),
+    )?
+    .stack_traces(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let map = StackTraceMap::read(&stack_trace_map_path(Path::new(SERIALIZE_OUTPUT)))?;
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    // the second abort gets the same trace, the first one reset the shadow stack
+    for _ in 0..2 {
+        runtime.stack_trace = None;
+        let result =
+            instance.call_typed_and_get_result::<u32, ()>(&mut runtime, "deser_malformed_bool", ());
+        assert!(matches!(
+            result,
+            Err(CallError::User(ProgramError::Abort(
+                BCS_DESERIALIZATION_FAILURE
+            )))
+        ));
+        let frames = runtime
+            .stack_trace
+            .as_deref()
+            .expect("stack trace recorded");
+        let trace = map.symbolize(frames, None)?;
+        let functions: Vec<_> = trace.frames.iter().map(|f| f.function.as_str()).collect();
+        assert_eq!(functions, ["0xa003::serialize::deser_malformed_bool"]);
+    }
+    Ok(())
+}
@@ -1,12 +1,24 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fs, path::Path};
 
+use anyhow::Context;
 use move_to_polka::{
+    abi::{abi_path, Abi},
     initialize_logger,
-    linker::{copy_bytes_to_guest, copy_to_guest, create_blob, create_instance},
+    linker::{
+        build_polka_from_move, copy_bytes_to_guest, copy_to_guest, create_blob, create_instance,
+        elf_path, package_build_options, parse_to_blob, relink, LinkOptions,
+    },
+    storage::{stored_resources, FileBackend},
 };
+use object::{Object, ObjectSection, ObjectSymbol};
 use once_cell::sync::OnceCell;
-use polkavm::ProgramBlob;
-use polkavm_move_native::types::{MoveAddress, MoveSigner, ACCOUNT_ADDRESS_LENGTH};
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::{
+    host::ProgramError,
+    storage::GlobalStorage,
+    types::{MoveAddress, MoveSigner, ABILITY_KEY, ACCOUNT_ADDRESS_LENGTH},
+};
+use serde_json::json;
 
 static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
 
@@ -291,3 +303,173 @@ pub fn test_selector() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn storage_persists_to_state_file() -> anyhow::Result<()> {
+    let state_file = "output/storage/state.json";
+    let _ = std::fs::remove_file(state_file);
+    let mut address_bytes = [1u8; ACCOUNT_ADDRESS_LENGTH];
+    address_bytes[0] = 0xab;
+    address_bytes[ACCOUNT_ADDRESS_LENGTH - 1] = 0xce;
+    let move_signer = MoveSigner(MoveAddress(address_bytes));
+
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    runtime.storage = Box::new(GlobalStorage::with_backend(Box::new(FileBackend::open(
+        state_file,
+    )?)));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+    instance
+        .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "store", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    runtime
+        .storage
+        .flush()
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    // a fresh instance sees the resource stored by the previous run
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    runtime.storage = Box::new(GlobalStorage::with_backend(Box::new(FileBackend::open(
+        state_file,
+    )?)));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+    instance
+        .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "load", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn storage_snapshot_restores_setup_state() -> anyhow::Result<()> {
+    let (mut instance, mut runtime) = create_instance(create_blob_once())?;
+    let mut address_bytes = [1u8; ACCOUNT_ADDRESS_LENGTH];
+    address_bytes[0] = 0xab;
+    address_bytes[ACCOUNT_ADDRESS_LENGTH - 1] = 0xce;
+    let move_signer = MoveSigner(MoveAddress(address_bytes));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+
+    // setup once
+    instance
+        .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "store", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let snapshot = runtime.storage.snapshot();
+    assert_eq!(snapshot.len(), 1);
+
+    // `load` moves the resource out, so it only succeeds on the setup state
+    for _ in 0..3 {
+        runtime.storage.restore(&snapshot);
+        instance
+            .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "load", (signer_address,))
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        assert!(!runtime.storage.exists(move_signer.0, TAG).unwrap());
+    }
+    let result =
+        instance.call_typed_and_get_result::<(), (u32,)>(&mut runtime, "load", (signer_address,));
+    assert!(result.is_err());
+
+    runtime.storage.restore(&snapshot);
+    assert_eq!(runtime.storage.snapshot(), snapshot);
+    Ok(())
+}
+
+#[test]
+pub fn stored_resources_are_decoded_with_the_abi() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/storage/dump/storage.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/storage/", HashSet::new())?.emit_abi(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let abi = Abi::read(&abi_path(Path::new(output)))?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let address_bytes = [0xab; ACCOUNT_ADDRESS_LENGTH];
+    let move_signer = MoveSigner(MoveAddress(address_bytes));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+    instance
+        .call_typed_and_get_result::<(), (u32,)>(&mut runtime, "store", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    let [resource] = stored_resources(runtime.storage.as_ref(), Some(&abi))
+        .try_into()
+        .map_err(|resources| anyhow::anyhow!("{resources:?}"))?;
+    assert_eq!(
+        resource.address,
+        format!("0x{}", hex::encode(address_bytes))
+    );
+    assert_eq!(resource.tag, hex::encode(TAG));
+    assert!(resource
+        .struct_tag
+        .as_deref()
+        .is_some_and(|tag| tag.ends_with("::storage::Container")));
+    assert_eq!(
+        resource.value,
+        Some(json!({ "value": 42, "inner": { "value": 69, "s": [0xca, 0xfe, 0xba, 0xbe] } }))
+    );
+    assert!(resource.bytes.is_none());
+
+    // without the layout, the stored bytes are kept
+    let [resource] = stored_resources(runtime.storage.as_ref(), None)
+        .try_into()
+        .map_err(|resources| anyhow::anyhow!("{resources:?}"))?;
+    assert!(resource.struct_tag.is_none() && resource.value.is_none());
+    assert!(resource
+        .bytes
+        .is_some_and(|bytes| bytes.starts_with("2a00000000000000")));
+    Ok(())
+}
+
+/// Clear the abilities in the type info of the struct `name` in the merged ELF object `elf`,
+/// returning the ones it had.
+fn clear_abilities(elf: &mut [u8], name: &str) -> anyhow::Result<u64> {
+    let abilities = {
+        let file = object::File::parse(&*elf)?;
+        let suffix = format!("__{name}_info");
+        let symbol = file
+            .symbols()
+            .find(|symbol| {
+                symbol
+                    .name()
+                    .is_ok_and(|n| n.starts_with("__move_rttydesc_") && n.ends_with(&suffix))
+            })
+            .with_context(|| format!("no type info of {name}"))?;
+        let section = file.section_by_index(symbol.section_index().context("undefined")?)?;
+        let (offset, _) = section.file_range().context("type info without data")?;
+        // the abilities follow the field array pointer, the field count, size and alignment
+        (offset + symbol.address() - section.address()) as usize + 32
+    };
+    let bytes = &mut elf[abilities..abilities + 8];
+    let previous = u64::from_le_bytes(bytes.try_into()?);
+    bytes.fill(0);
+    Ok(previous)
+}
+
+#[test]
+pub fn storage_rejects_a_type_without_key() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/storage/no_key.polkavm";
+    let build_options = package_build_options(output, "../../examples/storage/", HashSet::new())?
+        .emit_abi(true)
+        .emit_elf(true);
+    build_polka_from_move(build_options)?;
+    let abi = Abi::read(&abi_path(Path::new(output)))?;
+
+    // a malformed program the bytecode verifier would reject, storing a struct without `key`
+    let elf_file = elf_path(Path::new(output));
+    let mut elf = fs::read(&elf_file)?;
+    let abilities = clear_abilities(&mut elf, "Container")?;
+    assert_eq!(abilities & ABILITY_KEY, ABILITY_KEY);
+    fs::write(&elf_file, &elf)?;
+    let blob = parse_to_blob(&relink(
+        &elf_file,
+        Path::new(output),
+        &LinkOptions::default(),
+    )?)?;
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    runtime.call_data = abi.encode_call("storage::store", &[])?;
+    let result = instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ());
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::MissingAbility))),
+        "{result:?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{build_polka_from_move, create_instance, package_build_options, parse_to_blob},
+};
+
+#[test]
+pub fn test_without_compressed_instructions() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/target/uncompressed.polkavm",
+        "../../examples/multi_module/",
+        HashSet::new(),
+    )?
+    .llvm_cpu("generic-rv64")
+    .llvm_features("+e,+m,+a,-c");
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<(), ()>(&mut runtime, "main", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+pub fn loaded_llvm_matches_the_build() -> anyhow::Result<()> {
+    use move_polka_codegen::stackless::{check_llvm_version, llvm_version, LLVM_MAJOR_VERSION};
+
+    check_llvm_version()?;
+    assert_eq!(llvm_version().0, LLVM_MAJOR_VERSION);
+    assert!((18..=20).contains(&LLVM_MAJOR_VERSION));
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    initialize_logger,
+    linker::{create_colored_stdout, package_build_options},
+    run_to_polka_timed,
+    timing::PassTimings,
+};
+
+#[test]
+pub fn time_passes_covers_every_phase() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/timing/void.polkavm",
+        "../../examples/void/",
+        HashSet::new(),
+    )?
+    .time_passes(true)
+    .build();
+    let mut timings = PassTimings::default();
+    run_to_polka_timed(&mut create_colored_stdout(), &options, &mut timings)?;
+    for phase in [
+        "model building",
+        "check",
+        "stackless generation",
+        "object emission",
+        "lld merge",
+        "polka linking",
+    ] {
+        assert!(timings.get(phase).is_some(), "no {phase} in:\n{timings}");
+    }
+    assert!(
+        timings
+            .passes
+            .iter()
+            .any(|pass| pass.name.starts_with("translate ") && pass.name.contains("void")),
+        "no module translation in:\n{timings}"
+    );
+    assert!(!timings.total().is_zero());
+    let report = timings.to_string();
+    assert!(
+        report.contains("total") && report.contains("peak RSS"),
+        "{report}"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn time_passes_with_check_stops_after_check() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/timing/check.polkavm",
+        "../../examples/void/",
+        HashSet::new(),
+    )?
+    .check(true)
+    .build();
+    let mut timings = PassTimings::default();
+    run_to_polka_timed(&mut create_colored_stdout(), &options, &mut timings)?;
+    let phases = timings
+        .passes
+        .iter()
+        .map(|pass| pass.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(phases, ["model building", "check"]);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use move_to_polka::{
+    fuzz::Outcome,
+    initialize_logger,
+    linker::{
+        build_polka_from_move, create_stepping_instance, package_build_options, parse_to_blob,
+    },
+    trace::{record, replay, Trace},
+};
+use once_cell::sync::OnceCell;
+use polkavm::ProgramBlob;
+use polkavm_move_native::host::MockRandom;
+
+static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+// 2024-06-01 00:00:00 UTC
+const NOW_MS: u64 = 1_717_200_000_000;
+
+fn create_blob_once() -> ProgramBlob {
+    COMPILE_ONCE
+        .get_or_init(|| {
+            initialize_logger();
+            // revive::random::bytes is only served by the mock host
+            let options = package_build_options(
+                "output/trace/lottery.polkavm",
+                "../../examples/lottery/",
+                HashSet::new(),
+            )
+            .expect("Failed to resolve the package")
+            .mock_host(true);
+            let program_bytes = build_polka_from_move(options)
+                .expect("Failed to compile Move source to PolkaVM bytecode");
+            parse_to_blob(&program_bytes).expect("Failed to parse the blob")
+        })
+        .clone()
+}
+
+fn record_draw() -> anyhow::Result<Trace> {
+    let (mut instance, mut runtime) = create_stepping_instance(create_blob_once())?;
+    runtime.timestamp_ms = NOW_MS;
+    runtime.random = MockRandom::new(7);
+    let (trace, result) = record(&mut instance, &mut runtime, "draw", &[])?;
+    assert!(result.is_ok(), "{result:?}");
+    Ok(trace)
+}
+
+#[test]
+pub fn test_replay_recorded_trace() -> anyhow::Result<()> {
+    let trace = record_draw()?;
+    assert_eq!(trace.outcome, Outcome::Success);
+    assert!(!trace.host_calls.is_empty());
+    assert!(trace.steps > 0);
+
+    let path = std::env::temp_dir().join("move-to-polka-draw.trace.json");
+    trace.write(&path)?;
+    let trace = Trace::read(&path)?;
+
+    // no mocks: time and randomness come from the trace
+    let (mut instance, _) = create_stepping_instance(create_blob_once())?;
+    let replay = replay(&mut instance, &trace)?;
+    assert_eq!(replay.divergence, None);
+    assert_eq!(replay.host_calls, trace.host_calls.len());
+    assert_eq!(replay.steps, trace.steps);
+    assert_eq!(replay.outcome, Outcome::Success);
+    Ok(())
+}
+
+#[test]
+pub fn test_replay_reports_divergence() -> anyhow::Result<()> {
+    let mut trace = record_draw()?;
+    trace.host_calls[0].args[0] ^= 1;
+
+    let (mut instance, _) = create_stepping_instance(create_blob_once())?;
+    let divergence = replay(&mut instance, &trace)?
+        .divergence
+        .expect("the replay should diverge");
+    assert_eq!(divergence.host_call, 0);
+    assert_eq!(divergence.step, trace.host_calls[0].step);
+    assert!(divergence.message.starts_with("expected "), "{divergence}");
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use move_core_types::vm_status::StatusCode;
+use move_to_polka::{
+    compile_to_ir_strings, get_env_from_source, initialize_logger,
+    linker::{
+        build_polka_from_move, create_colored_stdout, create_instance, package_build_options,
+        parse_to_blob,
+    },
+};
+use once_cell::sync::OnceCell;
+use polkavm::{CallError, ProgramBlob};
+use polkavm_move_native::host::ProgramError;
+
+const ARITHMETIC_ERROR: u64 = StatusCode::ARITHMETIC_ERROR as u64;
+
+static INLINE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+static HELPERS_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
+
+fn build_blob(output: &str, u256_helpers: bool) -> ProgramBlob {
+    initialize_logger();
+    let build_options = package_build_options(output, "../../examples/u256/", HashSet::new())
+        .expect("Failed to read the package")
+        .u256_helpers(u256_helpers);
+    parse_to_blob(&build_polka_from_move(build_options).expect("Failed to compile"))
+        .expect("Failed to parse the blob")
+}
+
+/// The example compiled with `i256` LLVM operations and with the runtime helpers.
+fn blobs() -> [ProgramBlob; 2] {
+    [
+        INLINE_ONCE
+            .get_or_init(|| build_blob("output/u256/inline.polkavm", false))
+            .clone(),
+        HELPERS_ONCE
+            .get_or_init(|| build_blob("output/u256/helpers.polkavm", true))
+            .clone(),
+    ]
+}
+
+#[test]
+pub fn test_u256_operations() -> anyhow::Result<()> {
+    for blob in blobs() {
+        let (mut instance, mut runtime) = create_instance(blob)?;
+        instance
+            .call_typed_and_get_result::<u32, ()>(&mut runtime, "run", ())
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+    Ok(())
+}
+
+#[test]
+pub fn test_u256_arithmetic_errors() -> anyhow::Result<()> {
+    for blob in blobs() {
+        for entry in [
+            "add_overflow",
+            "sub_underflow",
+            "mul_overflow",
+            "div_by_zero",
+            "cast_overflow",
+        ] {
+            let (mut instance, mut runtime) = create_instance(blob.clone())?;
+            let result = instance.call_typed_and_get_result::<u32, ()>(&mut runtime, entry, ());
+            assert!(
+                matches!(
+                    result,
+                    Err(CallError::User(ProgramError::Abort(ARITHMETIC_ERROR)))
+                ),
+                "Expected {entry} to abort with ARITHMETIC_ERROR, got {result:?}"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+pub fn test_u256_helpers_replace_i256_arithmetic() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = package_build_options(
+        "output/u256/ir.polkavm",
+        "../../examples/u256/",
+        HashSet::new(),
+    )?
+    .u256_helpers(true)
+    .build();
+    let env = get_env_from_source(&mut create_colored_stdout(), &options)?;
+    let modules = compile_to_ir_strings(&env, &options)?;
+    let (_, ir) = modules
+        .iter()
+        .find(|(name, _)| name.contains("u256"))
+        .expect("u256 module not translated");
+    for helper in [
+        "add256", "sub256", "mul256", "div256", "mod256", "cmp256", "zext256", "trunc256",
+    ] {
+        assert!(
+            ir.contains(&format!("@move_rt_{helper}(")),
+            "no call of {helper} in:\n{ir}"
+        );
+    }
+    for op in ["add i256", "mul i256", "udiv i256", "icmp ult i256"] {
+        assert!(!ir.contains(op), "{op} left in:\n{ir}");
+    }
+    Ok(())
+}
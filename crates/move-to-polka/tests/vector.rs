@@ -2,10 +2,11 @@ use std::collections::HashSet;
 
 use move_to_polka::{
     initialize_logger,
-    linker::{create_blob, create_instance},
+    linker::{copy_to_guest, create_blob, create_instance},
 };
 use once_cell::sync::OnceCell;
 use polkavm::ProgramBlob;
+use polkavm_move_native::types::{MoveAddress, MoveSigner, ACCOUNT_ADDRESS_LENGTH};
 
 static COMPILE_ONCE: OnceCell<ProgramBlob> = OnceCell::new();
 
@@ -187,3 +188,53 @@ pub fn test_vector_filter() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn test_vector_literal() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "literal", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_vector_nestedliteral() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "nestedliteral", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_vector_structliteral() -> anyhow::Result<()> {
+    let blob = create_blob_once();
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    instance
+        .call_typed_and_get_result::<u32, ()>(&mut runtime, "structliteral", ())
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_vector_copies() -> anyhow::Result<()> {
+    initialize_logger();
+    let blob = create_blob(
+        "output/vector/vec_copy.polkavm",
+        "../../examples/vec-copy/",
+        HashSet::new(),
+    )?;
+    let (mut instance, mut runtime) = create_instance(blob)?;
+    let move_signer = MoveSigner(MoveAddress([1u8; ACCOUNT_ADDRESS_LENGTH]));
+    let signer_address = copy_to_guest(&mut instance, &mut runtime.allocator, &move_signer)?;
+    instance
+        .call_typed_and_get_result::<(), _>(&mut runtime, "run", (signer_address,))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use move_polka_codegen::stackless::{Context, FunctionType, VerificationError};
+
+#[test]
+pub fn invalid_ir_is_reported_with_the_offending_function() -> anyhow::Result<()> {
+    let llvm_cx = Context::new();
+    let module = llvm_cx.create_module("broken");
+    let ty = FunctionType::new(llvm_cx.void_type(), &[]);
+    let valid = module.add_function("native", "valid", ty);
+    let builder = llvm_cx.create_builder();
+    builder.position_at_end(valid.append_basic_block("entry"));
+    builder.build_return_void();
+    let invalid = module.add_function("native", "no_terminator", ty);
+    invalid.append_basic_block("entry");
+
+    let error = module
+        .verify()
+        .expect_err("a block without terminator is rejected")
+        .downcast::<VerificationError>()?;
+    assert_eq!(error.module, "broken");
+    assert!(!error.message.is_empty());
+    let [(name, ir)] = error.functions.as_slice() else {
+        panic!("{error}");
+    };
+    assert_eq!(name, "no_terminator");
+    assert!(ir.contains("define void @no_terminator"), "{ir}");
+    assert!(error.to_string().contains("in function no_terminator"));
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use move_to_polka::{
+    compile_to_ir_strings,
+    error::MoveToPolkaError,
+    get_env_and_diagnostics_from_memory, get_env_from_memory, initialize_logger,
+    linker::{create_colored_stdout, BuildOptions},
+    vfs::VirtualFiles,
+};
+
+const SNIPPET: &str = "module 0xa000::snippet {
+    public entry fun run() {
+        let x = 40u64;
+        assert!(x + 2 == 42, 1);
+    }
+}";
+
+#[test]
+pub fn in_memory_sources_compile() -> anyhow::Result<()> {
+    initialize_logger();
+    let options = BuildOptions::new("output/vfs/snippet.polkavm").build();
+    let files = VirtualFiles::new()
+        .file("sources/snippet.move", SNIPPET)
+        .file(
+            "sources/nested/other.move",
+            "module 0xa000::other { public fun one(): u64 { 1 } }",
+        );
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    let modules = compile_to_ir_strings(&env, &options)?;
+    let (_, ir) = modules
+        .iter()
+        .find(|(name, _)| name.contains("snippet"))
+        .expect("snippet module not translated");
+    assert!(
+        ir.contains("define") && ir.contains("run"),
+        "no `run` in:\n{ir}"
+    );
+    assert!(modules.iter().any(|(name, _)| name.contains("other")));
+
+    // Compiling again replaces the staged files of the previous compilation.
+    let files = VirtualFiles::new().file("sources/snippet.move", SNIPPET);
+    let env = get_env_from_memory(&mut create_colored_stdout(), &options, &files)?;
+    let modules = compile_to_ir_strings(&env, &options)?;
+    assert!(!modules.iter().any(|(name, _)| name.contains("other")));
+    Ok(())
+}
+
+#[test]
+pub fn in_memory_source_errors_are_reported() {
+    initialize_logger();
+    let options = BuildOptions::new("output/vfs/broken.polkavm").build();
+    let files = VirtualFiles::new().file(
+        "sources/broken.move",
+        "module 0xa000::broken { public fun f(): u64 { true } }",
+    );
+    let (result, diagnostics) =
+        get_env_and_diagnostics_from_memory(&mut create_colored_stdout(), &options, &files);
+    assert!(
+        matches!(result, Err(MoveToPolkaError::Compile(_))),
+        "{result:?}"
+    );
+    assert!(
+        diagnostics.iter().any(|d| d
+            .location
+            .as_deref()
+            .is_some_and(|location| location.contains("broken.move:1:"))),
+        "{diagnostics:?}"
+    );
+}
+
+#[test]
+pub fn in_memory_paths_must_stay_in_the_staging_dir() {
+    initialize_logger();
+    let options = BuildOptions::new("output/vfs/escape.polkavm").build();
+    let staged = VirtualFiles::new().file("sources/snippet.move", SNIPPET);
+    get_env_from_memory(&mut create_colored_stdout(), &options, &staged)
+        .expect("Failed to compile the snippet");
+    let staged_file = VirtualFiles::staging_dir(&options.output).join("sources/snippet.move");
+    for path in ["../escape.move", "/tmp/escape.move"] {
+        let files = staged.clone().file(path, SNIPPET);
+        let result = get_env_from_memory(&mut create_colored_stdout(), &options, &files);
+        assert!(
+            matches!(&result, Err(MoveToPolkaError::Compile(e)) if e.contains("relative path")),
+            "{result:?}"
+        );
+        // rejected before the files of the previous compilation are removed
+        assert!(staged_file.is_file());
+    }
+    let result = get_env_from_memory(&mut create_colored_stdout(), &options, &VirtualFiles::new());
+    assert!(matches!(result, Err(MoveToPolkaError::Compile(_))));
+}
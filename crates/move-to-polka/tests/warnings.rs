@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+
+use codespan_reporting::diagnostic::Severity;
+use move_to_polka::{
+    error::MoveToPolkaError,
+    initialize_logger,
+    linker::{build_polka_from_move, package_build_options, package_diagnostics},
+};
+
+#[test]
+pub fn warnings_are_returned_as_diagnostics() -> anyhow::Result<()> {
+    initialize_logger();
+    let diagnostics = package_diagnostics("../../examples/warnings/", HashSet::new())?;
+    let warning = diagnostics
+        .iter()
+        .find(|d| d.is_warning())
+        .expect("expected an unused variable warning");
+    assert!(warning.message.contains("unused"), "{warning:?}");
+    assert!(
+        warning
+            .location
+            .as_deref()
+            .is_some_and(|location| location.contains("warnings.move:4:")),
+        "{warning:?}"
+    );
+    assert!(diagnostics.iter().all(|d| d.severity < Severity::Error));
+    Ok(())
+}
+
+#[test]
+pub fn warnings_do_not_fail_the_build_by_default() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/warnings/default.polkavm",
+        "../../examples/warnings/",
+        HashSet::new(),
+    )?
+    .quiet_warnings(true);
+    build_polka_from_move(build_options)?;
+    Ok(())
+}
+
+#[test]
+pub fn warn_as_error_fails_the_build() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(
+        "output/warnings/warn_as_error.polkavm",
+        "../../examples/warnings/",
+        HashSet::new(),
+    )?
+    .warn_as_error(true);
+    let result = build_polka_from_move(build_options);
+    assert!(
+        matches!(&result, Err(MoveToPolkaError::Compile(e)) if e.contains("--warn-as-error")),
+        "Expected a compile error, got {result:?}"
+    );
+    Ok(())
+}
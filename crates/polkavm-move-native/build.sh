@@ -11,7 +11,7 @@ function build() {
     cargo rustc \
         --crate-type=staticlib \
         -Z build-std=core,alloc \
-        --target riscv32emac-unknown-none-polkavm.json \
+        --target riscv64emac-unknown-none-polkavm.json \
         --release \
         --features "polkavm" \
         --verbose \
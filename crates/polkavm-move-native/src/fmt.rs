@@ -0,0 +1,132 @@
+//! Integer formatting for the panic and abort paths of the guest.
+//!
+//! `core::fmt` is large, on rv32e in particular, and every contract would pay for it if the
+//! panic handler formatted its message. These helpers write integers as decimal or hex into
+//! a fixed buffer instead, so the runtime never pulls in the formatting machinery.
+
+/// Longest decimal representation of a `u128`.
+const MAX_DECIMAL_DIGITS: usize = 39;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// A message written into a fixed buffer. Whatever doesn't fit is dropped.
+pub struct Message<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Message<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn push_str(&mut self, s: &str) -> &mut Self {
+        self.push_bytes(s.as_bytes())
+    }
+
+    /// Append `value` in decimal.
+    pub fn push_u64(&mut self, value: u64) -> &mut Self {
+        let mut digits = [0; MAX_DECIMAL_DIGITS];
+        let start = write_decimal_u64(value, &mut digits);
+        self.push_bytes(&digits[start..])
+    }
+
+    /// Append `value` in decimal.
+    pub fn push_u128(&mut self, value: u128) -> &mut Self {
+        // values that fit a u64 don't need the u128 division libcall
+        match u64::try_from(value) {
+            Ok(value) => self.push_u64(value),
+            Err(_) => {
+                let mut digits = [0; MAX_DECIMAL_DIGITS];
+                let start = write_decimal_u128(value, &mut digits);
+                self.push_bytes(&digits[start..])
+            }
+        }
+    }
+
+    /// Append `value` in hex, with a `0x` prefix and without leading zeros.
+    pub fn push_hex_u64(&mut self, value: u64) -> &mut Self {
+        self.push_hex_u128(value.into())
+    }
+
+    /// Append `value` in hex, with a `0x` prefix and without leading zeros.
+    pub fn push_hex_u128(&mut self, value: u128) -> &mut Self {
+        let mut digits = [0; 32];
+        let mut start = digits.len();
+        let mut rest = value;
+        loop {
+            start -= 1;
+            digits[start] = HEX_DIGITS[(rest & 0xf) as usize];
+            rest >>= 4;
+            if rest == 0 {
+                break;
+            }
+        }
+        self.push_str("0x").push_bytes(&digits[start..])
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// The message as a string, cut short at the last complete character if truncated.
+    pub fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.as_bytes()) {
+            Ok(s) => s,
+            // only `push_str` can cut a character, the prefix up to it is valid
+            Err(e) => unsafe { core::str::from_utf8_unchecked(&self.buf[..e.valid_up_to()]) },
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        let len = bytes.len().min(N - self.len);
+        self.buf[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+        self.len += len;
+        self
+    }
+}
+
+impl<const N: usize> Default for Message<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write `value` right aligned into `digits`, returning the index of its first digit.
+fn write_decimal_u64(mut value: u64, digits: &mut [u8; MAX_DECIMAL_DIGITS]) -> usize {
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            return start;
+        }
+    }
+}
+
+/// Like [`write_decimal_u64`], for values above `u64::MAX`: the value is split into chunks
+/// of 19 digits, each formatted with u64 arithmetic.
+fn write_decimal_u128(value: u128, digits: &mut [u8; MAX_DECIMAL_DIGITS]) -> usize {
+    const CHUNK: u128 = 10_000_000_000_000_000_000;
+    let mut start = digits.len();
+    let mut rest = value;
+    while rest >= CHUNK {
+        let chunk = (rest % CHUNK) as u64;
+        rest /= CHUNK;
+        let mut chunk_digits = [b'0'; MAX_DECIMAL_DIGITS];
+        write_decimal_u64(chunk, &mut chunk_digits);
+        // every chunk but the leading one is zero padded to 19 digits
+        start -= 19;
+        digits[start..start + 19].copy_from_slice(&chunk_digits[MAX_DECIMAL_DIGITS - 19..]);
+    }
+    let mut leading = [0; MAX_DECIMAL_DIGITS];
+    let leading_start = write_decimal_u64(rest as u64, &mut leading);
+    let len = MAX_DECIMAL_DIGITS - leading_start;
+    start -= len;
+    digits[start..start + len].copy_from_slice(&leading[leading_start..]);
+    start
+}
@@ -1,24 +1,256 @@
-use core::alloc::{GlobalAlloc, Layout};
+//! The guest heap allocator.
+//!
+//! Blocks of up to `MAX_SMALL` bytes are rounded up to a power of two, and once freed kept on a
+//! free list per size class. Larger blocks are rounded up to `BLOCK_ALIGN` and kept on a single
+//! first-fit list, which splits them on reuse. New blocks are bumped off the end of the used
+//! part of the heap. The block at the end is freed by moving the end back, and reallocated by
+//! moving it forward, so a vector growing in a loop keeps growing its own buffer instead of
+//! leaving every outgrown buffer behind.
+//!
+//! Blocks carry no header: like any `GlobalAlloc`, the allocator relies on `dealloc` and
+//! `realloc` being passed the layout the block was allocated with.
+//!
+//! Not every pointer freed by generated code points into the heap: the host hands out buffers
+//! in aux memory (hash digests, `move_from` results, ...) that end up in vectors like any other.
+//! Those are never put on a free list, since the host may write to them again; freeing them
+//! leaks them, as the bump allocator this replaced leaked everything.
 
-use crate::HEAP_BASE;
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::{self, addr_of, addr_of_mut, null_mut},
+};
 
+use crate::HeapStats;
+
+extern "C" {
+    // Reserved by the compiler in the RW data, see `Module::add_memory_config`.
+    static mut move_rt_heap: u8;
+    static move_rt_heap_size: u32;
+}
+
+/// Alignment of every block, and the granularity of large blocks. Requests aligned to more
+/// are bumped, never served from a free list.
+const BLOCK_ALIGN: usize = 16;
+const MIN_SMALL_SHIFT: u32 = 4;
+const MAX_SMALL_SHIFT: u32 = 11;
+const MAX_SMALL: usize = 1 << MAX_SMALL_SHIFT;
+const SMALL_CLASSES: usize = (MAX_SMALL_SHIFT - MIN_SMALL_SHIFT + 1) as usize;
+
+/// Offset of the end of the used part of the heap.
 static mut OFFSET: u32 = 0;
+/// Heads of the free lists of small blocks, linked through their first word.
+static mut SMALL_FREE: [*mut u8; SMALL_CLASSES] = [null_mut(); SMALL_CLASSES];
+/// Head of the free list of large blocks.
+static mut LARGE_FREE: *mut FreeBlock = null_mut();
+static mut STATS: HeapStats = HeapStats {
+    in_use: 0,
+    peak: 0,
+    allocations: 0,
+    frees: 0,
+    reallocs_in_place: 0,
+    reallocs_moved: 0,
+};
+
+/// A free large block.
+#[repr(C)]
+struct FreeBlock {
+    next: *mut FreeBlock,
+    size: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// A block of `1 << (class + MIN_SMALL_SHIFT)` bytes.
+    Small(usize),
+    /// A block of the given size, a multiple of `BLOCK_ALIGN`.
+    Large(usize),
+}
+
+impl Block {
+    fn of(layout: Layout) -> Self {
+        let size = layout.size().max(layout.align());
+        if size <= MAX_SMALL {
+            let shift = size
+                .next_power_of_two()
+                .trailing_zeros()
+                .max(MIN_SMALL_SHIFT);
+            Block::Small((shift - MIN_SMALL_SHIFT) as usize)
+        } else {
+            Block::Large((size + BLOCK_ALIGN - 1) & !(BLOCK_ALIGN - 1))
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Block::Small(class) => 1 << (class as u32 + MIN_SMALL_SHIFT),
+            Block::Large(size) => size,
+        }
+    }
+}
+
+/// The counters of the allocator, see [`HeapStats`].
+pub(crate) fn stats() -> *const HeapStats {
+    // Safety: the guest is single threaded.
+    unsafe { addr_of!(STATS) }
+}
+
+/// The used part of the heap, from its start to the end of the last allocated block.
+pub(crate) fn used_heap() -> core::ops::Range<usize> {
+    // Safety: the guest is single threaded.
+    unsafe {
+        let base = heap_base() as usize;
+        base..base + OFFSET as usize
+    }
+}
+
+unsafe fn heap_base() -> *mut u8 {
+    addr_of_mut!(move_rt_heap)
+}
 
-pub struct BumpAlloc;
+/// Whether `ptr` points into the heap, rather than at memory of the host or the stack.
+unsafe fn is_heap(ptr: *mut u8) -> bool {
+    let base = heap_base() as usize;
+    (base..base + *addr_of!(move_rt_heap_size) as usize).contains(&(ptr as usize))
+}
+
+/// Whether the block at `ptr` of `size` bytes ends where the used part of the heap ends.
+unsafe fn is_last(ptr: *mut u8, size: usize) -> bool {
+    ptr as usize + size == heap_base() as usize + OFFSET as usize
+}
+
+/// Move the end of the used part of the heap to `end`, if the heap is that large.
+unsafe fn set_end(end: usize) -> bool {
+    if end > *addr_of!(move_rt_heap_size) as usize {
+        return false;
+    }
+    OFFSET = end as u32;
+    STATS.peak = STATS.peak.max(OFFSET);
+    true
+}
+
+/// A new block at the end of the used part of the heap.
+unsafe fn bump(size: usize, align: usize) -> *mut u8 {
+    let base = heap_base();
+    // align the address, not the offset, so alignments above the heap's own are honoured
+    let cursor = base as usize + OFFSET as usize;
+    let aligned = (cursor + align - 1) & !(align - 1);
+    let offset = aligned - base as usize;
+    match offset.checked_add(size) {
+        Some(end) if set_end(end) => base.add(offset),
+        // out of heap, handled as an allocation error
+        _ => null_mut(),
+    }
+}
+
+/// Take a free large block of at least `size` bytes, splitting off the rest.
+unsafe fn take_large(size: usize) -> *mut u8 {
+    let mut link = addr_of_mut!(LARGE_FREE);
+    while !(*link).is_null() {
+        let block = *link;
+        if (*block).size >= size {
+            let rest = (*block).size - size;
+            *link = if rest == 0 {
+                (*block).next
+            } else {
+                let tail = (block as *mut u8).add(size) as *mut FreeBlock;
+                tail.write(FreeBlock {
+                    next: (*block).next,
+                    size: rest,
+                });
+                tail
+            };
+            return block as *mut u8;
+        }
+        link = addr_of_mut!((*block).next);
+    }
+    null_mut()
+}
+
+unsafe fn release(ptr: *mut u8, block: Block) {
+    if is_last(ptr, block.size()) {
+        OFFSET = (ptr as usize - heap_base() as usize) as u32;
+        return;
+    }
+    match block {
+        Block::Small(class) => {
+            (ptr as *mut *mut u8).write(SMALL_FREE[class]);
+            SMALL_FREE[class] = ptr;
+        }
+        Block::Large(size) => {
+            let free = ptr as *mut FreeBlock;
+            free.write(FreeBlock {
+                next: LARGE_FREE,
+                size,
+            });
+            LARGE_FREE = free;
+        }
+    }
+}
+
+pub struct FreeListAlloc;
 
-unsafe impl GlobalAlloc for BumpAlloc {
+unsafe impl GlobalAlloc for FreeListAlloc {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let size = layout.size() as u32;
-        let align = layout.align() as u32;
-        let cursor = OFFSET;
-        let aligned = (cursor + align - 1) & !(align - 1);
-        let new_end = aligned + size;
-        OFFSET = new_end;
-        (HEAP_BASE + aligned) as *mut u8
+        let block = Block::of(layout);
+        let reusable = layout.align() <= BLOCK_ALIGN;
+        let ptr = match block {
+            Block::Small(class) if reusable && !SMALL_FREE[class].is_null() => {
+                let ptr = SMALL_FREE[class];
+                SMALL_FREE[class] = (ptr as *mut *mut u8).read();
+                ptr
+            }
+            Block::Large(size) if reusable => match take_large(size) {
+                ptr if ptr.is_null() => bump(size, BLOCK_ALIGN),
+                ptr => ptr,
+            },
+            _ => bump(block.size(), layout.align().max(BLOCK_ALIGN)),
+        };
+        if !ptr.is_null() {
+            STATS.allocations += 1;
+            STATS.in_use += layout.size() as u32;
+        }
+        ptr
     }
 
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if !is_heap(ptr) {
+            return;
+        }
+        STATS.frees += 1;
+        STATS.in_use -= layout.size() as u32;
+        release(ptr, Block::of(layout));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        if !is_heap(ptr) {
+            // copy out of memory the host owns, leaving it behind
+            let new_ptr = self.alloc(new_layout);
+            if !new_ptr.is_null() {
+                ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            }
+            return new_ptr;
+        }
+        let old_block = Block::of(layout);
+        let new_block = Block::of(new_layout);
+        // the block fits already, or is the last one and can be resized up to the heap's end
+        let in_place = old_block == new_block
+            || (is_last(ptr, old_block.size())
+                && set_end(ptr as usize - heap_base() as usize + new_block.size()));
+        if in_place {
+            STATS.reallocs_in_place += 1;
+            STATS.in_use = STATS.in_use - layout.size() as u32 + new_size as u32;
+            return ptr;
+        }
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            STATS.reallocs_moved += 1;
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
 }
 
 #[global_allocator]
-static GLOBAL: BumpAlloc = BumpAlloc;
+static GLOBAL: FreeListAlloc = FreeListAlloc;
@@ -1,8 +1,11 @@
-use crate::types::{AnyValue, MoveByteVector, MoveType};
+use crate::{
+    types::{AnyValue, MoveByteVector, MoveType},
+    HeapStats,
+};
 
 #[polkavm_derive::polkavm_import]
 extern "C" {
-    pub(crate) fn terminate(beneficiary: *const [u8; 20]);
+    pub(crate) fn seal_return(flags: u32, data_ptr: *const u8, data_len: u32);
 }
 
 #[polkavm_derive::polkavm_import]
@@ -10,6 +13,45 @@ extern "C" {
     pub(crate) fn debug_print(t: *const MoveType, v: *const AnyValue);
 }
 
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn log(level: u32, msg: *const u8, len: u64);
+}
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn balance_of(address: *const u8, out: *mut u8);
+}
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn value_transferred(out: *mut u8);
+}
+
+/// The pallet-revive `call` import. Pointer arguments are packed in pairs as
+/// `(hi << 32) | lo`, see `pack_hi_lo`.
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn call(
+        flags_and_callee: u64,
+        ref_time_limit: u64,
+        proof_size_limit: u64,
+        deposit_and_value: u64,
+        input_data: u64,
+        output_data: u64,
+    ) -> u32;
+}
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn return_data_size() -> u64;
+}
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn return_data_copy(out_ptr: *mut u8, out_len_ptr: *mut u32, offset: u32);
+}
+
 #[polkavm_derive::polkavm_import]
 extern "C" {
     pub(crate) fn hash_sha2_256(v: *const MoveByteVector) -> u32;
@@ -57,3 +99,31 @@ extern "C" {
 extern "C" {
     pub(crate) fn hex_dump();
 }
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn coverage_register(counters: *const u32, len: u32);
+}
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn heap_stats_register(stats: *const HeapStats);
+}
+
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn stack_trace(frames: *const u32, depth: u32);
+}
+
+/// The pallet-revive `now` import: the block timestamp in seconds, as a 32 byte little
+/// endian integer.
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn now(out: *mut u8);
+}
+
+/// Mock randomness. pallet-revive has no such import, only the mock host serves it.
+#[polkavm_derive::polkavm_import]
+extern "C" {
+    pub(crate) fn random_bytes(out: *mut u8, len: u32);
+}
@@ -1,9 +1,12 @@
 use crate::{
     types::{
         AnyValue, MoveAddress, MoveAsciiString, MoveByteVector, MoveSigner, MoveType,
-        MoveUntypedVector,
+        MoveUntypedVector, ABILITY_KEY, U256,
     },
     vector::{TypedMoveBorrowedRustVec, TypedMoveBorrowedRustVecMut},
+    BCS_DESERIALIZATION_FAILURE, CALL_FAILURE_CODE, INVALID_REFERENCE_CODE, MISSING_ABILITY_CODE,
+    NOT_PAYABLE_CODE, RETURN_FLAG_REVERT, REVIVE_SENTINEL, REVIVE_VALUE_LENGTH,
+    TRANSFER_FAILURE_CODE,
 };
 extern crate alloc;
 use core::str;
@@ -13,23 +16,31 @@ mod imports;
 mod panic;
 mod polkavm_imports;
 
-#[macro_export]
-macro_rules! heapless_format {
-    ($($arg:tt)*) => {{
-        use heapless::String;
-        use core::fmt::Write;
+/// Revert the call, returning the abort code as return data (`u64` little endian).
+#[export_name = "move_rt_abort"]
+unsafe extern "C" fn move_rt_abort(code: u64) {
+    trace_abort();
+    let data = code.to_le_bytes();
+    imports::seal_return(RETURN_FLAG_REVERT, data.as_ptr(), data.len() as u32);
+}
 
-        let mut s: String<256> = String::new();
-        let _ = write!(&mut s, $($arg)*);
-        s
-    }};
+/// Revert the call for a failure of the runtime itself, see [`crate::runtime_failure_data`].
+unsafe fn runtime_failure(code: u64) {
+    trace_abort();
+    let data = crate::runtime_failure_data(code);
+    imports::seal_return(RETURN_FLAG_REVERT, data.as_ptr(), data.len() as u32);
 }
 
-#[export_name = "move_rt_abort"]
-unsafe extern "C" fn move_rt_abort(code: u64) {
-    let mut beneficiary = [0u8; 20];
-    beneficiary[0] = code as u8;
-    imports::terminate(beneficiary.as_ptr() as *const [u8; 20]);
+#[export_name = "move_rt_assert"]
+unsafe extern "C" fn move_rt_assert(cond: bool, code: u64) {
+    if !cond {
+        move_rt_abort(code);
+    }
+}
+
+#[export_name = "move_rt_log"]
+unsafe extern "C" fn move_rt_log(level: u32, msg_ptr: *const u8, len: u64) {
+    imports::log(level, msg_ptr, len);
 }
 
 #[export_name = "move_native_debug_print"]
@@ -42,6 +53,171 @@ unsafe extern "C" fn hex_dump() {
     imports::hex_dump();
 }
 
+/// Called on entry of exported functions in coverage builds.
+#[export_name = "move_rt_coverage_register"]
+unsafe extern "C" fn move_rt_coverage_register(counters: *const u32, len: u32) {
+    imports::coverage_register(counters, len);
+}
+
+/// Called on entry of exported functions in `--heap-stats` builds.
+#[export_name = "move_rt_heap_stats_register"]
+unsafe extern "C" fn move_rt_heap_stats_register() {
+    imports::heap_stats_register(allocator::stats());
+}
+
+/// Lowest address of the program's data, the bottom of the PolkaVM address space.
+const DATA_START: usize = 0x10000;
+
+/// End of the PolkaVM address space, the last 64 KiB of 4 GiB are never mapped. The
+/// auxiliary data ends here, the stack right below it.
+const ADDRESS_SPACE_TOP: usize = 0xffff_0000;
+
+/// Called before every dereference in `--paranoid` builds. `ptr` must point into the data
+/// sections or the used part of the heap, which follows them, or between this call's own
+/// frame and the top of the address space: into the live part of the stack or the auxiliary
+/// data the host writes values to. Blocks freed inside the used part of the heap pass, the
+/// allocator doesn't keep track of them.
+#[export_name = "move_rt_check_ref"]
+unsafe extern "C" fn move_rt_check_ref(ptr: *const u8) {
+    let address = ptr as usize;
+    let stack_pointer = core::ptr::addr_of!(address) as usize;
+    let in_data = (DATA_START..allocator::used_heap().end).contains(&address);
+    let in_stack = (stack_pointer..ADDRESS_SPACE_TOP).contains(&address);
+    if !(in_data || in_stack) {
+        runtime_failure(INVALID_REFERENCE_CODE);
+    }
+}
+
+/// Maximum depth of the shadow stack of `--stack-traces` builds; deeper frames are counted
+/// but not recorded.
+const SHADOW_STACK_LEN: usize = 64;
+
+/// `(function id, caller pc)` of every active Move function, innermost last.
+static mut SHADOW_STACK: [[u32; 2]; SHADOW_STACK_LEN] = [[0; 2]; SHADOW_STACK_LEN];
+static mut SHADOW_STACK_DEPTH: usize = 0;
+
+/// Set by `move_rt_trace_enter`, so only stack trace builds link the `stack_trace` import.
+static mut TRACE_DUMP: Option<unsafe extern "C" fn()> = None;
+
+/// Called on entry of every Move function in stack trace builds.
+#[export_name = "move_rt_trace_enter"]
+unsafe extern "C" fn move_rt_trace_enter(function: u32, caller_pc: u32) {
+    TRACE_DUMP = Some(move_rt_trace_dump);
+    let depth = SHADOW_STACK_DEPTH;
+    if depth < SHADOW_STACK_LEN {
+        SHADOW_STACK[depth] = [function, caller_pc];
+    }
+    SHADOW_STACK_DEPTH = depth + 1;
+}
+
+/// Called before every return of a Move function in stack trace builds.
+#[export_name = "move_rt_trace_exit"]
+unsafe extern "C" fn move_rt_trace_exit() {
+    SHADOW_STACK_DEPTH = SHADOW_STACK_DEPTH.saturating_sub(1);
+}
+
+/// Hand the shadow stack to the host, for `print_stack_trace()` in stack trace builds.
+#[export_name = "move_rt_trace_dump"]
+unsafe extern "C" fn move_rt_trace_dump() {
+    let depth = SHADOW_STACK_DEPTH.min(SHADOW_STACK_LEN);
+    imports::stack_trace(
+        core::ptr::addr_of!(SHADOW_STACK) as *const u32,
+        depth as u32,
+    );
+}
+
+/// Hand the shadow stack to the host right before an abort, of Move code or of the runtime
+/// itself, in stack trace builds.
+pub(crate) unsafe fn trace_abort() {
+    if let Some(dump) = TRACE_DUMP {
+        dump();
+        // The aborted call never returns through the epilogues, start the next one afresh.
+        SHADOW_STACK_DEPTH = 0;
+    }
+}
+
+/// Balance of `account` in pallet-revive. Balances beyond `u128` saturate.
+#[export_name = "move_native_revive_balance"]
+unsafe extern "C" fn revive_balance(account: &MoveAddress) -> u128 {
+    let account = account.to_h160();
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    imports::balance_of(account.as_ptr(), value.as_mut_ptr());
+    let (low, high) = value.split_at(16);
+    if high.iter().any(|byte| *byte != 0) {
+        return u128::MAX;
+    }
+    u128::from_le_bytes(low.try_into().unwrap())
+}
+
+/// Transfer `amount` from the contract to `to`, by calling it with that value and no input.
+/// Aborts with [`TRANSFER_FAILURE_CODE`] if pallet-revive rejects the call.
+#[export_name = "move_native_revive_transfer"]
+unsafe extern "C" fn revive_transfer(to: &MoveAddress, amount: u128) {
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    value[..16].copy_from_slice(&amount.to_le_bytes());
+    let input: [u8; 0] = [];
+    let to = to.to_h160();
+    let result = imports::call(
+        pack_hi_lo(0, to.as_ptr() as u32),
+        // no limits beyond what is left of the caller's
+        u64::MAX,
+        u64::MAX,
+        pack_hi_lo(REVIVE_SENTINEL, value.as_ptr() as u32),
+        pack_hi_lo(input.len() as u32, input.as_ptr() as u32),
+        pack_hi_lo(0, REVIVE_SENTINEL),
+    );
+    if result != 0 {
+        move_rt_abort(TRANSFER_FAILURE_CODE);
+    }
+}
+
+/// Call the contract `callee` with `input` and no value, and return its output, which the
+/// runtime copies into a vector on the guest heap. Aborts with [`CALL_FAILURE_CODE`] if
+/// pallet-revive rejects the call or the callee reverts.
+#[export_name = "move_native_revive_call_contract"]
+unsafe extern "C" fn revive_call_contract(
+    callee: &MoveAddress,
+    input: *const MoveByteVector,
+) -> MoveByteVector {
+    let input = &*input;
+    let value = [0u8; REVIVE_VALUE_LENGTH];
+    let callee = callee.to_h160();
+    let result = imports::call(
+        pack_hi_lo(0, callee.as_ptr() as u32),
+        // no limits beyond what is left of the caller's
+        u64::MAX,
+        u64::MAX,
+        pack_hi_lo(REVIVE_SENTINEL, value.as_ptr() as u32),
+        pack_hi_lo(input.length as u32, input.ptr as u32),
+        // the output is fetched with `return_data_copy` once its size is known
+        pack_hi_lo(0, REVIVE_SENTINEL),
+    );
+    if result != 0 {
+        move_rt_abort(CALL_FAILURE_CODE);
+    }
+    let mut output = alloc::vec![0u8; imports::return_data_size() as usize];
+    let mut len = output.len() as u32;
+    imports::return_data_copy(output.as_mut_ptr(), &mut len, 0);
+    output.truncate(len as usize);
+    MoveByteVector::from_rust_vec(output)
+}
+
+/// Abort with [`NOT_PAYABLE_CODE`] if the call transfers value. `call_selector` calls this
+/// before dispatching to an entry function without `#[payable]`, with `--payable-checks`.
+#[export_name = "move_rt_require_no_value"]
+unsafe extern "C" fn require_no_value() {
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    imports::value_transferred(value.as_mut_ptr());
+    if value.iter().any(|byte| *byte != 0) {
+        move_rt_abort(NOT_PAYABLE_CODE);
+    }
+}
+
+/// Pack two 32 bit arguments of a pallet-revive import into one register.
+fn pack_hi_lo(hi: u32, lo: u32) -> u64 {
+    ((hi as u64) << 32) | lo as u64
+}
+
 #[export_name = "move_native_hash_sha2_256"]
 unsafe extern "C" fn move_native_hash_sha2_256(bytes: *const MoveByteVector) -> MoveByteVector {
     let address = imports::hash_sha2_256(bytes);
@@ -56,6 +232,45 @@ unsafe extern "C" fn move_native_hash_sha3_256(bytes: *const MoveByteVector) ->
     *mv_ptr
 }
 
+#[export_name = "move_native_hash_keccak256"]
+unsafe extern "C" fn move_native_hash_keccak256(bytes: &MoveByteVector) -> MoveByteVector {
+    MoveByteVector::from_rust_vec(crate::hash::keccak256(&bytes.as_rust_vec()).to_vec())
+}
+
+#[export_name = "move_native_hash_blake2b256"]
+unsafe extern "C" fn move_native_hash_blake2b256(bytes: &MoveByteVector) -> MoveByteVector {
+    MoveByteVector::from_rust_vec(crate::hash::blake2b256(&bytes.as_rust_vec()).to_vec())
+}
+
+/// Timestamp of the current block in milliseconds since the Unix epoch. pallet-revive
+/// reports whole seconds, timestamps beyond `u64` saturate.
+#[export_name = "move_native_timestamp_ms"]
+unsafe extern "C" fn timestamp_ms() -> u64 {
+    let mut value = [0u8; REVIVE_VALUE_LENGTH];
+    imports::now(value.as_mut_ptr());
+    let (low, high) = value.split_at(8);
+    if high.iter().any(|byte| *byte != 0) {
+        return u64::MAX;
+    }
+    u64::from_le_bytes(low.try_into().unwrap()).saturating_mul(1000)
+}
+
+/// `len` random bytes from the mock host, see `random_bytes` in the imports.
+#[export_name = "move_native_random_bytes"]
+unsafe extern "C" fn random_bytes(len: u64) -> MoveByteVector {
+    let mut bytes = alloc::vec![0u8; len as usize];
+    imports::random_bytes(bytes.as_mut_ptr(), len as u32);
+    MoveByteVector::from_rust_vec(bytes)
+}
+
+/// Abort unless `type_ve` may be kept in global storage. The bytecode verifier already
+/// checks this, the runtime check guards the storage imports against malformed programs.
+unsafe fn require_key(type_ve: &MoveType) {
+    if !type_ve.has_abilities(ABILITY_KEY) {
+        runtime_failure(MISSING_ABILITY_CODE);
+    }
+}
+
 #[export_name = "move_rt_move_to"]
 unsafe extern "C" fn move_to(
     type_ve: &MoveType,
@@ -63,6 +278,7 @@ unsafe extern "C" fn move_to(
     struct_ref: &AnyValue,
     tag: &AnyValue,
 ) {
+    require_key(type_ve);
     let bytes = crate::serialization::serialize(type_ve, struct_ref);
     imports::move_to(signer_ref, &bytes, tag);
 }
@@ -74,6 +290,7 @@ unsafe extern "C" fn move_from(
     out: *mut AnyValue,
     tag: &AnyValue,
 ) {
+    require_key(type_ve);
     let address = imports::move_from(s1, 1, tag, 0);
     let bytevec = &*(address as *const MoveByteVector);
     crate::serialization::deserialize(type_ve, bytevec, out);
@@ -87,13 +304,20 @@ unsafe extern "C" fn borrow_global(
     tag: &AnyValue,
     is_mut: u32,
 ) {
+    require_key(type_ve);
     let address = imports::move_from(s1, 0, tag, is_mut);
     let bytevec = &*(address as *const MoveByteVector);
-    // allocate a boxed slice of 2*bytevec.length bytes (due to alignment)
-    let boxed: alloc::boxed::Box<[u8]> =
-        alloc::vec![0u8; (bytevec.length * 2) as usize].into_boxed_slice();
-    let raw = alloc::boxed::Box::into_raw(boxed);
-    // Deserialize into the boxed location
+    // The global lives in a heap box of the struct's in-memory layout, which can be larger
+    // than its serialized form (e.g. an empty vector field is one byte serialized but a whole
+    // vector descriptor in memory). The reference handed out points into this box, and
+    // `move_rt_release` serializes it back, so writes through a `&mut` reach storage.
+    let info = &(*type_ve.type_info).struct_;
+    let layout = alloc::alloc::Layout::from_size_align(info.size as usize, info.alignment as usize)
+        .expect("bad size or alignment");
+    let raw = alloc::alloc::alloc_zeroed(layout);
+    if raw.is_null() {
+        alloc::alloc::handle_alloc_error(layout);
+    }
     crate::serialization::deserialize(type_ve, bytevec, raw as *mut AnyValue);
     let raw_addr_value = raw as *const u8 as u32;
     // Copy the address of the boxed value into the output pointer
@@ -101,7 +325,8 @@ unsafe extern "C" fn borrow_global(
 }
 
 #[export_name = "move_rt_exists"]
-unsafe extern "C" fn exists(_type_ve: &MoveType, s: &AnyValue, tag: &AnyValue) -> u32 {
+unsafe extern "C" fn exists(type_ve: &MoveType, s: &AnyValue, tag: &AnyValue) -> u32 {
+    require_key(type_ve);
     imports::exists(s, tag)
 }
 
@@ -112,6 +337,7 @@ unsafe extern "C" fn release(
     struct_ref: &AnyValue,
     tag: &AnyValue,
 ) {
+    require_key(type_ve);
     let bytes = crate::serialization::serialize(type_ve, struct_ref);
     imports::release(s, &bytes, tag);
 }
@@ -126,6 +352,44 @@ unsafe extern "C" fn vec_destroy(type_ve: &MoveType, v: MoveUntypedVector) {
     v.destroy(type_ve);
 }
 
+/// Decode the next argument of an entry function, of `type_v`, from the call data between
+/// `*cursor` and `end` into `v`, and advance `*cursor` past it. The generated `call_selector`
+/// calls this for every parameter but the signer, structs and vectors of structs included.
+/// Malformed call data aborts with `BCS_DESERIALIZATION_FAILURE`.
+#[export_name = "move_rt_decode_call_arg"]
+unsafe extern "C" fn decode_call_arg(
+    type_v: &MoveType,
+    cursor: &mut *const u8,
+    end: *const u8,
+    v: *mut AnyValue,
+) {
+    let len = (end as usize).saturating_sub(*cursor as usize);
+    let mut bytes = core::slice::from_raw_parts(*cursor, len);
+    if crate::serialization::decode_call_data_arg(type_v, &mut bytes, v).is_err() {
+        move_rt_abort(BCS_DESERIALIZATION_FAILURE);
+    }
+    *cursor = bytes.as_ptr();
+}
+
+/// BCS encoded return values of the entry function being called, see `move_rt_encode_return`.
+static mut RETURN_DATA: alloc::vec::Vec<u8> = alloc::vec::Vec::new();
+
+/// Append the return value `v`, of `type_v`, of the entry function being called to its return
+/// data. The generated `call_selector` calls this for every value the function returns, in
+/// order, then `move_rt_return_data`.
+#[export_name = "move_rt_encode_return"]
+unsafe extern "C" fn encode_return(type_v: &MoveType, v: &AnyValue) {
+    let data = &mut *core::ptr::addr_of_mut!(RETURN_DATA);
+    crate::serialization::encode_call_data_value(type_v, v, data);
+}
+
+/// Hand the return data to the host as the output of the call.
+#[export_name = "move_rt_return_data"]
+unsafe extern "C" fn return_data() {
+    let data = core::mem::take(&mut *core::ptr::addr_of_mut!(RETURN_DATA));
+    imports::seal_return(0, data.as_ptr(), data.len() as u32);
+}
+
 #[export_name = "move_rt_vec_empty"]
 unsafe extern "C" fn vec_empty(type_ve: &MoveType) -> MoveUntypedVector {
     MoveUntypedVector::empty(type_ve)
@@ -142,6 +406,15 @@ unsafe extern "C" fn vec_copy(
     dstv.copy_from(&srcv)
 }
 
+#[export_name = "move_rt_vec_push_back"]
+unsafe extern "C" fn vec_push_back(
+    type_ve: &MoveType,
+    v: &mut MoveUntypedVector,
+    e: *mut AnyValue,
+) {
+    TypedMoveBorrowedRustVecMut::new(type_ve, v).push_back(e)
+}
+
 #[export_name = "move_rt_vec_cmp_eq"]
 unsafe extern "C" fn vec_cmp_eq(
     type_ve: &MoveType,
@@ -172,6 +445,82 @@ unsafe extern "C" fn struct_cmp_eq(type_ve: &MoveType, s1: &AnyValue, s2: &AnyVa
     crate::structs::cmp_eq(type_ve, s1, s2)
 }
 
+// u256 operations of code compiled with `--u256-helpers`. The operands are little endian
+// 256-bit integers in memory and may alias the destination. The arithmetic helpers write
+// the wrapped result and return whether the operation overflowed, or divided by zero, in
+// which case the destination is left alone.
+
+unsafe fn u256_binop(
+    a: *const U256,
+    b: *const U256,
+    dst: *mut U256,
+    op: fn(ethnum::U256, ethnum::U256) -> Option<(ethnum::U256, bool)>,
+) -> bool {
+    let a = ethnum::U256(core::ptr::read_unaligned(a).0);
+    let b = ethnum::U256(core::ptr::read_unaligned(b).0);
+    match op(a, b) {
+        Some((result, overflow)) => {
+            core::ptr::write_unaligned(dst, U256(result.0));
+            overflow
+        }
+        None => true,
+    }
+}
+
+#[export_name = "move_rt_add256"]
+unsafe extern "C" fn add256(a: *const U256, b: *const U256, dst: *mut U256) -> bool {
+    u256_binop(a, b, dst, |a, b| Some(a.overflowing_add(b)))
+}
+
+#[export_name = "move_rt_sub256"]
+unsafe extern "C" fn sub256(a: *const U256, b: *const U256, dst: *mut U256) -> bool {
+    u256_binop(a, b, dst, |a, b| Some(a.overflowing_sub(b)))
+}
+
+#[export_name = "move_rt_mul256"]
+unsafe extern "C" fn mul256(a: *const U256, b: *const U256, dst: *mut U256) -> bool {
+    u256_binop(a, b, dst, |a, b| Some(a.overflowing_mul(b)))
+}
+
+#[export_name = "move_rt_div256"]
+unsafe extern "C" fn div256(a: *const U256, b: *const U256, dst: *mut U256) -> bool {
+    u256_binop(a, b, dst, |a, b| a.checked_div(b).map(|q| (q, false)))
+}
+
+#[export_name = "move_rt_mod256"]
+unsafe extern "C" fn mod256(a: *const U256, b: *const U256, dst: *mut U256) -> bool {
+    u256_binop(a, b, dst, |a, b| a.checked_rem(b).map(|r| (r, false)))
+}
+
+/// -1, 0 or 1 as `a` is less than, equal to or greater than `b`.
+#[export_name = "move_rt_cmp256"]
+unsafe extern "C" fn cmp256(a: *const U256, b: *const U256) -> i32 {
+    let a = ethnum::U256(core::ptr::read_unaligned(a).0);
+    let b = ethnum::U256(core::ptr::read_unaligned(b).0);
+    a.cmp(&b) as i32
+}
+
+/// Zero extend the `len` byte integer at `src` to a u256.
+#[export_name = "move_rt_zext256"]
+unsafe extern "C" fn zext256(src: *const u8, dst: *mut U256, len: u32) {
+    let mut bytes = [0u8; 32];
+    core::ptr::copy_nonoverlapping(src, bytes.as_mut_ptr(), len as usize);
+    core::ptr::write_unaligned(dst.cast::<[u8; 32]>(), bytes);
+}
+
+/// Truncate the u256 at `src` to the `len` byte integer at `dst`. Returns whether the value
+/// doesn't fit, in which case `dst` is left alone.
+#[export_name = "move_rt_trunc256"]
+unsafe extern "C" fn trunc256(src: *const U256, dst: *mut u8, len: u32) -> bool {
+    let bytes = core::ptr::read_unaligned(src.cast::<[u8; 32]>());
+    let (low, high) = bytes.split_at(len as usize);
+    if high.iter().any(|b| *b != 0) {
+        return true;
+    }
+    core::ptr::copy_nonoverlapping(low.as_ptr(), dst, low.len());
+    false
+}
+
 // Safety: Even empty Rust vectors have non-null buffer pointers,
 // which must be correctly aligned. This function crates empty Rust vecs
 // of the correct type and converts them to untyped move vecs.
@@ -277,6 +626,15 @@ pub unsafe extern "C" fn to_bytes(type_v: &MoveType, v: &AnyValue) -> MoveByteVe
     crate::serialization::serialize(type_v, v)
 }
 
+/// Decode the BCS `bytes` into a value of `type_v`, written to `v`, with the decoder of call
+/// data. Malformed input aborts with `BCS_DESERIALIZATION_FAILURE`.
+#[export_name = "move_native_bcs_from_bytes"]
+pub unsafe extern "C" fn from_bytes(type_v: &MoveType, bytes: &MoveByteVector, v: *mut AnyValue) {
+    if crate::serialization::decode_bcs(type_v, bytes, v).is_err() {
+        move_rt_abort(BCS_DESERIALIZATION_FAILURE);
+    }
+}
+
 #[allow(dead_code)]
 unsafe fn print_vec(vec: &MoveByteVector) {
     let typ_string = MoveType::vec();
@@ -1,11 +1,32 @@
+/// Logs where the runtime panicked, and the message if it is a plain string, then reverts
+/// with the runtime failure `PANIC_CODE`. The message is built with `crate::fmt`, not `core::fmt`, which would
+/// be linked into every contract otherwise.
 #[panic_handler]
-fn panic(_info: &core::panic::PanicInfo) -> ! {
-    use super::imports::terminate;
-    use crate::PANIC_CODE;
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    use super::imports::{log, seal_return};
+    use crate::{
+        fmt::Message, runtime_failure_data, LOG_LEVEL_ERROR, PANIC_CODE, RETURN_FLAG_REVERT,
+    };
+    let mut message = Message::<256>::new();
+    message.push_str("move-native panicked");
+    if let Some(location) = info.location() {
+        message
+            .push_str(" at ")
+            .push_str(location.file())
+            .push_str(":")
+            .push_u64(location.line().into())
+            .push_str(":")
+            .push_u64(location.column().into());
+    }
+    if let Some(text) = info.message().as_str() {
+        message.push_str(": ").push_str(text);
+    }
     unsafe {
-        let mut beneficiary = [0u8; 20];
-        beneficiary[0] = PANIC_CODE as u8;
-        terminate(beneficiary.as_ptr() as *const [u8; 20]);
+        let text = message.as_bytes();
+        log(LOG_LEVEL_ERROR, text.as_ptr(), text.len() as u64);
+        super::trace_abort();
+        let data = runtime_failure_data(PANIC_CODE);
+        seal_return(RETURN_FLAG_REVERT, data.as_ptr(), data.len() as u32);
         core::hint::unreachable_unchecked()
     }
 }
@@ -1,18 +1,27 @@
 extern crate alloc;
-use alloc::boxed::Box;
+use crate::{
+    types::{MoveAddress, ACCOUNT_ADDRESS_LENGTH},
+    REVIVE_ADDRESS_LENGTH,
+};
+use alloc::vec;
 
 // PolkaVM will call this function to execute the program.
 // We need to load the call data and pass it to the selector function.
 #[polkavm_derive::polkavm_export]
 unsafe extern "C" fn call() {
-    // 4 bytes for selector, 20 bytes for origin, rest padding
-    let mut buf = Box::new_uninit_slice(36).assume_init();
-    // a buffer for the origin
-    let out_ptr = buf.as_mut_ptr();
-    call_data_copy(out_ptr, 4, 0);
-    let signer_ptr = unsafe { out_ptr.add(4) }; // Skip first 4 bytes
-    origin(signer_ptr);
-    call_selector(out_ptr, 36);
+    // 4 bytes for selector, followed by the signer: the origin, extended to a Move address,
+    // and the encoded arguments, the rest of the call data
+    const HEADER: usize = 4 + ACCOUNT_ADDRESS_LENGTH;
+    let args_len = (call_data_size() as usize).saturating_sub(4);
+    let mut buf = vec![0u8; HEADER + args_len];
+    call_data_copy(buf.as_mut_ptr(), 4, 0);
+    let mut caller = [0u8; REVIVE_ADDRESS_LENGTH];
+    origin(caller.as_mut_ptr());
+    buf[4..HEADER].copy_from_slice(&MoveAddress::from_h160(caller).0);
+    if args_len > 0 {
+        call_data_copy(buf[HEADER..].as_mut_ptr(), args_len as u32, 4);
+    }
+    call_selector(buf.as_mut_ptr(), buf.len() as u64);
 }
 
 #[polkavm_derive::polkavm_export]
@@ -0,0 +1,25 @@
+//! Hash functions computed by the guest itself, for the natives of the `revive::hash` module.
+//!
+//! pallet-revive offers no Blake2b to contracts and the mock host no Keccak, so unlike
+//! `std::hash`, whose SHA-2 and SHA-3 natives call host imports, these are compiled into the
+//! runtime. They build for the host as well, where they are tested.
+
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use tiny_keccak::{Hasher, Keccak};
+
+/// Length of the digests, in bytes.
+pub const DIGEST_LENGTH: usize = 32;
+
+/// Keccak-256, as Ethereum uses it, not the standardized SHA3-256.
+pub fn keccak256(data: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    let mut digest = [0u8; DIGEST_LENGTH];
+    keccak.finalize(&mut digest);
+    digest
+}
+
+/// Blake2b with a 256 bit digest, Substrate's `blake2_256`.
+pub fn blake2b256(data: &[u8]) -> [u8; DIGEST_LENGTH] {
+    Blake2b::<U32>::digest(data).into()
+}
@@ -1,8 +1,13 @@
 extern crate alloc;
 use polkavm::MemoryAccessError;
 
-use crate::{allocator::MemAllocator, storage::Storage};
-use alloc::{boxed::Box, string::ToString};
+use crate::{allocator::MemAllocator, storage::Storage, REVIVE_ADDRESS_LENGTH};
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
 
 #[derive(Debug)]
 pub enum ProgramError {
@@ -12,8 +17,14 @@ pub enum ProgramError {
     NativeLibPanic,
     // there is no allocator available for guest program (Move program to be exact), any calls to malloc result in abort with special code
     NativeLibAllocatorCall,
+    // global storage operation on a type lacking the `key` ability, rejected by the native lib
+    MissingAbility,
+    // reference outside the data, the used heap and the live stack, caught by `--paranoid` builds
+    InvalidReference,
     // memory access error when we work inside callbacks and do memory reading
     MemoryAccess(alloc::string::String),
+    // storage backend failed to restore or persist global state
+    Persistence(alloc::string::String),
 }
 
 impl From<MemoryAccessError> for ProgramError {
@@ -25,4 +36,114 @@ impl From<MemoryAccessError> for ProgramError {
 pub struct Runtime {
     pub allocator: MemAllocator,
     pub storage: Box<dyn Storage>,
+    /// Guest address and length of the coverage counters, registered by coverage builds.
+    pub coverage_counters: Option<(u32, u32)>,
+    /// Guest address of the allocator's [`crate::HeapStats`], registered by `--heap-stats` builds.
+    pub heap_stats: Option<u32>,
+    /// Shadow stack at the last abort of a stack trace build, outermost frame first.
+    pub stack_trace: Option<alloc::vec::Vec<StackFrame>>,
+    /// Mock pallet-revive balances of H160 accounts, read by `balance_of` and moved by `call`.
+    pub balances: BTreeMap<[u8; REVIVE_ADDRESS_LENGTH], u128>,
+    /// Mock value transferred by the call, read by `value_transferred`.
+    pub value_transferred: u128,
+    /// Input of the call, served by `call_data_size` and `call_data_copy`: the selector of the
+    /// entry function followed by its BCS encoded arguments.
+    pub call_data: Vec<u8>,
+    /// Data the program returned with `seal_return`, e.g. to a contract calling it.
+    pub output: Vec<u8>,
+    /// Output of the last contract the program called, served by `return_data_size` and
+    /// `return_data_copy`.
+    pub return_data: Vec<u8>,
+    /// Mock contracts of H160 accounts, called by `call` with the input of the call.
+    pub contracts: BTreeMap<[u8; REVIVE_ADDRESS_LENGTH], Box<dyn Contract>>,
+    /// Receives the values printed with `debug::print`, [`LogPrintSink`] by default.
+    pub print_sink: Box<dyn PrintSink>,
+    /// Mock block timestamp in milliseconds since the Unix epoch, read by `now` in seconds.
+    pub timestamp_ms: u64,
+    /// Mock randomness, served by `random_bytes`.
+    pub random: MockRandom,
+}
+
+/// A deterministic source of mock randomness: the same seed yields the same bytes, so tests
+/// of contracts drawing random numbers are reproducible. Not suitable for anything else.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockRandom {
+    state: u64,
+}
+
+impl MockRandom {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Fill `bytes` with the next bytes of the stream.
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let word = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+
+    /// SplitMix64, which is fast and spreads any seed, including 0, over all 64 bits.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// A value printed by the program with `debug::print`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintedValue {
+    /// Move type of the value, `Unknown` if its type descriptor can't be read.
+    pub type_name: String,
+    /// The value as text: numbers in decimal, signers as addresses, and vectors as UTF-8 when
+    /// their bytes are valid UTF-8, in hex otherwise.
+    pub value: String,
+}
+
+/// Receives the values printed by the program, e.g. to capture them in tests.
+pub trait PrintSink {
+    fn print(&mut self, value: PrintedValue);
+}
+
+impl<F: FnMut(PrintedValue)> PrintSink for F {
+    fn print(&mut self, value: PrintedValue) {
+        self(value)
+    }
+}
+
+/// The default [`PrintSink`], logging every value at info level.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogPrintSink;
+
+impl PrintSink for LogPrintSink {
+    fn print(&mut self, value: PrintedValue) {
+        log::info!("[debug] {}", value.value);
+    }
+}
+
+/// A contract the program can call through the `call` import.
+pub trait Contract {
+    /// Handle a call with `input`, transferring `value`. Returns the output of the call, or
+    /// the output of the revert.
+    fn call(&mut self, input: &[u8], value: u128) -> Result<Vec<u8>, Vec<u8>>;
+}
+
+impl<F: FnMut(&[u8], u128) -> Result<Vec<u8>, Vec<u8>>> Contract for F {
+    fn call(&mut self, input: &[u8], value: u128) -> Result<Vec<u8>, Vec<u8>> {
+        self(input, value)
+    }
+}
+
+/// A frame of the shadow stack kept by `--stack-traces` builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Index of the function in the stack trace map written next to the blob.
+    pub function: u32,
+    /// Return address into the caller. Like all PolkaVM code addresses, it is a jump table
+    /// address rather than a program counter.
+    pub caller_pc: u32,
 }
@@ -56,6 +56,9 @@
 //!
 //!    /// Return the binary representation of `v` in BCS (Binary Canonical Serialization) format
 //!    native public fun to_bytes<MoveValue>(v: &MoveValue): vector<u8>;
+//!
+//!    /// Decode `bytes`, as produced by `to_bytes`, into a value of type `T`
+//!    native public(friend) fun from_bytes<T>(bytes: vector<u8>): T;
 //! ```
 //!
 //! In the Move VM these are implemented in Rust and registered with the VM at
@@ -231,11 +234,13 @@
 #[cfg(feature = "host")]
 pub mod allocator;
 pub mod conv;
+pub mod fmt;
 #[cfg(feature = "polkavm")]
 pub mod guest;
+pub mod hash;
 #[cfg(feature = "host")]
 pub mod host;
-#[cfg(feature = "polkavm")]
+#[cfg(any(feature = "polkavm", feature = "host"))]
 pub mod serialization;
 #[cfg(feature = "host")]
 pub mod storage;
@@ -243,11 +248,75 @@ pub mod structs;
 pub mod types;
 pub mod vector;
 
-// Define the base heap address. The actual heap start address is probably lower, but we have no
-// way to know it at compile time, so we use a fixed address that is guaranteed to be above the
-// heap start address.
-pub const HEAP_BASE: u32 = 0x30500;
+// The guest heap is reserved by the compiler as the `move_rt_heap` symbol in the RW data,
+// sized in pages of `HEAP_PAGE_SIZE` bytes (see the `--heap-pages` compiler option).
+pub const HEAP_PAGE_SIZE: u32 = 4096;
+pub const DEFAULT_HEAP_PAGES: u32 = 16;
 
-// abort codes used by native lib
+/// Counters of the guest allocator. `--heap-stats` builds register them with the host through
+/// the `heap_stats_register` import, so it can read them after a call. Sizes are in bytes.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Size of the live allocations, as requested.
+    pub in_use: u32,
+    /// Highest end of the used part of the heap, free blocks kept for reuse included: the heap
+    /// size the program needed so far.
+    pub peak: u32,
+    /// Blocks handed out, including the new blocks of moving `realloc`s.
+    pub allocations: u32,
+    /// Blocks freed, including the old blocks of moving `realloc`s.
+    pub frees: u32,
+    /// `realloc`s that resized the block where it is.
+    pub reallocs_in_place: u32,
+    /// `realloc`s that copied the contents to a new block.
+    pub reallocs_moved: u32,
+}
+
+// codes of failures of the native lib itself, reverted with `RUNTIME_FAILURE_TAG`
 pub const PANIC_CODE: u64 = 0xdead;
 pub const ALLOC_CODE: u64 = 0xca11;
+// global storage operation on a type without the `key` ability, a runtime failure
+pub const MISSING_ABILITY_CODE: u64 = 0xab;
+// `from_bytes` on malformed input, the code the Move VM aborts with (NFE_BCS_SERIALIZATION_FAILURE)
+pub const BCS_DESERIALIZATION_FAILURE: u64 = 0x1c5;
+// `revive::transfer` rejected by pallet-revive, e.g. for lack of balance
+pub const TRANSFER_FAILURE_CODE: u64 = 0xba1;
+// value transferred to an entry function without `#[payable]`, with `--payable-checks`
+pub const NOT_PAYABLE_CODE: u64 = 0xba2;
+// `revive::call_contract` rejected by pallet-revive or reverted by the callee
+pub const CALL_FAILURE_CODE: u64 = 0xba3;
+// call data shorter than the selector or the arguments of the called entry function
+pub const MALFORMED_INPUT_CODE: u64 = 0xba4;
+// dereference of a null or dangling reference, caught with `--paranoid`, a runtime failure
+pub const INVALID_REFERENCE_CODE: u64 = 0xbad;
+
+// Aborts revert the call through the pallet-revive `seal_return` import, with the abort code
+// as return data (`u64` little endian).
+pub const RETURN_FLAG_REVERT: u32 = 1;
+
+// Failures of the native lib itself revert with their code followed by this byte, so a Move
+// abort with the same number, e.g. `abort 0xab`, isn't taken for one.
+pub const RUNTIME_FAILURE_TAG: u8 = 0xff;
+
+/// Return data of the revert for the runtime failure `code`: the code, `u64` little endian,
+/// followed by [`RUNTIME_FAILURE_TAG`].
+pub fn runtime_failure_data(code: u64) -> [u8; 9] {
+    let mut data = [RUNTIME_FAILURE_TAG; 9];
+    data[..8].copy_from_slice(&code.to_le_bytes());
+    data
+}
+
+// pallet-revive accounts are H160 addresses, stored in the first 20 bytes of a Move address.
+pub const REVIVE_ADDRESS_LENGTH: usize = 20;
+// Balances cross the pallet-revive imports as `U256` little endian.
+pub const REVIVE_VALUE_LENGTH: usize = 32;
+// Absent optional pointer arguments of pallet-revive imports.
+pub const REVIVE_SENTINEL: u32 = u32::MAX;
+
+// log levels understood by `move_rt_log`, numbered like `log::Level`
+pub const LOG_LEVEL_ERROR: u32 = 1;
+pub const LOG_LEVEL_WARN: u32 = 2;
+pub const LOG_LEVEL_INFO: u32 = 3;
+pub const LOG_LEVEL_DEBUG: u32 = 4;
+pub const LOG_LEVEL_TRACE: u32 = 5;
@@ -13,8 +13,8 @@ fn borsh_to_buf<T: BorshSerialize>(v: &T, buf: &mut Vec<u8>) {
     borsh::to_writer(buf, v).expect("serialization failure")
 }
 
-fn borsh_from_slice<T: BorshDeserialize>(buf: &mut &[u8]) -> T {
-    BorshDeserialize::deserialize(buf).expect("deserialization failure")
+fn borsh_from_slice<T: BorshDeserialize>(buf: &mut &[u8]) -> borsh::io::Result<T> {
+    BorshDeserialize::deserialize(buf)
 }
 
 #[allow(clippy::missing_safety_doc)]
@@ -70,58 +70,83 @@ unsafe fn serialize_to_buf(type_v: &MoveType, v: &AnyValue, buf: &mut Vec<u8>) {
 pub unsafe fn deserialize(type_v: &MoveType, bytes: &MoveByteVector, v: *mut AnyValue) {
     let bytes = bytes.as_rust_vec();
     let bytes = &mut &bytes[..];
-    deserialize_from_slice(type_v, bytes, v);
+    deserialize_from_slice(type_v, bytes, v).expect("deserialization failure");
     assert!(bytes.is_empty());
 }
 
-unsafe fn deserialize_from_slice(type_v: &MoveType, bytes: &mut &[u8], v: *mut AnyValue) {
+/// Decode the BCS encoding of a value of `type_v` from `bytes` into `v`, like
+/// [`decode_call_data_arg`] but for all of `bytes`. Malformed input (invalid values, truncated
+/// data or trailing bytes) is reported as an error, and `v` may be partially written.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn decode_bcs(
+    type_v: &MoveType,
+    bytes: &MoveByteVector,
+    v: *mut AnyValue,
+) -> borsh::io::Result<()> {
+    let bytes = bytes.as_rust_vec();
+    let bytes = &mut &bytes[..];
+    decode_call_data_arg(type_v, bytes, v)?;
+    if !bytes.is_empty() {
+        return Err(borsh::io::Error::new(
+            borsh::io::ErrorKind::InvalidData,
+            "trailing bytes",
+        ));
+    }
+    Ok(())
+}
+
+unsafe fn deserialize_from_slice(
+    type_v: &MoveType,
+    bytes: &mut &[u8],
+    v: *mut AnyValue,
+) -> borsh::io::Result<()> {
     // These writes are to uninitialized memory.
     // Using `ptr::write` guarantees that the destination is never read,
     // which can happen if the type has destructors.
     let v = raw_borrow_move_value_as_rust_value(type_v, v);
     match v {
         RawBorrowedTypedMoveValue::Bool(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::U8(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::U16(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::U32(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::U64(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::U128(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::U256(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::Address(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::Signer(vptr) => {
-            let v = borsh_from_slice(bytes);
+            let v = borsh_from_slice(bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::Vector(t, vptr) => {
-            let v = deserialize_vector(&t, bytes);
+            let v = deserialize_vector(&t, bytes)?;
             ptr::write(vptr, v);
         }
         RawBorrowedTypedMoveValue::Struct(t, vptr) => {
-            deserialize_struct(&t, bytes, vptr);
+            deserialize_struct(&t, bytes, vptr)?;
         }
         RawBorrowedTypedMoveValue::Reference(_, _) => {
             todo!(
@@ -129,6 +154,201 @@ unsafe fn deserialize_from_slice(type_v: &MoveType, bytes: &mut &[u8], v: *mut A
             );
         }
     }
+    Ok(())
+}
+
+/// Decode an argument of an entry function of `type_v` from call data into `v`, and advance
+/// `bytes` past it. Call data is BCS encoded, like the compiler's `Abi::encode_call` does:
+/// scalars are encoded as in [`deserialize`], but vector lengths are ULEB128 instead of
+/// `u32`, also in the fields of structs and in vectors of structs.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn decode_call_data_arg(
+    type_v: &MoveType,
+    bytes: &mut &[u8],
+    v: *mut AnyValue,
+) -> borsh::io::Result<()> {
+    match raw_borrow_move_value_as_rust_value(type_v, v) {
+        RawBorrowedTypedMoveValue::Vector(t, vptr) => {
+            let v = decode_call_data_vector(&t, bytes)?;
+            ptr::write(vptr, v);
+            Ok(())
+        }
+        RawBorrowedTypedMoveValue::Struct(t, vptr) => decode_call_data_struct(&t, bytes, vptr),
+        RawBorrowedTypedMoveValue::Reference(..) => Err(not_in_call_data()),
+        RawBorrowedTypedMoveValue::Signer(..) => Err(signer_not_in_call_data()),
+        _ => deserialize_from_slice(type_v, bytes, v),
+    }
+}
+
+unsafe fn decode_call_data_vector(
+    type_elt: &MoveType,
+    bytes: &mut &[u8],
+) -> borsh::io::Result<MoveUntypedVector> {
+    fn decode_elements<T: BorshDeserialize>(
+        v: &mut Vec<T>,
+        len: usize,
+        bytes: &mut &[u8],
+    ) -> borsh::io::Result<()> {
+        v.reserve_exact(len);
+        for _ in 0..len {
+            v.push(borsh_from_slice(bytes)?);
+        }
+        Ok(())
+    }
+
+    let len = uleb128_vector_len(bytes)?;
+    let mut mv = MoveUntypedVector::empty(type_elt);
+    let mut rv = TypedMoveBorrowedRustVecMut::new(type_elt, &mut mv);
+    match &mut rv {
+        TypedMoveBorrowedRustVecMut::Bool(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::U8(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::U16(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::U32(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::U64(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::U128(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::U256(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::Address(v) => decode_elements(v, len, bytes)?,
+        TypedMoveBorrowedRustVecMut::Signer(_) => return Err(signer_not_in_call_data()),
+        TypedMoveBorrowedRustVecMut::Vector(inner_elt_type, v) => {
+            v.reserve_exact(len);
+            for _ in 0..len {
+                let eltv = decode_call_data_vector(inner_elt_type, bytes)?;
+                v.push(eltv);
+            }
+        }
+        TypedMoveBorrowedRustVecMut::Struct(vs) => {
+            vs.reserve_exact(len);
+            for i in 0..len {
+                let eltptr = vs.get_mut_unchecked_raw(i);
+                decode_call_data_struct(type_elt, bytes, eltptr)?;
+            }
+            vs.set_length(len);
+        }
+        TypedMoveBorrowedRustVecMut::Reference(..) => {
+            return Err(not_in_call_data());
+        }
+    }
+    drop(rv);
+    Ok(mv)
+}
+
+unsafe fn decode_call_data_struct(
+    t: &MoveType,
+    bytes: &mut &[u8],
+    v: *mut AnyValue,
+) -> borsh::io::Result<()> {
+    assert_eq!(t.type_desc, TypeDesc::Struct);
+    let structinfo = &(*(t.type_info)).struct_;
+    for (ft, fv, _) in crate::structs::walk_fields_mut(structinfo, v) {
+        decode_call_data_arg(ft, bytes, fv)?;
+    }
+    Ok(())
+}
+
+/// Append the BCS encoding of `v`, of `type_v`, to `buf`: the encoding of call data, that
+/// [`decode_call_data_arg`] decodes. The generated `call_selector` returns the values
+/// returned by entry functions in this encoding.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn encode_call_data_value(type_v: &MoveType, v: &AnyValue, buf: &mut Vec<u8>) {
+    match borrow_move_value_as_rust_value(type_v, v) {
+        BorrowedTypedMoveValue::Vector(t, v) => encode_call_data_vector(&t, v, buf),
+        BorrowedTypedMoveValue::Struct(t, v) => {
+            let structinfo = &(*(t.type_info)).struct_;
+            for (ft, fv, _) in crate::structs::walk_fields(structinfo, v) {
+                encode_call_data_value(ft, fv, buf);
+            }
+        }
+        // `call_selector` passes the referenced value of returned references, and Move has no
+        // references in struct fields
+        BorrowedTypedMoveValue::Reference(_, _) => {
+            unreachable!("encode_call_data_value: returned values are never references")
+        }
+        _ => serialize_to_buf(type_v, v, buf),
+    }
+}
+
+unsafe fn encode_call_data_vector(type_elt: &MoveType, v: &MoveUntypedVector, buf: &mut Vec<u8>) {
+    fn encode_elements<T: BorshSerialize>(v: &[T], buf: &mut Vec<u8>) {
+        encode_uleb128(v.len(), buf);
+        for elt in v {
+            borsh_to_buf(elt, buf);
+        }
+    }
+
+    let v = TypedMoveBorrowedRustVec::new(type_elt, v);
+    match v {
+        TypedMoveBorrowedRustVec::Bool(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::U8(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::U16(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::U32(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::U64(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::U128(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::U256(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::Address(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::Signer(v) => encode_elements(&v, buf),
+        TypedMoveBorrowedRustVec::Vector(t, v) => {
+            encode_uleb128(v.len(), buf);
+            for elt in v.iter() {
+                encode_call_data_vector(&t, elt, buf);
+            }
+        }
+        TypedMoveBorrowedRustVec::Struct(v) => {
+            encode_uleb128(v.len(), buf);
+            for elt in v.iter() {
+                encode_call_data_value(v.type_(), elt, buf);
+            }
+        }
+        // Move has no vectors of references
+        TypedMoveBorrowedRustVec::Reference(_, _) => {
+            unreachable!("encode_call_data_vector: vectors never hold references")
+        }
+    }
+}
+
+fn encode_uleb128(mut n: usize, buf: &mut Vec<u8>) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// ULEB128 length prefix of a vector in call data, checked like [`vector_len`].
+fn uleb128_vector_len(bytes: &mut &[u8]) -> borsh::io::Result<usize> {
+    let mut len = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte: u8 = borsh_from_slice(bytes)?;
+        len |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            if len > bytes.len() as u64 {
+                return Err(borsh::io::Error::new(
+                    borsh::io::ErrorKind::InvalidData,
+                    "vector length exceeds the input",
+                ));
+            }
+            return Ok(len as usize);
+        }
+    }
+    Err(borsh::io::Error::new(
+        borsh::io::ErrorKind::InvalidData,
+        "vector length overflows u64",
+    ))
+}
+
+fn not_in_call_data() -> borsh::io::Error {
+    borsh::io::Error::new(
+        borsh::io::ErrorKind::InvalidData,
+        "references can't be passed in call data",
+    )
+}
+
+/// Signers decoded from call data would be chosen by the caller; only the signer of the call
+/// is ever passed to an entry function.
+fn signer_not_in_call_data() -> borsh::io::Error {
+    borsh::io::Error::new(
+        borsh::io::ErrorKind::InvalidData,
+        "signers can't be passed in call data",
+    )
 }
 
 unsafe fn serialize_vector(type_elt: &MoveType, v: &MoveUntypedVector, buf: &mut Vec<u8>) {
@@ -163,53 +383,54 @@ unsafe fn serialize_vector(type_elt: &MoveType, v: &MoveUntypedVector, buf: &mut
     }
 }
 
-unsafe fn deserialize_vector(type_elt: &MoveType, bytes: &mut &[u8]) -> MoveUntypedVector {
+unsafe fn deserialize_vector(
+    type_elt: &MoveType,
+    bytes: &mut &[u8],
+) -> borsh::io::Result<MoveUntypedVector> {
     let mut mv = MoveUntypedVector::empty(type_elt);
     let mut rv = TypedMoveBorrowedRustVecMut::new(type_elt, &mut mv);
     match &mut rv {
         TypedMoveBorrowedRustVecMut::Bool(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::U8(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::U16(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::U32(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::U64(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::U128(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::U256(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::Address(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::Signer(v) => {
-            **v = borsh_from_slice(bytes);
+            **v = borsh_from_slice(bytes)?;
         }
         TypedMoveBorrowedRustVecMut::Vector(inner_elt_type, v) => {
-            let len: u32 = borsh_from_slice(bytes);
-            let len: usize = len as usize;
+            let len = vector_len(bytes)?;
             v.reserve_exact(len);
             for _ in 0..len {
-                let eltv = deserialize_vector(inner_elt_type, bytes);
+                let eltv = deserialize_vector(inner_elt_type, bytes)?;
                 v.push(eltv);
             }
         }
         TypedMoveBorrowedRustVecMut::Struct(vs) => {
-            let len: u32 = borsh_from_slice(bytes);
-            let len: usize = len as usize;
+            let len = vector_len(bytes)?;
             vs.reserve_exact(len);
             for i in 0..len {
                 let eltptr = vs.get_mut_unchecked_raw(i);
-                deserialize_struct(type_elt, bytes, eltptr);
+                deserialize_struct(type_elt, bytes, eltptr)?;
             }
             vs.set_length(len);
         }
@@ -218,7 +439,21 @@ unsafe fn deserialize_vector(type_elt: &MoveType, bytes: &mut &[u8]) -> MoveUnty
         }
     }
     drop(rv);
-    mv
+    Ok(mv)
+}
+
+/// Length prefix of a vector of vectors or structs. Every element takes at least one byte,
+/// so longer lengths are malformed and rejected before reserving memory for them.
+fn vector_len(bytes: &mut &[u8]) -> borsh::io::Result<usize> {
+    let len: u32 = borsh_from_slice(bytes)?;
+    let len = len as usize;
+    if len > bytes.len() {
+        return Err(borsh::io::Error::new(
+            borsh::io::ErrorKind::InvalidData,
+            "vector length exceeds the input",
+        ));
+    }
+    Ok(len)
 }
 
 unsafe fn serialize_struct(t: &MoveType, v: &AnyValue, buf: &mut Vec<u8>) {
@@ -231,10 +466,15 @@ unsafe fn serialize_struct(t: &MoveType, v: &AnyValue, buf: &mut Vec<u8>) {
     // print_str("serialize_struct done");
 }
 
-unsafe fn deserialize_struct(t: &MoveType, bytes: &mut &[u8], v: *mut AnyValue) {
+unsafe fn deserialize_struct(
+    t: &MoveType,
+    bytes: &mut &[u8],
+    v: *mut AnyValue,
+) -> borsh::io::Result<()> {
     assert_eq!(t.type_desc, TypeDesc::Struct);
     let structinfo = &(*(t.type_info)).struct_;
     for (ft, fv, _) in crate::structs::walk_fields_mut(structinfo, v) {
-        deserialize_from_slice(ft, bytes, fv);
+        deserialize_from_slice(ft, bytes, fv)?;
     }
+    Ok(())
 }
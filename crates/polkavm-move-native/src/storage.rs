@@ -1,7 +1,10 @@
 extern crate alloc;
 
-use crate::{host::ProgramError, types::MoveAddress};
-use alloc::{format, vec::Vec};
+use crate::{
+    host::ProgramError,
+    types::{MoveAddress, ACCOUNT_ADDRESS_LENGTH},
+};
+use alloc::{boxed::Box, collections::BTreeMap, format, vec::Vec};
 use hashbrown::HashMap;
 use log::debug;
 
@@ -36,6 +39,17 @@ pub trait Storage {
 
     fn is_borrowed(&self, move_signer: MoveAddress, tag: StructTagHash) -> bool;
 
+    /// Freeze all stored resources, to [`Storage::restore`] them later.
+    fn snapshot(&self) -> StorageSnapshot;
+
+    /// Replace all stored resources with the ones of `snapshot` and drop all borrows.
+    fn restore(&mut self, snapshot: &StorageSnapshot);
+
+    /// Persist any pending changes to the underlying backend.
+    fn flush(&mut self) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
     fn update(
         &mut self,
         address: MoveAddress,
@@ -47,6 +61,58 @@ pub trait Storage {
     }
 }
 
+/// Raw key-value store holding serialized global resources.
+///
+/// Backends only deal with resource bytes; borrow tracking is done by
+/// [`GlobalStorage`] and never persisted.
+pub trait StorageBackend {
+    /// Get the serialized resource stored under `(address, tag)`.
+    fn get(&self, address: MoveAddress, tag: StructTagHash) -> Option<Vec<u8>>;
+
+    /// Store the serialized resource under `(address, tag)`, replacing any previous value.
+    fn set(&mut self, address: MoveAddress, tag: StructTagHash, value: Vec<u8>);
+
+    /// Remove the resource stored under `(address, tag)`, returning it if present.
+    fn remove(&mut self, address: MoveAddress, tag: StructTagHash) -> Option<Vec<u8>>;
+
+    /// Check if a resource is stored under `(address, tag)`.
+    fn contains(&self, address: MoveAddress, tag: StructTagHash) -> bool;
+
+    /// All stored resources, used to inspect contract state.
+    fn entries(&self) -> Vec<(MoveAddress, StructTagHash, Vec<u8>)>;
+
+    /// Persist pending changes. In-memory backends have nothing to do.
+    fn flush(&mut self) -> Result<(), ProgramError> {
+        Ok(())
+    }
+}
+
+/// All resources of a storage at one point in time, taken by [`Storage::snapshot`].
+///
+/// Tests build their setup state once, take a snapshot and restore it before every call
+/// that should start from that state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageSnapshot {
+    resources: BTreeMap<([u8; ACCOUNT_ADDRESS_LENGTH], StructTagHash), Vec<u8>>,
+}
+
+impl StorageSnapshot {
+    /// The frozen resources, ordered by address and tag.
+    pub fn entries(&self) -> impl Iterator<Item = (MoveAddress, StructTagHash, &[u8])> {
+        self.resources
+            .iter()
+            .map(|((address, tag), data)| (MoveAddress(*address), *tag, data.as_slice()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
 #[derive(Debug, Eq, Hash, PartialEq)]
 struct Key(MoveAddress, StructTagHash);
 
@@ -57,11 +123,45 @@ impl Key {
     }
 }
 
-#[derive(Debug, Clone)]
-struct GlobalResourceEntry {
-    /// The serialized resource contents (Move struct instance).
-    pub data: Vec<u8>,
+/// Backend keeping all resources in memory for the lifetime of the runtime.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    storage: HashMap<Key, Vec<u8>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn get(&self, address: MoveAddress, tag: StructTagHash) -> Option<Vec<u8>> {
+        self.storage.get(&Key::new(address, tag)).cloned()
+    }
+
+    fn set(&mut self, address: MoveAddress, tag: StructTagHash, value: Vec<u8>) {
+        self.storage.insert(Key::new(address, tag), value);
+    }
+
+    fn remove(&mut self, address: MoveAddress, tag: StructTagHash) -> Option<Vec<u8>> {
+        self.storage.remove(&Key::new(address, tag))
+    }
+
+    fn contains(&self, address: MoveAddress, tag: StructTagHash) -> bool {
+        self.storage.contains_key(&Key::new(address, tag))
+    }
 
+    fn entries(&self) -> Vec<(MoveAddress, StructTagHash, Vec<u8>)> {
+        self.storage
+            .iter()
+            .map(|(Key(addr, tag), data)| (*addr, *tag, data.clone()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct BorrowState {
     /// Number of active shared borrows (`&T`).
     pub borrow_count: u32,
 
@@ -69,27 +169,29 @@ struct GlobalResourceEntry {
     pub borrow_mut: bool,
 }
 
-impl GlobalResourceEntry {
-    pub fn new(data: Vec<u8>) -> Self {
-        Self {
-            data,
-            borrow_count: 0,
-            borrow_mut: false,
-        }
-    }
-}
-
 pub struct GlobalStorage {
-    storage: HashMap<Key, GlobalResourceEntry>,
+    backend: Box<dyn StorageBackend>,
+    borrows: HashMap<Key, BorrowState>,
 }
 
 impl GlobalStorage {
-    /// Create a new global storage instance.
+    /// Create a new global storage instance backed by memory.
     pub fn new() -> Self {
+        Self::with_backend(Box::new(MemoryBackend::new()))
+    }
+
+    /// Create a new global storage instance on top of the given backend.
+    pub fn with_backend(backend: Box<dyn StorageBackend>) -> Self {
         Self {
-            storage: HashMap::new(),
+            backend,
+            borrows: HashMap::new(),
         }
     }
+
+    /// Access the underlying backend, e.g. to inspect stored resources.
+    pub fn backend(&self) -> &dyn StorageBackend {
+        self.backend.as_ref()
+    }
 }
 
 impl Default for GlobalStorage {
@@ -107,18 +209,17 @@ impl Storage for GlobalStorage {
     ) -> Result<(), ProgramError> {
         debug!("Storing global value of type {tag:x?} at address {address:?}",);
 
-        let key = Key::new(address, tag);
-
         // Check if the address already exists
-        if self.storage.contains_key(&key) {
+        if self.backend.contains(address, tag) {
             return Err(ProgramError::MemoryAccess(format!(
                 "global already exists at address {address:?} with type {tag:x?}",
             )));
         }
 
-        // Store the value in the storage map
-        self.storage.insert(key, GlobalResourceEntry::new(value));
-        debug!("storage: {:x?}", &self.storage);
+        // Store the value in the backend
+        self.backend.set(address, tag, value);
+        self.borrows.remove(&Key::new(address, tag));
+        debug!("borrows: {:x?}", &self.borrows);
 
         Ok(())
     }
@@ -132,17 +233,19 @@ impl Storage for GlobalStorage {
     ) -> Result<(), ProgramError> {
         debug!("Storing global value of type {tag:x?} at address {address:?}",);
 
+        if !self.backend.contains(address, tag) {
+            return Err(ProgramError::MemoryAccess(format!(
+                "global not found at {address:?}"
+            )));
+        }
         let key = Key::new(address, tag);
-
-        let entry = self.storage.get(&key).ok_or_else(|| {
-            ProgramError::MemoryAccess(format!("global not found at {address:?}"))
-        })?;
-        if entry.borrow_mut {
-            // update the value in the storage map if it was mutably borrowed
-            self.storage.insert(key, GlobalResourceEntry::new(value));
+        if self.borrows.get(&key).is_some_and(|b| b.borrow_mut) {
+            // update the value in the backend if it was mutably borrowed
+            self.backend.set(address, tag, value);
+            self.borrows.remove(&key);
         }
 
-        debug!("updated storage: {:x?}", &self.storage);
+        debug!("updated borrows: {:x?}", &self.borrows);
         Ok(())
     }
 
@@ -157,30 +260,31 @@ impl Storage for GlobalStorage {
         debug!("Loading global value of type {tag:x?} at address {address:?}, is_mut: {is_mut}, remove: {remove}",);
 
         let key = Key::new(address, tag);
-        let value = self.storage.get_mut(&key).ok_or_else(|| {
+        let rv = self.backend.get(address, tag).ok_or_else(|| {
             ProgramError::MemoryAccess(format!("global not found at {address:?}"))
         })?;
-        debug!("Found global value: {value:?} at address {address:?} with type {tag:x?}");
-        let rv = value.data.clone();
+        debug!("Found global value: {rv:?} at address {address:?} with type {tag:x?}");
         if remove {
-            self.storage.remove(&key);
+            self.backend.remove(address, tag);
+            self.borrows.remove(&key);
         } else {
-            if value.borrow_mut {
+            let state = self.borrows.entry(key).or_default();
+            if state.borrow_mut {
                 return Err(ProgramError::MemoryAccess(format!(
                     "mutable borrow already exists for global at {address:?} with type {tag:?}",
                 )));
             }
             if is_mut {
-                if value.borrow_count > 0 {
+                if state.borrow_count > 0 {
                     return Err(ProgramError::MemoryAccess(format!(
                         "cannot create mutable borrow for global at {address:?} with type {tag:?} while there are active shared borrows",
                     )));
                 }
-                value.borrow_mut = true;
+                state.borrow_mut = true;
             }
-            value.borrow_count += 1;
+            state.borrow_count += 1;
         }
-        debug!("storage: {:x?}", &self.storage);
+        debug!("borrows: {:x?}", &self.borrows);
 
         Ok(rv)
     }
@@ -189,10 +293,8 @@ impl Storage for GlobalStorage {
     fn exists(&mut self, address: MoveAddress, tag: StructTagHash) -> Result<bool, ProgramError> {
         debug!("Exists global value of type {tag:x?} at address {address:?}",);
 
-        let key = Key::new(address, tag);
-        let value = self.storage.contains_key(&key);
+        let value = self.backend.contains(address, tag);
         debug!("Global exists: {value}");
-        debug!("storage: {:x?}", &self.storage);
         Ok(value)
     }
 
@@ -201,7 +303,7 @@ impl Storage for GlobalStorage {
         debug!("Releasing global value at address {address:?} with tag {tag:x?}",);
 
         let key = Key::new(address, tag);
-        if let Some(entry) = self.storage.get_mut(&key) {
+        if let Some(entry) = self.borrows.get_mut(&key) {
             if entry.borrow_mut {
                 // If there's a mutable borrow, we can release it
                 debug!("Released mutable borrow for global at {address:?} with type {tag:?}");
@@ -218,13 +320,13 @@ impl Storage for GlobalStorage {
         } else {
             debug!("No global found at {address:?} with type {tag:?} to release");
         }
-        debug!("storage: {:x?}", &self.storage);
+        debug!("borrows: {:x?}", &self.borrows);
     }
 
     fn release_all(&mut self) {
         debug!("Releasing all global resources");
         let keys: Vec<(MoveAddress, StructTagHash)> = self
-            .storage
+            .borrows
             .keys()
             .map(|Key(addr, tag)| (*addr, *tag))
             .collect();
@@ -237,10 +339,35 @@ impl Storage for GlobalStorage {
 
     fn is_borrowed(&self, address: MoveAddress, tag: StructTagHash) -> bool {
         let key = Key::new(address, tag);
-        if let Some(entry) = self.storage.get(&key) {
+        if let Some(entry) = self.borrows.get(&key) {
             entry.borrow_count > 0 || entry.borrow_mut
         } else {
             false
         }
     }
+
+    fn snapshot(&self) -> StorageSnapshot {
+        let resources = self
+            .backend
+            .entries()
+            .into_iter()
+            .map(|(address, tag, data)| ((address.0, tag), data))
+            .collect();
+        StorageSnapshot { resources }
+    }
+
+    fn restore(&mut self, snapshot: &StorageSnapshot) {
+        for (address, tag, _) in self.backend.entries() {
+            self.backend.remove(address, tag);
+        }
+        for (address, tag, data) in snapshot.entries() {
+            self.backend.set(address, tag, data.to_vec());
+        }
+        self.borrows.clear();
+        debug!("Restored {} global resources", snapshot.len());
+    }
+
+    fn flush(&mut self) -> Result<(), ProgramError> {
+        self.backend.flush()
+    }
 }
@@ -1,3 +1,4 @@
+use crate::REVIVE_ADDRESS_LENGTH;
 use borsh::{BorshDeserialize, BorshSerialize};
 
 /// A Move vector with an untyped buffer.
@@ -101,6 +102,17 @@ impl MoveType {
             type_info: core::ptr::null(),
         }
     }
+
+    /// Whether this is a struct type declared with all of `abilities`.
+    ///
+    /// # Safety
+    ///
+    /// `type_info` must point to valid type info, i.e. this must be called in the guest.
+    pub unsafe fn has_abilities(&self, abilities: u64) -> bool {
+        self.type_desc == TypeDesc::Struct
+            && !self.type_info.is_null()
+            && (*self.type_info).struct_.abilities & abilities == abilities
+    }
 }
 
 /// # Safety
@@ -187,8 +199,16 @@ pub struct StructTypeInfo {
     pub size: u64,
     /// Alignment of the struct.
     pub alignment: u64,
+    /// Declared abilities of the struct, see the `ABILITY_*` bits.
+    pub abilities: u64,
 }
 
+// Ability bits of `StructTypeInfo::abilities`, numbered like Move's `AbilitySet`.
+pub const ABILITY_COPY: u64 = 0x1;
+pub const ABILITY_DROP: u64 = 0x2;
+pub const ABILITY_STORE: u64 = 0x4;
+pub const ABILITY_KEY: u64 = 0x8;
+
 unsafe impl Sync for StructTypeInfo {}
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
@@ -213,18 +233,61 @@ pub struct AnyValue(u8);
 #[derive(Copy, Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
 pub struct MoveSigner(pub MoveAddress);
 
+/// Length of a Move address. It must agree with the `--address-length` the code is compiled
+/// with, the `-addr20` runtime variants are built with the `address20` feature.
+#[cfg(not(feature = "address20"))]
 pub const ACCOUNT_ADDRESS_LENGTH: usize = 32;
+#[cfg(feature = "address20")]
+pub const ACCOUNT_ADDRESS_LENGTH: usize = 20;
+
+// H160 accounts are stored in the first bytes of a Move address
+const _: () = assert!(ACCOUNT_ADDRESS_LENGTH >= REVIVE_ADDRESS_LENGTH);
 
 /// A Move address.
 ///
 /// This is mapped to the address size of the target platform, and may
 /// differ from Move VM.
 ///
-/// Bytes are in little-endian order.
+/// Bytes are in little-endian order, like the compiler emits address literals. Serialized,
+/// they are in BCS order instead, the order the address is written in: `@0x1` is
+/// serialized as zeros followed by `0x01`.
 #[repr(transparent)]
-#[derive(Copy, Clone, Eq, Hash, PartialEq, BorshSerialize, BorshDeserialize)]
+#[derive(Copy, Clone, Eq, Hash, PartialEq)]
 pub struct MoveAddress(pub [u8; ACCOUNT_ADDRESS_LENGTH]);
 
+impl BorshSerialize for MoveAddress {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        let mut bytes = self.0;
+        bytes.reverse();
+        writer.write_all(&bytes)
+    }
+}
+
+impl BorshDeserialize for MoveAddress {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let mut bytes = <[u8; ACCOUNT_ADDRESS_LENGTH]>::deserialize_reader(reader)?;
+        bytes.reverse();
+        Ok(MoveAddress(bytes))
+    }
+}
+
+impl MoveAddress {
+    /// The Move address of a pallet-revive account: the H160 followed by zeros.
+    pub fn from_h160(h160: [u8; REVIVE_ADDRESS_LENGTH]) -> Self {
+        let mut address = [0; ACCOUNT_ADDRESS_LENGTH];
+        address[..REVIVE_ADDRESS_LENGTH].copy_from_slice(&h160);
+        MoveAddress(address)
+    }
+
+    /// The pallet-revive account of this address, its first bytes. The remaining bytes of a
+    /// 32 byte address are dropped.
+    pub fn to_h160(&self) -> [u8; REVIVE_ADDRESS_LENGTH] {
+        let mut h160 = [0; REVIVE_ADDRESS_LENGTH];
+        h160.copy_from_slice(&self.0[..REVIVE_ADDRESS_LENGTH]);
+        h160
+    }
+}
+
 impl core::fmt::Debug for MoveAddress {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str("@")?;
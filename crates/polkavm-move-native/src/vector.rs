@@ -854,6 +854,10 @@ impl TypedMoveBorrowedRustVecMut<'_> {
     // Safety: src must have same type.
     #[allow(clippy::missing_safety_doc)]
     pub unsafe fn copy_from(&mut self, srcv: &TypedMoveBorrowedRustVec) {
+        if self.copy_primitives_from(srcv) {
+            return;
+        }
+
         let src_len = srcv.len();
         let dst_len = self.len();
 
@@ -865,10 +869,51 @@ impl TypedMoveBorrowedRustVecMut<'_> {
         // Now copy.
         for i in 0..src_len {
             let se = srcv.borrow(i);
-            // fixme this is incorrect for vectors and structs containing vectors
             let septr = se as *const AnyValue as *mut AnyValue;
-            self.push_back(septr);
+            match self {
+                TypedMoveBorrowedRustVecMut::Vector(inner_type, _) => {
+                    // Nested vectors own their buffers, which may live in read-only
+                    // data for vector literals. Copy the elements instead of aliasing them.
+                    let inner_type = *inner_type;
+                    let src_inner = &*(septr as *const MoveUntypedVector);
+                    let mut dst_inner = MoveUntypedVector::empty(&inner_type);
+                    TypedMoveBorrowedRustVecMut::new(&inner_type, &mut dst_inner)
+                        .copy_from(&TypedMoveBorrowedRustVec::new(&inner_type, src_inner));
+                    self.push_back(&mut dst_inner as *mut MoveUntypedVector as *mut AnyValue);
+                }
+                // fixme this is incorrect for structs containing vectors
+                _ => self.push_back(septr),
+            }
+        }
+    }
+
+    // Vectors of primitives are plain data: replace the elements with the source buffer in
+    // one copy, a memcpy, instead of popping and pushing them one at a time. This is the
+    // path of every `copy` of a `vector<u8>` and of every byte string literal.
+    //
+    // Returns false, copying nothing, for elements that need a deep copy.
+    unsafe fn copy_primitives_from(&mut self, srcv: &TypedMoveBorrowedRustVec) -> bool {
+        use TypedMoveBorrowedRustVec as Src;
+        use TypedMoveBorrowedRustVecMut as Dst;
+
+        fn replace<T: Copy>(dst: &mut Vec<T>, src: &[T]) {
+            dst.clear();
+            dst.extend_from_slice(src);
+        }
+
+        match (self, srcv) {
+            (Dst::Bool(dst), Src::Bool(src)) => replace(dst, src),
+            (Dst::U8(dst), Src::U8(src)) => replace(dst, src),
+            (Dst::U16(dst), Src::U16(src)) => replace(dst, src),
+            (Dst::U32(dst), Src::U32(src)) => replace(dst, src),
+            (Dst::U64(dst), Src::U64(src)) => replace(dst, src),
+            (Dst::U128(dst), Src::U128(src)) => replace(dst, src),
+            (Dst::U256(dst), Src::U256(src)) => replace(dst, src),
+            (Dst::Address(dst), Src::Address(src)) => replace(dst, src),
+            (Dst::Signer(dst), Src::Signer(src)) => replace(dst, src),
+            _ => return false,
         }
+        true
     }
 }
 
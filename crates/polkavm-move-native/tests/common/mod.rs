@@ -0,0 +1,169 @@
+//! Type descriptors laid out like the ones the compiler emits, and helpers to pass Rust values
+//! to the runtime as Move values.
+
+#![allow(dead_code)]
+
+use core::{mem, ptr};
+use polkavm_move_native::{
+    structs,
+    types::{
+        AnyValue, MoveType, MoveUntypedVector, StaticTypeName, StructFieldInfo, StructTypeInfo,
+        TypeDesc, TypeInfo, VectorTypeInfo, ABILITY_COPY, ABILITY_DROP,
+    },
+};
+
+pub const fn name(name: &'static str) -> StaticTypeName {
+    StaticTypeName {
+        ptr: name.as_ptr(),
+        len: name.len() as u64,
+    }
+}
+
+const fn primitive(name_: &'static str, type_desc: TypeDesc) -> MoveType {
+    MoveType {
+        name: name(name_),
+        type_desc,
+        type_info: ptr::null(),
+    }
+}
+
+pub static BOOL: MoveType = primitive("bool", TypeDesc::Bool);
+pub static U8: MoveType = primitive("u8", TypeDesc::U8);
+pub static U64: MoveType = primitive("u64", TypeDesc::U64);
+pub static U128: MoveType = primitive("u128", TypeDesc::U128);
+pub static U256: MoveType = primitive("u256", TypeDesc::U256);
+pub static ADDRESS: MoveType = primitive("address", TypeDesc::Address);
+
+static VECTOR_U8_INFO: TypeInfo = TypeInfo {
+    vector: VectorTypeInfo { element_type: &U8 },
+};
+pub static VECTOR_U8: MoveType = MoveType {
+    name: name("vector<u8>"),
+    type_desc: TypeDesc::Vector,
+    type_info: &VECTOR_U8_INFO,
+};
+
+static VECTOR_U64_INFO: TypeInfo = TypeInfo {
+    vector: VectorTypeInfo { element_type: &U64 },
+};
+pub static VECTOR_U64: MoveType = MoveType {
+    name: name("vector<u64>"),
+    type_desc: TypeDesc::Vector,
+    type_info: &VECTOR_U64_INFO,
+};
+
+static VECTOR_ADDRESS_INFO: TypeInfo = TypeInfo {
+    vector: VectorTypeInfo {
+        element_type: &ADDRESS,
+    },
+};
+pub static VECTOR_ADDRESS: MoveType = MoveType {
+    name: name("vector<address>"),
+    type_desc: TypeDesc::Vector,
+    type_info: &VECTOR_ADDRESS_INFO,
+};
+
+static VECTOR_VECTOR_U64_INFO: TypeInfo = TypeInfo {
+    vector: VectorTypeInfo {
+        element_type: &VECTOR_U64,
+    },
+};
+pub static VECTOR_VECTOR_U64: MoveType = MoveType {
+    name: name("vector<vector<u64>>"),
+    type_desc: TypeDesc::Vector,
+    type_info: &VECTOR_VECTOR_U64_INFO,
+};
+
+/// `struct Record has copy, drop { id: u64, items: vector<u64>, flag: bool }`
+#[repr(C)]
+pub struct Record {
+    pub id: u64,
+    pub items: MoveUntypedVector,
+    pub flag: bool,
+}
+
+static RECORD_FIELDS: [StructFieldInfo; 3] = [
+    StructFieldInfo {
+        type_: primitive("u64", TypeDesc::U64),
+        offset: mem::offset_of!(Record, id) as u64,
+        name: name("id"),
+    },
+    StructFieldInfo {
+        type_: MoveType {
+            name: name("vector<u64>"),
+            type_desc: TypeDesc::Vector,
+            type_info: &VECTOR_U64_INFO,
+        },
+        offset: mem::offset_of!(Record, items) as u64,
+        name: name("items"),
+    },
+    StructFieldInfo {
+        type_: primitive("bool", TypeDesc::Bool),
+        offset: mem::offset_of!(Record, flag) as u64,
+        name: name("flag"),
+    },
+];
+static RECORD_INFO: TypeInfo = TypeInfo {
+    struct_: StructTypeInfo {
+        field_array_ptr: &RECORD_FIELDS as *const [StructFieldInfo; 3] as *const StructFieldInfo,
+        field_array_len: 3,
+        size: mem::size_of::<Record>() as u64,
+        alignment: mem::align_of::<Record>() as u64,
+        abilities: ABILITY_COPY | ABILITY_DROP,
+    },
+};
+pub static RECORD: MoveType = MoveType {
+    name: name("0x1::test::Record"),
+    type_desc: TypeDesc::Struct,
+    type_info: &RECORD_INFO,
+};
+
+static VECTOR_RECORD_INFO: TypeInfo = TypeInfo {
+    vector: VectorTypeInfo {
+        element_type: &RECORD,
+    },
+};
+pub static VECTOR_RECORD: MoveType = MoveType {
+    name: name("vector<0x1::test::Record>"),
+    type_desc: TypeDesc::Vector,
+    type_info: &VECTOR_RECORD_INFO,
+};
+
+impl Record {
+    pub fn new(id: u64, items: Vec<u64>, flag: bool) -> Record {
+        Record {
+            id,
+            items: MoveUntypedVector::from_rust_vec(items),
+            flag,
+        }
+    }
+
+    /// Free the vector owned by the record, like the compiler does when a record is dropped.
+    pub fn destroy(mut self) {
+        unsafe { structs::destroy(&(*RECORD.type_info).struct_, as_any_mut(&mut self)) }
+    }
+}
+
+/// A Move vector of `elements`, owned by the caller, who frees it with
+/// [`MoveUntypedVector::destroy`].
+pub fn vector<T>(elements: Vec<T>) -> MoveUntypedVector {
+    MoveUntypedVector::from_rust_vec(elements)
+}
+
+/// A Move vector of vectors of `u64`.
+pub fn vector_of_vectors(elements: &[&[u64]]) -> MoveUntypedVector {
+    vector(
+        elements
+            .iter()
+            .map(|elements| vector(elements.to_vec()))
+            .collect(),
+    )
+}
+
+pub fn as_any<T>(value: &T) -> &AnyValue {
+    unsafe { &*(value as *const T as *const AnyValue) }
+}
+
+pub fn as_any_mut<T>(value: &mut T) -> *mut AnyValue {
+    value as *mut T as *mut AnyValue
+}
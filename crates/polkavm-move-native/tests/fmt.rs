@@ -0,0 +1,32 @@
+use polkavm_move_native::fmt::Message;
+
+#[test]
+pub fn integers_are_formatted_like_core_fmt() {
+    for value in [
+        0,
+        9,
+        10,
+        u64::MAX as u128,
+        u64::MAX as u128 + 1,
+        10_000_000_000_000_000_000 * 10_000_000_000_000_000_000 + 7,
+        u128::MAX,
+    ] {
+        let mut message = Message::<96>::new();
+        message.push_u128(value).push_str(" ").push_hex_u128(value);
+        assert_eq!(message.as_str(), format!("{value} {value:#x}"));
+    }
+    let mut message = Message::<64>::new();
+    message.push_u64(4017).push_str(":").push_hex_u64(0xdead);
+    assert_eq!(message.as_str(), "4017:0xdead");
+}
+
+#[test]
+pub fn long_messages_are_truncated() {
+    let mut message = Message::<8>::new();
+    message.push_str("abort ").push_u64(12345);
+    assert_eq!(message.as_str(), "abort 12");
+    // a character cut in half is dropped
+    let mut message = Message::<3>::new();
+    message.push_str("abé");
+    assert_eq!(message.as_str(), "ab");
+}
@@ -0,0 +1,27 @@
+use hex_literal::hex;
+use polkavm_move_native::hash::{blake2b256, keccak256};
+
+#[test]
+pub fn keccak256_matches_ethereum() {
+    assert_eq!(
+        keccak256(b""),
+        hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+    );
+    // the selector of `transfer(address,uint256)` is its first four bytes
+    assert_eq!(
+        keccak256(b"transfer(address,uint256)")[..4],
+        hex!("a9059cbb")
+    );
+}
+
+#[test]
+pub fn blake2b256_matches_substrate() {
+    assert_eq!(
+        blake2b256(b""),
+        hex!("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+    );
+    assert_eq!(
+        blake2b256(b"abc"),
+        hex!("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+    );
+}
@@ -0,0 +1,316 @@
+mod common;
+
+use common::*;
+use core::mem::MaybeUninit;
+use polkavm_move_native::{
+    serialization::{
+        decode_bcs, decode_call_data_arg, deserialize, encode_call_data_value, serialize,
+    },
+    structs,
+    types::{
+        self, MoveAddress, MoveByteVector, MoveType, MoveUntypedVector, ACCOUNT_ADDRESS_LENGTH,
+    },
+    vector::TypedMoveBorrowedRustVec,
+};
+
+/// The bytes of `bytes`, freeing it.
+fn take(bytes: MoveByteVector) -> Vec<u8> {
+    unsafe { bytes.into_rust_vec() }
+}
+
+/// Deserialize a vector of `ty` from `bytes`, check it equals `expected` and free both.
+unsafe fn check_vector(ty: &MoveType, bytes: &[u8], expected: MoveUntypedVector) {
+    let bytes = MoveByteVector::from_rust_vec(bytes.to_vec());
+    let mut v = MaybeUninit::<MoveUntypedVector>::uninit();
+    deserialize(ty, &bytes, as_any_mut(&mut v));
+    let v = v.assume_init();
+    let elt = (*ty.type_info).vector.element_type;
+    assert!(TypedMoveBorrowedRustVec::new(elt, &v)
+        .cmp_eq(&TypedMoveBorrowedRustVec::new(elt, &expected)));
+    v.destroy(elt);
+    expected.destroy(elt);
+    take(bytes);
+}
+
+#[test]
+pub fn test_primitive_round_trip() {
+    unsafe {
+        for value in [0u64, 1, u64::MAX] {
+            let bytes = take(serialize(&U64, as_any(&value)));
+            assert_eq!(bytes, value.to_le_bytes());
+            let bytes = MoveByteVector::from_rust_vec(bytes);
+            let mut out = 0u64;
+            deserialize(&U64, &bytes, as_any_mut(&mut out));
+            assert_eq!(out, value);
+            take(bytes);
+        }
+        for value in [false, true] {
+            assert_eq!(take(serialize(&BOOL, as_any(&value))), [value as u8]);
+        }
+    }
+}
+
+/// Serialize `value` of `ty`, check it serializes to `expected`, and deserialize it back.
+unsafe fn check_round_trip<T: Copy + PartialEq + core::fmt::Debug>(
+    ty: &MoveType,
+    value: T,
+    expected: &[u8],
+) {
+    let bytes = serialize(ty, as_any(&value));
+    assert_eq!(bytes.as_rust_vec().as_slice(), expected, "{value:?}");
+    let mut out = MaybeUninit::<T>::uninit();
+    deserialize(ty, &bytes, as_any_mut(&mut out));
+    assert_eq!(out.assume_init(), value);
+    take(bytes);
+}
+
+/// The address `@0x<hex>`, little-endian like the compiler emits it.
+fn address(hex: u128) -> MoveAddress {
+    let mut address = [0; ACCOUNT_ADDRESS_LENGTH];
+    address[..16].copy_from_slice(&hex.to_le_bytes());
+    MoveAddress(address)
+}
+
+/// BCS of the address `@0x<hex>`: the address as written, most significant byte first.
+fn address_bcs(hex: u128) -> Vec<u8> {
+    let mut bytes = vec![0; ACCOUNT_ADDRESS_LENGTH - 16];
+    bytes.extend(hex.to_be_bytes());
+    bytes
+}
+
+#[test]
+pub fn test_wide_integer_golden_vectors() {
+    unsafe {
+        check_round_trip(&U128, 0u128, &[0; 16]);
+        check_round_trip(
+            &U128,
+            0x0102030405060708090a0b0c0d0e0f10u128,
+            &[16, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1],
+        );
+        check_round_trip(&U128, u128::MAX, &[0xff; 16]);
+
+        // `[low, high]` halves, each little-endian, so the whole value is little-endian
+        let mut bytes = [0; 32];
+        bytes[16] = 1;
+        check_round_trip(&U256, types::U256([0, 1]), &bytes);
+        let mut bytes = [0; 32];
+        bytes[0] = 0x2a;
+        bytes[31] = 0x80;
+        check_round_trip(&U256, types::U256([0x2a, 1 << 127]), &bytes);
+        check_round_trip(&U256, types::U256([u128::MAX; 2]), &[0xff; 32]);
+    }
+}
+
+#[test]
+pub fn test_wide_integer_byte_order() {
+    unsafe {
+        for bit in 0..128 {
+            for value in [1u128 << bit, (1u128 << bit) - 1, !(1u128 << bit)] {
+                check_round_trip(&U128, value, &value.to_le_bytes());
+            }
+        }
+        for bit in 0..256 {
+            let (lo, hi) = match bit {
+                0..=127 => (1u128 << bit, 0),
+                _ => (0, 1u128 << (bit - 128)),
+            };
+            for value in [types::U256([lo, hi]), types::U256([!lo, !hi])] {
+                let mut expected = value.0[0].to_le_bytes().to_vec();
+                expected.extend(value.0[1].to_le_bytes());
+                check_round_trip(&U256, value, &expected);
+            }
+        }
+    }
+}
+
+#[test]
+pub fn test_address_byte_order() {
+    unsafe {
+        check_round_trip(&ADDRESS, address(0x1), &address_bcs(0x1));
+        check_round_trip(&ADDRESS, address(0xcafe), &address_bcs(0xcafe));
+        let mut bcs = vec![0; ACCOUNT_ADDRESS_LENGTH];
+        bcs[ACCOUNT_ADDRESS_LENGTH - 2..].copy_from_slice(&[0xca, 0xfe]);
+        assert_eq!(address_bcs(0xcafe), bcs);
+
+        // every byte lands in the mirrored position
+        for i in 0..ACCOUNT_ADDRESS_LENGTH {
+            let mut memory = [0; ACCOUNT_ADDRESS_LENGTH];
+            memory[i] = i as u8 + 1;
+            let mut bcs = [0; ACCOUNT_ADDRESS_LENGTH];
+            bcs[ACCOUNT_ADDRESS_LENGTH - 1 - i] = i as u8 + 1;
+            check_round_trip(&ADDRESS, MoveAddress(memory), &bcs);
+        }
+
+        let v = vector(vec![address(0x1), address(0x2)]);
+        let bytes = take(serialize(&VECTOR_ADDRESS, as_any(&v)));
+        let mut expected = vec![2, 0, 0, 0];
+        expected.extend(address_bcs(0x1));
+        expected.extend(address_bcs(0x2));
+        assert_eq!(bytes, expected);
+        check_vector(&VECTOR_ADDRESS, &bytes, v);
+
+        // call data is BCS, like `Abi::encode_call` writes it
+        let mut call_data = address_bcs(0xa000);
+        call_data.extend([1]);
+        call_data.extend(address_bcs(0x1));
+        let bytes = &mut &call_data[..];
+        let mut owner = MaybeUninit::<MoveAddress>::uninit();
+        decode_call_data_arg(&ADDRESS, bytes, as_any_mut(&mut owner)).unwrap();
+        assert_eq!(owner.assume_init(), address(0xa000));
+        let mut v = MaybeUninit::<MoveUntypedVector>::uninit();
+        decode_call_data_arg(&VECTOR_ADDRESS, bytes, as_any_mut(&mut v)).unwrap();
+        assert!(bytes.is_empty());
+        assert_eq!(
+            v.assume_init().into_rust_vec::<MoveAddress>(),
+            [address(0x1)]
+        );
+    }
+}
+
+#[test]
+pub fn test_vector_round_trip() {
+    unsafe {
+        let v = vector(vec![1u8, 2, 3]);
+        let bytes = take(serialize(&VECTOR_U8, as_any(&v)));
+        assert_eq!(bytes, [3, 0, 0, 0, 1, 2, 3]);
+        check_vector(&VECTOR_U8, &bytes, v);
+
+        let v = vector_of_vectors(&[&[1], &[], &[2, 3]]);
+        let bytes = take(serialize(&VECTOR_VECTOR_U64, as_any(&v)));
+        assert_eq!(bytes.len(), 4 + 3 * 4 + 3 * 8);
+        assert_eq!(bytes[..8], [3, 0, 0, 0, 1, 0, 0, 0]);
+        check_vector(&VECTOR_VECTOR_U64, &bytes, v);
+    }
+}
+
+#[test]
+pub fn test_struct_round_trip() {
+    let record = Record::new(5, vec![6, 7], true);
+    unsafe {
+        let bytes = serialize(&RECORD, as_any(&record));
+        assert_eq!(bytes.as_rust_vec().len(), 8 + 4 + 2 * 8 + 1);
+        let mut out = MaybeUninit::<Record>::uninit();
+        deserialize(&RECORD, &bytes, as_any_mut(&mut out));
+        let out = out.assume_init();
+        assert!(structs::cmp_eq(&RECORD, as_any(&record), as_any(&out)));
+        out.destroy();
+        take(bytes);
+
+        let records = vector(vec![
+            Record::new(1, vec![], false),
+            Record::new(2, vec![3], true),
+        ]);
+        let bytes = take(serialize(&VECTOR_RECORD, as_any(&records)));
+        check_vector(&VECTOR_RECORD, &bytes, records);
+    }
+    record.destroy();
+}
+
+#[test]
+pub fn test_malformed_input() {
+    unsafe {
+        let mut out = 0u64;
+        for bytes in [vec![1, 2, 3], vec![0; 9]] {
+            let bytes = MoveByteVector::from_rust_vec(bytes);
+            assert!(decode_bcs(&U64, &bytes, as_any_mut(&mut out)).is_err());
+            take(bytes);
+        }
+        let mut flag = false;
+        let bytes = MoveByteVector::from_rust_vec(vec![2]);
+        assert!(decode_bcs(&BOOL, &bytes, as_any_mut(&mut flag)).is_err());
+        take(bytes);
+
+        // Lengths beyond the input are rejected before allocating the vector.
+        let mut v = MaybeUninit::<MoveUntypedVector>::uninit();
+        for (ty, bytes) in [
+            (&VECTOR_U8, vec![4, 1, 2, 3]),
+            (&VECTOR_VECTOR_U64, vec![0xff, 0xff, 0xff, 0xff, 0x0f, 0]),
+            (&VECTOR_RECORD, vec![1]),
+        ] {
+            let bytes = MoveByteVector::from_rust_vec(bytes);
+            assert!(decode_bcs(ty, &bytes, as_any_mut(&mut v)).is_err());
+            take(bytes);
+        }
+    }
+}
+
+#[test]
+pub fn test_decode_bcs_golden_vector() {
+    // `Record { id: 42, items: vector[1, 2], flag: true }` as BCS encoders write it, with a
+    // ULEB128 vector length
+    let golden = hex_literal::hex!("2a00000000000000 02 0100000000000000 0200000000000000 01");
+    let expected = Record::new(42, vec![1, 2], true);
+    unsafe {
+        let bytes = MoveByteVector::from_rust_vec(golden.to_vec());
+        let mut out = MaybeUninit::<Record>::uninit();
+        decode_bcs(&RECORD, &bytes, as_any_mut(&mut out)).unwrap();
+        let out = out.assume_init();
+        assert!(structs::cmp_eq(&RECORD, as_any(&expected), as_any(&out)));
+        out.destroy();
+        take(bytes);
+    }
+    expected.destroy();
+}
+
+#[test]
+pub fn test_decode_call_data() {
+    unsafe {
+        // A u64 and a vector<u64> of 130 elements, whose length takes two ULEB128 bytes.
+        let elements = (0..130u64).collect::<Vec<_>>();
+        let mut call_data = 9u64.to_le_bytes().to_vec();
+        call_data.extend([0x82, 0x01]);
+        call_data.extend(elements.iter().flat_map(|e| e.to_le_bytes()));
+        let bytes = &mut &call_data[..];
+
+        let mut n = 0u64;
+        decode_call_data_arg(&U64, bytes, as_any_mut(&mut n)).unwrap();
+        assert_eq!(n, 9);
+        let mut v = MaybeUninit::<MoveUntypedVector>::uninit();
+        decode_call_data_arg(&VECTOR_U64, bytes, as_any_mut(&mut v)).unwrap();
+        assert!(bytes.is_empty());
+        assert_eq!(v.assume_init().into_rust_vec::<u64>(), elements);
+
+        // Vector lengths are checked against the call data.
+        let mut v = MaybeUninit::<MoveUntypedVector>::uninit();
+        assert!(decode_call_data_arg(&VECTOR_U64, &mut &[3u8, 0][..], as_any_mut(&mut v)).is_err());
+    }
+}
+
+#[test]
+pub fn test_structs_in_call_data() {
+    let records = vector(vec![
+        Record::new(1, vec![], false),
+        Record::new(2, vec![3, 4], true),
+    ]);
+    unsafe {
+        // vector lengths are ULEB128, in structs and vectors of structs too
+        let mut call_data = vec![];
+        encode_call_data_value(&VECTOR_RECORD, as_any(&records), &mut call_data);
+        let mut expected = vec![2];
+        expected.extend(1u64.to_le_bytes());
+        expected.extend([0, 0]);
+        expected.extend(2u64.to_le_bytes());
+        expected.extend([2]);
+        expected.extend(3u64.to_le_bytes());
+        expected.extend(4u64.to_le_bytes());
+        expected.extend([1]);
+        assert_eq!(call_data, expected);
+
+        let bytes = &mut &call_data[..];
+        let mut v = MaybeUninit::<MoveUntypedVector>::uninit();
+        decode_call_data_arg(&VECTOR_RECORD, bytes, as_any_mut(&mut v)).unwrap();
+        assert!(bytes.is_empty());
+        let v = v.assume_init();
+        assert!(TypedMoveBorrowedRustVec::new(&RECORD, &v)
+            .cmp_eq(&TypedMoveBorrowedRustVec::new(&RECORD, &records)));
+        v.destroy(&RECORD);
+
+        // a record cut short in its items
+        let mut record = MaybeUninit::<Record>::uninit();
+        let call_data = &expected[1 + 10..expected.len() - 2];
+        assert!(
+            decode_call_data_arg(&RECORD, &mut &call_data[..], as_any_mut(&mut record)).is_err()
+        );
+        records.destroy(&RECORD);
+    }
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::*;
+use polkavm_move_native::structs;
+
+#[test]
+pub fn test_cmp_eq() {
+    let record = Record::new(1, vec![2, 3], true);
+    for (other, equal) in [
+        (Record::new(1, vec![2, 3], true), true),
+        (Record::new(2, vec![2, 3], true), false),
+        (Record::new(1, vec![2], true), false),
+        (Record::new(1, vec![2, 4], true), false),
+        (Record::new(1, vec![2, 3], false), false),
+    ] {
+        unsafe {
+            assert_eq!(
+                structs::cmp_eq(&RECORD, as_any(&record), as_any(&other)),
+                equal
+            );
+            assert_eq!(
+                structs::cmp_eq(&RECORD, as_any(&other), as_any(&record)),
+                equal
+            );
+        }
+        other.destroy();
+    }
+    record.destroy();
+}
+
+#[test]
+pub fn test_walk_fields() {
+    let record = Record::new(7, vec![1], false);
+    unsafe {
+        let info = &(*RECORD.type_info).struct_;
+        let fields = structs::walk_fields(info, as_any(&record))
+            .map(|(ty, value, name)| {
+                (
+                    name.as_ascii_str().to_string(),
+                    ty.type_desc,
+                    value as *const _ as usize - &record as *const _ as usize,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("id".to_string(), U64.type_desc, 0),
+                ("items".to_string(), VECTOR_U64.type_desc, 8),
+                ("flag".to_string(), BOOL.type_desc, 32),
+            ]
+        );
+    }
+    record.destroy();
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::*;
+use core::mem::MaybeUninit;
+use polkavm_move_native::{
+    types::{AnyValue, MoveUntypedVector},
+    vector::{TypedMoveBorrowedRustVec, TypedMoveBorrowedRustVecMut},
+};
+
+unsafe fn read<T: Copy>(value: &AnyValue) -> T {
+    *(value as *const AnyValue as *const T)
+}
+
+#[test]
+pub fn test_push_borrow_swap_pop() {
+    unsafe {
+        let mut mv = MoveUntypedVector::empty(&U64);
+        {
+            let mut v = TypedMoveBorrowedRustVecMut::new(&U64, &mut mv);
+            for mut e in [10u64, 20, 30] {
+                v.push_back(as_any_mut(&mut e));
+            }
+            assert_eq!(v.len(), 3);
+            v.swap(0, 2);
+            let mut last = 0u64;
+            v.pop_back(as_any_mut(&mut last));
+            assert_eq!(last, 10);
+            *(v.borrow_mut(0) as *mut u64) += 1;
+        }
+        assert_eq!((mv.length, mv.ptr.is_null()), (2, false));
+        {
+            let v = TypedMoveBorrowedRustVec::new(&U64, &mv);
+            assert_eq!(v.len(), 2);
+            assert_eq!(read::<u64>(v.borrow(0)), 31);
+            assert_eq!(read::<u64>(v.borrow(1)), 20);
+        }
+        mv.destroy(&U64);
+    }
+}
+
+#[test]
+pub fn test_empty_vectors() {
+    unsafe {
+        for ty in [&BOOL, &U8, &U64, &VECTOR_U64, &RECORD] {
+            let mut mv = MoveUntypedVector::empty(ty);
+            assert_eq!(TypedMoveBorrowedRustVecMut::new(ty, &mut mv).len(), 0);
+            let other = MoveUntypedVector::empty(ty);
+            assert!(TypedMoveBorrowedRustVec::new(ty, &mv)
+                .cmp_eq(&TypedMoveBorrowedRustVec::new(ty, &other)));
+            other.destroy_empty(ty);
+            mv.destroy_empty(ty);
+        }
+    }
+}
+
+#[test]
+pub fn test_cmp_eq() {
+    unsafe {
+        let a = vector(vec![1u64, 2, 3]);
+        let b = vector(vec![1u64, 2, 3]);
+        let c = vector(vec![1u64, 2]);
+        let d = vector(vec![1u64, 2, 4]);
+        let eq = |x: &MoveUntypedVector, y: &MoveUntypedVector| {
+            TypedMoveBorrowedRustVec::new(&U64, x).cmp_eq(&TypedMoveBorrowedRustVec::new(&U64, y))
+        };
+        assert!(eq(&a, &b));
+        assert!(!eq(&a, &c));
+        assert!(!eq(&a, &d));
+        for v in [a, b, c, d] {
+            v.destroy(&U64);
+        }
+
+        let a = vector_of_vectors(&[&[1, 2], &[], &[3]]);
+        let b = vector_of_vectors(&[&[1, 2], &[], &[3]]);
+        let c = vector_of_vectors(&[&[1, 2], &[3], &[]]);
+        let eq = |x: &MoveUntypedVector, y: &MoveUntypedVector| {
+            TypedMoveBorrowedRustVec::new(&VECTOR_U64, x)
+                .cmp_eq(&TypedMoveBorrowedRustVec::new(&VECTOR_U64, y))
+        };
+        assert!(eq(&a, &b));
+        assert!(!eq(&a, &c));
+        for v in [a, b, c] {
+            v.destroy(&VECTOR_U64);
+        }
+    }
+}
+
+#[test]
+pub fn test_copy_from() {
+    unsafe {
+        let src = vector(vec![7u8, 8, 9]);
+        let mut dst = MoveUntypedVector::empty(&U8);
+        TypedMoveBorrowedRustVecMut::new(&U8, &mut dst)
+            .copy_from(&TypedMoveBorrowedRustVec::new(&U8, &src));
+        assert_eq!(dst.into_rust_vec::<u8>(), [7, 8, 9]);
+        src.destroy(&U8);
+    }
+}
+
+#[test]
+pub fn test_copy_from_is_deep() {
+    unsafe {
+        let src = vector_of_vectors(&[&[1, 2], &[3]]);
+        let mut dst = MoveUntypedVector::empty(&VECTOR_U64);
+        TypedMoveBorrowedRustVecMut::new(&VECTOR_U64, &mut dst)
+            .copy_from(&TypedMoveBorrowedRustVec::new(&VECTOR_U64, &src));
+
+        // Changing the copy leaves the source alone, and both own their inner vectors.
+        {
+            let mut v = TypedMoveBorrowedRustVecMut::new(&VECTOR_U64, &mut dst);
+            let inner = &mut *(v.borrow_mut(0) as *mut MoveUntypedVector);
+            let mut e = 42u64;
+            TypedMoveBorrowedRustVecMut::new(&U64, inner).push_back(as_any_mut(&mut e));
+        }
+        let expected = vector_of_vectors(&[&[1, 2], &[3]]);
+        assert!(TypedMoveBorrowedRustVec::new(&VECTOR_U64, &src)
+            .cmp_eq(&TypedMoveBorrowedRustVec::new(&VECTOR_U64, &expected)));
+        assert!(!TypedMoveBorrowedRustVec::new(&VECTOR_U64, &dst)
+            .cmp_eq(&TypedMoveBorrowedRustVec::new(&VECTOR_U64, &expected)));
+
+        for v in [src, dst, expected] {
+            v.destroy(&VECTOR_U64);
+        }
+    }
+}
+
+#[test]
+pub fn test_vector_of_structs() {
+    unsafe {
+        let mut mv = MoveUntypedVector::empty(&RECORD);
+        {
+            let mut v = TypedMoveBorrowedRustVecMut::new(&RECORD, &mut mv);
+            for i in 0..5 {
+                // The vector takes ownership of the record's items.
+                let mut record = Record::new(i, vec![i; i as usize], i % 2 == 0);
+                v.push_back(as_any_mut(&mut record));
+            }
+            assert_eq!(v.len(), 5);
+            v.swap(0, 4);
+            let mut last = MaybeUninit::<Record>::uninit();
+            v.pop_back(as_any_mut(&mut last));
+            let last = last.assume_init();
+            assert_eq!((last.id, last.items.length, last.flag), (0, 0, true));
+            last.destroy();
+        }
+        {
+            let v = TypedMoveBorrowedRustVec::new(&RECORD, &mv);
+            assert_eq!(v.len(), 4);
+            let first = &*(v.borrow(0) as *const AnyValue as *const Record);
+            assert_eq!((first.id, first.items.length, first.flag), (4, 4, true));
+
+            let other = vector(
+                [4, 1, 2, 3]
+                    .map(|i| Record::new(i, vec![i; i as usize], i % 2 == 0))
+                    .into(),
+            );
+            assert!(v.cmp_eq(&TypedMoveBorrowedRustVec::new(&RECORD, &other)));
+            other.destroy(&RECORD);
+        }
+        // Frees the items of the remaining records too.
+        mv.destroy(&RECORD);
+    }
+}
@@ -1,11 +1,37 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    io::{BufRead, Write},
+    path::Path,
+};
 
-use clap::{ArgGroup, Parser};
-use move_to_polka::linker::{create_instance, new_move_program};
-use polkavm::ProgramBlob;
-use tracing::info;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use move_polka_driver::{
+    artifacts::{
+        abi_path, Abi, CoverageMap, ExportSignature, PackageMetadata, SourceMap, StackTraceMap,
+    },
+    build::{build_move_package, package_build_options},
+    run::{
+        create_instance, create_stepping_instance, parse_to_blob, read_coverage_counters,
+        read_heap_stats, stored_resources, AbortCodes, BlobContract, FileBackend, GlobalStorage,
+        MockRandom, ProgramError, Runtime, StorageBackend, StoredResource, CONTRACT_ADDRESS,
+        REVIVE_ADDRESS_LENGTH,
+    },
+    testing::{fuzz, record, replay, FuzzOptions, Outcome, Session, Trace, DEFAULT_GAS},
+};
+use polkavm::CallError;
+use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
+// process exit codes of a call, so scripts can tell aborts from other failures
+// the call aborted, the abort code is printed to stdout
+const EXIT_ABORT: i32 = 2;
+// the call failed without a Move abort: a native lib panic, a trap or a VM error
+const EXIT_RUNTIME_ERROR: i32 = 3;
+// --expect-abort was given, but the call succeeded or aborted with another code
+const EXIT_UNEXPECTED_RESULT: i32 = 4;
+// the replayed run made other host calls than the trace, or ended otherwise
+const EXIT_DIVERGED: i32 = 5;
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 #[command(
@@ -14,23 +40,165 @@ use tracing_subscriber::EnvFilter;
         ArgGroup::new("input")
             .required(true)
             .args(["source", "module"])
-    )
+    ),
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     #[arg(short, long)]
     // path to Move source to compile and load
     pub source: Option<String>,
     #[arg(short, long)]
     // path to Move module to load
     pub module: Option<String>,
-    #[arg(short, long)]
+    #[arg(short, long, required = true)]
     // entry point function name to call
-    pub entrypoint: String,
+    pub entrypoint: Option<String>,
     #[arg(short, long, value_delimiter = ' ', num_args = 0..)]
     // parameters to pass to function - only u64 args are supported
     pub params: Vec<u64>,
+    #[arg(long)]
+    // path to a JSON file used to persist contract storage across successful invocations
+    pub state_file: Option<String>,
+    #[arg(long)]
+    // path to the JSON source map of the module, used to report the source line of a trap
+    pub source_map: Option<String>,
+    #[arg(long)]
+    // path to the coverage map of a --coverage build, prints the hit count of every line after the call
+    pub coverage_map: Option<String>,
+    #[arg(long)]
+    // path to the stack trace map of a --stack-traces build, prints the shadow stack on abort
+    pub stack_trace_map: Option<String>,
+    #[arg(long)]
+    // abort code, or its name, the call is expected to abort with, exits with 0 if it does
+    pub expect_abort: Option<String>,
+    #[arg(long)]
+    // JSON file naming application abort codes, e.g. {"E_INSUFFICIENT_BALANCE": 5}
+    pub abort_codes: Option<String>,
+    #[arg(long, value_name = "H160=AMOUNT")]
+    // mock pallet-revive balance of an account, repeatable; the contract itself is `contract`
+    pub balance: Vec<String>,
+    #[arg(long, value_name = "H160=BLOB")]
+    // another .polkavm blob the module can call with revive::call_contract, repeatable
+    pub contract: Vec<String>,
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    // block timestamp returned by revive::timestamp::ms, in milliseconds since the Unix epoch
+    pub mock_time: u64,
+    #[arg(long, default_value_t = 0)]
+    // seed of the deterministic stream of bytes returned by revive::random::bytes
+    pub mock_seed: u64,
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "table")]
+    // print the stored resources after the call, decoded if the ABI next to the blob lists them
+    pub dump_state: Option<StateFormat>,
+    #[arg(long, value_name = "FILE")]
+    // record every host call of the run to a JSON trace file, for the replay command
+    pub record_trace: Option<String>,
+}
+
+/// How `--dump-state` prints the stored resources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StateFormat {
+    /// A JSON array of the resources.
+    Json,
+    /// A line per resource.
+    Table,
 }
 
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the call data (selector and BCS encoded arguments) invoking an entry function
+    EncodeCall {
+        #[arg(long)]
+        // path to the ABI written by --emit-abi
+        abi: String,
+        #[arg(short, long)]
+        // entry function, as `module::function`
+        function: String,
+        #[arg(long, default_value = "[]")]
+        // arguments as a JSON array, e.g. '["0x1", 42]', without the signer
+        args: String,
+    },
+    /// Decode the BCS encoded return data of an entry function to JSON
+    DecodeReturn {
+        #[arg(long)]
+        // path to the ABI written by --emit-abi
+        abi: String,
+        #[arg(short, long)]
+        // entry function, as `module::function`
+        function: String,
+        // returned bytes, hex encoded
+        data: String,
+    },
+    /// Call entry functions with random arguments, reporting failures other than Move aborts
+    Fuzz {
+        #[arg(short, long)]
+        // path to the .polkavm blob
+        module: String,
+        #[arg(long)]
+        // path to the ABI written by --emit-abi
+        abi: String,
+        #[arg(long, default_value_t = 1000)]
+        // number of calls
+        runs: u64,
+        #[arg(long)]
+        // seed of the first call, random if not given; replay a finding with its seed and --runs 1
+        seed: Option<u64>,
+        #[arg(long, default_value_t = DEFAULT_GAS)]
+        // gas limit of every call
+        gas: i64,
+        #[arg(long)]
+        // JSON file naming application abort codes, e.g. {"E_INSUFFICIENT_BALANCE": 5}
+        abort_codes: Option<String>,
+    },
+    /// Call exports and entry functions interactively, keeping the instance and storage alive
+    Repl {
+        #[arg(short, long)]
+        // path to the .polkavm blob
+        module: String,
+        #[arg(long)]
+        // path to the ABI written by --emit-abi, needed to call entry functions
+        abi: Option<String>,
+        #[arg(long)]
+        // path to a JSON file the storage is loaded from and persisted to on quit
+        state_file: Option<String>,
+        #[arg(long, value_name = "H160=AMOUNT")]
+        // mock pallet-revive balance of an account, repeatable
+        balance: Vec<String>,
+        #[arg(long, value_name = "H160=BLOB")]
+        // another .polkavm blob the module can call with revive::call_contract, repeatable
+        contract: Vec<String>,
+        #[arg(long, value_name = "MS", default_value_t = 0)]
+        // block timestamp returned by revive::timestamp::ms, in milliseconds since the Unix epoch
+        mock_time: u64,
+        #[arg(long, default_value_t = 0)]
+        // seed of the deterministic stream of bytes returned by revive::random::bytes
+        mock_seed: u64,
+        #[arg(long)]
+        // JSON file naming application abort codes, e.g. {"E_INSUFFICIENT_BALANCE": 5}
+        abort_codes: Option<String>,
+    },
+    /// Run a blob again with the host calls of a --record-trace run, single-stepping, and report
+    /// where it differs from the trace
+    Replay {
+        #[arg(short, long)]
+        // path to the recorded .polkavm blob
+        module: String,
+        #[arg(long)]
+        // path to the trace written by --record-trace
+        trace: String,
+    },
+}
+
+const REPL_HELP: &str = "\
+call <export> [u64 ...]            call an export with numeric arguments
+call <module::function> [json]     call an entry function, e.g. call m::f [1, \"0x2\"]
+exports                            list the exports and their signatures
+storage                            list the stored resources
+reset                              restore the initial storage on a fresh instance
+quit                               leave, persisting the storage to --state-file";
+
 fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -39,47 +207,213 @@ fn main() -> anyhow::Result<()> {
         .init();
 
     let args = Args::parse();
+    match args.command {
+        Some(Command::EncodeCall {
+            abi,
+            function,
+            args,
+        }) => {
+            let args: Vec<serde_json::Value> = serde_json::from_str(&args)?;
+            let call_data = Abi::read(Path::new(&abi))?.encode_call(&function, &args)?;
+            println!("0x{}", hex::encode(call_data));
+            return Ok(());
+        }
+        Some(Command::DecodeReturn {
+            abi,
+            function,
+            data,
+        }) => {
+            let data = hex::decode(data.trim_start_matches("0x"))?;
+            let values = Abi::read(Path::new(&abi))?.decode_return(&function, &data)?;
+            println!("{}", serde_json::to_string(&values)?);
+            return Ok(());
+        }
+        Some(Command::Fuzz {
+            module,
+            abi,
+            runs,
+            seed,
+            gas,
+            abort_codes,
+        }) => {
+            let abort_codes = read_abort_codes(abort_codes.as_deref())?;
+            let seed = seed.unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or_default()
+            });
+            info!("Fuzzing {module} with seed {seed}");
+            let blob = parse_to_blob(&std::fs::read(&module)?)?;
+            let options = FuzzOptions {
+                runs,
+                seed,
+                gas,
+                ..Default::default()
+            };
+            let report = fuzz(&blob, &Abi::read(Path::new(&abi))?, &options)?;
+            let aborts = report
+                .aborts
+                .iter()
+                .map(|(code, count)| format!("{}: {count}", abort_codes.describe(*code)))
+                .collect::<Vec<_>>();
+            info!(
+                "{} call(s): {} succeeded, {} aborted {{{}}}, {} ran out of gas",
+                report.runs,
+                report.successes,
+                report.aborts.values().sum::<u64>(),
+                aborts.join(", "),
+                report.out_of_gas
+            );
+            for finding in &report.findings {
+                error!(
+                    "{}({}) failed: {:?}, replay with --seed {} --runs 1",
+                    finding.case.function,
+                    serde_json::to_string(&finding.case.args)?,
+                    finding.outcome,
+                    finding.case.seed
+                );
+            }
+            if !report.findings.is_empty() {
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+            return Ok(());
+        }
+        Some(Command::Repl {
+            module,
+            abi,
+            state_file,
+            balance,
+            contract,
+            mock_time,
+            mock_seed,
+            abort_codes,
+        }) => {
+            let mocks = Mocks {
+                balances: &balance,
+                contracts: &contract,
+                time: mock_time,
+                seed: mock_seed,
+            };
+            let abort_codes = read_abort_codes(abort_codes.as_deref())?;
+            return repl(&module, abi, state_file, &mocks, &abort_codes);
+        }
+        Some(Command::Replay { module, trace }) => {
+            let trace = Trace::read(Path::new(&trace))?;
+            let (mut instance, _) =
+                create_stepping_instance(parse_to_blob(&std::fs::read(&module)?)?)?;
+            let replay = replay(&mut instance, &trace)?;
+            if let Some(divergence) = &replay.divergence {
+                error!("Diverged from the trace at {divergence}");
+                std::process::exit(EXIT_DIVERGED);
+            }
+            info!(
+                "Replayed {} host call(s) in {} steps: {:?}",
+                replay.host_calls, replay.steps, replay.outcome
+            );
+            return Ok(());
+        }
+        None => {}
+    }
+    // clap requires the entrypoint without a subcommand
+    let entrypoint = args.entrypoint.unwrap();
+    let abort_codes = read_abort_codes(args.abort_codes.as_deref())?;
+    let expect_abort = args
+        .expect_abort
+        .as_deref()
+        .map(|code| {
+            abort_codes
+                .parse(code)
+                .ok_or_else(|| anyhow::anyhow!("unknown abort code `{code}`"))
+        })
+        .transpose()?;
 
-    let (mut instance, mut allocator) = if let Some(source) = args.source {
-        let output = "/tmp/output.polkavm";
-        info!("Compiled Move source to PolkaVM bytecode at {}", output);
-        new_move_program(output, source.as_str(), HashSet::new())?
-    } else {
-        let program_bytes = std::fs::read(args.module.unwrap())?; // clap guarantees that module is provided
-        let blob =
-            ProgramBlob::parse(program_bytes.into()).map_err(|e| anyhow::anyhow!("{e:?}"))?;
-        create_instance(blob)?
+    // a directory of its own, so concurrent runs don't overwrite each other's blob
+    let build_dir = tempfile::tempdir()?;
+    let blob_path = match (&args.module, &args.source) {
+        (Some(module), _) => module.into(),
+        (None, Some(source)) => {
+            let output = build_dir.path().join("output.polkavm");
+            // the ABI lists the layouts of the resources for --dump-state, and the blob runs
+            // on the mock host, which serves --mock-seed randomness
+            let build_options =
+                package_build_options(&output.to_string_lossy(), source, HashSet::new())?
+                    .emit_abi(args.dump_state.is_some())
+                    .mock_host(true);
+            let blob_path = build_move_package(build_options)?
+                .blob_path
+                .ok_or_else(|| anyhow::anyhow!("no blob linked for {source}"))?;
+            info!(
+                "Compiled Move source to PolkaVM bytecode at {}",
+                blob_path.display()
+            );
+            blob_path
+        }
+        (None, None) => unreachable!("clap requires the source or the module"),
+    };
+    let blob = parse_to_blob(&std::fs::read(&blob_path)?)?;
+    // a recorded run is single-stepped, so the trace knows the step of every host call
+    let (mut instance, mut allocator) = match args.record_trace {
+        Some(_) => create_stepping_instance(blob)?,
+        None => create_instance(blob)?,
     };
+    if let Some(state_file) = &args.state_file {
+        info!("Using contract state from {}", state_file);
+        let backend = FileBackend::open(state_file)?;
+        allocator.storage = Box::new(GlobalStorage::with_backend(Box::new(backend)));
+    }
+    let mocks = Mocks {
+        balances: &args.balance,
+        contracts: &args.contract,
+        time: args.mock_time,
+        seed: args.mock_seed,
+    };
+    mocks.apply(&mut allocator)?;
     let module = instance.module().clone();
 
     let entry_point_export = module
         .exports()
-        .find(|export| export == args.entrypoint.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Module doesnt export {}", args.entrypoint))?;
+        .find(|export| export == entrypoint.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Module doesnt export {}", entrypoint))?;
 
     // now assuming all fuctions have args of u64, but thats not always true
     let reg_args = &args.params;
+    // check the call shape against the export signature, if the blob has metadata
+    let metadata = PackageMetadata::of_blob(&blob_path)?;
+    if let Some(signature) = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.export_signatures.get(&entrypoint))
+    {
+        let signature = ExportSignature::parse(signature)?;
+        info!("Signature: {entrypoint}{signature}");
+        signature.check_call(&entrypoint, reg_args)?;
+    }
     let ep = entry_point_export.program_counter();
     info!(
         "Calling entry point {} at PC {} with args: {:?}",
-        args.entrypoint, ep, reg_args
+        entrypoint, ep, reg_args
     );
     // assuming return value is u64. It's hard to handle with a dynamic CLI, when the function is generic
     let result = match reg_args.len() {
-        0 => instance
-            .call_typed_and_get_result::<(), ()>(&mut allocator, ep, ())
-            .map_err(|e| anyhow::anyhow!("{e:?}")),
+        _ if args.record_trace.is_some() => {
+            let (trace, result) = record(&mut instance, &mut allocator, &entrypoint, reg_args)?;
+            let path = args.record_trace.as_deref().unwrap_or_default();
+            trace.write(Path::new(path))?;
+            info!(
+                "Recorded {} host call(s) in {} steps to {path}",
+                trace.host_calls.len(),
+                trace.steps
+            );
+            result
+        }
+        0 => instance.call_typed_and_get_result::<(), ()>(&mut allocator, ep, ()),
         1 => {
             let (a,) = (reg_args[0],);
-            instance
-                .call_typed_and_get_result::<(), (u64,)>(&mut allocator, ep, (a,))
-                .map_err(|e| anyhow::anyhow!("{e:?}"))
+            instance.call_typed_and_get_result::<(), (u64,)>(&mut allocator, ep, (a,))
         }
         2 => {
             let (a, b) = (reg_args[0], reg_args[1]);
-            instance
-                .call_typed_and_get_result::<(), (u64, u64)>(&mut allocator, ep, (a, b))
-                .map_err(|e| anyhow::anyhow!("{e:?}"))
+            instance.call_typed_and_get_result::<(), (u64, u64)>(&mut allocator, ep, (a, b))
         }
         // … repeat up to your max arity …
         _ => anyhow::bail!("too many arguments (max = 2)"),
@@ -87,5 +421,281 @@ fn main() -> anyhow::Result<()> {
 
     info!("Done: {:?}", result);
 
+    if let (Err(_), Some(source_map)) = (&result, &args.source_map) {
+        let source_map = SourceMap::read(Path::new(source_map))?;
+        match instance
+            .program_counter()
+            .and_then(|pc| source_map.lookup(pc.0))
+        {
+            Some((function, range)) => {
+                error!("Trapped in {function} at {}:{}", range.file, range.line)
+            }
+            None => error!("Trap location not found in the source map"),
+        }
+    }
+
+    if let (Some(frames), Some(stack_trace_map)) = (&allocator.stack_trace, &args.stack_trace_map) {
+        let stack_trace_map = StackTraceMap::read(Path::new(stack_trace_map))?;
+        let source_map = args
+            .source_map
+            .as_deref()
+            .map(|source_map| SourceMap::read(Path::new(source_map)))
+            .transpose()?;
+        let blob = parse_to_blob(&std::fs::read(&blob_path)?)?;
+        let trace = stack_trace_map.symbolize(frames, source_map.as_ref().map(|m| (&blob, m)))?;
+        error!("Aborted at\n{trace}");
+    }
+
+    if let Some(coverage_map) = &args.coverage_map {
+        let coverage_map = CoverageMap::read(Path::new(coverage_map))?;
+        match read_coverage_counters(&mut instance, &allocator)? {
+            Some(counts) => println!("{}", coverage_map.report(&counts)?),
+            None => {
+                error!("No coverage counters registered, was the module built with --coverage?")
+            }
+        }
+    }
+
+    if let Some(stats) = read_heap_stats(&mut instance, &allocator)? {
+        info!(
+            "Heap: {} bytes in use, peak {} bytes, {} allocations, {} frees, {} reallocations in place, {} moved",
+            stats.in_use,
+            stats.peak,
+            stats.allocations,
+            stats.frees,
+            stats.reallocs_in_place,
+            stats.reallocs_moved
+        );
+    }
+
+    for (account, amount) in &allocator.balances {
+        info!("Balance of 0x{}: {amount}", hex::encode(account));
+    }
+
+    if let Some(format) = args.dump_state {
+        let abi_path = abi_path(&blob_path);
+        let abi = abi_path
+            .is_file()
+            .then(|| Abi::read(&abi_path))
+            .transpose()?;
+        dump_state(&allocator, abi.as_ref(), format)?;
+    }
+
+    // a reverted call leaves the state file as it was
+    if args.state_file.is_some() && result.is_ok() {
+        allocator
+            .storage
+            .flush()
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+
+    let abort_code = match &result {
+        Err(CallError::User(ProgramError::Abort(code))) => Some(*code),
+        _ => None,
+    };
+    if let Some(code) = abort_code {
+        error!("abort: {}", abort_codes.describe(code));
+        println!("{code}");
+    }
+    let exit_code = match (expect_abort, abort_code, &result) {
+        (Some(expected), Some(code), _) if expected == code => 0,
+        (Some(expected), _, _) => {
+            error!(
+                "Expected the call to abort with {}",
+                abort_codes.describe(expected)
+            );
+            EXIT_UNEXPECTED_RESULT
+        }
+        (None, Some(_), _) => EXIT_ABORT,
+        (None, None, Err(_)) => EXIT_RUNTIME_ERROR,
+        (None, None, Ok(())) => 0,
+    };
+    std::process::exit(exit_code)
+}
+
+/// Print the resources in the storage of `runtime`, see [`stored_resources`].
+fn dump_state(runtime: &Runtime, abi: Option<&Abi>, format: StateFormat) -> anyhow::Result<()> {
+    let resources = stored_resources(runtime.storage.as_ref(), abi);
+    match format {
+        StateFormat::Json => println!("{}", serde_json::to_string_pretty(&resources)?),
+        StateFormat::Table => print_resources(&resources),
+    }
+    Ok(())
+}
+
+/// Print a line per resource, the decoded value or else the stored bytes.
+fn print_resources(resources: &[StoredResource]) {
+    for resource in resources {
+        let struct_tag = resource.struct_tag.as_ref().unwrap_or(&resource.tag);
+        let value = match (&resource.value, &resource.bytes) {
+            (Some(value), _) => value.to_string(),
+            (None, bytes) => format!("0x{}", bytes.as_deref().unwrap_or_default()),
+        };
+        println!("{} {struct_tag}: {value}", resource.address);
+    }
+    println!("{} resource(s)", resources.len());
+}
+
+/// The mocked pallet-revive state given on the command line.
+struct Mocks<'a> {
+    /// `--balance` options
+    balances: &'a [String],
+    /// `--contract` options
+    contracts: &'a [String],
+    /// `--mock-time`
+    time: u64,
+    /// `--mock-seed`
+    seed: u64,
+}
+
+impl Mocks<'_> {
+    /// Apply the `--balance`, `--contract`, `--mock-time` and `--mock-seed` options to the
+    /// runtime.
+    fn apply(&self, runtime: &mut Runtime) -> anyhow::Result<()> {
+        for balance in self.balances {
+            let (account, amount) = parse_balance(balance)?;
+            runtime.balances.insert(account, amount);
+        }
+        for contract in self.contracts {
+            let (account, path) = contract
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("expected H160=BLOB, got {contract}"))?;
+            let blob = parse_to_blob(&std::fs::read(path)?)?;
+            runtime
+                .contracts
+                .insert(parse_account(account)?, Box::new(BlobContract::new(blob)));
+        }
+        runtime.timestamp_ms = self.time;
+        runtime.random = MockRandom::new(self.seed);
+        Ok(())
+    }
+}
+
+/// Read commands from stdin and run them in one [`Session`], until `quit` or the end of input.
+fn repl(
+    module: &str,
+    abi: Option<String>,
+    state_file: Option<String>,
+    mocks: &Mocks,
+    abort_codes: &AbortCodes,
+) -> anyhow::Result<()> {
+    let abi = abi.map(|abi| Abi::read(Path::new(&abi))).transpose()?;
+    let backend = state_file
+        .as_ref()
+        .map(FileBackend::open)
+        .transpose()?
+        .map(|backend| Box::new(backend) as Box<dyn StorageBackend>);
+    let metadata = PackageMetadata::of_blob(Path::new(module))?;
+    let mut session = Session::new(&std::fs::read(module)?, metadata, abi, backend)?;
+    mocks.apply(session.runtime())?;
+    println!("{module} loaded, `help` lists the commands");
+
+    let mut stdin = std::io::stdin().lock();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let result = match command {
+            "" => continue,
+            "quit" | "exit" => break,
+            "help" => {
+                println!("{REPL_HELP}");
+                continue;
+            }
+            _ => repl_command(&mut session, command, rest.trim(), abort_codes),
+        };
+        if let Err(e) = result {
+            println!("error: {e:#}");
+        }
+    }
+
+    if state_file.is_some() {
+        session
+            .runtime()
+            .storage
+            .flush()
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+    Ok(())
+}
+
+fn repl_command(
+    session: &mut Session,
+    command: &str,
+    args: &str,
+    abort_codes: &AbortCodes,
+) -> anyhow::Result<()> {
+    match command {
+        "call" => {
+            let (function, args) = args.split_once(' ').unwrap_or((args, ""));
+            let result = if function.contains("::") {
+                let args: Vec<serde_json::Value> = match args.trim() {
+                    "" => vec![],
+                    args => serde_json::from_str(args)?,
+                };
+                session.call_entry(function, &args)?
+            } else {
+                let args = args
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<u64>, _>>()?;
+                session.call_export(function, &args)?
+            };
+            match result.outcome {
+                Outcome::Success if result.values.is_empty() => println!("ok"),
+                Outcome::Success => println!("ok {}", serde_json::to_string(&result.values)?),
+                Outcome::Abort(code) => println!("abort: {}", abort_codes.describe(code)),
+                Outcome::OutOfGas => println!("out of gas"),
+                Outcome::Failure(e) => println!("failed: {e}"),
+            }
+        }
+        "exports" => {
+            for (name, signature) in session.exports()? {
+                match signature {
+                    Some(signature) => println!("{name}{signature}"),
+                    None => println!("{name}"),
+                }
+            }
+        }
+        "storage" => print_resources(&session.resources()),
+        "reset" => {
+            session.reset()?;
+            println!("storage reset");
+        }
+        _ => anyhow::bail!("unknown command `{command}`, `help` lists the commands"),
+    }
     Ok(())
 }
+
+/// The known abort codes, and those of the `--abort-codes` mapping at `path`.
+fn read_abort_codes(path: Option<&str>) -> anyhow::Result<AbortCodes> {
+    Ok(match path {
+        Some(path) => AbortCodes::read(Path::new(path))?,
+        None => AbortCodes::default(),
+    })
+}
+
+/// Parse a `--balance` of the form `0x<H160>=<amount>` or `contract=<amount>`.
+fn parse_balance(balance: &str) -> anyhow::Result<([u8; REVIVE_ADDRESS_LENGTH], u128)> {
+    let (account, amount) = balance
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expected H160=AMOUNT, got {balance}"))?;
+    let account = if account == "contract" {
+        CONTRACT_ADDRESS
+    } else {
+        parse_account(account)?
+    };
+    Ok((account, amount.parse()?))
+}
+
+/// Parse a `0x<H160>` account.
+fn parse_account(account: &str) -> anyhow::Result<[u8; REVIVE_ADDRESS_LENGTH]> {
+    hex::decode(account.trim_start_matches("0x"))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("{account} is not a 20 byte address"))
+}
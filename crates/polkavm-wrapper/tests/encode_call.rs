@@ -0,0 +1,46 @@
+use std::{collections::HashSet, path::Path, process::Command};
+
+use move_polka_driver::{
+    artifacts::abi_path,
+    build::{build_polka_from_move, package_build_options},
+    initialize_logger,
+    run::{create_instance, parse_to_blob, ProgramError},
+};
+use polkavm::CallError;
+
+/// Encode the call of `entry_args::sum_bytes` with the `encode-call` command.
+fn encode_call(abi: &Path, args: &str) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new(env!("CARGO_BIN_EXE_polkavm-wrapper"))
+        .arg("encode-call")
+        .arg("--abi")
+        .arg(abi)
+        .args(["-f", "entry_args::sum_bytes", "--args", args])
+        .output()?;
+    assert!(output.status.success(), "{output:?}");
+    let call_data = String::from_utf8(output.stdout)?;
+    Ok(hex::decode(call_data.trim().trim_start_matches("0x"))?)
+}
+
+#[test]
+pub fn encoded_call_is_decoded_by_the_guest() -> anyhow::Result<()> {
+    initialize_logger();
+    let output = "output/encode_call/entry_args.polkavm";
+    let build_options =
+        package_build_options(output, "../../examples/entry-args/", HashSet::new())?.emit_abi(true);
+    let blob = parse_to_blob(&build_polka_from_move(build_options)?)?;
+    let abi = abi_path(Path::new(output));
+    let call = |call_data: Vec<u8>| -> anyhow::Result<_> {
+        let (mut instance, mut runtime) = create_instance(blob.clone())?;
+        runtime.call_data = call_data;
+        Ok(instance.call_typed_and_get_result::<(), ()>(&mut runtime, "call", ()))
+    };
+
+    // `sum_bytes` aborts with 1 unless the sum of the bytes is the expected one
+    call(encode_call(&abi, "[[1, 2, 250], 253]")?)?.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    let result = call(encode_call(&abi, r#"["0x0102", 4]"#)?)?;
+    assert!(
+        matches!(result, Err(CallError::User(ProgramError::Abort(1)))),
+        "Expected abort 1, got {result:?}"
+    );
+    Ok(())
+}
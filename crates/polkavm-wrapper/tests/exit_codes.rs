@@ -0,0 +1,55 @@
+use std::{collections::HashSet, process::Command};
+
+use move_polka_driver::{
+    build::{build_polka_from_move, package_build_options},
+    initialize_logger,
+    run::AbortCodes,
+};
+
+const OUTPUT: &str = "output/exit_codes/overflow.polkavm";
+
+/// Call `entrypoint` of the overflow example with `args`, returning the exit status and stdout.
+fn run(entrypoint: &str, args: &[&str]) -> anyhow::Result<(Option<i32>, String)> {
+    let output = Command::new(env!("CARGO_BIN_EXE_polkavm-wrapper"))
+        .args(["-m", OUTPUT, "-e", entrypoint])
+        .args(args)
+        // the log goes to stdout too
+        .env("RUST_LOG", "off")
+        .output()?;
+    Ok((output.status.code(), String::from_utf8(output.stdout)?))
+}
+
+#[test]
+pub fn exit_status_tells_aborts_from_success() -> anyhow::Result<()> {
+    initialize_logger();
+    let build_options = package_build_options(OUTPUT, "../../examples/overflow/", HashSet::new())?;
+    build_polka_from_move(build_options)?;
+
+    let (status, stdout) = run("in_range", &[])?;
+    assert_eq!(status, Some(0), "{stdout}");
+    assert_eq!(stdout.trim(), "");
+
+    // an abort exits with 2 and prints the abort code
+    let code = AbortCodes::default()
+        .parse("ARITHMETIC_ERROR")
+        .expect("known code")
+        .to_string();
+    let (status, stdout) = run("add_u8_overflow", &[])?;
+    assert_eq!(status, Some(2), "{stdout}");
+    assert_eq!(stdout.trim(), code);
+
+    // the expected abort, given by code or by name, is a success
+    for expected in [code.as_str(), "ARITHMETIC_ERROR"] {
+        let (status, stdout) = run("add_u8_overflow", &["--expect-abort", expected])?;
+        assert_eq!(status, Some(0), "{expected}: {stdout}");
+        assert_eq!(stdout.trim(), code);
+    }
+
+    // another abort code, or no abort at all, is not
+    let (status, stdout) = run("add_u8_overflow", &["--expect-abort", "1"])?;
+    assert_eq!(status, Some(4), "{stdout}");
+    assert_eq!(stdout.trim(), code);
+    let (status, _) = run("in_range", &["--expect-abort", &code])?;
+    assert_eq!(status, Some(4));
+    Ok(())
+}
@@ -0,0 +1,8 @@
+module 0xa000::addresses {
+    // a 32 byte address, it doesn't fit in a 20 byte target address
+    const OWNER: address = @0xab010101010101010101010101010101010101010101010101010101010101ce;
+
+    public fun owner(): address {
+        OWNER
+    }
+}
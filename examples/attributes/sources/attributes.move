@@ -0,0 +1,14 @@
+module 0xa00c::attributes {
+    #[payable]
+    public entry fun deposit() {}
+
+    public entry fun withdraw() {}
+
+    #[selector = 0x11223344, deprecated(note = b"use withdraw")]
+    public entry fun legacy_withdraw() {}
+
+    #[view, audited(by = b"eiger", round = 2)]
+    public fun total(): u64 {
+        42
+    }
+}
@@ -0,0 +1,12 @@
+module 0xa000::address_cmp {
+    use std::signer;
+
+    public entry fun compare_addresses(account: &signer) {
+        let addr = signer::address_of(account);
+        let same = signer::address_of(account);
+        assert!(addr == same, 0x2001);
+        assert!(addr != @0x0, 0x2002);
+        assert!(!(addr == @0x0), 0x2003);
+        assert!(account == account, 0x2004);
+    }
+}
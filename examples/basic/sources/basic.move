@@ -1,9 +1,9 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 
 module 0xa000::basic {
-    use 0x10::debug;
+    use std::debug;
     use std::signer;
 
     public entry fun main_basic(account: &signer) {
@@ -0,0 +1,75 @@
+/// Workloads of the `backends` benchmark of move-to-polka.
+module 0xa000::bench {
+    use std::hash;
+    use std::signer;
+    use std::vector;
+
+    struct Counter has key, drop {
+        value: u64,
+        history: vector<u64>,
+    }
+
+    /// The `n`th Fibonacci number, computed recursively to stress calls.
+    public fun fib(n: u64): u64 {
+        if (n < 2) {
+            n
+        } else {
+            fib(n - 1) + fib(n - 2)
+        }
+    }
+
+    /// Insertion sort of `n` pseudo-random numbers, returning the smallest one.
+    public fun sort(n: u64): u64 {
+        let v = vector::empty<u64>();
+        let seed: u64 = 42;
+        let i = 0;
+        while (i < n) {
+            let next = ((seed as u128) * 6364136223846793005 + 1442695040888963407) % 18446744073709551557;
+            seed = (next as u64);
+            vector::push_back(&mut v, seed % 1000000);
+            i = i + 1;
+        };
+        let i = 1;
+        while (i < n) {
+            let j = i;
+            while (j > 0 && *vector::borrow(&v, j - 1) > *vector::borrow(&v, j)) {
+                vector::swap(&mut v, j - 1, j);
+                j = j - 1;
+            };
+            i = i + 1;
+        };
+        let i = 1;
+        while (i < n) {
+            assert!(*vector::borrow(&v, i - 1) <= *vector::borrow(&v, i), 1);
+            i = i + 1;
+        };
+        if (n == 0) 0 else *vector::borrow(&v, 0)
+    }
+
+    /// Hash a 32 byte digest `n` times with SHA3-256, then SHA2-256, returning its first byte.
+    public fun hash_loop(n: u64): u64 {
+        let digest = x"0000000000000000000000000000000000000000000000000000000000000000";
+        let i = 0;
+        while (i < n) {
+            digest = hash::sha2_256(hash::sha3_256(digest));
+            i = i + 1;
+        };
+        (*vector::borrow(&digest, 0) as u64)
+    }
+
+    /// Publish, update and remove a resource 16 times.
+    public entry fun storage_churn(account: &signer) acquires Counter {
+        let addr = signer::address_of(account);
+        let i = 0;
+        while (i < 16) {
+            move_to(account, Counter { value: i, history: vector::empty() });
+            let counter = borrow_global_mut<Counter>(addr);
+            counter.value = counter.value + 1;
+            vector::push_back(&mut counter.history, i);
+            let Counter { value, history: _ } = move_from<Counter>(addr);
+            assert!(value == i + 1, 2);
+            i = i + 1;
+        };
+        assert!(!exists<Counter>(addr), 3);
+    }
+}
@@ -0,0 +1,29 @@
+module 0xa00c::constants {
+    use std::vector;
+    use 0xa00c::primes;
+
+    // loaded by several functions, but emitted once
+    const PRIMES: vector<u64> = vector[2, 3, 5, 7, 11];
+    const GREETING: vector<u8> = b"hello";
+
+    public fun sum_primes(): u64 {
+        let primes = PRIMES;
+        let sum = 0;
+        while (!vector::is_empty(&primes)) {
+            sum = sum + vector::pop_back(&mut primes);
+        };
+        sum
+    }
+
+    public fun count_primes(): u64 {
+        vector::length(&PRIMES)
+    }
+
+    public fun greeting_len(): u64 {
+        vector::length(&GREETING) + vector::length(&GREETING)
+    }
+
+    public fun count_primes_generic(): u64 {
+        primes::count_per_item(&vector[true])
+    }
+}
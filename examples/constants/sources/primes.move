@@ -0,0 +1,12 @@
+module 0xa00c::primes {
+    use std::vector;
+
+    // the value of `constants::PRIMES` under another name
+    const FIRST_PRIMES: vector<u64> = vector[2, 3, 5, 7, 11];
+
+    /// The number of first primes, once per element of `items`. Generic, so its code is
+    /// generated into the modules calling it.
+    public fun count_per_item<T>(items: &vector<T>): u64 {
+        vector::length(&FIRST_PRIMES) * vector::length(items)
+    }
+}
@@ -0,0 +1,40 @@
+module 0xa00d::cross_call {
+    use std::signer;
+    use revive::revive;
+
+    const E_OUTPUT: u64 = 1;
+    const E_VALUE: u64 = 2;
+    const E_REVERT: u64 = 3;
+    const E_COUNT: u64 = 4;
+
+    struct Counter has key {
+        value: u64,
+    }
+
+    /// Call `callee` with `input` and check its output.
+    public entry fun forward(callee: address, input: vector<u8>, expected: vector<u8>) {
+        assert!(revive::call_contract(callee, input) == expected, E_OUTPUT);
+    }
+
+    /// Called by `forward` of another instance of this contract.
+    public entry fun check(value: u64) {
+        assert!(value == 42, E_VALUE);
+    }
+
+    /// Increment the counter of `account`, then revert if `revert` is set.
+    public entry fun bump(account: &signer, revert: bool) acquires Counter {
+        let addr = signer::address_of(account);
+        if (exists<Counter>(addr)) {
+            let counter = borrow_global_mut<Counter>(addr);
+            counter.value = counter.value + 1;
+        } else {
+            move_to(account, Counter { value: 1 });
+        };
+        assert!(!revert, E_REVERT);
+    }
+
+    /// Check the counter of `account`.
+    public entry fun count_is(account: &signer, expected: u64) acquires Counter {
+        assert!(borrow_global<Counter>(signer::address_of(account)).value == expected, E_COUNT);
+    }
+}
@@ -0,0 +1,44 @@
+module std::debug {
+    use std::string::String;
+
+    native public fun print<T>(x: &T);
+    // Aptos' flavor prints a string, not a reference
+    native public fun native_print(x: String);
+    native public fun print_stack_trace();
+    native public fun log(level: u8, msg: &vector<u8>);
+    native public fun assert(cond: bool, code: u64);
+}
+
+module 0xa000::debug_natives {
+    use std::debug;
+    use std::string;
+    use std::vector;
+
+    const LEVEL_INFO: u8 = 3;
+
+    public entry fun log_and_assert(_account: &signer) {
+        let value = 42u64;
+        debug::print(&value);
+        debug::native_print(string::utf8(b"value checked"));
+        debug::print_stack_trace();
+        debug::log(LEVEL_INFO, &b"checking value");
+        debug::log(LEVEL_INFO, &pushed(b"pushed byte by byte"));
+        debug::assert(value == 42, 1);
+    }
+
+    /// A copy of `bytes` grown with `push_back`, so its capacity is larger than its length.
+    fun pushed(bytes: vector<u8>): vector<u8> {
+        let v = vector::empty();
+        let i = 0;
+        while (i < vector::length(&bytes)) {
+            vector::push_back(&mut v, *vector::borrow(&bytes, i));
+            i = i + 1;
+        };
+        v
+    }
+
+    public entry fun failing_assert(_account: &signer) {
+        debug::log(LEVEL_INFO, &b"about to fail");
+        debug::assert(false, 42);
+    }
+}
@@ -0,0 +1,9 @@
+// Built with `--define mainnet --define fee=25` for mainnet, and `--define mainnet=false
+// --define fee=0` for testnets.
+module 0xa000::chain {
+    use defines::config;
+
+    public entry fun fee(): u64 {
+        if (config::mainnet()) config::fee() else 0
+    }
+}
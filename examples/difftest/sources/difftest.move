@@ -0,0 +1,66 @@
+module 0xa000::difftest {
+    use std::vector;
+
+    public fun add_u8(a: u8, b: u8): u8 {
+        a + b
+    }
+
+    public fun sub_u64(a: u64, b: u64): u64 {
+        a - b
+    }
+
+    public fun mul_u32(a: u32, b: u32): u32 {
+        a * b
+    }
+
+    public fun div_u16(a: u16, b: u16): u16 {
+        a / b
+    }
+
+    public fun mod_u64(a: u64, b: u64): u64 {
+        a % b
+    }
+
+    public fun shl_u8(a: u8, b: u8): u8 {
+        a << b
+    }
+
+    public fun shr_u64(a: u64, b: u8): u64 {
+        a >> b
+    }
+
+    public fun cast_u8(a: u64): u8 {
+        (a as u8)
+    }
+
+    public fun less(a: u32, b: u32): bool {
+        a < b
+    }
+
+    public fun xor_u16(a: u16, b: u16): u16 {
+        a ^ b
+    }
+
+    public fun sum(v: &vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(v)) {
+            sum = sum + *vector::borrow(v, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    public fun nth(v: vector<u8>, i: u64): u8 {
+        *vector::borrow(&v, i)
+    }
+
+    public fun checked(a: u64): u64 {
+        assert!(a != 7, 7);
+        a
+    }
+
+    public fun same_address(a: address, b: address): bool {
+        a == b
+    }
+}
@@ -0,0 +1,323 @@
+/// Many entry functions for `call_selector` to dispatch. Each entry function aborts with its
+/// own code when it isn't passed its own number, which shows the call reached the right one.
+module 0xa000::dispatch {
+    public entry fun entry_00(_account: &signer, n: u64) {
+        assert!(n == 0, 100);
+    }
+
+    public entry fun entry_01(_account: &signer, n: u64) {
+        assert!(n == 1, 101);
+    }
+
+    public entry fun entry_02(_account: &signer, n: u64) {
+        assert!(n == 2, 102);
+    }
+
+    public entry fun entry_03(_account: &signer, n: u64) {
+        assert!(n == 3, 103);
+    }
+
+    public entry fun entry_04(_account: &signer, n: u64) {
+        assert!(n == 4, 104);
+    }
+
+    public entry fun entry_05(_account: &signer, n: u64) {
+        assert!(n == 5, 105);
+    }
+
+    public entry fun entry_06(_account: &signer, n: u64) {
+        assert!(n == 6, 106);
+    }
+
+    public entry fun entry_07(_account: &signer, n: u64) {
+        assert!(n == 7, 107);
+    }
+
+    public entry fun entry_08(_account: &signer, n: u64) {
+        assert!(n == 8, 108);
+    }
+
+    public entry fun entry_09(_account: &signer, n: u64) {
+        assert!(n == 9, 109);
+    }
+
+    public entry fun entry_10(_account: &signer, n: u64) {
+        assert!(n == 10, 110);
+    }
+
+    public entry fun entry_11(_account: &signer, n: u64) {
+        assert!(n == 11, 111);
+    }
+
+    public entry fun entry_12(_account: &signer, n: u64) {
+        assert!(n == 12, 112);
+    }
+
+    public entry fun entry_13(_account: &signer, n: u64) {
+        assert!(n == 13, 113);
+    }
+
+    public entry fun entry_14(_account: &signer, n: u64) {
+        assert!(n == 14, 114);
+    }
+
+    public entry fun entry_15(_account: &signer, n: u64) {
+        assert!(n == 15, 115);
+    }
+
+    public entry fun entry_16(_account: &signer, n: u64) {
+        assert!(n == 16, 116);
+    }
+
+    public entry fun entry_17(_account: &signer, n: u64) {
+        assert!(n == 17, 117);
+    }
+
+    public entry fun entry_18(_account: &signer, n: u64) {
+        assert!(n == 18, 118);
+    }
+
+    public entry fun entry_19(_account: &signer, n: u64) {
+        assert!(n == 19, 119);
+    }
+
+    public entry fun entry_20(_account: &signer, n: u64) {
+        assert!(n == 20, 120);
+    }
+
+    public entry fun entry_21(_account: &signer, n: u64) {
+        assert!(n == 21, 121);
+    }
+
+    public entry fun entry_22(_account: &signer, n: u64) {
+        assert!(n == 22, 122);
+    }
+
+    public entry fun entry_23(_account: &signer, n: u64) {
+        assert!(n == 23, 123);
+    }
+
+    public entry fun entry_24(_account: &signer, n: u64) {
+        assert!(n == 24, 124);
+    }
+
+    public entry fun entry_25(_account: &signer, n: u64) {
+        assert!(n == 25, 125);
+    }
+
+    public entry fun entry_26(_account: &signer, n: u64) {
+        assert!(n == 26, 126);
+    }
+
+    public entry fun entry_27(_account: &signer, n: u64) {
+        assert!(n == 27, 127);
+    }
+
+    public entry fun entry_28(_account: &signer, n: u64) {
+        assert!(n == 28, 128);
+    }
+
+    public entry fun entry_29(_account: &signer, n: u64) {
+        assert!(n == 29, 129);
+    }
+
+    public entry fun entry_30(_account: &signer, n: u64) {
+        assert!(n == 30, 130);
+    }
+
+    public entry fun entry_31(_account: &signer, n: u64) {
+        assert!(n == 31, 131);
+    }
+
+    public entry fun entry_32(_account: &signer, n: u64) {
+        assert!(n == 32, 132);
+    }
+
+    public entry fun entry_33(_account: &signer, n: u64) {
+        assert!(n == 33, 133);
+    }
+
+    public entry fun entry_34(_account: &signer, n: u64) {
+        assert!(n == 34, 134);
+    }
+
+    public entry fun entry_35(_account: &signer, n: u64) {
+        assert!(n == 35, 135);
+    }
+
+    public entry fun entry_36(_account: &signer, n: u64) {
+        assert!(n == 36, 136);
+    }
+
+    public entry fun entry_37(_account: &signer, n: u64) {
+        assert!(n == 37, 137);
+    }
+
+    public entry fun entry_38(_account: &signer, n: u64) {
+        assert!(n == 38, 138);
+    }
+
+    public entry fun entry_39(_account: &signer, n: u64) {
+        assert!(n == 39, 139);
+    }
+
+    public entry fun entry_40(_account: &signer, n: u64) {
+        assert!(n == 40, 140);
+    }
+
+    public entry fun entry_41(_account: &signer, n: u64) {
+        assert!(n == 41, 141);
+    }
+
+    public entry fun entry_42(_account: &signer, n: u64) {
+        assert!(n == 42, 142);
+    }
+
+    public entry fun entry_43(_account: &signer, n: u64) {
+        assert!(n == 43, 143);
+    }
+
+    public entry fun entry_44(_account: &signer, n: u64) {
+        assert!(n == 44, 144);
+    }
+
+    public entry fun entry_45(_account: &signer, n: u64) {
+        assert!(n == 45, 145);
+    }
+
+    public entry fun entry_46(_account: &signer, n: u64) {
+        assert!(n == 46, 146);
+    }
+
+    public entry fun entry_47(_account: &signer, n: u64) {
+        assert!(n == 47, 147);
+    }
+
+    public entry fun entry_48(_account: &signer, n: u64) {
+        assert!(n == 48, 148);
+    }
+
+    public entry fun entry_49(_account: &signer, n: u64) {
+        assert!(n == 49, 149);
+    }
+
+    public entry fun entry_50(_account: &signer, n: u64) {
+        assert!(n == 50, 150);
+    }
+
+    public entry fun entry_51(_account: &signer, n: u64) {
+        assert!(n == 51, 151);
+    }
+
+    public entry fun entry_52(_account: &signer, n: u64) {
+        assert!(n == 52, 152);
+    }
+
+    public entry fun entry_53(_account: &signer, n: u64) {
+        assert!(n == 53, 153);
+    }
+
+    public entry fun entry_54(_account: &signer, n: u64) {
+        assert!(n == 54, 154);
+    }
+
+    public entry fun entry_55(_account: &signer, n: u64) {
+        assert!(n == 55, 155);
+    }
+
+    public entry fun entry_56(_account: &signer, n: u64) {
+        assert!(n == 56, 156);
+    }
+
+    public entry fun entry_57(_account: &signer, n: u64) {
+        assert!(n == 57, 157);
+    }
+
+    public entry fun entry_58(_account: &signer, n: u64) {
+        assert!(n == 58, 158);
+    }
+
+    public entry fun entry_59(_account: &signer, n: u64) {
+        assert!(n == 59, 159);
+    }
+
+    public entry fun entry_60(_account: &signer, n: u64) {
+        assert!(n == 60, 160);
+    }
+
+    public entry fun entry_61(_account: &signer, n: u64) {
+        assert!(n == 61, 161);
+    }
+
+    public entry fun entry_62(_account: &signer, n: u64) {
+        assert!(n == 62, 162);
+    }
+
+    public entry fun entry_63(_account: &signer, n: u64) {
+        assert!(n == 63, 163);
+    }
+
+    public entry fun entry_64(_account: &signer, n: u64) {
+        assert!(n == 64, 164);
+    }
+
+    public entry fun entry_65(_account: &signer, n: u64) {
+        assert!(n == 65, 165);
+    }
+
+    public entry fun entry_66(_account: &signer, n: u64) {
+        assert!(n == 66, 166);
+    }
+
+    public entry fun entry_67(_account: &signer, n: u64) {
+        assert!(n == 67, 167);
+    }
+
+    public entry fun entry_68(_account: &signer, n: u64) {
+        assert!(n == 68, 168);
+    }
+
+    public entry fun entry_69(_account: &signer, n: u64) {
+        assert!(n == 69, 169);
+    }
+
+    public entry fun entry_70(_account: &signer, n: u64) {
+        assert!(n == 70, 170);
+    }
+
+    public entry fun entry_71(_account: &signer, n: u64) {
+        assert!(n == 71, 171);
+    }
+
+    public entry fun entry_72(_account: &signer, n: u64) {
+        assert!(n == 72, 172);
+    }
+
+    public entry fun entry_73(_account: &signer, n: u64) {
+        assert!(n == 73, 173);
+    }
+
+    public entry fun entry_74(_account: &signer, n: u64) {
+        assert!(n == 74, 174);
+    }
+
+    public entry fun entry_75(_account: &signer, n: u64) {
+        assert!(n == 75, 175);
+    }
+
+    public entry fun entry_76(_account: &signer, n: u64) {
+        assert!(n == 76, 176);
+    }
+
+    public entry fun entry_77(_account: &signer, n: u64) {
+        assert!(n == 77, 177);
+    }
+
+    public entry fun entry_78(_account: &signer, n: u64) {
+        assert!(n == 78, 178);
+    }
+
+    public entry fun entry_79(_account: &signer, n: u64) {
+        assert!(n == 79, 179);
+    }
+}
@@ -0,0 +1,20 @@
+module 0xa000::scopes {
+    /// Sum of the squares of the even numbers below `n`, with locals declared in a loop and in
+    /// both arms of a branch.
+    public entry fun sum_even_squares(n: u64): u64 {
+        let total = 0;
+        let i = 0;
+        while (i < n) {
+            let square = i * i;
+            if (i % 2 == 0) {
+                let even_square = square;
+                total = total + even_square;
+            } else {
+                let odd_square = square;
+                total = total + odd_square - square;
+            };
+            i = i + 1;
+        };
+        total
+    }
+}
@@ -0,0 +1,52 @@
+/// Entry functions taking vector arguments, decoded from the call data by `call_selector`.
+/// Each aborts with its own code when the arguments are not the expected ones.
+module 0xa000::entry_args {
+    use std::vector;
+
+    public entry fun sum_bytes(bytes: vector<u8>, expected: u64) {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(&bytes)) {
+            sum = sum + (*vector::borrow(&bytes, i) as u64);
+            i = i + 1;
+        };
+        assert!(sum == expected, 1);
+    }
+
+    public entry fun sum_numbers(_account: &signer, numbers: vector<u64>, expected: u64) {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(&numbers)) {
+            sum = sum + *vector::borrow(&numbers, i);
+            i = i + 1;
+        };
+        assert!(sum == expected, 2);
+    }
+
+    public entry fun count_address(addresses: vector<address>, needle: address, expected: u64) {
+        let count = 0;
+        let i = 0;
+        while (i < vector::length(&addresses)) {
+            if (*vector::borrow(&addresses, i) == needle) {
+                count = count + 1;
+            };
+            i = i + 1;
+        };
+        assert!(count == expected, 3);
+    }
+
+    public entry fun is_owner(owner: address, owners: vector<address>) {
+        assert!(owner == @0xa000, 6);
+        assert!(owners == vector[@0x1, @0xcafe], 7);
+    }
+
+    public entry fun total_len(lists: vector<vector<u64>>, expected: u64) {
+        let len = 0;
+        let i = 0;
+        while (i < vector::length(&lists)) {
+            len = len + vector::length(vector::borrow(&lists, i));
+            i = i + 1;
+        };
+        assert!(len == expected, 5);
+    }
+}
@@ -0,0 +1,24 @@
+/// Entry functions in two modules, all dispatched by the one `call_selector`.
+/// Each aborts with its own code when the arguments are not the expected ones.
+module 0xa000::first {
+    public entry fun sum(a: u64, b: u64, expected: u64) {
+        assert!(a + b == expected, 1);
+    }
+
+    public entry fun product(_account: &signer, a: u64, b: u64, expected: u64) {
+        assert!(a * b == expected, 5);
+    }
+}
+
+module 0xa000::second {
+    use 0xa000::first;
+
+    public entry fun negate(flag: bool, expected: bool) {
+        assert!(!flag == expected, 3);
+    }
+
+    public entry fun sum_twice(a: u64, b: u64, expected: u64) {
+        first::sum(a, b, expected / 2);
+        assert!(2 * (a + b) == expected, 4);
+    }
+}
@@ -0,0 +1,49 @@
+/// Entry functions taking and returning structs, decoded from the call data and encoded
+/// into the output of the call by `call_selector`.
+module 0xa000::shapes {
+    use std::vector;
+
+    struct Point has copy, drop {
+        x: u64,
+        y: u64,
+    }
+
+    struct Polygon has copy, drop {
+        name: vector<u8>,
+        owner: address,
+        points: vector<Point>,
+    }
+
+    public entry fun midpoint(a: Point, b: Point): Point {
+        Point { x: (a.x + b.x) / 2, y: (a.y + b.y) / 2 }
+    }
+
+    public entry fun check_polygon(polygon: Polygon, expected_points: u64) {
+        assert!(polygon.name == b"triangle", 1);
+        assert!(polygon.owner == @0xcafe, 2);
+        assert!(vector::length(&polygon.points) == expected_points, 3);
+    }
+
+    /// The corners of the box bounding `points`, and the number of points.
+    public entry fun bounds(points: vector<Point>): (Point, Point, u64) {
+        let n = vector::length(&points);
+        assert!(n > 0, 4);
+        let min = *vector::borrow(&points, 0);
+        let max = min;
+        let i = 1;
+        while (i < n) {
+            let p = vector::borrow(&points, i);
+            if (p.x < min.x) min.x = p.x;
+            if (p.y < min.y) min.y = p.y;
+            if (p.x > max.x) max.x = p.x;
+            if (p.y > max.y) max.y = p.y;
+            i = i + 1;
+        };
+        (min, max, n)
+    }
+
+    public entry fun reversed(polygon: Polygon): Polygon {
+        vector::reverse(&mut polygon.points);
+        polygon
+    }
+}
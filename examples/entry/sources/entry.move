@@ -1,4 +1,4 @@
-module 0x10::debug {
+module std::debug {
   native public fun print<T>(x: &T);
 }
 
@@ -9,10 +9,14 @@ module 0xc::token {
     public fun new(owner: address): Token {
         Token { owner }
     }
+    // called by nothing, so pruned with --prune-unreachable
+    public fun never_called(token: &Token): address {
+        token.owner
+    }
 }
 
 module 0xe::entry_bar {
-    use 0x10::debug;
+    use std::debug;
 
     struct Coin<T> has key,drop {
         token: T,
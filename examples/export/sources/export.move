@@ -0,0 +1,25 @@
+module 0xa000::export {
+    use 0xa000::helper;
+
+    /// Exported only with `--export-public`.
+    public fun double(x: u64): u64 {
+        x * 2
+    }
+
+    /// Generic functions are never exported.
+    public fun identity<T>(x: T): T {
+        x
+    }
+
+    /// Private functions are never exported.
+    fun increment(x: u64): u64 {
+        x + 1
+    }
+
+    public entry fun run(_account: &signer) {
+        assert!(double(21) == 42, 1);
+        assert!(identity(7u64) == 7, 2);
+        assert!(increment(1) == 2, 3);
+        assert!(helper::triple(3) == 9, 4);
+    }
+}
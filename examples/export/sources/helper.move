@@ -0,0 +1,8 @@
+module 0xa000::helper {
+    friend 0xa000::export;
+
+    /// Friend functions are never exported, but stay callable from friend modules.
+    public(friend) fun triple(x: u64): u64 {
+        x * 3
+    }
+}
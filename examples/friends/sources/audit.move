@@ -0,0 +1,10 @@
+module 0xa000::audit {
+    use 0xa000::ledger;
+
+    friend 0xa000::bank;
+
+    /// Only reachable from the entry function through friends.
+    public(friend) fun check(balance: u64): u64 {
+        ledger::credit(balance, 0)
+    }
+}
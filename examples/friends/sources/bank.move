@@ -0,0 +1,15 @@
+module 0xa000::bank {
+    use 0xa000::audit;
+    use 0xa000::ledger;
+
+    public entry fun deposit(_account: &signer) {
+        assert!(ledger::credit(1, 2) == 3, 1);
+        assert!(ledger::scale(true, 4) == 40, 2);
+        assert!(audit::check(5) == 5, 3);
+        assert!(ledger::total(6) == 6, 4);
+    }
+
+    public entry fun overdraw(_account: &signer) {
+        ledger::credit(999, 1);
+    }
+}
@@ -0,0 +1,32 @@
+module 0xa000::ledger {
+    friend 0xa000::audit;
+    friend 0xa000::bank;
+
+    /// Called by the friends, linked externally but never exported.
+    public(friend) fun credit(balance: u64, amount: u64): u64 {
+        checked(balance + amount)
+    }
+
+    /// Instantiated in the modules calling it, along with its call to the private `mul`.
+    public(friend) fun scale<T: drop>(_tag: T, x: u64): u64 {
+        mul(x, 10)
+    }
+
+    /// A friend function no friend calls gets private linkage.
+    public(friend) fun only_local(x: u64): u64 {
+        x
+    }
+
+    public fun total(x: u64): u64 {
+        only_local(x)
+    }
+
+    fun mul(x: u64, factor: u64): u64 {
+        x * factor
+    }
+
+    fun checked(x: u64): u64 {
+        assert!(x < 1000, 7);
+        x
+    }
+}
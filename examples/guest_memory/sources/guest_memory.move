@@ -0,0 +1,48 @@
+module 0xa000::guest_memory {
+    use std::vector;
+
+    public fun sum(v: &vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(v)) {
+            sum = sum + *vector::borrow(v, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    public fun double_all(v: &mut vector<u64>) {
+        let i = 0;
+        while (i < vector::length(v)) {
+            let x = vector::borrow_mut(v, i);
+            *x = *x * 2;
+            i = i + 1;
+        };
+    }
+
+    public fun count_owned(owners: &vector<address>, owner: &address): u64 {
+        let count = 0;
+        let i = 0;
+        while (i < vector::length(owners)) {
+            if (vector::borrow(owners, i) == owner) {
+                count = count + 1;
+            };
+            i = i + 1;
+        };
+        count
+    }
+
+    public fun is_cafe(account: &address): u64 {
+        if (*account == @0xcafe) 1 else 0
+    }
+
+    public fun total_length(chunks: &vector<vector<u8>>): u64 {
+        let total = 0;
+        let i = 0;
+        while (i < vector::length(chunks)) {
+            total = total + vector::length(vector::borrow(chunks, i));
+            i = i + 1;
+        };
+        total
+    }
+}
@@ -1,11 +1,12 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 
 module 0xa002::hash_tests {
-    use 0x10::debug;
+    use std::debug;
     use std::vector;
     use std::hash;
+    use revive::hash as revive_hash;
 
     public entry fun sha2_256_expected_hash(_account: &signer) {
         let input = x"616263";
@@ -28,4 +29,27 @@ module 0xa002::hash_tests {
         let expected_output = x"3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532";
         assert!(hash::sha3_256(input) == expected_output, 0);
     }
+
+    public entry fun chained_hashes(_account: &signer) {
+        let empty = hash::sha2_256(vector::empty<u8>());
+        assert!(empty == x"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855", 0);
+        let chained = hash::sha3_256(hash::sha2_256(x"616263"));
+        assert!(vector::length(&chained) == 32, 0);
+        assert!(chained == hash::sha3_256(x"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"), 0);
+    }
+
+    public entry fun keccak256_expected_hash(_account: &signer) {
+        let expected_output = x"4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45";
+        assert!(revive_hash::keccak256(x"616263") == expected_output, 0);
+        let empty = x"c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+        assert!(revive_hash::keccak256(vector::empty<u8>()) == empty, 0);
+    }
+
+    public entry fun blake2b256_expected_hash(_account: &signer) {
+        let expected_output = x"bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319";
+        assert!(revive_hash::blake2b256(x"616263") == expected_output, 0);
+        // the Substrate storage key hashers chain with the others
+        let chained = revive_hash::blake2b256(revive_hash::keccak256(x"616263"));
+        assert!(vector::length(&chained) == 32, 0);
+    }
 }
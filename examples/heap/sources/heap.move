@@ -0,0 +1,50 @@
+module 0xa000::heap {
+    use std::hash;
+    use std::vector;
+
+    // 160 KiB of elements, more than the default heap holds.
+    const LEN: u64 = 20000;
+    // 1000 vectors of 1 KiB buffers, more than the default heap holds unless they are freed.
+    const ROUNDS: u64 = 1000;
+    const ROUND_LEN: u64 = 100;
+    // digests the host writes to its own memory, which are dropped again
+    const DIGESTS: u64 = 50;
+
+    public entry fun big_vector(_account: &signer) {
+        let v = vector::empty<u64>();
+        let i = 0;
+        while (i < LEN) {
+            vector::push_back(&mut v, i);
+            i = i + 1;
+        };
+        assert!(vector::length(&v) == LEN, 1);
+    }
+
+    public entry fun temporary_vectors(_account: &signer) {
+        let round = 0;
+        while (round < ROUNDS) {
+            let v = vector::empty<u64>();
+            let i = 0;
+            while (i < ROUND_LEN) {
+                vector::push_back(&mut v, i);
+                i = i + 1;
+            };
+            assert!(vector::length(&v) == ROUND_LEN, 2);
+            while (!vector::is_empty(&v)) {
+                vector::pop_back(&mut v);
+            };
+            vector::destroy_empty(v);
+            round = round + 1;
+        };
+    }
+
+    public entry fun dropped_digests(_account: &signer) {
+        let input = b"abc";
+        let round = 0;
+        while (round < DIGESTS) {
+            let digest = hash::sha3_256(copy input);
+            assert!(vector::length(&digest) == 32, 3);
+            round = round + 1;
+        };
+    }
+}
@@ -0,0 +1,36 @@
+module 0xa000::counter {
+    struct Counter has drop {
+        value: u64,
+    }
+
+    /// A small getter on the hot path, inlined into every caller.
+    #[inline_always]
+    fun value(counter: &Counter): u64 {
+        counter.value
+    }
+
+    /// Left to the inliner, which is told inlining is worthwhile.
+    #[inline_hint]
+    fun bump(counter: &mut Counter) {
+        counter.value = counter.value + 1;
+    }
+
+    /// The cold path stays out of its callers.
+    #[inline_never]
+    fun fail(code: u64) {
+        abort code
+    }
+
+    /// No hint, unless one is given with `--inline`.
+    fun double(x: u64): u64 {
+        x * 2
+    }
+
+    public entry fun count(_account: &signer) {
+        let counter = Counter { value: 0 };
+        bump(&mut counter);
+        bump(&mut counter);
+        if (value(&counter) != 2) fail(1);
+        if (double(value(&counter)) != 4) fail(2);
+    }
+}
@@ -0,0 +1,24 @@
+module std::debug {
+    native public fun print<T>(x: &T);
+}
+
+module 0xa00c::lottery {
+    use std::debug;
+    use std::vector;
+    use 0x10::random;
+    use 0x10::timestamp;
+
+    // 2024-01-01 00:00:00 UTC
+    const OPENS_AT: u64 = 1704067200;
+
+    const E_CLOSED: u64 = 1;
+
+    /// Draw a ticket number in [0, 1000) and print it, once the lottery is open.
+    public entry fun draw() {
+        assert!(timestamp::seconds() >= OPENS_AT, E_CLOSED);
+        let bytes = random::bytes(2);
+        let high = (*vector::borrow(&bytes, 0) as u64);
+        let low = (*vector::borrow(&bytes, 1) as u64);
+        debug::print(&(((high << 8) | low) % 1000));
+    }
+}
@@ -0,0 +1,76 @@
+module 0xa000::overflow {
+    const MAX_U64: u64 = 18446744073709551615;
+    const MAX_U128: u128 = 340282366920938463463374607431768211455;
+
+    fun add_u8(a: u8, b: u8): u8 {
+        a + b
+    }
+
+    fun add_u64(a: u64, b: u64): u64 {
+        a + b
+    }
+
+    fun sub_u64(a: u64, b: u64): u64 {
+        a - b
+    }
+
+    fun mul_u32(a: u32, b: u32): u32 {
+        a * b
+    }
+
+    fun mul_u128(a: u128, b: u128): u128 {
+        a * b
+    }
+
+    fun div_u64(a: u64, b: u64): u64 {
+        a / b
+    }
+
+    fun shl_u8(a: u8, n: u8): u8 {
+        a << n
+    }
+
+    fun cast_u8(x: u64): u8 {
+        (x as u8)
+    }
+
+    public entry fun in_range(_account: &signer) {
+        assert!(add_u8(254, 1) == 255, 1);
+        assert!(add_u64(MAX_U64 - 1, 1) == MAX_U64, 2);
+        assert!(sub_u64(1, 1) == 0, 3);
+        assert!(mul_u32(65535, 65537) == 4294967295, 4);
+        assert!(mul_u128(MAX_U128 / 2, 2) == MAX_U128 - 1, 5);
+        assert!(div_u64(6, 3) == 2, 6);
+        assert!(shl_u8(1, 7) == 128, 7);
+        assert!(cast_u8(255) == 255, 8);
+    }
+
+    public entry fun add_u8_overflow(_account: &signer) {
+        add_u8(255, 1);
+    }
+
+    public entry fun add_u64_overflow(_account: &signer) {
+        add_u64(MAX_U64, MAX_U64);
+    }
+
+    public entry fun sub_u64_underflow(_account: &signer) {
+        sub_u64(0, 1);
+    }
+
+    public entry fun mul_u32_overflow(_account: &signer) {
+        mul_u32(65536, 65536);
+    }
+
+    public entry fun mul_u128_overflow(_account: &signer) {
+        mul_u128(MAX_U128, 2);
+    }
+
+    /// Only passes when compiled with `--overflow-checks false`.
+    public entry fun wrapping(_account: &signer) {
+        assert!(add_u8(255, 1) == 0, 1);
+        assert!(add_u64(MAX_U64, 2) == 1, 2);
+        assert!(sub_u64(0, 1) == MAX_U64, 3);
+        assert!(mul_u32(65536, 65537) == 65536, 4);
+        assert!(mul_u128(MAX_U128, 2) == MAX_U128 - 1, 5);
+    }
+}
@@ -0,0 +1,26 @@
+module 0xa00b::payable {
+    use 0x10::revive;
+
+    // H160 accounts live in the first 20 bytes of an address
+    const RECIPIENT: address = @0xbeefbeefbeefbeefbeefbeefbeefbeefbeefbeef000000000000000000000000;
+    const CONTRACT: address = @0xc0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0000000000000000000000000;
+
+    const E_BALANCE: u64 = 1;
+
+    public entry fun pay() {
+        let contract = revive::balance(CONTRACT);
+        let recipient = revive::balance(RECIPIENT);
+        revive::transfer(RECIPIENT, 100);
+        assert!(revive::balance(CONTRACT) == contract - 100, E_BALANCE);
+        assert!(revive::balance(RECIPIENT) == recipient + 100, E_BALANCE);
+    }
+
+    public entry fun pay_everything() {
+        revive::transfer(RECIPIENT, revive::balance(CONTRACT));
+        assert!(revive::balance(CONTRACT) == 0, E_BALANCE);
+    }
+
+    public entry fun overdraw() {
+        revive::transfer(RECIPIENT, revive::balance(CONTRACT) + 1);
+    }
+}
@@ -0,0 +1,111 @@
+module 0xa000::refs {
+    use std::signer;
+    use std::vector;
+
+    struct Inner has store, drop, copy {
+        value: u64,
+        items: vector<u64>,
+    }
+
+    struct Counter has key, drop {
+        count: u64,
+        history: vector<u64>,
+        inner: Inner,
+    }
+
+    fun set<T: drop>(r: &mut T, value: T) {
+        *r = value;
+    }
+
+    fun get<T: copy>(r: &T): T {
+        *r
+    }
+
+    public fun read(r: &u64): u64 {
+        *r
+    }
+
+    public entry fun write_local() {
+        let x = 1u64;
+        let r = &mut x;
+        *r = 2;
+        assert!(x == 2, 1);
+        set(&mut x, 3);
+        assert!(get(&x) == 3, 2);
+    }
+
+    public entry fun write_vector_element() {
+        let v = vector[1u64, 2, 3];
+        *vector::borrow_mut(&mut v, 1) = 20;
+        set(vector::borrow_mut(&mut v, 2), 30);
+        assert!(*vector::borrow(&v, 0) == 1, 1);
+        assert!(get(vector::borrow(&v, 1)) == 20, 2);
+        assert!(v == vector[1, 20, 30], 3);
+
+        let vv = vector[vector[1u64], vector[2u64, 3]];
+        *vector::borrow_mut(&mut vv, 0) = vector[10, 11, 12];
+        vector::push_back(vector::borrow_mut(&mut vv, 1), 4);
+        assert!(vv == vector[vector[10, 11, 12], vector[2, 3, 4]], 4);
+    }
+
+    public entry fun write_struct_field() {
+        let inner = Inner { value: 1, items: vector[] };
+        inner.value = 2;
+        *&mut inner.items = vector[7, 8];
+        set(&mut inner.value, 3);
+        assert!(inner.value == 3, 1);
+        assert!(inner.items == vector[7, 8], 2);
+
+        let r = &mut inner;
+        *r = Inner { value: 4, items: vector[9] };
+        assert!(inner.value == 4 && inner.items == vector[9], 3);
+    }
+
+    public entry fun read_copies_vector() {
+        let v = vector[1u64, 2];
+        let snapshot = *&v;
+        vector::push_back(&mut v, 3);
+        *vector::borrow_mut(&mut v, 0) = 10;
+        assert!(snapshot == vector[1, 2], 1);
+        assert!(v == vector[10, 2, 3], 2);
+    }
+
+    public entry fun publish(account: &signer) {
+        let counter = Counter {
+            count: 0,
+            history: vector[],
+            inner: Inner { value: 0, items: vector[] },
+        };
+        move_to(account, counter);
+    }
+
+    public entry fun increment(account: &signer) acquires Counter {
+        let counter = borrow_global_mut<Counter>(signer::address_of(account));
+        let count = counter.count + 1;
+        *&mut counter.count = count;
+        vector::push_back(&mut counter.history, count);
+        set(&mut counter.inner.value, count * 10);
+        *&mut counter.inner.items = vector[count, count];
+    }
+
+    public entry fun check(account: &signer, expected: u64) acquires Counter {
+        let counter = borrow_global<Counter>(signer::address_of(account));
+        assert!(get(&counter.count) == expected, 1);
+        assert!(vector::length(&counter.history) == expected, 2);
+        let inner = *&counter.inner;
+        assert!(inner.value == expected * 10, 3);
+        if (expected > 0) {
+            assert!(inner.items == vector[expected, expected], 4);
+            assert!(*vector::borrow(&counter.history, expected - 1) == expected, 5);
+        };
+    }
+
+    public entry fun replace(account: &signer) acquires Counter {
+        let counter = borrow_global_mut<Counter>(signer::address_of(account));
+        *counter = Counter {
+            count: 1,
+            history: vector[1],
+            inner: Inner { value: 10, items: vector[1, 1] },
+        };
+    }
+}
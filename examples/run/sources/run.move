@@ -1,9 +1,9 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 
 module 0xa009::run {
-    use 0x10::debug;
+    use std::debug;
     use std::signer;
 
     struct Containee has key, drop, store, copy {
@@ -0,0 +1,9 @@
+module 0xa000::selector {
+    #[selector = 0x11223344]
+    public entry fun first(_account: &signer) {
+    }
+
+    #[selector = 0x11223344]
+    public entry fun second(_account: &signer) {
+    }
+}
@@ -0,0 +1,5 @@
+module std::from_bcs {
+    /// Decode the BCS `bytes` into a value of type `T`.
+    /// Aborts if `bytes` is not a valid encoding of a `T`.
+    native public fun from_bytes<T>(bytes: vector<u8>): T;
+}
@@ -1,10 +1,19 @@
 module 0xa003::serialize {
     use std::bcs;
     use std::string;
+    use std::vector;
+    use std::from_bcs;
+
+    struct Payload has copy, drop {
+        amount: u64,
+        recipients: vector<address>,
+        memo: vector<u8>,
+    }
 
     public entry fun ser_signer(account: &signer) {
         let bytes = bcs::to_bytes(account);
-        let expected_output = x"ab010101010101010101010101010101010101010101010101010101010101ce";
+        // the host passes the address bytes 0xab, 0x01, ..., 0x01, 0xce, least significant first
+        let expected_output = x"ce010101010101010101010101010101010101010101010101010101010101ab";
         assert!(bytes == expected_output, 0);
     }
 
@@ -15,4 +24,48 @@ module 0xa003::serialize {
         let expected_output = x"0f00000048656c6c6f2c20506f6c6b61564d21";
         assert!(bytes == expected_output, 0);
     }
+
+    /// Literals serialize to the bytes other Move chains' BCS tooling produces.
+    public entry fun ser_literals() {
+        assert!(bcs::to_bytes(&0x0102030405060708090a0b0c0d0e0f10u128)
+            == x"100f0e0d0c0b0a090807060504030201", 0);
+        assert!(bcs::to_bytes(&340282366920938463463374607431768211456u256)
+            == x"0000000000000000000000000000000001000000000000000000000000000000", 1);
+        assert!(bcs::to_bytes(&@0x1)
+            == x"0000000000000000000000000000000000000000000000000000000000000001", 2);
+        assert!(bcs::to_bytes(&@0xcafe)
+            == x"000000000000000000000000000000000000000000000000000000000000cafe", 3);
+        let addresses = x"02000000";
+        vector::append(&mut addresses, x"0000000000000000000000000000000000000000000000000000000000000001");
+        vector::append(&mut addresses, x"0000000000000000000000000000000000000000000000000000000000000002");
+        assert!(bcs::to_bytes(&vector[@0x1, @0x2]) == addresses, 4);
+        let a: address = from_bcs::from_bytes(
+            x"000000000000000000000000000000000000000000000000000000000000cafe"
+        );
+        assert!(a == @0xcafe, 5);
+        let n: u256 = from_bcs::from_bytes(bcs::to_bytes(&(1u256 << 200)));
+        assert!(n == 1u256 << 200, 6);
+    }
+
+    /// Payloads BCS encoded by other Move chains' tooling decode to their values.
+    public entry fun deser_bcs(_account: &signer) {
+        let payload = Payload { amount: 42, recipients: vector[@0x1, @0x2], memo: b"memo" };
+        let bytes = x"2a00000000000000";
+        vector::append(&mut bytes, x"02");
+        vector::append(&mut bytes, x"0000000000000000000000000000000000000000000000000000000000000001");
+        vector::append(&mut bytes, x"0000000000000000000000000000000000000000000000000000000000000002");
+        vector::append(&mut bytes, x"046d656d6f");
+        let decoded: Payload = from_bcs::from_bytes(bytes);
+        assert!(decoded == payload, 0);
+        let n: u64 = from_bcs::from_bytes(x"2a00000000000000");
+        assert!(n == 42, 1);
+    }
+
+    public entry fun deser_malformed_bool(_account: &signer) {
+        let _b: bool = from_bcs::from_bytes(x"02");
+    }
+
+    public entry fun deser_trailing_bytes(_account: &signer) {
+        let _n: u8 = from_bcs::from_bytes(x"0102");
+    }
 }
@@ -1,11 +1,11 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 
 module 0xa000::storage {
     use std::signer;
     use std::vector;
-    use 0x10::debug;
+    use std::debug;
 
     struct Containee has key, drop, store, copy {
         value: u64,
@@ -1,9 +1,9 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 
 module 0xa004::my_string {
-    use 0x10::debug;
+    use std::debug;
     use std::string;
 
     public entry fun foo(_account: &signer) {
@@ -1,8 +1,8 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 module 0x42::entry {
-    use 0x10::debug;
+    use std::debug;
     use TaoHe::root;
 
     struct MyContent has store, key{
@@ -0,0 +1,74 @@
+module 0xa000::u256 {
+    const MAX: u256 = 115792089237316195423570985008687907853269984665640564039457584007913129639935;
+    const HALF: u256 = 57896044618658097711785492504343953926634992332820282019728792003956564819968;
+
+    fun add(a: u256, b: u256): u256 {
+        a + b
+    }
+
+    fun sub(a: u256, b: u256): u256 {
+        a - b
+    }
+
+    fun mul(a: u256, b: u256): u256 {
+        a * b
+    }
+
+    fun div(a: u256, b: u256): u256 {
+        a / b
+    }
+
+    fun rem(a: u256, b: u256): u256 {
+        a % b
+    }
+
+    fun widen(a: u8): u256 {
+        (a as u256)
+    }
+
+    fun narrow(a: u256): u64 {
+        (a as u64)
+    }
+
+    fun less(a: &u256, b: &u256): bool {
+        *a < *b
+    }
+
+    public entry fun run(_account: &signer) {
+        assert!(add(MAX - 1, 1) == MAX, 1);
+        assert!(add(HALF - 1, HALF) == MAX, 2);
+        assert!(sub(MAX, HALF) == HALF - 1, 3);
+        assert!(mul(HALF - 1, 2) == MAX - 1, 4);
+        assert!(mul(1 << 128, 1 << 127) == HALF, 5);
+        assert!(div(MAX, HALF) == 1, 6);
+        assert!(rem(MAX, HALF) == HALF - 1, 7);
+        assert!(div(HALF, 1 << 200) == 1 << 55, 8);
+        assert!(less(&HALF, &MAX) && !less(&MAX, &HALF) && !less(&MAX, &MAX), 9);
+        assert!(HALF <= HALF && HALF >= HALF && MAX > HALF && HALF != MAX, 10);
+        assert!(((255u8 as u256) << 248) >> 248 == 255, 11);
+        assert!(((MAX >> 128) as u128) == 340282366920938463463374607431768211455, 12);
+        assert!(narrow(HALF >> 192) == 9223372036854775808, 13);
+        assert!(narrow(widen(42) + (1 << 64) - (1 << 64)) == 42, 14);
+        assert!((MAX ^ HALF) == HALF - 1 && (MAX & HALF) == HALF, 15);
+    }
+
+    public entry fun add_overflow(_account: &signer) {
+        add(MAX, 1);
+    }
+
+    public entry fun sub_underflow(_account: &signer) {
+        sub(HALF, MAX);
+    }
+
+    public entry fun mul_overflow(_account: &signer) {
+        mul(HALF, 2);
+    }
+
+    public entry fun div_by_zero(_account: &signer) {
+        div(MAX, 0);
+    }
+
+    public entry fun cast_overflow(_account: &signer) {
+        narrow(1 << 64);
+    }
+}
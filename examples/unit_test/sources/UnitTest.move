@@ -1,9 +1,9 @@
-module 0x10::debug {
+module std::debug {
   native public fun print<T>(x: &T);
 }
 
 module UnitTest::UnitTest {
-    use 0x10::debug;
+    use std::debug;
 
     const ONE: u64 = 0x20001;
     const TWO: u64 = 0x20002;
@@ -0,0 +1,14 @@
+module unsupported::shape {
+    enum Shape has drop {
+        Circle { radius: u64 },
+        Square { side: u64 },
+    }
+
+    public fun circle(radius: u64): Shape {
+        Shape::Circle { radius }
+    }
+
+    public fun is_circle(shape: &Shape): bool {
+        shape is Circle
+    }
+}
@@ -0,0 +1,88 @@
+/// Vector copies of growing length, for the `vec_copy` benchmark of move-to-polka.
+module 0xa000::vec_copy {
+    use std::vector;
+
+    /// A `u64` wrapped in a struct, so vectors of it are copied element by element.
+    struct Wrapped has copy, drop {
+        value: u64,
+    }
+
+    /// Build a `vector<u8>` of `len` elements and copy it `copies` times.
+    public fun copy_u8(len: u64, copies: u64): u64 {
+        let v = vector::empty<u8>();
+        let i = 0;
+        while (i < len) {
+            vector::push_back(&mut v, ((i % 256) as u8));
+            i = i + 1;
+        };
+        let total = 0;
+        let i = 0;
+        while (i < copies) {
+            let w = copy v;
+            total = total + vector::length(&w);
+            i = i + 1;
+        };
+        total
+    }
+
+    /// Build a `vector<u64>` of `len` elements and copy it `copies` times.
+    public fun copy_u64(len: u64, copies: u64): u64 {
+        let v = vector::empty<u64>();
+        let i = 0;
+        while (i < len) {
+            vector::push_back(&mut v, i);
+            i = i + 1;
+        };
+        let total = 0;
+        let i = 0;
+        while (i < copies) {
+            let w = copy v;
+            total = total + vector::length(&w);
+            i = i + 1;
+        };
+        total
+    }
+
+    /// Build a `vector<address>` of `len` elements and copy it `copies` times.
+    public fun copy_address(len: u64, copies: u64): u64 {
+        let v = vector::empty<address>();
+        let i = 0;
+        while (i < len) {
+            vector::push_back(&mut v, @0xa000);
+            i = i + 1;
+        };
+        let total = 0;
+        let i = 0;
+        while (i < copies) {
+            let w = copy v;
+            total = total + vector::length(&w);
+            i = i + 1;
+        };
+        total
+    }
+
+    /// Like `copy_u64`, but the elements are `Wrapped`: the baseline without the memcpy.
+    public fun copy_wrapped_u64(len: u64, copies: u64): u64 {
+        let v = vector::empty<Wrapped>();
+        let i = 0;
+        while (i < len) {
+            vector::push_back(&mut v, Wrapped { value: i });
+            i = i + 1;
+        };
+        let total = 0;
+        let i = 0;
+        while (i < copies) {
+            let w = copy v;
+            total = total + vector::length(&w);
+            i = i + 1;
+        };
+        total
+    }
+
+    public entry fun run(_account: &signer) {
+        assert!(copy_u8(16, 2) == 32, 1);
+        assert!(copy_u64(16, 2) == 32, 2);
+        assert!(copy_address(16, 2) == 32, 3);
+        assert!(copy_wrapped_u64(16, 2) == 32, 4);
+    }
+}
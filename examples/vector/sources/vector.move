@@ -1,9 +1,9 @@
-module 0x10::debug {
+module std::debug {
     native public fun print<T>(x: &T);
 }
 
 module 0xa000::my_vector {
-    use 0x10::debug;
+    use std::debug;
     use std::vector;
 
     public entry fun vecnew(_account: &signer) {
@@ -130,4 +130,35 @@ module 0xa000::my_vector {
         let v2 = vector::filter(v, |e| *e > 5);
         assert!(*vector::borrow(&v2, 0) == 6u8, 0);
     }
+
+    struct Holder has drop {
+        v: vector<u64>,
+        nested: vector<vector<u8>>,
+    }
+
+    public entry fun literal(_account: &signer) {
+        let a = 1u64;
+        let b = a + 1;
+        let v = vector[a, b, b + 1];
+        assert!(vector::length(&v) == 3, 0);
+        assert!(*vector::borrow(&v, 2) == 3, 0);
+    }
+
+    public entry fun nestedliteral(_account: &signer) {
+        let bytes = vector[x"0102", x"03", x""];
+        assert!(vector::length(&bytes) == 3, 0);
+        assert!(vector::length(vector::borrow(&bytes, 0)) == 2, 0);
+        assert!(*vector::borrow(vector::borrow(&bytes, 1), 0) == 3u8, 0);
+        let numbers = vector[vector[1u64, 2], vector[3u64]];
+        vector::push_back(vector::borrow_mut(&mut numbers, 1), 4);
+        assert!(*vector::borrow(vector::borrow(&numbers, 1), 1) == 4, 0);
+        let addresses = vector[vector[@0x1, @0x2]];
+        assert!(*vector::borrow(vector::borrow(&addresses, 0), 1) == @0x2, 0);
+    }
+
+    public entry fun structliteral(_account: &signer) {
+        let h = Holder { v: vector[1, 2, 3], nested: vector[x"0a0b", x"0c"] };
+        assert!(*vector::borrow(&h.v, 1) == 2, 0);
+        assert!(*vector::borrow(vector::borrow(&h.nested, 1), 0) == 12u8, 0);
+    }
 }
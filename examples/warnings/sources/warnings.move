@@ -0,0 +1,6 @@
+module 0xb000::warnings {
+    // compiles, but with an unused local variable warning
+    public entry fun main(_account: &signer) {
+        let unused = 42;
+    }
+}
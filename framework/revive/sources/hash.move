@@ -0,0 +1,9 @@
+/// Hashes for Ethereum and Substrate interop, computed by the runtime
+/// (`move_native_hash_*`). The SHA-2 and SHA-3 hashes are in `std::hash`.
+module revive::hash {
+    /// Keccak-256 of `bytes`, as Ethereum uses it, e.g. for event topics and selectors.
+    native public fun keccak256(bytes: vector<u8>): vector<u8>;
+
+    /// Blake2b-256 of `bytes`, Substrate's `blake2_256`, e.g. for storage keys.
+    native public fun blake2b256(bytes: vector<u8>): vector<u8>;
+}
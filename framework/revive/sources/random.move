@@ -0,0 +1,8 @@
+/// Mock randomness provided by the test host (`move_native_random_bytes`), drawn from a
+/// deterministic stream seeded with the `--mock-seed` of the wrapper, so tests are
+/// reproducible. pallet-revive has no source of randomness: only packages built with
+/// `--mock-host` or as unit tests may call it.
+module revive::random {
+    /// `n` random bytes.
+    native public fun bytes(n: u64): vector<u8>;
+}
@@ -0,0 +1,15 @@
+/// Access to pallet-revive from Move contracts. The natives are provided by the runtime
+/// (`move_native_revive_*`). H160 accounts are the first 20 bytes of an address.
+module revive::revive {
+    /// Balance of the H160 account in the first 20 bytes of `account`.
+    native public fun balance(account: address): u128;
+
+    /// Transfer `amount` from the contract to `to`, aborts with `0xba1` if the contract can't
+    /// afford it.
+    native public fun transfer(to: address, amount: u128);
+
+    /// Call the contract `callee` with `input`, e.g. a selector followed by BCS encoded
+    /// arguments, and return its output. Aborts with `0xba3` if the call fails or the callee
+    /// reverts.
+    native public fun call_contract(callee: address, input: vector<u8>): vector<u8>;
+}
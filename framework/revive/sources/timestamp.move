@@ -0,0 +1,12 @@
+/// Time of the current block, provided by the host (`move_native_timestamp_ms`) through
+/// pallet-revive's `now`, in whole seconds. The test host returns the `--mock-time` of the
+/// wrapper, `Runtime::timestamp_ms` in Rust.
+module revive::timestamp {
+    /// Milliseconds since the Unix epoch, a multiple of 1000.
+    native public fun ms(): u64;
+
+    /// Seconds since the Unix epoch.
+    public fun seconds(): u64 {
+        ms() / 1000
+    }
+}